@@ -7,9 +7,12 @@ use std::time::Duration;
 use thiserror::Error;
 
 use crate::adb::{
-    back, clear_text, detect_and_set_adb_keyboard, double_tap, home, launch_app, long_press,
-    restore_keyboard, swipe, tap, type_text,
+    back, clear_text, detect_and_set_adb_keyboard, double_tap, home, launch_package, long_press,
+    resolve_installed_app, restore_keyboard, swipe, tap, type_text,
 };
+use crate::calibration::CoordinateMapping;
+use crate::config::{format_message, get_messages, AppRegion, Messages};
+use crate::settings::AppSettings;
 
 /// Coordinate system mode for interpreting LLM output coordinates.
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -38,6 +41,21 @@ pub enum ActionError {
     ParseError(String),
 }
 
+impl ActionError {
+    /// The error message in a UI language (`Display` is always English).
+    pub fn localized(&self, lang: &str) -> String {
+        let m = get_messages(lang);
+        let (template, detail) = match self {
+            ActionError::UnknownActionType(detail) => (m.err_unknown_action_type, detail),
+            ActionError::UnknownAction(detail) => (m.err_unknown_action, detail),
+            ActionError::MissingParameter(detail) => (m.err_missing_parameter, detail),
+            ActionError::ExecutionFailed(detail) => (m.err_action_failed, detail),
+            ActionError::ParseError(detail) => (m.err_parse, detail),
+        };
+        format_message(template, &[detail])
+    }
+}
+
 /// Result of an action execution.
 #[derive(Debug, Clone)]
 pub struct ActionResult {
@@ -45,6 +63,11 @@ pub struct ActionResult {
     pub should_finish: bool,
     pub message: Option<String>,
     pub requires_confirmation: bool,
+    /// Device pixels the action touched, in order: one point for taps and
+    /// presses, start and end for swipes.
+    pub touches: Vec<(i32, i32)>,
+    /// Whether the action failed because a coordinate was off screen.
+    pub out_of_bounds: bool,
 }
 
 impl ActionResult {
@@ -55,6 +78,8 @@ impl ActionResult {
             should_finish: false,
             message: None,
             requires_confirmation: false,
+            touches: Vec::new(),
+            out_of_bounds: false,
         }
     }
 
@@ -65,9 +90,17 @@ impl ActionResult {
             should_finish: false,
             message: Some(message.into()),
             requires_confirmation: false,
+            touches: Vec::new(),
+            out_of_bounds: false,
         }
     }
 
+    /// Attach the device pixels the action touched.
+    pub fn with_touches(mut self, touches: Vec<(i32, i32)>) -> Self {
+        self.touches = touches;
+        self
+    }
+
     /// Create a finish result.
     pub fn finish(message: Option<String>) -> Self {
         Self {
@@ -75,6 +108,8 @@ impl ActionResult {
             should_finish: true,
             message,
             requires_confirmation: false,
+            touches: Vec::new(),
+            out_of_bounds: false,
         }
     }
 }
@@ -101,8 +136,22 @@ pub struct ActionHandler {
     scale_x: f64,
     /// Scale factor for Y coordinates (LLM output * scale = actual coordinate)
     scale_y: f64,
+    /// Offset added to X coordinates after scaling (e.g. status bar shift)
+    offset_x: f64,
+    /// Offset added to Y coordinates after scaling (e.g. status bar shift)
+    offset_y: f64,
+    /// Nonlinear mapping from grid calibration; replaces scale and offset when set
+    mapping: Option<CoordinateMapping>,
     /// Coordinate system mode
     coordinate_system: CoordinateSystem,
+    /// App catalogs `Launch` looks names up in
+    app_region: AppRegion,
+    /// Actions the model may not use (see [`is_action_disabled`])
+    disabled_actions: Vec<String>,
+    /// Language of the error messages ("en" unless set)
+    lang: String,
+    /// Describe actions instead of performing them
+    dry_run: bool,
 }
 
 impl ActionHandler {
@@ -198,6 +247,13 @@ impl ActionHandler {
             takeover_callback: takeover_callback.unwrap_or_else(|| Box::new(default_takeover)),
             scale_x,
             scale_y,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            mapping: None,
+            app_region: AppRegion::default(),
+            disabled_actions: Vec::new(),
+            lang: "en".to_string(),
+            dry_run: false,
             coordinate_system,
         }
     }
@@ -213,6 +269,56 @@ impl ActionHandler {
         (self.scale_x, self.scale_y)
     }
 
+    /// Set the pixel offsets added after scaling (absolute coordinate system only).
+    pub fn set_offset(&mut self, offset_x: f64, offset_y: f64) {
+        self.offset_x = offset_x;
+        self.offset_y = offset_y;
+    }
+
+    /// Get the current coordinate offsets.
+    pub fn get_offset(&self) -> (f64, f64) {
+        (self.offset_x, self.offset_y)
+    }
+
+    /// Set a nonlinear coordinate mapping from grid calibration (absolute coordinate
+    /// system only). While set, it is used instead of the scale factors and offsets.
+    pub fn set_mapping(&mut self, mapping: Option<CoordinateMapping>) {
+        self.mapping = mapping;
+    }
+
+    /// Set the app catalogs `Launch` looks names up in.
+    pub fn set_app_region(&mut self, app_region: AppRegion) {
+        self.app_region = app_region;
+    }
+
+    /// Set the language of error messages ("cn", "en" or a registered one).
+    pub fn set_lang(&mut self, lang: &str) {
+        self.lang = lang.to_string();
+    }
+
+    /// UI messages in the handler's language.
+    fn messages(&self) -> &'static Messages {
+        get_messages(&self.lang)
+    }
+
+    /// Set the actions the model may not use. They fail without touching
+    /// the device.
+    pub fn set_disabled_actions(&mut self, disabled_actions: Vec<String>) {
+        self.disabled_actions = disabled_actions;
+    }
+
+    /// Only describe actions instead of performing them. Coordinates are
+    /// still converted and checked and app names resolved, but nothing is
+    /// sent to the device; the result message says what would have been done.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Whether actions are only described (see [`ActionHandler::set_dry_run`]).
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Set the coordinate system mode.
     pub fn set_coordinate_system(&mut self, coordinate_system: CoordinateSystem) {
         self.coordinate_system = coordinate_system;
@@ -251,7 +357,10 @@ impl ActionHandler {
 
                 self.handle_action(action_name, action, screen_width, screen_height)
             }
-            _ => ActionResult::failure(format!("Unknown action type: {}", action_type)),
+            _ => ActionResult::failure(format_message(
+                self.messages().err_unknown_action_type,
+                &[&action_type],
+            )),
         }
     }
 
@@ -262,6 +371,17 @@ impl ActionHandler {
         screen_width: u32,
         screen_height: u32,
     ) -> ActionResult {
+        let m = self.messages();
+        if is_action_disabled(action_name, &self.disabled_actions) {
+            return ActionResult::failure(format_message(m.err_action_disabled, &[&action_name]));
+        }
+        if self.dry_run {
+            if let Some(result) =
+                self.describe_action(action_name, action, screen_width, screen_height)
+            {
+                return result;
+            }
+        }
         match action_name {
             "Launch" => self.handle_launch(action),
             "Tap" => self.handle_tap(action, screen_width, screen_height),
@@ -278,13 +398,111 @@ impl ActionHandler {
             "Interact" => ActionResult {
                 success: true,
                 should_finish: false,
-                message: Some("User interaction required".to_string()),
+                message: Some(m.user_interaction_required.to_string()),
                 requires_confirmation: false,
+                touches: Vec::new(),
+                out_of_bounds: false,
             },
-            _ => ActionResult::failure(format!("Unknown action: {}", action_name)),
+            _ => ActionResult::failure(format_message(m.err_unknown_action, &[&action_name])),
         }
     }
 
+    /// Dry-run result of an action that would affect the device, or `None`
+    /// for actions that don't (notes, API calls) and unknown ones.
+    fn describe_action(
+        &self,
+        action_name: &str,
+        action: &Value,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> Option<ActionResult> {
+        let m = self.messages();
+        let point = |key: &str| -> Result<(i32, i32), ActionResult> {
+            let coords: Vec<i64> = match action.get(key).and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_i64()).collect(),
+                None => return Err(ActionResult::failure(self.coordinates_error(true, key))),
+            };
+            if coords.len() < 2 {
+                return Err(ActionResult::failure(self.coordinates_error(false, key)));
+            }
+            self.validate_coordinates(&coords, key, screen_width, screen_height)
+        };
+
+        let (description, touches) = match action_name {
+            "Tap" | "Double Tap" | "Long Press" => {
+                let (x, y) = match point("element") {
+                    Ok(coords) => coords,
+                    Err(result) => return Some(result),
+                };
+                let mut description = format!("{} ({}, {})", action_name, x, y);
+                if let Some(message) = action.get("message").and_then(|v| v.as_str()) {
+                    description =
+                        format_message(m.dry_run_needs_confirmation, &[&description, &message]);
+                }
+                (description, vec![(x, y)])
+            }
+            "Swipe" => {
+                let (start, end) = match (point("start"), point("end")) {
+                    (Ok(start), Ok(end)) => (start, end),
+                    (Err(result), _) | (_, Err(result)) => return Some(result),
+                };
+                (
+                    format!("Swipe ({}, {}) → ({}, {})", start.0, start.1, end.0, end.1),
+                    vec![start, end],
+                )
+            }
+            "Type" | "Type_Name" => {
+                let text = action.get("text").and_then(|v| v.as_str()).unwrap_or("");
+                (format!("{} {:?}", action_name, text), Vec::new())
+            }
+            "Launch" => {
+                let Some(app_name) = action.get("app").and_then(|v| v.as_str()) else {
+                    return Some(ActionResult::failure(m.err_no_app_name));
+                };
+                let package = self.app_region.find_package(app_name).map(str::to_string);
+                let package = package.or_else(|| {
+                    let cache_path = AppSettings::installed_apps_path();
+                    resolve_installed_app(
+                        app_name,
+                        self.device_id.as_deref(),
+                        cache_path.as_deref(),
+                    )
+                });
+                match package {
+                    Some(package) => (format!("Launch {} ({})", app_name, package), Vec::new()),
+                    None => {
+                        return Some(ActionResult::failure(format_message(
+                            m.err_app_not_found,
+                            &[&app_name],
+                        )))
+                    }
+                }
+            }
+            "Back" | "Home" => (action_name.to_string(), Vec::new()),
+            "Wait" => {
+                let duration = action
+                    .get("duration")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("1 seconds");
+                (format!("Wait ({})", duration), Vec::new())
+            }
+            "Take_over" => {
+                let message = action
+                    .get("message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(m.user_intervention_required);
+                (format!("Take_over: {}", message), Vec::new())
+            }
+            _ => return None,
+        };
+
+        let message = format_message(m.dry_run_action, &[&description]);
+        Some(ActionResult {
+            message: Some(message),
+            ..ActionResult::success().with_touches(touches)
+        })
+    }
+
     /// Convert and validate coordinates based on the current coordinate system.
     ///
     /// For Relative mode: LLM outputs 0-999 range, converted to actual screen coordinates.
@@ -297,20 +515,21 @@ impl ActionHandler {
     ) -> Result<(i32, i32), String> {
         let raw_x = element[0];
         let raw_y = element[1];
+        let m = self.messages();
 
         match self.coordinate_system {
             CoordinateSystem::Relative => {
                 // Relative coordinates: 0-999 range mapped to screen size
                 if raw_x < 0 || raw_x >= RELATIVE_COORDINATE_MAX as i64 {
-                    return Err(format!(
-                        "Relative X coordinate {} is out of bounds. Valid range is [0, 999].",
-                        raw_x
+                    return Err(format_message(
+                        m.err_relative_out_of_bounds,
+                        &[&"X", &raw_x],
                     ));
                 }
                 if raw_y < 0 || raw_y >= RELATIVE_COORDINATE_MAX as i64 {
-                    return Err(format!(
-                        "Relative Y coordinate {} is out of bounds. Valid range is [0, 999].",
-                        raw_y
+                    return Err(format_message(
+                        m.err_relative_out_of_bounds,
+                        &[&"Y", &raw_y],
                     ));
                 }
 
@@ -323,23 +542,44 @@ impl ActionHandler {
                 Ok((abs_x, abs_y))
             }
             CoordinateSystem::Absolute => {
-                // Absolute coordinates: apply scale factors
-                let scaled_x = (raw_x as f64 * self.scale_x).round() as i64;
-                let scaled_y = (raw_y as f64 * self.scale_y).round() as i64;
+                // Absolute coordinates: apply the grid mapping, or scale factors then offsets
+                let (mapped_x, mapped_y) = match &self.mapping {
+                    Some(mapping) => mapping.apply(raw_x as f64, raw_y as f64),
+                    None => (
+                        raw_x as f64 * self.scale_x + self.offset_x,
+                        raw_y as f64 * self.scale_y + self.offset_y,
+                    ),
+                };
+                let scaled_x = mapped_x.round() as i64;
+                let scaled_y = mapped_y.round() as i64;
+                let transform = |scale: f64, offset: f64| match self.mapping {
+                    Some(_) => m.grid_mapping.to_string(),
+                    None => format!("× {:.2} + {:.0}", scale, offset),
+                };
 
                 // Check if scaled coordinates are within valid screen range
                 if scaled_x < 0 || scaled_x >= screen_width as i64 {
-                    return Err(format!(
-                        "Scaled X coordinate {} (raw: {} × {:.2}) is out of bounds. Valid range is [0, {}). \
-                        Please provide coordinates within the screen area.",
-                        scaled_x, raw_x, self.scale_x, screen_width
+                    return Err(format_message(
+                        m.err_scaled_out_of_bounds,
+                        &[
+                            &"X",
+                            &scaled_x,
+                            &raw_x,
+                            &transform(self.scale_x, self.offset_x),
+                            &screen_width,
+                        ],
                     ));
                 }
                 if scaled_y < 0 || scaled_y >= screen_height as i64 {
-                    return Err(format!(
-                        "Scaled Y coordinate {} (raw: {} × {:.2}) is out of bounds. Valid range is [0, {}). \
-                        Please provide coordinates within the screen area.",
-                        scaled_y, raw_y, self.scale_y, screen_height
+                    return Err(format_message(
+                        m.err_scaled_out_of_bounds,
+                        &[
+                            &"Y",
+                            &scaled_y,
+                            &raw_y,
+                            &transform(self.scale_y, self.offset_y),
+                            &screen_height,
+                        ],
                     ));
                 }
 
@@ -348,6 +588,17 @@ impl ActionHandler {
         }
     }
 
+    /// Message for missing or malformed coordinates of `coord_name`.
+    fn coordinates_error(&self, missing: bool, coord_name: &str) -> String {
+        let m = self.messages();
+        let template = if missing {
+            m.err_missing_coordinates
+        } else {
+            m.err_invalid_coordinates
+        };
+        format_message(template, &[&coord_name])
+    }
+
     /// Validate and convert coordinates with detailed error messages.
     fn validate_coordinates(
         &self,
@@ -358,23 +609,39 @@ impl ActionHandler {
     ) -> Result<(i32, i32), ActionResult> {
         match self.convert_coordinates(coords, screen_width, screen_height) {
             Ok((x, y)) => Ok((x, y)),
-            Err(msg) => Err(ActionResult::failure(format!(
-                "Coordinate error for {}: {}",
-                coord_name, msg
-            ))),
+            Err(msg) => Err(ActionResult {
+                out_of_bounds: true,
+                ..ActionResult::failure(format_message(
+                    self.messages().err_coordinate,
+                    &[&coord_name, &msg],
+                ))
+            }),
         }
     }
 
     fn handle_launch(&self, action: &Value) -> ActionResult {
         let app_name = match action.get("app").and_then(|v| v.as_str()) {
             Some(name) => name,
-            None => return ActionResult::failure("No app name specified"),
+            None => return ActionResult::failure(self.messages().err_no_app_name),
         };
 
-        if launch_app(app_name, self.device_id.as_deref(), None) {
-            ActionResult::success()
-        } else {
-            ActionResult::failure(format!("App not found: {}", app_name))
+        let device_id = self.device_id.as_deref();
+        if let Some(package) = self.app_region.find_package(app_name) {
+            launch_package(package, device_id, None);
+            return ActionResult::success();
+        }
+
+        // Not in the built-in catalog: look among the apps on the device
+        let cache_path = AppSettings::installed_apps_path();
+        match resolve_installed_app(app_name, device_id, cache_path.as_deref()) {
+            Some(package) => {
+                launch_package(&package, device_id, None);
+                ActionResult::success()
+            }
+            None => ActionResult::failure(format_message(
+                self.messages().err_app_not_found,
+                &[&app_name],
+            )),
         }
     }
 
@@ -383,11 +650,11 @@ impl ActionHandler {
             Some(arr) => {
                 let coords: Vec<i64> = arr.iter().filter_map(|v| v.as_i64()).collect();
                 if coords.len() < 2 {
-                    return ActionResult::failure("Invalid element coordinates");
+                    return ActionResult::failure(self.coordinates_error(false, "element"));
                 }
                 coords
             }
-            None => return ActionResult::failure("No element coordinates"),
+            None => return ActionResult::failure(self.coordinates_error(true, "element")),
         };
 
         // Check for sensitive operation
@@ -396,8 +663,10 @@ impl ActionHandler {
                 return ActionResult {
                     success: false,
                     should_finish: true,
-                    message: Some("User cancelled sensitive operation".to_string()),
+                    message: Some(self.messages().user_cancelled_sensitive.to_string()),
                     requires_confirmation: true,
+                    touches: Vec::new(),
+                    out_of_bounds: false,
                 };
             }
         }
@@ -408,7 +677,7 @@ impl ActionHandler {
                 Err(result) => return result,
             };
         tap(x, y, self.device_id.as_deref(), None);
-        ActionResult::success()
+        ActionResult::success().with_touches(vec![(x, y)])
     }
 
     fn handle_type(&self, action: &Value) -> ActionResult {
@@ -437,22 +706,22 @@ impl ActionHandler {
             Some(arr) => {
                 let coords: Vec<i64> = arr.iter().filter_map(|v| v.as_i64()).collect();
                 if coords.len() < 2 {
-                    return ActionResult::failure("Invalid start coordinates");
+                    return ActionResult::failure(self.coordinates_error(false, "start"));
                 }
                 coords
             }
-            None => return ActionResult::failure("Missing start coordinates"),
+            None => return ActionResult::failure(self.coordinates_error(true, "start")),
         };
 
         let end = match action.get("end").and_then(|v| v.as_array()) {
             Some(arr) => {
                 let coords: Vec<i64> = arr.iter().filter_map(|v| v.as_i64()).collect();
                 if coords.len() < 2 {
-                    return ActionResult::failure("Invalid end coordinates");
+                    return ActionResult::failure(self.coordinates_error(false, "end"));
                 }
                 coords
             }
-            None => return ActionResult::failure("Missing end coordinates"),
+            None => return ActionResult::failure(self.coordinates_error(true, "end")),
         };
 
         let (start_x, start_y) =
@@ -475,7 +744,7 @@ impl ActionHandler {
             self.device_id.as_deref(),
             None,
         );
-        ActionResult::success()
+        ActionResult::success().with_touches(vec![(start_x, start_y), (end_x, end_y)])
     }
 
     fn handle_back(&self) -> ActionResult {
@@ -498,11 +767,11 @@ impl ActionHandler {
             Some(arr) => {
                 let coords: Vec<i64> = arr.iter().filter_map(|v| v.as_i64()).collect();
                 if coords.len() < 2 {
-                    return ActionResult::failure("Invalid element coordinates");
+                    return ActionResult::failure(self.coordinates_error(false, "element"));
                 }
                 coords
             }
-            None => return ActionResult::failure("No element coordinates"),
+            None => return ActionResult::failure(self.coordinates_error(true, "element")),
         };
 
         let (x, y) =
@@ -511,7 +780,7 @@ impl ActionHandler {
                 Err(result) => return result,
             };
         double_tap(x, y, self.device_id.as_deref(), None);
-        ActionResult::success()
+        ActionResult::success().with_touches(vec![(x, y)])
     }
 
     fn handle_long_press(
@@ -524,11 +793,11 @@ impl ActionHandler {
             Some(arr) => {
                 let coords: Vec<i64> = arr.iter().filter_map(|v| v.as_i64()).collect();
                 if coords.len() < 2 {
-                    return ActionResult::failure("Invalid element coordinates");
+                    return ActionResult::failure(self.coordinates_error(false, "element"));
                 }
                 coords
             }
-            None => return ActionResult::failure("No element coordinates"),
+            None => return ActionResult::failure(self.coordinates_error(true, "element")),
         };
 
         let (x, y) =
@@ -537,7 +806,7 @@ impl ActionHandler {
                 Err(result) => return result,
             };
         long_press(x, y, None, self.device_id.as_deref(), None);
-        ActionResult::success()
+        ActionResult::success().with_touches(vec![(x, y)])
     }
 
     fn handle_wait(&self, action: &Value) -> ActionResult {
@@ -560,7 +829,7 @@ impl ActionHandler {
         let message = action
             .get("message")
             .and_then(|v| v.as_str())
-            .unwrap_or("User intervention required");
+            .unwrap_or(self.messages().user_intervention_required);
 
         (self.takeover_callback)(message);
         ActionResult::success()
@@ -580,7 +849,7 @@ fn default_confirmation(message: &str) -> bool {
 }
 
 /// Default takeover callback using console input.
-fn default_takeover(message: &str) {
+pub fn default_takeover(message: &str) {
     print!(
         "{}\nPress Enter after completing manual operation...",
         message
@@ -592,6 +861,18 @@ fn default_takeover(message: &str) {
     stdin.lock().read_line(&mut line).unwrap();
 }
 
+/// Whether `action` is in the `disabled` list. Names match ignoring case
+/// and spaces vs underscores. "Type" and "Type_Name" share a handler, so
+/// either disables both.
+pub fn is_action_disabled(action: &str, disabled: &[String]) -> bool {
+    let normalize = |name: &str| match name.trim().to_lowercase().replace(' ', "_") {
+        name if name == "type_name" => "type".to_string(),
+        name => name,
+    };
+    let action = normalize(action);
+    disabled.iter().any(|name| normalize(name) == action)
+}
+
 /// Parse action from model response.
 ///
 /// # Arguments
@@ -779,33 +1060,139 @@ fn parse_finish_action(response: &str) -> Result<Value, ActionError> {
         "_metadata": "finish"
     });
 
-    // Try to extract message
-    if content.contains("message=") {
-        let msg_start = content.find("message=").unwrap() + 8;
-        let remaining = &content[msg_start..];
-
-        // Find the message value (handle both quoted and unquoted)
-        let message = if remaining.starts_with('"') || remaining.starts_with('\'') {
-            let quote_char = remaining.chars().next().unwrap();
-            let end = remaining[1..]
-                .find(quote_char)
-                .map(|i| i + 1)
-                .unwrap_or(remaining.len());
-            &remaining[1..end]
-        } else {
-            remaining.split(',').next().unwrap_or(remaining).trim()
-        };
-
-        result["message"] = json!(message);
+    for (key, value) in keyword_args(content) {
+        match key {
+            "message" => result["message"] = json!(unquote(value)),
+            // Optional structured data handed back to the planner: data={"key": "value"}
+            "data" => match parse_value(value) {
+                Ok(data) if data.is_object() => result["data"] = data,
+                _ => {}
+            },
+            _ => {}
+        }
     }
 
     Ok(result)
 }
 
+/// Split the arguments of an action call into `key=value` pairs, values as
+/// written. Commas inside quotes, brackets and braces don't split, and quotes
+/// may contain backslash escapes. Arguments without a key are skipped.
+fn keyword_args(content: &str) -> Vec<(&str, &str)> {
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in content.char_indices() {
+        if let Some(quote_char) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote_char {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(&content[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(&content[start..]);
+
+    args.into_iter()
+        .filter_map(|arg| {
+            let (key, value) = arg.split_once('=')?;
+            let key = key.trim();
+            let is_key =
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            is_key.then_some((key, value.trim()))
+        })
+        .collect()
+}
+
+/// The text of a quoted argument value with its escapes resolved; unquoted
+/// values are returned as they are.
+fn unquote(value: &str) -> String {
+    let Some(quote_char) = value.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+        return value.to_string();
+    };
+    let inner = &value[1..];
+    let inner = inner.strip_suffix(quote_char).unwrap_or(inner);
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next)) if next == quote_char || next == '\\' => {
+                text.push(next);
+                chars.next();
+            }
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+/// Parse a `{...}` object written as JSON or Python style, with
+/// single-quoted strings.
+fn parse_object(text: &str) -> Option<Value> {
+    serde_json::from_str::<Value>(text)
+        .or_else(|_| serde_json::from_str::<Value>(&single_to_double_quotes(text)))
+        .ok()
+        .filter(|v| v.is_object())
+}
+
+/// Rewrite single-quoted strings as JSON strings. Double-quoted strings,
+/// apostrophes inside them included, are kept as they are.
+fn single_to_double_quotes(text: &str) -> String {
+    let mut json = String::with_capacity(text.len());
+    let mut quote = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                json.push('"');
+            }
+            (None, _) => json.push(c),
+            (Some(quote_char), '\\') => match chars.next() {
+                Some('\'') if quote_char == '\'' => json.push('\''),
+                Some(next) => {
+                    json.push('\\');
+                    json.push(next);
+                }
+                None => json.push('\\'),
+            },
+            (Some(quote_char), _) if c == quote_char => {
+                quote = None;
+                json.push('"');
+            }
+            (Some('\''), '"') => json.push_str("\\\""),
+            (Some(_), _) => json.push(c),
+        }
+    }
+    json
+}
+
 /// Parse a value string into a JSON Value.
 fn parse_value(value_str: &str) -> Result<Value, ActionError> {
     let trimmed = value_str.trim();
 
+    // Check if it's an object
+    if trimmed.starts_with('{') && trimmed.ends_with('}') {
+        if let Some(object) = parse_object(trimmed) {
+            return Ok(object);
+        }
+    }
+
     // Check if it's a list
     if trimmed.starts_with('[') && trimmed.ends_with(']') {
         let inner = &trimmed[1..trimmed.len() - 1];
@@ -891,6 +1278,47 @@ mod tests {
         assert_eq!(action["action"], "Tap");
     }
 
+    #[test]
+    fn test_parse_finish_action_with_data() {
+        let response =
+            r#"finish(message="已查到价格", data={"price": "12.50", "order": {"id": "A123"}})"#;
+        let result = parse_action(response).unwrap();
+        assert_eq!(result["message"], "已查到价格");
+        assert_eq!(result["data"]["price"], "12.50");
+        assert_eq!(result["data"]["order"]["id"], "A123");
+
+        let response = r#"finish(message='done', data={'code': 4821})"#;
+        let result = parse_action(response).unwrap();
+        assert_eq!(result["data"]["code"], 4821);
+
+        let result = parse_action(r#"finish(message="no data")"#).unwrap();
+        assert!(result.get("data").is_none());
+    }
+
+    #[test]
+    fn test_finish_data_is_a_keyword_argument() {
+        // data= inside the message or another argument's name isn't the data
+        let result = parse_action(r#"finish(message="set data={'a': 1} later")"#).unwrap();
+        assert_eq!(result["message"], "set data={'a': 1} later");
+        assert!(result.get("data").is_none());
+        let result = parse_action(r#"finish(message="ok", metadata={"a": 1})"#).unwrap();
+        assert!(result.get("data").is_none());
+
+        // Escaped quotes don't end a string
+        let response =
+            r#"finish(message="He said \"done\"", data={"note": "a \"}\" here", "n": 2})"#;
+        let result = parse_action(response).unwrap();
+        assert_eq!(result["message"], r#"He said "done""#);
+        assert_eq!(result["data"]["note"], r#"a "}" here"#);
+        assert_eq!(result["data"]["n"], 2);
+
+        // Apostrophes inside Python-style objects survive
+        let response = r#"finish(message='ok', data={'name': "O'Brien", 'quote': 'it\'s "fine"'})"#;
+        let result = parse_action(response).unwrap();
+        assert_eq!(result["data"]["name"], "O'Brien");
+        assert_eq!(result["data"]["quote"], r#"it's "fine""#);
+    }
+
     #[test]
     fn test_finish_action_helper() {
         let action = finish_action(Some("Done"));
@@ -903,6 +1331,10 @@ mod tests {
         let success = ActionResult::success();
         assert!(success.success);
         assert!(!success.should_finish);
+        assert!(success.touches.is_empty());
+
+        let swipe = ActionResult::success().with_touches(vec![(540, 2000), (540, 400)]);
+        assert_eq!(swipe.touches, vec![(540, 2000), (540, 400)]);
 
         let finish = ActionResult::finish(Some("Done".to_string()));
         assert!(finish.success);
@@ -955,6 +1387,32 @@ mod tests {
         assert_eq!(y, 300); // 200 * 1.5
     }
 
+    #[test]
+    fn test_scale_with_offset() {
+        // Model reports Y shifted up by a 72px status bar
+        let mut handler = ActionHandler::with_scale(None, None, None, 1.0, 1.0);
+        handler.set_offset(0.0, 72.0);
+
+        let result = handler.convert_coordinates(&[100, 200], 1080, 1920);
+        assert_eq!(result.unwrap(), (100, 272));
+
+        // A grid mapping takes precedence over scale and offset
+        handler.set_mapping(Some(CoordinateMapping {
+            x_coeffs: [10.0, 1000.0, 0.0, 0.0, 0.0, 0.0],
+            y_coeffs: [0.0, 0.0, 2000.0, 0.0, 0.0, 0.0],
+            norm_x: 1000.0,
+            norm_y: 1000.0,
+        }));
+        let result = handler.convert_coordinates(&[100, 200], 1080, 1920);
+        assert_eq!(result.unwrap(), (110, 400));
+
+        // Offsets are ignored in relative mode
+        let mut handler = ActionHandler::with_relative_coordinates(None, None, None);
+        handler.set_offset(0.0, 72.0);
+        let result = handler.convert_coordinates(&[500, 500], 1000, 2000);
+        assert_eq!(result.unwrap(), (500, 1000));
+    }
+
     #[test]
     fn test_coordinate_bounds_check_invalid_x() {
         // Use scale factor of 1.0 for testing bounds in Absolute mode
@@ -1115,4 +1573,75 @@ finish(message="已完成浏览，共看了15个帖子")"#;
         assert!(result.is_some());
         assert_eq!(result.unwrap(), r#"do(action="Test", value="hello")"#);
     }
+
+    #[test]
+    fn test_localized_errors() {
+        let mut handler = ActionHandler::with_scale(None, None, None, 1.0, 1.0);
+        handler.set_lang("cn");
+        let result = handler.execute(&do_action("Launch", &[]), 1080, 1920);
+        assert_eq!(result.message.as_deref(), Some("未指定应用名称"));
+
+        let result = handler.execute(
+            &do_action("Tap", &[("element", json!([1500, 500]))]),
+            1080,
+            1920,
+        );
+        assert!(result.out_of_bounds);
+        assert!(result.message.unwrap().starts_with("element 坐标错误"));
+
+        let error = ActionError::UnknownAction("Fly".to_string());
+        assert_eq!(error.localized("cn"), "未知操作: Fly");
+        assert_eq!(error.localized("en"), error.to_string());
+    }
+
+    #[test]
+    fn test_disabled_actions() {
+        let disabled = vec!["type".to_string(), "Long_Press".to_string()];
+        assert!(is_action_disabled("Type_Name", &disabled));
+        assert!(is_action_disabled("Long Press", &disabled));
+        assert!(!is_action_disabled("Tap", &disabled));
+
+        // Rejected before anything reaches the device
+        let mut handler = ActionHandler::new(None, None, None);
+        handler.set_disabled_actions(disabled);
+        let result = handler.execute(
+            &parse_action(r#"do(action="Type", text="hi")"#).unwrap(),
+            1080,
+            1920,
+        );
+        assert!(!result.success);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let mut handler = ActionHandler::with_relative_coordinates(None, None, None);
+        handler.set_dry_run(true);
+        let run = |response: &str| handler.execute(&parse_action(response).unwrap(), 1080, 2400);
+
+        let result = run(r#"do(action="Tap", element=[500, 250])"#);
+        assert!(result.success);
+        assert_eq!(result.touches, [(540, 600)]);
+        assert_eq!(
+            result.message.as_deref(),
+            Some("Dry run, not performed: Tap (540, 600)")
+        );
+
+        let result = run(r#"do(action="Swipe", start=[500, 800], end=[500, 200])"#);
+        assert_eq!(result.touches, [(540, 1920), (540, 480)]);
+        let result = run(r#"do(action="Launch", app="微信")"#);
+        assert_eq!(
+            result.message.as_deref(),
+            Some("Dry run, not performed: Launch 微信 (com.tencent.mm)")
+        );
+
+        // Sensitive taps are described without asking for confirmation
+        let result = run(r#"do(action="Tap", element=[10, 10], message="支付")"#);
+        assert!(result.success);
+        assert!(result.message.unwrap().contains("needs confirmation: 支付"));
+
+        // Coordinates are still checked
+        let result = run(r#"do(action="Swipe", start=[500, 800], end=[500, 1200])"#);
+        assert!(!result.success);
+        assert!(result.out_of_bounds);
+    }
 }
@@ -0,0 +1,204 @@
+//! Recorded action macros.
+//!
+//! A [`Macro`] is a sequence of actions in the same format the model produces,
+//! with coordinates in device pixels. It is recorded from the GUI preview and
+//! can be replayed later, also on a screen of a different resolution.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use super::handler::{ActionHandler, CoordinateSystem};
+
+/// Action parameters that hold a `[x, y]` pixel coordinate.
+const POINT_KEYS: [&str; 3] = ["element", "start", "end"];
+
+/// One recorded action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep {
+    /// Action as produced by `do_action`, with pixel coordinates.
+    pub action: Value,
+    /// Pause before the action, in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// A reusable sequence of actions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    /// Screen width the macro was recorded at.
+    pub screen_width: u32,
+    /// Screen height the macro was recorded at.
+    pub screen_height: u32,
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    /// Start an empty macro recorded on a screen of the given size.
+    pub fn new(name: impl Into<String>, screen_width: u32, screen_height: u32) -> Self {
+        Self {
+            name: name.into(),
+            screen_width,
+            screen_height,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Append an action, to be replayed `delay` after the previous one.
+    pub fn record(&mut self, action: Value, delay: Duration) {
+        self.steps.push(MacroStep {
+            action,
+            delay_ms: delay.as_millis() as u64,
+        });
+    }
+
+    /// Load a macro from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MacroError> {
+        let content = fs::read_to_string(path).map_err(|e| MacroError::IoError(e.to_string()))?;
+        serde_json::from_str(&content).map_err(|e| MacroError::ParseError(e.to_string()))
+    }
+
+    /// Save the macro as a JSON file, creating its directory if needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MacroError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| MacroError::IoError(e.to_string()))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| MacroError::SerializeError(e.to_string()))?;
+        fs::write(path, content).map_err(|e| MacroError::IoError(e.to_string()))
+    }
+
+    /// The steps' actions with coordinates moved to a screen of the given size.
+    pub fn scaled_actions(&self, screen_width: u32, screen_height: u32) -> Vec<Value> {
+        let ratio = |to: u32, from: u32| to as f64 / from.max(1) as f64;
+        let (rx, ry) = (
+            ratio(screen_width, self.screen_width),
+            ratio(screen_height, self.screen_height),
+        );
+
+        self.steps
+            .iter()
+            .map(|step| {
+                let mut action = step.action.clone();
+                for key in POINT_KEYS {
+                    let Some(point) = action.get(key).and_then(|v| v.as_array()) else {
+                        continue;
+                    };
+                    let x = point.first().and_then(Value::as_f64);
+                    let y = point.get(1).and_then(Value::as_f64);
+                    if let (Some(x), Some(y)) = (x, y) {
+                        action[key] =
+                            serde_json::json!([(x * rx).round() as i64, (y * ry).round() as i64]);
+                    }
+                }
+                action
+            })
+            .collect()
+    }
+
+    /// Replay the macro on a screen of the given size, waiting each step's
+    /// delay first. Stops at the first failed action.
+    ///
+    /// # Returns
+    /// Number of actions executed.
+    pub fn play(
+        &self,
+        device_id: Option<&str>,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> Result<usize, MacroError> {
+        let handler = macro_handler(device_id);
+        let actions = self.scaled_actions(screen_width, screen_height);
+
+        for (i, (step, action)) in self.steps.iter().zip(&actions).enumerate() {
+            std::thread::sleep(Duration::from_millis(step.delay_ms));
+            let result = handler.execute(action, screen_width, screen_height);
+            if !result.success {
+                return Err(MacroError::ActionFailed {
+                    step: i + 1,
+                    message: result.message.unwrap_or_default(),
+                });
+            }
+        }
+        Ok(actions.len())
+    }
+}
+
+/// Action handler that takes coordinates as device pixels, unscaled.
+pub fn macro_handler(device_id: Option<&str>) -> ActionHandler {
+    ActionHandler::with_options(
+        device_id.map(str::to_string),
+        None,
+        None,
+        1.0,
+        1.0,
+        CoordinateSystem::Absolute,
+    )
+}
+
+/// Macro errors.
+#[derive(Debug, Clone)]
+pub enum MacroError {
+    IoError(String),
+    ParseError(String),
+    SerializeError(String),
+    ActionFailed { step: usize, message: String },
+}
+
+impl std::fmt::Display for MacroError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::ParseError(e) => write!(f, "Parse error: {}", e),
+            Self::SerializeError(e) => write!(f, "Serialize error: {}", e),
+            Self::ActionFailed { step, message } => {
+                write!(f, "Step {} failed: {}", step, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MacroError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::do_action;
+    use serde_json::json;
+
+    #[test]
+    fn test_macro_round_trip_and_scaling() {
+        let mut recorded = Macro::new("open chat", 1080, 2400);
+        recorded.record(
+            do_action("Tap", &[("element", json!([540, 1200]))]),
+            Duration::ZERO,
+        );
+        recorded.record(
+            do_action(
+                "Swipe",
+                &[("start", json!([540, 2000])), ("end", json!([540, 400]))],
+            ),
+            Duration::from_millis(1500),
+        );
+        recorded.record(do_action("Back", &[]), Duration::from_millis(800));
+
+        let path = std::env::temp_dir().join(format!("macro_{}.json", uuid::Uuid::new_v4()));
+        recorded.save(&path).unwrap();
+        let loaded = Macro::load(&path).unwrap();
+        assert_eq!(loaded, recorded);
+        assert_eq!(loaded.steps[1].delay_ms, 1500);
+
+        // Replayed on a 720x1600 screen
+        let actions = loaded.scaled_actions(720, 1600);
+        assert_eq!(actions[0]["element"], json!([360, 800]));
+        assert_eq!(actions[1]["start"], json!([360, 1333]));
+        assert_eq!(actions[1]["end"], json!([360, 267]));
+        assert_eq!(actions[2], recorded.steps[2].action);
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,8 +1,11 @@
 //! Actions module for handling AI model outputs.
 
 mod handler;
+mod macros;
 
 pub use handler::{
-    do_action, finish_action, parse_action, ActionHandler, ActionResult, ConfirmationCallback,
-    CoordinateSystem, TakeoverCallback, DEFAULT_COORDINATE_SCALE, RELATIVE_COORDINATE_MAX,
+    default_takeover, do_action, finish_action, is_action_disabled, parse_action, ActionHandler,
+    ActionResult, ConfirmationCallback, CoordinateSystem, TakeoverCallback,
+    DEFAULT_COORDINATE_SCALE, RELATIVE_COORDINATE_MAX,
 };
+pub use macros::{macro_handler, Macro, MacroError, MacroStep};
@@ -0,0 +1,272 @@
+//! Discovery of the apps installed on a device.
+//!
+//! `Launch` only knows the apps in [`APP_PACKAGES`](crate::config::APP_PACKAGES).
+//! For anything else the device is asked for its installed packages and their
+//! labels, and the resulting name → package map is cached per device so the
+//! lookup runs once, not on every launch.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use super::command::CommandExt;
+use super::connection::get_adb_prefix;
+use super::device::get_serial_number;
+
+/// Lists every package with its label(s). `aapt` is used from the device's
+/// PATH or `/data/local/tmp`; without it only the package names come back.
+const DISCOVERY_SCRIPT: &str = "A=$(command -v aapt || echo /data/local/tmp/aapt); \
+for l in $(pm list packages -f); do \
+p=${l##*=}; f=${l#package:}; f=${f%=*}; \
+echo \"##$p\"; \"$A\" dump badging \"$f\" 2>/dev/null | grep application-label; \
+done";
+
+/// The apps installed on one device.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstalledApps {
+    /// Hardware serial number (or ADB device ID if it has none).
+    pub serial: String,
+    /// When the device was scanned (RFC 3339).
+    pub discovered_at: String,
+    /// App name → package name.
+    pub apps: BTreeMap<String, String>,
+}
+
+impl InstalledApps {
+    /// Scan a device for its installed apps.
+    pub fn discover(device_id: Option<&str>) -> Result<Self, String> {
+        let prefix = get_adb_prefix(device_id);
+
+        let output = Command::new(&prefix[0])
+            .args(&prefix[1..])
+            .args(["shell", DISCOVERY_SCRIPT])
+            .device_output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        let apps = parse_discovery(&String::from_utf8_lossy(&output.stdout));
+        if apps.is_empty() {
+            return Err("No packages found".to_string());
+        }
+        Ok(Self {
+            serial: device_serial(device_id),
+            discovered_at: Local::now().to_rfc3339(),
+            apps,
+        })
+    }
+
+    /// Package of the app called `name`. Matches labels exactly, then
+    /// ignoring case, and finally full package names.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        let name = name.trim();
+        self.apps
+            .get(name)
+            .or_else(|| {
+                self.apps
+                    .iter()
+                    .find(|(label, _)| label.eq_ignore_ascii_case(name))
+                    .map(|(_, package)| package)
+            })
+            .or_else(|| self.apps.values().find(|package| *package == name))
+            .map(String::as_str)
+    }
+}
+
+/// Installed apps of every scanned device.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstalledAppsCache {
+    pub devices: Vec<InstalledApps>,
+}
+
+impl InstalledAppsCache {
+    /// Load the cache from a JSON file. A missing file yields an empty cache.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    /// Save the cache to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    /// Apps of the device with the given serial.
+    pub fn get(&self, serial: &str) -> Option<&InstalledApps> {
+        self.devices.iter().find(|apps| apps.serial == serial)
+    }
+
+    /// Store a device's apps, replacing its previous scan.
+    pub fn insert(&mut self, apps: InstalledApps) {
+        self.devices
+            .retain(|existing| existing.serial != apps.serial);
+        self.devices.push(apps);
+    }
+}
+
+/// Find the package of an installed app by name.
+///
+/// The cached scan of the device is tried first. When the app isn't in it
+/// (or there is none), the device is scanned again and the cache updated,
+/// so newly installed apps are found too.
+///
+/// # Arguments
+/// * `app_name` - App label (e.g. "Telegram") or package name.
+/// * `device_id` - Optional ADB device ID.
+/// * `cache_path` - JSON file the scans are cached in (`None` = no cache).
+pub fn resolve_installed_app(
+    app_name: &str,
+    device_id: Option<&str>,
+    cache_path: Option<&Path>,
+) -> Option<String> {
+    let serial = device_serial(device_id);
+    let mut cache = cache_path
+        .and_then(|path| InstalledAppsCache::load(path).ok())
+        .unwrap_or_default();
+
+    if let Some(package) = cache.get(&serial).and_then(|apps| apps.resolve(app_name)) {
+        return Some(package.to_string());
+    }
+
+    let apps = match InstalledApps::discover(device_id) {
+        Ok(apps) => apps,
+        Err(e) => {
+            tracing::warn!("Failed to list installed apps: {}", e);
+            return None;
+        }
+    };
+    let package = apps.resolve(app_name).map(str::to_string);
+    if let Some(path) = cache_path {
+        cache.insert(apps);
+        if let Err(e) = cache.save(path) {
+            tracing::warn!("Failed to save installed apps: {}", e);
+        }
+    }
+    package
+}
+
+/// Key a device's scan is cached under.
+fn device_serial(device_id: Option<&str>) -> String {
+    get_serial_number(device_id)
+        .or_else(|| device_id.map(str::to_string))
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Build the name → package map from the output of [`DISCOVERY_SCRIPT`].
+///
+/// Every `application-label*` line under a `##package` header names that
+/// package. Packages without a label are named after the last part of the
+/// package name that isn't a generic word like "android" or "app".
+fn parse_discovery(output: &str) -> BTreeMap<String, String> {
+    let mut apps = BTreeMap::new();
+    let mut current: Option<(&str, bool)> = None;
+
+    let finish = |current: Option<(&str, bool)>, apps: &mut BTreeMap<String, String>| {
+        if let Some((package, false)) = current {
+            if let Some(name) = short_name(package) {
+                apps.entry(name).or_insert_with(|| package.to_string());
+            }
+        }
+    };
+
+    for line in output.lines().map(str::trim) {
+        if let Some(package) = line.strip_prefix("##") {
+            finish(current, &mut apps);
+            current = Some((package, false)).filter(|(p, _)| !p.is_empty());
+        } else if let Some((package, labelled)) = &mut current {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let label = value.trim().trim_matches('\'').trim();
+            if key.starts_with("application-label") && !label.is_empty() {
+                apps.entry(label.to_string())
+                    .or_insert_with(|| package.to_string());
+                *labelled = true;
+            }
+        }
+    }
+    finish(current, &mut apps);
+    apps
+}
+
+/// Name for a package without a label, e.g. "telegram" for
+/// `org.telegram.messenger`.
+fn short_name(package: &str) -> Option<String> {
+    const GENERIC: &[&str] = &[
+        "com",
+        "org",
+        "net",
+        "cn",
+        "android",
+        "app",
+        "apps",
+        "mobile",
+        "client",
+        "messenger",
+        "main",
+        "lite",
+    ];
+    package
+        .split('.')
+        .rev()
+        .find(|part| !GENERIC.contains(part))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_discovery() {
+        let output = "##org.telegram.messenger\n\
+                      application-label:'Telegram'\n\
+                      application-label-zh-CN:'电报'\n\
+                      ##com.example.notes\n\
+                      ##com.android.settings\n\
+                      application-label:'Settings'\n";
+        let apps = parse_discovery(output);
+
+        assert_eq!(apps["Telegram"], "org.telegram.messenger");
+        assert_eq!(apps["电报"], "org.telegram.messenger");
+        assert_eq!(apps["notes"], "com.example.notes");
+        assert_eq!(apps["Settings"], "com.android.settings");
+        // Labelled packages don't get a made-up name
+        assert!(!apps.contains_key("telegram"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let apps = InstalledApps {
+            serial: "abc".to_string(),
+            discovered_at: String::new(),
+            apps: parse_discovery("##org.telegram.messenger\napplication-label:'Telegram'\n"),
+        };
+        assert_eq!(apps.resolve("Telegram"), Some("org.telegram.messenger"));
+        assert_eq!(apps.resolve("telegram"), Some("org.telegram.messenger"));
+        assert_eq!(
+            apps.resolve("org.telegram.messenger"),
+            Some("org.telegram.messenger")
+        );
+        assert_eq!(apps.resolve("Signal"), None);
+
+        let mut cache = InstalledAppsCache::default();
+        cache.insert(apps.clone());
+        cache.insert(apps);
+        assert_eq!(cache.devices.len(), 1);
+        assert!(cache.get("abc").is_some());
+    }
+}
@@ -0,0 +1,177 @@
+//! Running ADB commands so that an abandoned step can stop them.
+//!
+//! Device functions block on `adb`. [`run_device`] moves such work to the
+//! blocking thread pool; when its future is dropped, e.g. by the executor's
+//! step timeout, the ADB command running at that moment is killed and any
+//! further ones fail right away.
+
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How often a running command checks whether its step was abandoned.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+thread_local! {
+    /// Abort flag of the `run_device` work running on this thread.
+    static ABORTED: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Run blocking device work off the async runtime.
+///
+/// Dropping the returned future before it completes kills the ADB command
+/// the work is waiting on, so a timeout around it takes effect at once.
+pub(crate) async fn run_device<T, F>(work: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let aborted = Arc::new(AtomicBool::new(false));
+    let _abort = AbortOnDrop(aborted.clone());
+    tokio::task::spawn_blocking(move || {
+        let _scope = Scope::enter(aborted);
+        work()
+    })
+    .await
+    .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Output of ADB commands that honours [`run_device`].
+pub(crate) trait CommandExt {
+    /// Run the command to completion and collect its output, like
+    /// `Command::output`. Inside [`run_device`] the command is killed as
+    /// soon as the work is abandoned.
+    fn device_output(&mut self) -> io::Result<Output>;
+}
+
+impl CommandExt for Command {
+    fn device_output(&mut self) -> io::Result<Output> {
+        let Some(aborted) = ABORTED.with(|flag| flag.borrow().clone()) else {
+            return self.output();
+        };
+        if aborted.load(Ordering::SeqCst) {
+            return Err(abandoned());
+        }
+
+        let mut child = self
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        // Drain both pipes while waiting, so a large screenshot can't fill them
+        thread::scope(|scope| {
+            let stdout = scope.spawn(move || read_pipe(stdout));
+            let stderr = scope.spawn(move || read_pipe(stderr));
+            let status = loop {
+                let abandoned_step = aborted.load(Ordering::SeqCst);
+                match child.try_wait() {
+                    Ok(Some(status)) => break status,
+                    Ok(None) if !abandoned_step => thread::sleep(POLL_INTERVAL),
+                    result => {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(result.err().unwrap_or_else(abandoned));
+                    }
+                }
+            };
+            Ok(Output {
+                status,
+                stdout: stdout.join().unwrap_or_default(),
+                stderr: stderr.join().unwrap_or_default(),
+            })
+        })
+    }
+}
+
+fn read_pipe(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buffer);
+    }
+    buffer
+}
+
+fn abandoned() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "device step was abandoned")
+}
+
+/// Marks the work of a `run_device` call as abandoned when dropped.
+struct AbortOnDrop(Arc<AtomicBool>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Installs the abort flag on a blocking thread for the duration of the work,
+/// even if it panics, since pool threads are reused.
+struct Scope;
+
+impl Scope {
+    fn enter(aborted: Arc<AtomicBool>) -> Self {
+        ABORTED.with(|flag| *flag.borrow_mut() = Some(aborted));
+        Scope
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        ABORTED.with(|flag| *flag.borrow_mut() = None);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_timeout_kills_hung_command() {
+        let dir = std::env::temp_dir().join(format!("phone-agent-hang-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("pid");
+
+        // Stands in for an `adb` call that never returns
+        let script = format!("echo $$ > {}; exec sleep 30", pid_file.display());
+        let started = Instant::now();
+        let step = run_device(move || Command::new("sh").args(["-c", &script]).device_output());
+        let outcome = tokio::time::timeout(Duration::from_millis(300), step).await;
+
+        // The timeout fired on this single-threaded runtime instead of
+        // waiting for the command
+        assert!(outcome.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let alive = || {
+            Command::new("kill")
+                .args(["-0", pid.trim()])
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while alive() && Instant::now() < deadline {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        assert!(!alive(), "the hung command was not killed");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_run_device_output() {
+        let output = run_device(|| Command::new("sh").args(["-c", "echo ok"]).device_output())
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+    }
+}
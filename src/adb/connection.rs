@@ -3,6 +3,8 @@
 use std::process::Command;
 use thiserror::Error;
 
+use super::command::CommandExt;
+
 /// Type of ADB connection.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConnectionType {
@@ -12,7 +14,7 @@ pub enum ConnectionType {
 }
 
 /// Information about a connected device.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DeviceInfo {
     pub device_id: String,
     pub status: String,
@@ -89,7 +91,7 @@ impl ADBConnection {
 
         let output = Command::new(&self.adb_path)
             .args(["connect", &address])
-            .output()
+            .device_output()
             .map_err(|e| AdbError::Connection(e.to_string()))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
@@ -117,7 +119,7 @@ impl ADBConnection {
 
         let output = Command::new(&self.adb_path)
             .args(&args)
-            .output()
+            .device_output()
             .map_err(|e| AdbError::Connection(e.to_string()))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
@@ -128,55 +130,23 @@ impl ADBConnection {
     pub fn list_devices(&self) -> Result<Vec<DeviceInfo>, AdbError> {
         let output = Command::new(&self.adb_path)
             .args(["devices", "-l"])
-            .output()
+            .device_output()
             .map_err(|e| AdbError::CommandFailed(e.to_string()))?;
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut devices = Vec::new();
-
-        for line in stdout.lines().skip(1) {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let device_id = parts[0].to_string();
-                let status = parts[1].to_string();
-
-                let connection_type = if device_id.contains(':') {
-                    ConnectionType::Remote
-                } else if device_id.contains("usb") {
-                    ConnectionType::Usb
-                } else {
-                    ConnectionType::Wifi
-                };
-
-                // Extract model if available
-                let model = parts
-                    .iter()
-                    .find(|p| p.starts_with("model:"))
-                    .map(|m| m.replace("model:", ""));
-
-                devices.push(DeviceInfo {
-                    device_id,
-                    status,
-                    connection_type,
-                    model,
-                    android_version: None,
-                });
-            }
-        }
+        Ok(parse_device_list(&String::from_utf8_lossy(&output.stdout)))
+    }
 
-        Ok(devices)
+    /// The ready device with the given ID, or the first ready one when no ID
+    /// is given; `AdbError::DeviceNotFound` if there is none.
+    pub fn find_device(&self, device_id: Option<&str>) -> Result<DeviceInfo, AdbError> {
+        select_device(self.list_devices()?, device_id)
     }
 
     /// Check if ADB server is running.
     pub fn is_running(&self) -> bool {
         Command::new(&self.adb_path)
             .args(["devices"])
-            .output()
+            .device_output()
             .is_ok()
     }
 
@@ -184,7 +154,7 @@ impl ADBConnection {
     pub fn start_server(&self) -> Result<(), AdbError> {
         Command::new(&self.adb_path)
             .args(["start-server"])
-            .output()
+            .device_output()
             .map_err(|e| AdbError::CommandFailed(e.to_string()))?;
         Ok(())
     }
@@ -193,12 +163,78 @@ impl ADBConnection {
     pub fn kill_server(&self) -> Result<(), AdbError> {
         Command::new(&self.adb_path)
             .args(["kill-server"])
-            .output()
+            .device_output()
             .map_err(|e| AdbError::CommandFailed(e.to_string()))?;
         Ok(())
     }
 }
 
+/// Parse the output of `adb devices -l`.
+fn parse_device_list(stdout: &str) -> Vec<DeviceInfo> {
+    let mut devices = Vec::new();
+
+    for line in stdout.lines().skip(1) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() >= 2 {
+            let device_id = parts[0].to_string();
+            let status = parts[1].to_string();
+
+            let connection_type = if device_id.contains(':') {
+                ConnectionType::Remote
+            } else if device_id.contains("usb") || parts.iter().any(|p| p.starts_with("usb:")) {
+                ConnectionType::Usb
+            } else {
+                ConnectionType::Wifi
+            };
+
+            // Extract model if available
+            let model = parts
+                .iter()
+                .find(|p| p.starts_with("model:"))
+                .map(|m| m.replace("model:", ""));
+
+            devices.push(DeviceInfo {
+                device_id,
+                status,
+                connection_type,
+                model,
+                android_version: None,
+            });
+        }
+    }
+
+    devices
+}
+
+/// Pick the ready device with the given ID, or the first ready one.
+fn select_device(
+    devices: Vec<DeviceInfo>,
+    device_id: Option<&str>,
+) -> Result<DeviceInfo, AdbError> {
+    let wanted = |device: &DeviceInfo| device_id.is_none_or(|id| device.device_id == id);
+    devices
+        .into_iter()
+        .find(|device| device.is_ready() && wanted(device))
+        .ok_or_else(|| match device_id {
+            Some(id) => {
+                AdbError::DeviceNotFound(format!("{} is not connected or not authorized", id))
+            }
+            None => AdbError::DeviceNotFound("no device connected".to_string()),
+        })
+}
+
+impl DeviceInfo {
+    /// Whether the device is online and authorized (`adb devices` state "device").
+    pub fn is_ready(&self) -> bool {
+        self.status == "device"
+    }
+}
+
 /// Get ADB command prefix with optional device specifier.
 pub(crate) fn get_adb_prefix(device_id: Option<&str>) -> Vec<String> {
     match device_id {
@@ -225,4 +261,32 @@ mod tests {
         let prefix_with_device = get_adb_prefix(Some("device123"));
         assert_eq!(prefix_with_device, vec!["adb", "-s", "device123"]);
     }
+
+    #[test]
+    fn test_parse_device_list() {
+        let stdout = "List of devices attached\n\
+                      R5CT1 device usb:1-1 product:a52 model:SM_A525F device:a52q\n\
+                      192.168.1.100:5555 offline\n\
+                      emulator-5554 unauthorized\n\n";
+        let devices = parse_device_list(stdout);
+        assert_eq!(devices.len(), 3);
+
+        assert_eq!(devices[0].device_id, "R5CT1");
+        assert_eq!(devices[0].connection_type, ConnectionType::Usb);
+        assert_eq!(devices[0].model.as_deref(), Some("SM_A525F"));
+        assert!(devices[0].is_ready());
+
+        assert_eq!(devices[1].connection_type, ConnectionType::Remote);
+        assert!(!devices[1].is_ready());
+        assert_eq!(devices[2].status, "unauthorized");
+
+        // Only ready devices are picked
+        let picked = select_device(devices.clone(), None).unwrap();
+        assert_eq!(picked.device_id, "R5CT1");
+        assert!(matches!(
+            select_device(devices, Some("emulator-5554")),
+            Err(AdbError::DeviceNotFound(_))
+        ));
+        assert!(select_device(Vec::new(), None).is_err());
+    }
 }
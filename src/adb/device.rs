@@ -1,11 +1,13 @@
 //! Device control utilities for Android automation.
 
+use std::path::Path;
 use std::process::Command;
 use std::thread;
 use std::time::Duration;
 
-use crate::config::APP_PACKAGES;
+use crate::config::{APP_PACKAGES, INTERNATIONAL_APP_PACKAGES};
 
+use super::command::CommandExt;
 use super::connection::get_adb_prefix;
 
 /// Get the currently focused app name.
@@ -21,7 +23,7 @@ pub fn get_current_app(device_id: Option<&str>) -> String {
     let output = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "dumpsys", "window"])
-        .output();
+        .device_output();
 
     let output = match output {
         Ok(o) => o,
@@ -32,7 +34,8 @@ pub fn get_current_app(device_id: Option<&str>) -> String {
 
     for line in stdout.lines() {
         if line.contains("mCurrentFocus") || line.contains("mFocusedApp") {
-            for (app_name, package) in APP_PACKAGES.iter() {
+            for (app_name, package) in APP_PACKAGES.iter().chain(INTERNATIONAL_APP_PACKAGES.iter())
+            {
                 if line.contains(*package) {
                     return app_name.to_string();
                 }
@@ -43,6 +46,87 @@ pub fn get_current_app(device_id: Option<&str>) -> String {
     "System Home".to_string()
 }
 
+/// Get the package of the focused app, e.g. "com.tencent.mm".
+pub fn get_current_package(device_id: Option<&str>) -> Option<String> {
+    let prefix = get_adb_prefix(device_id);
+
+    let output = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args(["shell", "dumpsys", "window"])
+        .device_output()
+        .ok()?;
+
+    parse_focused_package(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Find the focused package in the output of `dumpsys window`, from a line
+/// like `mCurrentFocus=Window{1a2b u0 com.tencent.mm/com.tencent.mm.ui.LauncherUI}`.
+fn parse_focused_package(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter(|line| line.contains("mCurrentFocus") || line.contains("mFocusedApp"))
+        .find_map(|line| {
+            line.split_whitespace()
+                .find_map(|word| word.split_once('/'))
+                .map(|(package, _)| package.to_string())
+                .filter(|package| !package.is_empty())
+        })
+}
+
+/// Get the device model (`ro.product.model`), e.g. "Pixel 8".
+pub fn get_device_model(device_id: Option<&str>) -> Option<String> {
+    let prefix = get_adb_prefix(device_id);
+
+    let output = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args(["shell", "getprop", "ro.product.model"])
+        .device_output()
+        .ok()?;
+
+    let model = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !model.is_empty()).then_some(model)
+}
+
+/// Get the hardware serial number (`ro.serialno`), which unlike the ADB
+/// device ID does not change between USB and wireless connections.
+pub fn get_serial_number(device_id: Option<&str>) -> Option<String> {
+    let prefix = get_adb_prefix(device_id);
+
+    let output = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args(["shell", "getprop", "ro.serialno"])
+        .device_output()
+        .ok()?;
+
+    let serial = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !serial.is_empty()).then_some(serial)
+}
+
+/// Get the screen resolution in pixels (width, height) as reported by
+/// `wm size`, preferring an override size over the physical one.
+pub fn get_screen_size(device_id: Option<&str>) -> Option<(u32, u32)> {
+    let prefix = get_adb_prefix(device_id);
+
+    let output = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args(["shell", "wm", "size"])
+        .device_output()
+        .ok()?;
+
+    parse_wm_size(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the output of `wm size`.
+fn parse_wm_size(output: &str) -> Option<(u32, u32)> {
+    let size_of = |label: &str| {
+        output.lines().find_map(|line| {
+            let (width, height) = line.strip_prefix(label)?.trim().split_once('x')?;
+            Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+        })
+    };
+    size_of("Override size:").or_else(|| size_of("Physical size:"))
+}
+
 /// Tap at the specified coordinates.
 ///
 /// # Arguments
@@ -57,7 +141,7 @@ pub fn tap(x: i32, y: i32, device_id: Option<&str>, delay_ms: Option<u64>) {
     let _ = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "input", "tap", &x.to_string(), &y.to_string()])
-        .output();
+        .device_output();
 
     thread::sleep(Duration::from_millis(delay));
 }
@@ -76,14 +160,14 @@ pub fn double_tap(x: i32, y: i32, device_id: Option<&str>, delay_ms: Option<u64>
     let _ = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "input", "tap", &x.to_string(), &y.to_string()])
-        .output();
+        .device_output();
 
     thread::sleep(Duration::from_millis(100));
 
     let _ = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "input", "tap", &x.to_string(), &y.to_string()])
-        .output();
+        .device_output();
 
     thread::sleep(Duration::from_millis(delay));
 }
@@ -119,7 +203,7 @@ pub fn long_press(
             &y.to_string(),
             &duration.to_string(),
         ])
-        .output();
+        .device_output();
 
     thread::sleep(Duration::from_millis(delay));
 }
@@ -165,7 +249,7 @@ pub fn swipe(
             &end_y.to_string(),
             &duration.to_string(),
         ])
-        .output();
+        .device_output();
 
     thread::sleep(Duration::from_millis(delay));
 }
@@ -182,7 +266,7 @@ pub fn back(device_id: Option<&str>, delay_ms: Option<u64>) {
     let _ = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "input", "keyevent", "4"])
-        .output();
+        .device_output();
 
     thread::sleep(Duration::from_millis(delay));
 }
@@ -199,7 +283,24 @@ pub fn home(device_id: Option<&str>, delay_ms: Option<u64>) {
     let _ = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "input", "keyevent", "KEYCODE_HOME"])
-        .output();
+        .device_output();
+
+    thread::sleep(Duration::from_millis(delay));
+}
+
+/// Open the recent apps screen.
+///
+/// # Arguments
+/// * `device_id` - Optional ADB device ID.
+/// * `delay_ms` - Delay in milliseconds after pressing recents (default 1000).
+pub fn recents(device_id: Option<&str>, delay_ms: Option<u64>) {
+    let prefix = get_adb_prefix(device_id);
+    let delay = delay_ms.unwrap_or(1000);
+
+    let _ = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args(["shell", "input", "keyevent", "KEYCODE_APP_SWITCH"])
+        .device_output();
 
     thread::sleep(Duration::from_millis(delay));
 }
@@ -214,11 +315,23 @@ pub fn home(device_id: Option<&str>, delay_ms: Option<u64>) {
 /// # Returns
 /// True if app was launched, False if app not found.
 pub fn launch_app(app_name: &str, device_id: Option<&str>, delay_ms: Option<u64>) -> bool {
-    let package = match APP_PACKAGES.get(app_name) {
-        Some(p) => *p,
-        None => return false,
-    };
+    match APP_PACKAGES.get(app_name) {
+        Some(package) => {
+            launch_package(package, device_id, delay_ms);
+            true
+        }
+        None => false,
+    }
+}
 
+/// Launch an app by package name, e.g. one found by
+/// [`resolve_installed_app`](super::resolve_installed_app).
+///
+/// # Arguments
+/// * `package` - The package name.
+/// * `device_id` - Optional ADB device ID.
+/// * `delay_ms` - Delay in milliseconds after launching (default 1000).
+pub fn launch_package(package: &str, device_id: Option<&str>, delay_ms: Option<u64>) {
     let prefix = get_adb_prefix(device_id);
     let delay = delay_ms.unwrap_or(1000);
 
@@ -233,10 +346,99 @@ pub fn launch_app(app_name: &str, device_id: Option<&str>, delay_ms: Option<u64>
             "android.intent.category.LAUNCHER",
             "1",
         ])
-        .output();
+        .device_output();
+
+    thread::sleep(Duration::from_millis(delay));
+}
+
+/// Copy a local file to the device with `adb push`.
+///
+/// # Returns
+/// The adb error output if the push failed.
+pub fn push_file(local: &Path, remote: &str, device_id: Option<&str>) -> Result<(), String> {
+    let prefix = get_adb_prefix(device_id);
+
+    let output = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .arg("push")
+        .arg(local)
+        .arg(remote)
+        .device_output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Delete a file on the device.
+pub fn remove_file(remote: &str, device_id: Option<&str>) {
+    let prefix = get_adb_prefix(device_id);
+
+    let _ = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args(["shell", "rm", "-f", remote])
+        .device_output();
+}
+
+/// Open an image on the device in the default viewer.
+///
+/// The file is announced to the media scanner first so gallery apps that
+/// only read the media store can open it.
+///
+/// # Arguments
+/// * `remote` - Absolute path of the image on the device.
+/// * `device_id` - Optional ADB device ID.
+/// * `delay_ms` - Delay in milliseconds for the viewer to render (default 1500).
+///
+/// # Returns
+/// True if the viewer activity was started.
+pub fn show_image(remote: &str, device_id: Option<&str>, delay_ms: Option<u64>) -> bool {
+    let prefix = get_adb_prefix(device_id);
+    let delay = delay_ms.unwrap_or(1500);
+    let uri = format!("file://{}", remote);
+
+    let _ = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args([
+            "shell",
+            "am",
+            "broadcast",
+            "-a",
+            "android.intent.action.MEDIA_SCANNER_SCAN_FILE",
+            "-d",
+            &uri,
+        ])
+        .device_output();
+
+    let output = Command::new(&prefix[0])
+        .args(&prefix[1..])
+        .args([
+            "shell",
+            "am",
+            "start",
+            "-a",
+            "android.intent.action.VIEW",
+            "-d",
+            &uri,
+            "-t",
+            "image/png",
+            "--grant-read-uri-permission",
+        ])
+        .device_output();
+
+    let started = match output {
+        Ok(o) => {
+            let stdout = String::from_utf8_lossy(&o.stdout);
+            o.status.success() && !stdout.contains("Error")
+        }
+        Err(_) => false,
+    };
 
     thread::sleep(Duration::from_millis(delay));
-    true
+    started
 }
 
 #[cfg(test)]
@@ -248,4 +450,28 @@ mod tests {
         // Should return false for unknown apps
         assert!(!launch_app("UnknownApp123", None, None));
     }
+
+    #[test]
+    fn test_parse_focused_package() {
+        let output =
+            "  mCurrentFocus=Window{1a2b u0 com.tencent.mm/com.tencent.mm.ui.LauncherUI}\n";
+        assert_eq!(
+            parse_focused_package(output).as_deref(),
+            Some("com.tencent.mm")
+        );
+        assert_eq!(parse_focused_package("mCurrentFocus=null\n"), None);
+    }
+
+    #[test]
+    fn test_parse_wm_size() {
+        assert_eq!(
+            parse_wm_size("Physical size: 1080x2400\n"),
+            Some((1080, 2400))
+        );
+        assert_eq!(
+            parse_wm_size("Physical size: 1440x3200\nOverride size: 1080x2400\n"),
+            Some((1080, 2400))
+        );
+        assert_eq!(parse_wm_size("error: no devices/emulators found"), None);
+    }
 }
@@ -5,6 +5,7 @@ use std::process::Command;
 use std::thread;
 use std::time::Duration;
 
+use super::command::CommandExt;
 use super::connection::get_adb_prefix;
 
 /// Type text into the currently focused input field using ADB Keyboard.
@@ -32,7 +33,7 @@ pub fn type_text(text: &str, device_id: Option<&str>) {
             "msg",
             &encoded_text,
         ])
-        .output();
+        .device_output();
 }
 
 /// Clear text in the currently focused input field.
@@ -45,7 +46,7 @@ pub fn clear_text(device_id: Option<&str>) {
     let _ = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "am", "broadcast", "-a", "ADB_CLEAR_TEXT"])
-        .output();
+        .device_output();
 }
 
 /// Detect current keyboard and switch to ADB Keyboard if needed.
@@ -62,7 +63,7 @@ pub fn detect_and_set_adb_keyboard(device_id: Option<&str>) -> String {
     let output = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "settings", "get", "secure", "default_input_method"])
-        .output();
+        .device_output();
 
     let current_ime = match output {
         Ok(o) => {
@@ -78,7 +79,7 @@ pub fn detect_and_set_adb_keyboard(device_id: Option<&str>) -> String {
         let _ = Command::new(&prefix[0])
             .args(&prefix[1..])
             .args(["shell", "ime", "set", "com.android.adbkeyboard/.AdbIME"])
-            .output();
+            .device_output();
     }
 
     // Warm up the keyboard
@@ -102,7 +103,7 @@ pub fn restore_keyboard(ime: &str, device_id: Option<&str>) {
     let _ = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["shell", "ime", "set", ime])
-        .output();
+        .device_output();
 }
 
 /// Type text with full keyboard handling (switch, type, restore).
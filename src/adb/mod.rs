@@ -1,11 +1,19 @@
 //! ADB (Android Debug Bridge) module for device interaction.
 
+mod apps;
+mod command;
 mod connection;
 mod device;
 pub mod input;
 mod screenshot;
 
-pub use connection::{ADBConnection, ConnectionType, DeviceInfo};
-pub use device::{back, double_tap, get_current_app, home, launch_app, long_press, swipe, tap};
+pub use apps::{resolve_installed_app, InstalledApps, InstalledAppsCache};
+pub(crate) use command::run_device;
+pub use connection::{ADBConnection, AdbError, ConnectionType, DeviceInfo};
+pub use device::{
+    back, double_tap, get_current_app, get_current_package, get_device_model, get_screen_size,
+    get_serial_number, home, launch_app, launch_package, long_press, push_file, recents,
+    remove_file, show_image, swipe, tap,
+};
 pub use input::{clear_text, detect_and_set_adb_keyboard, restore_keyboard, type_text};
-pub use screenshot::{get_screenshot, Screenshot};
+pub use screenshot::{difference_hash, get_screenshot, hash_similarity, Screenshot};
@@ -1,11 +1,13 @@
 //! Screenshot utilities for capturing Android device screen.
 
 use base64::{engine::general_purpose::STANDARD, Engine};
+use image::codecs::jpeg::JpegEncoder;
 use image::{DynamicImage, RgbImage};
 use std::io::Cursor;
 use std::process::Command;
 use thiserror::Error;
 
+use super::command::CommandExt;
 use super::connection::get_adb_prefix;
 
 /// Screenshot errors.
@@ -46,6 +48,53 @@ impl Screenshot {
     pub fn fallback(is_sensitive: bool) -> Self {
         create_fallback_screenshot(is_sensitive)
     }
+
+    /// 64-bit difference hash (dHash) of the screen, used to compare
+    /// consecutive screenshots. Returns `None` for sensitive (black fallback)
+    /// screens or undecodable data.
+    pub fn perceptual_hash(&self) -> Option<u64> {
+        if self.is_sensitive {
+            return None;
+        }
+        let data = STANDARD.decode(&self.base64_data).ok()?;
+        let img = image::load_from_memory(&data).ok()?;
+        Some(difference_hash(&img))
+    }
+
+    /// Re-encode the screenshot as JPEG with the given quality (1-100) and
+    /// return the base64 data. Returns `None` if the data cannot be decoded.
+    pub fn to_jpeg(&self, quality: u8) -> Option<String> {
+        let data = STANDARD.decode(&self.base64_data).ok()?;
+        let img = image::load_from_memory(&data).ok()?.to_rgb8();
+        let mut buffer = Cursor::new(Vec::new());
+        JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100))
+            .encode_image(&img)
+            .ok()?;
+        Some(STANDARD.encode(buffer.into_inner()))
+    }
+}
+
+/// Compute a 64-bit difference hash: the image is shrunk to 9x8 grayscale
+/// and each bit records whether a pixel is brighter than its right neighbour.
+pub fn difference_hash(img: &DynamicImage) -> u64 {
+    let small = img
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// Similarity of two perceptual hashes, from 0.0 (unrelated) to 1.0 (identical).
+pub fn hash_similarity(a: u64, b: u64) -> f64 {
+    1.0 - (a ^ b).count_ones() as f64 / 64.0
 }
 
 /// Capture a screenshot from the connected Android device.
@@ -70,7 +119,7 @@ pub fn get_screenshot(device_id: Option<&str>) -> Screenshot {
     let result = Command::new(&prefix[0])
         .args(&prefix[1..])
         .args(["exec-out", "screencap", "-p"])
-        .output();
+        .device_output();
 
     match result {
         Ok(output) => {
@@ -153,4 +202,26 @@ mod tests {
         assert!(screenshot.is_sensitive);
         assert!(!screenshot.base64_data.is_empty());
     }
+
+    #[test]
+    fn test_perceptual_hash_similarity() {
+        let gradient = |offset: u32| {
+            DynamicImage::ImageRgb8(RgbImage::from_fn(90, 160, |x, y| {
+                let v = ((x * 2 + y + offset) % 256) as u8;
+                image::Rgb([v, v, v])
+            }))
+        };
+        let a = difference_hash(&gradient(0));
+        assert_eq!(hash_similarity(a, a), 1.0);
+        assert_eq!(hash_similarity(a, difference_hash(&gradient(0))), 1.0);
+
+        let flipped = difference_hash(&gradient(0).fliph());
+        assert!(hash_similarity(a, flipped) < 0.5);
+
+        // Sensitive screens are never compared
+        assert!(create_fallback_screenshot(true).perceptual_hash().is_none());
+        assert!(create_fallback_screenshot(false)
+            .perceptual_hash()
+            .is_some());
+    }
 }
@@ -7,11 +7,41 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::sync::mpsc;
-use tokio::time::interval;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::{interval, sleep, Instant};
 
+use super::event_log::EventLog;
+use super::event_stream::{event_stream_bind_addr, EventStream, EVENT_STREAM_PATH};
+use super::events::{DualLoopEvent, EventBus};
 use super::executor::{ExecutorFeedback, ExecutorStatus};
 use super::planner::PlannerAgent;
+use super::session::{SessionError, SessionState};
+use super::spend::{SpendGuard, SpendLimit};
+
+/// Default task patterns that require user approval (payments, deletions,
+/// public posting). English patterns match at the start of a word, so a stem
+/// covers its inflections ("delet" holds "deleting") but not longer words
+/// ending in it ("pay" doesn't hold "repay").
+pub const DEFAULT_APPROVAL_PATTERNS: &[&str] = &[
+    "支付",
+    "付款",
+    "转账",
+    "购买",
+    "删除",
+    "发布",
+    "发朋友圈",
+    "pay",
+    "transfer",
+    "buy",
+    "purchas",
+    "delet",
+    "remov",
+    "publish",
+    "post",
+];
+
+/// Interval between checks for due scheduled todos (milliseconds).
+const SCHEDULER_INTERVAL_MS: u64 = 1000;
 
 /// Configuration for the dual loop runner.
 #[derive(Debug, Clone)]
@@ -22,6 +52,26 @@ pub struct DualLoopConfig {
     pub executor_interval_ms: u64,
     /// Whether to start immediately.
     pub auto_start: bool,
+    /// Path to persist session state to (disabled when `None`).
+    pub session_path: Option<String>,
+    /// Interval between session snapshots (milliseconds).
+    pub session_save_interval_ms: u64,
+    /// Whether matching tasks must be approved before the executor starts them.
+    pub require_approval: bool,
+    /// Task patterns that require approval (matched against description and type).
+    pub approval_patterns: Vec<String>,
+    /// Adapt the planner interval to executor progress: supervise immediately
+    /// on Stuck/Failed/Completed feedback and back off while the executor is
+    /// making progress.
+    pub adaptive_cadence: bool,
+    /// Upper bound for the backed-off planner interval (milliseconds).
+    pub max_planner_interval_ms: u64,
+    /// Token/cost cap; both loops pause when it is reached.
+    pub spend_limit: SpendLimit,
+    /// Directory for per-session JSONL event logs (disabled when `None`).
+    pub event_log_dir: Option<String>,
+    /// Address the event stream is served on over WebSocket (disabled when `None`).
+    pub event_stream_addr: Option<String>,
 }
 
 impl Default for DualLoopConfig {
@@ -30,6 +80,18 @@ impl Default for DualLoopConfig {
             planner_interval_ms: 2000, // 2 seconds
             executor_interval_ms: 500, // 0.5 seconds
             auto_start: true,
+            session_path: None,
+            session_save_interval_ms: 5000, // 5 seconds
+            require_approval: false,
+            approval_patterns: DEFAULT_APPROVAL_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            adaptive_cadence: true,
+            max_planner_interval_ms: 16000, // 16 seconds
+            spend_limit: SpendLimit::default(),
+            event_log_dir: None,
+            event_stream_addr: None,
         }
     }
 }
@@ -52,6 +114,217 @@ impl DualLoopConfig {
         self.auto_start = auto_start;
         self
     }
+
+    /// Persist session state to the given path.
+    pub fn with_session_path(mut self, path: impl Into<String>) -> Self {
+        self.session_path = Some(path.into());
+        self
+    }
+
+    /// Write every event of the session to a JSONL file in the given directory.
+    pub fn with_event_log_dir(mut self, dir: impl Into<String>) -> Self {
+        self.event_log_dir = Some(dir.into());
+        self
+    }
+
+    /// Broadcast every event of the session to WebSocket clients connecting
+    /// to the given address, e.g. `127.0.0.1:8765`.
+    pub fn with_event_stream_addr(mut self, addr: impl Into<String>) -> Self {
+        self.event_stream_addr = Some(addr.into());
+        self
+    }
+
+    /// Set the session snapshot interval.
+    pub fn with_session_save_interval(mut self, ms: u64) -> Self {
+        self.session_save_interval_ms = ms;
+        self
+    }
+
+    /// Require user approval before starting tasks that match the approval patterns.
+    pub fn with_require_approval(mut self, require: bool) -> Self {
+        self.require_approval = require;
+        self
+    }
+
+    /// Set the task patterns that require approval.
+    pub fn with_approval_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.approval_patterns = patterns;
+        self
+    }
+
+    /// Enable or disable the adaptive planner cadence.
+    pub fn with_adaptive_cadence(mut self, adaptive: bool) -> Self {
+        self.adaptive_cadence = adaptive;
+        self
+    }
+
+    /// Set the maximum backed-off planner interval.
+    pub fn with_max_planner_interval(mut self, ms: u64) -> Self {
+        self.max_planner_interval_ms = ms;
+        self
+    }
+
+    /// Pause both loops once this many tokens have been used.
+    pub fn with_max_tokens(mut self, max_tokens: u64) -> Self {
+        self.spend_limit.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Pause both loops once the estimated cost reaches this amount.
+    pub fn with_max_cost(mut self, max_cost: f64) -> Self {
+        self.spend_limit.max_cost = Some(max_cost);
+        self
+    }
+
+    /// Set the prices (per million tokens) used to estimate cost.
+    pub fn with_token_prices(mut self, planner: f64, executor: f64) -> Self {
+        self.spend_limit.planner_price_per_million = planner;
+        self.spend_limit.executor_price_per_million = executor;
+        self
+    }
+}
+
+/// Settings a running session takes over without a restart when the
+/// settings file changes (see [`DualLoopHandle::apply_settings`]). Fields
+/// left `None` keep their current value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LiveSettings {
+    /// Executor coordinate scale factors (absolute coordinates only).
+    pub scale_x: Option<f64>,
+    pub scale_y: Option<f64>,
+    /// Executor coordinate offsets in pixels, added after scaling.
+    pub offset_x: Option<f64>,
+    pub offset_y: Option<f64>,
+    /// Interval between Planner ticks (milliseconds).
+    pub planner_interval_ms: Option<u64>,
+    /// Interval between Executor ticks (milliseconds).
+    pub executor_interval_ms: Option<u64>,
+    /// Consecutive unchanged screens before the Executor counts as stuck.
+    pub stuck_threshold: Option<u32>,
+    /// Screenshot similarity at or above which the screen counts as unchanged.
+    pub screen_similarity_threshold: Option<f64>,
+    /// Language of prompts and messages ("cn" or "en").
+    pub lang: Option<String>,
+}
+
+impl LiveSettings {
+    /// Only the fields that differ from `previous`, so values the session
+    /// changed itself (e.g. scale from a calibration) are kept unless the
+    /// file changes them too.
+    pub fn changes_since(&self, previous: &LiveSettings) -> LiveSettings {
+        fn changed<T: Clone + PartialEq>(new: &Option<T>, old: &Option<T>) -> Option<T> {
+            new.clone().filter(|_| new != old)
+        }
+        LiveSettings {
+            scale_x: changed(&self.scale_x, &previous.scale_x),
+            scale_y: changed(&self.scale_y, &previous.scale_y),
+            offset_x: changed(&self.offset_x, &previous.offset_x),
+            offset_y: changed(&self.offset_y, &previous.offset_y),
+            planner_interval_ms: changed(&self.planner_interval_ms, &previous.planner_interval_ms),
+            executor_interval_ms: changed(
+                &self.executor_interval_ms,
+                &previous.executor_interval_ms,
+            ),
+            stuck_threshold: changed(&self.stuck_threshold, &previous.stuck_threshold),
+            screen_similarity_threshold: changed(
+                &self.screen_similarity_threshold,
+                &previous.screen_similarity_threshold,
+            ),
+            lang: changed(&self.lang, &previous.lang),
+        }
+    }
+
+    /// Whether no field is set.
+    pub fn is_empty(&self) -> bool {
+        *self == LiveSettings::default()
+    }
+}
+
+/// Decides when the Planner should supervise the Executor.
+///
+/// Significant feedback (Stuck, Failed, Completed, TimedOut, a new proposal,
+/// context overflow) is supervised immediately. While the Executor keeps making progress the
+/// planner interval doubles up to the configured maximum, and it drops back
+/// to the base interval as soon as progress stops.
+#[derive(Debug, Clone)]
+struct SupervisionCadence {
+    adaptive: bool,
+    base_ms: u64,
+    max_ms: u64,
+    current_ms: u64,
+    progressing: bool,
+}
+
+impl SupervisionCadence {
+    fn new(config: &DualLoopConfig) -> Self {
+        let base_ms = config.planner_interval_ms.max(1);
+        Self {
+            adaptive: config.adaptive_cadence,
+            base_ms,
+            max_ms: config.max_planner_interval_ms.max(base_ms),
+            current_ms: base_ms,
+            progressing: false,
+        }
+    }
+
+    /// Change the base interval, keeping the configured maximum above it.
+    fn set_base_interval(&mut self, ms: u64) {
+        self.base_ms = ms.max(1);
+        self.max_ms = self.max_ms.max(self.base_ms);
+        self.current_ms = self.base_ms;
+        self.progressing = false;
+    }
+
+    /// Current planner interval.
+    fn interval(&self) -> Duration {
+        Duration::from_millis(self.current_ms)
+    }
+
+    /// Record executor feedback. Returns true if the Planner should
+    /// supervise right away.
+    fn on_feedback(&mut self, feedback: &ExecutorFeedback) -> bool {
+        if !self.adaptive {
+            return true;
+        }
+
+        let significant = feedback.context_overflow_detected
+            || matches!(
+                feedback.status,
+                ExecutorStatus::Stuck
+                    | ExecutorStatus::Completed
+                    | ExecutorStatus::Failed(_)
+                    | ExecutorStatus::TimedOut
+            )
+            // A fresh proposal carries the step result; repeats while waiting do not
+            || (feedback.status == ExecutorStatus::AwaitingApproval
+                && feedback.last_result.is_some());
+
+        if significant {
+            self.current_ms = self.base_ms;
+            self.progressing = false;
+            return true;
+        }
+
+        if matches!(feedback.status, ExecutorStatus::Running) && feedback.screen_changed {
+            self.progressing = true;
+        }
+        false
+    }
+
+    /// Record a scheduled planner tick and return the delay until the next one.
+    fn on_planner_tick(&mut self) -> Duration {
+        if self.adaptive && self.progressing {
+            self.current_ms = (self.current_ms.saturating_mul(2)).min(self.max_ms);
+            tracing::debug!(
+                "Executor progressing, planner interval {}ms",
+                self.current_ms
+            );
+        } else {
+            self.current_ms = self.base_ms;
+        }
+        self.progressing = false;
+        self.interval()
+    }
 }
 
 /// Handle for controlling the dual loop from outside.
@@ -63,9 +336,33 @@ pub struct DualLoopHandle {
     control_tx: mpsc::Sender<ControlCommand>,
     /// Running flag.
     running: Arc<AtomicBool>,
+    /// Event stream shared with the planner.
+    events: EventBus,
+    /// Set to true once the loop has shut down and flushed its state.
+    finished: watch::Receiver<bool>,
+    /// Latest end-of-session summary from the planner.
+    summary: watch::Receiver<Option<String>>,
+}
+
+impl std::fmt::Debug for DualLoopHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DualLoopHandle")
+            .field("running", &self.is_running())
+            .finish_non_exhaustive()
+    }
 }
 
 impl DualLoopHandle {
+    /// Subscribe to live events (planner actions, executor feedback,
+    /// todo changes and reports).
+    ///
+    /// Each call returns an independent receiver that only sees events
+    /// published after it was created. Slow receivers may observe
+    /// `RecvError::Lagged` and should simply continue receiving.
+    pub fn subscribe(&self) -> broadcast::Receiver<DualLoopEvent> {
+        self.events.subscribe()
+    }
+
     /// Send user input to the planner.
     pub async fn send_user_input(&self, input: String) -> Result<(), DualLoopError> {
         self.user_input_tx
@@ -82,12 +379,39 @@ impl DualLoopHandle {
     }
 
     /// Stop the dual loop.
+    ///
+    /// The in-flight executor step is allowed to finish; prompt memory, the
+    /// session and episodic memory are persisted and a final report is
+    /// emitted before this returns.
     pub async fn stop(&self) -> Result<(), DualLoopError> {
-        self.running.store(false, Ordering::SeqCst);
         self.control_tx
             .send(ControlCommand::Stop)
             .await
-            .map_err(|_| DualLoopError::ChannelClosed)
+            .map_err(|_| DualLoopError::ChannelClosed)?;
+        self.wait_stopped().await;
+        Ok(())
+    }
+
+    /// Wait until the loop has shut down.
+    pub async fn wait_stopped(&self) {
+        let mut finished = self.finished.clone();
+        let _ = finished.wait_for(|done| *done).await;
+    }
+
+    /// The planner's latest end-of-session summary, published once every
+    /// task has finished (what was done, what failed and why, extracted data).
+    pub fn session_summary(&self) -> Option<String> {
+        self.summary.borrow().clone()
+    }
+
+    /// Wait for the first end-of-session summary. Returns `None` if the loop
+    /// stops before all tasks finished.
+    pub async fn wait_session_summary(&self) -> Option<String> {
+        let mut summary = self.summary.clone();
+        tokio::select! {
+            result = summary.wait_for(|s| s.is_some()) => result.ok().and_then(|s| s.clone()),
+            _ = self.wait_stopped() => self.session_summary(),
+        }
     }
 
     /// Pause the dual loop.
@@ -106,6 +430,93 @@ impl DualLoopHandle {
             .map_err(|_| DualLoopError::ChannelClosed)
     }
 
+    /// Approve a task that is waiting for approval.
+    pub async fn approve(&self, task_id: impl Into<String>) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::Approve(task_id.into()))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Reject a task that is waiting for approval (the task is skipped).
+    pub async fn reject(&self, task_id: impl Into<String>) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::Reject(task_id.into()))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Approve the executor's proposed action (propose-only mode); the
+    /// following `steps - 1` steps run without review.
+    pub async fn approve_proposal(&self, steps: u32) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::ApproveProposal(steps))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Reject the executor's proposed action (propose-only mode).
+    pub async fn reject_proposal(&self, reason: Option<String>) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::RejectProposal(reason))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Inject a prompt straight into the executor, bypassing the planner.
+    /// It is queued as soon as the loop processes control commands, before
+    /// the next executor step.
+    pub async fn inject_executor_prompt(
+        &self,
+        text: impl Into<String>,
+    ) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::InjectPrompt(text.into()))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Rearrange todos into the given order (unlisted tasks keep their place).
+    pub async fn reorder_todos(&self, task_ids: Vec<String>) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::ReorderTodos(task_ids))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Change the description of a task that hasn't started yet.
+    pub async fn edit_todo(
+        &self,
+        task_id: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::EditTodo {
+                task_id: task_id.into(),
+                description: description.into(),
+            })
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Turn propose-only mode on or off.
+    pub async fn set_propose_only(&self, enabled: bool) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::SetProposeOnly(enabled))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
+    /// Take over reloaded settings: the new intervals apply from the next
+    /// tick, scale and thresholds from the next executor step. Publishes
+    /// `SettingsReloaded` if anything changed.
+    pub async fn apply_settings(&self, settings: LiveSettings) -> Result<(), DualLoopError> {
+        self.control_tx
+            .send(ControlCommand::ApplySettings(Box::new(settings)))
+            .await
+            .map_err(|_| DualLoopError::ChannelClosed)
+    }
+
     /// Check if the loop is running.
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::SeqCst)
@@ -118,6 +529,18 @@ enum ControlCommand {
     Stop,
     Pause,
     Resume,
+    Approve(String),
+    Reject(String),
+    ApproveProposal(u32),
+    RejectProposal(Option<String>),
+    SetProposeOnly(bool),
+    InjectPrompt(String),
+    ReorderTodos(Vec<String>),
+    EditTodo {
+        task_id: String,
+        description: String,
+    },
+    ApplySettings(Box<LiveSettings>),
 }
 
 /// Errors from the dual loop.
@@ -155,17 +578,33 @@ pub struct DualLoopRunner {
     paused: Arc<AtomicBool>,
     /// Optional feedback callback.
     feedback_callback: Option<FeedbackCallback>,
+    /// Pauses both loops when the spend cap is reached.
+    spend_guard: SpendGuard,
 }
 
 impl DualLoopRunner {
     /// Create a new dual loop runner.
-    pub fn new(planner: PlannerAgent, config: DualLoopConfig) -> Self {
+    pub fn new(mut planner: PlannerAgent, config: DualLoopConfig) -> Self {
+        if config.require_approval {
+            planner.set_approval_patterns(config.approval_patterns.clone());
+        }
+        if let Some(ref dir) = config.event_log_dir {
+            match EventLog::create(dir) {
+                Ok(log) => {
+                    tracing::info!("Event log: {}", log.path().display());
+                    planner.event_bus().attach_log(log);
+                }
+                Err(e) => tracing::warn!("Failed to create event log in {}: {}", dir, e),
+            }
+        }
+        let spend_guard = SpendGuard::new(config.spend_limit.clone());
         Self {
             planner,
             config,
             running: Arc::new(AtomicBool::new(false)),
             paused: Arc::new(AtomicBool::new(false)),
             feedback_callback: None,
+            spend_guard,
         }
     }
 
@@ -178,16 +617,136 @@ impl DualLoopRunner {
         self
     }
 
+    /// Restore a session previously saved to `path`.
+    ///
+    /// The restored session keeps being persisted to the same path unless
+    /// the configuration already specifies a different one.
+    pub fn resume(mut self, path: impl AsRef<std::path::Path>) -> Result<Self, SessionError> {
+        let path = path.as_ref();
+        let state = SessionState::load(path)?;
+        self.planner.restore_session(state);
+        if self.config.session_path.is_none() {
+            self.config.session_path = Some(path.display().to_string());
+        }
+        Ok(self)
+    }
+
+    /// Save a session snapshot if persistence is enabled.
+    fn save_session(&self) {
+        if let Some(ref path) = self.config.session_path {
+            if let Err(e) = self.planner.snapshot_session().save(path) {
+                tracing::warn!("Failed to save session state: {}", e);
+            }
+        }
+    }
+
+    /// Pause both loops and ask the user to continue once the spend cap is reached.
+    fn check_spend(&mut self) {
+        let ledger = self.planner.spend_ledger();
+        if !self.spend_guard.check(ledger) {
+            return;
+        }
+
+        self.paused.store(true, Ordering::SeqCst);
+        let limit = self.spend_guard.limit();
+        let report = ledger.report(limit);
+        println!("💰 [System] 已达到花费上限，双循环已暂停");
+        println!("{}", report);
+        println!("   输入 resume 继续（将再放宽一个额度），或输入 quit 退出");
+        tracing::warn!("Spend limit reached: {} tokens", ledger.total_tokens());
+
+        let event = DualLoopEvent::SpendLimitReached {
+            total_tokens: ledger.total_tokens(),
+            cost: ledger.cost(limit),
+            report,
+        };
+        self.planner.event_bus().emit(event);
+    }
+
+    /// Take over reloaded settings, publishing `SettingsReloaded` with the
+    /// names of the ones that changed. Returns true if the executor interval
+    /// changed, so the caller can restart its timer.
+    fn apply_settings(
+        &mut self,
+        settings: &LiveSettings,
+        cadence: &mut SupervisionCadence,
+    ) -> bool {
+        let mut changed = self.planner.apply_live_settings(settings);
+        if let Some(ms) = settings
+            .planner_interval_ms
+            .filter(|ms| *ms != self.config.planner_interval_ms)
+        {
+            self.config.planner_interval_ms = ms;
+            cadence.set_base_interval(ms);
+            changed.push("planner_interval_ms".to_string());
+        }
+        let executor_interval = settings
+            .executor_interval_ms
+            .filter(|ms| *ms != self.config.executor_interval_ms);
+        if let Some(ms) = executor_interval {
+            self.config.executor_interval_ms = ms;
+            changed.push("executor_interval_ms".to_string());
+        }
+
+        if !changed.is_empty() {
+            println!("🔧 [System] 设置已重新加载: {}", changed.join(", "));
+            tracing::info!("Settings reloaded: {}", changed.join(", "));
+            self.planner
+                .event_bus()
+                .emit(DualLoopEvent::SettingsReloaded { changed });
+        }
+        executor_interval.is_some()
+    }
+
+    /// Flush all state and emit the final report. Called exactly once when
+    /// the loop terminates.
+    fn shutdown(&mut self) {
+        tracing::info!("Dual loop shutting down...");
+        self.planner.shutdown();
+        self.save_session();
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Path of this session's event log, if logging is enabled.
+    pub fn event_log_path(&self) -> Option<std::path::PathBuf> {
+        self.planner.event_bus().log_path()
+    }
+
+    /// Subscribe to events before the loop is started.
+    pub fn subscribe(&self) -> broadcast::Receiver<DualLoopEvent> {
+        self.planner.subscribe()
+    }
+
+    /// Start serving the event stream, if an address is configured.
+    async fn start_event_stream(&self) {
+        let Some(ref addr) = self.config.event_stream_addr else {
+            return;
+        };
+        match EventStream::bind(event_stream_bind_addr(addr)).await {
+            Ok(server) => {
+                if let Ok(local) = server.local_addr() {
+                    tracing::info!("Event stream: ws://{}{}", local, EVENT_STREAM_PATH);
+                }
+                server.spawn(self.subscribe());
+            }
+            Err(e) => tracing::warn!("Failed to serve the event stream on {}: {}", addr, e),
+        }
+    }
+
     /// Create a handle for external control.
     fn create_handle(
         &self,
         user_input_tx: mpsc::Sender<String>,
         control_tx: mpsc::Sender<ControlCommand>,
+        finished: watch::Receiver<bool>,
     ) -> DualLoopHandle {
         DualLoopHandle {
             user_input_tx,
             control_tx,
             running: self.running.clone(),
+            events: self.planner.event_bus().clone(),
+            finished,
+            summary: self.planner.session_summary_receiver(),
         }
     }
 
@@ -197,22 +756,30 @@ impl DualLoopRunner {
         // Create channels
         let (user_input_tx, mut user_input_rx) = mpsc::channel::<String>(100);
         let (control_tx, mut control_rx) = mpsc::channel::<ControlCommand>(10);
+        let (finished_tx, finished_rx) = watch::channel(false);
 
         // Create handle before moving self
-        let handle = self.create_handle(user_input_tx, control_tx);
+        let handle = self.create_handle(user_input_tx, control_tx, finished_rx);
 
         // Set running flag
         self.running.store(true, Ordering::SeqCst);
 
+        self.start_event_stream().await;
+
         // Start planner
         self.planner.start();
 
         // Spawn the main loop
         tokio::spawn(async move {
-            let mut planner_interval =
-                interval(Duration::from_millis(self.config.planner_interval_ms));
+            let mut cadence = SupervisionCadence::new(&self.config);
+            let planner_sleep = sleep(cadence.interval());
+            tokio::pin!(planner_sleep);
             let mut executor_interval =
                 interval(Duration::from_millis(self.config.executor_interval_ms));
+            let mut session_interval = interval(Duration::from_millis(
+                self.config.session_save_interval_ms.max(1),
+            ));
+            let mut scheduler_interval = interval(Duration::from_millis(SCHEDULER_INTERVAL_MS));
 
             loop {
                 // PRIORITY 1: Always check for user input first (non-blocking)
@@ -233,9 +800,8 @@ impl DualLoopRunner {
                     Some(cmd) = control_rx.recv() => {
                         match cmd {
                             ControlCommand::Stop => {
+                                // Branches run to completion, so no step is in flight here
                                 tracing::info!("Dual loop stopping...");
-                                self.planner.stop();
-                                self.running.store(false, Ordering::SeqCst);
                                 break;
                             }
                             ControlCommand::Pause => {
@@ -243,9 +809,57 @@ impl DualLoopRunner {
                                 self.paused.store(true, Ordering::SeqCst);
                             }
                             ControlCommand::Resume => {
+                                if self.spend_guard.is_tripped() {
+                                    self.spend_guard.extend(self.planner.spend_ledger());
+                                    println!("💰 [System] 用户确认继续，花费上限已放宽");
+                                }
                                 tracing::info!("Dual loop resumed");
                                 self.paused.store(false, Ordering::SeqCst);
                             }
+                            ControlCommand::Approve(task_id) => {
+                                if !self.planner.approve_task(&task_id) {
+                                    println!("⚠️ [System] 任务 {} 不在待批准列表中", task_id);
+                                }
+                            }
+                            ControlCommand::Reject(task_id) => {
+                                if !self.planner.reject_task(&task_id) {
+                                    println!("⚠️ [System] 任务 {} 不在待批准列表中", task_id);
+                                }
+                            }
+                            ControlCommand::ApproveProposal(steps) => {
+                                if !self.planner.approve_proposal(steps) {
+                                    println!("⚠️ [System] 当前没有等待审核的执行器提议");
+                                }
+                            }
+                            ControlCommand::RejectProposal(reason) => {
+                                if !self.planner.reject_proposal(reason) {
+                                    println!("⚠️ [System] 当前没有等待审核的执行器提议");
+                                }
+                            }
+                            ControlCommand::SetProposeOnly(enabled) => {
+                                self.planner.set_propose_only(enabled);
+                            }
+                            ControlCommand::InjectPrompt(content) => {
+                                self.planner.inject_executor_prompt(content);
+                            }
+                            ControlCommand::ReorderTodos(task_ids) => {
+                                if !self.planner.reorder_todos(&task_ids) {
+                                    println!("⚠️ [System] 无法调整任务顺序: {}", task_ids.join(", "));
+                                }
+                            }
+                            ControlCommand::EditTodo { task_id, description } => {
+                                if !self.planner.edit_todo(&task_id, &description) {
+                                    println!("⚠️ [System] 任务 {} 不存在或已开始，无法修改", task_id);
+                                }
+                            }
+                            ControlCommand::ApplySettings(settings) => {
+                                if self.apply_settings(&settings, &mut cadence) {
+                                    executor_interval = interval(Duration::from_millis(
+                                        self.config.executor_interval_ms.max(1),
+                                    ));
+                                }
+                                planner_sleep.as_mut().reset(Instant::now() + cadence.interval());
+                            }
                         }
                     }
 
@@ -259,8 +873,13 @@ impl DualLoopRunner {
                                 callback(&feedback);
                             }
 
-                            // React to new executor feedback immediately to reduce planner latency
-                            let _ = self.planner.tick_planner().await;
+                            // React to significant feedback immediately to reduce planner latency
+                            if cadence.on_feedback(&feedback) {
+                                let _ = self.planner.tick_planner().await;
+                                planner_sleep.as_mut().reset(Instant::now() + cadence.interval());
+                            }
+
+                            self.check_spend();
 
                             // Log significant events
                             match &feedback.status {
@@ -273,20 +892,43 @@ impl DualLoopRunner {
                                 ExecutorStatus::Stuck => {
                                     tracing::warn!("Executor stuck detected");
                                 }
+                                ExecutorStatus::TimedOut => {
+                                    tracing::warn!("Executor step timed out");
+                                }
                                 _ => {}
                             }
                         }
                     }
 
-                    // Planner tick (slower)
-                    _ = planner_interval.tick() => {
+                    // Planner tick (slower, adaptive)
+                    _ = &mut planner_sleep => {
                         if !self.paused.load(Ordering::SeqCst) {
                             let should_continue = self.planner.tick_planner().await;
 
+                            self.check_spend();
+
                             if !should_continue && !self.planner.has_pending_input() {
                                 tracing::info!("Planner has no more work, waiting for input...");
                             }
                         }
+                        planner_sleep.as_mut().reset(Instant::now() + cadence.on_planner_tick());
+                    }
+
+                    // Start scheduled todos whose time has come
+                    _ = scheduler_interval.tick() => {
+                        if !self.paused.load(Ordering::SeqCst) {
+                            self.planner.start_due_scheduled_task();
+                            self.planner.check_overdue_tasks();
+                        }
+                    }
+
+                    // Periodic session snapshot and debounced prompt memory writes
+                    _ = session_interval.tick() => {
+                        self.save_session();
+                        if let Err(e) = self.planner.flush_prompt_memory() {
+                            tracing::warn!("Failed to save prompt memory: {}", e);
+                        }
+                        self.planner.sync_prompt_memory_if_due().await;
                     }
 
                     // Check if we should exit
@@ -303,6 +945,12 @@ impl DualLoopRunner {
                 }
             }
 
+            // Share what this session learned before the final save
+            if let Err(e) = self.planner.sync_prompt_memory().await {
+                tracing::warn!("Failed to sync prompt memory: {}", e);
+            }
+            self.shutdown();
+            let _ = finished_tx.send(true);
             tracing::info!("Dual loop stopped");
         });
 
@@ -313,11 +961,18 @@ impl DualLoopRunner {
     /// This is a convenience method that blocks until the loop completes.
     pub async fn run_blocking(mut self) {
         self.running.store(true, Ordering::SeqCst);
+        self.start_event_stream().await;
         self.planner.start();
 
-        let mut planner_interval = interval(Duration::from_millis(self.config.planner_interval_ms));
+        let mut cadence = SupervisionCadence::new(&self.config);
+        let planner_sleep = sleep(cadence.interval());
+        tokio::pin!(planner_sleep);
         let mut executor_interval =
             interval(Duration::from_millis(self.config.executor_interval_ms));
+        let mut session_interval = interval(Duration::from_millis(
+            self.config.session_save_interval_ms.max(1),
+        ));
+        let mut scheduler_interval = interval(Duration::from_millis(SCHEDULER_INTERVAL_MS));
 
         loop {
             tokio::select! {
@@ -330,13 +985,16 @@ impl DualLoopRunner {
                             callback(&feedback);
                         }
 
-                        // React immediately to new executor feedback in blocking mode
-                        let _ = self.planner.tick_planner().await;
+                        // React immediately to significant executor feedback in blocking mode
+                        if cadence.on_feedback(&feedback) {
+                            let _ = self.planner.tick_planner().await;
+                            planner_sleep.as_mut().reset(Instant::now() + cadence.interval());
+                        }
                     }
                 }
 
-                // Planner tick
-                _ = planner_interval.tick() => {
+                // Planner tick (adaptive)
+                _ = &mut planner_sleep => {
                     if !self.paused.load(Ordering::SeqCst) {
                         let should_continue = self.planner.tick_planner().await;
 
@@ -345,15 +1003,42 @@ impl DualLoopRunner {
                             break;
                         }
                     }
+                    planner_sleep.as_mut().reset(Instant::now() + cadence.on_planner_tick());
+                }
+
+                // Start scheduled todos whose time has come
+                _ = scheduler_interval.tick() => {
+                    if !self.paused.load(Ordering::SeqCst) {
+                        self.planner.start_due_scheduled_task();
+                        self.planner.check_overdue_tasks();
+                    }
+                }
+
+                // Periodic session snapshot and debounced prompt memory writes
+                _ = session_interval.tick() => {
+                    self.save_session();
+                    if let Err(e) = self.planner.flush_prompt_memory() {
+                        tracing::warn!("Failed to save prompt memory: {}", e);
+                    }
+                    self.planner.sync_prompt_memory_if_due().await;
                 }
             }
 
+            // Nobody can confirm continuing in blocking mode, so the cap is final
+            self.check_spend();
+            if self.spend_guard.is_tripped() {
+                break;
+            }
+
             if !self.running.load(Ordering::SeqCst) {
                 break;
             }
         }
 
-        self.planner.stop();
+        if let Err(e) = self.planner.sync_prompt_memory().await {
+            tracing::warn!("Failed to sync prompt memory: {}", e);
+        }
+        self.shutdown();
         tracing::info!("Dual loop completed");
     }
 }
@@ -433,6 +1118,11 @@ mod tests {
         assert_eq!(config.planner_interval_ms, 2000);
         assert_eq!(config.executor_interval_ms, 500);
         assert!(config.auto_start);
+        assert!(config.session_path.is_none());
+        assert!(!config.require_approval);
+        assert!(!config.approval_patterns.is_empty());
+        assert!(config.adaptive_cadence);
+        assert_eq!(config.max_planner_interval_ms, 16000);
     }
 
     #[test]
@@ -446,4 +1136,322 @@ mod tests {
         assert_eq!(config.executor_interval_ms, 1000);
         assert!(!config.auto_start);
     }
+
+    fn feedback(status: ExecutorStatus, screen_changed: bool) -> ExecutorFeedback {
+        ExecutorFeedback {
+            task_id: Some("task_1".to_string()),
+            step_count: 1,
+            status,
+            last_result: None,
+            screen_changed,
+            timestamp: 0,
+            context_overflow_detected: false,
+            consecutive_parse_errors: 0,
+            screen_similarity: None,
+            result_data: None,
+            proposed_action: None,
+        }
+    }
+
+    #[test]
+    fn test_supervision_cadence_backoff() {
+        let config = DualLoopConfig::default()
+            .with_planner_interval(1000)
+            .with_max_planner_interval(3000);
+        let mut cadence = SupervisionCadence::new(&config);
+
+        // Progress defers supervision and backs off up to the maximum
+        assert!(!cadence.on_feedback(&feedback(ExecutorStatus::Running, true)));
+        assert_eq!(cadence.on_planner_tick(), Duration::from_millis(2000));
+        assert!(!cadence.on_feedback(&feedback(ExecutorStatus::Running, true)));
+        assert_eq!(cadence.on_planner_tick(), Duration::from_millis(3000));
+
+        // No progress since the last tick resets to the base interval
+        assert_eq!(cadence.on_planner_tick(), Duration::from_millis(1000));
+
+        // Significant feedback is supervised immediately and resets the interval
+        assert!(!cadence.on_feedback(&feedback(ExecutorStatus::Running, true)));
+        cadence.on_planner_tick();
+        assert!(cadence.on_feedback(&feedback(ExecutorStatus::Stuck, false)));
+        assert_eq!(cadence.interval(), Duration::from_millis(1000));
+        assert!(cadence.on_feedback(&feedback(ExecutorStatus::Completed, false)));
+        assert!(cadence.on_feedback(&feedback(ExecutorStatus::Failed("boom".to_string()), false)));
+    }
+
+    #[test]
+    fn test_supervision_cadence_fixed() {
+        let config = DualLoopConfig::default().with_adaptive_cadence(false);
+        let mut cadence = SupervisionCadence::new(&config);
+
+        assert!(cadence.on_feedback(&feedback(ExecutorStatus::Running, true)));
+        assert_eq!(cadence.on_planner_tick(), Duration::from_millis(2000));
+    }
+
+    #[tokio::test]
+    async fn test_stop_flushes_and_emits_final_report() {
+        use super::super::event_log::read_event_log;
+        use super::super::planner::PlannerConfig;
+        use crate::agent::AgentConfig;
+        use crate::model::ModelConfig;
+
+        let planner_config = PlannerConfig {
+            prompt_memory_path: None,
+            episodic_memory_path: None,
+            ..PlannerConfig::default()
+        };
+        let planner = PlannerAgent::new(
+            planner_config,
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let path = std::env::temp_dir().join("test_dual_loop_stop.json");
+        let log_dir = std::env::temp_dir().join("test_dual_loop_stop_events");
+        let _ = std::fs::remove_dir_all(&log_dir);
+        let config = DualLoopConfig::default()
+            .with_executor_interval(10)
+            .with_session_path(path.display().to_string())
+            .with_event_log_dir(log_dir.display().to_string());
+        let runner = DualLoopRunner::new(planner, config);
+        let log_path = runner.event_log_path().unwrap();
+        let mut rx = runner.subscribe();
+        let handle = runner.run().await;
+
+        handle.stop().await.unwrap();
+        assert!(!handle.is_running());
+        assert!(path.exists());
+
+        let mut stopped = false;
+        while let Ok(event) = rx.try_recv() {
+            if matches!(event, DualLoopEvent::Stopped { .. }) {
+                stopped = true;
+            }
+        }
+        assert!(stopped);
+
+        // The event log captured the same stream
+        let entries = read_event_log(&log_path).unwrap();
+        assert!(matches!(
+            entries.last().unwrap().event,
+            DualLoopEvent::Stopped { .. }
+        ));
+        let _ = std::fs::remove_dir_all(&log_dir);
+        let _ = SessionState::clear(&path);
+    }
+
+    #[test]
+    fn test_runner_resume_restores_todos() {
+        use super::super::planner::PlannerConfig;
+        use crate::agent::{AgentConfig, TodoList};
+        use crate::model::ModelConfig;
+
+        let mut todo_list = TodoList::new();
+        todo_list.add("打开设置", "设置调整");
+        let state = SessionState {
+            version: crate::agent::SESSION_FORMAT_VERSION,
+            saved_at: String::new(),
+            planner_context: Vec::new(),
+            todo_list,
+            pending_user_input: Vec::new(),
+            current_task_id: None,
+        };
+        let path = std::env::temp_dir().join("test_dual_loop_resume.json");
+        state.save(&path).unwrap();
+
+        let planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let runner = DualLoopRunner::new(planner, DualLoopConfig::default())
+            .resume(&path)
+            .unwrap();
+
+        assert_eq!(runner.planner.todo_list().items().len(), 1);
+        assert_eq!(
+            runner.config.session_path.as_deref(),
+            Some(path.display().to_string().as_str())
+        );
+        let _ = SessionState::clear(&path);
+    }
+
+    #[tokio::test]
+    async fn test_handle_subscribe_receives_executor_feedback() {
+        use super::super::planner::PlannerConfig;
+        use crate::agent::AgentConfig;
+        use crate::model::ModelConfig;
+
+        let planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let config = DualLoopConfig::default()
+            .with_planner_interval(60_000)
+            .with_executor_interval(10);
+        let handle = DualLoopRunner::new(planner, config).run().await;
+        let mut rx = handle.subscribe();
+
+        let event = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Ok(DualLoopEvent::ExecutorFeedback { feedback }) = rx.recv().await {
+                    return feedback;
+                }
+            }
+        })
+        .await
+        .expect("no executor feedback received");
+
+        assert_eq!(event.status, ExecutorStatus::Idle);
+        handle.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handle_inject_executor_prompt() {
+        use super::super::planner::PlannerConfig;
+        use crate::agent::AgentConfig;
+        use crate::model::ModelConfig;
+
+        let planner = PlannerAgent::new(
+            PlannerConfig {
+                prompt_memory_path: None,
+                episodic_memory_path: None,
+                ..PlannerConfig::default()
+            },
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let config = DualLoopConfig::default()
+            .with_planner_interval(60_000)
+            .with_executor_interval(60_000);
+        let handle = DualLoopRunner::new(planner, config).run().await;
+        let mut rx = handle.subscribe();
+
+        handle.inject_executor_prompt("往下滑一点").await.unwrap();
+        let (content, source) = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Ok(DualLoopEvent::PromptInjected {
+                    content, source, ..
+                }) = rx.recv().await
+                {
+                    return (content, source);
+                }
+            }
+        })
+        .await
+        .expect("no prompt injected");
+
+        assert_eq!(content, "往下滑一点");
+        assert_eq!(source, "user");
+        handle.stop().await.unwrap();
+    }
+
+    async fn next_todo_snapshot(
+        rx: &mut broadcast::Receiver<DualLoopEvent>,
+    ) -> Vec<super::super::TodoItem> {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Ok(DualLoopEvent::TodoChanged { items, .. }) = rx.recv().await {
+                    return items;
+                }
+            }
+        })
+        .await
+        .expect("no todo change received")
+    }
+
+    #[tokio::test]
+    async fn test_handle_edits_todos() {
+        use super::super::planner::PlannerConfig;
+        use crate::agent::AgentConfig;
+        use crate::model::ModelConfig;
+
+        let mut planner = PlannerAgent::new(
+            PlannerConfig {
+                prompt_memory_path: None,
+                episodic_memory_path: None,
+                ..PlannerConfig::default()
+            },
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let first = planner.todo_list_mut().add("打开微信", "open_app");
+        let second = planner.todo_list_mut().add("打开设置", "open_app");
+        let config = DualLoopConfig::default()
+            .with_planner_interval(60_000)
+            .with_executor_interval(60_000);
+        let handle = DualLoopRunner::new(planner, config).run().await;
+        let mut rx = handle.subscribe();
+
+        handle
+            .reorder_todos(vec![second.clone(), first.clone()])
+            .await
+            .unwrap();
+        let items = next_todo_snapshot(&mut rx).await;
+        assert_eq!(items[0].id, second);
+        assert_eq!(items[1].id, first);
+
+        handle.edit_todo(&second, "打开系统设置").await.unwrap();
+        let items = next_todo_snapshot(&mut rx).await;
+        assert_eq!(items[0].description, "打开系统设置");
+
+        handle.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handle_applies_reloaded_settings() {
+        use super::super::planner::PlannerConfig;
+        use crate::agent::AgentConfig;
+        use crate::model::ModelConfig;
+
+        let planner = PlannerAgent::new(
+            PlannerConfig {
+                prompt_memory_path: None,
+                episodic_memory_path: None,
+                ..PlannerConfig::default()
+            },
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let config = DualLoopConfig::default()
+            .with_planner_interval(60_000)
+            .with_executor_interval(60_000);
+        let handle = DualLoopRunner::new(planner, config).run().await;
+        let mut rx = handle.subscribe();
+
+        let settings = LiveSettings {
+            executor_interval_ms: Some(30_000),
+            stuck_threshold: Some(9),
+            lang: Some("cn".to_string()),
+            ..LiveSettings::default()
+        };
+        handle.apply_settings(settings).await.unwrap();
+        let changed = loop {
+            match rx.recv().await.unwrap() {
+                DualLoopEvent::SettingsReloaded { changed } => break changed,
+                _ => continue,
+            }
+        };
+        // The language was already Chinese
+        assert_eq!(changed, ["stuck_threshold", "executor_interval_ms"]);
+
+        handle.stop().await.unwrap();
+    }
+
+    #[test]
+    fn test_live_settings_changes_since() {
+        let previous = LiveSettings {
+            scale_x: Some(1.0),
+            lang: Some("cn".to_string()),
+            ..LiveSettings::default()
+        };
+        let current = LiveSettings {
+            scale_x: Some(1.0),
+            lang: Some("en".to_string()),
+            ..LiveSettings::default()
+        };
+        let changes = current.changes_since(&previous);
+        assert_eq!(changes.scale_x, None);
+        assert_eq!(changes.lang.as_deref(), Some("en"));
+        assert!(current.changes_since(&current).is_empty());
+    }
 }
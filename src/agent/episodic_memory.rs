@@ -0,0 +1,331 @@
+//! Episodic memory of past planner sessions.
+//!
+//! While `PromptMemory` learns *how* to execute a task type, episodic memory
+//! remembers *what* the user asked for in previous sessions and how it went.
+//! Relevant episodes are surfaced to the Planner so repeat requests such as
+//! "do my usual morning routine" can be planned from past experience.
+//!
+//! Relevance is a simple keyword / character-bigram overlap; no vector
+//! database or embedding model is used.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::prompt_memory::PromptMemoryError;
+use super::todo::{TodoItem, TodoStatus};
+
+/// Default maximum number of episodes kept on disk.
+pub const DEFAULT_MAX_EPISODES: usize = 50;
+
+/// Outcome of a single task within an episode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeTask {
+    /// Task description.
+    pub description: String,
+    /// Task type used for prompt memory.
+    pub task_type: String,
+    /// Final status of the task.
+    pub status: TodoStatus,
+}
+
+impl From<&TodoItem> for EpisodeTask {
+    fn from(item: &TodoItem) -> Self {
+        Self {
+            description: item.description.clone(),
+            task_type: item.task_type.clone(),
+            status: item.status.clone(),
+        }
+    }
+}
+
+/// Summary of one past session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    /// What the user asked for (in order).
+    pub requests: Vec<String>,
+    /// Tasks the Planner created and their outcome.
+    pub tasks: Vec<EpisodeTask>,
+    /// When the episode was recorded (ISO 8601 format).
+    pub timestamp: String,
+}
+
+impl Episode {
+    /// Create a new episode from the user's requests and the resulting tasks.
+    pub fn new(requests: Vec<String>, tasks: Vec<EpisodeTask>) -> Self {
+        Self {
+            requests,
+            tasks,
+            timestamp: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Number of tasks that completed successfully.
+    pub fn succeeded(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|t| t.status == TodoStatus::Done)
+            .count()
+    }
+
+    /// Whether every task in the episode completed successfully.
+    pub fn is_success(&self) -> bool {
+        !self.tasks.is_empty() && self.succeeded() == self.tasks.len()
+    }
+
+    /// Format the episode for the Planner's context.
+    pub fn summary(&self) -> String {
+        let date = self.timestamp.get(..10).unwrap_or(&self.timestamp);
+        let mut lines = vec![format!(
+            "- [{}] 用户请求: {} (成功 {}/{})",
+            date,
+            self.requests.join(" / "),
+            self.succeeded(),
+            self.tasks.len()
+        )];
+        for task in &self.tasks {
+            let icon = match task.status {
+                TodoStatus::Done => "✅",
+                TodoStatus::Failed => "❌",
+                TodoStatus::Skipped => "⏭️",
+                _ => "⏳",
+            };
+            lines.push(format!(
+                "    {} {} [{}]",
+                icon, task.description, task.task_type
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Text used for relevance matching.
+    fn search_text(&self) -> String {
+        let mut text = self.requests.join(" ");
+        for task in &self.tasks {
+            text.push(' ');
+            text.push_str(&task.description);
+            text.push(' ');
+            text.push_str(&task.task_type);
+        }
+        text
+    }
+}
+
+/// Episodic memory storage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodicMemory {
+    /// Recorded episodes, oldest first.
+    pub episodes: Vec<Episode>,
+    /// Maximum number of episodes to keep.
+    #[serde(default = "default_max_episodes")]
+    pub max_episodes: usize,
+}
+
+fn default_max_episodes() -> usize {
+    DEFAULT_MAX_EPISODES
+}
+
+impl Default for EpisodicMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EpisodicMemory {
+    /// Create a new empty episodic memory.
+    pub fn new() -> Self {
+        Self {
+            episodes: Vec::new(),
+            max_episodes: DEFAULT_MAX_EPISODES,
+        }
+    }
+
+    /// Load episodic memory from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PromptMemoryError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+
+        let content =
+            fs::read_to_string(path).map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+
+        serde_json::from_str(&content).map_err(|e| PromptMemoryError::ParseError(e.to_string()))
+    }
+
+    /// Save episodic memory to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PromptMemoryError> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| PromptMemoryError::SerializeError(e.to_string()))?;
+
+        fs::write(path, content).map_err(|e| PromptMemoryError::IoError(e.to_string()))
+    }
+
+    /// Record an episode, dropping the oldest ones beyond the limit.
+    pub fn record(&mut self, episode: Episode) {
+        self.episodes.push(episode);
+        let max = self.max_episodes.max(1);
+        if self.episodes.len() > max {
+            let excess = self.episodes.len() - max;
+            self.episodes.drain(..excess);
+        }
+    }
+
+    /// Get the number of episodes.
+    pub fn len(&self) -> usize {
+        self.episodes.len()
+    }
+
+    /// Check if empty.
+    pub fn is_empty(&self) -> bool {
+        self.episodes.is_empty()
+    }
+
+    /// Get the most recent episodes, newest first.
+    pub fn recent(&self, limit: usize) -> Vec<&Episode> {
+        self.episodes.iter().rev().take(limit).collect()
+    }
+
+    /// Find the episodes most relevant to a request, best match first.
+    /// Episodes without any overlap are never returned.
+    pub fn find_relevant(&self, query: &str, limit: usize) -> Vec<&Episode> {
+        let query_terms = terms(query);
+        if query_terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scored: Vec<(usize, usize, &Episode)> = self
+            .episodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, episode)| {
+                let score = terms(&episode.search_text())
+                    .intersection(&query_terms)
+                    .count();
+                (score > 0).then_some((score, i, episode))
+            })
+            .collect();
+
+        // Higher score first; newer episodes win ties
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        scored.into_iter().take(limit).map(|(_, _, e)| e).collect()
+    }
+
+    /// Format the most relevant episodes for the Planner's context.
+    /// Returns `None` when nothing relevant was found.
+    pub fn relevant_summary(&self, query: &str, limit: usize) -> Option<String> {
+        let episodes = self.find_relevant(query, limit);
+        if episodes.is_empty() {
+            return None;
+        }
+        Some(
+            episodes
+                .iter()
+                .map(|e| e.summary())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    /// Clear all episodes.
+    pub fn clear(&mut self) {
+        self.episodes.clear();
+    }
+}
+
+/// Split text into matching terms: lowercase words for alphabetic scripts
+/// and character bigrams for CJK text (which has no word separators).
+fn terms(text: &str) -> HashSet<String> {
+    let mut result = HashSet::new();
+    let lower = text.to_lowercase();
+
+    for word in lower.split(|c: char| !c.is_alphanumeric()) {
+        if word.is_empty() {
+            continue;
+        }
+        if word.is_ascii() {
+            if word.len() > 2 {
+                result.insert(word.to_string());
+            }
+            continue;
+        }
+
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() == 1 {
+            result.insert(word.to_string());
+        }
+        for pair in chars.windows(2) {
+            result.insert(pair.iter().collect());
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(description: &str, task_type: &str, status: TodoStatus) -> EpisodeTask {
+        EpisodeTask {
+            description: description.to_string(),
+            task_type: task_type.to_string(),
+            status,
+        }
+    }
+
+    #[test]
+    fn test_find_relevant_episodes() {
+        let mut memory = EpisodicMemory::new();
+        memory.record(Episode::new(
+            vec!["早上例行任务".to_string()],
+            vec![
+                task("打开天气查看今日天气", "天气查询", TodoStatus::Done),
+                task("打开网易云播放日推", "音乐播放", TodoStatus::Done),
+            ],
+        ));
+        memory.record(Episode::new(
+            vec!["给张三发微信".to_string()],
+            vec![task("在微信给张三发消息", "微信操作", TodoStatus::Failed)],
+        ));
+
+        let relevant = memory.find_relevant("帮我做一下早上的例行任务", 3);
+        assert_eq!(relevant.len(), 1);
+        assert_eq!(relevant[0].requests[0], "早上例行任务");
+        assert!(relevant[0].is_success());
+
+        let relevant = memory.find_relevant("微信发消息", 3);
+        assert_eq!(relevant.len(), 1);
+        assert!(!relevant[0].is_success());
+
+        assert!(memory.find_relevant("xyz", 3).is_empty());
+        assert!(memory.relevant_summary("早上", 3).unwrap().contains("天气"));
+    }
+
+    #[test]
+    fn test_record_limit_and_persistence() {
+        let mut memory = EpisodicMemory::new();
+        memory.max_episodes = 2;
+        for i in 0..3 {
+            memory.record(Episode::new(vec![format!("request {}", i)], Vec::new()));
+        }
+        assert_eq!(memory.len(), 2);
+        assert_eq!(memory.recent(1)[0].requests[0], "request 2");
+
+        let path = std::env::temp_dir().join("test_episodic_memory.json");
+        memory.save(&path).unwrap();
+        let loaded = EpisodicMemory::load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.max_episodes, 2);
+        let _ = std::fs::remove_file(&path);
+    }
+}
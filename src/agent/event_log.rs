@@ -0,0 +1,211 @@
+//! Structured JSONL log of dual-loop sessions.
+//!
+//! Every event published on the `EventBus` (planner actions, executor
+//! feedback, prompt injections, consolidations, ...) is appended as one JSON
+//! line to a per-session file. The reader API loads a log back so sessions
+//! can be audited and bugs reproduced.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::events::DualLoopEvent;
+
+/// File extension of event log files.
+pub const EVENT_LOG_EXTENSION: &str = "jsonl";
+
+/// One line of an event log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventLogEntry {
+    /// Sequence number within the session, starting at 1.
+    pub seq: u64,
+    /// When the event was logged (RFC 3339).
+    pub timestamp: String,
+    /// The logged event.
+    pub event: DualLoopEvent,
+}
+
+/// Append-only writer for a session's event log.
+#[derive(Debug)]
+pub struct EventLog {
+    path: PathBuf,
+    file: File,
+    seq: u64,
+}
+
+impl EventLog {
+    /// Create a new log file for a session in `dir`.
+    ///
+    /// File names start with the local start time so that sorting by name
+    /// sorts sessions chronologically.
+    pub fn create(dir: impl AsRef<Path>) -> Result<Self, EventLogError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(|e| EventLogError::IoError(e.to_string()))?;
+
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let name = format!(
+            "session-{}-{}.{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            &id[..8],
+            EVENT_LOG_EXTENSION
+        );
+        Self::open(dir.join(name))
+    }
+
+    /// Open (or create) a log file and continue appending to it.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, EventLogError> {
+        let path = path.into();
+        let seq = if path.exists() {
+            read_event_log(&path)?.last().map(|e| e.seq).unwrap_or(0)
+        } else {
+            0
+        };
+        let truncated = fs::read(&path).is_ok_and(|b| !b.is_empty() && !b.ends_with(b"\n"));
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| EventLogError::IoError(e.to_string()))?;
+
+        // Terminate a line truncated by a crash so new entries start cleanly
+        if truncated {
+            file.write_all(b"\n")
+                .map_err(|e| EventLogError::IoError(e.to_string()))?;
+        }
+        Ok(Self { path, file, seq })
+    }
+
+    /// Path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event. Each line is written in a single call so a crash
+    /// leaves at most one truncated line at the end.
+    pub fn append(&mut self, event: &DualLoopEvent) -> Result<(), EventLogError> {
+        let entry = EventLogEntry {
+            seq: self.seq + 1,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            event: event.clone(),
+        };
+        let mut line = serde_json::to_string(&entry)
+            .map_err(|e| EventLogError::SerializeError(e.to_string()))?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .map_err(|e| EventLogError::IoError(e.to_string()))?;
+        self.seq = entry.seq;
+        Ok(())
+    }
+}
+
+/// Read all entries of an event log.
+///
+/// Lines that cannot be parsed (e.g. truncated by a crash mid-write) are
+/// skipped with a warning so the rest of the session can still be audited.
+pub fn read_event_log(path: impl AsRef<Path>) -> Result<Vec<EventLogEntry>, EventLogError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(EventLogError::NotFound(path.display().to_string()));
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| EventLogError::IoError(e.to_string()))?;
+
+    let mut entries = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<EventLogEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => tracing::warn!(
+                "Skipping malformed line {} in {}: {}",
+                i + 1,
+                path.display(),
+                e
+            ),
+        }
+    }
+    Ok(entries)
+}
+
+/// List the event logs in `dir`, oldest first.
+pub fn list_event_logs(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == EVENT_LOG_EXTENSION))
+        .collect();
+    logs.sort();
+    logs
+}
+
+/// Event log errors.
+#[derive(Debug, Clone)]
+pub enum EventLogError {
+    NotFound(String),
+    IoError(String),
+    ParseError(String),
+    SerializeError(String),
+}
+
+impl std::fmt::Display for EventLogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(p) => write!(f, "Event log not found: {}", p),
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::ParseError(e) => write!(f, "Parse error: {}", e),
+            Self::SerializeError(e) => write!(f, "Serialize error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EventLogError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::events::EventBus;
+
+    #[test]
+    fn test_event_log_roundtrip() {
+        let dir = std::env::temp_dir().join("test_event_log_roundtrip");
+        let _ = fs::remove_dir_all(&dir);
+
+        let bus = EventBus::default();
+        let log = EventLog::create(&dir).unwrap();
+        let path = log.path().to_path_buf();
+        bus.attach_log(log);
+        bus.emit(DualLoopEvent::UserInput {
+            input: "打开微信".to_string(),
+        });
+        bus.emit(DualLoopEvent::Report {
+            message: "done".to_string(),
+        });
+
+        // Simulate a crash mid-write
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"seq\":3,\"times").unwrap();
+
+        let entries = read_event_log(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].seq, 2);
+        assert!(matches!(entries[0].event, DualLoopEvent::UserInput { .. }));
+        assert_eq!(list_event_logs(&dir), vec![path.clone()]);
+
+        // Reopening continues the sequence after the truncated line
+        let mut log = EventLog::open(&path).unwrap();
+        log.append(&DualLoopEvent::Report {
+            message: "resumed".to_string(),
+        })
+        .unwrap();
+        let entries = read_event_log(&path).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].seq, 3);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,307 @@
+//! WebSocket endpoint for the dual-loop event stream.
+//!
+//! An `EventStream` serves the events of one session at
+//! `ws://<addr>/events`, one JSON text message per event (the same objects
+//! as the event log), so external dashboards can render planner actions,
+//! executor feedback and todo changes live. Clients only listen: apart from
+//! pings and close frames, whatever they send is ignored.
+//!
+//! Browsers send the page's `Origin` with the handshake; only pages served
+//! from this machine may connect, so a website opened in the user's browser
+//! can't read the session. Clients outside a browser send no `Origin`.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, WebSocketConfig};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use super::events::{DualLoopEvent, DEFAULT_EVENT_CAPACITY};
+
+/// Path the event stream is served on (`/` works too).
+pub const EVENT_STREAM_PATH: &str = "/events";
+
+/// Largest client message accepted; clients only send control frames.
+const MAX_CLIENT_MESSAGE_LEN: usize = 64 * 1024;
+/// How long a client gets to answer the close frame sent when the session stops.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// WebSocket server broadcasting dual-loop events to every connected client.
+#[derive(Debug)]
+pub struct EventStream {
+    listener: TcpListener,
+}
+
+impl EventStream {
+    /// Listen on the given address, e.g. `127.0.0.1:8765`.
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr).await?,
+        })
+    }
+
+    /// Address the server listens on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serve events from the given subscription until the dual loop stops.
+    /// Clients receive the events published after they connect; they are
+    /// closed normally once the session is over.
+    pub fn spawn(self, mut rx: broadcast::Receiver<DualLoopEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let (clients, _) = broadcast::channel::<String>(DEFAULT_EVENT_CAPACITY);
+            loop {
+                tokio::select! {
+                    accepted = self.listener.accept() => match accepted {
+                        Ok((stream, peer)) => {
+                            let events = clients.subscribe();
+                            tokio::spawn(async move {
+                                if let Err(e) = serve_client(stream, events).await {
+                                    tracing::debug!("Event stream client {} dropped: {}", peer, e);
+                                }
+                            });
+                        }
+                        Err(e) => tracing::warn!("Event stream failed to accept a client: {}", e),
+                    },
+                    event = rx.recv() => match event {
+                        Ok(event) => {
+                            let stopped = matches!(event, DualLoopEvent::Stopped { .. });
+                            if let Ok(json) = serde_json::to_string(&event) {
+                                let _ = clients.send(json);
+                            }
+                            if stopped {
+                                break;
+                            }
+                        }
+                        Err(RecvError::Lagged(n)) => {
+                            tracing::warn!("Event stream lagged behind, {} events skipped", n);
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+        })
+    }
+}
+
+/// Address to bind for an `event_stream_addr` setting: `host:port`, or just
+/// a port (`8765` or `:8765`) for the loopback interface.
+pub fn event_stream_bind_addr(addr: &str) -> String {
+    let addr = addr.trim();
+    let port = addr.strip_prefix(':').unwrap_or(addr);
+    match port.parse::<u16>() {
+        Ok(port) => format!("127.0.0.1:{}", port),
+        Err(_) => addr.to_string(),
+    }
+}
+
+/// Upgrade one connection and forward events to it.
+async fn serve_client(
+    stream: TcpStream,
+    mut events: broadcast::Receiver<String>,
+) -> tungstenite::Result<()> {
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(MAX_CLIENT_MESSAGE_LEN))
+        .max_frame_size(Some(MAX_CLIENT_MESSAGE_LEN));
+    let mut socket =
+        tokio_tungstenite::accept_hdr_async_with_config(stream, check_request, Some(config))
+            .await?;
+
+    loop {
+        tokio::select! {
+            // Pings and close frames are answered by tungstenite itself
+            message = socket.next() => match message {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            event = events.recv() => match event {
+                Ok(json) => socket.send(Message::text(json)).await?,
+                Err(RecvError::Lagged(n)) => {
+                    tracing::warn!("Event stream client lagged behind, {} events skipped", n);
+                }
+                Err(RecvError::Closed) => {
+                    socket
+                        .close(Some(CloseFrame {
+                            code: CloseCode::Normal,
+                            reason: "session stopped".into(),
+                        }))
+                        .await?;
+                    // Wait for the client's reply to complete the closing handshake
+                    let _ = tokio::time::timeout(CLOSE_TIMEOUT, async {
+                        while let Some(Ok(_)) = socket.next().await {}
+                    })
+                    .await;
+                    return Ok(());
+                }
+            },
+        }
+    }
+}
+
+/// Accept handshakes for the event stream path from non-browser clients and
+/// local pages; refuse everything else.
+// The signature is tungstenite's handshake callback
+#[allow(clippy::result_large_err)]
+fn check_request(request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    let path = request.uri().path();
+    if path != "/" && path != EVENT_STREAM_PATH {
+        return Err(refuse(
+            StatusCode::NOT_FOUND,
+            format!("The event stream is at {}", EVENT_STREAM_PATH),
+        ));
+    }
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        if !origin.to_str().is_ok_and(is_local_origin) {
+            tracing::warn!("Event stream refused a page from origin {:?}", origin);
+            return Err(refuse(
+                StatusCode::FORBIDDEN,
+                "Only pages served from this machine may connect".to_string(),
+            ));
+        }
+    }
+    Ok(response)
+}
+
+fn refuse(status: StatusCode, body: String) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(body));
+    *response.status_mut() = status;
+    response
+}
+
+/// Whether a browser `Origin` (`scheme://host[:port]`) is on this machine.
+fn is_local_origin(origin: &str) -> bool {
+    let Some((_, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::events::EventBus;
+    use crate::agent::todo::TodoStats;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::Bytes;
+
+    #[test]
+    fn test_handshake_checks() {
+        assert!(is_local_origin("http://localhost:3000"));
+        assert!(is_local_origin("http://127.0.0.1"));
+        assert!(is_local_origin("https://[::1]:8080"));
+        assert!(!is_local_origin("https://evil.example"));
+        assert!(!is_local_origin("http://localhost.evil.example"));
+        assert!(!is_local_origin("null"));
+
+        assert_eq!(event_stream_bind_addr("8765"), "127.0.0.1:8765");
+        assert_eq!(event_stream_bind_addr(":8765"), "127.0.0.1:8765");
+        assert_eq!(event_stream_bind_addr("0.0.0.0:8765"), "0.0.0.0:8765");
+    }
+
+    #[tokio::test]
+    async fn test_stream_events() {
+        let bus = EventBus::default();
+        let server = EventStream::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.spawn(bus.subscribe());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (mut client, response) =
+            tokio_tungstenite::client_async(format!("ws://{}/events", addr), stream)
+                .await
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+
+        bus.emit(DualLoopEvent::Report {
+            message: "hello".to_string(),
+        });
+        let Some(Ok(Message::Text(json))) = client.next().await else {
+            panic!("expected an event");
+        };
+        let event: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(event["event"], "report");
+        assert_eq!(event["message"], "hello");
+
+        // Pings are answered
+        client
+            .send(Message::Ping(Bytes::from_static(b"hi")))
+            .await
+            .unwrap();
+        assert_eq!(
+            client.next().await.unwrap().unwrap(),
+            Message::Pong(Bytes::from_static(b"hi"))
+        );
+
+        // The session stopping closes the stream
+        bus.emit(DualLoopEvent::Stopped {
+            stats: TodoStats::default(),
+        });
+        assert!(client.next().await.unwrap().unwrap().is_text());
+        let Some(Ok(Message::Close(Some(frame)))) = client.next().await else {
+            panic!("expected a close frame");
+        };
+        assert_eq!(frame.code, CloseCode::Normal);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refused_handshakes() {
+        let bus = EventBus::default();
+        let server = EventStream::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let _handle = server.spawn(bus.subscribe());
+
+        // A page from another site
+        let mut request = format!("ws://{}/events", addr)
+            .into_client_request()
+            .unwrap();
+        request
+            .headers_mut()
+            .insert(header::ORIGIN, "https://evil.example".parse().unwrap());
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let Err(tungstenite::Error::Http(response)) =
+            tokio_tungstenite::client_async(request, stream).await
+        else {
+            panic!("foreign origin was accepted");
+        };
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Another path, and an unsupported protocol version
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let Err(tungstenite::Error::Http(response)) =
+            tokio_tungstenite::client_async(format!("ws://{}/api", addr), stream).await
+        else {
+            panic!("unknown path was accepted");
+        };
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                b"GET /events HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Version: 8\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        assert!(!String::from_utf8_lossy(&response).starts_with("HTTP/1.1 101"));
+    }
+}
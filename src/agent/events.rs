@@ -0,0 +1,182 @@
+//! Typed event stream for the dual-loop architecture.
+//!
+//! The Planner publishes events on a broadcast channel so that GUIs and web
+//! frontends can render live state without parsing terminal output.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use super::event_log::EventLog;
+use super::executor::ExecutorFeedback;
+use super::planner::PlannerAction;
+use super::todo::{TodoItem, TodoStats};
+
+/// Default capacity of the event broadcast channel.
+pub const DEFAULT_EVENT_CAPACITY: usize = 256;
+
+/// Events emitted by the dual loop.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DualLoopEvent {
+    /// The user sent a request to the Planner.
+    UserInput { input: String },
+    /// The Planner is about to execute an action.
+    PlannerAction { action: PlannerAction },
+    /// The Executor produced feedback after a tick.
+    ExecutorFeedback { feedback: Box<ExecutorFeedback> },
+    /// The todo list changed (snapshot of all items after the change).
+    TodoChanged {
+        items: Vec<TodoItem>,
+        stats: TodoStats,
+    },
+    /// The Planner model replied (raw response text, before its actions run).
+    PlannerResponse { content: String },
+    /// Feedback the system gave the Planner about what happened.
+    SystemFeedback { content: String },
+    /// The Planner reported a message to the user.
+    Report { message: String },
+    /// A correction was injected into the Executor.
+    PromptInjected {
+        task_id: Option<String>,
+        content: String,
+        /// "planner" for inject_prompt actions, "stuck_correction" for
+        /// automatic corrections when the Executor is stuck, "user" for
+        /// prompts injected directly through the handle.
+        source: String,
+    },
+    /// Accumulated corrections were consolidated into a task type's prompt.
+    PromptConsolidated { task_type: String, prompt: String },
+    /// A task is waiting for user approval before it can start.
+    ApprovalRequired {
+        task_id: String,
+        description: String,
+    },
+    /// Propose-only mode: the Executor proposed an action that waits for review.
+    ProposalPending {
+        task_id: Option<String>,
+        step: u32,
+        action: Value,
+        thinking: String,
+    },
+    /// The Executor asked a human to take over (login, captcha, ...).
+    TakeoverRequired { message: String },
+    /// A task missed its deadline or exceeded its maximum duration.
+    TaskOverdue { task_id: String, reason: String },
+    /// Every task in the todo list reached a terminal state.
+    AllTasksFinished { stats: TodoStats },
+    /// The Planner's final summary once every task reached a terminal state.
+    SessionSummary { summary: String, stats: TodoStats },
+    /// The spend cap was reached and both loops were paused.
+    SpendLimitReached {
+        total_tokens: u64,
+        cost: f64,
+        report: String,
+    },
+    /// The settings file changed and these settings were applied to the
+    /// running session.
+    SettingsReloaded { changed: Vec<String> },
+    /// The dual loop shut down; no further events follow.
+    Stopped { stats: TodoStats },
+}
+
+/// Sending half of the event stream.
+///
+/// Sending never fails from the caller's point of view: events published
+/// while nobody is subscribed are simply dropped. When an event log is
+/// attached, every event is also appended to it (shared by all clones).
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<DualLoopEvent>,
+    log: Arc<Mutex<Option<EventLog>>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+impl EventBus {
+    /// Create a new event bus with the given channel capacity.
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self {
+            tx,
+            log: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Write every event published from now on to the given log.
+    pub fn attach_log(&self, log: EventLog) {
+        *self.log.lock().unwrap() = Some(log);
+    }
+
+    /// Path of the attached event log, if any.
+    pub fn log_path(&self) -> Option<std::path::PathBuf> {
+        self.log
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|log| log.path().to_path_buf())
+    }
+
+    /// Publish an event to all current subscribers.
+    pub fn emit(&self, event: DualLoopEvent) {
+        if let Some(log) = self.log.lock().unwrap().as_mut() {
+            if let Err(e) = log.append(&event) {
+                tracing::warn!("Failed to write event log: {}", e);
+            }
+        }
+        let _ = self.tx.send(event);
+    }
+
+    /// Subscribe to the event stream.
+    pub fn subscribe(&self) -> broadcast::Receiver<DualLoopEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Number of active subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emit_without_subscribers() {
+        let bus = EventBus::default();
+        assert_eq!(bus.subscriber_count(), 0);
+        bus.emit(DualLoopEvent::Report {
+            message: "nobody listens".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_subscribe_receives_events() {
+        let bus = EventBus::default();
+        let mut rx = bus.subscribe();
+        bus.emit(DualLoopEvent::Report {
+            message: "hello".to_string(),
+        });
+
+        match rx.try_recv().unwrap() {
+            DualLoopEvent::Report { message } => assert_eq!(message, "hello"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let event = DualLoopEvent::PlannerAction {
+            action: PlannerAction::Wait,
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"event\":\"planner_action\""));
+        assert!(json.contains("\"action\":\"wait\""));
+    }
+}
@@ -9,19 +9,24 @@ use std::fs::OpenOptions;
 use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::dual_loop::LiveSettings;
 use super::phone_agent::{AgentConfig, PhoneAgent, StepResult};
-use crate::model::ModelConfig;
+use crate::actions::CoordinateSystem;
+use crate::adb::{get_current_app, get_screenshot, hash_similarity, run_device, Screenshot};
+use crate::model::{MessageBuilder, ModelClient, ModelConfig, TokenUsage};
 
 /// Executor status enumeration.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum ExecutorStatus {
     /// Idle, waiting for task.
+    #[default]
     Idle,
     /// Currently executing.
     Running,
@@ -33,12 +38,10 @@ pub enum ExecutorStatus {
     Completed,
     /// Task failed with error message.
     Failed(String),
-}
-
-impl Default for ExecutorStatus {
-    fn default() -> Self {
-        Self::Idle
-    }
+    /// The last step exceeded the step timeout and was aborted.
+    TimedOut,
+    /// Propose-only mode: an action was proposed and waits for approval.
+    AwaitingApproval,
 }
 
 /// Commands that can be sent from Planner to Executor.
@@ -49,6 +52,12 @@ pub enum ExecutorCommand {
         task_id: String,
         description: String,
         system_prompt: Option<String>,
+        /// Executor settings for this task's type.
+        #[serde(default)]
+        overrides: Option<ExecutorOverrides>,
+        /// Successful run of a task of the same type, shown as an example.
+        #[serde(default)]
+        example: Option<String>,
     },
     /// Pause execution.
     Pause,
@@ -60,6 +69,83 @@ pub enum ExecutorCommand {
     ResetContext,
     /// Stop the current task.
     Stop,
+    /// Execute the proposed action and let the next `steps - 1` steps run
+    /// without asking (propose-only mode).
+    ApproveProposal { steps: u32 },
+    /// Discard the proposed action; the reason is passed to the next step.
+    RejectProposal { reason: Option<String> },
+    /// Turn propose-only mode on or off.
+    SetProposeOnly { enabled: bool },
+}
+
+/// Executor settings overridden for one task type (e.g. map navigation
+/// needs more steps than opening an app). Unset fields keep the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutorOverrides {
+    /// Maximum steps before the task fails.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_steps: Option<u32>,
+    /// Coordinate system ("relative" or "absolute").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coordinate_system: Option<String>,
+    /// Scale factor for X coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_x: Option<f64>,
+    /// Scale factor for Y coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_y: Option<f64>,
+    /// JPEG quality (1-100) for screenshots sent to the model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screenshot_quality: Option<u8>,
+}
+
+impl ExecutorOverrides {
+    /// Whether no field is overridden.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Combine with `other`; fields set in `other` win.
+    pub fn merged(&self, other: &ExecutorOverrides) -> Self {
+        Self {
+            max_steps: other.max_steps.or(self.max_steps),
+            coordinate_system: other
+                .coordinate_system
+                .clone()
+                .or_else(|| self.coordinate_system.clone()),
+            scale_x: other.scale_x.or(self.scale_x),
+            scale_y: other.scale_y.or(self.scale_y),
+            screenshot_quality: other.screenshot_quality.or(self.screenshot_quality),
+        }
+    }
+
+    /// Apply the overrides to an agent configuration.
+    pub fn apply(&self, config: &mut AgentConfig) {
+        if let Some(max_steps) = self.max_steps {
+            config.max_steps = max_steps;
+        }
+        match self
+            .coordinate_system
+            .as_deref()
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("relative") => config.coordinate_system = CoordinateSystem::Relative,
+            Some("absolute") => config.coordinate_system = CoordinateSystem::Absolute,
+            Some(other) => tracing::warn!("Ignoring unknown coordinate system override: {}", other),
+            None => {}
+        }
+        if let Some(scale_x) = self.scale_x {
+            config.scale_x = scale_x;
+        }
+        if let Some(scale_y) = self.scale_y {
+            config.scale_y = scale_y;
+        }
+        if let Some(quality) = self.screenshot_quality {
+            config.screenshot_quality = Some(quality.clamp(1, 100));
+        }
+    }
 }
 
 /// Feedback from Executor to Planner.
@@ -83,6 +169,16 @@ pub struct ExecutorFeedback {
     /// Consecutive parse error count.
     #[serde(default)]
     pub consecutive_parse_errors: u32,
+    /// Perceptual similarity between the last two screenshots (0.0-1.0).
+    /// `None` when either screen could not be compared (e.g. sensitive screens).
+    #[serde(default)]
+    pub screen_similarity: Option<f64>,
+    /// Data returned with `finish(data=...)` once the task completed.
+    #[serde(default)]
+    pub result_data: Option<Value>,
+    /// Action waiting for approval in propose-only mode.
+    #[serde(default)]
+    pub proposed_action: Option<Value>,
 }
 
 /// Summarized step result for feedback (without large data).
@@ -93,6 +189,15 @@ pub struct StepResultSummary {
     pub thinking: String,
     pub message: Option<String>,
     pub action_type: Option<String>,
+    /// Compact form of the executed action, e.g. `do(action="Tap", element=[500,300])`.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// App in the foreground when the step was taken.
+    #[serde(default)]
+    pub app: Option<String>,
+    /// Tokens consumed by the step's model call.
+    #[serde(default)]
+    pub usage: TokenUsage,
 }
 
 impl From<&StepResult> for StepResultSummary {
@@ -120,16 +225,49 @@ impl From<&StepResult> for StepResultSummary {
             thinking: result.thinking.clone(),
             message: result.message.clone(),
             action_type,
+            action: result.action.as_ref().and_then(format_action),
+            app: None,
+            usage: result.usage,
         }
     }
 }
 
+/// Format an action JSON back into the compact call syntax the model emits,
+/// e.g. `do(action="Tap", element=[500,300])` or `finish(message="done")`.
+pub fn format_action(action: &Value) -> Option<String> {
+    let obj = action.as_object()?;
+    let name = obj.get("_metadata").and_then(|v| v.as_str())?;
+    let mut params: Vec<String> = Vec::new();
+    if let Some(kind) = obj.get("action").and_then(|v| v.as_str()) {
+        params.push(format!("action={}", Value::from(kind)));
+    }
+    params.extend(
+        obj.iter()
+            .filter(|(k, _)| *k != "_metadata" && *k != "action")
+            .map(|(k, v)| format!("{}={}", k, v)),
+    );
+    Some(format!("{}({})", name, params.join(", ")))
+}
+
+/// Takeover callback shared by every PhoneAgent the wrapper creates.
+pub type SharedTakeoverCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Sensitive-operation confirmation callback shared by every PhoneAgent the
+/// wrapper creates.
+pub type SharedConfirmationCallback = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
 /// Default stuck threshold (consecutive unchanged screens).
 pub const DEFAULT_STUCK_THRESHOLD: u32 = 3;
 
+/// Default screenshot similarity at or above which the screen counts as unchanged.
+pub const DEFAULT_SCREEN_SIMILARITY_THRESHOLD: f64 = 0.95;
+
 /// Default parse error threshold before suggesting context reset.
 pub const DEFAULT_PARSE_ERROR_THRESHOLD: u32 = 3;
 
+/// Default per-step timeout (model call plus device action), in seconds.
+pub const DEFAULT_STEP_TIMEOUT_SECS: u64 = 120;
+
 /// Executor wrapper that provides control interfaces for PhoneAgent.
 pub struct ExecutorWrapper {
     /// Inner PhoneAgent instance.
@@ -146,8 +284,14 @@ pub struct ExecutorWrapper {
     current_task_description: Option<String>,
     /// Command queue from Planner.
     command_queue: VecDeque<ExecutorCommand>,
-    /// Last screenshot hash for stuck detection.
+    /// Last context hash for stuck detection.
     last_screen_hash: Option<u64>,
+    /// Perceptual hash of the last screenshot.
+    last_image_hash: Option<u64>,
+    /// Similarity between the last two screenshots.
+    screen_similarity: Option<f64>,
+    /// Similarity at or above which the screen counts as unchanged.
+    similarity_threshold: f64,
     /// Consecutive unchanged screen count.
     stuck_count: u32,
     /// Stuck detection threshold.
@@ -156,6 +300,22 @@ pub struct ExecutorWrapper {
     pending_prompt: Option<String>,
     /// Consecutive parse error count (indicates potential context overflow).
     consecutive_parse_errors: u32,
+    /// Maximum duration of a single step (`None` = no watchdog).
+    step_timeout: Option<Duration>,
+    /// Data returned by the finish action of the current task.
+    result_data: Option<Value>,
+    /// Only propose actions; each one must be approved before it runs.
+    propose_only: bool,
+    /// Steps that may still run without approval in propose-only mode.
+    approved_steps: u32,
+    /// Result describing the proposed action waiting for approval.
+    proposal: Option<StepResult>,
+    /// Called when the executor asks a human to take over (login, captcha).
+    takeover_callback: Option<SharedTakeoverCallback>,
+    /// Asked before sensitive operations (payments, deletions).
+    confirmation_callback: Option<SharedConfirmationCallback>,
+    /// Overrides for the current task's type.
+    overrides: Option<ExecutorOverrides>,
 }
 
 impl ExecutorWrapper {
@@ -172,19 +332,151 @@ impl ExecutorWrapper {
             current_task_description: None,
             command_queue: VecDeque::new(),
             last_screen_hash: None,
+            last_image_hash: None,
+            screen_similarity: None,
+            similarity_threshold: DEFAULT_SCREEN_SIMILARITY_THRESHOLD,
             stuck_count: 0,
             stuck_threshold: DEFAULT_STUCK_THRESHOLD,
             pending_prompt: None,
             consecutive_parse_errors: 0,
+            step_timeout: Some(Duration::from_secs(DEFAULT_STEP_TIMEOUT_SECS)),
+            result_data: None,
+            propose_only: false,
+            approved_steps: 0,
+            proposal: None,
+            takeover_callback: None,
+            confirmation_callback: None,
+            overrides: None,
         }
     }
 
+    /// Set the callback invoked when the executor requests a human takeover.
+    pub fn with_takeover_callback(mut self, callback: SharedTakeoverCallback) -> Self {
+        self.set_takeover_callback(callback);
+        self
+    }
+
+    /// Set the callback invoked when the executor requests a human takeover.
+    pub fn set_takeover_callback(&mut self, callback: SharedTakeoverCallback) {
+        self.takeover_callback = Some(callback);
+        self.inner = self.build_agent();
+    }
+
+    /// Set the callback asked before sensitive operations. Without one the
+    /// executor asks on the console.
+    pub fn with_confirmation_callback(mut self, callback: SharedConfirmationCallback) -> Self {
+        self.set_confirmation_callback(callback);
+        self
+    }
+
+    /// Set the callback asked before sensitive operations.
+    pub fn set_confirmation_callback(&mut self, callback: SharedConfirmationCallback) {
+        self.confirmation_callback = Some(callback);
+        self.inner = self.build_agent();
+    }
+
+    /// Agent configuration of the current task (base config plus overrides).
+    fn effective_agent_config(&self) -> AgentConfig {
+        let mut config = self.agent_config.clone();
+        if let Some(overrides) = &self.overrides {
+            overrides.apply(&mut config);
+        }
+        config
+    }
+
+    /// Create the inner PhoneAgent from the current configuration.
+    fn build_agent(&self) -> PhoneAgent {
+        let confirmation = self.confirmation_callback.clone().map(|callback| {
+            Box::new(move |message: &str| callback(message)) as crate::actions::ConfirmationCallback
+        });
+        let takeover = self.takeover_callback.clone().map(|callback| {
+            Box::new(move |message: &str| callback(message)) as crate::actions::TakeoverCallback
+        });
+        PhoneAgent::new(
+            self.model_config.clone(),
+            self.effective_agent_config(),
+            confirmation,
+            takeover,
+        )
+    }
+
+    /// Enable propose-only mode: each step returns the action it would take
+    /// and waits for approval instead of executing it.
+    pub fn with_propose_only(mut self, enabled: bool) -> Self {
+        self.propose_only = enabled;
+        self
+    }
+
+    /// Whether propose-only mode is enabled.
+    pub fn is_propose_only(&self) -> bool {
+        self.propose_only
+    }
+
     /// Set the stuck detection threshold.
     pub fn with_stuck_threshold(mut self, threshold: u32) -> Self {
         self.stuck_threshold = threshold;
         self
     }
 
+    /// Set the screenshot similarity at or above which the screen counts as unchanged.
+    pub fn with_similarity_threshold(mut self, threshold: f64) -> Self {
+        self.similarity_threshold = threshold;
+        self
+    }
+
+    /// Set the per-step timeout (`None` disables the watchdog).
+    pub fn with_step_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.step_timeout = timeout;
+        self
+    }
+
+    /// Take over reloaded settings (scale, offsets, stuck detection and
+    /// language) without interrupting the current task. Returns the names
+    /// of the settings that changed.
+    pub fn apply_live_settings(&mut self, settings: &LiveSettings) -> Vec<String> {
+        let mut changed = Vec::new();
+        let config = &mut self.agent_config;
+        for (name, value, current) in [
+            ("scale_x", settings.scale_x, &mut config.scale_x),
+            ("scale_y", settings.scale_y, &mut config.scale_y),
+            ("offset_x", settings.offset_x, &mut config.offset_x),
+            ("offset_y", settings.offset_y, &mut config.offset_y),
+        ] {
+            if let Some(value) = value.filter(|value| value != current) {
+                *current = value;
+                changed.push(name.to_string());
+            }
+        }
+        if !changed.is_empty() {
+            self.inner.set_transform(
+                config.scale_x,
+                config.scale_y,
+                config.offset_x,
+                config.offset_y,
+            );
+        }
+        if let Some(threshold) = settings
+            .stuck_threshold
+            .filter(|threshold| *threshold != self.stuck_threshold)
+        {
+            self.stuck_threshold = threshold;
+            changed.push("stuck_threshold".to_string());
+        }
+        if let Some(threshold) = settings
+            .screen_similarity_threshold
+            .filter(|threshold| *threshold != self.similarity_threshold)
+        {
+            self.similarity_threshold = threshold;
+            changed.push("screen_similarity_threshold".to_string());
+        }
+        if let Some(lang) = settings.lang.as_ref().filter(|lang| **lang != config.lang) {
+            config.lang = lang.clone();
+            self.inner.set_lang(lang);
+            changed.push("lang".to_string());
+        }
+        changed
+    }
+
     /// Get current status.
     pub fn status(&self) -> &ExecutorStatus {
         &self.status
@@ -206,6 +498,11 @@ impl ExecutorWrapper {
         self.inner.step_count()
     }
 
+    /// Screenshot taken by the last step (`None` for sensitive screens).
+    pub fn last_screenshot(&self) -> Option<&Screenshot> {
+        self.inner.last_screenshot()
+    }
+
     /// Enqueue a command from Planner.
     pub fn enqueue(&mut self, cmd: ExecutorCommand) {
         self.command_queue.push_back(cmd);
@@ -234,8 +531,10 @@ impl ExecutorWrapper {
                 task_id,
                 description,
                 system_prompt,
+                overrides,
+                example,
             } => {
-                self.start_task(task_id, description, system_prompt);
+                self.start_task(task_id, description, system_prompt, overrides, example);
             }
             ExecutorCommand::Pause => {
                 if self.status == ExecutorStatus::Running {
@@ -244,7 +543,10 @@ impl ExecutorWrapper {
                 }
             }
             ExecutorCommand::Resume => {
-                if self.status == ExecutorStatus::Paused {
+                if matches!(
+                    self.status,
+                    ExecutorStatus::Paused | ExecutorStatus::TimedOut
+                ) {
                     self.status = ExecutorStatus::Running;
                     tracing::info!("Executor resumed");
                 }
@@ -287,29 +589,91 @@ impl ExecutorWrapper {
                 self.current_task_id = None;
                 self.current_task_description = None;
                 self.inner.reset();
+                self.proposal = None;
                 tracing::info!("Executor stopped");
             }
+            ExecutorCommand::ApproveProposal { steps } => {
+                if self.status == ExecutorStatus::AwaitingApproval {
+                    // The proposed action is the first approved step
+                    self.approved_steps = steps.saturating_sub(1);
+                    self.status = ExecutorStatus::Running;
+                    tracing::info!("Proposal approved ({} step(s))", steps.max(1));
+                }
+            }
+            ExecutorCommand::RejectProposal { reason } => {
+                if self.status == ExecutorStatus::AwaitingApproval {
+                    self.inner.reject_proposal();
+                    self.proposal = None;
+                    let reason = reason.unwrap_or_else(|| "请换一种操作".to_string());
+                    self.pending_prompt = Some(format!(
+                        "上一步提议的操作未被批准，没有执行。原因: {}",
+                        reason
+                    ));
+                    self.status = ExecutorStatus::Running;
+                    tracing::info!("Proposal rejected: {}", reason);
+                }
+            }
+            ExecutorCommand::SetProposeOnly { enabled } => {
+                self.propose_only = enabled;
+                self.approved_steps = 0;
+                if !enabled && self.status == ExecutorStatus::AwaitingApproval {
+                    // Full autonomy: run the waiting proposal as well
+                    self.status = ExecutorStatus::Running;
+                }
+                tracing::info!("Propose-only mode: {}", enabled);
+            }
         }
     }
 
     /// Start a new task.
-    fn start_task(&mut self, task_id: String, description: String, system_prompt: Option<String>) {
+    fn start_task(
+        &mut self,
+        task_id: String,
+        description: String,
+        system_prompt: Option<String>,
+        overrides: Option<ExecutorOverrides>,
+        example: Option<String>,
+    ) {
         // Reset state
         self.inner.reset();
         self.last_screen_hash = None;
+        self.last_image_hash = None;
+        self.screen_similarity = None;
         self.stuck_count = 0;
         self.pending_prompt = None;
+        self.result_data = None;
+        self.proposal = None;
+        self.approved_steps = 0;
+
+        // Overrides only apply to this task; a previous task's must be dropped
+        let overrides = overrides.filter(|o| !o.is_empty());
+        let mut recreate = overrides.is_some() || self.overrides.is_some();
+        self.overrides = overrides;
 
         // Update agent config with custom system prompt if provided
         if let Some(prompt) = system_prompt {
             self.agent_config.system_prompt = Some(prompt);
+            recreate = true;
+        }
+        // Examples are per task, unlike the sticky system prompt
+        if self.agent_config.example != example {
+            self.agent_config.example = example;
+            recreate = true;
+        }
+        if recreate {
+            // Keep a mid-session recalibration across the rebuild
+            if let Some(result) = self.inner.recalibration() {
+                self.agent_config.scale_x = result.scale_x;
+                self.agent_config.scale_y = result.scale_y;
+                self.agent_config.offset_x = result.offset_x;
+                self.agent_config.offset_y = result.offset_y;
+                self.agent_config.coordinate_mapping = result.mapping.clone();
+                self.agent_config
+                    .orientation_calibrations
+                    .insert(result.clone());
+            }
             // Recreate inner agent with new config
-            self.inner = PhoneAgent::new(
-                self.model_config.clone(),
-                self.agent_config.clone(),
-                None,
-                None,
-            );
+            self.inner = self.build_agent();
         }
 
         self.current_task_id = Some(task_id.clone());
@@ -319,10 +683,76 @@ impl ExecutorWrapper {
         tracing::info!("Executor started task: {}", task_id);
     }
 
+    /// Take a screenshot and ask the executor model for a textual summary
+    /// of the current screen, without performing any action.
+    /// This does not touch the executor's own conversation context.
+    ///
+    /// Returns the summary (empty if the model gave none) with the tokens the
+    /// request used.
+    pub async fn describe_screen(
+        &self,
+        question: Option<&str>,
+    ) -> Result<(String, TokenUsage), String> {
+        let device_id = self.agent_config.device_id.clone();
+        let (screenshot, current_app) = run_device(move || {
+            let device_id = device_id.as_deref();
+            (get_screenshot(device_id), get_current_app(device_id))
+        })
+        .await;
+        if screenshot.is_sensitive {
+            return Err("Screen is sensitive and cannot be captured".to_string());
+        }
+
+        let prompt = if self.agent_config.lang == "cn" {
+            format!(
+                "请观察这张手机截图，用文字描述当前屏幕状态，不要执行任何操作，不要输出 do(...) 或 finish(...)。\n\
+                请说明：当前所在应用和页面、主要可见元素（按钮、输入框、列表内容）、是否有弹窗或异常。\n\
+                当前应用: {}{}",
+                current_app,
+                question
+                    .map(|q| format!("\n需要特别回答的问题: {}", q))
+                    .unwrap_or_default()
+            )
+        } else {
+            format!(
+                "Look at this phone screenshot and describe the current screen in text. \
+                Do not perform any action and do not output do(...) or finish(...).\n\
+                Cover: current app and page, main visible elements (buttons, inputs, list content), \
+                and any dialogs or errors.\n\
+                Current app: {}{}",
+                current_app,
+                question
+                    .map(|q| format!("\nAlso answer this question: {}", q))
+                    .unwrap_or_default()
+            )
+        };
+
+        let messages = vec![MessageBuilder::create_user_message(
+            &prompt,
+            Some(&screenshot.base64_data),
+        )];
+
+        let client = ModelClient::new(self.model_config.clone());
+        let response = client.request(&messages).await.map_err(|e| e.to_string())?;
+
+        let summary = response
+            .raw_content
+            .replace("<think>", "")
+            .replace("</think>", "")
+            .replace("<answer>", "")
+            .replace("</answer>", "")
+            .trim()
+            .to_string();
+        Ok((summary, response.usage))
+    }
+
     /// Reset context without stopping.
     fn reset_context(&mut self) {
         self.inner.reset();
+        self.proposal = None;
         self.last_screen_hash = None;
+        self.last_image_hash = None;
+        self.screen_similarity = None;
         self.stuck_count = 0;
         self.pending_prompt = None;
         tracing::info!("Executor context reset");
@@ -337,70 +767,69 @@ impl ExecutorWrapper {
             return self.create_feedback(None, true, false);
         }
 
+        // An approved proposal runs without asking the model again
+        if self.inner.has_proposal() {
+            self.proposal = None;
+            let context_len = self.inner.context().len();
+            let step_count = self.inner.step_count();
+            let executed = match self.step_timeout {
+                Some(limit) => tokio::time::timeout(limit, self.inner.execute_proposal())
+                    .await
+                    .ok(),
+                None => Some(self.inner.execute_proposal().await),
+            };
+            return match executed {
+                Some(Some(step_result)) => self.handle_step_result(step_result),
+                Some(None) => self.create_feedback(None, true, false),
+                None => self.handle_step_timeout(context_len, step_count, None),
+            };
+        }
+
+        let max_steps = self.effective_agent_config().max_steps;
+        if self.inner.step_count() >= max_steps {
+            self.status = ExecutorStatus::Failed(format!("Max steps reached ({})", max_steps));
+            tracing::warn!("Executor reached max steps: {}", max_steps);
+            return self.create_feedback(None, true, false);
+        }
+
         let task = if self.inner.step_count() == 0 {
             self.current_task_description.clone()
-        } else if let Some(prompt) = self.pending_prompt.take() {
-            Some(prompt)
         } else {
-            None
+            self.pending_prompt.take()
         };
 
-        match self.inner.step(task.as_deref()).await {
-            Ok(step_result) => {
-                let is_parse_error = step_result
-                    .action
-                    .as_ref()
-                    .and_then(|a| a.get("error"))
-                    .and_then(|e| e.as_str())
-                    .map(|s| s == "parse_failed")
-                    .unwrap_or(false);
-
-                if is_parse_error {
-                    self.consecutive_parse_errors += 1;
-                    tracing::warn!(
-                        "Executor parse error (consecutive: {})",
-                        self.consecutive_parse_errors
-                    );
-
-                    if self.consecutive_parse_errors >= DEFAULT_PARSE_ERROR_THRESHOLD {
-                        tracing::error!(
-                            "Executor context overflow detected: {} consecutive parse errors",
-                            self.consecutive_parse_errors
-                        );
-                        self.reset_context_on_error();
-                    }
-                } else {
-                    self.consecutive_parse_errors = 0;
-                }
-
-                let screen_hash = self.calculate_context_hash();
-                let screen_changed = self.detect_screen_change(screen_hash);
-
-                if !screen_changed && !is_parse_error {
-                    self.stuck_count += 1;
-                    if self.stuck_count >= self.stuck_threshold {
-                        self.status = ExecutorStatus::Stuck;
-                        tracing::warn!(
-                            "Executor stuck: {} consecutive unchanged screens",
-                            self.stuck_count
-                        );
-                    }
-                } else if !is_parse_error {
-                    self.stuck_count = 0;
-                }
-
-                if step_result.finished {
-                    self.status = ExecutorStatus::Completed;
-                    tracing::info!("Executor completed task");
-                }
+        let propose = self.propose_only && self.approved_steps == 0;
+        if self.propose_only && !propose {
+            self.approved_steps -= 1;
+        }
 
-                let context_overflow =
-                    self.consecutive_parse_errors >= DEFAULT_PARSE_ERROR_THRESHOLD;
+        // Watchdog: a hung model call or device action must not freeze the dual loop
+        let context_len = self.inner.context().len();
+        let step_count = self.inner.step_count();
+        let outcome = match (self.step_timeout, propose) {
+            (Some(limit), true) => tokio::time::timeout(limit, self.inner.propose(task.as_deref()))
+                .await
+                .ok(),
+            (Some(limit), false) => tokio::time::timeout(limit, self.inner.step(task.as_deref()))
+                .await
+                .ok(),
+            (None, true) => Some(self.inner.propose(task.as_deref()).await),
+            (None, false) => Some(self.inner.step(task.as_deref()).await),
+        };
 
-                self.log_context_snapshot(Some(&step_result), context_overflow);
+        let Some(outcome) = outcome else {
+            return self.handle_step_timeout(context_len, step_count, task);
+        };
 
-                self.create_feedback(Some(&step_result), screen_changed, context_overflow)
+        match outcome {
+            Ok(step_result) if self.inner.has_proposal() => {
+                self.status = ExecutorStatus::AwaitingApproval;
+                tracing::info!("Executor proposed an action, awaiting approval");
+                self.log_context_snapshot(Some(&step_result), false);
+                self.proposal = Some(step_result.clone());
+                self.create_feedback(Some(&step_result), false, false)
             }
+            Ok(step_result) => self.handle_step_result(step_result),
             Err(e) => {
                 self.status = ExecutorStatus::Failed(e.to_string());
                 tracing::error!("Executor failed: {}", e);
@@ -409,6 +838,96 @@ impl ExecutorWrapper {
             }
         }
     }
+
+    /// Analyse the result of an executed step and build the feedback.
+    fn handle_step_result(&mut self, step_result: StepResult) -> ExecutorFeedback {
+        let is_parse_error = step_result
+            .action
+            .as_ref()
+            .and_then(|a| a.get("error"))
+            .and_then(|e| e.as_str())
+            .map(|s| s == "parse_failed")
+            .unwrap_or(false);
+
+        if is_parse_error {
+            self.consecutive_parse_errors += 1;
+            tracing::warn!(
+                "Executor parse error (consecutive: {})",
+                self.consecutive_parse_errors
+            );
+
+            if self.consecutive_parse_errors >= DEFAULT_PARSE_ERROR_THRESHOLD {
+                tracing::error!(
+                    "Executor context overflow detected: {} consecutive parse errors",
+                    self.consecutive_parse_errors
+                );
+                self.reset_context_on_error();
+            }
+        } else {
+            self.consecutive_parse_errors = 0;
+        }
+
+        let screen_hash = self.calculate_context_hash();
+        let context_changed = self.detect_screen_change(screen_hash);
+        let similarity = self.compare_screenshots(self.inner.screen_hash());
+        // Near-identical screenshots mean the screen did not change even
+        // though the conversation moved on
+        let screen_changed =
+            context_changed && similarity.is_none_or(|s| s < self.similarity_threshold);
+
+        if !screen_changed && !is_parse_error {
+            self.stuck_count += 1;
+            if self.stuck_count >= self.stuck_threshold {
+                self.status = ExecutorStatus::Stuck;
+                tracing::warn!(
+                    "Executor stuck: {} consecutive unchanged screens",
+                    self.stuck_count
+                );
+            }
+        } else if !is_parse_error {
+            self.stuck_count = 0;
+        }
+
+        if step_result.finished {
+            self.result_data = step_result
+                .action
+                .as_ref()
+                .and_then(|a| a.get("data"))
+                .filter(|d| d.is_object())
+                .cloned();
+            self.status = ExecutorStatus::Completed;
+            tracing::info!("Executor completed task");
+        }
+
+        let context_overflow = self.consecutive_parse_errors >= DEFAULT_PARSE_ERROR_THRESHOLD;
+
+        self.log_context_snapshot(Some(&step_result), context_overflow);
+
+        self.create_feedback(Some(&step_result), screen_changed, context_overflow)
+    }
+
+    /// Roll back an aborted step and report `TimedOut` to the Planner.
+    /// The step can be retried as-is by resuming the executor.
+    fn handle_step_timeout(
+        &mut self,
+        context_len: usize,
+        step_count: u32,
+        task: Option<String>,
+    ) -> ExecutorFeedback {
+        self.inner.rollback(context_len, step_count);
+        if step_count > 0 {
+            // Keep an injected prompt for the retried step
+            self.pending_prompt = task;
+        }
+        self.status = ExecutorStatus::TimedOut;
+        tracing::error!(
+            "Executor step timed out after {:?}",
+            self.step_timeout.unwrap_or_default()
+        );
+        self.log_context_snapshot(None, false);
+        self.create_feedback(None, false, false)
+    }
+
     /// Hash a small slice of the context (text only) to detect screen changes.
     fn calculate_context_hash(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
@@ -453,11 +972,10 @@ impl ExecutorWrapper {
             .iter()
             .rev()
             .take(4)
-            .map(|msg| Self::summarize_message(msg))
+            .map(Self::summarize_message)
             .collect();
 
-        let action_summary =
-            result.and_then(|r| r.action.as_ref().map(|a| Self::summarize_message(a)));
+        let action_summary = result.and_then(|r| r.action.as_ref().map(Self::summarize_message));
         let thinking = result.map(|r| Self::shorten(&r.thinking));
         let message = result.and_then(|r| r.message.clone());
 
@@ -498,10 +1016,10 @@ impl ExecutorWrapper {
                     .filter_map(|item| {
                         if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
                             Some(Self::shorten(text))
-                        } else if let Some(txt) = item.get("content").and_then(|t| t.as_str()) {
-                            Some(Self::shorten(txt))
                         } else {
-                            None
+                            item.get("content")
+                                .and_then(|t| t.as_str())
+                                .map(Self::shorten)
                         }
                     })
                     .collect();
@@ -538,6 +1056,16 @@ impl ExecutorWrapper {
         changed
     }
 
+    /// Compare the latest screenshot with the previous one.
+    fn compare_screenshots(&mut self, current: Option<u64>) -> Option<f64> {
+        self.screen_similarity = match (self.last_image_hash, current) {
+            (Some(last), Some(current)) => Some(hash_similarity(last, current)),
+            _ => None,
+        };
+        self.last_image_hash = current;
+        self.screen_similarity
+    }
+
     /// Create feedback for Planner.
     fn create_feedback(
         &self,
@@ -554,11 +1082,17 @@ impl ExecutorWrapper {
             task_id: self.current_task_id.clone(),
             step_count: self.inner.step_count(),
             status: self.status.clone(),
-            last_result: result.map(StepResultSummary::from),
+            last_result: result.map(|r| StepResultSummary {
+                app: self.inner.current_app().map(str::to_string),
+                ..StepResultSummary::from(r)
+            }),
             screen_changed,
             timestamp,
             context_overflow_detected: context_overflow,
             consecutive_parse_errors: self.consecutive_parse_errors,
+            screen_similarity: self.screen_similarity,
+            result_data: self.result_data.clone(),
+            proposed_action: self.proposal.as_ref().and_then(|p| p.action.clone()),
         }
     }
 
@@ -580,6 +1114,60 @@ pub fn current_timestamp() -> u64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_proposal_commands() {
+        let mut executor = ExecutorWrapper::new(ModelConfig::default(), AgentConfig::default())
+            .with_propose_only(true);
+        assert!(executor.is_propose_only());
+
+        // Commands are ignored unless a proposal is waiting
+        executor.enqueue(ExecutorCommand::ApproveProposal { steps: 3 });
+        executor.process_next_command();
+        assert_eq!(executor.status(), &ExecutorStatus::Idle);
+
+        executor.set_status(ExecutorStatus::AwaitingApproval);
+        executor.enqueue(ExecutorCommand::ApproveProposal { steps: 3 });
+        executor.process_next_command();
+        assert_eq!(executor.status(), &ExecutorStatus::Running);
+        assert_eq!(executor.approved_steps, 2);
+
+        executor.set_status(ExecutorStatus::AwaitingApproval);
+        executor.enqueue(ExecutorCommand::RejectProposal {
+            reason: Some("不要点广告".to_string()),
+        });
+        executor.process_next_command();
+        assert_eq!(executor.status(), &ExecutorStatus::Running);
+        assert!(executor
+            .pending_prompt
+            .as_ref()
+            .unwrap()
+            .contains("不要点广告"));
+
+        executor.set_status(ExecutorStatus::AwaitingApproval);
+        executor.enqueue(ExecutorCommand::SetProposeOnly { enabled: false });
+        executor.process_next_command();
+        assert!(!executor.is_propose_only());
+        assert_eq!(executor.status(), &ExecutorStatus::Running);
+        assert_eq!(executor.approved_steps, 0);
+    }
+
+    #[test]
+    fn test_screen_similarity() {
+        let mut executor = ExecutorWrapper::new(ModelConfig::default(), AgentConfig::default());
+
+        // Nothing to compare with yet
+        assert_eq!(executor.compare_screenshots(Some(0xFFFF)), None);
+        assert_eq!(executor.compare_screenshots(Some(0xFFFF)), Some(1.0));
+        // Two bits differ: 62/64 alike
+        let similarity = executor.compare_screenshots(Some(0xFFFC)).unwrap();
+        assert!((similarity - 62.0 / 64.0).abs() < 1e-9);
+        // Sensitive screens are not compared
+        assert_eq!(executor.compare_screenshots(None), None);
+
+        let feedback = executor.create_feedback(None, false, false);
+        assert_eq!(feedback.screen_similarity, None);
+    }
+
     #[test]
     fn test_executor_status_default() {
         let status = ExecutorStatus::default();
@@ -602,6 +1190,78 @@ mod tests {
         assert!(!executor.has_pending_commands());
     }
 
+    #[tokio::test]
+    async fn test_task_type_overrides() {
+        let mut executor = ExecutorWrapper::new(
+            ModelConfig::default(),
+            AgentConfig::default().with_max_steps(50),
+        );
+        executor.enqueue(ExecutorCommand::StartTask {
+            task_id: "task_1".to_string(),
+            description: "Navigate home".to_string(),
+            system_prompt: None,
+            example: None,
+            overrides: Some(ExecutorOverrides {
+                max_steps: Some(0),
+                coordinate_system: Some("relative".to_string()),
+                ..ExecutorOverrides::default()
+            }),
+        });
+        executor.process_next_command();
+        let config = executor.effective_agent_config();
+        assert_eq!(config.coordinate_system, CoordinateSystem::Relative);
+
+        // The overridden step limit is enforced without calling the model
+        let feedback = executor.tick().await;
+        assert!(matches!(feedback.status, ExecutorStatus::Failed(_)));
+
+        // Overrides do not leak into the next task
+        executor.enqueue(ExecutorCommand::StartTask {
+            task_id: "task_2".to_string(),
+            description: "Open app".to_string(),
+            system_prompt: None,
+            example: None,
+            overrides: None,
+        });
+        executor.process_next_command();
+        assert_eq!(executor.effective_agent_config().max_steps, 50);
+
+        let merged = ExecutorOverrides {
+            max_steps: Some(10),
+            scale_x: Some(1.5),
+            ..ExecutorOverrides::default()
+        }
+        .merged(&ExecutorOverrides {
+            max_steps: Some(20),
+            ..ExecutorOverrides::default()
+        });
+        assert_eq!(merged.max_steps, Some(20));
+        assert_eq!(merged.scale_x, Some(1.5));
+    }
+
+    #[test]
+    fn test_executor_step_timeout_resume() {
+        let mut executor = ExecutorWrapper::new(ModelConfig::default(), AgentConfig::default())
+            .with_step_timeout(Some(Duration::from_secs(1)));
+        executor.enqueue(ExecutorCommand::StartTask {
+            task_id: "test".to_string(),
+            description: "Test task".to_string(),
+            system_prompt: None,
+            example: None,
+            overrides: None,
+        });
+        executor.process_next_command();
+
+        let feedback = executor.handle_step_timeout(0, 0, None);
+        assert_eq!(feedback.status, ExecutorStatus::TimedOut);
+        assert_eq!(executor.step_count(), 0);
+
+        // The planner retries the aborted step by resuming
+        executor.enqueue(ExecutorCommand::Resume);
+        executor.process_next_command();
+        assert_eq!(*executor.status(), ExecutorStatus::Running);
+    }
+
     #[test]
     fn test_executor_pause_resume() {
         let model_config = ModelConfig::default();
@@ -613,6 +1273,8 @@ mod tests {
             task_id: "test".to_string(),
             description: "Test task".to_string(),
             system_prompt: None,
+            example: None,
+            overrides: None,
         });
         executor.process_next_command();
         assert_eq!(*executor.status(), ExecutorStatus::Running);
@@ -639,6 +1301,8 @@ mod tests {
             task_id: "test".to_string(),
             description: "Test task".to_string(),
             system_prompt: None,
+            example: None,
+            overrides: None,
         });
         executor.process_next_command();
 
@@ -665,6 +1329,8 @@ mod tests {
             task_id: "test".to_string(),
             description: "Test task".to_string(),
             system_prompt: None,
+            example: None,
+            overrides: None,
         });
         executor.process_next_command();
 
@@ -690,6 +1356,8 @@ mod tests {
             task_id: "test".to_string(),
             description: "Test task".to_string(),
             system_prompt: None,
+            example: None,
+            overrides: None,
         });
         executor.process_next_command();
 
@@ -722,6 +1390,8 @@ mod tests {
             message: Some("Tapping button".to_string()),
             success: true,
             finished: false,
+            usage: TokenUsage::default(),
+            touches: Vec::new(),
         };
 
         let summary = StepResultSummary::from(&result);
@@ -729,4 +1399,19 @@ mod tests {
         assert!(summary.success);
         assert!(!summary.finished);
     }
+
+    #[test]
+    fn test_format_action() {
+        let action = serde_json::json!({"_metadata": "do", "action": "Tap", "element": [500, 300]});
+        assert_eq!(
+            format_action(&action).as_deref(),
+            Some("do(action=\"Tap\", element=[500,300])")
+        );
+        let action = serde_json::json!({"_metadata": "finish", "message": "done"});
+        assert_eq!(
+            format_action(&action).as_deref(),
+            Some("finish(message=\"done\")")
+        );
+        assert!(format_action(&serde_json::json!({"type": "Tap"})).is_none());
+    }
 }
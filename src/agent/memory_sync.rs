@@ -0,0 +1,386 @@
+//! Remote prompt memory sync over HTTP.
+//!
+//! Machines driving different phones can share one evolving prompt memory
+//! through a plain HTTP endpoint: `GET <url>` returns the shared memory as
+//! JSON (404 while nothing was pushed yet) and `PUT <url>` replaces it. A
+//! sync pulls the remote memory, merges it (per task type, the entry with the
+//! newer `last_updated` wins) and pushes the result back if the remote was
+//! missing anything.
+//!
+//! The push is conditional: it carries the pulled `ETag` in `If-Match` (or
+//! `If-None-Match: *` when the remote was empty), so an endpoint that
+//! supports them answers 412 if another machine pushed in between, and the
+//! sync pulls and merges again. Endpoints without ETags are overwritten.
+//!
+//! The memory is sent as plain JSON, without encryption (`PROMPT_MEMORY_KEY`
+//! only covers the local file) or authentication. Use an `https://` URL and
+//! put the endpoint behind access control of its own.
+
+use std::time::Duration;
+
+use reqwest::header::{ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Client, StatusCode};
+
+use super::prompt_memory::PromptMemory;
+
+/// Timeout of a single sync request.
+const MEMORY_SYNC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pull-merge-push rounds before a sync gives up on a busy remote.
+const MEMORY_SYNC_ATTEMPTS: usize = 3;
+
+/// Default time between two syncs of a running planner, in seconds.
+pub const DEFAULT_MEMORY_SYNC_INTERVAL_SECS: u64 = 300;
+
+/// Outcome of a sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemorySyncReport {
+    /// Task types added or replaced from the remote memory.
+    pub pulled: usize,
+    /// Whether local changes were pushed.
+    pub pushed: bool,
+}
+
+/// Version of the remote memory a push expects to replace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RemoteVersion {
+    /// Nothing was pushed yet.
+    Missing,
+    /// The endpoint's `ETag`.
+    Tagged(String),
+    /// The endpoint sends no `ETag`; a push overwrites whatever is there.
+    Untagged,
+}
+
+/// HTTP client of a shared prompt memory.
+#[derive(Debug, Clone)]
+pub struct MemorySync {
+    url: String,
+    client: Client,
+}
+
+impl MemorySync {
+    /// Create a sync client for the given endpoint.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: Client::builder()
+                .timeout(MEMORY_SYNC_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The endpoint URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Fetch the remote memory (`None` if nothing was pushed yet).
+    pub async fn pull(&self) -> Result<Option<PromptMemory>, MemorySyncError> {
+        Ok(self.pull_versioned().await?.0)
+    }
+
+    /// Fetch the remote memory and its version.
+    async fn pull_versioned(
+        &self,
+    ) -> Result<(Option<PromptMemory>, RemoteVersion), MemorySyncError> {
+        let response = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .map_err(|e| MemorySyncError::RequestFailed(e.to_string()))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok((None, RemoteVersion::Missing));
+        }
+        if !response.status().is_success() {
+            return Err(MemorySyncError::BadStatus(response.status().as_u16()));
+        }
+        let version = match response.headers().get(ETAG).and_then(|v| v.to_str().ok()) {
+            Some(etag) => RemoteVersion::Tagged(etag.to_string()),
+            None => RemoteVersion::Untagged,
+        };
+        let body = response
+            .text()
+            .await
+            .map_err(|e| MemorySyncError::RequestFailed(e.to_string()))?;
+        if body.trim().is_empty() {
+            return Ok((None, version));
+        }
+        serde_json::from_str(&body)
+            .map(|memory| (Some(memory), version))
+            .map_err(|e| MemorySyncError::ParseError(e.to_string()))
+    }
+
+    /// Replace the remote memory.
+    pub async fn push(&self, memory: &PromptMemory) -> Result<(), MemorySyncError> {
+        self.push_if(memory, &RemoteVersion::Untagged).await
+    }
+
+    /// Replace the remote memory if it is still at `version`. Fails with
+    /// [`MemorySyncError::Conflict`] if it changed.
+    async fn push_if(
+        &self,
+        memory: &PromptMemory,
+        version: &RemoteVersion,
+    ) -> Result<(), MemorySyncError> {
+        let request = self.client.put(&self.url).json(memory);
+        let request = match version {
+            RemoteVersion::Missing => request.header(IF_NONE_MATCH, "*"),
+            RemoteVersion::Tagged(etag) => request.header(IF_MATCH, etag),
+            RemoteVersion::Untagged => request,
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| MemorySyncError::RequestFailed(e.to_string()))?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(MemorySyncError::Conflict);
+        }
+        if !response.status().is_success() {
+            return Err(MemorySyncError::BadStatus(response.status().as_u16()));
+        }
+        Ok(())
+    }
+
+    /// Pull, merge into `memory` and push back what the remote is missing.
+    /// If another machine pushed in between, the remote is pulled and merged
+    /// again before retrying.
+    pub async fn sync(
+        &self,
+        memory: &mut PromptMemory,
+    ) -> Result<MemorySyncReport, MemorySyncError> {
+        let mut pulled = 0;
+        for _ in 0..MEMORY_SYNC_ATTEMPTS {
+            let (remote, version) = self.pull_versioned().await?;
+            let remote = remote.unwrap_or_default();
+
+            // Anything the remote would take from us has to be pushed
+            let mut merged_remote = remote.clone();
+            let remote_behind = merged_remote.merge(memory) > 0
+                || memory
+                    .aliases
+                    .keys()
+                    .any(|alias| !remote.aliases.contains_key(alias));
+
+            pulled += memory.merge(&remote);
+            if !remote_behind {
+                return Ok(MemorySyncReport {
+                    pulled,
+                    pushed: false,
+                });
+            }
+            match self.push_if(memory, &version).await {
+                Ok(()) => {
+                    return Ok(MemorySyncReport {
+                        pulled,
+                        pushed: true,
+                    })
+                }
+                Err(MemorySyncError::Conflict) => {
+                    tracing::info!("Shared prompt memory changed during sync, merging again");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(MemorySyncError::Conflict)
+    }
+}
+
+/// Memory sync errors.
+#[derive(Debug, Clone)]
+pub enum MemorySyncError {
+    RequestFailed(String),
+    BadStatus(u16),
+    ParseError(String),
+    /// The remote kept changing while we tried to push.
+    Conflict,
+}
+
+impl std::fmt::Display for MemorySyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RequestFailed(e) => write!(f, "Request failed: {}", e),
+            Self::BadStatus(code) => write!(f, "Unexpected status code: {}", code),
+            Self::ParseError(e) => write!(f, "Parse error: {}", e),
+            Self::Conflict => write!(f, "Remote memory changed during sync"),
+        }
+    }
+}
+
+impl std::error::Error for MemorySyncError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Shared memory held by the test server.
+    #[derive(Default)]
+    struct Remote {
+        body: Option<String>,
+        /// Bumped by every push; sent as the ETag.
+        version: u32,
+        /// Pushed by "another machine" right after the next GET.
+        concurrent_push: Option<String>,
+    }
+
+    /// Minimal in-memory sync server: GET returns the stored body with an
+    /// ETag, PUT replaces it unless its `If-Match` / `If-None-Match` fails.
+    async fn serve(remote: Arc<Mutex<Remote>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/memory", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    break;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                // Read headers, then as much body as Content-Length announces
+                let (head, body) = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break (
+                                text[..end].to_string(),
+                                String::from_utf8_lossy(&request[end + 4..]).to_string(),
+                            );
+                        }
+                    }
+                };
+                let header = |name: &str| {
+                    head.lines().find_map(|l| {
+                        let (key, value) = l.split_once(':')?;
+                        key.eq_ignore_ascii_case(name)
+                            .then(|| value.trim().to_string())
+                    })
+                };
+
+                let response = {
+                    let mut remote = remote.lock().unwrap();
+                    let etag = format!("\"v{}\"", remote.version);
+                    if head.starts_with("PUT") {
+                        let changed = match (header("if-match"), header("if-none-match")) {
+                            (Some(expected), _) => remote.body.is_none() || expected != etag,
+                            (None, Some(_)) => remote.body.is_some(),
+                            (None, None) => false,
+                        };
+                        if changed {
+                            "HTTP/1.1 412 Precondition Failed\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+                                .to_string()
+                        } else {
+                            remote.body = Some(body);
+                            remote.version += 1;
+                            "HTTP/1.1 204 No Content\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+                                .to_string()
+                        }
+                    } else {
+                        let response = match remote.body.clone() {
+                            Some(body) => format!(
+                                "HTTP/1.1 200 OK\r\nConnection: close\r\nETag: {}\r\nContent-Length: {}\r\n\r\n{}",
+                                etag,
+                                body.len(),
+                                body
+                            ),
+                            None => "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: 0\r\n\r\n".to_string(),
+                        };
+                        if let Some(body) = remote.concurrent_push.take() {
+                            remote.body = Some(body);
+                            remote.version += 1;
+                        }
+                        response
+                    }
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_sync_merges_by_last_updated() {
+        let sync = MemorySync::new(serve(Arc::default()).await);
+
+        // First machine pushes its memory to the empty remote
+        let mut first = PromptMemory::new();
+        first.update("微信聊天", "old prompt");
+        first.get_mut("微信聊天").unwrap().last_updated = "2024-01-01T00:00:00+00:00".to_string();
+        let report = sync.sync(&mut first).await.unwrap();
+        assert_eq!(
+            report,
+            MemorySyncReport {
+                pulled: 0,
+                pushed: true
+            }
+        );
+
+        // Second machine has a newer prompt and another task type
+        let mut second = PromptMemory::new();
+        second.update("微信聊天", "new prompt");
+        second.update("地图导航", "map prompt");
+        let report = sync.sync(&mut second).await.unwrap();
+        assert_eq!(
+            report,
+            MemorySyncReport {
+                pulled: 0,
+                pushed: true
+            }
+        );
+
+        // First machine picks up both; nothing left to push
+        let report = sync.sync(&mut first).await.unwrap();
+        assert_eq!(
+            report,
+            MemorySyncReport {
+                pulled: 2,
+                pushed: false
+            }
+        );
+        assert_eq!(first.get_prompt("微信聊天"), Some("new prompt"));
+        assert_eq!(first.get_prompt("地图导航"), Some("map prompt"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_merges_again_after_concurrent_push() {
+        // Another machine pushes between our pull and our push
+        let mut other = PromptMemory::new();
+        other.update("地图导航", "map prompt");
+        let remote = Arc::new(Mutex::new(Remote {
+            concurrent_push: Some(serde_json::to_string(&other).unwrap()),
+            ..Remote::default()
+        }));
+        let sync = MemorySync::new(serve(remote.clone()).await);
+
+        let mut memory = PromptMemory::new();
+        memory.update("微信聊天", "chat prompt");
+        let report = sync.sync(&mut memory).await.unwrap();
+        assert_eq!(
+            report,
+            MemorySyncReport {
+                pulled: 1,
+                pushed: true
+            }
+        );
+        assert_eq!(memory.get_prompt("地图导航"), Some("map prompt"));
+
+        // Neither push was lost
+        let shared = sync.pull().await.unwrap().unwrap();
+        assert_eq!(shared.get_prompt("微信聊天"), Some("chat prompt"));
+        assert_eq!(shared.get_prompt("地图导航"), Some("map prompt"));
+        assert_eq!(remote.lock().unwrap().version, 2);
+    }
+}
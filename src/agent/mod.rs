@@ -11,27 +11,71 @@
 //! - `DualLoopRunner`: Coordinates both loops
 //! - `TodoList`: Task management
 //! - `PromptMemory`: Optimized prompt storage by task type
+//! - `PromptStore`: JSON or SQLite persistence for prompt memory
+//! - `MemorySync`: HTTP sync of prompt memory across machines
+//! - `EpisodicMemory`: Summaries of past sessions
+//! - `SpendLedger`: Token and cost accounting with a hard cap
+//! - `EventLog`: Per-session JSONL log of all dual-loop events
+//! - `EventStream`: WebSocket endpoint broadcasting dual-loop events
+//! - `Notifier`: Webhook/Telegram/Bark notifications for unattended sessions
 
 mod dual_loop;
+mod episodic_memory;
+mod event_log;
+mod event_stream;
+mod events;
 mod executor;
+mod memory_sync;
+mod notifier;
 mod phone_agent;
 mod planner;
 mod prompt_memory;
+mod prompt_store;
+mod session;
+mod spend;
 mod todo;
 
 // Single loop exports (original)
-pub use phone_agent::{AgentConfig, AgentError, PhoneAgent, StepResult};
+pub use phone_agent::{AgentConfig, AgentError, PhoneAgent, StepCallback, StepEvent, StepResult};
 
 // Dual loop exports (new)
 pub use dual_loop::{
-    DualLoopBuilder, DualLoopConfig, DualLoopError, DualLoopHandle, DualLoopRunner,
+    DualLoopBuilder, DualLoopConfig, DualLoopError, DualLoopHandle, DualLoopRunner, LiveSettings,
+    DEFAULT_APPROVAL_PATTERNS,
 };
+pub use episodic_memory::{Episode, EpisodeTask, EpisodicMemory, DEFAULT_MAX_EPISODES};
+pub use event_log::{
+    list_event_logs, read_event_log, EventLog, EventLogEntry, EventLogError, EVENT_LOG_EXTENSION,
+};
+pub use event_stream::{event_stream_bind_addr, EventStream, EVENT_STREAM_PATH};
+pub use events::{DualLoopEvent, EventBus, DEFAULT_EVENT_CAPACITY};
 pub use executor::{
-    ExecutorCommand, ExecutorFeedback, ExecutorStatus, ExecutorWrapper, StepResultSummary,
-    DEFAULT_STUCK_THRESHOLD,
+    ExecutorCommand, ExecutorFeedback, ExecutorOverrides, ExecutorStatus, ExecutorWrapper,
+    SharedConfirmationCallback, SharedTakeoverCallback, StepResultSummary,
+    DEFAULT_SCREEN_SIMILARITY_THRESHOLD, DEFAULT_STEP_TIMEOUT_SECS, DEFAULT_STUCK_THRESHOLD,
+};
+pub use memory_sync::{
+    MemorySync, MemorySyncError, MemorySyncReport, DEFAULT_MEMORY_SYNC_INTERVAL_SECS,
+};
+pub use notifier::{Notification, NotificationChannel, NotificationKind, Notifier, NotifierError};
+pub use planner::{
+    PlannerAction, PlannerAgent, PlannerConfig, PlannerProfile, BUILTIN_PLANNER_PROFILES,
 };
-pub use planner::{PlannerAction, PlannerAgent, PlannerConfig};
 pub use prompt_memory::{
-    create_default_prompt_memory, PromptEntry, PromptMemory, PromptMemoryError,
+    create_default_prompt_memory, normalize_task_type, save_correction_screenshot,
+    CorrectionRecord, DiffLine, PromptDiff, PromptEntry, PromptMemory, PromptMemoryError,
+    PromptMemoryLimits, PromptReport, PromptReportRow, PromptScope, PromptSummary, ScopedPrompt,
+    TaskTypeNormalizer, Trajectory, TrajectoryStep, CORRECTION_SCREENSHOT_QUALITY,
+    DEFAULT_MAX_CORRECTIONS_PER_TYPE, DEFAULT_MAX_TASK_TYPES, DEFAULT_MAX_TRAJECTORIES_PER_TYPE,
+    DEFAULT_SUCCESS_HALF_LIFE_DAYS, MAX_TRAJECTORY_STEPS, REOPTIMIZE_CONFIDENCE,
+};
+pub use prompt_store::{
+    open_prompt_store, EncryptionKey, JsonPromptStore, PromptStore, SqlitePromptStore,
+    PROMPT_MEMORY_KEY_ACCOUNT, PROMPT_MEMORY_KEY_ENV, SQLITE_EXTENSIONS,
+};
+pub use session::{SessionError, SessionState, SESSION_FORMAT_VERSION};
+pub use spend::{SpendGuard, SpendLedger, SpendLimit, TaskSpend};
+pub use todo::{
+    format_schedule_time, parse_schedule_time, CronSchedule, PlannedTask, Recurrence,
+    TaskTypeStats, TodoError, TodoItem, TodoList, TodoStats, TodoStatus,
 };
-pub use todo::{TodoItem, TodoList, TodoStats, TodoStatus};
@@ -0,0 +1,296 @@
+//! Notifications for unattended dual-loop sessions.
+//!
+//! A `Notifier` subscribes to the `EventBus` and posts a short message to
+//! every configured channel (generic webhook, Telegram bot, Bark) when all
+//! tasks finish (with or without failures) or the session needs a human.
+
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+
+use super::events::DualLoopEvent;
+
+/// Timeout of a single notification request.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where notifications are delivered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannel {
+    /// POST the notification as JSON to an arbitrary URL.
+    Webhook { url: String },
+    /// Send a message through a Telegram bot.
+    Telegram { bot_token: String, chat_id: String },
+    /// Push to the Bark iOS app (`https://api.day.app/<device key>`).
+    Bark { url: String },
+}
+
+/// Why a notification was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Every task finished successfully.
+    Completed,
+    /// All tasks finished, but some of them failed.
+    Failed,
+    /// The session is blocked until a human steps in.
+    TakeoverRequired,
+}
+
+/// A notification ready to be delivered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    /// Build the notification for an event, if the event warrants one.
+    pub fn from_event(event: &DualLoopEvent, lang: &str) -> Option<Self> {
+        let cn = lang == "cn";
+        let (kind, title, body) = match event {
+            DualLoopEvent::AllTasksFinished { stats } if stats.failed == 0 => (
+                NotificationKind::Completed,
+                if cn {
+                    "任务全部完成"
+                } else {
+                    "All tasks completed"
+                },
+                if cn {
+                    format!("共 {} 个任务，完成 {} 个", stats.total, stats.done)
+                } else {
+                    format!("{} of {} tasks done", stats.done, stats.total)
+                },
+            ),
+            DualLoopEvent::AllTasksFinished { stats } => (
+                NotificationKind::Failed,
+                if cn {
+                    "任务结束，有失败"
+                } else {
+                    "Tasks finished with failures"
+                },
+                if cn {
+                    format!(
+                        "共 {} 个任务，完成 {} 个，失败 {} 个",
+                        stats.total, stats.done, stats.failed
+                    )
+                } else {
+                    format!(
+                        "{} done, {} failed of {} tasks",
+                        stats.done, stats.failed, stats.total
+                    )
+                },
+            ),
+            DualLoopEvent::TakeoverRequired { message } => (
+                NotificationKind::TakeoverRequired,
+                if cn {
+                    "需要人工接管"
+                } else {
+                    "Takeover required"
+                },
+                message.clone(),
+            ),
+            DualLoopEvent::ApprovalRequired {
+                task_id,
+                description,
+            } => (
+                NotificationKind::TakeoverRequired,
+                if cn {
+                    "任务等待批准"
+                } else {
+                    "Task awaiting approval"
+                },
+                format!("{} - {}", task_id, description),
+            ),
+            DualLoopEvent::SpendLimitReached { report, .. } => (
+                NotificationKind::TakeoverRequired,
+                if cn {
+                    "已达到花费上限"
+                } else {
+                    "Spend limit reached"
+                },
+                report.clone(),
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            title: title.to_string(),
+            body,
+        })
+    }
+}
+
+/// Delivers notifications to the configured channels.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    channels: Vec<NotificationChannel>,
+    lang: String,
+    client: Client,
+}
+
+impl Notifier {
+    /// Create a notifier for the given channels.
+    pub fn new(channels: Vec<NotificationChannel>) -> Self {
+        Self {
+            channels,
+            lang: "cn".to_string(),
+            client: Client::builder()
+                .timeout(NOTIFY_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Set the language of notification texts ("cn" or "en").
+    pub fn with_lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = lang.into();
+        self
+    }
+
+    /// Whether no channel is configured.
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Deliver a notification to every channel. Failures are logged and do
+    /// not stop delivery to the remaining channels.
+    pub async fn send(&self, notification: &Notification) -> Result<(), NotifierError> {
+        let mut last_error = None;
+        for channel in &self.channels {
+            if let Err(e) = self.send_to(channel, notification).await {
+                tracing::warn!("Failed to send notification: {}", e);
+                last_error = Some(e);
+            }
+        }
+        last_error.map_or(Ok(()), Err)
+    }
+
+    async fn send_to(
+        &self,
+        channel: &NotificationChannel,
+        notification: &Notification,
+    ) -> Result<(), NotifierError> {
+        let request = match channel {
+            NotificationChannel::Webhook { url } => {
+                self.client.post(url).json(&serde_json::json!({
+                    "kind": notification.kind,
+                    "title": notification.title,
+                    "body": notification.body,
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                }))
+            }
+            NotificationChannel::Telegram { bot_token, chat_id } => self
+                .client
+                .post(format!(
+                    "https://api.telegram.org/bot{}/sendMessage",
+                    bot_token
+                ))
+                .json(&serde_json::json!({
+                    "chat_id": chat_id,
+                    "text": format!("{}\n{}", notification.title, notification.body),
+                })),
+            NotificationChannel::Bark { url } => self.client.post(url).json(&serde_json::json!({
+                "title": notification.title,
+                "body": notification.body,
+                "group": "phone-agent",
+            })),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| NotifierError::RequestFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(NotifierError::BadStatus(response.status().as_u16()));
+        }
+        Ok(())
+    }
+
+    /// Deliver notifications for events from the given subscription until
+    /// the dual loop stops.
+    pub fn spawn(self, mut rx: broadcast::Receiver<DualLoopEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(DualLoopEvent::Stopped { .. }) | Err(RecvError::Closed) => break,
+                    Ok(event) => {
+                        if let Some(notification) = Notification::from_event(&event, &self.lang) {
+                            let _ = self.send(&notification).await;
+                        }
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("Notifier lagged behind, {} events skipped", n);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Notifier errors.
+#[derive(Debug, Clone)]
+pub enum NotifierError {
+    RequestFailed(String),
+    BadStatus(u16),
+}
+
+impl std::fmt::Display for NotifierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RequestFailed(e) => write!(f, "Request failed: {}", e),
+            Self::BadStatus(code) => write!(f, "Unexpected status code: {}", code),
+        }
+    }
+}
+
+impl std::error::Error for NotifierError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::todo::TodoStats;
+
+    #[test]
+    fn test_notification_from_event() {
+        let stats = TodoStats {
+            total: 2,
+            done: 2,
+            ..TodoStats::default()
+        };
+        let n = Notification::from_event(&DualLoopEvent::AllTasksFinished { stats }, "en").unwrap();
+        assert_eq!(n.kind, NotificationKind::Completed);
+        assert_eq!(n.body, "2 of 2 tasks done");
+
+        let stats = TodoStats {
+            total: 2,
+            done: 1,
+            failed: 1,
+            ..TodoStats::default()
+        };
+        let n = Notification::from_event(&DualLoopEvent::AllTasksFinished { stats }, "cn").unwrap();
+        assert_eq!(n.kind, NotificationKind::Failed);
+
+        let n = Notification::from_event(
+            &DualLoopEvent::TakeoverRequired {
+                message: "请输入验证码".to_string(),
+            },
+            "cn",
+        )
+        .unwrap();
+        assert_eq!(n.kind, NotificationKind::TakeoverRequired);
+        assert_eq!(n.body, "请输入验证码");
+
+        assert!(Notification::from_event(
+            &DualLoopEvent::Report {
+                message: "progress".to_string()
+            },
+            "cn"
+        )
+        .is_none());
+    }
+}
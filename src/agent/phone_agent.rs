@@ -1,16 +1,28 @@
 //! Main PhoneAgent class for orchestrating phone automation.
 
 use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use thiserror::Error;
 
 use crate::actions::{
-    parse_action, ActionHandler, ConfirmationCallback, CoordinateSystem, TakeoverCallback,
+    parse_action, ActionHandler, ActionResult, ConfirmationCallback, CoordinateSystem,
+    TakeoverCallback,
+};
+use crate::adb::{
+    get_current_app, get_current_package, get_screenshot, hash_similarity, run_device, Screenshot,
+};
+use crate::calibration::{
+    save_device_profile, CalibrationConfig, CalibrationResult, CoordinateCalibrator,
+    CoordinateMapping, DriftMonitor, Orientation, OrientedCalibrations, TapOutcome,
+    INEFFECTIVE_TAP_SIMILARITY,
 };
-use crate::adb::{get_current_app, get_screenshot};
 use crate::config::{
-    get_messages, get_system_prompt, get_system_prompt_relative, get_system_prompt_with_resolution,
+    executor_prompt, format_message, get_messages, omit_actions, AppHints, AppRegion, PromptStyle,
+    PromptVars, DEFAULT_PROMPT_VARIANT,
 };
-use crate::model::{MessageBuilder, ModelClient, ModelConfig};
+use crate::model::{MessageBuilder, ModelClient, ModelConfig, ModelResponse, TokenUsage};
+use crate::settings::AppSettings;
 
 /// Agent errors.
 #[derive(Error, Debug)]
@@ -21,8 +33,12 @@ pub enum AgentError {
     ActionError(String),
     #[error("Task required for first step")]
     TaskRequired,
+    #[error("Task failed: {0}")]
+    TaskFailed(String),
     #[error("Max steps reached")]
     MaxStepsReached,
+    #[error("Task cancelled")]
+    Cancelled,
 }
 
 /// Configuration for the PhoneAgent.
@@ -44,8 +60,43 @@ pub struct AgentConfig {
     /// Scale factor for Y coordinates (LLM output * scale = actual coordinate).
     /// Only used when coordinate_system is Absolute.
     pub scale_y: f64,
+    /// Pixel offset added to X coordinates after scaling (Absolute only).
+    pub offset_x: f64,
+    /// Pixel offset added to Y coordinates after scaling (Absolute only).
+    pub offset_y: f64,
+    /// Nonlinear mapping from grid calibration, used instead of scale and
+    /// offset when set (Absolute only).
+    pub coordinate_mapping: Option<CoordinateMapping>,
+    /// Calibrations per screen orientation; the agent switches to the one
+    /// matching each screenshot and keeps the current transform when there
+    /// is none (Absolute only).
+    pub orientation_calibrations: OrientedCalibrations,
     /// Coordinate system mode (Relative 0-999 or Absolute pixel coordinates).
     pub coordinate_system: CoordinateSystem,
+    /// JPEG quality (1-100) screenshots are re-encoded with before being sent
+    /// to the model (`None` = send the captured PNG).
+    pub screenshot_quality: Option<u8>,
+    /// Successful run of a similar task appended to the system prompt as an
+    /// in-context example.
+    pub example: Option<String>,
+    /// Recalibrate mid-session when too many taps miss, instead of only
+    /// suggesting it (Absolute only).
+    pub auto_recalibrate: bool,
+    /// Hints appended to the system prompt while their app is in the foreground.
+    pub app_hints: AppHints,
+    /// Full or concise built-in system prompt (ignored with `system_prompt`).
+    pub prompt_style: PromptStyle,
+    /// Named version of the built-in prompts (see `config::prompt_variants`).
+    pub prompt_variant: String,
+    /// App catalogs `Launch` looks names up in.
+    pub app_region: AppRegion,
+    /// Actions the model may not use, e.g. "Type" and "Launch" for an agent
+    /// that only browses. They are left out of the system prompt and rejected
+    /// by the action handler.
+    pub disabled_actions: Vec<String>,
+    /// Perceive and decide as usual but only describe the actions instead of
+    /// performing them (see `ActionHandler::set_dry_run`).
+    pub dry_run: bool,
 }
 
 impl Default for AgentConfig {
@@ -59,7 +110,20 @@ impl Default for AgentConfig {
             verbose: true,
             scale_x: DEFAULT_COORDINATE_SCALE,
             scale_y: DEFAULT_COORDINATE_SCALE,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            coordinate_mapping: None,
+            orientation_calibrations: OrientedCalibrations::default(),
             coordinate_system: CoordinateSystem::Absolute,
+            screenshot_quality: None,
+            example: None,
+            auto_recalibrate: false,
+            app_hints: AppHints::default(),
+            prompt_style: PromptStyle::Full,
+            prompt_variant: DEFAULT_PROMPT_VARIANT.to_string(),
+            app_region: AppRegion::China,
+            disabled_actions: Vec::new(),
+            dry_run: false,
         }
     }
 }
@@ -76,7 +140,20 @@ impl AgentConfig {
             verbose: true,
             scale_x: 1.0,
             scale_y: 1.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            coordinate_mapping: None,
+            orientation_calibrations: OrientedCalibrations::default(),
             coordinate_system: CoordinateSystem::Relative,
+            screenshot_quality: None,
+            example: None,
+            auto_recalibrate: false,
+            app_hints: AppHints::default(),
+            prompt_style: PromptStyle::Full,
+            prompt_variant: DEFAULT_PROMPT_VARIANT.to_string(),
+            app_region: AppRegion::China,
+            disabled_actions: Vec::new(),
+            dry_run: false,
         }
     }
 
@@ -112,6 +189,34 @@ impl AgentConfig {
         self
     }
 
+    /// Set the pixel offsets added after scaling (only used for Absolute coordinate system),
+    /// e.g. to correct models that report coordinates shifted by the status bar.
+    pub fn with_offset(mut self, offset_x: f64, offset_y: f64) -> Self {
+        self.offset_x = offset_x;
+        self.offset_y = offset_y;
+        self
+    }
+
+    /// Use a nonlinear coordinate mapping from grid calibration (only used for
+    /// Absolute coordinate system). Takes precedence over scale and offset.
+    pub fn with_coordinate_mapping(mut self, mapping: Option<CoordinateMapping>) -> Self {
+        self.coordinate_mapping = mapping;
+        self
+    }
+
+    /// Use per-orientation calibrations, switched as the phone rotates (only
+    /// used for Absolute coordinate system).
+    pub fn with_orientation_calibrations(mut self, calibrations: OrientedCalibrations) -> Self {
+        self.orientation_calibrations = calibrations;
+        self
+    }
+
+    /// Recalibrate automatically when coordinate drift is detected.
+    pub fn with_auto_recalibrate(mut self, enabled: bool) -> Self {
+        self.auto_recalibrate = enabled;
+        self
+    }
+
     /// Set both X and Y scale factors to the same value.
     pub fn with_uniform_scale(mut self, scale: f64) -> Self {
         self.scale_x = scale;
@@ -125,6 +230,56 @@ impl AgentConfig {
         self
     }
 
+    /// Re-encode screenshots as JPEG with the given quality (1-100) to save tokens.
+    pub fn with_screenshot_quality(mut self, quality: u8) -> Self {
+        self.screenshot_quality = Some(quality.clamp(1, 100));
+        self
+    }
+
+    /// Append an in-context example to the system prompt.
+    pub fn with_example(mut self, example: impl Into<String>) -> Self {
+        self.example = Some(example.into());
+        self
+    }
+
+    /// Set the per-app hints appended to the system prompt.
+    pub fn with_app_hints(mut self, app_hints: AppHints) -> Self {
+        self.app_hints = app_hints;
+        self
+    }
+
+    /// Choose the full or concise built-in system prompt. Concise suits
+    /// small local models that the full prompt plus screenshots overwhelm.
+    pub fn with_prompt_style(mut self, prompt_style: PromptStyle) -> Self {
+        self.prompt_style = prompt_style;
+        self
+    }
+
+    /// Use a named version of the built-in prompts, e.g. "v2".
+    pub fn with_prompt_variant(mut self, variant: impl Into<String>) -> Self {
+        self.prompt_variant = variant.into();
+        self
+    }
+
+    /// Only describe actions instead of performing them, e.g. to try a
+    /// prompt or model on a new app without touching it.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Set the actions the model may not use.
+    pub fn with_disabled_actions(mut self, disabled_actions: Vec<String>) -> Self {
+        self.disabled_actions = disabled_actions;
+        self
+    }
+
+    /// Set the app catalogs `Launch` looks names up in.
+    pub fn with_app_region(mut self, app_region: AppRegion) -> Self {
+        self.app_region = app_region;
+        self
+    }
+
     /// Use relative coordinate system (0-999 range, original AutoGLM-Phone style).
     pub fn with_relative_coordinates(mut self) -> Self {
         self.coordinate_system = CoordinateSystem::Relative;
@@ -145,25 +300,56 @@ impl AgentConfig {
     /// Get the system prompt (custom or default based on language and coordinate system).
     /// This version doesn't include screen resolution information.
     pub fn get_system_prompt(&self) -> String {
-        self.system_prompt
-            .clone()
-            .unwrap_or_else(|| match self.coordinate_system {
-                CoordinateSystem::Relative => get_system_prompt_relative(&self.lang),
-                CoordinateSystem::Absolute => get_system_prompt(&self.lang),
-            })
+        self.build_system_prompt(PromptVars::new(&self.lang), (1080, 1920))
     }
 
     /// Get the system prompt with screen resolution information.
     /// This is the preferred method when screen dimensions are known.
     pub fn get_system_prompt_with_resolution(&self, width: u32, height: u32) -> String {
-        self.system_prompt
-            .clone()
-            .unwrap_or_else(|| match self.coordinate_system {
-                CoordinateSystem::Relative => get_system_prompt_relative(&self.lang),
-                CoordinateSystem::Absolute => {
-                    get_system_prompt_with_resolution(&self.lang, width, height)
-                }
-            })
+        self.build_system_prompt(
+            PromptVars::new(&self.lang).with_screen(width, height),
+            (width, height),
+        )
+    }
+
+    /// The custom system prompt filled in from `vars`, or the built-in one of
+    /// the selected variant and style for `screen`.
+    fn build_system_prompt(&self, vars: PromptVars, screen: (u32, u32)) -> String {
+        let prompt = match &self.system_prompt {
+            Some(prompt) => vars.render(prompt),
+            None => executor_prompt(
+                &self.prompt_variant,
+                &self.lang,
+                self.prompt_style,
+                (self.coordinate_system == CoordinateSystem::Absolute).then_some(screen),
+            ),
+        };
+        self.with_example_appended(omit_actions(&prompt, &self.disabled_actions))
+    }
+
+    /// Append the hint of the foreground app to a system prompt.
+    fn with_app_hint(&self, prompt: String, hint: Option<&str>) -> String {
+        match hint {
+            Some(hint) if self.lang == "cn" => format!("{}\n\n## 当前应用提示\n{}", prompt, hint),
+            Some(hint) => format!("{}\n\n## Current App Hint\n{}", prompt, hint),
+            None => prompt,
+        }
+    }
+
+    fn with_example_appended(&self, prompt: String) -> String {
+        match &self.example {
+            Some(example) if self.lang == "cn" => {
+                format!(
+                    "{}\n\n## 参考示例\n以下是一次成功完成类似任务的操作过程：\n{}",
+                    prompt, example
+                )
+            }
+            Some(example) => format!(
+                "{}\n\n## Example\nA successful run of a similar task:\n{}",
+                prompt, example
+            ),
+            None => prompt,
+        }
     }
 }
 
@@ -180,8 +366,45 @@ pub struct StepResult {
     pub thinking: String,
     /// Optional message (e.g., finish message).
     pub message: Option<String>,
+    /// Tokens consumed by the model call of this step.
+    pub usage: TokenUsage,
+    /// Device pixels the executed action touched (see [`ActionResult::touches`]).
+    pub touches: Vec<(i32, i32)>,
+}
+
+/// A finished step, passed to the step callback.
+#[derive(Debug, Clone)]
+pub struct StepEvent {
+    /// Step number, starting at 1.
+    pub step: u32,
+    /// Screenshot the model saw (`None` for sensitive screens).
+    pub screenshot: Option<Screenshot>,
+    /// The step itself.
+    pub result: StepResult,
 }
 
+/// Callback type for finished steps.
+pub type StepCallback = Box<dyn Fn(&StepEvent) + Send + Sync>;
+
+/// An action chosen by the model that has not been executed yet.
+struct PendingAction {
+    action: Value,
+    response: ModelResponse,
+    screen_width: u32,
+    screen_height: u32,
+}
+
+/// Outcome of the first half of a step: either a finished result (model or
+/// parse error) or an action that is ready to execute.
+enum PlannedStep {
+    Result(StepResult),
+    Action(PendingAction),
+}
+
+/// Handler callbacks, shared by every handler the agent builds.
+type SharedConfirmation = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+type SharedTakeover = Arc<dyn Fn(&str) + Send + Sync>;
+
 /// AI-powered agent for automating Android phone interactions.
 ///
 /// The agent uses a vision-language model to understand screen content
@@ -207,9 +430,39 @@ pub struct StepResult {
 pub struct PhoneAgent {
     model_client: ModelClient,
     agent_config: AgentConfig,
-    action_handler: ActionHandler,
+    /// Shared with the blocking thread that executes the current action.
+    action_handler: Arc<Mutex<ActionHandler>>,
+    /// Callbacks of the action handler, kept to rebuild it.
+    confirmation_callback: Option<SharedConfirmation>,
+    takeover_callback: Option<SharedTakeover>,
     context: Vec<Value>,
     step_count: u32,
+    /// Action proposed by `propose` and waiting to be executed or rejected.
+    pending: Option<PendingAction>,
+    /// Perceptual hash of the screenshot taken by the last step.
+    screen_hash: Option<u64>,
+    /// Screenshot taken by the last step (`None` for sensitive screens).
+    last_screenshot: Option<Screenshot>,
+    /// App in the foreground at the last step.
+    current_app: Option<String>,
+    /// Hint of the foreground app currently in the system prompt.
+    app_hint: Option<String>,
+    /// Recent tap outcomes, used to detect a stale calibration.
+    drift: DriftMonitor,
+    /// Screen hash before the last executed tap, checked on the next step.
+    tap_screen_hash: Option<u64>,
+    /// Last successful mid-session recalibration.
+    recalibration: Option<CalibrationResult>,
+    /// Orientation of the last screenshot, once one was taken.
+    orientation: Option<Orientation>,
+    /// Called after every executed step.
+    step_callback: Option<StepCallback>,
+    /// Set from outside to stop the run before its next action.
+    cancel: Arc<AtomicBool>,
+    /// Model request failure that ended the last step, if any.
+    model_error: Option<String>,
+    /// Why the last run finished without success, if it did.
+    failure: Option<AgentError>,
 }
 
 impl PhoneAgent {
@@ -226,31 +479,90 @@ impl PhoneAgent {
         confirmation_callback: Option<ConfirmationCallback>,
         takeover_callback: Option<TakeoverCallback>,
     ) -> Self {
-        let action_handler = ActionHandler::with_options(
-            agent_config.device_id.clone(),
-            confirmation_callback,
-            takeover_callback,
-            agent_config.scale_x,
-            agent_config.scale_y,
-            agent_config.coordinate_system,
-        );
+        let confirmation_callback = confirmation_callback.map(Arc::from);
+        let takeover_callback = takeover_callback.map(Arc::from);
+        let action_handler =
+            Self::build_handler(&agent_config, &confirmation_callback, &takeover_callback);
 
         Self {
             model_client: ModelClient::new(model_config),
             agent_config,
-            action_handler,
+            action_handler: Arc::new(Mutex::new(action_handler)),
+            confirmation_callback,
+            takeover_callback,
             context: Vec::new(),
             step_count: 0,
+            pending: None,
+            screen_hash: None,
+            last_screenshot: None,
+            current_app: None,
+            app_hint: None,
+            drift: DriftMonitor::default(),
+            tap_screen_hash: None,
+            recalibration: None,
+            orientation: None,
+            step_callback: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            model_error: None,
+            failure: None,
         }
     }
 
+    /// Action handler for `config` and its current transform.
+    fn build_handler(
+        config: &AgentConfig,
+        confirmation_callback: &Option<SharedConfirmation>,
+        takeover_callback: &Option<SharedTakeover>,
+    ) -> ActionHandler {
+        let confirmation = confirmation_callback.clone().map(|callback| {
+            Box::new(move |message: &str| callback(message)) as ConfirmationCallback
+        });
+        let takeover = takeover_callback
+            .clone()
+            .map(|callback| Box::new(move |message: &str| callback(message)) as TakeoverCallback);
+        let mut handler = ActionHandler::with_options(
+            config.device_id.clone(),
+            confirmation,
+            takeover,
+            config.scale_x,
+            config.scale_y,
+            config.coordinate_system,
+        );
+        handler.set_offset(config.offset_x, config.offset_y);
+        handler.set_mapping(config.coordinate_mapping.clone());
+        handler.set_app_region(config.app_region);
+        handler.set_disabled_actions(config.disabled_actions.clone());
+        handler.set_lang(&config.lang);
+        handler.set_dry_run(config.dry_run);
+        handler
+    }
+
+    /// Stop running once `cancel` is set: no further action is executed and
+    /// `run` / `step` return `AgentError::Cancelled`.
+    pub fn with_cancellation(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Whether the run was cancelled through the flag given to `with_cancellation`.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// Call `callback` after every executed step, e.g. to show progress live.
+    pub fn with_step_callback(mut self, callback: StepCallback) -> Self {
+        self.step_callback = Some(callback);
+        self
+    }
+
     /// Run the agent to complete a task.
     ///
     /// # Arguments
     /// * `task` - Natural language description of the task.
     ///
     /// # Returns
-    /// Final message from the agent.
+    /// Final message from the agent, also when the task finished without
+    /// success; [`take_failure`](Self::take_failure) tells why.
     pub async fn run(&mut self, task: &str) -> Result<String, AgentError> {
         self.reset();
 
@@ -258,9 +570,7 @@ impl PhoneAgent {
         let result = self.execute_step(Some(task), true).await?;
 
         if result.finished {
-            return Ok(result
-                .message
-                .unwrap_or_else(|| "Task completed".to_string()));
+            return Ok(self.finish_run(result));
         }
 
         // Continue until finished or max steps reached
@@ -268,15 +578,34 @@ impl PhoneAgent {
             let result = self.execute_step(None, false).await?;
 
             if result.finished {
-                return Ok(result
-                    .message
-                    .unwrap_or_else(|| "Task completed".to_string()));
+                return Ok(self.finish_run(result));
             }
         }
 
         Err(AgentError::MaxStepsReached)
     }
 
+    /// Final message of a run that ended with `result`. Records why it
+    /// failed, if it did: the model couldn't be reached, or an action ended
+    /// the task without success (e.g. a declined sensitive action).
+    fn finish_run(&mut self, result: StepResult) -> String {
+        let message = result
+            .message
+            .unwrap_or_else(|| "Task completed".to_string());
+        self.failure = match self.model_error.take() {
+            Some(error) => Some(AgentError::ModelError(error)),
+            None if !result.success => Some(AgentError::TaskFailed(message.clone())),
+            None => None,
+        };
+        message
+    }
+
+    /// Why the last `run` finished without success (`ModelError` or
+    /// `TaskFailed`), or `None` if it succeeded. Cleared by the call.
+    pub fn take_failure(&mut self) -> Option<AgentError> {
+        self.failure.take()
+    }
+
     /// Execute a single step of the agent.
     ///
     /// Useful for manual control or debugging.
@@ -296,10 +625,76 @@ impl PhoneAgent {
         self.execute_step(task, is_first).await
     }
 
+    /// Ask the model for the next action without executing it.
+    ///
+    /// If the model chose an action, the returned result describes it
+    /// (`success` is true, `finished` false) and `has_proposal` returns true
+    /// until it is executed with `execute_proposal` or discarded with
+    /// `reject_proposal`. Model and parse errors are returned as in `step`.
+    pub async fn propose(&mut self, task: Option<&str>) -> Result<StepResult, AgentError> {
+        let is_first = self.context.is_empty();
+
+        if is_first && task.is_none() {
+            return Err(AgentError::TaskRequired);
+        }
+
+        match self.plan_step(task, is_first).await? {
+            PlannedStep::Result(result) => Ok(result),
+            PlannedStep::Action(pending) => {
+                let result = StepResult {
+                    success: true,
+                    finished: false,
+                    action: Some(pending.action.clone()),
+                    thinking: pending.response.thinking.clone(),
+                    message: None,
+                    usage: pending.response.usage,
+                    touches: Vec::new(),
+                };
+                self.pending = Some(pending);
+                Ok(result)
+            }
+        }
+    }
+
+    /// Whether a proposed action is waiting to be executed or rejected.
+    pub fn has_proposal(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Execute the action returned by the last `propose` call.
+    pub async fn execute_proposal(&mut self) -> Option<StepResult> {
+        let pending = self.pending.take()?;
+        let result = self.apply_step(pending).await;
+        self.notify_step(&result);
+        Some(result)
+    }
+
+    /// Discard the proposed action. It is kept in the context as the
+    /// model's answer so the next step knows what was turned down.
+    pub fn reject_proposal(&mut self) -> bool {
+        let Some(pending) = self.pending.take() else {
+            return false;
+        };
+        self.context
+            .push(MessageBuilder::create_assistant_message(&format!(
+                "<think>{}</think><answer>{}</answer>",
+                pending.response.thinking, pending.response.action
+            )));
+        true
+    }
+
     /// Reset the agent state for a new task.
     pub fn reset(&mut self) {
         self.context.clear();
         self.step_count = 0;
+        self.pending = None;
+        self.screen_hash = None;
+        self.last_screenshot = None;
+        self.current_app = None;
+        self.app_hint = None;
+        self.tap_screen_hash = None;
+        self.model_error = None;
+        self.failure = None;
     }
 
     /// Execute a single step of the agent loop.
@@ -308,20 +703,98 @@ impl PhoneAgent {
         user_prompt: Option<&str>,
         is_first: bool,
     ) -> Result<StepResult, AgentError> {
+        if self.is_cancelled() {
+            return Err(AgentError::Cancelled);
+        }
+        let result = match self.plan_step(user_prompt, is_first).await? {
+            PlannedStep::Result(result) => result,
+            // Cancelled while waiting for the model: don't touch the phone
+            PlannedStep::Action(_) if self.is_cancelled() => return Err(AgentError::Cancelled),
+            PlannedStep::Action(pending) => self.apply_step(pending).await,
+        };
+        self.notify_step(&result);
+        Ok(result)
+    }
+
+    /// Pass a finished step to the step callback, if any.
+    fn notify_step(&self, result: &StepResult) {
+        if let Some(callback) = &self.step_callback {
+            callback(&StepEvent {
+                step: self.step_count,
+                screenshot: self.last_screenshot.clone(),
+                result: result.clone(),
+            });
+        }
+    }
+
+    /// First half of a step: capture the screen and ask the model for an action.
+    async fn plan_step(
+        &mut self,
+        user_prompt: Option<&str>,
+        is_first: bool,
+    ) -> Result<PlannedStep, AgentError> {
         self.step_count += 1;
 
-        // Capture current screen state
-        let screenshot = get_screenshot(self.agent_config.device_id.as_deref());
-        let current_app = get_current_app(self.agent_config.device_id.as_deref());
+        // Capture current screen state, plus the package when an app hint may apply
+        let device_id = self.agent_config.device_id.clone();
+        let with_package = !self.agent_config.app_hints.is_empty();
+        let (mut screenshot, current_app, package) = run_device(move || {
+            let device_id = device_id.as_deref();
+            (
+                get_screenshot(device_id),
+                get_current_app(device_id),
+                with_package
+                    .then(|| get_current_package(device_id))
+                    .flatten(),
+            )
+        })
+        .await;
+        self.screen_hash = screenshot.perceptual_hash();
+        if let (Some(before), Some(after)) = (self.tap_screen_hash.take(), self.screen_hash) {
+            self.drift.record(
+                if hash_similarity(before, after) >= INEFFECTIVE_TAP_SIMILARITY {
+                    TapOutcome::Ineffective
+                } else {
+                    TapOutcome::Effective
+                },
+            );
+        }
+        if !screenshot.is_sensitive {
+            self.follow_orientation(Orientation::from_size(screenshot.width, screenshot.height));
+        }
+        self.check_drift().await;
+        if let Some(quality) = self.agent_config.screenshot_quality {
+            if let Some(jpeg) = screenshot.to_jpeg(quality) {
+                screenshot.base64_data = jpeg;
+            }
+        }
+        self.last_screenshot = (!screenshot.is_sensitive).then(|| screenshot.clone());
+        let app_changed = self.current_app.as_deref() != Some(current_app.as_str());
+        self.current_app = Some(current_app.clone());
+
+        // Hint for the app in the foreground, if one is configured
+        let app_hint = package.and_then(|package| {
+            self.agent_config
+                .app_hints
+                .get(&package)
+                .map(str::to_string)
+        });
+        // Use system prompt with screen resolution for absolute coordinate system
+        let system_message = || {
+            let template = self.agent_config.with_app_hint(
+                self.agent_config
+                    .get_system_prompt_with_resolution(screenshot.width, screenshot.height),
+                app_hint.as_deref(),
+            );
+            let vars = PromptVars::new(&self.agent_config.lang)
+                .with_screen(screenshot.width, screenshot.height)
+                .with_current_app(&current_app);
+            MessageBuilder::create_system_message_from_template(&template, &vars)
+        };
 
         // Build messages
         if is_first {
-            // Use system prompt with screen resolution for absolute coordinate system
-            self.context.push(MessageBuilder::create_system_message(
-                &self
-                    .agent_config
-                    .get_system_prompt_with_resolution(screenshot.width, screenshot.height),
-            ));
+            self.context.push(system_message());
 
             let screen_info = MessageBuilder::build_screen_info(&current_app);
             let text_content = format!("{}\n\n{}", user_prompt.unwrap_or(""), screen_info);
@@ -331,6 +804,11 @@ impl PhoneAgent {
                 Some(&screenshot.base64_data),
             ));
         } else {
+            // Another app came to the foreground: swap the hint and
+            // `{{current_app}}`
+            if (app_changed || app_hint != self.app_hint) && !self.context.is_empty() {
+                self.context[0] = system_message();
+            }
             let screen_info = MessageBuilder::build_screen_info(&current_app);
             // Include injected prompt if provided
             let text_content = if let Some(prompt) = user_prompt {
@@ -347,6 +825,7 @@ impl PhoneAgent {
                 Some(&screenshot.base64_data),
             ));
         }
+        self.app_hint = app_hint;
 
         // Get model response
         let response = match self.model_client.request(&self.context).await {
@@ -355,13 +834,16 @@ impl PhoneAgent {
                 if self.agent_config.verbose {
                     eprintln!("Model error: {}", e);
                 }
-                return Ok(StepResult {
+                self.model_error = Some(e.to_string());
+                return Ok(PlannedStep::Result(StepResult {
                     success: false,
                     finished: true,
                     action: None,
                     thinking: String::new(),
                     message: Some(format!("Model error: {}", e)),
-                });
+                    usage: TokenUsage::default(),
+                    touches: Vec::new(),
+                }));
             }
         };
 
@@ -371,7 +853,7 @@ impl PhoneAgent {
             Err(e) => {
                 if self.agent_config.verbose {
                     eprintln!("Failed to parse action: {}", response.action);
-                    eprintln!("Parse error: {}", e);
+                    eprintln!("{}", e.localized(&self.agent_config.lang));
                 }
                 // Return a retry action instead of finishing
                 // This will prompt the model to continue/retry in the next step
@@ -388,7 +870,10 @@ impl PhoneAgent {
                     serde_json::json!({
                         "_metadata": "error",
                         "error": "parse_failed",
-                        "message": format!("无法解析动作指令，请重新输出完整的 do(...) 或 finish(...) 指令。原始输出: {}", truncated_action)
+                        "message": format_message(
+                            get_messages(&self.agent_config.lang).err_parse_retry,
+                            &[&truncated_action],
+                        )
                     }),
                     true,
                 )
@@ -431,19 +916,51 @@ impl PhoneAgent {
                 None,
             ));
 
-            return Ok(StepResult {
+            return Ok(PlannedStep::Result(StepResult {
                 success: false,
                 finished: false,
                 action: Some(action),
                 thinking: response.thinking,
                 message: Some("解析失败，等待模型重试".to_string()),
-            });
+                usage: response.usage,
+                touches: Vec::new(),
+            }));
         }
 
-        // Execute action
-        let result = self
-            .action_handler
-            .execute(&action, screenshot.width, screenshot.height);
+        Ok(PlannedStep::Action(PendingAction {
+            action,
+            response,
+            screen_width: screenshot.width,
+            screen_height: screenshot.height,
+        }))
+    }
+
+    /// Second half of a step: execute the action and record it in the context.
+    async fn apply_step(&mut self, pending: PendingAction) -> StepResult {
+        let PendingAction {
+            action,
+            response,
+            screen_width,
+            screen_height,
+        } = pending;
+
+        // Execute action off the runtime, so a step timeout can abandon it
+        let handler = self.free_handler();
+        let (action, result) = run_device(move || {
+            let result = handler
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .execute(&action, screen_width, screen_height);
+            (action, result)
+        })
+        .await;
+        if !self.agent_config.dry_run {
+            self.track_tap(&action, &result);
+        } else if self.agent_config.verbose {
+            if let Some(message) = &result.message {
+                println!("🧪 {}", message);
+            }
+        }
 
         // Add assistant response to context
         self.context
@@ -476,7 +993,7 @@ impl PhoneAgent {
             println!("{}\n", "=".repeat(50));
         }
 
-        Ok(StepResult {
+        StepResult {
             success: result.success,
             finished,
             action: Some(action.clone()),
@@ -487,7 +1004,216 @@ impl PhoneAgent {
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string())
             }),
-        })
+            usage: response.usage,
+            touches: result.touches,
+        }
+    }
+
+    /// The action handler, rebuilt if the blocking thread of an abandoned
+    /// action still holds it or panicked while holding it. Never waits, so
+    /// it is safe to call on the runtime.
+    fn free_handler(&mut self) -> Arc<Mutex<ActionHandler>> {
+        if self.action_handler.try_lock().is_err() {
+            tracing::warn!("Action handler still busy or poisoned, rebuilding it");
+            let handler = Self::build_handler(
+                &self.agent_config,
+                &self.confirmation_callback,
+                &self.takeover_callback,
+            );
+            self.action_handler = Arc::new(Mutex::new(handler));
+        }
+        self.action_handler.clone()
+    }
+
+    /// Lock the action handler (see [`PhoneAgent::free_handler`]).
+    fn handler(&mut self) -> MutexGuard<'_, ActionHandler> {
+        self.free_handler();
+        self.action_handler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record out-of-bounds taps right away; executed taps are judged on the
+    /// next step by whether the screen changed.
+    fn track_tap(&mut self, action: &Value, result: &ActionResult) {
+        if self.agent_config.coordinate_system != CoordinateSystem::Absolute {
+            return;
+        }
+        let name = action.get("action").and_then(|v| v.as_str()).unwrap_or("");
+        if !matches!(name, "Tap" | "Double Tap" | "Long Press") {
+            return;
+        }
+
+        if result.success {
+            self.tap_screen_hash = self.screen_hash;
+        } else if result.out_of_bounds {
+            self.drift.record(TapOutcome::OutOfBounds);
+        }
+    }
+
+    /// Switch to the calibration of the screen's orientation when it changes.
+    fn follow_orientation(&mut self, orientation: Orientation) {
+        if self.orientation == Some(orientation) {
+            return;
+        }
+        let rotated = self.orientation.is_some();
+        self.orientation = Some(orientation);
+
+        match self.agent_config.orientation_calibrations.get(orientation) {
+            Some(result) => {
+                // Kept in the config too, for a rebuilt handler
+                let result = result.clone();
+                self.agent_config.scale_x = result.scale_x;
+                self.agent_config.scale_y = result.scale_y;
+                self.agent_config.offset_x = result.offset_x;
+                self.agent_config.offset_y = result.offset_y;
+                self.agent_config.coordinate_mapping = result.mapping.clone();
+                self.handler().set_scale(result.scale_x, result.scale_y);
+                self.handler().set_offset(result.offset_x, result.offset_y);
+                self.handler().set_mapping(result.mapping.clone());
+                tracing::info!(
+                    "Using {} calibration: scale X={:.4}, Y={:.4}",
+                    orientation.as_str(),
+                    result.scale_x,
+                    result.scale_y
+                );
+                if rotated && self.agent_config.verbose {
+                    println!(
+                        "🔄 屏幕方向已切换为 {}，已应用对应的校准",
+                        orientation.as_str()
+                    );
+                }
+            }
+            None if rotated && !self.agent_config.orientation_calibrations.is_empty() => {
+                tracing::warn!(
+                    "No {} calibration, keeping the current one",
+                    orientation.as_str()
+                );
+            }
+            None => {}
+        }
+    }
+
+    /// Suggest or run a recalibration once too many recent taps missed.
+    async fn check_drift(&mut self) {
+        if !self.drift.is_drifting() {
+            return;
+        }
+        let miss_rate = self.drift.miss_rate();
+        self.drift.reset();
+        tracing::warn!(
+            "Coordinate drift suspected: {:.0}% of recent taps missed",
+            miss_rate * 100.0
+        );
+
+        if !self.agent_config.auto_recalibrate {
+            if self.agent_config.verbose {
+                println!(
+                    "⚠️ 最近 {:.0}% 的点击越界或未生效，坐标校准可能已失效，建议重新校准 (--calibrate)",
+                    miss_rate * 100.0
+                );
+            }
+            return;
+        }
+
+        if self.agent_config.verbose {
+            println!(
+                "🎯 最近 {:.0}% 的点击越界或未生效，正在重新校准...",
+                miss_rate * 100.0
+            );
+        }
+        self.recalibrate().await;
+    }
+
+    /// Switch to new scale factors and offsets, e.g. after the settings file
+    /// changed. Applies from the next action.
+    pub fn set_transform(&mut self, scale_x: f64, scale_y: f64, offset_x: f64, offset_y: f64) {
+        self.agent_config.scale_x = scale_x;
+        self.agent_config.scale_y = scale_y;
+        self.agent_config.offset_x = offset_x;
+        self.agent_config.offset_y = offset_y;
+        self.handler().set_scale(scale_x, scale_y);
+        self.handler().set_offset(offset_x, offset_y);
+    }
+
+    /// Switch the language of prompts and action messages from the next step.
+    pub fn set_lang(&mut self, lang: &str) {
+        self.agent_config.lang = lang.to_string();
+        self.handler().set_lang(lang);
+    }
+
+    /// Run a quick calibration with the agent's model and switch to the new
+    /// transform if it succeeds. The result is saved as the device profile.
+    ///
+    /// # Returns
+    /// True if the active scale factors were updated.
+    pub async fn recalibrate(&mut self) -> bool {
+        let device_id = self.agent_config.device_id.clone();
+        let result = CoordinateCalibrator::new(self.recalibration_config())
+            .calibrate(&self.model_client)
+            .await;
+        if !result.success {
+            tracing::warn!("Recalibration failed: {:?}", result.error);
+            return false;
+        }
+
+        self.agent_config.scale_x = result.scale_x;
+        self.agent_config.scale_y = result.scale_y;
+        self.agent_config.offset_x = result.offset_x;
+        self.agent_config.offset_y = result.offset_y;
+        self.agent_config.coordinate_mapping = result.mapping.clone();
+        self.handler().set_scale(result.scale_x, result.scale_y);
+        self.handler().set_offset(result.offset_x, result.offset_y);
+        self.handler().set_mapping(result.mapping.clone());
+        self.orientation = Some(result.orientation());
+        self.agent_config
+            .orientation_calibrations
+            .insert(result.clone());
+        tracing::info!(
+            "Recalibrated: scale X={:.4}, Y={:.4}, offset X={:.1}, Y={:.1}",
+            result.scale_x,
+            result.scale_y,
+            result.offset_x,
+            result.offset_y
+        );
+
+        if let Some(path) = AppSettings::calibration_profiles_path() {
+            if let Err(e) = save_device_profile(path, device_id.as_deref(), &result) {
+                tracing::warn!("Failed to save calibration profile: {}", e);
+            }
+        }
+        self.recalibration = Some(result);
+        true
+    }
+
+    /// Calibration settings for a mid-session recalibration. Progress goes to
+    /// stderr: stdout may carry a protocol (`phone-agent mcp`).
+    pub(crate) fn recalibration_config(&self) -> CalibrationConfig {
+        let config = CalibrationConfig::default()
+            .with_lang(&self.agent_config.lang)
+            .with_progress_to_stderr(true);
+        match &self.agent_config.device_id {
+            Some(id) => config.with_device_id(id),
+            None => config,
+        }
+    }
+
+    /// Result of the last successful mid-session recalibration, if any.
+    pub fn recalibration(&self) -> Option<&CalibrationResult> {
+        self.recalibration.as_ref()
+    }
+
+    /// Recent tap outcomes used for drift detection.
+    pub fn drift_monitor(&self) -> &DriftMonitor {
+        &self.drift
+    }
+
+    /// Discard context messages and steps recorded after the given point.
+    /// Used when an in-flight step was aborted before it finished.
+    pub fn rollback(&mut self, context_len: usize, step_count: u32) {
+        self.context.truncate(context_len);
+        self.step_count = step_count;
+        self.pending = None;
     }
 
     /// Get the current conversation context.
@@ -499,12 +1225,72 @@ impl PhoneAgent {
     pub fn step_count(&self) -> u32 {
         self.step_count
     }
+
+    /// Perceptual hash of the screenshot taken by the last step
+    /// (`None` for sensitive screens).
+    pub fn screen_hash(&self) -> Option<u64> {
+        self.screen_hash
+    }
+
+    /// Screenshot taken by the last step (`None` for sensitive screens).
+    pub fn last_screenshot(&self) -> Option<&Screenshot> {
+        self.last_screenshot.as_ref()
+    }
+
+    /// App in the foreground at the last step.
+    pub fn current_app(&self) -> Option<&str> {
+        self.current_app.as_deref()
+    }
+
+    /// Configuration the agent runs with.
+    pub fn config(&self) -> &AgentConfig {
+        &self.agent_config
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_busy_handler_is_rebuilt() {
+        let config = AgentConfig::default().with_dry_run(true);
+        let mut agent = PhoneAgent::new(ModelConfig::default(), config, None, None);
+
+        // The blocking thread of an abandoned step still holds the handler
+        let abandoned = agent.action_handler.clone();
+        let (held_tx, held) = std::sync::mpsc::channel();
+        let (release, release_rx) = std::sync::mpsc::channel::<()>();
+        let step = std::thread::spawn(move || {
+            let _handler = abandoned.lock().unwrap();
+            held_tx.send(()).unwrap();
+            let _ = release_rx.recv();
+        });
+        held.recv().unwrap();
+
+        // Doesn't wait for it, and the new handler keeps the configuration
+        agent.set_transform(2.0, 3.0, 4.0, 5.0);
+        {
+            let handler = agent.handler();
+            assert_eq!(handler.get_scale(), (2.0, 3.0));
+            assert_eq!(handler.get_offset(), (4.0, 5.0));
+            assert!(handler.is_dry_run());
+        }
+        drop(release);
+        step.join().unwrap();
+
+        // A step that panicked poisoned the handler
+        let poisoned = agent.action_handler.clone();
+        let _ = std::thread::spawn(move || {
+            let _handler = poisoned.lock().unwrap();
+            panic!("action panicked");
+        })
+        .join();
+        agent.set_lang("en");
+        assert_eq!(agent.handler().get_scale(), (2.0, 3.0));
+        assert!(!agent.action_handler.is_poisoned());
+    }
+
     #[test]
     fn test_agent_config_default() {
         let config = AgentConfig::default();
@@ -526,4 +1312,47 @@ mod tests {
         assert_eq!(config.max_steps, 50);
         assert!(!config.verbose);
     }
+
+    #[test]
+    fn test_concise_prompt_style() {
+        let full = AgentConfig::default().get_system_prompt_with_resolution(1080, 2400);
+        let concise = AgentConfig::default()
+            .with_prompt_style(PromptStyle::Concise)
+            .get_system_prompt_with_resolution(1080, 2400);
+        assert!(concise.contains("1080x2400"));
+        assert!(concise.len() < full.len());
+    }
+
+    #[test]
+    fn test_system_prompt_template() {
+        let config = AgentConfig {
+            system_prompt: Some(
+                "Screen {{screen_width}}x{{screen_height}}, app {{current_app}}".into(),
+            ),
+            ..AgentConfig::default()
+        };
+        let prompt = config.get_system_prompt_with_resolution(1080, 2400);
+        // `current_app` is filled in per step
+        assert_eq!(prompt, "Screen 1080x2400, app {{current_app}}");
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_agent_stops_before_acting() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut agent = PhoneAgent::new(
+            ModelConfig::default(),
+            AgentConfig::default().quiet(),
+            None,
+            None,
+        )
+        .with_cancellation(cancel.clone());
+        assert!(!agent.is_cancelled());
+
+        cancel.store(true, Ordering::SeqCst);
+        assert!(matches!(
+            agent.run("打开微信").await,
+            Err(AgentError::Cancelled)
+        ));
+        assert_eq!(agent.step_count(), 0);
+    }
 }
@@ -4,19 +4,41 @@
 //! - Task planning and todo list management
 //! - Supervising the Executor
 //! - Detecting stuck situations and intervening
-//! - Managing prompt memory
+//! - Managing prompt memory and episodic memory of past sessions
 //! - Handling user input
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-
-use super::executor::{ExecutorCommand, ExecutorFeedback, ExecutorStatus, ExecutorWrapper};
-use super::prompt_memory::PromptMemory;
-use super::todo::{TodoList, TodoStatus};
+use tokio::sync::watch;
+
+use super::dual_loop::LiveSettings;
+use super::episodic_memory::{Episode, EpisodeTask, EpisodicMemory};
+use super::events::{DualLoopEvent, EventBus};
+use super::executor::{
+    ExecutorCommand, ExecutorFeedback, ExecutorOverrides, ExecutorStatus, ExecutorWrapper,
+    SharedConfirmationCallback, SharedTakeoverCallback, DEFAULT_SCREEN_SIMILARITY_THRESHOLD,
+    DEFAULT_STEP_TIMEOUT_SECS,
+};
+use super::memory_sync::{MemorySync, MemorySyncError, DEFAULT_MEMORY_SYNC_INTERVAL_SECS};
+use super::prompt_memory::{
+    read_correction_screenshot, remove_correction_screenshots, save_correction_screenshot,
+    CorrectionRecord, PromptMemory, PromptMemoryLimits, PromptScope, Trajectory, TrajectoryStep,
+    CORRECTION_SCREENSHOT_QUALITY, DEFAULT_SUCCESS_HALF_LIFE_DAYS,
+};
+use super::session::{SessionState, SESSION_FORMAT_VERSION};
+use super::spend::SpendLedger;
+use super::todo::{
+    format_schedule_time, parse_schedule_time, Recurrence, TodoError, TodoItem, TodoList,
+    TodoStatus,
+};
+use crate::actions::default_takeover;
 use crate::agent::AgentConfig;
-use crate::model::{MessageBuilder, ModelClient, ModelConfig};
+use crate::config::{prompt_text, PromptVars};
+use crate::model::{MessageBuilder, ModelClient, ModelConfig, TokenUsage};
 
 /// Configuration for the Planner agent.
 #[derive(Debug, Clone)]
@@ -27,7 +49,7 @@ pub struct PlannerConfig {
     pub max_executor_feedback_history: usize,
     /// Stuck detection threshold (consecutive unchanged screens).
     pub stuck_threshold: u32,
-    /// Path to prompt memory JSON file.
+    /// Path to prompt memory JSON file (`None` = not persisted).
     pub prompt_memory_path: Option<String>,
     /// Maximum retries for stuck situations before giving up.
     pub max_stuck_retries: u32,
@@ -37,6 +59,47 @@ pub struct PlannerConfig {
     pub system_prompt: Option<String>,
     /// Language for prompts ("cn" or "en").
     pub lang: String,
+    /// Whether to decompose tasks that failed after all retries into smaller steps.
+    pub auto_replan_failed: bool,
+    /// Maximum number of steps a failed task may be decomposed into.
+    pub max_replan_steps: usize,
+    /// Executor step timeout in seconds (0 disables the watchdog).
+    pub step_timeout_secs: u64,
+    /// Consecutive step timeouts retried before the task is treated as failed.
+    pub max_timeout_retries: u32,
+    /// Path to episodic memory JSON file (summaries of past sessions).
+    pub episodic_memory_path: Option<String>,
+    /// Maximum number of past sessions surfaced to the Planner per request.
+    pub max_relevant_episodes: usize,
+    /// Name of the applied planner profile (if any).
+    pub profile: Option<String>,
+    /// Supervision style instructions appended to the system prompt.
+    pub style_prompt: Option<String>,
+    /// Executor only proposes actions; each one must be approved first.
+    pub propose_only: bool,
+    /// Proposals are approved by the user instead of the Planner.
+    pub user_reviews_proposals: bool,
+    /// Screenshot similarity at or above which the screen counts as unchanged.
+    pub screen_similarity_threshold: f64,
+    /// Executor overrides by task type (take precedence over prompt memory).
+    pub executor_overrides: HashMap<String, ExecutorOverrides>,
+    /// Size caps applied to prompt memory before it is saved.
+    pub prompt_memory_limits: PromptMemoryLimits,
+    /// Device model / app version used to pick and store scoped prompts.
+    pub prompt_scope: PromptScope,
+    /// Half-life of a prompt's success rate (`None` = no decay). Prompts
+    /// whose decayed rate gets too low are re-optimized after their next run.
+    pub prompt_success_half_life: Option<Duration>,
+    /// Endpoint of a shared prompt memory (`None` = no remote sync).
+    pub memory_sync_url: Option<String>,
+    /// Minimum time between two remote syncs, in seconds.
+    pub memory_sync_interval_secs: u64,
+    /// Directory the screen is saved to when the user corrects the executor
+    /// (`None` = corrections are recorded without screenshots).
+    pub correction_screenshot_dir: Option<String>,
+    /// File the todo list is saved to after every change and restored from
+    /// on startup (`None` = not persisted).
+    pub todo_list_path: Option<String>,
 }
 
 impl Default for PlannerConfig {
@@ -45,11 +108,32 @@ impl Default for PlannerConfig {
             model_config: ModelConfig::default(),
             max_executor_feedback_history: 2,
             stuck_threshold: 3,
-            prompt_memory_path: Some("prompt_memory.json".to_string()),
+            prompt_memory_path: None,
             max_stuck_retries: 3,
             auto_optimize_prompts: true,
             system_prompt: None,
             lang: "cn".to_string(),
+            auto_replan_failed: true,
+            max_replan_steps: 5,
+            step_timeout_secs: DEFAULT_STEP_TIMEOUT_SECS,
+            max_timeout_retries: 2,
+            episodic_memory_path: Some("episodic_memory.json".to_string()),
+            max_relevant_episodes: 3,
+            profile: None,
+            style_prompt: None,
+            propose_only: false,
+            user_reviews_proposals: false,
+            screen_similarity_threshold: DEFAULT_SCREEN_SIMILARITY_THRESHOLD,
+            executor_overrides: HashMap::new(),
+            prompt_memory_limits: PromptMemoryLimits::default(),
+            prompt_scope: PromptScope::default(),
+            prompt_success_half_life: Some(Duration::from_secs(
+                DEFAULT_SUCCESS_HALF_LIFE_DAYS * 24 * 60 * 60,
+            )),
+            memory_sync_url: None,
+            memory_sync_interval_secs: DEFAULT_MEMORY_SYNC_INTERVAL_SECS,
+            correction_screenshot_dir: None,
+            todo_list_path: None,
         }
     }
 }
@@ -79,6 +163,78 @@ impl PlannerConfig {
         self
     }
 
+    /// Set the screenshot similarity at or above which the screen counts as unchanged.
+    pub fn with_screen_similarity_threshold(mut self, threshold: f64) -> Self {
+        self.screen_similarity_threshold = threshold;
+        self
+    }
+
+    /// Override executor settings for a task type.
+    pub fn with_executor_overrides(
+        mut self,
+        task_type: impl Into<String>,
+        overrides: ExecutorOverrides,
+    ) -> Self {
+        self.executor_overrides.insert(task_type.into(), overrides);
+        self
+    }
+
+    /// Set the device model / app version prompts are scoped to.
+    pub fn with_prompt_scope(mut self, scope: PromptScope) -> Self {
+        self.prompt_scope = scope;
+        self
+    }
+
+    /// Set the half-life of prompt success rates in days (0 disables decay).
+    pub fn with_prompt_success_half_life_days(mut self, days: u64) -> Self {
+        self.prompt_success_half_life =
+            Some(Duration::from_secs(days * 24 * 60 * 60)).filter(|h| !h.is_zero());
+        self
+    }
+
+    /// Share prompt memory with other machines through an HTTP endpoint.
+    pub fn with_memory_sync_url(mut self, url: impl Into<String>) -> Self {
+        self.memory_sync_url = Some(url.into());
+        self
+    }
+
+    /// Set the minimum time between two remote syncs, in seconds.
+    pub fn with_memory_sync_interval_secs(mut self, secs: u64) -> Self {
+        self.memory_sync_interval_secs = secs;
+        self
+    }
+
+    /// Save the screen alongside each user correction into `dir`.
+    pub fn with_correction_screenshot_dir(mut self, dir: impl Into<String>) -> Self {
+        self.correction_screenshot_dir = Some(dir.into());
+        self
+    }
+
+    /// Persist the todo list to `path` and resume unfinished tasks from it
+    /// on startup.
+    pub fn with_todo_list_path(mut self, path: impl Into<String>) -> Self {
+        self.todo_list_path = Some(path.into());
+        self
+    }
+
+    /// Set the size caps of prompt memory.
+    pub fn with_prompt_memory_limits(mut self, limits: PromptMemoryLimits) -> Self {
+        self.prompt_memory_limits = limits;
+        self
+    }
+
+    /// Set the executor step timeout in seconds (0 disables the watchdog).
+    pub fn with_step_timeout_secs(mut self, secs: u64) -> Self {
+        self.step_timeout_secs = secs;
+        self
+    }
+
+    /// Set the episodic memory path.
+    pub fn with_episodic_memory_path(mut self, path: impl Into<String>) -> Self {
+        self.episodic_memory_path = Some(path.into());
+        self
+    }
+
     /// Set the system prompt.
     pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
         self.system_prompt = Some(prompt.into());
@@ -91,15 +247,187 @@ impl PlannerConfig {
         self
     }
 
+    /// Enable propose-only mode. Proposals are reviewed by the Planner, or by
+    /// the user when `user_reviews` is set.
+    pub fn with_propose_only(mut self, enabled: bool, user_reviews: bool) -> Self {
+        self.propose_only = enabled;
+        self.user_reviews_proposals = user_reviews;
+        self
+    }
+
+    /// Enable or disable automatic re-planning of failed tasks.
+    pub fn with_auto_replan_failed(mut self, enabled: bool) -> Self {
+        self.auto_replan_failed = enabled;
+        self
+    }
+
+    /// Apply a planner profile. Only the fields the profile sets are changed.
+    pub fn with_profile(mut self, profile: &PlannerProfile) -> Self {
+        if let Some(prompt) = &profile.system_prompt {
+            self.system_prompt = Some(prompt.clone());
+        }
+        if let Some(model_name) = &profile.model_name {
+            self.model_config.model_name = model_name.clone();
+        }
+        if let Some(max) = profile.max_executor_feedback_history {
+            self.max_executor_feedback_history = max;
+        }
+        if let Some(threshold) = profile.stuck_threshold {
+            self.stuck_threshold = threshold;
+        }
+        if let Some(retries) = profile.max_stuck_retries {
+            self.max_stuck_retries = retries;
+        }
+        if let Some(secs) = profile.step_timeout_secs {
+            self.step_timeout_secs = secs;
+        }
+        if let Some(enabled) = profile.auto_replan_failed {
+            self.auto_replan_failed = enabled;
+        }
+        self.style_prompt = profile.style_prompt.clone();
+        self.profile = Some(profile.name.clone());
+        self
+    }
+
     /// Get the default system prompt for Planner.
     pub fn get_system_prompt(&self) -> String {
-        self.system_prompt.clone().unwrap_or_else(|| {
+        let prompt = self.system_prompt.clone().unwrap_or_else(|| {
             if self.lang == "cn" {
-                DEFAULT_PLANNER_SYSTEM_PROMPT_CN.to_string()
+                prompt_text("planner_system_prompt_cn", DEFAULT_PLANNER_SYSTEM_PROMPT_CN)
             } else {
-                DEFAULT_PLANNER_SYSTEM_PROMPT_EN.to_string()
+                prompt_text("planner_system_prompt_en", DEFAULT_PLANNER_SYSTEM_PROMPT_EN)
+            }
+        });
+        let prompt = PromptVars::new(&self.lang).render(&prompt);
+        match &self.style_prompt {
+            Some(style) if self.lang == "cn" => format!("{}\n\n## 监督风格\n\n{}", prompt, style),
+            Some(style) => format!("{}\n\n## Supervision Style\n\n{}", prompt, style),
+            None => prompt,
+        }
+    }
+}
+
+/// Names of the built-in planner profiles.
+pub const BUILTIN_PLANNER_PROFILES: &[&str] = &["cautious", "fast", "verbose"];
+
+/// Todo lists longer than this show finished tasks grouped by type in the
+/// Planner's summaries.
+const COMPACT_TODO_SUMMARY_THRESHOLD: usize = 20;
+
+/// A named planner configuration (supervision style) that overrides parts of
+/// `PlannerConfig`. Unset fields keep the configured value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlannerProfile {
+    /// Profile name used for selection.
+    pub name: String,
+    /// Short description shown to the user.
+    pub description: String,
+    /// Replaces the whole Planner system prompt.
+    pub system_prompt: Option<String>,
+    /// Supervision style instructions appended to the system prompt.
+    pub style_prompt: Option<String>,
+    /// Planner model name (base URL and API key are unchanged).
+    pub model_name: Option<String>,
+    /// Maximum number of Executor feedback entries to keep in history.
+    pub max_executor_feedback_history: Option<usize>,
+    /// Stuck detection threshold.
+    pub stuck_threshold: Option<u32>,
+    /// Maximum retries for stuck situations.
+    pub max_stuck_retries: Option<u32>,
+    /// Executor step timeout in seconds.
+    pub step_timeout_secs: Option<u64>,
+    /// Whether to decompose failed tasks into smaller steps.
+    pub auto_replan_failed: Option<bool>,
+    /// Planner loop interval in milliseconds (applied to the dual loop).
+    pub planner_interval_ms: Option<u64>,
+}
+
+impl PlannerProfile {
+    /// Get a built-in profile by name.
+    pub fn builtin(name: &str, lang: &str) -> Option<Self> {
+        let cn = lang == "cn";
+        let (description, style) = match name {
+            "cautious" => (
+                "Small steps, checks the screen often and intervenes early",
+                if cn {
+                    "谨慎模式：把任务拆成尽量小的子任务；不确定屏幕状态时先 query_screen；\
+                     执行器一出现偏差就用 inject_prompt 纠偏；涉及支付、发送、删除的操作先用 report 向用户说明。"
+                } else {
+                    "Cautious: split work into the smallest sensible tasks; use query_screen whenever \
+                     unsure of the screen; correct the executor with inject_prompt at the first sign of \
+                     drift; report before anything that pays, sends or deletes."
+                },
+            ),
+            "fast" => (
+                "Few tasks, minimal reporting, intervenes only when stuck or failed",
+                if cn {
+                    "快速模式：尽量少拆分子任务；减少汇报；只有在执行器卡住或失败时才介入。"
+                } else {
+                    "Fast: use as few tasks as possible; keep reports to a minimum; only intervene \
+                     when the executor is stuck or has failed."
+                },
+            ),
+            "verbose" => (
+                "Reports progress and reasoning after every task",
+                if cn {
+                    "详细模式：每完成一个子任务都用 report 向用户汇报进度、结果以及你下一步的打算和理由。"
+                } else {
+                    "Verbose: after every task, use report to tell the user the progress, the result \
+                     and what you will do next and why."
+                },
+            ),
+            _ => return None,
+        };
+
+        let mut profile = Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            style_prompt: Some(style.to_string()),
+            ..Self::default()
+        };
+        match name {
+            "cautious" => {
+                profile.max_executor_feedback_history = Some(4);
+                profile.stuck_threshold = Some(2);
+                profile.max_stuck_retries = Some(2);
+                profile.planner_interval_ms = Some(1000);
+            }
+            "fast" => {
+                profile.max_executor_feedback_history = Some(1);
+                profile.stuck_threshold = Some(5);
+                profile.auto_replan_failed = Some(false);
+                profile.planner_interval_ms = Some(4000);
+            }
+            _ => {
+                profile.max_executor_feedback_history = Some(3);
+            }
+        }
+        Some(profile)
+    }
+
+    /// Find a profile by name. User-defined profiles take precedence over
+    /// the built-in ones with the same name.
+    pub fn find(name: &str, custom: &[PlannerProfile], lang: &str) -> Option<Self> {
+        custom
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .or_else(|| Self::builtin(name, lang))
+    }
+
+    /// Names of all available profiles (built-in first).
+    pub fn available(custom: &[PlannerProfile]) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_PLANNER_PROFILES
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        for profile in custom {
+            if !names.contains(&profile.name) {
+                names.push(profile.name.clone());
             }
-        })
+        }
+        names
     }
 }
 
@@ -131,6 +459,38 @@ pub const DEFAULT_PLANNER_SYSTEM_PROMPT_CN: &str = r#"你是一个手机自动
 - 相似任务使用相同的 task_type，系统会自动学习并记忆优化提示词
 - 新 task_type 会被自动保存供以后使用
 
+**任务间传递数据**：执行器可以用 finish(data={...}) 返回提取到的数据（如订单号、价格），
+完成的任务会在任务列表中显示其数据。后续任务的描述可以用 {task_1.data.price} 引用，
+启动时会自动替换为实际值：
+{"action": "add_todo", "description": "在备忘录记下价格 {task_1.data.price}", "task_type": "备忘录"}
+
+**任务依赖**：用 depends_on 指定必须先完成的任务，该任务会等依赖全部完成后才执行
+（不再只按添加顺序）；依赖失败时该任务会被跳过：
+{"action": "add_todo", "description": "把路线发给张三", "task_type": "微信聊天", "depends_on": ["task_1", "task_2"]}
+
+**子任务**：复杂任务可以先添加一个父任务，再用 parent_id 在其下添加子步骤。
+父任务本身不执行，子任务全部完成后自动完成（有子任务失败则父任务失败）：
+{"action": "add_todo", "description": "发布一条小红书", "task_type": "小红书发布"}
+{"action": "add_todo", "description": "选择照片", "task_type": "小红书发布", "parent_id": "task_1"}
+{"action": "add_todo", "description": "填写文案", "task_type": "小红书发布", "parent_id": "task_1"}
+{"action": "add_todo", "description": "点击发布", "task_type": "小红书发布", "parent_id": "task_1"}
+
+**截止时间与时长上限**：deadline（格式同 schedule_todo 的 at）指定任务最晚完成时间，
+max_duration_secs 限制单次执行时长。超时后系统会通知你，由你决定放弃、重试或调整优先级：
+{"action": "add_todo", "description": "签到领积分", "task_type": "签到", "deadline": "12:00", "max_duration_secs": 300}
+
+**标签**：add_todo 和 schedule_todo 可用 tags 给任务加标签，便于在较长的任务列表中分组：
+{"action": "add_todo", "description": "比较牛奶价格", "task_type": "购物", "tags": ["比价", "周末采购"]}
+
+### 添加定时任务（到时间后自动启动，无需 start_executor）
+{"action": "schedule_todo", "description": "发布朋友圈", "task_type": "微信操作", "at": "21:00"}
+{"action": "schedule_todo", "description": "查看快递状态", "task_type": "通用任务", "delay_secs": 1800}
+{"action": "schedule_todo", "description": "淘宝签到领金币", "task_type": "签到", "repeat": "daily 08:00"}
+
+at 支持 "21:00"（今天，已过则为明天）、"2025-01-01 21:00" 或 RFC 3339 格式。
+repeat 让任务重复执行：支持 "daily 08:00"、"weekly mon,thu 08:00"、"every 2h" 或 cron 表达式
+（"0 8 * * 1-5"）。每次结束后系统会自动安排下一次，删除该任务即可停止重复。
+
 ### 启动执行器
 {"action": "start_executor", "task_id": "task_1"}
 
@@ -153,6 +513,27 @@ pub const DEFAULT_PLANNER_SYSTEM_PROMPT_CN: &str = r#"你是一个手机自动
 {"action": "complete_todo", "task_id": "task_1"}
 {"action": "fail_todo", "task_id": "task_1", "reason": "失败原因"}
 
+### 修改/删除/重排任务（用户改变主意时修订计划，而不是只追加或标记失败）
+{"action": "update_todo", "task_id": "task_2", "description": "新的任务描述", "task_type": "任务类型"}
+{"action": "remove_todo", "task_id": "task_3"}
+{"action": "reorder_todos", "task_ids": ["task_3", "task_2"]}
+
+update_todo 只能修改待执行的任务，description 和 task_type 可只填一个。
+remove_todo 删除正在执行的任务时会停止执行器。
+reorder_todos 按给定顺序重排这些任务，未列出的任务位置不变。
+
+### 审核执行器提议（仅在只提议模式下）
+{"action": "approve_proposal", "steps": 1}
+{"action": "reject_proposal", "reason": "原因和建议"}
+
+只提议模式下执行器每一步只给出打算执行的操作，由你审核。steps 大于 1 时接下来几步不再逐步审核。
+
+### 查询屏幕（截图并获取文字描述，不执行任何操作）
+{"action": "query_screen"}
+{"action": "query_screen", "question": "当前是否已登录？"}
+
+在添加任务前不确定手机当前状态时使用，系统会返回屏幕描述。
+
 ### 汇报进度
 {"action": "report", "message": "汇报内容"}
 
@@ -185,7 +566,8 @@ pub const DEFAULT_PLANNER_SYSTEM_PROMPT_CN: &str = r#"你是一个手机自动
 3. **任务描述要具体**，包含清晰的操作指导
 4. **用户中途反馈时**，使用 inject_prompt 而不是添加新任务
 5. **reset_executor 只清除对话历史**，不会影响任务列表
-6. **task_type 要有描述性**，方便系统学习和复用记忆"#;
+6. **task_type 要有描述性**，方便系统学习和复用记忆
+7. **看屏幕相似度判断是否卡住**：执行器反馈中的屏幕相似度是前后两张截图的相似度。接近但低于 1.00 通常是页面在加载或有动画，可以 wait；连续多步为 1.00 说明操作没有生效，需要纠偏"#;
 
 /// Default Planner system prompt (English).
 pub const DEFAULT_PLANNER_SYSTEM_PROMPT_EN: &str = r#"You are a phone automation task planning and supervision assistant. Your job is to break down user requests into sub-tasks, supervise execution, and intervene when needed.
@@ -200,12 +582,42 @@ You must use JSON format to call tools. Only call one tool per response. Format:
 ```
 task_type options: "wechat", "xiaohongshu", "douyin", "system", "general"
 
+### Schedule Task (starts automatically, no start_executor needed)
+```json
+{"action": "schedule_todo", "description": "Post the photo", "task_type": "social", "at": "21:00"}
+{"action": "schedule_todo", "description": "Check delivery", "task_type": "general", "delay_secs": 1800}
+{"action": "schedule_todo", "description": "Daily check-in", "task_type": "check-in", "repeat": "daily 08:00"}
+```
+at accepts "21:00" (today, or tomorrow if passed), "2025-01-01 21:00" or RFC 3339.
+repeat makes the task recurring: "daily 08:00", "weekly mon,thu 08:00", "every 2h" or a
+cron expression ("0 8 * * 1-5"). The next run is scheduled after each one finishes;
+remove the task to stop the series.
+
 ### Start Executor
 ```json
 {"action": "start_executor", "task_id": "task_id"}
 ```
 task_id is auto-generated after add_todo, format: "task_1", "task_2", etc.
 
+Passing data between tasks: the executor can return extracted values (order number,
+price, ...) with finish(data={...}); they are shown on the completed task. Later task
+descriptions may reference them as {task_1.data.price}, substituted when the task starts.
+
+Task dependencies: add_todo accepts "depends_on": ["task_1", "task_2"]; the task then
+only runs after all of them are done (instead of relying on insertion order), and is
+skipped if one of them fails.
+
+Subtasks: add an umbrella task, then add its steps with "parent_id": "task_1". The
+umbrella is not executed itself; it completes once all its steps are done (and fails
+if one of them fails).
+
+Deadlines: add_todo accepts "deadline" (same formats as schedule_todo's at) and
+"max_duration_secs" (limit of a single run). You are told when a task is overdue and
+decide whether to fail, retry or reprioritize it.
+
+Tags: add_todo and schedule_todo accept "tags": ["groceries", "weekly"] to group
+tasks in long lists.
+
 ### Pause/Resume Executor
 ```json
 {"action": "pause_executor"}
@@ -228,6 +640,30 @@ task_id is auto-generated after add_todo, format: "task_1", "task_2", etc.
 {"action": "fail_todo", "task_id": "task_id", "reason": "failure reason"}
 ```
 
+### Update/Remove/Reorder Tasks (revise the plan when the user changes their mind)
+```json
+{"action": "update_todo", "task_id": "task_2", "description": "new description", "task_type": "task_type"}
+{"action": "remove_todo", "task_id": "task_3"}
+{"action": "reorder_todos", "task_ids": ["task_3", "task_2"]}
+```
+update_todo only changes pending tasks; description and task_type are both optional.
+remove_todo stops the executor if the task is running.
+reorder_todos puts the listed tasks in the given order; unlisted tasks keep their place.
+
+### Review Executor Proposals (propose-only mode only)
+```json
+{"action": "approve_proposal", "steps": 1}
+{"action": "reject_proposal", "reason": "why, and what to do instead"}
+```
+In propose-only mode the executor only proposes each action for you to review. steps > 1 approves the following steps as well.
+
+### Query Screen (screenshot + text summary, no action)
+```json
+{"action": "query_screen"}
+{"action": "query_screen", "question": "Is the user logged in?"}
+```
+Use it when unsure about the current phone state before adding tasks.
+
 ### Report Progress (no action)
 ```json
 {"action": "report", "message": "report content"}
@@ -250,7 +686,8 @@ task_id is auto-generated after add_todo, format: "task_1", "task_2", etc.
 - Output only one JSON tool call per response
 - Briefly explain your thinking, then output JSON
 - Do not use code blocks, output JSON object directly
-- When receiving user request, first add the first task, wait for confirmation, then add next or start execution"#;
+- When receiving user request, first add the first task, wait for confirmation, then add next or start execution
+- Executor feedback includes the screen similarity between consecutive screenshots. Slightly below 1.00 usually means the page is loading or animating, so wait; 1.00 for several steps means the action had no effect and needs correcting"#;
 
 /// Planner action types.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -260,6 +697,40 @@ pub enum PlannerAction {
     AddTodo {
         description: String,
         task_type: String,
+        /// IDs of tasks that must be done before this one starts.
+        #[serde(default)]
+        depends_on: Vec<String>,
+        /// Umbrella task this is a step of.
+        #[serde(default)]
+        parent_id: Option<String>,
+        /// Time the task should be done by ("21:00", "2025-01-01 21:00" or RFC 3339).
+        #[serde(default)]
+        deadline: Option<String>,
+        /// Maximum duration of a single run, in seconds.
+        #[serde(default)]
+        max_duration_secs: Option<u64>,
+        /// Free-form labels for grouping.
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// Add a todo item that starts automatically at a given time or after a delay.
+    ScheduleTodo {
+        description: String,
+        task_type: String,
+        /// Start time ("21:00", "2025-01-01 21:00" or RFC 3339).
+        #[serde(default)]
+        at: Option<String>,
+        /// Start after this many seconds (used when `at` is absent).
+        #[serde(default)]
+        delay_secs: Option<u64>,
+        /// Recurrence rule ("daily 08:00", "weekly mon 09:00", "every 2h" or
+        /// cron). Without `at`/`delay_secs` the first run is the next
+        /// occurrence.
+        #[serde(default)]
+        repeat: Option<String>,
+        /// Free-form labels for grouping.
+        #[serde(default)]
+        tags: Vec<String>,
     },
     /// Start the executor on a task.
     StartExecutor { task_id: String },
@@ -275,6 +746,34 @@ pub enum PlannerAction {
     CompleteTodo { task_id: String },
     /// Mark a todo as failed.
     FailTodo { task_id: String, reason: String },
+    /// Change the description and/or task type of a pending todo.
+    UpdateTodo {
+        task_id: String,
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        task_type: Option<String>,
+    },
+    /// Remove a todo (stops the executor if it is running).
+    RemoveTodo { task_id: String },
+    /// Rearrange todos into the given order.
+    ReorderTodos { task_ids: Vec<String> },
+    /// Approve the executor's proposed action (propose-only mode). `steps`
+    /// greater than 1 lets the following steps run without review.
+    ApproveProposal {
+        #[serde(default = "default_approved_steps")]
+        steps: u32,
+    },
+    /// Reject the executor's proposed action with a reason.
+    RejectProposal {
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    /// Take a screenshot and get a textual summary of the screen (no action).
+    QueryScreen {
+        #[serde(default)]
+        question: Option<String>,
+    },
     /// Report to user (no action, just message).
     Report { message: String },
     /// Wait for more information.
@@ -283,6 +782,44 @@ pub enum PlannerAction {
     Done { message: String },
 }
 
+fn default_approved_steps() -> u32 {
+    1
+}
+
+/// Format a screenshot similarity for the Planner ("n/a" when not comparable).
+fn format_similarity(similarity: Option<f64>) -> String {
+    similarity
+        .map(|s| format!("{:.2}", s))
+        .unwrap_or_else(|| "n/a".to_string())
+}
+
+/// Wrap a takeover callback so `TakeoverRequired` is published before it
+/// blocks, letting notifiers reach the user.
+fn announce_takeover(
+    events: &EventBus,
+    callback: SharedTakeoverCallback,
+) -> SharedTakeoverCallback {
+    let events = events.clone();
+    Arc::new(move |message: &str| {
+        events.emit(DualLoopEvent::TakeoverRequired {
+            message: message.to_string(),
+        });
+        callback(message);
+    })
+}
+
+/// Whether `text` contains `term`. A term starting with a Latin letter or
+/// digit must start a word in `text` ("pay" matches "payments", not "repay");
+/// CJK terms match anywhere.
+fn contains_term(text: &str, term: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    if !is_word(term.chars().next()) {
+        return text.contains(term);
+    }
+    text.match_indices(term)
+        .any(|(start, _)| !is_word(text[..start].chars().next_back()))
+}
+
 /// Planner agent for the outer loop.
 pub struct PlannerAgent {
     /// Model client for Planner.
@@ -293,10 +830,6 @@ pub struct PlannerAgent {
     todo_list: TodoList,
     /// Executor instance.
     executor: ExecutorWrapper,
-    /// Executor model config (for recreating).
-    executor_model_config: ModelConfig,
-    /// Executor agent config (for recreating).
-    executor_agent_config: AgentConfig,
     /// Limited history of Executor feedback.
     executor_feedback_history: VecDeque<ExecutorFeedback>,
     /// User input queue.
@@ -307,6 +840,10 @@ pub struct PlannerAgent {
     prompt_memory: PromptMemory,
     /// Consecutive stuck count (for multi-stuck handling).
     consecutive_stuck_count: u32,
+    /// Consecutive executor step timeouts.
+    consecutive_timeout_count: u32,
+    /// Task and step of the last proposal presented for review.
+    reviewed_proposal: Option<(Option<String>, u32)>,
     /// Execution log for prompt optimization.
     execution_log: Vec<String>,
     /// Whether the planner is running.
@@ -315,6 +852,43 @@ pub struct PlannerAgent {
     pending_consolidation_task_types: Vec<String>,
     /// Whether there is new executor feedback that hasn't been handled yet.
     pending_executor_feedback: bool,
+    /// Event stream for live observers (GUI, web frontends).
+    events: EventBus,
+    /// Whether the context was restored from a saved session.
+    resumed: bool,
+    /// Patterns that require user approval before a task starts (empty = disabled).
+    approval_patterns: Vec<String>,
+    /// Tasks waiting for user approval.
+    awaiting_approval: Vec<String>,
+    /// Tasks the user has approved.
+    approved_tasks: HashSet<String>,
+    /// Summaries of past sessions.
+    episodic_memory: EpisodicMemory,
+    /// User requests received since the last recorded episode.
+    session_requests: Vec<String>,
+    /// Tasks already included in a recorded episode.
+    recorded_task_ids: HashSet<String>,
+    /// Token usage of both loops.
+    spend: SpendLedger,
+    /// Whether `AllTasksFinished` was published for the current todo list.
+    finished_reported: bool,
+    /// A final summary should be generated on the next planner tick.
+    summary_pending: bool,
+    /// Latest end-of-session summary, shared with `DualLoopHandle`.
+    summary_tx: watch::Sender<Option<String>>,
+    /// Client of the shared remote prompt memory.
+    memory_sync: Option<MemorySync>,
+    /// When prompt memory was last synced with the remote.
+    last_memory_sync: Option<Instant>,
+    /// Successful steps of the running task, stored as an example when it
+    /// completes.
+    trajectory: Vec<TrajectoryStep>,
+    /// Executor step count of the last step added to `trajectory`.
+    trajectory_step: u32,
+    /// Overdue tasks (ID, reason) the Planner has not been told about yet.
+    overdue_notices: Vec<(String, String)>,
+    /// Executor step count already added to the running task's steps.
+    counted_steps: u32,
 }
 
 impl PlannerAgent {
@@ -327,33 +901,351 @@ impl PlannerAgent {
         let model_client = ModelClient::new(planner_config.model_config.clone());
 
         // Load prompt memory if path specified
-        let prompt_memory = planner_config
+        let mut prompt_memory: PromptMemory = planner_config
             .prompt_memory_path
             .as_ref()
             .and_then(|path| PromptMemory::load(path).ok())
             .unwrap_or_default();
+        prompt_memory.enforce_limits(&planner_config.prompt_memory_limits);
 
-        let executor =
-            ExecutorWrapper::new(executor_model_config.clone(), executor_agent_config.clone())
-                .with_stuck_threshold(planner_config.stuck_threshold);
+        let episodic_memory = planner_config
+            .episodic_memory_path
+            .as_ref()
+            .and_then(|path| EpisodicMemory::load(path).ok())
+            .unwrap_or_default();
 
-        Self {
+        let memory_sync = planner_config.memory_sync_url.as_ref().map(MemorySync::new);
+
+        let events = EventBus::default();
+        let on_takeover = announce_takeover(&events, Arc::new(default_takeover));
+
+        let executor = ExecutorWrapper::new(executor_model_config, executor_agent_config)
+            .with_takeover_callback(on_takeover)
+            .with_stuck_threshold(planner_config.stuck_threshold)
+            .with_similarity_threshold(planner_config.screen_similarity_threshold)
+            .with_propose_only(planner_config.propose_only)
+            .with_step_timeout(
+                (planner_config.step_timeout_secs > 0)
+                    .then(|| Duration::from_secs(planner_config.step_timeout_secs)),
+            );
+
+        let mut planner = Self {
             model_client,
             config: planner_config,
             todo_list: TodoList::new(),
             executor,
-            executor_model_config,
-            executor_agent_config,
             executor_feedback_history: VecDeque::new(),
             user_input_queue: VecDeque::new(),
             context: Vec::new(),
             prompt_memory,
             consecutive_stuck_count: 0,
+            consecutive_timeout_count: 0,
+            reviewed_proposal: None,
             execution_log: Vec::new(),
             is_running: false,
             pending_consolidation_task_types: Vec::new(),
             pending_executor_feedback: false,
+            events,
+            resumed: false,
+            approval_patterns: Vec::new(),
+            awaiting_approval: Vec::new(),
+            approved_tasks: HashSet::new(),
+            episodic_memory,
+            session_requests: Vec::new(),
+            recorded_task_ids: HashSet::new(),
+            spend: SpendLedger::new(),
+            finished_reported: false,
+            summary_pending: false,
+            summary_tx: watch::channel(None).0,
+            memory_sync,
+            last_memory_sync: None,
+            trajectory: Vec::new(),
+            trajectory_step: 0,
+            overdue_notices: Vec::new(),
+            counted_steps: 0,
+        };
+        planner.load_todo_list();
+        planner
+    }
+
+    /// Hand takeovers (login, captcha) to `callback` instead of the console.
+    /// `TakeoverRequired` is still published before it is called.
+    pub fn with_takeover_callback(mut self, callback: SharedTakeoverCallback) -> Self {
+        let on_takeover = announce_takeover(&self.events, callback);
+        self.executor.set_takeover_callback(on_takeover);
+        self
+    }
+
+    /// Ask `callback` before sensitive operations instead of the console.
+    pub fn with_confirmation_callback(mut self, callback: SharedConfirmationCallback) -> Self {
+        self.executor.set_confirmation_callback(callback);
+        self
+    }
+
+    /// Resume an unfinished todo list saved by an interrupted session. The
+    /// task that was running is restarted from scratch; a fully finished
+    /// list is not restored.
+    fn load_todo_list(&mut self) {
+        let Some(path) = self.config.todo_list_path.clone() else {
+            return;
+        };
+        let list = match TodoList::load(&path) {
+            Ok(list) => list,
+            Err(TodoError::NotFound(_)) => return,
+            Err(e) => {
+                tracing::warn!("Failed to load todo list: {}", e);
+                return;
+            }
+        };
+        if list.items().is_empty() || list.is_all_done() {
+            return;
+        }
+
+        let running = list.current_running().map(|t| t.id.clone());
+        let unfinished = list.items().iter().filter(|t| !t.is_terminal()).count();
+        self.todo_list = list;
+        println!(
+            "🔁 [System] 已恢复未完成的任务列表 ({} 个待执行)",
+            unfinished
+        );
+        tracing::info!("Restored todo list from {}", path);
+        match running {
+            Some(task_id) => self.start_task(&task_id),
+            None => self.notify_todo_changed(),
+        }
+    }
+
+    /// Preload a plan file (see `TodoList::import`) and start executing it
+    /// without going through the Planner model. Returns the new task IDs.
+    pub fn import_plan(&mut self, path: &str) -> Result<Vec<String>, TodoError> {
+        let ids = self.todo_list.import(path)?;
+        println!("📥 [System] 已导入计划: {} ({} 个任务)", path, ids.len());
+        tracing::info!("Imported {} tasks from {}", ids.len(), path);
+
+        let next = match self.todo_list.current_running() {
+            Some(_) => None,
+            None => self.todo_list.next_pending().map(|t| t.id.clone()),
+        };
+        match next {
+            Some(task_id) => self.start_task(&task_id),
+            None => self.notify_todo_changed(),
+        }
+        Ok(ids)
+    }
+
+    /// Set the patterns that require user approval before starting a task.
+    /// Patterns are matched case-insensitively against the task description
+    /// and task type; one starting with a Latin letter or digit must start a
+    /// word ("pay" matches "payments", not "repay"). An empty list disables
+    /// the approval gate.
+    pub fn set_approval_patterns(&mut self, patterns: Vec<String>) {
+        self.approval_patterns = patterns
+            .iter()
+            .map(|p| p.trim().to_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect();
+    }
+
+    /// Get the tasks currently waiting for user approval.
+    pub fn pending_approvals(&self) -> &[String] {
+        &self.awaiting_approval
+    }
+
+    /// Check whether a task matches one of the approval patterns.
+    fn matches_approval_pattern(&self, task: &TodoItem) -> bool {
+        let description = task.description.to_lowercase();
+        let task_type = task.task_type.to_lowercase();
+        self.approval_patterns
+            .iter()
+            .any(|p| contains_term(&description, p) || contains_term(&task_type, p))
+    }
+
+    /// Check whether starting a task must wait for user approval.
+    fn needs_approval(&self, task_id: &str) -> bool {
+        if self.approval_patterns.is_empty() || self.approved_tasks.contains(task_id) {
+            return false;
+        }
+        self.todo_list
+            .get(task_id)
+            .map(|t| self.matches_approval_pattern(t))
+            .unwrap_or(false)
+    }
+
+    /// Hold a task until the user approves it.
+    fn request_approval(&mut self, task_id: &str) {
+        if self.awaiting_approval.iter().any(|id| id == task_id) {
+            return;
+        }
+        let description = self
+            .todo_list
+            .get(task_id)
+            .map(|t| t.description.clone())
+            .unwrap_or_default();
+
+        println!(
+            "🛑 [System] 任务需要用户批准: {} - {} (输入 approve {} 批准)",
+            task_id, description, task_id
+        );
+        tracing::info!("Task {} awaiting approval", task_id);
+
+        self.awaiting_approval.push(task_id.to_string());
+        self.events.emit(DualLoopEvent::ApprovalRequired {
+            task_id: task_id.to_string(),
+            description,
+        });
+    }
+
+    /// Approve a task that is waiting for approval and start it.
+    /// Returns false if the task was not waiting for approval.
+    pub fn approve_task(&mut self, task_id: &str) -> bool {
+        let Some(pos) = self.awaiting_approval.iter().position(|id| id == task_id) else {
+            return false;
+        };
+        self.awaiting_approval.remove(pos);
+        self.approved_tasks.insert(task_id.to_string());
+
+        println!("✅ [System] 用户已批准任务: {}", task_id);
+        let feedback = format!("[系统反馈] 用户已批准任务 {}，执行器已启动。", task_id);
+        self.push_feedback(&feedback);
+
+        self.start_task(task_id);
+        true
+    }
+
+    /// Reject a task that is waiting for approval; the task is skipped.
+    /// Returns false if the task was not waiting for approval.
+    pub fn reject_task(&mut self, task_id: &str) -> bool {
+        let Some(pos) = self.awaiting_approval.iter().position(|id| id == task_id) else {
+            return false;
+        };
+        self.awaiting_approval.remove(pos);
+
+        if let Some(task) = self.todo_list.get_mut(task_id) {
+            task.skip();
+        }
+        self.notify_todo_changed();
+
+        println!("⏭️ [System] 用户拒绝了任务: {}", task_id);
+        let feedback = format!("[系统反馈] 用户拒绝执行任务 {}，该任务已跳过。", task_id);
+        self.push_feedback(&feedback);
+
+        // Move on to the next task if nothing else is running
+        if self.todo_list.current_running().is_none() {
+            if let Some(next_task) = self.todo_list.next_pending() {
+                let next_id = next_task.id.clone();
+                self.start_task(&next_id);
+            }
+        }
+        true
+    }
+
+    /// Rearrange todos on the user's behalf (see [`TodoList::reorder_many`]).
+    /// Returns false if an ID is unknown or repeated.
+    pub fn reorder_todos(&mut self, task_ids: &[String]) -> bool {
+        if !self.todo_list.reorder_many(task_ids) {
+            return false;
+        }
+        self.notify_todo_changed();
+
+        println!("🔀 [System] 用户调整了任务顺序: {}", task_ids.join(" → "));
+        let feedback = format!(
+            "[系统反馈] 用户调整了任务顺序。\n\n当前任务列表:\n{}",
+            self.build_todo_summary()
+        );
+        self.push_feedback(&feedback);
+        true
+    }
+
+    /// Change the description of a pending todo on the user's behalf.
+    /// Returns false if the task does not exist or has already started.
+    pub fn edit_todo(&mut self, task_id: &str, description: &str) -> bool {
+        if !self.todo_list.update(task_id, Some(description), None) {
+            return false;
+        }
+        self.notify_todo_changed();
+
+        println!("✏️ [System] 用户修改了任务 {}: {}", task_id, description);
+        let feedback = format!(
+            "[系统反馈] 用户把任务 {} 的描述改为: {}",
+            task_id, description
+        );
+        self.push_feedback(&feedback);
+        true
+    }
+
+    /// Get the event bus used to publish planner events.
+    pub fn event_bus(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Subscribe to planner events.
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<DualLoopEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record tokens used by a Planner model call against the running task.
+    fn track_planner_usage(&mut self, usage: &TokenUsage) {
+        let task_id = self.todo_list.current_running().map(|t| t.id.clone());
+        self.spend.record_planner(task_id.as_deref(), usage);
+    }
+
+    /// Token usage accumulated by both loops.
+    pub fn spend_ledger(&self) -> &SpendLedger {
+        &self.spend
+    }
+
+    /// Publish a snapshot of the todo list to subscribers, followed by
+    /// `AllTasksFinished` the first time every task is terminal.
+    /// Tell the planner model what the system did, and publish it so
+    /// frontends can show it in the planner conversation.
+    fn push_feedback(&mut self, feedback: &str) {
+        self.context
+            .push(MessageBuilder::create_user_message(feedback, None));
+        self.events.emit(DualLoopEvent::SystemFeedback {
+            content: feedback.to_string(),
+        });
+    }
+
+    fn notify_todo_changed(&mut self) {
+        for task_id in self.todo_list.respawn_recurring(chrono::Local::now()) {
+            if let Some(task) = self.todo_list.get(&task_id) {
+                println!(
+                    "🔁 [System] 已安排下一次重复任务: {} - {} ({})",
+                    task_id,
+                    task.description,
+                    task.scheduled_at
+                        .map(format_schedule_time)
+                        .unwrap_or_default()
+                );
+                tracing::info!("Scheduled next occurrence of recurring task: {}", task_id);
+            }
+        }
+        for parent_id in self.todo_list.sync_parents() {
+            if let Some(parent) = self.todo_list.get(&parent_id) {
+                println!(
+                    "🪜 [System] 父任务 {} 的子任务已全部结束: {:?}",
+                    parent_id, parent.status
+                );
+            }
+        }
+        if let Some(path) = &self.config.todo_list_path {
+            if let Err(e) = self.todo_list.save(path) {
+                tracing::warn!("Failed to save todo list: {}", e);
+            }
+        }
+
+        let stats = self.todo_list.stats();
+        self.events.emit(DualLoopEvent::TodoChanged {
+            items: self.todo_list.items().to_vec(),
+            stats: stats.clone(),
+        });
+
+        let finished = stats.total > 0 && self.todo_list.is_all_done();
+        if finished && !self.finished_reported {
+            self.events.emit(DualLoopEvent::AllTasksFinished { stats });
+            self.summary_pending = true;
         }
+        self.finished_reported = finished;
     }
 
     /// Queue user input for processing.
@@ -389,31 +1281,132 @@ impl PlannerAgent {
     /// Start the planner.
     pub fn start(&mut self) {
         self.is_running = true;
-        self.initialize_context();
+        if self.resumed {
+            // Keep the restored conversation, only refresh the system prompt
+            self.resumed = false;
+            self.refresh_context_with_task_types();
+        } else {
+            self.initialize_context();
+        }
+    }
+
+    /// Take a snapshot of the current session for persistence.
+    pub fn snapshot_session(&self) -> SessionState {
+        SessionState {
+            version: SESSION_FORMAT_VERSION,
+            saved_at: chrono::Utc::now().to_rfc3339(),
+            planner_context: self.context.clone(),
+            todo_list: self.todo_list.clone(),
+            pending_user_input: self.user_input_queue.iter().cloned().collect(),
+            current_task_id: self.todo_list.current_running().map(|t| t.id.clone()),
+        }
+    }
+
+    /// Restore a previously saved session.
+    /// The task the Executor was working on is restarted from scratch,
+    /// since the Executor's own context is not persisted.
+    pub fn restore_session(&mut self, state: SessionState) {
+        self.context = state.planner_context;
+        self.todo_list = state.todo_list;
+        self.user_input_queue = state.pending_user_input.into_iter().collect();
+        self.executor_feedback_history.clear();
+        self.pending_executor_feedback = false;
+        self.resumed = !self.context.is_empty();
+
+        if let Some(task_id) = state.current_task_id {
+            let resumable = self
+                .todo_list
+                .get(&task_id)
+                .map(|t| !t.is_terminal())
+                .unwrap_or(false);
+            if resumable {
+                println!("🔁 [System] 恢复会话，重新启动任务: {}", task_id);
+                self.start_task(&task_id);
+            }
+        }
+
+        tracing::info!(
+            "Restored session with {} todos and {} context messages",
+            self.todo_list.items().len(),
+            self.context.len()
+        );
+        self.notify_todo_changed();
     }
 
     /// Stop the planner.
     pub fn stop(&mut self) {
+        self.record_episode();
         self.is_running = false;
         self.executor.enqueue(ExecutorCommand::Stop);
     }
 
+    /// Stop the planner, flush learned state and emit a final report.
+    /// Called by the dual loop once the in-flight step has finished.
+    pub fn shutdown(&mut self) {
+        self.stop();
+
+        if let Err(e) = self.save_prompt_memory() {
+            tracing::warn!("Failed to save prompt memory on shutdown: {}", e);
+        }
+
+        let stats = self.todo_list.stats();
+        let message = format!(
+            "会话结束: 完成 {}/{} 个任务 (失败 {}, 跳过 {}, 未完成 {})",
+            stats.done,
+            stats.total,
+            stats.failed,
+            stats.skipped,
+            stats.pending + stats.running
+        );
+        println!("🏁 [System] {}", message);
+        for (task_type, type_stats) in stats.slowest_types() {
+            println!(
+                "⏱️ [System] {}: {} 个任务, 平均 {:.0} 秒, 平均 {:.1} 步",
+                task_type,
+                type_stats.finished,
+                type_stats.avg_run_secs(),
+                type_stats.avg_steps()
+            );
+        }
+        self.events.emit(DualLoopEvent::Report { message });
+        self.events.emit(DualLoopEvent::Stopped { stats });
+    }
+
     /// Initialize planner context with system prompt and available task types.
     fn initialize_context(&mut self) {
         self.context.clear();
 
-        // Build system prompt with available task types
+        let full_prompt = self.build_system_prompt();
+        self.context
+            .push(MessageBuilder::create_system_message(&full_prompt));
+    }
+
+    /// Build the system prompt with available task types and recent sessions.
+    fn build_system_prompt(&self) -> String {
         let base_prompt = self.config.get_system_prompt();
         let task_types_summary = self.prompt_memory.get_task_types_summary();
 
-        let full_prompt = format!(
+        let mut full_prompt = format!(
             "{}\n\n## 已保存的任务类型记忆\n\n以下是系统已学习的任务类型，优先使用这些类型以便复用记忆：\n\n{}\n\n你也可以创建新的任务类型，系统会自动学习。",
             base_prompt,
             task_types_summary
         );
 
-        self.context
-            .push(MessageBuilder::create_system_message(&full_prompt));
+        if !self.episodic_memory.is_empty() {
+            let recent = self
+                .episodic_memory
+                .recent(self.config.max_relevant_episodes)
+                .iter()
+                .map(|e| e.summary())
+                .collect::<Vec<_>>()
+                .join("\n");
+            full_prompt.push_str(&format!(
+                "\n\n## 最近的历史会话\n\n以下是用户最近的请求及执行结果。当用户提到\"平常的\"、\"和上次一样\"等说法时，参考这些记录规划任务，并避开曾经失败的做法：\n\n{}",
+                recent
+            ));
+        }
+
+        full_prompt
     }
 
     /// Refresh the system context with updated task types.
@@ -421,23 +1414,33 @@ impl PlannerAgent {
     pub fn refresh_context_with_task_types(&mut self) {
         if !self.context.is_empty() {
             // Update the system message (first message)
-            let base_prompt = self.config.get_system_prompt();
-            let task_types_summary = self.prompt_memory.get_task_types_summary();
-
-            let full_prompt = format!(
-                "{}\n\n## 已保存的任务类型记忆\n\n以下是系统已学习的任务类型，优先使用这些类型以便复用记忆：\n\n{}\n\n你也可以创建新的任务类型，系统会自动学习。",
-                base_prompt,
-                task_types_summary
-            );
-
+            let full_prompt = self.build_system_prompt();
             self.context[0] = MessageBuilder::create_system_message(&full_prompt);
         }
     }
 
+    /// Rebuild the system message when the planner prompt changed on disk
+    /// (see [`prompt_text`]), so edited prompt files apply to the next turn.
+    fn reload_prompt_overrides(&mut self) {
+        let base_prompt = self.config.get_system_prompt();
+        let current = self.context.first().and_then(|m| m["content"].as_str());
+        if current.is_some_and(|content| !content.starts_with(&base_prompt)) {
+            tracing::info!("Planner system prompt changed, reloading");
+            self.refresh_context_with_task_types();
+        }
+    }
+
     /// Execute one tick of the Executor loop.
     /// Call this in the Executor's dedicated loop.
     pub async fn tick_executor(&mut self) -> ExecutorFeedback {
         let feedback = self.executor.tick().await;
+        if let Some(ref result) = feedback.last_result {
+            self.spend
+                .record_executor(feedback.task_id.as_deref(), &result.usage);
+        }
+        self.events.emit(DualLoopEvent::ExecutorFeedback {
+            feedback: Box::new(feedback.clone()),
+        });
         self.collect_executor_feedback(feedback.clone());
         feedback
     }
@@ -452,9 +1455,15 @@ impl PlannerAgent {
         let has_input = self.has_pending_input();
         let has_feedback = self.pending_executor_feedback;
         let has_consolidation = !self.pending_consolidation_task_types.is_empty();
+        let has_overdue = !self.overdue_notices.is_empty();
 
         // If nothing new to process, skip heavy work
-        if !has_input && !has_feedback && !has_consolidation {
+        if !has_input
+            && !has_feedback
+            && !has_consolidation
+            && !has_overdue
+            && !self.summary_pending
+        {
             return !self.todo_list.is_all_done();
         }
 
@@ -470,7 +1479,20 @@ impl PlannerAgent {
             self.pending_executor_feedback = false;
         }
 
-        // 4. Check if we should continue
+        // 3b. Let the Planner decide what to do with overdue tasks
+        self.process_overdue_notices().await;
+
+        // 4. Tell the user what came out of the finished work
+        if std::mem::take(&mut self.summary_pending) && self.todo_list.is_all_done() {
+            self.deliver_session_summary().await;
+        }
+
+        // 5. Remember the session once all requested work has finished
+        if self.todo_list.is_all_done() {
+            self.record_episode();
+        }
+
+        // 6. Check if we should continue
         !self.todo_list.is_all_done() || self.has_pending_input() || self.pending_executor_feedback
     }
 
@@ -479,16 +1501,28 @@ impl PlannerAgent {
         while let Some(input) = self.user_input_queue.pop_front() {
             println!("\n🧠 [Planner] Processing user input: {}", input);
             tracing::info!("Processing user input: {}", input);
+            self.events.emit(DualLoopEvent::UserInput {
+                input: input.clone(),
+            });
 
             // Build executor status to include with user input
             let executor_status_summary = self.build_executor_status_summary();
             let todo_summary = self.build_todo_summary();
 
             // Add user message along with current executor state
-            let enriched_input = format!(
+            let mut enriched_input = format!(
                 "[用户输入]\n{}\n\n[当前执行器状态]\n{}\n\n[当前任务列表]\n{}",
                 input, executor_status_summary, todo_summary
             );
+
+            // Surface past sessions that look like this request
+            if let Some(episodes) = self
+                .episodic_memory
+                .relevant_summary(&input, self.config.max_relevant_episodes)
+            {
+                enriched_input.push_str(&format!("\n\n[相关历史会话]\n{}", episodes));
+            }
+            self.session_requests.push(input);
             self.context
                 .push(MessageBuilder::create_user_message(&enriched_input, None));
 
@@ -517,6 +1551,9 @@ impl PlannerAgent {
                     println!("\n💬 [DeepSeek Response]:");
                     println!("{}", response_text);
                     println!();
+                    self.events.emit(DualLoopEvent::PlannerResponse {
+                        content: response_text.clone(),
+                    });
 
                     // Execute all parsed actions
                     if actions.is_empty() {
@@ -566,6 +1603,14 @@ impl PlannerAgent {
         match action {
             // After adding a task, continue to add more or start executor
             PlannerAction::AddTodo { .. } => true,
+            // After looking at the screen, continue to decide what to do
+            PlannerAction::QueryScreen { .. } => true,
+            // Scheduling behaves like adding a task
+            PlannerAction::ScheduleTodo { .. } => true,
+            // Plan revisions are usually followed by more edits or start_executor
+            PlannerAction::UpdateTodo { .. } => true,
+            PlannerAction::RemoveTodo { .. } => true,
+            PlannerAction::ReorderTodos { .. } => true,
             // After starting executor, stop the conversation loop
             PlannerAction::StartExecutor { .. } => false,
             // Report should stop to avoid repeated summaries
@@ -578,36 +1623,15 @@ impl PlannerAgent {
         }
     }
 
-    /// Get planner's response and parse action.
-    /// Returns (raw_response, parsed_action).
-    async fn get_planner_response(&mut self) -> Option<(String, Option<PlannerAction>)> {
-        // Call planner model
-        match self.model_client.request(&self.context).await {
-            Ok(response) => {
-                let response_text = response.raw_content.clone();
-
-                // Add assistant response to context
-                self.context
-                    .push(MessageBuilder::create_assistant_message(&response.action));
-
-                // Parse action from response
-                let action = self.parse_planner_action(&response.action);
-                Some((response_text, action))
-            }
-            Err(e) => {
-                println!("❌ [Planner] Model error: {}", e);
-                tracing::error!("Planner model error: {}", e);
-                None
-            }
-        }
-    }
-
     /// Get planner's response and parse ALL actions.
     /// Returns (raw_response, parsed_actions).
     async fn get_planner_response_multi(&mut self) -> Option<(String, Vec<PlannerAction>)> {
+        self.reload_prompt_overrides();
+
         // Call planner model
         match self.model_client.request(&self.context).await {
             Ok(response) => {
+                self.track_planner_usage(&response.usage);
                 let response_text = response.raw_content.clone();
 
                 // Add assistant response to context
@@ -638,7 +1662,7 @@ impl PlannerAgent {
             )
         });
 
-        if let Some((status, context_overflow, parse_errors, _feedback)) = feedback_info {
+        if let Some((status, context_overflow, parse_errors, feedback)) = feedback_info {
             // Handle context overflow first (highest priority)
             if context_overflow {
                 self.handle_context_overflow(parse_errors).await;
@@ -655,14 +1679,215 @@ impl PlannerAgent {
                 ExecutorStatus::Failed(reason) => {
                     self.handle_executor_failed(reason).await;
                 }
+                ExecutorStatus::TimedOut => {
+                    self.handle_executor_timed_out().await;
+                }
+                ExecutorStatus::AwaitingApproval => {
+                    self.review_proposal(&feedback).await;
+                }
                 _ => {
                     // Running, Paused, or Idle - nothing special to do
                     self.consecutive_stuck_count = 0;
+                    self.consecutive_timeout_count = 0;
                 }
             }
         }
     }
 
+    /// Present a proposed executor action for review (propose-only mode).
+    /// Each proposal is reviewed once, by the Planner or by the user.
+    async fn review_proposal(&mut self, feedback: &ExecutorFeedback) {
+        let Some(action) = feedback.proposed_action.clone() else {
+            return;
+        };
+        let key = (feedback.task_id.clone(), feedback.step_count);
+        if self.reviewed_proposal.as_ref() == Some(&key) {
+            return;
+        }
+        self.reviewed_proposal = Some(key);
+
+        let thinking = feedback
+            .last_result
+            .as_ref()
+            .map(|r| r.thinking.clone())
+            .unwrap_or_default();
+        let task = self
+            .todo_list
+            .current_running()
+            .map(|t| format!("{} - {}", t.id, t.description))
+            .unwrap_or_default();
+        let action_str = serde_json::to_string(&action).unwrap_or_default();
+
+        println!(
+            "🔍 [Proposal] 第 {} 步提议 ({}): {}",
+            feedback.step_count, task, action_str
+        );
+        self.events.emit(DualLoopEvent::ProposalPending {
+            task_id: feedback.task_id.clone(),
+            step: feedback.step_count,
+            action,
+            thinking: thinking.clone(),
+        });
+
+        if self.config.user_reviews_proposals {
+            println!("   输入 approve-step [步数] 批准，reject-step [原因] 拒绝");
+            return;
+        }
+
+        let review = format!(
+            "[执行器提议]\n任务: {}\n第 {} 步\n思考: {}\n动作: {}\n\n\
+            执行器处于只提议模式，该操作尚未执行。请审核它是否符合任务目标且安全：\n\
+            - 批准: {{\"action\": \"approve_proposal\", \"steps\": 1}}（steps 大于 1 表示接下来几步无需逐步审核）\n\
+            - 拒绝: {{\"action\": \"reject_proposal\", \"reason\": \"原因和建议\"}}",
+            task, feedback.step_count, thinking, action_str
+        );
+        self.context
+            .push(MessageBuilder::create_user_message(&review, None));
+        self.continue_planner_conversation().await;
+    }
+
+    /// Approve the executor's proposed action. Returns false if no
+    /// proposal is waiting.
+    pub fn approve_proposal(&mut self, steps: u32) -> bool {
+        if *self.executor.status() != ExecutorStatus::AwaitingApproval {
+            return false;
+        }
+        let steps = steps.max(1);
+        println!("✅ [System] 已批准执行器提议 ({} 步)", steps);
+        self.executor
+            .enqueue(ExecutorCommand::ApproveProposal { steps });
+        self.push_feedback(&format!(
+            "[系统反馈] 执行器提议已批准，接下来 {} 步将直接执行。",
+            steps
+        ));
+        true
+    }
+
+    /// Reject the executor's proposed action. Returns false if no
+    /// proposal is waiting.
+    pub fn reject_proposal(&mut self, reason: Option<String>) -> bool {
+        if *self.executor.status() != ExecutorStatus::AwaitingApproval {
+            return false;
+        }
+        println!(
+            "⛔ [System] 已拒绝执行器提议: {}",
+            reason.as_deref().unwrap_or("-")
+        );
+        self.executor
+            .enqueue(ExecutorCommand::RejectProposal { reason });
+        self.push_feedback("[系统反馈] 执行器提议已拒绝，执行器将重新提议。");
+        true
+    }
+
+    /// Inject a prompt from the user straight into the executor, without a
+    /// planner round-trip. The planner is told about it on its next turn.
+    pub fn inject_executor_prompt(&mut self, content: String) {
+        let was_stopped = matches!(
+            self.executor.status(),
+            ExecutorStatus::Completed | ExecutorStatus::Idle
+        );
+        println!("💉 [用户] 直接注入提示词: {}", content);
+
+        self.learn_from_correction(&content, was_stopped);
+        self.push_feedback(&format!(
+            "[系统反馈] 用户直接向执行器注入了提示: {}",
+            content
+        ));
+        self.events.emit(DualLoopEvent::PromptInjected {
+            task_id: self.executor.task_id().map(|id| id.to_string()),
+            content: content.clone(),
+            source: "user".to_string(),
+        });
+        self.executor
+            .enqueue(ExecutorCommand::InjectPrompt { content });
+    }
+
+    /// Auto-learn: record a correction for the current task type (or the most
+    /// recently completed one) and schedule consolidation when enough pile up.
+    fn learn_from_correction(&mut self, content: &str, was_stopped: bool) {
+        let task_info = self
+            .todo_list
+            .current_running()
+            .or_else(|| self.todo_list.last_completed())
+            .map(|t| (t.task_type.clone(), t.id.clone(), t.description.clone()));
+
+        let Some((task_type, task_id, task_desc)) = task_info else {
+            return;
+        };
+        let context = Some(format!("任务: {} - {}", task_id, task_desc));
+        let mut record = CorrectionRecord::new(content, context);
+        if let Some(path) = self.save_correction_screen() {
+            record = record.with_screenshot(path);
+        }
+        self.prompt_memory.add_correction_record(&task_type, record);
+
+        // Check if we should consolidate corrections
+        let correction_count = self.prompt_memory.pending_corrections(&task_type);
+        if correction_count >= 3 {
+            println!(
+                "📚 [System] 检测到 {} 条纠偏记录，将自动整合到记忆中...",
+                correction_count
+            );
+            // Schedule consolidation (will be done async)
+            self.pending_consolidation_task_types.push(task_type);
+        }
+
+        // Save memory
+        self.persist_prompt_memory();
+
+        // If executor was stopped, re-mark the task as running
+        if was_stopped {
+            if let Some(task) = self.todo_list.get_mut(&task_id) {
+                task.start(); // Re-mark as running
+            }
+            self.notify_todo_changed();
+        }
+    }
+
+    /// Save the executor's latest screen for a correction, if enabled.
+    fn save_correction_screen(&self) -> Option<String> {
+        let dir = self.config.correction_screenshot_dir.as_ref()?;
+        let screenshot = self.executor.last_screenshot()?;
+        let jpeg = screenshot.to_jpeg(CORRECTION_SCREENSHOT_QUALITY)?;
+        match save_correction_screenshot(dir, &jpeg) {
+            Ok(path) => Some(path.display().to_string()),
+            Err(e) => {
+                tracing::warn!("Failed to save correction screenshot: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Turn propose-only mode on or off for the rest of the session.
+    pub fn set_propose_only(&mut self, enabled: bool) {
+        self.config.propose_only = enabled;
+        self.executor
+            .enqueue(ExecutorCommand::SetProposeOnly { enabled });
+        println!(
+            "🔐 [System] 只提议模式: {}",
+            if enabled {
+                "开启"
+            } else {
+                "关闭（完全自主）"
+            }
+        );
+    }
+
+    /// Take over reloaded settings for the rest of the session. Returns the
+    /// names of the settings that changed.
+    pub fn apply_live_settings(&mut self, settings: &LiveSettings) -> Vec<String> {
+        if let Some(threshold) = settings.stuck_threshold {
+            self.config.stuck_threshold = threshold;
+        }
+        if let Some(threshold) = settings.screen_similarity_threshold {
+            self.config.screen_similarity_threshold = threshold;
+        }
+        if let Some(lang) = &settings.lang {
+            self.config.lang = lang.clone();
+        }
+        self.executor.apply_live_settings(settings)
+    }
+
     /// Handle context overflow (too many parse errors).
     async fn handle_context_overflow(&mut self, parse_errors: u32) {
         println!(
@@ -730,12 +1955,18 @@ impl PlannerAgent {
                         tracing::error!("Task {} failed after max retries", task_id);
                     }
                 }
+                self.notify_todo_changed();
             }
 
             self.consecutive_stuck_count = 0;
         } else {
             // Generate correction prompt
             let correction = self.generate_correction_prompt().await;
+            self.events.emit(DualLoopEvent::PromptInjected {
+                task_id: self.executor.task_id().map(|id| id.to_string()),
+                content: correction.clone(),
+                source: "stuck_correction".to_string(),
+            });
             self.executor.enqueue(ExecutorCommand::InjectPrompt {
                 content: correction,
             });
@@ -746,6 +1977,12 @@ impl PlannerAgent {
     async fn handle_executor_completed(&mut self) {
         tracing::info!("Executor completed task");
 
+        // Values returned with finish(data=...) for later tasks to reference
+        let result_data = self
+            .executor_feedback_history
+            .back()
+            .and_then(|f| f.result_data.clone());
+
         // Mark current todo as done
         if let Some(task) = self.todo_list.current_running() {
             let task_id = task.id.clone();
@@ -753,15 +1990,46 @@ impl PlannerAgent {
 
             if let Some(task) = self.todo_list.get_mut(&task_id) {
                 task.complete();
+                if let Some(data) = result_data {
+                    println!("📦 [System] 任务 {} 返回数据: {}", task_id, data);
+                    task.data = Some(data);
+                }
             }
+            self.notify_todo_changed();
+
+            // A prompt that has lost confidence over time is refreshed with
+            // this run's log (checked before the success renews it)
+            let stale = self
+                .prompt_memory
+                .get(&task_type)
+                .is_some_and(|e| e.needs_reoptimization(self.config.prompt_success_half_life));
 
             // Record success in prompt memory
             self.prompt_memory.record_usage(&task_type, true);
+            let steps = std::mem::take(&mut self.trajectory);
+            if let Some(task) = self.todo_list.get(&task_id) {
+                let trajectory = Trajectory::new(task.description.clone(), steps);
+                if self.prompt_memory.add_trajectory(&task_type, trajectory) {
+                    tracing::debug!("Stored trajectory for task type {}", task_type);
+                }
+            }
 
-            // Save prompt memory
-            if let Some(path) = &self.config.prompt_memory_path {
-                let _ = self.prompt_memory.save(path);
+            if stale && self.config.auto_optimize_prompts {
+                if let Some(task) = self.todo_list.get(&task_id) {
+                    self.execution_log.push(format!(
+                        "[COMPLETED] {} - {} (stored prompt has not been confirmed for a long time)",
+                        task_id, task.description
+                    ));
+                }
+                println!(
+                    "♻️ [System] {} 的提示词置信度已衰减，重新优化...",
+                    task_type
+                );
+                self.optimize_prompt(&task_type).await;
             }
+
+            // Save prompt memory
+            self.persist_prompt_memory();
         }
 
         // Check if there are more tasks
@@ -771,6 +2039,39 @@ impl PlannerAgent {
         }
     }
 
+    /// Handle an aborted executor step.
+    /// The step is retried a few times; after that the task goes through the
+    /// normal failure path (retry from scratch, then re-plan or give up).
+    async fn handle_executor_timed_out(&mut self) {
+        if self.todo_list.current_running().is_none() {
+            return;
+        }
+        self.consecutive_timeout_count += 1;
+
+        if self.consecutive_timeout_count <= self.config.max_timeout_retries {
+            println!(
+                "⏱️ [System] 执行器步骤超时，重试 ({}/{})",
+                self.consecutive_timeout_count, self.config.max_timeout_retries
+            );
+            tracing::warn!(
+                "Executor step timed out, retrying ({}/{})",
+                self.consecutive_timeout_count,
+                self.config.max_timeout_retries
+            );
+            self.executor.enqueue(ExecutorCommand::Resume);
+            return;
+        }
+
+        self.consecutive_timeout_count = 0;
+        println!("⏱️ [System] 执行器连续超时，重置上下文并按失败处理");
+        self.executor.enqueue(ExecutorCommand::ResetContext);
+        self.handle_executor_failed(format!(
+            "Step timed out {} times in a row",
+            self.config.max_timeout_retries + 1
+        ))
+        .await;
+    }
+
     /// Handle executor failure.
     async fn handle_executor_failed(&mut self, reason: String) {
         tracing::error!("Executor failed: {}", reason);
@@ -790,6 +2091,7 @@ impl PlannerAgent {
                     self.start_task(&task_id);
                 } else {
                     task.fail(&reason);
+                    self.notify_todo_changed();
 
                     // Record failure in prompt memory
                     self.prompt_memory.record_usage(&task_type, false);
@@ -799,6 +2101,18 @@ impl PlannerAgent {
                         self.optimize_prompt(&task_type).await;
                     }
 
+                    // Decompose the failed task into smaller steps
+                    if self.config.auto_replan_failed {
+                        self.replan_failed_task(&task_id, &reason).await;
+                    }
+
+                    // Tasks that depended on it can no longer run
+                    let skipped = self.todo_list.skip_blocked();
+                    if !skipped.is_empty() {
+                        println!("⏭️ [System] 依赖的任务失败，已跳过: {}", skipped.join(", "));
+                        self.notify_todo_changed();
+                    }
+
                     // Move to next task
                     if let Some(next_task) = self.todo_list.next_pending() {
                         let next_id = next_task.id.clone();
@@ -809,24 +2123,292 @@ impl PlannerAgent {
         }
     }
 
+    /// Ask the planner model to break a failed task into smaller steps and
+    /// insert them right after it. Steps produced by re-planning are not
+    /// re-planned again, so a failure can only be decomposed once.
+    async fn replan_failed_task(&mut self, task_id: &str, reason: &str) {
+        let Some(task) = self.todo_list.get(task_id) else {
+            return;
+        };
+        if task.replanned_from.is_some() {
+            return;
+        }
+        let description = task.description.clone();
+        let task_type = task.task_type.clone();
+        let parent_id = task.parent_id.clone();
+
+        println!(
+            "🧩 [System] 任务 {} 多次失败，正在重新规划为更小的步骤...",
+            task_id
+        );
+
+        let request = if self.config.lang == "cn" {
+            format!(
+                "以下手机操作任务在多次重试后仍然失败。\n\
+                任务: {}\n类型: {}\n失败原因: {}\n\n\
+                请把它拆分成 2 到 {} 个更小、更具体、可以独立验证的步骤，\
+                每一步只做一件事（例如：打开某应用、进入某页面、点击某按钮）。\n\
+                只输出 JSON 数组，不要其他解释，格式:\n\
+                [{{\"description\": \"步骤描述\", \"task_type\": \"任务类型\"}}]",
+                description, task_type, reason, self.config.max_replan_steps
+            )
+        } else {
+            format!(
+                "The following phone task still failed after several retries.\n\
+                Task: {}\nType: {}\nFailure reason: {}\n\n\
+                Break it into 2 to {} smaller, concrete steps that can each be verified \
+                on their own (e.g. open an app, go to a page, tap a button).\n\
+                Only output a JSON array, no explanations, format:\n\
+                [{{\"description\": \"step description\", \"task_type\": \"task type\"}}]",
+                description, task_type, reason, self.config.max_replan_steps
+            )
+        };
+
+        let messages = vec![
+            MessageBuilder::create_system_message(
+                "You are a task decomposition assistant for phone automation.",
+            ),
+            MessageBuilder::create_user_message(&request, None),
+        ];
+
+        let steps = match self.model_client.request(&messages).await {
+            Ok(response) => {
+                self.track_planner_usage(&response.usage);
+                self.parse_replan_steps(&response.action, &task_type)
+            }
+            Err(e) => {
+                tracing::warn!("Re-planning failed for {}: {}", task_id, e);
+                Vec::new()
+            }
+        };
+
+        if steps.is_empty() {
+            println!("⚠️ [System] 未能重新规划任务 {}", task_id);
+            return;
+        }
+
+        let mut after_id = task_id.to_string();
+        let mut new_ids = Vec::new();
+        for (step_description, step_type) in steps {
+            let new_id = self
+                .todo_list
+                .insert_after(&after_id, &step_description, &step_type);
+            if let Some(item) = self.todo_list.get_mut(&new_id) {
+                item.replanned_from = Some(task_id.to_string());
+                item.parent_id = parent_id.clone();
+            }
+            println!("   ➕ {} - {}", new_id, step_description);
+            after_id = new_id.clone();
+            new_ids.push(new_id);
+        }
+
+        if let Some(task) = self.todo_list.get_mut(task_id) {
+            task.add_note(format!("Re-planned into: {}", new_ids.join(", ")));
+        }
+        // Tasks waiting for the failed one now wait for its last step
+        self.todo_list.replace_dependency(task_id, &after_id);
+        self.todo_list.reopen_parents(task_id);
+        self.notify_todo_changed();
+        tracing::info!("Re-planned {} into {:?}", task_id, new_ids);
+
+        let feedback = format!(
+            "[系统反馈] 任务 {} 多次失败，已自动拆分为更小的步骤: {}\n\
+            新步骤将自动依次执行。\n\n当前任务列表:\n{}",
+            task_id,
+            new_ids.join(", "),
+            self.build_todo_summary()
+        );
+        self.push_feedback(&feedback);
+    }
+
+    /// Parse re-planned steps from the model response.
+    /// Accepts a JSON array of `{description, task_type}` objects, falling
+    /// back to one step per non-empty (optionally numbered) line.
+    fn parse_replan_steps(&self, response: &str, default_type: &str) -> Vec<(String, String)> {
+        #[derive(Deserialize)]
+        struct ReplanStep {
+            description: String,
+            #[serde(default)]
+            task_type: Option<String>,
+        }
+
+        let json_steps = match (response.find('['), response.rfind(']')) {
+            (Some(start), Some(end)) if start < end => {
+                serde_json::from_str::<Vec<ReplanStep>>(&response[start..=end]).ok()
+            }
+            _ => None,
+        };
+
+        let steps: Vec<(String, String)> = match json_steps {
+            Some(steps) => steps
+                .into_iter()
+                .map(|s| {
+                    let task_type = s
+                        .task_type
+                        .filter(|t| !t.trim().is_empty())
+                        .map(|t| self.prompt_memory.resolve(&t))
+                        .unwrap_or_else(|| default_type.to_string());
+                    (s.description.trim().to_string(), task_type)
+                })
+                .collect(),
+            None => response
+                .lines()
+                .map(|line| {
+                    line.trim()
+                        .trim_start_matches(|c: char| {
+                            c.is_ascii_digit() || matches!(c, '.' | ')' | '-' | '*' | '、')
+                        })
+                        .trim()
+                        .to_string()
+                })
+                .map(|line| (line, default_type.to_string()))
+                .collect(),
+        };
+
+        steps
+            .into_iter()
+            .filter(|(description, _)| !description.is_empty())
+            .take(self.config.max_replan_steps)
+            .collect()
+    }
+
+    /// Start the earliest scheduled task whose time has come, if the
+    /// executor is not busy with another task.
+    /// Returns the ID of the started task.
+    pub fn start_due_scheduled_task(&mut self) -> Option<String> {
+        if !self.is_running || self.todo_list.current_running().is_some() {
+            return None;
+        }
+
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let (task_id, description) = self
+            .todo_list
+            .next_due_scheduled(now)
+            .map(|t| (t.id.clone(), t.description.clone()))?;
+        if self.awaiting_approval.contains(&task_id) {
+            return None;
+        }
+
+        println!("⏰ [System] 定时任务到期: {} - {}", task_id, description);
+        tracing::info!("Scheduled task due: {}", task_id);
+
+        let feedback = format!(
+            "[系统反馈] 定时任务 {} 已到执行时间，执行器已自动启动。",
+            task_id
+        );
+        self.push_feedback(&feedback);
+
+        self.start_task(&task_id);
+        Some(task_id)
+    }
+
+    /// Flag tasks that missed their deadline or exceeded their maximum
+    /// duration. The Planner is told on its next tick so it can fail, retry
+    /// or reprioritize them. Returns the IDs of the newly overdue tasks.
+    pub fn check_overdue_tasks(&mut self) -> Vec<String> {
+        if !self.is_running {
+            return Vec::new();
+        }
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let flagged = self.todo_list.mark_overdue(now);
+        if flagged.is_empty() {
+            return Vec::new();
+        }
+
+        for (task_id, reason) in &flagged {
+            println!("⏰ [System] 任务 {} 已超时: {}", task_id, reason);
+            tracing::warn!("Task {} is overdue: {}", task_id, reason);
+            self.events.emit(DualLoopEvent::TaskOverdue {
+                task_id: task_id.clone(),
+                reason: reason.clone(),
+            });
+        }
+        self.notify_todo_changed();
+        let ids = flagged.iter().map(|(id, _)| id.clone()).collect();
+        self.overdue_notices.extend(flagged);
+        ids
+    }
+
+    /// Tell the Planner about overdue tasks and let it react.
+    async fn process_overdue_notices(&mut self) {
+        if self.overdue_notices.is_empty() {
+            return;
+        }
+        let notices: Vec<String> = std::mem::take(&mut self.overdue_notices)
+            .into_iter()
+            .map(|(task_id, reason)| {
+                let description = self
+                    .todo_list
+                    .get(&task_id)
+                    .map(|t| t.description.clone())
+                    .unwrap_or_default();
+                format!("- {} ({}): {}", task_id, description, reason)
+            })
+            .collect();
+        let feedback = format!(
+            "[系统反馈] 以下任务已超时:\n{}\n\n\
+            请决定如何处理: 用 fail_todo 放弃、用 start_executor 重试，\
+            或用 reorder_todos 调整优先级，避免单个任务占用整个会话。\n\n\
+            当前任务列表:\n{}",
+            notices.join("\n"),
+            self.build_todo_summary()
+        );
+        self.push_feedback(&feedback);
+        self.continue_planner_conversation().await;
+    }
+
     /// Start executing a task.
     fn start_task(&mut self, task_id: &str) {
+        if self.needs_approval(task_id) {
+            self.request_approval(task_id);
+            return;
+        }
+
         if let Some(task) = self.todo_list.get_mut(task_id) {
             task.start();
 
+            let task_id = task.id.clone();
+            let raw_description = task.description.clone();
+            let task_type = task.task_type.clone();
+
             // Get system prompt from memory if available
             let system_prompt = self
                 .prompt_memory
-                .get_prompt(&task.task_type)
+                .get_prompt_for(&task_type, &self.config.prompt_scope)
+                .filter(|s| !s.is_empty())
                 .map(|s| s.to_string());
+            let overrides = self.executor_overrides_for(&task_type);
+            let example = self
+                .prompt_memory
+                .best_trajectory(&task_type)
+                .map(Trajectory::to_example);
 
+            // Fill in {task_N.data.key} references from earlier tasks
+            let description = self.todo_list.resolve_references(&raw_description);
+
+            self.trajectory.clear();
+            self.trajectory_step = 0;
+            self.counted_steps = 0;
             self.executor.enqueue(ExecutorCommand::StartTask {
-                task_id: task.id.clone(),
-                description: task.description.clone(),
+                task_id: task_id.clone(),
+                description: description.clone(),
                 system_prompt,
+                overrides,
+                example,
             });
 
-            tracing::info!("Started task: {} - {}", task.id, task.description);
+            tracing::info!("Started task: {} - {}", task_id, description);
+            self.notify_todo_changed();
+        }
+    }
+
+    /// Executor overrides for a task type: prompt memory, then planner config.
+    fn executor_overrides_for(&self, task_type: &str) -> Option<ExecutorOverrides> {
+        let from_memory = self.prompt_memory.get_executor_overrides(task_type);
+        let from_config = self.config.executor_overrides.get(task_type);
+        match (from_memory, from_config) {
+            (Some(memory), Some(config)) => Some(memory.merged(config)),
+            (memory, config) => memory.or(config).cloned(),
         }
     }
 
@@ -839,8 +2421,11 @@ impl PlannerAgent {
             .iter()
             .map(|f| {
                 format!(
-                    "Step {}: status={:?}, screen_changed={}",
-                    f.step_count, f.status, f.screen_changed
+                    "Step {}: status={:?}, screen_changed={}, similarity={}",
+                    f.step_count,
+                    f.status,
+                    f.screen_changed,
+                    format_similarity(f.screen_similarity)
                 )
             })
             .collect::<Vec<_>>()
@@ -903,12 +2488,11 @@ impl PlannerAgent {
         ];
 
         if let Ok(response) = self.model_client.request(&messages).await {
+            self.track_planner_usage(&response.usage);
             let optimized_prompt = response.action.trim().to_string();
             if !optimized_prompt.is_empty() {
-                self.prompt_memory.update(task_type, &optimized_prompt);
-                if let Some(path) = &self.config.prompt_memory_path {
-                    let _ = self.prompt_memory.save(path);
-                }
+                self.store_learned_prompt(task_type, &optimized_prompt);
+                self.persist_prompt_memory();
                 tracing::info!("Optimized prompt for task type: {}", task_type);
             }
         }
@@ -927,9 +2511,14 @@ impl PlannerAgent {
 
         let current_prompt = self
             .prompt_memory
-            .get_prompt(task_type)
+            .get_prompt_for(task_type, &self.config.prompt_scope)
             .unwrap_or("")
             .to_string();
+        let screenshot = self
+            .prompt_memory
+            .get(task_type)
+            .and_then(|e| e.latest_correction_screenshot())
+            .and_then(read_correction_screenshot);
 
         println!("📚 [System] 正在整合 {} 的用户纠偏记录...", task_type);
 
@@ -947,7 +2536,11 @@ impl PlannerAgent {
                     &current_prompt
                 },
                 corrections_summary
-            )
+            ) + if screenshot.is_some() {
+                "\n附图为最近一次纠偏时的手机屏幕，请结合屏幕内容给出具体的操作指引。"
+            } else {
+                ""
+            }
         } else {
             format!(
                 "Task type: {}\n\nCurrent system prompt:\n{}\n\nUser corrections:\n{}\n\n\
@@ -962,29 +2555,69 @@ impl PlannerAgent {
                     &current_prompt
                 },
                 corrections_summary
-            )
+            ) + if screenshot.is_some() {
+                "\nThe attached image is the phone screen at the latest correction; \
+                 ground the guidance in what it shows."
+            } else {
+                ""
+            }
+        };
+
+        // Let the optimizer see how costly this task type currently is
+        let stats = self.todo_list.stats();
+        let request = match stats.by_type.get(task_type) {
+            Some(cost) if self.config.lang == "cn" => format!(
+                "{}\n\n本次会话中该类型任务平均 {:.1} 步、{:.0} 秒完成，\
+                如果步骤偏多，请让提示词帮助执行器更直接地完成任务。",
+                request,
+                cost.avg_steps(),
+                cost.avg_run_secs()
+            ),
+            Some(cost) => format!(
+                "{}\n\nIn this session tasks of this type took {:.1} steps and {:.0}s on \
+                average; if that is a lot, make the prompt help the executor get there \
+                more directly.",
+                request,
+                cost.avg_steps(),
+                cost.avg_run_secs()
+            ),
+            None => request,
         };
 
         // Request optimization from planner model
-        let messages = vec![
-            MessageBuilder::create_system_message("You are a prompt optimization assistant. Generate concise, actionable system prompts."),
-            MessageBuilder::create_user_message(&request, None),
-        ];
+        let system = MessageBuilder::create_system_message(
+            "You are a prompt optimization assistant. Generate concise, actionable system prompts.",
+        );
+        let mut result = self
+            .model_client
+            .request(&[
+                system.clone(),
+                MessageBuilder::create_user_message(&request, screenshot.as_deref()),
+            ])
+            .await;
+        if result.is_err() && screenshot.is_some() {
+            // The planner model may not accept images
+            tracing::warn!("Consolidation with screenshot failed, retrying without it");
+            result = self
+                .model_client
+                .request(&[system, MessageBuilder::create_user_message(&request, None)])
+                .await;
+        }
 
-        if let Ok(response) = self.model_client.request(&messages).await {
+        if let Ok(response) = result {
+            self.track_planner_usage(&response.usage);
             let optimized_prompt = response.action.trim().to_string();
             if !optimized_prompt.is_empty() {
                 // Update the prompt
-                self.prompt_memory.update(task_type, &optimized_prompt);
+                self.store_learned_prompt(task_type, &optimized_prompt);
 
                 // Clear corrections after consolidation
                 if let Some(entry) = self.prompt_memory.get_mut(task_type) {
+                    remove_correction_screenshots(&entry.corrections);
                     entry.clear_corrections();
                 }
 
-                if let Some(path) = &self.config.prompt_memory_path {
-                    let _ = self.prompt_memory.save(path);
-                }
+                self.persist_prompt_memory();
 
                 println!("✅ [System] 已整合用户纠偏到记忆: {}", task_type);
                 // Safe truncation for display (handle UTF-8 properly)
@@ -998,6 +2631,10 @@ impl PlannerAgent {
                 };
                 println!("📝 新提示词: {}", display_prompt);
                 tracing::info!("Consolidated corrections for task type: {}", task_type);
+                self.events.emit(DualLoopEvent::PromptConsolidated {
+                    task_type: task_type.to_string(),
+                    prompt: optimized_prompt,
+                });
 
                 // Refresh context so Planner knows about updated task types
                 self.refresh_context_with_task_types();
@@ -1013,45 +2650,6 @@ impl PlannerAgent {
         }
     }
 
-    /// Get planner's decision based on current context.
-    /// Used by supervise_executor (no need to print response again).
-    async fn get_planner_decision(&mut self) -> Option<PlannerAction> {
-        // Add executor status summary to context
-        let status_summary = self.build_executor_status_summary();
-        let todo_summary = self.build_todo_summary();
-
-        let context_update = format!(
-            "[当前状态]\n{}\n\n[任务列表]\n{}\n\n请决定下一步操作。",
-            status_summary, todo_summary
-        );
-
-        // Add as a system message update
-        self.context
-            .push(MessageBuilder::create_user_message(&context_update, None));
-
-        // Call planner model
-        match self.model_client.request(&self.context).await {
-            Ok(response) => {
-                // Print response for debugging
-                println!("\n🧠 [Planner Supervision Response]:");
-                println!("{}", response.raw_content);
-                println!();
-
-                // Add assistant response to context
-                self.context
-                    .push(MessageBuilder::create_assistant_message(&response.action));
-
-                // Parse action from response
-                self.parse_planner_action(&response.action)
-            }
-            Err(e) => {
-                println!("❌ [Planner] Model error: {}", e);
-                tracing::error!("Planner model error: {}", e);
-                None
-            }
-        }
-    }
-
     /// Build executor status summary.
     fn build_executor_status_summary(&self) -> String {
         let status = self.executor.status();
@@ -1064,14 +2662,15 @@ impl PlannerAgent {
             summary.push_str("\n=== 执行器最近输出 ===\n");
             for (i, feedback) in self.executor_feedback_history.iter().enumerate() {
                 summary.push_str(&format!(
-                    "\n--- 第{}条反馈 (step={}, 屏幕变化={}) ---\n",
+                    "\n--- 第{}条反馈 (step={}, 屏幕变化={}, 屏幕相似度={}) ---\n",
                     i + 1,
                     feedback.step_count,
                     if feedback.screen_changed {
                         "是"
                     } else {
                         "否"
-                    }
+                    },
+                    format_similarity(feedback.screen_similarity)
                 ));
 
                 if let Some(ref result) = feedback.last_result {
@@ -1104,24 +2703,118 @@ impl PlannerAgent {
     }
 
     /// Build todo list summary.
+    ///
+    /// Lists longer than `COMPACT_TODO_SUMMARY_THRESHOLD` collapse finished
+    /// tasks (without data) into one line per task type.
     fn build_todo_summary(&self) -> String {
         let stats = self.todo_list.stats();
         let mut summary = format!(
-            "总任务: {} | 待执行: {} | 执行中: {} | 完成: {} | 失败: {}\n\n",
+            "总任务: {} | 待执行: {} | 执行中: {} | 完成: {} | 失败: {}\n",
             stats.total, stats.pending, stats.running, stats.done, stats.failed
         );
+        let tag_counts = self.todo_list.tag_counts();
+        if !tag_counts.is_empty() {
+            let tags: Vec<String> = tag_counts
+                .iter()
+                .map(|(tag, count)| format!("#{}({})", tag, count))
+                .collect();
+            summary.push_str(&format!("标签: {}\n", tags.join(" ")));
+        }
+        summary.push('\n');
+
+        let compact = stats.total > COMPACT_TODO_SUMMARY_THRESHOLD;
+        let collapsed = |item: &TodoItem| {
+            compact
+                && matches!(item.status, TodoStatus::Done | TodoStatus::Skipped)
+                && item.data.is_none()
+                && !self.todo_list.has_children(&item.id)
+        };
+        let mut collapsed_groups: Vec<(TodoStatus, String)> = Vec::new();
 
         for item in self.todo_list.items() {
-            let status_icon = match item.status {
-                TodoStatus::Pending => "⏳",
-                TodoStatus::Running => "🔄",
-                TodoStatus::Done => "✅",
-                TodoStatus::Failed => "❌",
-                TodoStatus::Skipped => "⏭️",
+            if collapsed(item) {
+                let group = (item.status.clone(), item.task_type.clone());
+                if !collapsed_groups.contains(&group) {
+                    collapsed_groups.push(group);
+                }
+                continue;
+            }
+            let status_icon = item.status.emoji();
+            let schedule = match item.scheduled_at {
+                Some(at) if item.status == TodoStatus::Pending => {
+                    format!(", 计划时间: {}", format_schedule_time(at))
+                }
+                _ => String::new(),
+            };
+            let data = item
+                .data
+                .as_ref()
+                .map(|d| format!(", 数据: {}", d))
+                .unwrap_or_default();
+            let depends_on = if item.depends_on.is_empty() {
+                String::new()
+            } else {
+                format!(", 依赖: {}", item.depends_on.join(", "))
+            };
+            let recurrence = item
+                .recurrence
+                .as_ref()
+                .map(|rule| format!(", 重复: {}", rule))
+                .unwrap_or_default();
+            let cost = if item.is_terminal() && item.step_count > 0 {
+                format!(", 耗时: {}秒/{}步", item.run_secs, item.step_count)
+            } else {
+                String::new()
+            };
+            let tags = if item.tags.is_empty() {
+                String::new()
+            } else {
+                format!(", 标签: {}", item.tags.join(", "))
+            };
+            let deadline = match item.deadline {
+                _ if item.overdue && !item.is_terminal() => ", ⏰已超时".to_string(),
+                Some(at) => format!(", 截止: {}", format_schedule_time(at)),
+                None => String::new(),
+            };
+            // Steps are indented under their umbrella task
+            let indent = "  ".repeat(self.todo_list.depth(&item.id));
+            summary.push_str(&format!(
+                "{}{} [{}] {} (类型: {}{}{}{}{}{}{}{})\n",
+                indent,
+                status_icon,
+                item.id,
+                item.description,
+                item.task_type,
+                schedule,
+                recurrence,
+                depends_on,
+                deadline,
+                tags,
+                cost,
+                data
+            ));
+        }
+
+        for (status, task_type) in collapsed_groups {
+            let ids: Vec<&str> = self
+                .todo_list
+                .by_status_and_type(status.clone(), &task_type)
+                .into_iter()
+                .filter(|item| collapsed(item))
+                .map(|item| item.id.as_str())
+                .collect();
+            let (icon, label) = if status == TodoStatus::Done {
+                ("✅", "已完成")
+            } else {
+                ("⏭️", "已跳过")
             };
             summary.push_str(&format!(
-                "{} [{}] {} (类型: {})\n",
-                status_icon, item.id, item.description, item.task_type
+                "{} {} {} 个 (类型: {}): {}\n",
+                icon,
+                label,
+                ids.len(),
+                task_type,
+                ids.join(", ")
             ));
         }
 
@@ -1173,11 +2866,6 @@ impl PlannerAgent {
         }
     }
 
-    /// Legacy single-action parser for backward compatibility.
-    fn parse_planner_action(&self, response: &str) -> Option<PlannerAction> {
-        self.parse_planner_actions(response).into_iter().next()
-    }
-
     /// Extract ALL complete JSON objects from text.
     /// Uses brace counting to find matching pairs.
     fn extract_all_json_objects(&self, text: &str) -> Vec<String> {
@@ -1230,11 +2918,6 @@ impl PlannerAgent {
                     }
                     _ => {}
                 }
-
-                // If we reach the end without closing, skip this '{'  
-                if i == chars.len() - 1 && brace_count > 0 {
-                    pos = start + 1;
-                }
             }
 
             // Safety: if we didn't find a complete object, move past current position
@@ -1244,7 +2927,9 @@ impl PlannerAgent {
         }
 
         results
-    }    /// Extract JSON from markdown code blocks or bare JSON.
+    }
+
+    /// Extract JSON from markdown code blocks or bare JSON.
     fn extract_json_from_response(&self, response: &str) -> Option<String> {
         // Pattern 1: ```json\n{...}\n```
         if let Some(start) = response.find("```json") {
@@ -1278,12 +2963,72 @@ impl PlannerAgent {
 
     /// Execute a planner action.
     async fn execute_planner_action(&mut self, action: PlannerAction) {
+        self.events.emit(DualLoopEvent::PlannerAction {
+            action: action.clone(),
+        });
+
         match action {
             PlannerAction::AddTodo {
                 description,
                 task_type,
+                depends_on,
+                parent_id,
+                deadline,
+                max_duration_secs,
+                tags,
             } => {
-                let task_id = self.todo_list.add(&description, &task_type);
+                let task_type = self.prompt_memory.resolve(&task_type);
+                let task_id = match &parent_id {
+                    Some(parent_id) => {
+                        match self
+                            .todo_list
+                            .add_subtask(parent_id, &description, &task_type)
+                        {
+                            Some(task_id) => task_id,
+                            None => {
+                                let feedback = format!(
+                                    "[系统反馈] 无法添加子任务：父任务 {} 不存在或已开始执行。",
+                                    parent_id
+                                );
+                                self.push_feedback(&feedback);
+                                return;
+                            }
+                        }
+                    }
+                    None => self.todo_list.add(&description, &task_type),
+                };
+                let deadline_note = match deadline
+                    .as_deref()
+                    .map(|at| parse_schedule_time(Some(at), None, chrono::Local::now()))
+                {
+                    Some(Ok(deadline)) => {
+                        if let Some(task) = self.todo_list.get_mut(&task_id) {
+                            task.deadline = Some(deadline);
+                        }
+                        format!("\n- 截止时间: {}", format_schedule_time(deadline))
+                    }
+                    Some(Err(e)) => format!("\n- 截止时间无效，已忽略: {}", e),
+                    None => String::new(),
+                };
+                if let Some(task) = self.todo_list.get_mut(&task_id) {
+                    task.max_duration_secs = max_duration_secs.filter(|&secs| secs > 0);
+                    task.set_tags(tags);
+                }
+                let dependency_note = if depends_on.is_empty() {
+                    String::new()
+                } else if self.todo_list.set_depends_on(&task_id, depends_on.clone()) {
+                    format!("\n- 依赖: {}", depends_on.join(", "))
+                } else {
+                    tracing::warn!(
+                        "Ignored invalid dependencies of {}: {:?}",
+                        task_id,
+                        depends_on
+                    );
+                    format!(
+                        "\n- 依赖 {} 无效（任务不存在或形成循环），已忽略",
+                        depends_on.join(", ")
+                    )
+                };
                 println!(
                     "✅ [System] 已添加任务: {} (ID: {}, 类型: {})",
                     description, task_id, task_type
@@ -1294,36 +3039,149 @@ impl PlannerAgent {
                     task_id,
                     task_type
                 );
+                self.notify_todo_changed();
 
                 // Add system feedback with clear next step instructions
                 let feedback = format!(
                     "[系统反馈] 任务已添加成功。\n\
                     - ID: {}\n\
                     - 描述: {}\n\
-                    - 类型: {}\n\n\
+                    - 类型: {}{}{}\n\n\
                     当前任务列表:\n{}\n\n\
                     请继续: 如果还有更多子任务，请继续使用 add_todo 添加。\
                     如果任务列表已完整，请使用 start_executor 启动执行第一个任务。",
                     task_id,
                     description,
                     task_type,
+                    dependency_note,
+                    deadline_note,
                     self.build_todo_summary()
                 );
-                self.context
-                    .push(MessageBuilder::create_user_message(&feedback, None));
+                self.push_feedback(&feedback);
+            }
+            PlannerAction::ScheduleTodo {
+                description,
+                task_type,
+                at,
+                delay_secs,
+                repeat,
+                tags,
+            } => {
+                let task_type = self.prompt_memory.resolve(&task_type);
+                let now = chrono::Local::now();
+                let schedule = match repeat.as_deref().map(Recurrence::parse) {
+                    Some(Err(e)) => Err(e),
+                    Some(Ok(recurrence)) if at.is_none() && delay_secs.is_none() => {
+                        recurrence.next_after(now)
+                    }
+                    _ => parse_schedule_time(at.as_deref(), delay_secs, now),
+                };
+                let feedback = match schedule {
+                    Ok(scheduled_at) => {
+                        let task_id =
+                            self.todo_list
+                                .add_scheduled(&description, &task_type, scheduled_at);
+                        if let Some(task) = self.todo_list.get_mut(&task_id) {
+                            task.recurrence = repeat.clone();
+                            task.set_tags(tags);
+                        }
+                        let repeat_note = repeat
+                            .as_ref()
+                            .map(|rule| format!("\n- 重复: {}", rule))
+                            .unwrap_or_default();
+                        let time_str = format_schedule_time(scheduled_at);
+                        println!(
+                            "⏰ [System] 已添加定时任务: {} (ID: {}, 类型: {}, 时间: {})",
+                            description, task_id, task_type, time_str
+                        );
+                        tracing::info!(
+                            "Scheduled todo: {} (id: {}, at: {})",
+                            description,
+                            task_id,
+                            scheduled_at
+                        );
+                        self.notify_todo_changed();
+
+                        format!(
+                            "[系统反馈] 定时任务已添加。\n\
+                            - ID: {}\n\
+                            - 描述: {}\n\
+                            - 计划时间: {}{}\n\n\
+                            到时间后系统会自动启动执行器，不需要对该任务使用 start_executor。",
+                            task_id, description, time_str, repeat_note
+                        )
+                    }
+                    Err(e) => {
+                        println!("❌ [System] 定时任务时间无效: {}", e);
+                        format!(
+                            "[系统反馈] 定时任务添加失败: {}。\n\
+                            at 支持 \"21:00\"、\"2025-01-01 21:00\" 或 RFC 3339 格式，也可以使用 delay_secs。\
+                            repeat 支持 \"daily 08:00\"、\"weekly mon,thu 08:00\"、\"every 2h\" 或 cron 表达式。",
+                            e
+                        )
+                    }
+                };
+                self.push_feedback(&feedback);
             }
             PlannerAction::StartExecutor { task_id } => {
+                // An umbrella task runs through its steps
+                let task_id = if self.todo_list.has_children(&task_id) {
+                    let step = self
+                        .todo_list
+                        .next_pending()
+                        .filter(|t| self.todo_list.is_descendant_of(&t.id, &task_id))
+                        .map(|t| t.id.clone());
+                    match step {
+                        Some(step) => {
+                            println!("🪜 [System] {} 是父任务，启动其子任务 {}", task_id, step);
+                            step
+                        }
+                        None => {
+                            let feedback = format!(
+                                "[系统反馈] 任务 {} 是父任务，当前没有可以执行的子任务。",
+                                task_id
+                            );
+                            self.push_feedback(&feedback);
+                            return;
+                        }
+                    }
+                } else {
+                    task_id
+                };
+                let unmet = self.todo_list.unmet_dependencies(&task_id);
+                if !unmet.is_empty() {
+                    println!(
+                        "⛓️ [System] 任务 {} 的依赖尚未完成: {}",
+                        task_id,
+                        unmet.join(", ")
+                    );
+                    let feedback = format!(
+                        "[系统反馈] 任务 {} 依赖的任务尚未完成: {}\n\
+                        请先启动这些任务，依赖完成后该任务会自动执行。",
+                        task_id,
+                        unmet.join(", ")
+                    );
+                    self.push_feedback(&feedback);
+                    return;
+                }
                 println!("🚀 [System] 启动执行器，任务ID: {}", task_id);
                 self.start_task(&task_id);
 
                 // Add system feedback
-                let feedback = format!(
-                    "[系统反馈] 执行器已启动，正在执行任务: {}\n\
-                    执行器将自动运行，完成后会自动执行下一个任务。",
-                    task_id
-                );
-                self.context
-                    .push(MessageBuilder::create_user_message(&feedback, None));
+                let feedback = if self.awaiting_approval.contains(&task_id) {
+                    format!(
+                        "[系统反馈] 任务 {} 需要用户批准后才能执行，已暂缓启动。\n\
+                        请等待用户批准，不要重复启动该任务。",
+                        task_id
+                    )
+                } else {
+                    format!(
+                        "[系统反馈] 执行器已启动，正在执行任务: {}\n\
+                        执行器将自动运行，完成后会自动执行下一个任务。",
+                        task_id
+                    )
+                };
+                self.push_feedback(&feedback);
             }
             PlannerAction::PauseExecutor => {
                 println!("⏸️ [System] 暂停执行器");
@@ -1351,44 +3209,13 @@ impl PlannerAgent {
                     println!("💉 [System] 注入提示词: {}", content);
                 }
 
-                // Auto-learn: record this correction for the current task type
-                // Look for the most recently active task if no running task
-                let task_info = self
-                    .todo_list
-                    .current_running()
-                    .or_else(|| self.todo_list.last_completed())
-                    .map(|t| (t.task_type.clone(), t.id.clone(), t.description.clone()));
-
-                if let Some((task_type, task_id, task_desc)) = task_info {
-                    let context = Some(format!("任务: {} - {}", task_id, task_desc));
-                    self.prompt_memory
-                        .add_correction(&task_type, &content, context);
-
-                    // Check if we should consolidate corrections
-                    let correction_count = self.prompt_memory.pending_corrections(&task_type);
-                    if correction_count >= 3 {
-                        println!(
-                            "📚 [System] 检测到 {} 条纠偏记录，将自动整合到记忆中...",
-                            correction_count
-                        );
-                        // Schedule consolidation (will be done async)
-                        self.pending_consolidation_task_types
-                            .push(task_type.clone());
-                    }
-
-                    // Save memory
-                    if let Some(path) = &self.config.prompt_memory_path {
-                        let _ = self.prompt_memory.save(path);
-                    }
-
-                    // If executor was stopped, re-mark the task as running
-                    if was_stopped {
-                        if let Some(task) = self.todo_list.get_mut(&task_id) {
-                            task.start(); // Re-mark as running
-                        }
-                    }
-                }
+                self.learn_from_correction(&content, was_stopped);
 
+                self.events.emit(DualLoopEvent::PromptInjected {
+                    task_id: self.executor.task_id().map(|id| id.to_string()),
+                    content: content.clone(),
+                    source: "planner".to_string(),
+                });
                 self.executor
                     .enqueue(ExecutorCommand::InjectPrompt { content });
             }
@@ -1400,17 +3227,140 @@ impl PlannerAgent {
                 if let Some(task) = self.todo_list.get_mut(&task_id) {
                     task.complete();
                     println!("✅ [System] 任务完成: {}", task_id);
+                    self.notify_todo_changed();
                 }
             }
             PlannerAction::FailTodo { task_id, reason } => {
                 if let Some(task) = self.todo_list.get_mut(&task_id) {
                     task.fail(&reason);
                     println!("❌ [System] 任务失败: {} - {}", task_id, reason);
+                    self.notify_todo_changed();
+                }
+            }
+            PlannerAction::UpdateTodo {
+                task_id,
+                description,
+                task_type,
+            } => {
+                let task_type = task_type.map(|t| self.prompt_memory.resolve(&t));
+                let feedback =
+                    if self
+                        .todo_list
+                        .update(&task_id, description.as_deref(), task_type.as_deref())
+                    {
+                        println!("✏️ [System] 已修改任务: {}", task_id);
+                        tracing::info!("Updated todo: {}", task_id);
+                        self.notify_todo_changed();
+                        format!("[系统反馈] 任务 {} 已修改。", task_id)
+                    } else {
+                        format!(
+                            "[系统反馈] 无法修改任务 {}：任务不存在或已开始执行。
+                        只能修改待执行的任务；正在执行的任务请先 remove_todo 再重新添加。",
+                            task_id
+                        )
+                    };
+                self.push_feedback(&feedback);
+            }
+            PlannerAction::RemoveTodo { task_id } => {
+                let running = self.todo_list.current_running().map(|t| t.id.clone());
+                let removed = self.todo_list.remove(&task_id).is_some();
+                // Removing an umbrella task also removes its running step
+                let was_running = running.is_some_and(|id| self.todo_list.get(&id).is_none());
+                let feedback = if removed {
+                    if was_running {
+                        self.executor.enqueue(ExecutorCommand::Stop);
+                    }
+                    self.awaiting_approval.retain(|id| id != &task_id);
+                    println!("🗑️ [System] 已删除任务: {}", task_id);
+                    tracing::info!("Removed todo: {} (was running: {})", task_id, was_running);
+                    self.notify_todo_changed();
+                    if was_running {
+                        format!(
+                            "[系统反馈] 任务 {} 已删除，执行器已停止。
+                            如需继续，请使用 start_executor 启动下一个任务。",
+                            task_id
+                        )
+                    } else {
+                        format!("[系统反馈] 任务 {} 已删除。", task_id)
+                    }
+                } else {
+                    format!("[系统反馈] 无法删除任务 {}：任务不存在。", task_id)
+                };
+                self.push_feedback(&feedback);
+            }
+            PlannerAction::ReorderTodos { task_ids } => {
+                let feedback = if self.todo_list.reorder_many(&task_ids) {
+                    println!("🔀 [System] 已调整任务顺序: {}", task_ids.join(" → "));
+                    tracing::info!("Reordered todos: {:?}", task_ids);
+                    self.notify_todo_changed();
+                    format!(
+                        "[系统反馈] 任务顺序已调整。
+
+当前任务列表:
+{}",
+                        self.build_todo_summary()
+                    )
+                } else {
+                    format!(
+                        "[系统反馈] 无法调整任务顺序：task_ids 中有不存在或重复的任务 ID ({})。",
+                        task_ids.join(", ")
+                    )
+                };
+                self.push_feedback(&feedback);
+            }
+            PlannerAction::ApproveProposal { steps } => {
+                if !self.approve_proposal(steps) {
+                    self.context.push(MessageBuilder::create_user_message(
+                        "[系统反馈] 当前没有等待审核的执行器提议。",
+                        None,
+                    ));
+                }
+            }
+            PlannerAction::RejectProposal { reason } => {
+                if !self.reject_proposal(reason) {
+                    self.context.push(MessageBuilder::create_user_message(
+                        "[系统反馈] 当前没有等待审核的执行器提议。",
+                        None,
+                    ));
                 }
             }
+            PlannerAction::QueryScreen { question } => {
+                println!("👀 [System] 查询当前屏幕状态...");
+                let described = self.executor.describe_screen(question.as_deref()).await;
+                let described = described.and_then(|(summary, usage)| {
+                    // The executor model answered: its tokens count either way
+                    let task_id = self.todo_list.current_running().map(|t| t.id.clone());
+                    self.spend.record_executor(task_id.as_deref(), &usage);
+                    if summary.is_empty() {
+                        Err("Empty screen summary".to_string())
+                    } else {
+                        Ok(summary)
+                    }
+                });
+                let feedback = match described {
+                    Ok(summary) => {
+                        println!("📱 [Screen] {}", summary);
+                        format!(
+                            "[系统反馈] 当前屏幕描述:\n{}\n\n\
+                            请根据屏幕状态继续规划（add_todo / start_executor 等）。",
+                            summary
+                        )
+                    }
+                    Err(e) => {
+                        println!("❌ [System] 查询屏幕失败: {}", e);
+                        tracing::warn!("query_screen failed: {}", e);
+                        format!(
+                            "[系统反馈] 查询屏幕失败: {}。请在没有屏幕信息的情况下继续规划。",
+                            e
+                        )
+                    }
+                };
+                self.push_feedback(&feedback);
+            }
             PlannerAction::Report { message } => {
                 println!("📢 [Planner] {}", message);
                 tracing::info!("Planner report: {}", message);
+                self.events.emit(DualLoopEvent::Report { message });
             }
             PlannerAction::Wait => {
                 println!("⏳ [Planner] 等待中...");
@@ -1418,12 +3368,15 @@ impl PlannerAgent {
             PlannerAction::Done { message } => {
                 println!("🎉 [Planner] 规划完成: {}", message);
                 tracing::info!("Planner done: {}", message);
+                self.events.emit(DualLoopEvent::Report { message });
             }
         }
     }
 
     /// Collect executor feedback with history limit.
     fn collect_executor_feedback(&mut self, feedback: ExecutorFeedback) {
+        self.record_trajectory_step(&feedback);
+        self.record_task_steps(&feedback);
         self.executor_feedback_history.push_back(feedback);
         self.pending_executor_feedback = true;
 
@@ -1433,6 +3386,88 @@ impl PlannerAgent {
         }
     }
 
+    /// Add the step reported by `feedback` to the running task's trajectory
+    /// if it succeeded. Feedback repeats the last step while the executor
+    /// is idle, so each step count is only taken once.
+    fn record_trajectory_step(&mut self, feedback: &ExecutorFeedback) {
+        let Some(result) = &feedback.last_result else {
+            return;
+        };
+        let Some(action) = result.action.clone().filter(|_| result.success) else {
+            return;
+        };
+        let running = self.todo_list.current_running().map(|t| t.id.as_str());
+        if feedback.task_id.is_none()
+            || feedback.task_id.as_deref() != running
+            || feedback.step_count == self.trajectory_step
+        {
+            return;
+        }
+        self.trajectory_step = feedback.step_count;
+        self.trajectory.push(TrajectoryStep {
+            app: result.app.clone(),
+            action,
+        });
+    }
+
+    /// Add the executor steps reported since the last feedback to the
+    /// running task.
+    fn record_task_steps(&mut self, feedback: &ExecutorFeedback) {
+        let Some(task_id) = feedback.task_id.as_deref() else {
+            return;
+        };
+        let Some(task) = self
+            .todo_list
+            .get_mut(task_id)
+            .filter(|t| t.status == TodoStatus::Running)
+        else {
+            return;
+        };
+        task.record_steps(feedback.step_count.saturating_sub(self.counted_steps));
+        self.counted_steps = self.counted_steps.max(feedback.step_count);
+    }
+
+    /// Record the user's requests since the last episode and the tasks
+    /// they produced into episodic memory.
+    fn record_episode(&mut self) {
+        if self.session_requests.is_empty() {
+            return;
+        }
+
+        let tasks: Vec<EpisodeTask> = self
+            .todo_list
+            .items()
+            .iter()
+            .filter(|t| !self.recorded_task_ids.contains(&t.id))
+            .map(EpisodeTask::from)
+            .collect();
+        if tasks.is_empty() {
+            return;
+        }
+
+        for task in self.todo_list.items() {
+            self.recorded_task_ids.insert(task.id.clone());
+        }
+        let episode = Episode::new(std::mem::take(&mut self.session_requests), tasks);
+        tracing::info!(
+            "Recorded episode: {}/{} tasks succeeded",
+            episode.succeeded(),
+            episode.tasks.len()
+        );
+        self.episodic_memory.record(episode);
+
+        if let Some(path) = &self.config.episodic_memory_path {
+            if let Err(e) = self.episodic_memory.save(path) {
+                tracing::warn!("Failed to save episodic memory: {}", e);
+            }
+        }
+    }
+
+    /// Get the episodic memory of past sessions.
+    pub fn episodic_memory(&self) -> &EpisodicMemory {
+        &self.episodic_memory
+    }
+
     /// Get the prompt memory.
     pub fn prompt_memory(&self) -> &PromptMemory {
         &self.prompt_memory
@@ -1444,7 +3479,9 @@ impl PlannerAgent {
     }
 
     /// Save prompt memory to disk.
-    pub fn save_prompt_memory(&self) -> Result<(), crate::agent::prompt_memory::PromptMemoryError> {
+    pub fn save_prompt_memory(
+        &mut self,
+    ) -> Result<(), crate::agent::prompt_memory::PromptMemoryError> {
         if let Some(path) = &self.config.prompt_memory_path {
             self.prompt_memory.save(path)
         } else {
@@ -1452,6 +3489,208 @@ impl PlannerAgent {
         }
     }
 
+    /// Store a learned prompt for the configured device/app scope. The first
+    /// prompt learned for a task type also becomes its global prompt, so
+    /// other devices get a starting point.
+    fn store_learned_prompt(&mut self, task_type: &str, prompt: &str) {
+        let scope = &self.config.prompt_scope;
+        let entry = self.prompt_memory.ensure_task_type(task_type);
+        if entry.system_prompt.is_empty() {
+            entry.update(prompt);
+        }
+        entry.update_scoped(scope, prompt);
+    }
+
+    /// Apply the size caps and save prompt memory (debounced).
+    fn persist_prompt_memory(&mut self) {
+        let evicted = self
+            .prompt_memory
+            .enforce_limits(&self.config.prompt_memory_limits);
+        if !evicted.is_empty() {
+            tracing::info!("Evicted least recently used task types: {:?}", evicted);
+        }
+        if let Some(path) = &self.config.prompt_memory_path {
+            let _ = self.prompt_memory.save_debounced(path);
+        }
+    }
+
+    /// Write prompt memory changes held back by debounced saves.
+    pub fn flush_prompt_memory(
+        &mut self,
+    ) -> Result<(), crate::agent::prompt_memory::PromptMemoryError> {
+        if let Some(path) = &self.config.prompt_memory_path {
+            self.prompt_memory.flush(path)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sync prompt memory with the shared remote memory, if configured:
+    /// pull and merge newer entries, then push what the remote is missing.
+    pub async fn sync_prompt_memory(&mut self) -> Result<(), MemorySyncError> {
+        let Some(sync) = self.memory_sync.clone() else {
+            return Ok(());
+        };
+        self.last_memory_sync = Some(Instant::now());
+        let report = sync.sync(&mut self.prompt_memory).await?;
+        tracing::info!(
+            "Synced prompt memory with {}: {} pulled, pushed: {}",
+            sync.url(),
+            report.pulled,
+            report.pushed
+        );
+        if report.pulled > 0 {
+            println!(
+                "🔄 [System] 已从远程记忆同步 {} 个任务类型的提示词",
+                report.pulled
+            );
+            self.persist_prompt_memory();
+            self.refresh_context_with_task_types();
+        }
+        Ok(())
+    }
+
+    /// Sync prompt memory with the remote when the sync interval has passed
+    /// (immediately on the first call). Failures are logged.
+    pub async fn sync_prompt_memory_if_due(&mut self) {
+        let interval = Duration::from_secs(self.config.memory_sync_interval_secs);
+        if self
+            .last_memory_sync
+            .is_some_and(|last| last.elapsed() < interval)
+        {
+            return;
+        }
+        if let Err(e) = self.sync_prompt_memory().await {
+            tracing::warn!("Failed to sync prompt memory: {}", e);
+        }
+    }
+
+    /// Receiver for end-of-session summaries (`None` until the first one).
+    pub fn session_summary_receiver(&self) -> watch::Receiver<Option<String>> {
+        self.summary_tx.subscribe()
+    }
+
+    /// Latest end-of-session summary.
+    pub fn session_summary(&self) -> Option<String> {
+        self.summary_tx.borrow().clone()
+    }
+
+    /// Tasks finished since the last recorded episode (all tasks after a resume).
+    fn session_tasks(&self) -> Vec<&TodoItem> {
+        let tasks: Vec<_> = self
+            .todo_list
+            .items()
+            .iter()
+            .filter(|t| !self.recorded_task_ids.contains(&t.id))
+            .collect();
+        if tasks.is_empty() {
+            self.todo_list.items().iter().collect()
+        } else {
+            tasks
+        }
+    }
+
+    /// Plain outcome report of the session's tasks: what succeeded, what
+    /// failed and why, and the data extracted along the way.
+    fn build_session_report(&self) -> String {
+        let cn = self.config.lang == "cn";
+        let tasks = self.session_tasks();
+        let done = tasks
+            .iter()
+            .filter(|t| t.status == TodoStatus::Done)
+            .count();
+        let mut report = if cn {
+            format!("完成 {}/{} 个任务\n", done, tasks.len())
+        } else {
+            format!("{}/{} tasks done\n", done, tasks.len())
+        };
+
+        for task in tasks {
+            let line = match task.status {
+                TodoStatus::Done => format!("✅ {}", task.description),
+                TodoStatus::Failed => {
+                    let reason = task.error.as_deref().unwrap_or(if cn {
+                        "未知原因"
+                    } else {
+                        "unknown reason"
+                    });
+                    if cn {
+                        format!("❌ {} (失败原因: {})", task.description, reason)
+                    } else {
+                        format!("❌ {} (failed: {})", task.description, reason)
+                    }
+                }
+                TodoStatus::Skipped => format!("⏭️ {}", task.description),
+                TodoStatus::Pending | TodoStatus::Running => format!("⏳ {}", task.description),
+            };
+            report.push_str(&line);
+            if let Some(data) = &task.data {
+                report.push_str(&if cn {
+                    format!(" 数据: {}", data)
+                } else {
+                    format!(" data: {}", data)
+                });
+            }
+            report.push('\n');
+        }
+        report.trim_end().to_string()
+    }
+
+    /// Ask the planner model to turn the outcome report into a short answer
+    /// for the user. Falls back to the plain report if the model fails.
+    async fn generate_session_summary(&mut self) -> String {
+        let report = self.build_session_report();
+        let requests = self.session_requests.join("\n");
+
+        let request = if self.config.lang == "cn" {
+            format!(
+                "用户请求:\n{}\n\n任务结果:\n{}\n\n\
+                请为用户写一段简洁的最终总结：完成了什么、哪些失败以及原因、\
+                提取到的数据（原样列出）。只输出总结内容。",
+                requests, report
+            )
+        } else {
+            format!(
+                "User requests:\n{}\n\nTask results:\n{}\n\n\
+                Write a concise final summary for the user: what was accomplished, \
+                what failed and why, and any extracted data (listed verbatim). \
+                Only output the summary.",
+                requests, report
+            )
+        };
+        let messages = vec![
+            MessageBuilder::create_system_message("You are a task reporting assistant."),
+            MessageBuilder::create_user_message(&request, None),
+        ];
+
+        match self.model_client.request(&messages).await {
+            Ok(response) => {
+                self.track_planner_usage(&response.usage);
+                let summary = response.action.trim();
+                if summary.is_empty() {
+                    report
+                } else {
+                    summary.to_string()
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to generate session summary: {}", e);
+                report
+            }
+        }
+    }
+
+    /// Generate the end-of-session summary, print it and publish it.
+    async fn deliver_session_summary(&mut self) {
+        let summary = self.generate_session_summary().await;
+        println!("📋 [Planner] 会话总结:\n{}", summary);
+        self.events.emit(DualLoopEvent::SessionSummary {
+            summary: summary.clone(),
+            stats: self.todo_list.stats(),
+        });
+        self.summary_tx.send_replace(Some(summary));
+    }
+
     /// Get executor feedback history.
     pub fn feedback_history(&self) -> &VecDeque<ExecutorFeedback> {
         &self.executor_feedback_history
@@ -1487,6 +3726,565 @@ mod tests {
         assert_eq!(config.lang, "en");
     }
 
+    #[test]
+    fn test_record_episode_after_tasks_finish() {
+        let config = PlannerConfig {
+            episodic_memory_path: None,
+            ..PlannerConfig::default()
+        };
+        let mut planner = PlannerAgent::new(config, ModelConfig::default(), AgentConfig::default());
+        planner.episodic_memory = EpisodicMemory::new();
+
+        planner.session_requests.push("早上例行任务".to_string());
+        let first = planner.todo_list.add("查看天气", "天气查询");
+        planner.todo_list.get_mut(&first).unwrap().complete();
+        planner.record_episode();
+        assert_eq!(planner.episodic_memory().len(), 1);
+        assert!(planner.session_requests.is_empty());
+
+        // Tasks from earlier episodes are not recorded again
+        planner.session_requests.push("播放音乐".to_string());
+        let second = planner.todo_list.add("播放日推", "音乐播放");
+        planner.todo_list.get_mut(&second).unwrap().complete();
+        planner.record_episode();
+        assert_eq!(planner.episodic_memory().len(), 2);
+        assert_eq!(planner.episodic_memory().episodes[1].tasks.len(), 1);
+
+        let prompt = planner.build_system_prompt();
+        assert!(prompt.contains("最近的历史会话"));
+        assert!(prompt.contains("早上例行任务"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_task_data_is_stored() {
+        let config = PlannerConfig {
+            prompt_memory_path: None,
+            episodic_memory_path: None,
+            auto_optimize_prompts: false,
+            ..PlannerConfig::default()
+        };
+        let mut planner = PlannerAgent::new(config, ModelConfig::default(), AgentConfig::default());
+        let first = planner.todo_list.add("查询商品价格", "购物");
+        planner
+            .todo_list
+            .add("在备忘录记下 {task_1.data.price}", "备忘录");
+        planner.start_task(&first);
+
+        planner
+            .executor_feedback_history
+            .push_back(ExecutorFeedback {
+                task_id: Some(first.clone()),
+                step_count: 3,
+                status: ExecutorStatus::Completed,
+                last_result: None,
+                screen_changed: false,
+                timestamp: 0,
+                context_overflow_detected: false,
+                consecutive_parse_errors: 0,
+                screen_similarity: None,
+                result_data: Some(serde_json::json!({"price": "99元"})),
+                proposed_action: None,
+            });
+        planner.handle_executor_completed().await;
+
+        let task = planner.todo_list.get(&first).unwrap();
+        assert_eq!(task.status, TodoStatus::Done);
+        assert_eq!(task.data.as_ref().unwrap()["price"], "99元");
+        assert!(planner
+            .build_todo_summary()
+            .contains("数据: {\"price\":\"99元\"}"));
+        assert_eq!(
+            planner
+                .todo_list
+                .resolve_references("在备忘录记下 {task_1.data.price}"),
+            "在备忘录记下 99元"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_summary_after_all_tasks_finish() {
+        // Unreachable model: the summary falls back to the plain report
+        let config = PlannerConfig {
+            prompt_memory_path: None,
+            episodic_memory_path: None,
+            auto_optimize_prompts: false,
+            model_config: ModelConfig::default()
+                .with_base_url("http://127.0.0.1:9/v1")
+                .with_max_retries(0),
+            ..PlannerConfig::default()
+        };
+        let mut planner = PlannerAgent::new(config, ModelConfig::default(), AgentConfig::default());
+        planner.start();
+        let summary = planner.session_summary_receiver();
+        let price = planner.todo_list.add("查询商品价格", "购物");
+        let order = planner.todo_list.add("下单", "购物");
+        planner.todo_list.get_mut(&price).unwrap().complete();
+        planner.todo_list.get_mut(&price).unwrap().data =
+            Some(serde_json::json!({"price": "99元"}));
+        let failed = planner.todo_list.get_mut(&order).unwrap();
+        failed.max_retries = 0;
+        failed.fail("余额不足");
+        planner.notify_todo_changed();
+
+        planner.tick_planner().await;
+        let text = summary.borrow().clone().expect("no summary");
+        assert!(text.contains("完成 1/2 个任务"));
+        assert!(text.contains("❌ 下单 (失败原因: 余额不足)"));
+        assert!(text.contains("\"price\":\"99元\""));
+
+        // Delivered once per finished todo list
+        assert!(!planner.summary_pending);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_retries_then_fails_task() {
+        let config = PlannerConfig {
+            prompt_memory_path: None,
+            episodic_memory_path: None,
+            auto_optimize_prompts: false,
+            auto_replan_failed: false,
+            max_timeout_retries: 1,
+            ..PlannerConfig::default()
+        };
+        let mut planner = PlannerAgent::new(config, ModelConfig::default(), AgentConfig::default());
+        let id = planner.todo_list.add("打开设置", "设置调整");
+        planner.start_task(&id);
+
+        // First timeout: the step is retried
+        planner.handle_executor_timed_out().await;
+        assert_eq!(planner.consecutive_timeout_count, 1);
+        assert_eq!(planner.todo_list.get(&id).unwrap().retry_count, 0);
+
+        // Second timeout: treated as a task failure (which retries the task)
+        planner.handle_executor_timed_out().await;
+        assert_eq!(planner.consecutive_timeout_count, 0);
+        let task = planner.todo_list.get(&id).unwrap();
+        assert_eq!(task.retry_count, 1);
+        assert_eq!(task.status, TodoStatus::Running);
+    }
+
+    #[test]
+    fn test_parse_query_screen_action() {
+        let planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+
+        let actions = planner.parse_planner_actions(r#"{"action": "query_screen"}"#);
+        assert!(matches!(
+            actions[0],
+            PlannerAction::QueryScreen { question: None }
+        ));
+
+        let actions = planner
+            .parse_planner_actions(r#"{"action": "query_screen", "question": "是否已登录？"}"#);
+        match &actions[0] {
+            PlannerAction::QueryScreen { question } => {
+                assert_eq!(question.as_deref(), Some("是否已登录？"))
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+        assert!(planner.should_continue_after_action(&actions[0]));
+    }
+
+    #[test]
+    fn test_parse_replan_steps() {
+        let planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+
+        let response = r#"好的：
+[{"description": "打开微信", "task_type": "微信操作"}, {"description": "进入通讯录"}]"#;
+        let steps = planner.parse_replan_steps(response, "通用任务");
+        assert_eq!(
+            steps,
+            vec![
+                ("打开微信".to_string(), "微信操作".to_string()),
+                ("进入通讯录".to_string(), "通用任务".to_string()),
+            ]
+        );
+
+        let steps = planner.parse_replan_steps("1. 打开设置\n2) 点击 WLAN\n\n", "设置调整");
+        assert_eq!(
+            steps,
+            vec![
+                ("打开设置".to_string(), "设置调整".to_string()),
+                ("点击 WLAN".to_string(), "设置调整".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_schedule_todo_action() {
+        let planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+
+        let actions = planner.parse_planner_actions(
+            r#"{"action": "schedule_todo", "description": "发布朋友圈", "task_type": "微信操作", "at": "21:00"}"#,
+        );
+        match &actions[0] {
+            PlannerAction::ScheduleTodo { at, delay_secs, .. } => {
+                assert_eq!(at.as_deref(), Some("21:00"));
+                assert!(delay_secs.is_none());
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_planner_profiles() {
+        let fast = PlannerProfile::builtin("fast", "cn").unwrap();
+        let config = PlannerConfig::default()
+            .with_stuck_threshold(3)
+            .with_profile(&fast);
+        assert_eq!(config.stuck_threshold, 5);
+        assert!(!config.auto_replan_failed);
+        // Fields the profile does not set keep their value
+        assert_eq!(config.max_replan_steps, 5);
+        assert_eq!(config.profile.as_deref(), Some("fast"));
+        assert!(config.get_system_prompt().contains("## 监督风格"));
+
+        let custom = vec![PlannerProfile {
+            name: "fast".to_string(),
+            model_name: Some("deepseek-reasoner".to_string()),
+            ..PlannerProfile::default()
+        }];
+        let found = PlannerProfile::find("fast", &custom, "en").unwrap();
+        assert_eq!(found.model_name.as_deref(), Some("deepseek-reasoner"));
+        assert!(found.stuck_threshold.is_none());
+        assert!(PlannerProfile::find("verbose", &custom, "en").is_some());
+        assert!(PlannerProfile::find("unknown", &custom, "en").is_none());
+        assert_eq!(PlannerProfile::available(&custom).len(), 3);
+    }
+
+    #[test]
+    fn test_builtin_profile_styles_are_single_spaced() {
+        for name in BUILTIN_PLANNER_PROFILES {
+            for lang in ["cn", "en"] {
+                let profile = PlannerProfile::builtin(name, lang).unwrap();
+                let style = profile.style_prompt.unwrap();
+                assert!(!style.contains("  "), "{} ({}): {:?}", name, lang, style);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_reviews_proposal_once() {
+        let config = PlannerConfig {
+            prompt_memory_path: None,
+            episodic_memory_path: None,
+            ..PlannerConfig::default()
+        }
+        .with_propose_only(true, true);
+        let mut planner = PlannerAgent::new(config, ModelConfig::default(), AgentConfig::default());
+        let mut rx = planner.subscribe();
+        assert!(!planner.approve_proposal(1));
+
+        let feedback = ExecutorFeedback {
+            task_id: Some("task_1".to_string()),
+            step_count: 1,
+            status: ExecutorStatus::AwaitingApproval,
+            last_result: None,
+            screen_changed: false,
+            timestamp: 0,
+            context_overflow_detected: false,
+            consecutive_parse_errors: 0,
+            screen_similarity: None,
+            result_data: None,
+            proposed_action: Some(serde_json::json!({"_metadata": "do", "action": "Tap"})),
+        };
+        planner.review_proposal(&feedback).await;
+        planner.review_proposal(&feedback).await;
+
+        let mut pending = 0;
+        while let Ok(event) = rx.try_recv() {
+            if let DualLoopEvent::ProposalPending { step, .. } = event {
+                assert_eq!(step, 1);
+                pending += 1;
+            }
+        }
+        assert_eq!(pending, 1);
+
+        planner
+            .executor
+            .set_status(ExecutorStatus::AwaitingApproval);
+        assert!(planner.approve_proposal(2));
+        assert!(planner.executor.has_pending_commands());
+    }
+
+    #[tokio::test]
+    async fn test_revise_plan_actions() {
+        let config = PlannerConfig {
+            prompt_memory_path: None,
+            episodic_memory_path: None,
+            ..PlannerConfig::default()
+        };
+        let mut planner = PlannerAgent::new(config, ModelConfig::default(), AgentConfig::default());
+        let actions = planner.parse_planner_actions(
+            r#"{"action": "update_todo", "task_id": "task_2", "description": "给李四发消息"}
+{"action": "reorder_todos", "task_ids": ["task_3", "task_2"]}
+{"action": "remove_todo", "task_id": "task_1"}"#,
+        );
+        assert_eq!(actions.len(), 3);
+
+        planner.todo_list.add("打开微信", "微信操作");
+        planner.todo_list.add("给张三发消息", "微信操作");
+        planner.todo_list.add("截图保存", "系统操作");
+        for action in actions {
+            planner.execute_planner_action(action).await;
+        }
+
+        let items = planner.todo_list.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "task_3");
+        assert_eq!(items[1].description, "给李四发消息");
+    }
+
+    #[test]
+    fn test_start_due_scheduled_task() {
+        let mut planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        planner.start();
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let future_id = planner
+            .todo_list
+            .add_scheduled("晚点执行", "通用任务", now + 3600);
+        assert!(planner.start_due_scheduled_task().is_none());
+
+        let due_id = planner
+            .todo_list
+            .add_scheduled("马上执行", "通用任务", now - 1);
+        assert_eq!(planner.start_due_scheduled_task(), Some(due_id.clone()));
+        assert_eq!(
+            planner.todo_list.get(&due_id).unwrap().status,
+            TodoStatus::Running
+        );
+        assert_eq!(
+            planner.todo_list.get(&future_id).unwrap().status,
+            TodoStatus::Pending
+        );
+
+        // Busy executor: nothing else starts
+        assert!(planner.start_due_scheduled_task().is_none());
+    }
+
+    #[test]
+    fn test_todo_list_persisted_and_resumed() {
+        let path = std::env::temp_dir().join("test_planner_todo_list.json");
+        let _ = std::fs::remove_file(&path);
+        let config = PlannerConfig::default().with_todo_list_path(path.display().to_string());
+
+        // Every change is written through
+        let mut planner = PlannerAgent::new(
+            config.clone(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let first = planner.todo_list.add("打开微信", "微信操作");
+        let second = planner.todo_list.add("发送消息", "微信操作");
+        planner.start_task(&first);
+        planner.todo_list.get_mut(&first).unwrap().retry();
+        planner.start_task(&first);
+        let saved = TodoList::load(&path).unwrap();
+        assert_eq!(saved.get(&first).unwrap().status, TodoStatus::Running);
+        assert_eq!(saved.get(&first).unwrap().retry_count, 1);
+
+        // A new planner picks up where the interrupted one stopped
+        let planner = PlannerAgent::new(
+            config.clone(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        assert_eq!(planner.todo_list().items().len(), 2);
+        assert_eq!(planner.todo_list().current_running().unwrap().id, first);
+        assert_eq!(planner.todo_list().get(&first).unwrap().retry_count, 1);
+        assert_eq!(
+            planner.todo_list().get(&second).unwrap().status,
+            TodoStatus::Pending
+        );
+
+        // A finished list is not resumed
+        let mut done = saved.clone();
+        done.get_mut(&first).unwrap().complete();
+        done.get_mut(&second).unwrap().complete();
+        done.save(&path).unwrap();
+        let planner = PlannerAgent::new(config, ModelConfig::default(), AgentConfig::default());
+        assert!(planner.todo_list().items().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_compact_todo_summary() {
+        let mut planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        for i in 0..COMPACT_TODO_SUMMARY_THRESHOLD {
+            let id = planner.todo_list.add(format!("签到 {}", i), "签到");
+            planner.todo_list.get_mut(&id).unwrap().complete();
+        }
+        let pending = planner.todo_list.add("比较牛奶价格", "购物");
+        planner
+            .todo_list
+            .get_mut(&pending)
+            .unwrap()
+            .set_tags(vec!["比价".to_string()]);
+
+        let summary = planner.build_todo_summary();
+        assert!(summary.contains("标签: #比价(1)"));
+        assert!(summary.contains("[task_21] 比较牛奶价格 (类型: 购物, 标签: 比价)"));
+        assert!(summary.contains("✅ 已完成 20 个 (类型: 签到): task_1, task_2"));
+        assert!(!summary.contains("[task_1]"));
+    }
+
+    #[test]
+    fn test_check_overdue_tasks() {
+        let mut planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let mut events = planner.subscribe();
+        let task_id = planner.todo_list.add("签到", "签到");
+        planner.todo_list.get_mut(&task_id).unwrap().deadline = Some(1);
+
+        // Nothing is flagged while the planner is stopped
+        assert!(planner.check_overdue_tasks().is_empty());
+
+        planner.start();
+        assert_eq!(planner.check_overdue_tasks(), vec![task_id.clone()]);
+        assert_eq!(planner.overdue_notices.len(), 1);
+        assert!(planner.build_todo_summary().contains("已超时"));
+        assert!(std::iter::from_fn(|| events.try_recv().ok())
+            .any(|e| matches!(e, DualLoopEvent::TaskOverdue { task_id: id, .. } if id == task_id)));
+
+        // A task is only reported once
+        assert!(planner.check_overdue_tasks().is_empty());
+    }
+
+    #[test]
+    fn test_system_feedback_is_published() {
+        let mut planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        let mut events = planner.subscribe();
+        let task_id = planner.todo_list.add("签到", "签到");
+
+        assert!(planner.edit_todo(&task_id, "每日签到"));
+        let feedback: Vec<String> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|e| match e {
+                DualLoopEvent::SystemFeedback { content } => Some(content),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(feedback.len(), 1);
+        assert!(feedback[0].contains("每日签到"));
+    }
+
+    #[test]
+    fn test_approval_gate() {
+        let mut planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        planner.set_approval_patterns(vec!["删除".to_string(), "Payment".to_string()]);
+
+        let safe_id = planner.todo_list.add("打开相册", "通用任务");
+        let risky_id = planner.todo_list.add("删除所有照片", "文件管理");
+        let paid_id = planner.todo_list.add("Buy coffee", "payment");
+
+        planner.start_task(&safe_id);
+        assert_eq!(
+            planner.todo_list.get(&safe_id).unwrap().status,
+            TodoStatus::Running
+        );
+
+        planner.start_task(&risky_id);
+        planner.start_task(&paid_id);
+        assert_eq!(
+            planner.todo_list.get(&risky_id).unwrap().status,
+            TodoStatus::Pending
+        );
+        assert_eq!(
+            planner.pending_approvals(),
+            &[risky_id.clone(), paid_id.clone()]
+        );
+
+        assert!(planner.approve_task(&risky_id));
+        assert_eq!(
+            planner.todo_list.get(&risky_id).unwrap().status,
+            TodoStatus::Running
+        );
+        assert!(!planner.approve_task(&risky_id));
+
+        assert!(planner.reject_task(&paid_id));
+        assert_eq!(
+            planner.todo_list.get(&paid_id).unwrap().status,
+            TodoStatus::Skipped
+        );
+        assert!(planner.pending_approvals().is_empty());
+    }
+
+    #[test]
+    fn test_approval_patterns_match_words() {
+        let mut planner = PlannerAgent::new(
+            PlannerConfig::default(),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        planner.set_approval_patterns(
+            crate::agent::DEFAULT_APPROVAL_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        );
+        let needs_approval = |planner: &mut PlannerAgent, description: &str| {
+            let id = planner.todo_list.add(description, "general");
+            planner.needs_approval(&id)
+        };
+
+        assert!(needs_approval(&mut planner, "Pay the electricity bill"));
+        assert!(needs_approval(&mut planner, "Make payments"));
+        assert!(needs_approval(&mut planner, "Transfer 500 to Bob"));
+        assert!(needs_approval(&mut planner, "Buy a new phone case"));
+        assert!(needs_approval(&mut planner, "Delete old photos"));
+        assert!(needs_approval(&mut planner, "Deleting old photos"));
+        assert!(needs_approval(&mut planner, "Remove the contact"));
+        assert!(needs_approval(&mut planner, "Post the photo"));
+        assert!(needs_approval(&mut planner, "在淘宝购买耳机"));
+        // No false positives on words that merely end in a pattern
+        assert!(!needs_approval(&mut planner, "Open the repay screen"));
+        assert!(!needs_approval(&mut planner, "Find a compost bin nearby"));
+        assert!(!needs_approval(&mut planner, "Check the repayment plan"));
+        assert!(!needs_approval(&mut planner, "Check the weather"));
+    }
+
+    #[test]
+    fn test_contains_term() {
+        assert!(contains_term("pay now", "pay"));
+        assert!(contains_term("(pay)", "pay"));
+        assert!(contains_term("payday", "pay"));
+        assert!(!contains_term("repay", "pay"));
+        assert!(!contains_term("repayment", "pay"));
+        assert!(contains_term("去支付宝", "支付"));
+        assert!(contains_term("repay, then pay", "pay"));
+    }
+
     #[test]
     fn test_parse_planner_action_json() {
         let planner_config = PlannerConfig::default();
@@ -1496,7 +4294,7 @@ mod tests {
             PlannerAgent::new(planner_config, executor_model_config, executor_agent_config);
 
         let json = r#"{"action": "add_todo", "description": "Test task", "task_type": "general"}"#;
-        let action = planner.parse_planner_action(json);
+        let action = planner.parse_planner_actions(json).into_iter().next();
 
         assert!(matches!(action, Some(PlannerAction::AddTodo { .. })));
     }
@@ -1598,6 +4396,10 @@ Third: {"key": "value3"}
         assert_eq!(jsons[0], r#"{"key": "value1"}"#);
         assert!(jsons[1].contains("nested"));
         assert_eq!(jsons[2], r#"{"key": "value3"}"#);
+
+        // An unclosed '{' is skipped and scanning resumes right after it
+        let jsons = planner.extract_all_json_objects(r#"{ oops {"key": "value"} {"#);
+        assert_eq!(jsons, vec![r#"{"key": "value"}"#.to_string()]);
     }
 
     #[test]
@@ -1652,20 +4454,31 @@ Third: {"key": "value3"}
             PlannerAgent::new(planner_config, executor_model_config, executor_agent_config);
 
         // AddTodo should continue
-        assert!(planner.should_continue_after_action(&PlannerAction::AddTodo {
-            description: "test".to_string(),
-            task_type: "general".to_string(),
-        }));
+        assert!(
+            planner.should_continue_after_action(&PlannerAction::AddTodo {
+                description: "test".to_string(),
+                task_type: "general".to_string(),
+                depends_on: Vec::new(),
+                parent_id: None,
+                deadline: None,
+                max_duration_secs: None,
+                tags: Vec::new(),
+            })
+        );
 
         // StartExecutor should stop
-        assert!(!planner.should_continue_after_action(&PlannerAction::StartExecutor {
-            task_id: "task_1".to_string(),
-        }));
+        assert!(
+            !planner.should_continue_after_action(&PlannerAction::StartExecutor {
+                task_id: "task_1".to_string(),
+            })
+        );
 
         // Report should stop
-        assert!(!planner.should_continue_after_action(&PlannerAction::Report {
-            message: "test".to_string(),
-        }));
+        assert!(
+            !planner.should_continue_after_action(&PlannerAction::Report {
+                message: "test".to_string(),
+            })
+        );
 
         // Done should stop
         assert!(!planner.should_continue_after_action(&PlannerAction::Done {
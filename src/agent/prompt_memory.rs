@@ -6,14 +6,23 @@
 //! Enhanced with user correction learning: when users manually correct
 //! the executor's behavior, the corrections are accumulated and can be
 //! consolidated into optimized prompts.
+//!
+//! The stored memory may be shared by several processes (GUI + CLI, planners
+//! for different devices). Saving merges in changes other processes made
+//! since we last synced; see `prompt_store` for the storage backends.
 
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::time::{Duration, Instant};
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
+use super::executor::ExecutorOverrides;
+use super::prompt_store::{open_prompt_store, EncryptionKey, PromptStore};
+
 /// A user correction record.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CorrectionRecord {
@@ -23,6 +32,9 @@ pub struct CorrectionRecord {
     pub context: Option<String>,
     /// Timestamp of correction.
     pub timestamp: String,
+    /// Path of the screenshot (JPEG) taken when the user corrected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
 }
 
 impl CorrectionRecord {
@@ -31,10 +43,171 @@ impl CorrectionRecord {
             content: content.into(),
             context,
             timestamp: Utc::now().to_rfc3339(),
+            screenshot: None,
+        }
+    }
+
+    /// Attach the screenshot taken at the moment of correction.
+    pub fn with_screenshot(mut self, path: impl Into<String>) -> Self {
+        self.screenshot = Some(path.into());
+        self
+    }
+}
+
+/// JPEG quality correction screenshots are stored with.
+pub const CORRECTION_SCREENSHOT_QUALITY: u8 = 70;
+
+/// Save a base64-encoded JPEG screenshot for a correction into `dir`.
+/// Returns the path of the written file. Screenshots are not encrypted,
+/// even when the prompt memory is; that is logged once.
+pub fn save_correction_screenshot(
+    dir: impl AsRef<Path>,
+    base64_jpeg: &str,
+) -> Result<PathBuf, PromptMemoryError> {
+    let dir = dir.as_ref();
+    if EncryptionKey::configured().is_some() {
+        static WARNED: Once = Once::new();
+        WARNED.call_once(|| {
+            tracing::warn!(
+                "A prompt memory key is configured, but correction screenshots in {} are \
+                 stored unencrypted",
+                dir.display()
+            )
+        });
+    }
+    std::fs::create_dir_all(dir).map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+    let bytes = STANDARD
+        .decode(base64_jpeg)
+        .map_err(|e| PromptMemoryError::ParseError(e.to_string()))?;
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let path = dir.join(format!(
+        "correction-{}-{}.jpg",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        &id[..8]
+    ));
+    std::fs::write(&path, bytes).map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+    Ok(path)
+}
+
+/// Read a correction screenshot back as base64 (`None` if it is gone).
+pub fn read_correction_screenshot(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read(path).ok().map(|bytes| STANDARD.encode(bytes))
+}
+
+/// Delete the screenshot files referenced by `corrections`.
+pub fn remove_correction_screenshots(corrections: &[CorrectionRecord]) {
+    for path in corrections.iter().filter_map(|c| c.screenshot.as_deref()) {
+        if let Err(e) = std::fs::remove_file(path) {
+            tracing::debug!("Failed to remove correction screenshot {}: {}", path, e);
         }
     }
 }
 
+/// Device model and app version a prompt applies to (`None` = any).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptScope {
+    /// Device model, as reported by `ro.product.model`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_model: Option<String>,
+    /// App version name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_version: Option<String>,
+}
+
+impl PromptScope {
+    /// A scope matching any device and app version.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict to a device model.
+    pub fn with_device_model(mut self, device_model: impl Into<String>) -> Self {
+        self.device_model = Some(device_model.into());
+        self
+    }
+
+    /// Restrict to an app version.
+    pub fn with_app_version(mut self, app_version: impl Into<String>) -> Self {
+        self.app_version = Some(app_version.into());
+        self
+    }
+
+    /// Whether the scope matches anything.
+    pub fn is_empty(&self) -> bool {
+        self.device_model.is_none() && self.app_version.is_none()
+    }
+
+    /// How specific this scope is for `target` (number of matching fields),
+    /// or `None` if it restricts a field to a different value than `target`.
+    fn specificity_for(&self, target: &PromptScope) -> Option<usize> {
+        let field = |own: &Option<String>, target: &Option<String>| match (own, target) {
+            (None, _) => Some(0),
+            (Some(own), Some(target)) if own == target => Some(1),
+            _ => None,
+        };
+        Some(
+            field(&self.device_model, &target.device_model)?
+                + field(&self.app_version, &target.app_version)?,
+        )
+    }
+}
+
+/// A prompt variant for a specific device model and/or app version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopedPrompt {
+    #[serde(flatten)]
+    pub scope: PromptScope,
+    /// The system prompt for this scope.
+    pub system_prompt: String,
+    /// Last update timestamp (ISO 8601 format).
+    pub last_updated: String,
+}
+
+/// One step of a recorded trajectory: the screen it was taken on and the
+/// action executed there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrajectoryStep {
+    /// App in the foreground.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// Compact action, e.g. `do(action="Tap", element=[500,300])`.
+    pub action: String,
+}
+
+/// A compact successful run of a task, used as a few-shot example.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trajectory {
+    /// The task that was completed.
+    pub task: String,
+    /// Steps in execution order.
+    pub steps: Vec<TrajectoryStep>,
+    /// When the run finished (ISO 8601 format).
+    pub recorded_at: String,
+}
+
+impl Trajectory {
+    /// Create a trajectory recorded now.
+    pub fn new(task: impl Into<String>, steps: Vec<TrajectoryStep>) -> Self {
+        Self {
+            task: task.into(),
+            steps,
+            recorded_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Render as an in-context example for the executor.
+    pub fn to_example(&self) -> String {
+        let mut lines = vec![format!("任务: {}", self.task)];
+        for (i, step) in self.steps.iter().enumerate() {
+            match &step.app {
+                Some(app) => lines.push(format!("{}. [{}] {}", i + 1, app, step.action)),
+                None => lines.push(format!("{}. {}", i + 1, step.action)),
+            }
+        }
+        lines.join("\n")
+    }
+}
+
 /// A single prompt entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptEntry {
@@ -54,6 +227,18 @@ pub struct PromptEntry {
     /// User corrections accumulated (not yet consolidated).
     #[serde(default)]
     pub corrections: Vec<CorrectionRecord>,
+    /// Executor settings applied when a task of this type starts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executor_overrides: Option<ExecutorOverrides>,
+    /// When a task of this type last ran (ISO 8601 format).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+    /// Variants preferred over `system_prompt` on matching devices/app versions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scoped: Vec<ScopedPrompt>,
+    /// Successful runs kept as few-shot examples, best first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trajectories: Vec<Trajectory>,
 }
 
 impl PromptEntry {
@@ -66,6 +251,10 @@ impl PromptEntry {
             usage_count: 0,
             notes: None,
             corrections: Vec::new(),
+            executor_overrides: None,
+            last_used: None,
+            scoped: Vec::new(),
+            trajectories: Vec::new(),
         }
     }
 
@@ -75,10 +264,45 @@ impl PromptEntry {
         self.last_updated = Utc::now().to_rfc3339();
     }
 
+    /// The prompt for a scope: the most specific matching variant (newest
+    /// on ties), otherwise the global `system_prompt`.
+    pub fn prompt_for(&self, scope: &PromptScope) -> &str {
+        self.scoped
+            .iter()
+            .filter_map(|v| Some((v.scope.specificity_for(scope)?, v)))
+            .filter(|(specificity, _)| *specificity > 0)
+            .max_by(|(a, va), (b, vb)| a.cmp(b).then(va.last_updated.cmp(&vb.last_updated)))
+            .map(|(_, v)| v.system_prompt.as_str())
+            .unwrap_or(&self.system_prompt)
+    }
+
+    /// Set the prompt for a scope. An empty scope updates `system_prompt`.
+    pub fn update_scoped(&mut self, scope: &PromptScope, new_prompt: impl Into<String>) {
+        if scope.is_empty() {
+            self.update(new_prompt);
+            return;
+        }
+        let now = Utc::now().to_rfc3339();
+        let new_prompt = new_prompt.into();
+        match self.scoped.iter_mut().find(|v| v.scope == *scope) {
+            Some(variant) => {
+                variant.system_prompt = new_prompt;
+                variant.last_updated = now.clone();
+            }
+            None => self.scoped.push(ScopedPrompt {
+                scope: scope.clone(),
+                system_prompt: new_prompt,
+                last_updated: now.clone(),
+            }),
+        }
+        self.last_updated = now;
+    }
+
     /// Record a usage and update success rate.
     pub fn record_usage(&mut self, success: bool) {
         let current_successes = self.success_rate.unwrap_or(0.0) * self.usage_count as f32;
         self.usage_count += 1;
+        self.last_used = Some(Utc::now().to_rfc3339());
         let new_successes = if success {
             current_successes + 1.0
         } else {
@@ -89,11 +313,82 @@ impl PromptEntry {
 
     /// Add a user correction.
     pub fn add_correction(&mut self, content: impl Into<String>, context: Option<String>) {
-        self.corrections
-            .push(CorrectionRecord::new(content, context));
+        self.add_correction_record(CorrectionRecord::new(content, context));
+    }
+
+    /// Add a prepared correction record (e.g. one with a screenshot).
+    pub fn add_correction_record(&mut self, record: CorrectionRecord) {
+        self.corrections.push(record);
         self.last_updated = Utc::now().to_rfc3339();
     }
 
+    /// Screenshot of the most recent correction that has one.
+    pub fn latest_correction_screenshot(&self) -> Option<&str> {
+        self.corrections
+            .iter()
+            .rev()
+            .find_map(|c| c.screenshot.as_deref())
+    }
+
+    /// Record a successful run. Empty runs and runs longer than
+    /// `MAX_TRAJECTORY_STEPS` are ignored; a run with the same steps as a
+    /// stored one replaces it. Returns whether the run was kept.
+    pub fn add_trajectory(&mut self, trajectory: Trajectory) -> bool {
+        if trajectory.steps.is_empty() || trajectory.steps.len() > MAX_TRAJECTORY_STEPS {
+            return false;
+        }
+        self.trajectories.retain(|t| t.steps != trajectory.steps);
+        self.trajectories.push(trajectory);
+        // Fewest steps first, newest first among equally short runs
+        self.trajectories.sort_by(|a, b| {
+            a.steps
+                .len()
+                .cmp(&b.steps.len())
+                .then_with(|| b.recorded_at.cmp(&a.recorded_at))
+        });
+        true
+    }
+
+    /// The stored run best suited as an example (the shortest one).
+    pub fn best_trajectory(&self) -> Option<&Trajectory> {
+        self.trajectories.first()
+    }
+
+    /// Success rate decayed by the time since the prompt was last used: it
+    /// halves every `half_life` (`None` = no decay), so a prompt that has
+    /// not been confirmed for a long time (e.g. since an app redesign)
+    /// gradually loses confidence.
+    pub fn confidence(&self, half_life: Option<Duration>) -> Option<f32> {
+        let rate = self.success_rate?;
+        let Some(half_life) = half_life.filter(|h| !h.is_zero()) else {
+            return Some(rate);
+        };
+        let last = self.last_used.as_deref().unwrap_or(&self.last_updated);
+        let Ok(last) = chrono::DateTime::parse_from_rfc3339(last) else {
+            return Some(rate);
+        };
+        let age = (Utc::now() - last.with_timezone(&Utc))
+            .to_std()
+            .unwrap_or_default();
+        let half_lives = age.as_secs_f64() / half_life.as_secs_f64();
+        Some(rate * 0.5f64.powf(half_lives) as f32)
+    }
+
+    /// Whether the prompt's (decayed) success rate is low enough that it
+    /// should be re-optimized.
+    pub fn needs_reoptimization(&self, half_life: Option<Duration>) -> bool {
+        self.confidence(half_life)
+            .is_some_and(|c| c < REOPTIMIZE_CONFIDENCE)
+    }
+
+    /// When the entry was last used or updated, whichever is later.
+    pub fn last_active(&self) -> &str {
+        match &self.last_used {
+            Some(used) if *used > self.last_updated => used,
+            _ => &self.last_updated,
+        }
+    }
+
     /// Get pending corrections count.
     pub fn pending_corrections_count(&self) -> usize {
         self.corrections.len()
@@ -112,20 +407,135 @@ impl PromptEntry {
         self.corrections
             .iter()
             .enumerate()
-            .map(|(i, c)| format!("{}. {}", i + 1, c.content))
+            .map(|(i, c)| match c.screenshot {
+                Some(_) => format!("{}. {} [附截图]", i + 1, c.content),
+                None => format!("{}. {}", i + 1, c.content),
+            })
             .collect::<Vec<_>>()
             .join("\n")
     }
 }
 
+/// Default maximum number of task types kept in prompt memory.
+pub const DEFAULT_MAX_TASK_TYPES: usize = 100;
+
+/// Default maximum number of pending corrections kept per task type.
+pub const DEFAULT_MAX_CORRECTIONS_PER_TYPE: usize = 20;
+
+/// Default maximum number of trajectories kept per task type.
+pub const DEFAULT_MAX_TRAJECTORIES_PER_TYPE: usize = 3;
+
+/// Runs with more steps than this are not kept as examples.
+pub const MAX_TRAJECTORY_STEPS: usize = 30;
+
+/// Default half-life (in days) of a prompt's success rate.
+pub const DEFAULT_SUCCESS_HALF_LIFE_DAYS: u64 = 90;
+
+/// Confidence below which a prompt becomes eligible for re-optimization.
+pub const REOPTIMIZE_CONFIDENCE: f32 = 0.5;
+
+/// Size caps for prompt memory (`None` = unlimited).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptMemoryLimits {
+    /// Maximum number of task types; the least recently used are evicted.
+    pub max_task_types: Option<usize>,
+    /// Maximum pending corrections per task type; the oldest are dropped.
+    pub max_corrections_per_type: Option<usize>,
+    /// Maximum trajectories per task type; the worst are dropped.
+    pub max_trajectories_per_type: Option<usize>,
+}
+
+impl Default for PromptMemoryLimits {
+    fn default() -> Self {
+        Self {
+            max_task_types: Some(DEFAULT_MAX_TASK_TYPES),
+            max_corrections_per_type: Some(DEFAULT_MAX_CORRECTIONS_PER_TYPE),
+            max_trajectories_per_type: Some(DEFAULT_MAX_TRAJECTORIES_PER_TYPE),
+        }
+    }
+}
+
+impl PromptMemoryLimits {
+    /// No caps at all.
+    pub fn unlimited() -> Self {
+        Self {
+            max_task_types: None,
+            max_corrections_per_type: None,
+            max_trajectories_per_type: None,
+        }
+    }
+}
+
+/// Minimum time between two debounced saves.
+pub const PROMPT_MEMORY_SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// What this process last read from or wrote to disk, used to tell other
+/// processes' additions from our own removals when merging.
+#[derive(Debug, Clone, Default)]
+struct SyncState {
+    /// Task types present at the last sync.
+    seen_types: HashSet<String>,
+    /// Aliases present at the last sync.
+    seen_aliases: HashSet<String>,
+    /// Corrections (task type, timestamp, content) present at the last sync.
+    seen_corrections: HashSet<(String, String, String)>,
+    /// Changes not written yet because of debouncing.
+    dirty: bool,
+    /// When the file was last written.
+    last_saved: Option<Instant>,
+}
+
+/// Maps a task type name to the form used for alias and fuzzy lookups.
+pub type TaskTypeNormalizer = fn(&str) -> String;
+
+/// Default task type normalization: trims, folds full-width characters to
+/// ASCII, lowercases and joins words with `_`, so "WeChat Chat",
+/// "ｗｅｃｈａｔ-chat" and "wechat_chat" are the same task type.
+pub fn normalize_task_type(name: &str) -> String {
+    let folded: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect();
+    folded
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// The normalization hook of a memory (not persisted).
+#[derive(Debug, Clone, Copy)]
+struct Normalizer(TaskTypeNormalizer);
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self(normalize_task_type)
+    }
+}
+
 /// Prompt memory storage.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PromptMemory {
     /// Prompts indexed by task type.
     pub prompts: HashMap<String, PromptEntry>,
+    /// Alternative names of task types: normalized alias -> task type.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub aliases: HashMap<String, String>,
     /// Version for future compatibility.
     #[serde(default = "default_version")]
     pub version: String,
+    /// Sync bookkeeping (not persisted).
+    #[serde(skip)]
+    sync: SyncState,
+    /// Normalization applied before alias and fuzzy lookups.
+    #[serde(skip)]
+    normalizer: Normalizer,
 }
 
 fn default_version() -> String {
@@ -137,58 +547,217 @@ impl PromptMemory {
     pub fn new() -> Self {
         Self {
             prompts: HashMap::new(),
+            aliases: HashMap::new(),
             version: default_version(),
+            sync: SyncState::default(),
+            normalizer: Normalizer::default(),
         }
     }
 
-    /// Load prompt memory from a JSON file.
+    /// Load prompt memory from a file. The backend is chosen from the
+    /// extension (see `open_prompt_store`); a missing file loads as empty.
     pub fn load(path: impl AsRef<Path>) -> Result<Self, PromptMemoryError> {
-        let path = path.as_ref();
-        if !path.exists() {
-            return Ok(Self::new());
+        Self::load_from(open_prompt_store(path).as_ref())
+    }
+
+    /// Load prompt memory from a storage backend.
+    pub fn load_from(store: &dyn PromptStore) -> Result<Self, PromptMemoryError> {
+        let mut memory = store.load()?;
+        memory.mark_synced();
+        Ok(memory)
+    }
+
+    /// Save prompt memory to a file. The backend is chosen from the
+    /// extension (see `open_prompt_store`).
+    ///
+    /// Changes another process saved since our last sync (new task types,
+    /// new corrections, newer prompts) are merged in first, so concurrent
+    /// writers do not clobber each other.
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<(), PromptMemoryError> {
+        self.save_to(open_prompt_store(path).as_ref())
+    }
+
+    /// Save prompt memory to a storage backend.
+    pub fn save_to(&mut self, store: &dyn PromptStore) -> Result<(), PromptMemoryError> {
+        store.save(self)?;
+        self.mark_synced();
+        self.sync.dirty = false;
+        self.sync.last_saved = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Save unless the last save was less than `PROMPT_MEMORY_SAVE_DEBOUNCE`
+    /// ago, in which case the change is kept until the next save or `flush`.
+    /// Returns whether the file was written.
+    pub fn save_debounced(&mut self, path: impl AsRef<Path>) -> Result<bool, PromptMemoryError> {
+        let recent = self
+            .sync
+            .last_saved
+            .is_some_and(|t| t.elapsed() < PROMPT_MEMORY_SAVE_DEBOUNCE);
+        if recent {
+            self.sync.dirty = true;
+            return Ok(false);
         }
+        self.save(path)?;
+        Ok(true)
+    }
 
-        let content =
-            fs::read_to_string(path).map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+    /// Write changes held back by `save_debounced`.
+    pub fn flush(&mut self, path: impl AsRef<Path>) -> Result<(), PromptMemoryError> {
+        if self.sync.dirty {
+            self.save(path)?;
+        }
+        Ok(())
+    }
 
-        serde_json::from_str(&content).map_err(|e| PromptMemoryError::ParseError(e.to_string()))
+    /// Whether changes are waiting to be written by `flush`.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.sync.dirty
     }
 
-    /// Save prompt memory to a JSON file.
-    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PromptMemoryError> {
-        let path = path.as_ref();
+    /// Merge the on-disk state written by other processes into this one.
+    ///
+    /// Task types and corrections we never saw were added elsewhere and are
+    /// kept; ones we saw but no longer have were removed here (deleted or
+    /// consolidated) and stay removed. For entries present on both sides the
+    /// more recently updated prompt wins.
+    pub(super) fn merge_concurrent(&mut self, disk: PromptMemory) {
+        for (alias, task_type) in disk.aliases {
+            if !self.sync.seen_aliases.contains(&alias) {
+                self.aliases.entry(alias).or_insert(task_type);
+            }
+        }
+        for (task_type, disk_entry) in disk.prompts {
+            let Some(entry) = self.prompts.get_mut(&task_type) else {
+                if !self.sync.seen_types.contains(&task_type) {
+                    self.prompts.insert(task_type, disk_entry);
+                }
+                continue;
+            };
+
+            let mut corrections = std::mem::take(&mut entry.corrections);
+            for correction in &disk_entry.corrections {
+                let key = (
+                    task_type.clone(),
+                    correction.timestamp.clone(),
+                    correction.content.clone(),
+                );
+                let known = corrections
+                    .iter()
+                    .any(|c| c.timestamp == key.1 && c.content == key.2);
+                if !known && !self.sync.seen_corrections.contains(&key) {
+                    corrections.push(correction.clone());
+                }
+            }
+            corrections.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
 
-        // Create parent directories if needed
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+            if disk_entry.last_updated > entry.last_updated {
+                *entry = disk_entry;
+            }
+            entry.corrections = corrections;
         }
+    }
 
-        let content = serde_json::to_string_pretty(self)
-            .map_err(|e| PromptMemoryError::SerializeError(e.to_string()))?;
+    /// Remember the current contents as the last synced state.
+    fn mark_synced(&mut self) {
+        self.sync.seen_types = self.prompts.keys().cloned().collect();
+        self.sync.seen_aliases = self.aliases.keys().cloned().collect();
+        self.sync.seen_corrections = self
+            .prompts
+            .iter()
+            .flat_map(|(task_type, entry)| {
+                entry
+                    .corrections
+                    .iter()
+                    .map(move |c| (task_type.clone(), c.timestamp.clone(), c.content.clone()))
+            })
+            .collect();
+    }
+
+    /// Replace the normalization hook (e.g. to add pinyin transliteration).
+    pub fn with_normalizer(mut self, normalizer: TaskTypeNormalizer) -> Self {
+        self.normalizer = Normalizer(normalizer);
+        self
+    }
+
+    /// Resolve a task type name to the entry it refers to: an exact match,
+    /// then an alias, then a task type with the same normalized name.
+    /// Unknown names are returned unchanged.
+    pub fn resolve(&self, task_type: &str) -> String {
+        if self.prompts.contains_key(task_type) {
+            return task_type.to_string();
+        }
+        let normalized = (self.normalizer.0)(task_type);
+        if let Some(target) = self.aliases.get(&normalized) {
+            return target.clone();
+        }
+        self.prompts
+            .keys()
+            .find(|k| (self.normalizer.0)(k) == normalized)
+            .cloned()
+            .unwrap_or_else(|| task_type.to_string())
+    }
+
+    /// Make `alias` resolve to `task_type` (itself resolved first, so
+    /// aliases of aliases point at the real entry).
+    pub fn add_alias(&mut self, alias: &str, task_type: &str) {
+        let target = self.resolve(task_type);
+        self.aliases.insert((self.normalizer.0)(alias), target);
+    }
+
+    /// Remove an alias. Returns the task type it pointed to.
+    pub fn remove_alias(&mut self, alias: &str) -> Option<String> {
+        self.aliases.remove(&(self.normalizer.0)(alias))
+    }
 
-        fs::write(path, content).map_err(|e| PromptMemoryError::IoError(e.to_string()))
+    /// Aliases pointing to a task type, sorted.
+    pub fn aliases_of(&self, task_type: &str) -> Vec<&str> {
+        let mut aliases: Vec<&str> = self
+            .aliases
+            .iter()
+            .filter(|(_, target)| *target == task_type)
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        aliases.sort();
+        aliases
     }
 
-    /// Get a prompt by task type.
+    /// Get a prompt by task type (or alias).
     pub fn get(&self, task_type: &str) -> Option<&PromptEntry> {
-        self.prompts.get(task_type)
+        self.prompts.get(&self.resolve(task_type))
     }
 
-    /// Get a mutable prompt by task type.
+    /// Get a mutable prompt by task type (or alias).
     pub fn get_mut(&mut self, task_type: &str) -> Option<&mut PromptEntry> {
-        self.prompts.get_mut(task_type)
+        let task_type = self.resolve(task_type);
+        self.prompts.get_mut(&task_type)
     }
 
     /// Get the system prompt string for a task type.
     pub fn get_prompt(&self, task_type: &str) -> Option<&str> {
-        self.prompts
-            .get(task_type)
-            .map(|e| e.system_prompt.as_str())
+        self.get(task_type).map(|e| e.system_prompt.as_str())
+    }
+
+    /// Get the prompt for a task type that best matches a device model and
+    /// app version (see `PromptEntry::prompt_for`).
+    pub fn get_prompt_for(&self, task_type: &str, scope: &PromptScope) -> Option<&str> {
+        self.get(task_type).map(|e| e.prompt_for(scope))
+    }
+
+    /// Update or create a prompt for a task type, limited to a scope.
+    pub fn update_scoped(
+        &mut self,
+        task_type: impl Into<String>,
+        scope: &PromptScope,
+        prompt: impl Into<String>,
+    ) {
+        self.ensure_task_type(task_type)
+            .update_scoped(scope, prompt);
     }
 
     /// Update or create a prompt for a task type.
     pub fn update(&mut self, task_type: impl Into<String>, prompt: impl Into<String>) {
-        let task_type = task_type.into();
+        let task_type = self.resolve(&task_type.into());
         let prompt = prompt.into();
 
         if let Some(entry) = self.prompts.get_mut(&task_type) {
@@ -200,14 +769,28 @@ impl PromptMemory {
 
     /// Record usage of a prompt.
     pub fn record_usage(&mut self, task_type: &str, success: bool) {
-        if let Some(entry) = self.prompts.get_mut(task_type) {
+        if let Some(entry) = self.get_mut(task_type) {
             entry.record_usage(success);
         }
     }
 
-    /// Remove a prompt.
+    /// Record a successful run for an existing task type (see
+    /// `PromptEntry::add_trajectory`). Returns whether the run was kept.
+    pub fn add_trajectory(&mut self, task_type: &str, trajectory: Trajectory) -> bool {
+        self.get_mut(task_type)
+            .is_some_and(|entry| entry.add_trajectory(trajectory))
+    }
+
+    /// Best stored run of a task type.
+    pub fn best_trajectory(&self, task_type: &str) -> Option<&Trajectory> {
+        self.get(task_type)?.best_trajectory()
+    }
+
+    /// Remove a prompt and the aliases pointing to it.
     pub fn remove(&mut self, task_type: &str) -> Option<PromptEntry> {
-        self.prompts.remove(task_type)
+        let task_type = self.resolve(task_type);
+        self.aliases.retain(|_, target| *target != task_type);
+        self.prompts.remove(&task_type)
     }
 
     /// List all task types.
@@ -222,7 +805,7 @@ impl PromptMemory {
 
     /// Check if a task type exists.
     pub fn contains(&self, task_type: &str) -> bool {
-        self.prompts.contains_key(task_type)
+        self.get(task_type).is_some()
     }
 
     /// Add a correction for a task type.
@@ -233,23 +816,23 @@ impl PromptMemory {
         content: impl Into<String>,
         context: Option<String>,
     ) {
-        let task_type = task_type.into();
-        let content = content.into();
+        self.add_correction_record(task_type, CorrectionRecord::new(content, context));
+    }
 
-        if let Some(entry) = self.prompts.get_mut(&task_type) {
-            entry.add_correction(content, context);
-        } else {
-            // Create new entry with empty prompt and add correction
-            let mut entry = PromptEntry::new("");
-            entry.add_correction(content, context);
-            self.prompts.insert(task_type, entry);
-        }
+    /// Add a prepared correction record for a task type.
+    /// If the task type doesn't exist, creates a new entry with default prompt.
+    pub fn add_correction_record(
+        &mut self,
+        task_type: impl Into<String>,
+        record: CorrectionRecord,
+    ) {
+        self.ensure_task_type(task_type)
+            .add_correction_record(record);
     }
 
     /// Get pending corrections count for a task type.
     pub fn pending_corrections(&self, task_type: &str) -> usize {
-        self.prompts
-            .get(task_type)
+        self.get(task_type)
             .map(|e| e.pending_corrections_count())
             .unwrap_or(0)
     }
@@ -265,7 +848,7 @@ impl PromptMemory {
 
     /// Get corrections summary for a task type.
     pub fn get_corrections_summary(&self, task_type: &str) -> Option<String> {
-        self.prompts.get(task_type).map(|e| e.corrections_summary())
+        self.get(task_type).map(|e| e.corrections_summary())
     }
 
     /// Get the number of stored prompts.
@@ -285,17 +868,54 @@ impl PromptMemory {
 
     /// Merge another prompt memory into this one.
     /// Existing entries are updated only if the incoming entry is newer.
-    pub fn merge(&mut self, other: &PromptMemory) {
+    /// Returns the number of entries added or replaced.
+    pub fn merge(&mut self, other: &PromptMemory) -> usize {
+        for (alias, task_type) in &other.aliases {
+            self.aliases
+                .entry(alias.clone())
+                .or_insert_with(|| task_type.clone());
+        }
+        let mut changed = 0;
         for (task_type, entry) in &other.prompts {
             if let Some(existing) = self.prompts.get_mut(task_type) {
                 // Compare timestamps and keep newer
                 if entry.last_updated > existing.last_updated {
                     *existing = entry.clone();
+                    changed += 1;
                 }
             } else {
                 self.prompts.insert(task_type.clone(), entry.clone());
+                changed += 1;
             }
         }
+        changed
+    }
+
+    /// Write a standalone JSON copy of this memory, e.g. for a backup or to
+    /// share learned prompts with another machine. Overwrites `path`.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<(), PromptMemoryError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| PromptMemoryError::SerializeError(e.to_string()))?;
+        std::fs::write(path, content).map_err(|e| PromptMemoryError::IoError(e.to_string()))
+    }
+
+    /// Merge an exported memory (JSON or SQLite) into this one; see `merge`.
+    /// Returns the number of entries added or replaced.
+    pub fn import_and_merge(&mut self, path: impl AsRef<Path>) -> Result<usize, PromptMemoryError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(PromptMemoryError::IoError(format!(
+                "{} not found",
+                path.display()
+            )));
+        }
+        let imported = Self::load(path)?;
+        Ok(self.merge(&imported))
     }
 
     /// Get prompts with success rate above threshold.
@@ -338,7 +958,17 @@ impl PromptMemory {
                         .unwrap_or_default()
                 );
 
-                format!("- **{}**: {} [{}]", task_type, prompt_preview, stats)
+                let aliases = self.aliases_of(task_type);
+                let aliases = if aliases.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (别名: {})", aliases.join(", "))
+                };
+
+                format!(
+                    "- **{}**{}: {} [{}]",
+                    task_type, aliases, prompt_preview, stats
+                )
             })
             .collect();
 
@@ -355,6 +985,10 @@ impl PromptMemory {
     /// Returns None if no good match is found (Planner should create a new type).
     /// This is a simple keyword-based matching; Planner can do better semantic matching.
     pub fn find_matching_task_type(&self, description: &str) -> Option<String> {
+        let resolved = self.resolve(description);
+        if self.prompts.contains_key(&resolved) {
+            return Some(resolved);
+        }
         let desc_lower = description.to_lowercase();
 
         // Simple keyword matching - find task type whose name appears in description
@@ -375,22 +1009,388 @@ impl PromptMemory {
         None
     }
 
+    /// Get the executor overrides for a task type.
+    pub fn get_executor_overrides(&self, task_type: &str) -> Option<&ExecutorOverrides> {
+        self.get(task_type)
+            .and_then(|e| e.executor_overrides.as_ref())
+    }
+
+    /// Set (or clear with `None`) the executor overrides for a task type.
+    pub fn set_executor_overrides(
+        &mut self,
+        task_type: impl Into<String>,
+        overrides: Option<ExecutorOverrides>,
+    ) {
+        let entry = self.ensure_task_type(task_type);
+        entry.executor_overrides = overrides.filter(|o| !o.is_empty());
+        entry.last_updated = Utc::now().to_rfc3339();
+    }
+
+    /// Apply size caps: drop the oldest corrections and the longest
+    /// trajectories of each task type beyond the per-type caps, then evict
+    /// the least recently used task types. Returns the evicted task types.
+    pub fn enforce_limits(&mut self, limits: &PromptMemoryLimits) -> Vec<String> {
+        if let Some(max) = limits.max_corrections_per_type {
+            for entry in self.prompts.values_mut() {
+                let excess = entry.corrections.len().saturating_sub(max);
+                entry.corrections.drain(..excess);
+            }
+        }
+        if let Some(max) = limits.max_trajectories_per_type {
+            for entry in self.prompts.values_mut() {
+                entry.trajectories.truncate(max);
+            }
+        }
+
+        let Some(max) = limits.max_task_types else {
+            return Vec::new();
+        };
+        let excess = self.prompts.len().saturating_sub(max);
+        if excess == 0 {
+            return Vec::new();
+        }
+
+        let mut by_age: Vec<(&String, &PromptEntry)> = self.prompts.iter().collect();
+        by_age.sort_by(|a, b| a.1.last_active().cmp(b.1.last_active()).then(a.0.cmp(b.0)));
+        let evicted: Vec<String> = by_age
+            .into_iter()
+            .take(excess)
+            .map(|(task_type, _)| task_type.clone())
+            .collect();
+        for task_type in &evicted {
+            self.prompts.remove(task_type);
+        }
+        evicted
+    }
+
+    /// Task types whose prompt should be re-optimized because its decayed
+    /// success rate dropped below `REOPTIMIZE_CONFIDENCE`, sorted by name.
+    pub fn stale_prompts(&self, half_life: Option<Duration>) -> Vec<String> {
+        let mut stale: Vec<String> = self
+            .prompts
+            .iter()
+            .filter(|(_, entry)| entry.needs_reoptimization(half_life))
+            .map(|(task_type, _)| task_type.clone())
+            .collect();
+        stale.sort();
+        stale
+    }
+
+    /// Overview of every task type, sorted by name.
+    pub fn list(&self) -> Vec<PromptSummary> {
+        let mut list: Vec<PromptSummary> = self
+            .prompts
+            .iter()
+            .map(|(task_type, entry)| PromptSummary {
+                task_type: task_type.clone(),
+                system_prompt: entry.system_prompt.clone(),
+                usage_count: entry.usage_count,
+                success_rate: entry.success_rate,
+                last_active: entry.last_active().to_string(),
+                pending_corrections: entry.corrections.len(),
+                scoped_variants: entry.scoped.len(),
+            })
+            .collect();
+        list.sort_by(|a, b| a.task_type.cmp(&b.task_type));
+        list
+    }
+
+    /// Analytics of what has been learned per task type, most used first.
+    pub fn report(&self) -> PromptReport {
+        let mut rows: Vec<PromptReportRow> = self
+            .prompts
+            .iter()
+            .map(|(task_type, entry)| PromptReportRow {
+                task_type: task_type.clone(),
+                usage_count: entry.usage_count,
+                success_rate: entry.success_rate,
+                pending_corrections: entry.corrections.len(),
+                last_used: entry.last_used.clone(),
+                prompt_length: entry.system_prompt.chars().count(),
+                trajectories: entry.trajectories.len(),
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.usage_count
+                .cmp(&a.usage_count)
+                .then_with(|| a.task_type.cmp(&b.task_type))
+        });
+
+        // Weighted by usage, so rarely used types don't skew it
+        let total_usage: u32 = rows.iter().map(|r| r.usage_count).sum();
+        let successes: f32 = rows
+            .iter()
+            .filter_map(|r| r.success_rate.map(|rate| rate * r.usage_count as f32))
+            .sum();
+        PromptReport {
+            generated_at: Utc::now().to_rfc3339(),
+            total_usage,
+            success_rate: (total_usage > 0).then(|| successes / total_usage as f32),
+            pending_corrections: rows.iter().map(|r| r.pending_corrections).sum(),
+            rows,
+        }
+    }
+
+    /// Human-readable details of a task type: prompt, scoped variants,
+    /// usage statistics and pending corrections.
+    pub fn show(&self, task_type: &str) -> Option<String> {
+        let task_type = self.resolve(task_type);
+        let entry = self.prompts.get(&task_type)?;
+        let mut lines = vec![
+            format!("任务类型: {}", task_type),
+            format!(
+                "使用次数: {}{}",
+                entry.usage_count,
+                entry
+                    .success_rate
+                    .map(|r| format!(", 成功率 {:.0}%", r * 100.0))
+                    .unwrap_or_default()
+            ),
+            format!("最近活跃: {}", entry.last_active()),
+            "提示词:".to_string(),
+            if entry.system_prompt.is_empty() {
+                "(无)".to_string()
+            } else {
+                entry.system_prompt.clone()
+            },
+        ];
+        let aliases = self.aliases_of(&task_type);
+        if !aliases.is_empty() {
+            lines.insert(1, format!("别名: {}", aliases.join(", ")));
+        }
+        for scoped in &entry.scoped {
+            lines.push(format!(
+                "提示词 [设备: {}, 版本: {}]:",
+                scoped.scope.device_model.as_deref().unwrap_or("*"),
+                scoped.scope.app_version.as_deref().unwrap_or("*")
+            ));
+            lines.push(scoped.system_prompt.clone());
+        }
+        if !entry.corrections.is_empty() {
+            lines.push("待整合纠偏:".to_string());
+            lines.push(entry.corrections_summary());
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Diff this memory's prompt for a task type against `other`'s
+    /// (`None` if neither memory has the task type).
+    pub fn diff(&self, task_type: &str, other: &PromptMemory) -> Option<PromptDiff> {
+        let ours = self.get(task_type);
+        let theirs = other.get(task_type);
+        if ours.is_none() && theirs.is_none() {
+            return None;
+        }
+        let prompt =
+            |e: Option<&PromptEntry>| e.map(|e| e.system_prompt.clone()).unwrap_or_default();
+        Some(PromptDiff {
+            task_type: task_type.to_string(),
+            added: ours.is_none(),
+            removed: theirs.is_none(),
+            lines: diff_lines(&prompt(ours), &prompt(theirs)),
+        })
+    }
+
+    /// Remove task types not used or updated in the last `days` days, along
+    /// with their correction screenshots. Returns the removed task types.
+    pub fn prune_unused(&mut self, days: u64) -> Vec<String> {
+        let Some(cutoff) = i64::try_from(days)
+            .ok()
+            .and_then(chrono::Duration::try_days)
+            .and_then(|age| Utc::now().checked_sub_signed(age))
+        else {
+            return Vec::new();
+        };
+        let cutoff = cutoff.to_rfc3339();
+        let mut pruned: Vec<String> = self
+            .prompts
+            .iter()
+            .filter(|(_, entry)| entry.last_active() < cutoff.as_str())
+            .map(|(task_type, _)| task_type.clone())
+            .collect();
+        pruned.sort();
+        for task_type in &pruned {
+            if let Some(entry) = self.prompts.remove(task_type) {
+                remove_correction_screenshots(&entry.corrections);
+            }
+        }
+        pruned
+    }
+
     /// Create or get a task type entry.
     /// If the task type exists, returns it; otherwise creates a new empty entry.
     pub fn ensure_task_type(&mut self, task_type: impl Into<String>) -> &mut PromptEntry {
-        let task_type = task_type.into();
+        let task_type = self.resolve(&task_type.into());
         self.prompts
             .entry(task_type)
             .or_insert_with(|| PromptEntry::new(""))
     }
 }
 
+/// Overview of one task type, for management views.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptSummary {
+    pub task_type: String,
+    /// The global prompt (empty if only corrections were recorded).
+    pub system_prompt: String,
+    pub usage_count: u32,
+    pub success_rate: Option<f32>,
+    /// When the entry was last used or updated (ISO 8601 format).
+    pub last_active: String,
+    pub pending_corrections: usize,
+    /// Number of device / app version specific variants.
+    pub scoped_variants: usize,
+}
+
+/// Analytics of one task type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptReportRow {
+    pub task_type: String,
+    pub usage_count: u32,
+    pub success_rate: Option<f32>,
+    pub pending_corrections: usize,
+    /// When a task of this type last ran (ISO 8601 format).
+    pub last_used: Option<String>,
+    /// Length of the global prompt in characters.
+    pub prompt_length: usize,
+    /// Number of stored example runs.
+    pub trajectories: usize,
+}
+
+/// Analytics report over all task types (see `PromptMemory::report`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptReport {
+    /// When the report was generated (ISO 8601 format).
+    pub generated_at: String,
+    pub total_usage: u32,
+    /// Success rate over all runs.
+    pub success_rate: Option<f32>,
+    pub pending_corrections: usize,
+    pub rows: Vec<PromptReportRow>,
+}
+
+impl std::fmt::Display for PromptReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rate = |rate: Option<f32>| {
+            rate.map(|r| format!("{:.0}%", r * 100.0))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        writeln!(
+            f,
+            "任务类型 {} 个, 共使用 {} 次, 总成功率 {}, 待整合纠偏 {} 条",
+            self.rows.len(),
+            self.total_usage,
+            rate(self.success_rate),
+            self.pending_corrections
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "| 任务类型 | 使用次数 | 成功率 | 待整合纠偏 | 最近使用 | 提示词长度 | 示例 |"
+        )?;
+        writeln!(f, "|---|---:|---:|---:|---|---:|---:|")?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "| {} | {} | {} | {} | {} | {} | {} |",
+                row.task_type,
+                row.usage_count,
+                rate(row.success_rate),
+                row.pending_corrections,
+                row.last_used.as_deref().unwrap_or("从未"),
+                row.prompt_length,
+                row.trajectories
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// One line of a prompt diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "line", rename_all = "snake_case")]
+pub enum DiffLine {
+    Unchanged(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Line diff between the prompts two memories store for a task type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptDiff {
+    pub task_type: String,
+    /// The task type only exists in the other memory.
+    pub added: bool,
+    /// The task type only exists in this memory.
+    pub removed: bool,
+    pub lines: Vec<DiffLine>,
+}
+
+impl PromptDiff {
+    /// Whether both memories store the same prompt.
+    pub fn is_unchanged(&self) -> bool {
+        self.lines
+            .iter()
+            .all(|l| matches!(l, DiffLine::Unchanged(_)))
+    }
+}
+
+impl std::fmt::Display for PromptDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                DiffLine::Unchanged(l) => writeln!(f, "  {}", l)?,
+                DiffLine::Added(l) => writeln!(f, "+ {}", l)?,
+                DiffLine::Removed(l) => writeln!(f, "- {}", l)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Longest-common-subsequence line diff of `old` against `new`.
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Unchanged(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    lines
+}
+
 /// Prompt memory errors.
 #[derive(Debug, Clone)]
 pub enum PromptMemoryError {
     IoError(String),
     ParseError(String),
     SerializeError(String),
+    DatabaseError(String),
+    EncryptionError(String),
 }
 
 impl std::fmt::Display for PromptMemoryError {
@@ -399,6 +1399,8 @@ impl std::fmt::Display for PromptMemoryError {
             Self::IoError(e) => write!(f, "IO error: {}", e),
             Self::ParseError(e) => write!(f, "Parse error: {}", e),
             Self::SerializeError(e) => write!(f, "Serialize error: {}", e),
+            Self::DatabaseError(e) => write!(f, "Database error: {}", e),
+            Self::EncryptionError(e) => write!(f, "Encryption error: {}", e),
         }
     }
 }
@@ -429,6 +1431,15 @@ pub fn create_default_prompt_memory() -> PromptMemory {
 mod tests {
     use super::*;
     use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Fresh directory for a test, so saves and their lock files stay in it.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("{}_{}", name, uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
 
     #[test]
     fn test_prompt_entry_creation() {
@@ -473,9 +1484,8 @@ mod tests {
 
     #[test]
     fn test_prompt_memory_persistence() {
-        // Use temp directory from environment or current dir
-        let temp_dir = env::temp_dir();
-        let path = temp_dir.join("phone_agent_test_prompts.json");
+        let dir = test_dir("phone_agent_test_prompts");
+        let path = dir.join("prompts.json");
 
         // Create and save
         let mut memory = PromptMemory::new();
@@ -490,9 +1500,140 @@ mod tests {
         assert_eq!(loaded.get_prompt("type_b"), Some("Prompt B"));
 
         // Cleanup
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_concurrent_saves_merge() {
+        let dir = test_dir("phone_agent_test_prompts_concurrent");
+        let path = dir.join("prompts.json");
+
+        let mut memory = PromptMemory::new();
+        memory.update("shared", "Shared prompt");
+        memory.update("stale", "Stale prompt");
+        memory.save(&path).unwrap();
+
+        // Two processes load the same file and change it independently
+        let mut gui = PromptMemory::load(&path).unwrap();
+        let mut cli = PromptMemory::load(&path).unwrap();
+        gui.add_correction("shared", "GUI correction", None);
+        gui.update("gui_only", "GUI prompt");
+        gui.remove("stale");
+        cli.add_correction("shared", "CLI correction", None);
+        cli.save(&path).unwrap();
+        gui.save(&path).unwrap();
+
+        let merged = PromptMemory::load(&path).unwrap();
+        let corrections = &merged.get("shared").unwrap().corrections;
+        assert_eq!(corrections.len(), 2);
+        assert!(merged.contains("gui_only"));
+        // Removed by a process that had seen it, so it stays removed
+        assert!(!merged.contains("stale"));
+        // The in-memory copy picked up the other process's changes too
+        assert_eq!(gui.get("shared").unwrap().corrections.len(), 2);
+
+        // Saves right after a write are debounced until flushed
+        gui.update("later", "Later prompt");
+        assert!(!gui.save_debounced(&path).unwrap());
+        assert!(gui.has_unsaved_changes());
+        assert!(!PromptMemory::load(&path).unwrap().contains("later"));
+        gui.flush(&path).unwrap();
+        assert!(!gui.has_unsaved_changes());
+        assert!(PromptMemory::load(&path).unwrap().contains("later"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_import_and_merge() {
+        let path = env::temp_dir().join("phone_agent_test_prompts_export.json");
+        let _ = fs::remove_file(&path);
+
+        let mut laptop = PromptMemory::new();
+        laptop.update("微信操作", "Laptop prompt");
+        laptop.update("设置调整", "Settings prompt");
+        laptop.export(&path).unwrap();
+
+        let mut phone = PromptMemory::new();
+        phone.update("微信操作", "Newer phone prompt");
+        assert_eq!(phone.import_and_merge(&path).unwrap(), 1);
+        assert_eq!(phone.get_prompt("微信操作"), Some("Newer phone prompt"));
+        assert_eq!(phone.get_prompt("设置调整"), Some("Settings prompt"));
+        assert!(phone
+            .import_and_merge(env::temp_dir().join("missing_export.json"))
+            .is_err());
+
         let _ = fs::remove_file(&path);
     }
 
+    #[test]
+    fn test_enforce_limits_evicts_least_recently_used() {
+        let mut memory = PromptMemory::new();
+        for (task_type, updated) in [
+            ("a", "2026-01-01"),
+            ("b", "2026-02-01"),
+            ("c", "2026-03-01"),
+        ] {
+            memory.update(task_type, "prompt");
+            memory.get_mut(task_type).unwrap().last_updated = format!("{}T00:00:00+00:00", updated);
+        }
+        // Using the oldest type keeps it around
+        memory.record_usage("a", true);
+        for i in 0..5 {
+            memory.add_correction("c", format!("correction {}", i), None);
+        }
+
+        let evicted = memory.enforce_limits(&PromptMemoryLimits {
+            max_task_types: Some(2),
+            max_corrections_per_type: Some(3),
+            max_trajectories_per_type: None,
+        });
+        assert_eq!(evicted, vec!["b".to_string()]);
+        assert!(memory.contains("a") && memory.contains("c"));
+        let corrections = &memory.get("c").unwrap().corrections;
+        assert_eq!(corrections.len(), 3);
+        assert_eq!(corrections[0].content, "correction 2");
+
+        assert!(memory
+            .enforce_limits(&PromptMemoryLimits::unlimited())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_scoped_prompts_prefer_most_specific() {
+        let mut memory = PromptMemory::new();
+        memory.update("微信操作", "Global");
+        let pixel = PromptScope::new().with_device_model("Pixel 8");
+        let pixel_v8 = pixel.clone().with_app_version("8.0.50");
+        memory.update_scoped("微信操作", &pixel, "Pixel");
+        memory.update_scoped("微信操作", &pixel_v8, "Pixel on 8.0.50");
+        memory.update_scoped(
+            "微信操作",
+            &PromptScope::new().with_app_version("8.0.50"),
+            "Any phone on 8.0.50",
+        );
+
+        let get = |scope: &PromptScope| memory.get_prompt_for("微信操作", scope).unwrap();
+        assert_eq!(get(&pixel_v8), "Pixel on 8.0.50");
+        assert_eq!(get(&pixel.clone().with_app_version("8.0.49")), "Pixel");
+        assert_eq!(
+            get(&PromptScope::new()
+                .with_device_model("Mi 14")
+                .with_app_version("8.0.50")),
+            "Any phone on 8.0.50"
+        );
+        assert_eq!(
+            get(&PromptScope::new().with_device_model("Mi 14")),
+            "Global"
+        );
+        assert_eq!(get(&PromptScope::new()), "Global");
+
+        // Updating an existing scope replaces its variant
+        memory.update_scoped("微信操作", &pixel, "Pixel v2");
+        assert_eq!(memory.get("微信操作").unwrap().scoped.len(), 3);
+        assert_eq!(memory.get_prompt_for("微信操作", &pixel), Some("Pixel v2"));
+    }
+
     #[test]
     fn test_default_prompt_memory() {
         let memory = create_default_prompt_memory();
@@ -553,7 +1694,10 @@ mod tests {
         memory.update("设置调整", "Settings prompt");
 
         // Exact match
-        assert_eq!(memory.find_matching_task_type("微信操作"), Some("微信操作".to_string()));
+        assert_eq!(
+            memory.find_matching_task_type("微信操作"),
+            Some("微信操作".to_string())
+        );
 
         // Partial match
         let result = memory.find_matching_task_type("微信");
@@ -562,6 +1706,33 @@ mod tests {
         assert!(matched.contains("微信"));
     }
 
+    #[test]
+    fn test_executor_overrides_roundtrip() {
+        let mut memory = PromptMemory::new();
+        memory.set_executor_overrides(
+            "地图导航",
+            Some(ExecutorOverrides {
+                max_steps: Some(200),
+                ..ExecutorOverrides::default()
+            }),
+        );
+        assert_eq!(
+            memory.get_executor_overrides("地图导航").unwrap().max_steps,
+            Some(200)
+        );
+
+        let json = serde_json::to_string(&memory).unwrap();
+        let loaded: PromptMemory = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            loaded.get_executor_overrides("地图导航").unwrap().max_steps,
+            Some(200)
+        );
+
+        // Empty overrides are cleared
+        memory.set_executor_overrides("地图导航", Some(ExecutorOverrides::default()));
+        assert!(memory.get_executor_overrides("地图导航").is_none());
+    }
+
     #[test]
     fn test_ensure_task_type() {
         let mut memory = PromptMemory::new();
@@ -575,4 +1746,245 @@ mod tests {
         memory.ensure_task_type("existing");
         assert_eq!(memory.get_prompt("existing"), Some("Custom prompt"));
     }
+
+    #[test]
+    fn test_correction_screenshot() {
+        let dir = std::env::temp_dir().join("test_correction_screenshot");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let jpeg = STANDARD.encode(b"\xff\xd8fake jpeg");
+        let path = save_correction_screenshot(&dir, &jpeg).unwrap();
+        let path = path.display().to_string();
+        assert_eq!(
+            read_correction_screenshot(&path).as_deref(),
+            Some(jpeg.as_str())
+        );
+
+        let mut memory = PromptMemory::new();
+        memory.add_correction("微信操作", "先点搜索框", None);
+        memory.add_correction_record(
+            "微信操作",
+            CorrectionRecord::new("不要点广告", None).with_screenshot(&path),
+        );
+        let entry = memory.get("微信操作").unwrap();
+        assert_eq!(entry.latest_correction_screenshot(), Some(path.as_str()));
+        assert!(entry.corrections_summary().ends_with("不要点广告 [附截图]"));
+
+        // The reference survives both storage backends
+        for file in ["memory.json", "memory.db"] {
+            let file = dir.join(file);
+            memory.save(&file).unwrap();
+            let loaded = PromptMemory::load(&file).unwrap();
+            let entry = loaded.get("微信操作").unwrap();
+            assert_eq!(entry.corrections[0].screenshot, None);
+            assert_eq!(
+                entry.corrections[1].screenshot.as_deref(),
+                Some(path.as_str())
+            );
+        }
+
+        remove_correction_screenshots(&memory.get("微信操作").unwrap().corrections);
+        assert!(!Path::new(&path).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_trajectories() {
+        let step = |action: &str| TrajectoryStep {
+            app: Some("微信".to_string()),
+            action: action.to_string(),
+        };
+        let mut memory = PromptMemory::new();
+        memory.update("微信聊天", "prompt");
+
+        let long = vec![step("a"), step("b"), step("c")];
+        let short = vec![step("a"), step("finish(message=\"ok\")")];
+        assert!(memory.add_trajectory("微信聊天", Trajectory::new("long", long.clone())));
+        assert!(memory.add_trajectory("微信聊天", Trajectory::new("short", short)));
+        assert!(!memory.add_trajectory("微信聊天", Trajectory::new("empty", Vec::new())));
+        assert!(!memory.add_trajectory(
+            "微信聊天",
+            Trajectory::new("too long", vec![step("a"); MAX_TRAJECTORY_STEPS + 1])
+        ));
+        assert!(!memory.add_trajectory("未知", Trajectory::new("x", vec![step("a")])));
+
+        let best = memory.best_trajectory("微信聊天").unwrap();
+        assert_eq!(best.task, "short");
+        assert_eq!(
+            best.to_example(),
+            "任务: short\n1. [微信] a\n2. [微信] finish(message=\"ok\")"
+        );
+
+        // The same steps replace the stored run; the cap keeps the shortest
+        assert!(memory.add_trajectory("微信聊天", Trajectory::new("long again", long)));
+        assert!(memory.add_trajectory("微信聊天", Trajectory::new("mid", vec![step("x"); 2])));
+        assert_eq!(memory.get("微信聊天").unwrap().trajectories.len(), 3);
+        memory.enforce_limits(&PromptMemoryLimits {
+            max_trajectories_per_type: Some(2),
+            ..PromptMemoryLimits::unlimited()
+        });
+        let tasks: Vec<&str> = memory
+            .get("微信聊天")
+            .unwrap()
+            .trajectories
+            .iter()
+            .map(|t| t.task.as_str())
+            .collect();
+        assert_eq!(tasks.len(), 2);
+        assert!(!tasks.contains(&"long again"));
+
+        // Trajectories survive a JSON round trip
+        let json = serde_json::to_string(&memory).unwrap();
+        let loaded: PromptMemory = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get("微信聊天").unwrap().trajectories.len(), 2);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut memory = PromptMemory::new();
+        memory.update("微信聊天", "打开微信");
+        memory.record_usage("微信聊天", true);
+        memory.record_usage("微信聊天", false);
+        memory.record_usage("微信聊天", true);
+        memory.update("地图导航", "");
+        memory.record_usage("地图导航", true);
+        memory.add_correction("相机", "先授予权限", None);
+
+        let report = memory.report();
+        let types: Vec<&str> = report.rows.iter().map(|r| r.task_type.as_str()).collect();
+        assert_eq!(types, vec!["微信聊天", "地图导航", "相机"]);
+        assert_eq!(report.rows[0].prompt_length, 4);
+        assert!(report.rows[0].last_used.is_some());
+        assert!(report.rows[2].last_used.is_none());
+        assert_eq!(report.total_usage, 4);
+        assert_eq!(report.pending_corrections, 1);
+        assert!((report.success_rate.unwrap() - 0.75).abs() < 1e-4);
+
+        let text = report.to_string();
+        assert!(text.contains("| 微信聊天 | 3 | 67% | 0 |"));
+        assert!(text.contains("| 相机 | 0 | - | 1 | 从未 |"));
+    }
+
+    #[test]
+    fn test_management_api() {
+        let mut memory = PromptMemory::new();
+        memory.update("微信操作", "先打开微信\n点击搜索\n输入联系人");
+        memory.update("地图导航", "打开地图");
+        memory.add_correction("地图导航", "先定位", None);
+        memory.get_mut("地图导航").unwrap().last_updated = "2020-01-01T00:00:00+00:00".to_string();
+
+        let list = memory.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].task_type, "地图导航");
+        assert_eq!(list[0].pending_corrections, 1);
+        let details = memory.show("微信操作").unwrap();
+        assert!(details.contains("点击搜索"));
+        assert!(memory.show("unknown").is_none());
+
+        let mut other = memory.clone();
+        other.update("微信操作", "先打开微信\n点击通讯录\n输入联系人");
+        let diff = memory.diff("微信操作", &other).unwrap();
+        assert!(!diff.is_unchanged());
+        assert_eq!(
+            diff.lines,
+            vec![
+                DiffLine::Unchanged("先打开微信".to_string()),
+                DiffLine::Removed("点击搜索".to_string()),
+                DiffLine::Added("点击通讯录".to_string()),
+                DiffLine::Unchanged("输入联系人".to_string()),
+            ]
+        );
+        assert!(memory.diff("地图导航", &other).unwrap().is_unchanged());
+        other.remove("地图导航");
+        assert!(memory.diff("地图导航", &other).unwrap().removed);
+        assert!(memory.diff("unknown", &other).is_none());
+
+        assert_eq!(memory.prune_unused(30), vec!["地图导航".to_string()]);
+        assert!(memory.contains("微信操作"));
+        assert!(memory.prune_unused(u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_success_rate_decay() {
+        let half_life = Some(Duration::from_secs(30 * 24 * 60 * 60));
+        let mut memory = PromptMemory::new();
+        memory.update("微信操作", "WeChat prompt");
+        memory.record_usage("微信操作", true);
+        memory.update("地图导航", "Map prompt");
+        memory.record_usage("地图导航", true);
+
+        // Used just now: full confidence
+        let entry = memory.get("微信操作").unwrap();
+        assert!((entry.confidence(half_life).unwrap() - 1.0).abs() < 1e-3);
+        assert!(!entry.needs_reoptimization(half_life));
+
+        // Two half-lives ago: a quarter of the success rate is left
+        let sixty_days_ago = (Utc::now() - chrono::Duration::days(60)).to_rfc3339();
+        let entry = memory.get_mut("地图导航").unwrap();
+        entry.last_used = Some(sixty_days_ago.clone());
+        entry.last_updated = sixty_days_ago;
+        assert!((entry.confidence(half_life).unwrap() - 0.25).abs() < 1e-3);
+        assert_eq!(entry.confidence(None), Some(1.0));
+        assert_eq!(
+            memory.stale_prompts(half_life),
+            vec!["地图导航".to_string()]
+        );
+        assert!(memory.stale_prompts(None).is_empty());
+
+        // Prompts without a success rate are never stale
+        memory.update("新任务", "New prompt");
+        assert!(memory
+            .get("新任务")
+            .unwrap()
+            .confidence(half_life)
+            .is_none());
+    }
+
+    #[test]
+    fn test_task_type_aliases() {
+        assert_eq!(normalize_task_type("  WeChat Chat "), "wechat_chat");
+        assert_eq!(normalize_task_type("ＷｅＣｈａｔ-chat"), "wechat_chat");
+
+        let mut memory = PromptMemory::new();
+        memory.update("微信聊天", "WeChat prompt");
+        memory.add_alias("微信消息", "微信聊天");
+        memory.add_alias("wechat_chat", "微信消息");
+        assert_eq!(
+            memory.aliases_of("微信聊天"),
+            vec!["wechat_chat", "微信消息"]
+        );
+
+        // Aliases and normalized spellings reach the same entry
+        assert_eq!(memory.get_prompt("微信消息"), Some("WeChat prompt"));
+        assert_eq!(memory.get_prompt("WeChat Chat"), Some("WeChat prompt"));
+        memory.record_usage("wechat-chat", true);
+        memory.add_correction("微信消息", "先找到联系人", None);
+        memory.update("微信消息", "Updated prompt");
+        assert_eq!(memory.len(), 1);
+        let entry = memory.get("微信聊天").unwrap();
+        assert_eq!(entry.usage_count, 1);
+        assert_eq!(entry.corrections.len(), 1);
+        assert_eq!(entry.system_prompt, "Updated prompt");
+        assert_eq!(memory.resolve("unknown"), "unknown");
+
+        // A custom hook can fold more spellings together
+        let mut memory = memory.with_normalizer(|name| name.replace("聊天", "chat").to_lowercase());
+        assert!(memory.contains("微信CHAT"));
+
+        // Aliases are persisted by both backends
+        let dir = std::env::temp_dir().join("test_task_type_aliases");
+        let _ = std::fs::remove_dir_all(&dir);
+        for file in ["memory.json", "memory.db"] {
+            let file = dir.join(file);
+            memory.save(&file).unwrap();
+            let loaded = PromptMemory::load(&file).unwrap();
+            assert_eq!(loaded.get_prompt("微信消息"), Some("Updated prompt"));
+        }
+
+        memory.remove("wechat_chat");
+        assert!(memory.is_empty());
+        assert!(memory.aliases.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,769 @@
+//! Storage backends for prompt memory.
+//!
+//! - `JsonPromptStore` keeps the whole memory in one JSON file (the default).
+//! - `SqlitePromptStore` keeps one row per task type and per correction, so a
+//!   save only writes what changed instead of rewriting one large file.
+//!
+//! `open_prompt_store` picks the backend from the file extension. Both
+//! backends merge in changes other processes stored since the memory was last
+//! loaded or saved (see `PromptMemory::save`).
+//!
+//! When a key is configured (`PROMPT_MEMORY_KEY`, or the `prompt_memory_key`
+//! entry of the OS keychain), JSON files are encrypted at rest with
+//! AES-256-GCM, since corrections often contain personal context.
+//! Passphrases are stretched with Argon2id, salted with `<file>.salt`.
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Once, OnceLock};
+use std::time::Duration;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::{params, Connection, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::prompt_memory::{CorrectionRecord, PromptEntry, PromptMemory, PromptMemoryError};
+use crate::keychain::{Keychain, SecretStore};
+
+/// File extensions stored with `SqlitePromptStore`.
+pub const SQLITE_EXTENSIONS: &[&str] = &["db", "sqlite", "sqlite3"];
+
+/// Environment variable holding the prompt memory encryption key.
+pub const PROMPT_MEMORY_KEY_ENV: &str = "PROMPT_MEMORY_KEY";
+
+/// Keychain account holding the prompt memory encryption key, used when
+/// `PROMPT_MEMORY_KEY` is not set.
+pub const PROMPT_MEMORY_KEY_ACCOUNT: &str = "prompt_memory_key";
+
+/// Cipher name recorded in encrypted files.
+const CIPHER: &str = "aes-256-gcm";
+/// Key derivation recorded in files encrypted with a passphrase.
+const KDF_ARGON2ID: &str = "argon2id";
+/// Length of the random salt in `<file>.salt`.
+const SALT_LEN: usize = 16;
+
+/// How long a SQLite writer waits for another process to finish its save.
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SQLITE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS prompts (
+        task_type TEXT PRIMARY KEY,
+        entry TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS corrections (
+        task_type TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        content TEXT NOT NULL,
+        context TEXT,
+        screenshot TEXT,
+        PRIMARY KEY (task_type, timestamp, content)
+    );
+    CREATE TABLE IF NOT EXISTS aliases (
+        alias TEXT PRIMARY KEY,
+        task_type TEXT NOT NULL
+    );
+";
+
+/// A place prompt memory is persisted to.
+pub trait PromptStore: std::fmt::Debug + Send + Sync {
+    /// Load the stored memory (empty if nothing was stored yet).
+    fn load(&self) -> Result<PromptMemory, PromptMemoryError>;
+
+    /// Store `memory`, first merging in changes other processes stored
+    /// since it was last loaded or saved.
+    fn save(&self, memory: &mut PromptMemory) -> Result<(), PromptMemoryError>;
+}
+
+/// Open the backend for `path`: SQLite for `.db`/`.sqlite`/`.sqlite3`,
+/// JSON otherwise. JSON files are encrypted with the configured key (see
+/// `EncryptionKey::configured`); the SQLite backend is not encrypted, which
+/// is logged once when a key is configured.
+pub fn open_prompt_store(path: impl AsRef<Path>) -> Box<dyn PromptStore> {
+    let path = path.as_ref();
+    let is_sqlite = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SQLITE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+    let key = EncryptionKey::configured();
+    if is_sqlite {
+        if key.is_some() {
+            static WARNED: Once = Once::new();
+            WARNED.call_once(|| {
+                tracing::warn!(
+                    "A prompt memory key is configured, but the SQLite backend ({}) is not \
+                     encrypted; use a .json path to encrypt corrections at rest",
+                    path.display()
+                )
+            });
+        }
+        Box::new(SqlitePromptStore::new(path))
+    } else {
+        let store = JsonPromptStore::new(path);
+        match key {
+            Some(key) => Box::new(store.with_key(key)),
+            None => Box::new(store),
+        }
+    }
+}
+
+/// Key for prompt memory files: a 32-byte AES-256-GCM key, or a passphrase
+/// the key is derived from with Argon2id.
+#[derive(Clone)]
+pub struct EncryptionKey(Secret);
+
+#[derive(Clone)]
+enum Secret {
+    Key([u8; 32]),
+    Passphrase(String),
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+impl EncryptionKey {
+    /// Build a key from a secret: a base64-encoded 32-byte key is used as
+    /// is, anything else is treated as a passphrase.
+    pub fn from_secret(secret: &str) -> Self {
+        match STANDARD.decode(secret.trim()) {
+            Ok(bytes) if bytes.len() == 32 => {
+                let mut key = [0u8; 32];
+                key.copy_from_slice(&bytes);
+                Self(Secret::Key(key))
+            }
+            _ => Self(Secret::Passphrase(secret.to_string())),
+        }
+    }
+
+    /// Key from `PROMPT_MEMORY_KEY`, if set and not empty.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(PROMPT_MEMORY_KEY_ENV)
+            .ok()
+            .filter(|secret| !secret.trim().is_empty())
+            .map(|secret| Self::from_secret(&secret))
+    }
+
+    /// Key stored in `store` under `PROMPT_MEMORY_KEY_ACCOUNT`, if any.
+    pub fn from_secret_store(store: &impl SecretStore) -> Option<Self> {
+        match store.get(PROMPT_MEMORY_KEY_ACCOUNT) {
+            Ok(secret) => secret
+                .filter(|secret| !secret.trim().is_empty())
+                .map(|secret| Self::from_secret(&secret)),
+            Err(e) => {
+                tracing::debug!("No prompt memory key from the keychain: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Key from `PROMPT_MEMORY_KEY`, or else from the OS keychain. The
+    /// keychain is asked once per process.
+    pub fn configured() -> Option<Self> {
+        static KEYCHAIN_KEY: OnceLock<Option<EncryptionKey>> = OnceLock::new();
+        Self::from_env().or_else(|| {
+            KEYCHAIN_KEY
+                .get_or_init(|| Self::from_secret_store(&Keychain))
+                .clone()
+        })
+    }
+
+    /// Whether a salt file is needed, i.e. the key is derived from a passphrase.
+    fn is_passphrase(&self) -> bool {
+        matches!(self.0, Secret::Passphrase(_))
+    }
+
+    /// AES key for a file written with key derivation `kdf`.
+    fn cipher_key(
+        &self,
+        kdf: Option<&str>,
+        salt: Option<&[u8]>,
+    ) -> Result<[u8; 32], PromptMemoryError> {
+        let invalid = |what: &str| PromptMemoryError::EncryptionError(what.to_string());
+        let passphrase = match &self.0 {
+            Secret::Key(key) => return Ok(*key),
+            Secret::Passphrase(passphrase) => passphrase,
+        };
+        let mut key = [0u8; 32];
+        match kdf {
+            Some(KDF_ARGON2ID) => {
+                let salt = salt.ok_or_else(|| invalid("salt file missing"))?;
+                Argon2::default()
+                    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                    .map_err(|e| invalid(&e.to_string()))?;
+            }
+            // Files encrypted before passphrases were stretched
+            None => key.copy_from_slice(&Sha256::digest(passphrase.as_bytes())),
+            Some(_) => return Err(invalid("unsupported key derivation")),
+        }
+        Ok(key)
+    }
+
+    fn encrypt(
+        &self,
+        plaintext: &[u8],
+        salt: Option<&[u8]>,
+    ) -> Result<EncryptedFile, PromptMemoryError> {
+        let kdf = self.is_passphrase().then(|| KDF_ARGON2ID.to_string());
+        let cipher = Aes256Gcm::new(&self.cipher_key(kdf.as_deref(), salt)?.into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let data = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|e| PromptMemoryError::EncryptionError(e.to_string()))?;
+        Ok(EncryptedFile {
+            encrypted: CIPHER.to_string(),
+            kdf,
+            nonce: STANDARD.encode(nonce),
+            data: STANDARD.encode(data),
+        })
+    }
+
+    fn decrypt(
+        &self,
+        file: &EncryptedFile,
+        salt: Option<&[u8]>,
+    ) -> Result<Vec<u8>, PromptMemoryError> {
+        let invalid = |what: &str| PromptMemoryError::EncryptionError(what.to_string());
+        if file.encrypted != CIPHER {
+            return Err(invalid("unsupported cipher"));
+        }
+        let nonce = STANDARD
+            .decode(&file.nonce)
+            .ok()
+            .filter(|n| n.len() == 12)
+            .ok_or_else(|| invalid("invalid nonce"))?;
+        let data = STANDARD
+            .decode(&file.data)
+            .map_err(|_| invalid("invalid data"))?;
+        Aes256Gcm::new(&self.cipher_key(file.kdf.as_deref(), salt)?.into())
+            .decrypt(Nonce::from_slice(&nonce), data.as_slice())
+            .map_err(|_| invalid("wrong key or corrupted file"))
+    }
+}
+
+/// On-disk layout of an encrypted prompt memory file.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedFile {
+    encrypted: String,
+    /// How the key was derived from a passphrase (`None` for raw keys).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<String>,
+    nonce: String,
+    data: String,
+}
+
+/// Prompt memory in a single JSON file.
+///
+/// Saving takes an exclusive lock on `<file>.lock`, merges, and replaces the
+/// file atomically (write to a temp file, then rename). With a key, the file
+/// is encrypted; plain files are still read and get encrypted on next save.
+#[derive(Debug, Clone)]
+pub struct JsonPromptStore {
+    path: PathBuf,
+    key: Option<EncryptionKey>,
+}
+
+impl JsonPromptStore {
+    /// Create a store for the given JSON file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            key: None,
+        }
+    }
+
+    /// Encrypt the file with the given key.
+    pub fn with_key(mut self, key: EncryptionKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Path of the JSON file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<PromptMemory, PromptMemoryError> {
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+        let content = match serde_json::from_str::<EncryptedFile>(&content) {
+            Ok(file) => {
+                let key = self.key.as_ref().ok_or_else(|| {
+                    PromptMemoryError::EncryptionError(format!(
+                        "file is encrypted, set {} or store the key in the keychain",
+                        PROMPT_MEMORY_KEY_ENV
+                    ))
+                })?;
+                let salt = self.read_salt()?;
+                String::from_utf8(key.decrypt(&file, salt.as_deref())?)
+                    .map_err(|e| PromptMemoryError::ParseError(e.to_string()))?
+            }
+            Err(_) => content,
+        };
+        serde_json::from_str(&content).map_err(|e| PromptMemoryError::ParseError(e.to_string()))
+    }
+
+    /// Salt from `<file>.salt`, if it exists.
+    fn read_salt(&self) -> Result<Option<Vec<u8>>, PromptMemoryError> {
+        let path = sibling_path(&self.path, ".salt");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+        STANDARD
+            .decode(content.trim())
+            .map(Some)
+            .map_err(|_| PromptMemoryError::EncryptionError("invalid salt file".to_string()))
+    }
+
+    /// Salt from `<file>.salt`, created with a random one if missing.
+    fn salt(&self) -> Result<Vec<u8>, PromptMemoryError> {
+        if let Some(salt) = self.read_salt()? {
+            return Ok(salt);
+        }
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        fs::write(sibling_path(&self.path, ".salt"), STANDARD.encode(&salt))
+            .map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+        Ok(salt)
+    }
+}
+
+impl PromptStore for JsonPromptStore {
+    fn load(&self) -> Result<PromptMemory, PromptMemoryError> {
+        if !self.path.exists() {
+            return Ok(PromptMemory::new());
+        }
+        let _lock = lock_file(&self.path, false)?;
+        self.read()
+    }
+
+    fn save(&self, memory: &mut PromptMemory) -> Result<(), PromptMemoryError> {
+        let path = self.path.as_path();
+
+        // Create parent directories if needed
+        create_parent_dir(path)?;
+
+        let _lock = lock_file(path, true)?;
+        if path.exists() {
+            match self.read() {
+                Ok(disk) => memory.merge_concurrent(disk),
+                // Never replace a file we merely cannot decrypt
+                Err(e @ PromptMemoryError::EncryptionError(_)) => return Err(e),
+                Err(e) => tracing::warn!("Overwriting unreadable prompt memory: {}", e),
+            }
+        }
+
+        let mut content = serde_json::to_string_pretty(memory)
+            .map_err(|e| PromptMemoryError::SerializeError(e.to_string()))?;
+        if let Some(key) = &self.key {
+            let salt = key.is_passphrase().then(|| self.salt()).transpose()?;
+            content =
+                serde_json::to_string_pretty(&key.encrypt(content.as_bytes(), salt.as_deref())?)
+                    .map_err(|e| PromptMemoryError::SerializeError(e.to_string()))?;
+        }
+
+        let tmp = sibling_path(path, &format!(".tmp-{}", std::process::id()));
+        let write = || -> std::io::Result<()> {
+            let mut file = File::create(&tmp)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp, path)
+        };
+        if let Err(e) = write() {
+            let _ = fs::remove_file(&tmp);
+            return Err(PromptMemoryError::IoError(e.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Prompt memory in a SQLite database.
+///
+/// Each save runs in one write transaction and only touches the task types
+/// and corrections that differ from what is stored.
+#[derive(Debug, Clone)]
+pub struct SqlitePromptStore {
+    path: PathBuf,
+}
+
+impl SqlitePromptStore {
+    /// Create a store for the given database file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Path of the database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn connect(&self) -> Result<Connection, PromptMemoryError> {
+        create_parent_dir(&self.path)?;
+        let conn = Connection::open(&self.path).map_err(db_error)?;
+        conn.busy_timeout(SQLITE_BUSY_TIMEOUT).map_err(db_error)?;
+        conn.execute_batch(SQLITE_SCHEMA).map_err(db_error)?;
+        migrate_sqlite(&conn)?;
+        Ok(conn)
+    }
+}
+
+impl PromptStore for SqlitePromptStore {
+    fn load(&self) -> Result<PromptMemory, PromptMemoryError> {
+        if !self.path.exists() {
+            return Ok(PromptMemory::new());
+        }
+        read_sqlite(&self.connect()?)
+    }
+
+    fn save(&self, memory: &mut PromptMemory) -> Result<(), PromptMemoryError> {
+        let mut conn = self.connect()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(db_error)?;
+
+        let disk = read_sqlite(&tx)?;
+        memory.merge_concurrent(disk.clone());
+
+        for (task_type, entry) in &memory.prompts {
+            let row = entry_row(entry)?;
+            let stored = disk.prompts.get(task_type);
+            if stored.map(entry_row).transpose()?.as_ref() != Some(&row) {
+                tx.execute(
+                    "INSERT OR REPLACE INTO prompts (task_type, entry) VALUES (?1, ?2)",
+                    params![task_type, row],
+                )
+                .map_err(db_error)?;
+            }
+
+            let stored_keys: HashSet<_> = stored
+                .map(|e| e.corrections.iter().map(correction_key).collect())
+                .unwrap_or_default();
+            for correction in &entry.corrections {
+                if !stored_keys.contains(&correction_key(correction)) {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO corrections \
+                         (task_type, timestamp, content, context, screenshot) \
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            task_type,
+                            correction.timestamp,
+                            correction.content,
+                            correction.context,
+                            correction.screenshot
+                        ],
+                    )
+                    .map_err(db_error)?;
+                }
+            }
+        }
+
+        if memory.aliases != disk.aliases {
+            tx.execute("DELETE FROM aliases", []).map_err(db_error)?;
+            for (alias, task_type) in &memory.aliases {
+                tx.execute(
+                    "INSERT INTO aliases (alias, task_type) VALUES (?1, ?2)",
+                    params![alias, task_type],
+                )
+                .map_err(db_error)?;
+            }
+        }
+
+        // Remove what this process deleted or consolidated
+        for (task_type, stored) in &disk.prompts {
+            let Some(entry) = memory.prompts.get(task_type) else {
+                tx.execute(
+                    "DELETE FROM prompts WHERE task_type = ?1",
+                    params![task_type],
+                )
+                .map_err(db_error)?;
+                tx.execute(
+                    "DELETE FROM corrections WHERE task_type = ?1",
+                    params![task_type],
+                )
+                .map_err(db_error)?;
+                continue;
+            };
+            let keys: HashSet<_> = entry.corrections.iter().map(correction_key).collect();
+            for correction in &stored.corrections {
+                if !keys.contains(&correction_key(correction)) {
+                    tx.execute(
+                        "DELETE FROM corrections \
+                         WHERE task_type = ?1 AND timestamp = ?2 AND content = ?3",
+                        params![task_type, correction.timestamp, correction.content],
+                    )
+                    .map_err(db_error)?;
+                }
+            }
+        }
+
+        tx.commit().map_err(db_error)
+    }
+}
+
+/// Add columns introduced after a database was created.
+fn migrate_sqlite(conn: &Connection) -> Result<(), PromptMemoryError> {
+    let has_screenshot = conn
+        .prepare("SELECT screenshot FROM corrections LIMIT 0")
+        .is_ok();
+    if !has_screenshot {
+        conn.execute("ALTER TABLE corrections ADD COLUMN screenshot TEXT", [])
+            .map_err(db_error)?;
+    }
+    Ok(())
+}
+
+fn read_sqlite(conn: &Connection) -> Result<PromptMemory, PromptMemoryError> {
+    let mut memory = PromptMemory::new();
+
+    let mut stmt = conn
+        .prepare("SELECT task_type, entry FROM prompts")
+        .map_err(db_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(db_error)?;
+    for row in rows {
+        let (task_type, entry) = row.map_err(db_error)?;
+        let entry: PromptEntry = serde_json::from_str(&entry)
+            .map_err(|e| PromptMemoryError::ParseError(e.to_string()))?;
+        memory.prompts.insert(task_type, entry);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT task_type, timestamp, content, context, screenshot \
+             FROM corrections ORDER BY timestamp",
+        )
+        .map_err(db_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                CorrectionRecord {
+                    timestamp: row.get(1)?,
+                    content: row.get(2)?,
+                    context: row.get(3)?,
+                    screenshot: row.get(4)?,
+                },
+            ))
+        })
+        .map_err(db_error)?;
+    for row in rows {
+        let (task_type, correction) = row.map_err(db_error)?;
+        if let Some(entry) = memory.prompts.get_mut(&task_type) {
+            entry.corrections.push(correction);
+        }
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT alias, task_type FROM aliases")
+        .map_err(db_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(db_error)?;
+    for row in rows {
+        let (alias, task_type) = row.map_err(db_error)?;
+        memory.aliases.insert(alias, task_type);
+    }
+    Ok(memory)
+}
+
+/// JSON of an entry without its corrections, which have their own table.
+fn entry_row(entry: &PromptEntry) -> Result<String, PromptMemoryError> {
+    let entry = PromptEntry {
+        corrections: Vec::new(),
+        ..entry.clone()
+    };
+    serde_json::to_string(&entry).map_err(|e| PromptMemoryError::SerializeError(e.to_string()))
+}
+
+fn correction_key(correction: &CorrectionRecord) -> (String, String) {
+    (correction.timestamp.clone(), correction.content.clone())
+}
+
+fn db_error(e: rusqlite::Error) -> PromptMemoryError {
+    PromptMemoryError::DatabaseError(e.to_string())
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), PromptMemoryError> {
+    match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) => {
+            fs::create_dir_all(parent).map_err(|e| PromptMemoryError::IoError(e.to_string()))
+        }
+        None => Ok(()),
+    }
+}
+
+/// `<file name><suffix>` next to `path`.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Lock `<path>.lock` (exclusive for writers, shared for readers). The lock
+/// is released when the returned file is dropped.
+fn lock_file(path: &Path, exclusive: bool) -> Result<File, PromptMemoryError> {
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling_path(path, ".lock"))
+        .map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+    let locked = if exclusive {
+        lock.lock()
+    } else {
+        lock.lock_shared()
+    };
+    locked.map_err(|e| PromptMemoryError::IoError(e.to_string()))?;
+    Ok(lock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_store_roundtrip_and_merge() {
+        let path = std::env::temp_dir().join("phone_agent_test_prompts.db");
+        let _ = fs::remove_file(&path);
+
+        let mut memory = PromptMemory::new();
+        memory.update("shared", "Shared prompt");
+        memory.add_correction("shared", "先等页面加载", Some("点击无响应".to_string()));
+        memory.update("stale", "Stale prompt");
+        memory.save(&path).unwrap();
+
+        let loaded = PromptMemory::load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        let shared = loaded.get("shared").unwrap();
+        assert_eq!(shared.system_prompt, "Shared prompt");
+        assert_eq!(shared.corrections.len(), 1);
+        assert_eq!(shared.corrections[0].context.as_deref(), Some("点击无响应"));
+
+        // Two processes change the database independently
+        let mut gui = PromptMemory::load(&path).unwrap();
+        let mut cli = PromptMemory::load(&path).unwrap();
+        gui.add_correction("shared", "GUI correction", None);
+        gui.remove("stale");
+        cli.add_correction("shared", "CLI correction", None);
+        cli.update("cli_only", "CLI prompt");
+        cli.save(&path).unwrap();
+        gui.save(&path).unwrap();
+
+        let merged = PromptMemory::load(&path).unwrap();
+        assert_eq!(merged.get("shared").unwrap().corrections.len(), 3);
+        assert!(merged.contains("cli_only"));
+        assert!(!merged.contains("stale"));
+
+        // Consolidating corrections removes them from the database
+        let mut memory = PromptMemory::load(&path).unwrap();
+        memory.get_mut("shared").unwrap().clear_corrections();
+        memory.save(&path).unwrap();
+        let loaded = PromptMemory::load(&path).unwrap();
+        assert!(loaded.get("shared").unwrap().corrections.is_empty());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_encrypted_json_store() {
+        let dir = std::env::temp_dir().join(format!(
+            "phone_agent_test_prompts_encrypted_{}",
+            uuid::Uuid::new_v4().simple()
+        ));
+        let path = dir.join("prompts.json");
+        let store = JsonPromptStore::new(&path).with_key(EncryptionKey::from_secret("hunter2"));
+
+        let mut memory = PromptMemory::new();
+        memory.add_correction("微信操作", "给张三发消息前先确认联系人", None);
+        memory.save_to(&store).unwrap();
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(raw.contains("aes-256-gcm"));
+        assert!(raw.contains("argon2id"));
+        assert!(!raw.contains("张三"));
+        // The passphrase is stretched with a salt kept next to the file
+        let salt = fs::read_to_string(sibling_path(&path, ".salt")).unwrap();
+        assert_eq!(STANDARD.decode(salt.trim()).unwrap().len(), SALT_LEN);
+
+        let loaded = PromptMemory::load_from(&store).unwrap();
+        assert_eq!(loaded.pending_corrections("微信操作"), 1);
+
+        // Without the right key the file is neither readable nor overwritten
+        let plain = JsonPromptStore::new(&path);
+        let wrong = JsonPromptStore::new(&path).with_key(EncryptionKey::from_secret("nope"));
+        assert!(matches!(
+            PromptMemory::load_from(&plain),
+            Err(PromptMemoryError::EncryptionError(_))
+        ));
+        assert!(PromptMemory::load_from(&wrong).is_err());
+        assert!(PromptMemory::new().save_to(&plain).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), raw);
+
+        // Files encrypted before the key derivation are still read
+        let key = EncryptionKey::from_secret("hunter2");
+        let legacy = Aes256Gcm::new(&key.cipher_key(None, None).unwrap().into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let file = EncryptedFile {
+            encrypted: CIPHER.to_string(),
+            kdf: None,
+            nonce: STANDARD.encode(nonce),
+            data: STANDARD.encode(legacy.encrypt(&nonce, b"{}".as_slice()).unwrap()),
+        };
+        assert_eq!(key.decrypt(&file, None).unwrap(), b"{}");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_key_sources() {
+        /// A keychain holding only the prompt memory key.
+        struct OneSecret(&'static str);
+
+        impl SecretStore for OneSecret {
+            fn get(&self, account: &str) -> Result<Option<String>, String> {
+                Ok((account == PROMPT_MEMORY_KEY_ACCOUNT).then(|| self.0.to_string()))
+            }
+
+            fn set(&self, _: &str, _: &str) -> Result<(), String> {
+                Err("read-only".to_string())
+            }
+
+            fn delete(&self, _: &str) -> Result<(), String> {
+                Err("read-only".to_string())
+            }
+        }
+
+        let key = EncryptionKey::from_secret_store(&OneSecret("hunter2")).unwrap();
+        assert!(key.is_passphrase());
+        assert!(EncryptionKey::from_secret_store(&OneSecret(" ")).is_none());
+
+        // A base64 32-byte key is used as is, without a salt
+        let raw = EncryptionKey::from_secret(&STANDARD.encode([7u8; 32]));
+        assert!(!raw.is_passphrase());
+        assert_eq!(raw.cipher_key(None, None).unwrap(), [7u8; 32]);
+        assert!(key.cipher_key(Some(KDF_ARGON2ID), None).is_err());
+    }
+
+    #[test]
+    fn test_open_prompt_store_by_extension() {
+        let store = open_prompt_store("memory.SQLite");
+        assert!(format!("{:?}", store).starts_with("SqlitePromptStore"));
+        let store = open_prompt_store("prompt_memory.json");
+        assert!(format!("{:?}", store).starts_with("JsonPromptStore"));
+    }
+}
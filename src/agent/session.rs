@@ -0,0 +1,156 @@
+//! Dual-loop session persistence.
+//!
+//! A `SessionState` captures everything needed to continue an in-flight
+//! multi-task plan after a crash or an intentional restart: the Planner's
+//! conversation, the todo list, pending user input, and the task the
+//! Executor was working on.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use super::todo::TodoList;
+
+/// Current session file format version.
+pub const SESSION_FORMAT_VERSION: u32 = 1;
+
+/// Snapshot of a dual-loop session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionState {
+    /// File format version.
+    pub version: u32,
+    /// When the snapshot was taken (RFC 3339).
+    pub saved_at: String,
+    /// Planner conversation context (system, user and assistant messages).
+    pub planner_context: Vec<Value>,
+    /// Todo list including per-task status.
+    pub todo_list: TodoList,
+    /// User input that was queued but not yet processed.
+    #[serde(default)]
+    pub pending_user_input: Vec<String>,
+    /// ID of the task the Executor was running, if any.
+    pub current_task_id: Option<String>,
+}
+
+impl SessionState {
+    /// Load a session snapshot from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SessionError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(SessionError::NotFound(path.display().to_string()));
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| SessionError::IoError(e.to_string()))?;
+
+        let state: SessionState =
+            serde_json::from_str(&content).map_err(|e| SessionError::ParseError(e.to_string()))?;
+
+        if state.version > SESSION_FORMAT_VERSION {
+            return Err(SessionError::ParseError(format!(
+                "Unsupported session version {} (max {})",
+                state.version, SESSION_FORMAT_VERSION
+            )));
+        }
+
+        Ok(state)
+    }
+
+    /// Save the session snapshot to a JSON file.
+    ///
+    /// The file is written to a temporary sibling first and then renamed,
+    /// so a crash mid-write never leaves a truncated session behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SessionError::IoError(e.to_string()))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| SessionError::SerializeError(e.to_string()))?;
+
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content).map_err(|e| SessionError::IoError(e.to_string()))?;
+        fs::rename(&tmp_path, path).map_err(|e| SessionError::IoError(e.to_string()))
+    }
+
+    /// Remove a session file if it exists.
+    pub fn clear(path: impl AsRef<Path>) -> Result<(), SessionError> {
+        let path = path.as_ref();
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| SessionError::IoError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Session persistence errors.
+#[derive(Debug, Clone)]
+pub enum SessionError {
+    NotFound(String),
+    IoError(String),
+    ParseError(String),
+    SerializeError(String),
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(p) => write!(f, "Session file not found: {}", p),
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::ParseError(e) => write!(f, "Parse error: {}", e),
+            Self::SerializeError(e) => write!(f, "Serialize error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn sample_state() -> SessionState {
+        let mut todo_list = TodoList::new();
+        let id = todo_list.add("打开微信", "微信操作");
+        todo_list.add("发送消息", "微信操作");
+        todo_list.get_mut(&id).unwrap().start();
+
+        SessionState {
+            version: SESSION_FORMAT_VERSION,
+            saved_at: chrono::Utc::now().to_rfc3339(),
+            planner_context: vec![serde_json::json!({"role": "system", "content": "test"})],
+            todo_list,
+            pending_user_input: vec!["然后看朋友圈".to_string()],
+            current_task_id: Some(id),
+        }
+    }
+
+    #[test]
+    fn test_session_save_and_load() {
+        let path = env::temp_dir().join("test_session_state.json");
+        let state = sample_state();
+        state.save(&path).unwrap();
+
+        let loaded = SessionState::load(&path).unwrap();
+        assert_eq!(loaded.planner_context.len(), 1);
+        assert_eq!(loaded.todo_list.items().len(), 2);
+        assert_eq!(loaded.current_task_id.as_deref(), Some("task_1"));
+        assert_eq!(loaded.pending_user_input, vec!["然后看朋友圈".to_string()]);
+
+        SessionState::clear(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_session_load_missing() {
+        let path = env::temp_dir().join("test_session_state_missing.json");
+        let _ = SessionState::clear(&path);
+        assert!(matches!(
+            SessionState::load(&path),
+            Err(SessionError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+//! Token and cost accounting for the dual loop.
+//!
+//! The Planner records the usage reported by every model call (its own and
+//! the Executor's) in a `SpendLedger`. The dual loop checks the ledger
+//! against a `SpendLimit` and pauses both loops when the cap is reached.
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::TokenUsage;
+
+/// Bucket for usage that does not belong to any task (e.g. planning a new request).
+pub const UNASSIGNED_TASK: &str = "(planning)";
+
+/// Hard cap on accumulated spend. Prices are used to estimate cost.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpendLimit {
+    /// Maximum total tokens across both loops (`None` = unlimited).
+    pub max_tokens: Option<u64>,
+    /// Maximum estimated cost across both loops (`None` = unlimited).
+    pub max_cost: Option<f64>,
+    /// Planner price per million tokens.
+    pub planner_price_per_million: f64,
+    /// Executor price per million tokens.
+    pub executor_price_per_million: f64,
+}
+
+impl SpendLimit {
+    /// Whether any cap is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.max_tokens.is_some() || self.max_cost.is_some()
+    }
+}
+
+/// Usage of a single task, split by loop.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskSpend {
+    pub task_id: String,
+    pub planner: TokenUsage,
+    pub executor: TokenUsage,
+}
+
+/// Accumulated usage across both loops.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpendLedger {
+    /// Total Planner usage.
+    pub planner: TokenUsage,
+    /// Total Executor usage.
+    pub executor: TokenUsage,
+    /// Per-task usage in order of first use.
+    pub tasks: Vec<TaskSpend>,
+}
+
+impl SpendLedger {
+    /// Create an empty ledger.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn task_mut(&mut self, task_id: Option<&str>) -> &mut TaskSpend {
+        let task_id = task_id.unwrap_or(UNASSIGNED_TASK);
+        match self.tasks.iter().position(|t| t.task_id == task_id) {
+            Some(pos) => &mut self.tasks[pos],
+            None => {
+                self.tasks.push(TaskSpend {
+                    task_id: task_id.to_string(),
+                    ..TaskSpend::default()
+                });
+                self.tasks.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Record usage of a Planner model call.
+    pub fn record_planner(&mut self, task_id: Option<&str>, usage: &TokenUsage) {
+        if usage.total_tokens == 0 {
+            return;
+        }
+        self.planner.add(usage);
+        self.task_mut(task_id).planner.add(usage);
+    }
+
+    /// Record usage of an Executor step.
+    pub fn record_executor(&mut self, task_id: Option<&str>, usage: &TokenUsage) {
+        if usage.total_tokens == 0 {
+            return;
+        }
+        self.executor.add(usage);
+        self.task_mut(task_id).executor.add(usage);
+    }
+
+    /// Total tokens across both loops.
+    pub fn total_tokens(&self) -> u64 {
+        self.planner.total_tokens + self.executor.total_tokens
+    }
+
+    /// Estimated total cost with the given prices.
+    pub fn cost(&self, limit: &SpendLimit) -> f64 {
+        cost_of(&self.planner, &self.executor, limit)
+    }
+
+    /// Human-readable report with per-task consumption.
+    pub fn report(&self, limit: &SpendLimit) -> String {
+        let mut lines = vec![format!(
+            "总计: {} tokens (Planner {}, Executor {}), 预估花费 {:.4}",
+            self.total_tokens(),
+            self.planner.total_tokens,
+            self.executor.total_tokens,
+            self.cost(limit)
+        )];
+        for task in &self.tasks {
+            lines.push(format!(
+                "  - {}: Planner {} / Executor {} tokens, 预估花费 {:.4}",
+                task.task_id,
+                task.planner.total_tokens,
+                task.executor.total_tokens,
+                cost_of(&task.planner, &task.executor, limit)
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+fn cost_of(planner: &TokenUsage, executor: &TokenUsage, limit: &SpendLimit) -> f64 {
+    (planner.total_tokens as f64 * limit.planner_price_per_million
+        + executor.total_tokens as f64 * limit.executor_price_per_million)
+        / 1_000_000.0
+}
+
+/// Trips once the ledger crosses the limit. After the user chooses to
+/// continue, the thresholds move up by one more limit's worth of spend.
+#[derive(Debug, Clone)]
+pub struct SpendGuard {
+    limit: SpendLimit,
+    token_threshold: Option<u64>,
+    cost_threshold: Option<f64>,
+    tripped: bool,
+}
+
+impl SpendGuard {
+    /// Create a guard for the given limit.
+    pub fn new(limit: SpendLimit) -> Self {
+        Self {
+            token_threshold: limit.max_tokens,
+            cost_threshold: limit.max_cost,
+            limit,
+            tripped: false,
+        }
+    }
+
+    /// The configured limit.
+    pub fn limit(&self) -> &SpendLimit {
+        &self.limit
+    }
+
+    /// Whether the guard is currently tripped (waiting for the user).
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Check the ledger. Returns true only when the guard trips.
+    pub fn check(&mut self, ledger: &SpendLedger) -> bool {
+        if self.tripped {
+            return false;
+        }
+        let over_tokens = self
+            .token_threshold
+            .is_some_and(|max| ledger.total_tokens() >= max);
+        let over_cost = self
+            .cost_threshold
+            .is_some_and(|max| ledger.cost(&self.limit) >= max);
+        self.tripped = over_tokens || over_cost;
+        self.tripped
+    }
+
+    /// Allow another limit's worth of spend on top of the current usage.
+    pub fn extend(&mut self, ledger: &SpendLedger) {
+        if let Some(max) = self.limit.max_tokens {
+            self.token_threshold = Some(ledger.total_tokens() + max);
+        }
+        if let Some(max) = self.limit.max_cost {
+            self.cost_threshold = Some(ledger.cost(&self.limit) + max);
+        }
+        self.tripped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(total: u64) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: total,
+            completion_tokens: 0,
+            total_tokens: total,
+        }
+    }
+
+    #[test]
+    fn test_ledger_per_task() {
+        let mut ledger = SpendLedger::new();
+        ledger.record_planner(None, &usage(100));
+        ledger.record_executor(Some("task_1"), &usage(1000));
+        ledger.record_planner(Some("task_1"), &usage(50));
+
+        assert_eq!(ledger.total_tokens(), 1150);
+        assert_eq!(ledger.tasks.len(), 2);
+        assert_eq!(ledger.tasks[1].executor.total_tokens, 1000);
+
+        let limit = SpendLimit {
+            planner_price_per_million: 2.0,
+            executor_price_per_million: 1.0,
+            ..SpendLimit::default()
+        };
+        assert!((ledger.cost(&limit) - 0.0013).abs() < 1e-9);
+        assert!(ledger.report(&limit).contains("task_1"));
+    }
+
+    #[test]
+    fn test_guard_trips_and_extends() {
+        let mut guard = SpendGuard::new(SpendLimit {
+            max_tokens: Some(1000),
+            ..SpendLimit::default()
+        });
+        let mut ledger = SpendLedger::new();
+
+        ledger.record_executor(Some("task_1"), &usage(600));
+        assert!(!guard.check(&ledger));
+        ledger.record_executor(Some("task_1"), &usage(600));
+        assert!(guard.check(&ledger));
+        // Only trips once until the user continues
+        assert!(!guard.check(&ledger));
+        assert!(guard.is_tripped());
+
+        guard.extend(&ledger);
+        assert!(!guard.check(&ledger));
+        ledger.record_executor(Some("task_1"), &usage(1000));
+        assert!(guard.check(&ledger));
+    }
+}
@@ -3,14 +3,28 @@
 //! This module provides a simple todo list structure for tracking
 //! tasks that the Planner assigns to the Executor.
 
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Matches data references such as `{task_1.data.price}`.
+static DATA_REFERENCE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{(task_\d+)\.data((?:\.[^.{}\s]+)+)\}").unwrap());
 
 /// Todo task status.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum TodoStatus {
     /// Task is pending execution.
+    #[default]
     Pending,
     /// Task is currently running.
     Running,
@@ -22,9 +36,16 @@ pub enum TodoStatus {
     Skipped,
 }
 
-impl Default for TodoStatus {
-    fn default() -> Self {
-        Self::Pending
+impl TodoStatus {
+    /// Icon shown next to tasks with this status.
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            TodoStatus::Pending => "⏳",
+            TodoStatus::Running => "🔄",
+            TodoStatus::Done => "✅",
+            TodoStatus::Failed => "❌",
+            TodoStatus::Skipped => "⏭️",
+        }
     }
 }
 
@@ -51,6 +72,49 @@ pub struct TodoItem {
     pub error: Option<String>,
     /// Execution notes/log.
     pub notes: Vec<String>,
+    /// Unix timestamp before which the task must not start (scheduled tasks).
+    #[serde(default)]
+    pub scheduled_at: Option<u64>,
+    /// ID of the failed task this step was re-planned from.
+    #[serde(default)]
+    pub replanned_from: Option<String>,
+    /// Values extracted by the executor via `finish(data=...)`.
+    #[serde(default)]
+    pub data: Option<Value>,
+    /// IDs of the tasks that must be done before this one starts.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// ID of the umbrella task this is a step of.
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Unix timestamp by which the task should be done.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// Maximum time (in seconds) a single run of the task may take.
+    #[serde(default)]
+    pub max_duration_secs: Option<u64>,
+    /// When the current run started (Unix timestamp).
+    #[serde(default)]
+    pub started_at: Option<u64>,
+    /// Whether the task was reported as overdue.
+    #[serde(default)]
+    pub overdue: bool,
+    /// Recurrence rule (see [`Recurrence`]); a new instance is scheduled
+    /// once this one has finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    /// Free-form labels for grouping and filtering.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// When the task reached its latest final status (Unix timestamp).
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    /// Total time spent running, over all runs (seconds).
+    #[serde(default)]
+    pub run_secs: u64,
+    /// Executor steps taken, over all runs.
+    #[serde(default)]
+    pub step_count: u32,
 }
 
 impl TodoItem {
@@ -72,6 +136,20 @@ impl TodoItem {
             updated_at: now,
             error: None,
             notes: Vec::new(),
+            scheduled_at: None,
+            replanned_from: None,
+            data: None,
+            depends_on: Vec::new(),
+            parent_id: None,
+            deadline: None,
+            max_duration_secs: None,
+            started_at: None,
+            overdue: false,
+            recurrence: None,
+            tags: Vec::new(),
+            finished_at: None,
+            run_secs: 0,
+            step_count: 0,
         }
     }
 
@@ -81,29 +159,141 @@ impl TodoItem {
         self
     }
 
+    /// Schedule the task to start no earlier than the given Unix timestamp.
+    pub fn with_schedule(mut self, scheduled_at: u64) -> Self {
+        self.scheduled_at = Some(scheduled_at);
+        self
+    }
+
+    /// Repeat the task according to a recurrence rule.
+    pub fn with_recurrence(mut self, recurrence: impl Into<String>) -> Self {
+        self.recurrence = Some(recurrence.into());
+        self
+    }
+
+    /// Attach tags (trimmed, empty and duplicate tags dropped).
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.set_tags(tags);
+        self
+    }
+
+    /// Replace the tags (trimmed, empty and duplicate tags dropped).
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags.clear();
+        for tag in tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !self.has_tag(tag) {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    /// Whether the task carries a tag (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    /// Require other tasks to be done before this one starts.
+    pub fn with_depends_on(mut self, depends_on: Vec<String>) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
+    /// Require the task to be done by the given Unix timestamp.
+    pub fn with_deadline(mut self, deadline: u64) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Limit how long a single run of the task may take.
+    pub fn with_max_duration(mut self, secs: u64) -> Self {
+        self.max_duration_secs = Some(secs);
+        self
+    }
+
+    /// Why the task is overdue at the given time, if it is: its deadline
+    /// passed before it finished, or its current run exceeded the maximum
+    /// duration.
+    pub fn overdue_reason(&self, now: u64) -> Option<String> {
+        if !matches!(self.status, TodoStatus::Pending | TodoStatus::Running) {
+            return None;
+        }
+        if let Some(deadline) = self.deadline.filter(|&d| d <= now) {
+            return Some(format!(
+                "deadline {} passed",
+                format_schedule_time(deadline)
+            ));
+        }
+        match (self.status.clone(), self.started_at, self.max_duration_secs) {
+            (TodoStatus::Running, Some(started), Some(max)) if now >= started + max => {
+                Some(format!("running for {}s (max {}s)", now - started, max))
+            }
+            _ => None,
+        }
+    }
+
+    /// Check whether the task may start at the given time.
+    pub fn is_due(&self, now: u64) -> bool {
+        self.scheduled_at.map(|at| at <= now).unwrap_or(true)
+    }
+
     /// Mark task as running.
     pub fn start(&mut self) {
+        let now = current_timestamp();
         self.status = TodoStatus::Running;
-        self.updated_at = current_timestamp();
+        self.started_at = Some(now);
+        // A new run gets a fresh time budget, a missed deadline stays missed
+        self.overdue = self.deadline.is_some_and(|d| d <= now) && self.overdue;
+        self.updated_at = now;
     }
 
     /// Mark task as done.
     pub fn complete(&mut self) {
+        self.end_run();
         self.status = TodoStatus::Done;
-        self.updated_at = current_timestamp();
     }
 
     /// Mark task as failed.
     pub fn fail(&mut self, error: impl Into<String>) {
+        self.end_run();
         self.error = Some(error.into());
         self.status = TodoStatus::Failed;
-        self.updated_at = current_timestamp();
+    }
+
+    /// Account the time of the current run and stamp the finish time.
+    fn end_run(&mut self) {
+        self.account_run();
+        self.finished_at = Some(self.updated_at);
+    }
+
+    /// Add the time of the current run (if any) to `run_secs`.
+    fn account_run(&mut self) {
+        let now = current_timestamp();
+        if self.status == TodoStatus::Running {
+            if let Some(started) = self.started_at {
+                self.run_secs += now.saturating_sub(started);
+            }
+        }
+        self.updated_at = now;
+    }
+
+    /// Time spent running so far, including the current run.
+    pub fn run_time(&self, now: u64) -> u64 {
+        match (&self.status, self.started_at) {
+            (TodoStatus::Running, Some(started)) => self.run_secs + now.saturating_sub(started),
+            _ => self.run_secs,
+        }
+    }
+
+    /// Add executor steps to the task's step count.
+    pub fn record_steps(&mut self, steps: u32) {
+        self.step_count += steps;
     }
 
     /// Increment retry count and return whether more retries are allowed.
     pub fn retry(&mut self) -> bool {
+        self.account_run();
         self.retry_count += 1;
-        self.updated_at = current_timestamp();
         if self.retry_count <= self.max_retries {
             self.status = TodoStatus::Pending;
             true
@@ -115,8 +305,8 @@ impl TodoItem {
 
     /// Skip this task.
     pub fn skip(&mut self) {
+        self.end_run();
         self.status = TodoStatus::Skipped;
-        self.updated_at = current_timestamp();
     }
 
     /// Add a note to the task.
@@ -155,6 +345,134 @@ impl TodoList {
         }
     }
 
+    /// Load a todo list from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TodoError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(TodoError::NotFound(path.display().to_string()));
+        }
+        let content = fs::read_to_string(path).map_err(|e| TodoError::IoError(e.to_string()))?;
+        serde_json::from_str(&content).map_err(|e| TodoError::ParseError(e.to_string()))
+    }
+
+    /// Save the todo list to a JSON file.
+    ///
+    /// The file is written to a temporary sibling first and then renamed,
+    /// so a crash mid-write never leaves a truncated list behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TodoError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| TodoError::IoError(e.to_string()))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| TodoError::SerializeError(e.to_string()))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content).map_err(|e| TodoError::IoError(e.to_string()))?;
+        fs::rename(&tmp_path, path).map_err(|e| TodoError::IoError(e.to_string()))
+    }
+
+    /// Append the tasks of a plan file (see [`PlannedTask`]) and return
+    /// their IDs.
+    ///
+    /// `.yaml`/`.yml` files are read as a list of flat task mappings; any
+    /// other file as JSON (a list of tasks, or `{"tasks": [...]}`).
+    /// `depends_on` and `parent` name the `id`s used in the file; a parent
+    /// has to come before its steps. Nothing is added if any task is invalid.
+    pub fn import(&mut self, path: impl AsRef<Path>) -> Result<Vec<String>, TodoError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(TodoError::NotFound(path.display().to_string()));
+        }
+        let content = fs::read_to_string(path).map_err(|e| TodoError::IoError(e.to_string()))?;
+        let is_yaml = path
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml");
+        let value = if is_yaml {
+            parse_plan_yaml(&content).map_err(TodoError::ParseError)?
+        } else {
+            serde_json::from_str(&content).map_err(|e| TodoError::ParseError(e.to_string()))?
+        };
+        let tasks = match serde_json::from_value::<PlanFile>(value)
+            .map_err(|e| TodoError::ParseError(e.to_string()))?
+        {
+            PlanFile::List(tasks) | PlanFile::Wrapped { tasks } => tasks,
+        };
+
+        let mut list = self.clone();
+        let mut ids: HashMap<String, String> = HashMap::new();
+        let mut added = Vec::with_capacity(tasks.len());
+        for task in &tasks {
+            let id = list.add(&task.description, &task.task_type);
+            if let Some(key) = &task.id {
+                if ids.insert(key.clone(), id.clone()).is_some() {
+                    return Err(TodoError::ParseError(format!("Duplicate task id: {}", key)));
+                }
+            }
+            added.push(id);
+        }
+
+        let resolve = |key: &str| {
+            ids.get(key)
+                .cloned()
+                .ok_or_else(|| TodoError::ParseError(format!("Unknown task id: {}", key)))
+        };
+        let now = Local::now();
+        for (task, id) in tasks.iter().zip(&added) {
+            let depends_on = task
+                .depends_on
+                .iter()
+                .map(|key| resolve(key))
+                .collect::<Result<Vec<_>, _>>()?;
+            if !list.set_depends_on(id, depends_on) {
+                return Err(TodoError::ParseError(format!(
+                    "Dependency cycle at task: {}",
+                    task.description
+                )));
+            }
+            let parent_id = match &task.parent {
+                Some(key) => {
+                    let parent_id = resolve(key)?;
+                    if added.iter().position(|a| a == &parent_id)
+                        >= added.iter().position(|a| a == id)
+                    {
+                        return Err(TodoError::ParseError(format!(
+                            "Parent {} must come before its steps",
+                            key
+                        )));
+                    }
+                    Some(parent_id)
+                }
+                None => None,
+            };
+            let scheduled_at = match (&task.at, &task.repeat) {
+                (Some(at), _) => {
+                    Some(parse_schedule_time(Some(at), None, now).map_err(TodoError::ParseError)?)
+                }
+                (None, Some(rule)) => Some(
+                    Recurrence::parse(rule)
+                        .and_then(|r| r.next_after(now))
+                        .map_err(TodoError::ParseError)?,
+                ),
+                (None, None) => None,
+            };
+            if let Some(rule) = &task.repeat {
+                Recurrence::parse(rule).map_err(TodoError::ParseError)?;
+            }
+
+            let item = list.get_mut(id).expect("task was just added");
+            item.parent_id = parent_id;
+            item.scheduled_at = scheduled_at;
+            item.recurrence = task.repeat.clone();
+            item.set_tags(task.tags.clone());
+            if let Some(max_retries) = task.max_retries {
+                item.max_retries = max_retries;
+            }
+        }
+
+        *self = list;
+        Ok(added)
+    }
+
     /// Add a task with auto-generated ID.
     /// Returns the ID of the newly created task.
     pub fn add(&mut self, description: impl Into<String>, task_type: impl Into<String>) -> String {
@@ -165,6 +483,178 @@ impl TodoList {
         id
     }
 
+    /// Add a task that must not start before `scheduled_at` (Unix timestamp).
+    /// Returns the ID of the newly created task.
+    pub fn add_scheduled(
+        &mut self,
+        description: impl Into<String>,
+        task_type: impl Into<String>,
+        scheduled_at: u64,
+    ) -> String {
+        let id = format!("task_{}", self.next_id);
+        self.next_id += 1;
+        let item = TodoItem::new(id.clone(), description, task_type).with_schedule(scheduled_at);
+        self.items.push(item);
+        id
+    }
+
+    /// Insert a task directly after another task (or at the end if
+    /// `after_id` is not found).
+    /// Returns the ID of the newly created task.
+    pub fn insert_after(
+        &mut self,
+        after_id: &str,
+        description: impl Into<String>,
+        task_type: impl Into<String>,
+    ) -> String {
+        let id = format!("task_{}", self.next_id);
+        self.next_id += 1;
+        let item = TodoItem::new(id.clone(), description, task_type);
+        let pos = self
+            .items
+            .iter()
+            .position(|item| item.id == after_id)
+            .map(|p| p + 1)
+            .unwrap_or(self.items.len());
+        self.items.insert(pos, item);
+        id
+    }
+
+    /// Add a step under an umbrella task, placed after the parent's existing
+    /// steps. The parent is no longer executed itself; its status follows
+    /// its steps (see `sync_parents`).
+    /// Returns `None` if the parent does not exist or has already started.
+    pub fn add_subtask(
+        &mut self,
+        parent_id: &str,
+        description: impl Into<String>,
+        task_type: impl Into<String>,
+    ) -> Option<String> {
+        if self.get(parent_id)?.status != TodoStatus::Pending {
+            return None;
+        }
+        let after_id = self
+            .items
+            .iter()
+            .rev()
+            .find(|item| item.id == parent_id || self.is_descendant_of(&item.id, parent_id))
+            .map(|item| item.id.clone())?;
+        let id = self.insert_after(&after_id, description, task_type);
+        if let Some(item) = self.get_mut(&id) {
+            item.parent_id = Some(parent_id.to_string());
+        }
+        Some(id)
+    }
+
+    /// Direct steps of a task.
+    pub fn children(&self, id: &str) -> Vec<&TodoItem> {
+        self.items
+            .iter()
+            .filter(|item| item.parent_id.as_deref() == Some(id))
+            .collect()
+    }
+
+    /// Whether a task is an umbrella over other steps.
+    pub fn has_children(&self, id: &str) -> bool {
+        self.items
+            .iter()
+            .any(|item| item.parent_id.as_deref() == Some(id))
+    }
+
+    /// Whether a failed task was re-planned into replacement steps.
+    fn was_replanned(&self, id: &str) -> bool {
+        self.items
+            .iter()
+            .any(|item| item.replanned_from.as_deref() == Some(id))
+    }
+
+    /// Reopen the umbrella tasks above `id` that failed because of it, e.g.
+    /// once the failed step was re-planned into new steps.
+    pub fn reopen_parents(&mut self, id: &str) {
+        let mut current = self.get(id).and_then(|item| item.parent_id.clone());
+        while let Some(parent_id) = current {
+            let Some(parent) = self.get_mut(&parent_id) else {
+                return;
+            };
+            if parent.status != TodoStatus::Failed {
+                return;
+            }
+            parent.status = TodoStatus::Pending;
+            parent.error = None;
+            current = parent.parent_id.clone();
+        }
+    }
+
+    /// Nesting level of a task (0 for top-level tasks).
+    pub fn depth(&self, id: &str) -> usize {
+        let mut depth = 0;
+        let mut current = self.get(id).and_then(|item| item.parent_id.as_deref());
+        while let Some(parent) = current.filter(|_| depth < self.items.len()) {
+            depth += 1;
+            current = self.get(parent).and_then(|item| item.parent_id.as_deref());
+        }
+        depth
+    }
+
+    /// Whether `id` is a (possibly nested) step of `ancestor_id`.
+    pub fn is_descendant_of(&self, id: &str, ancestor_id: &str) -> bool {
+        let mut current = self.get(id).and_then(|item| item.parent_id.as_deref());
+        // The depth bound guards against hand-edited cyclic files
+        for _ in 0..self.items.len() {
+            match current {
+                Some(parent) if parent == ancestor_id => return true,
+                Some(parent) => {
+                    current = self.get(parent).and_then(|item| item.parent_id.as_deref())
+                }
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Derive the status of umbrella tasks from their steps: done once all
+    /// steps are done or skipped (skipped if all were skipped), failed once
+    /// all steps finished and one of them failed for good. A failed step
+    /// that was re-planned is judged by its replacement steps instead.
+    /// Nested umbrellas are resolved bottom-up.
+    /// Returns the IDs of the umbrella tasks that changed status.
+    pub fn sync_parents(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        loop {
+            let update = self
+                .items
+                .iter()
+                .filter(|item| item.status == TodoStatus::Pending)
+                .find_map(|item| {
+                    let children = self.children(&item.id);
+                    if children.is_empty() || !children.iter().all(|c| c.is_terminal()) {
+                        return None;
+                    }
+                    let failed = children
+                        .iter()
+                        .find(|c| c.status == TodoStatus::Failed && !self.was_replanned(&c.id))
+                        .map(|c| c.id.clone());
+                    let skipped = children.iter().all(|c| c.status == TodoStatus::Skipped);
+                    Some((item.id.clone(), failed, skipped))
+                });
+            let Some((id, failed, skipped)) = update else {
+                return changed;
+            };
+            if let Some(item) = self.get_mut(&id) {
+                if let Some(child) = failed {
+                    item.fail(format!("Subtask {} failed", child));
+                    // An umbrella is never retried itself
+                    item.retry_count = item.max_retries;
+                } else if skipped {
+                    item.skip();
+                } else {
+                    item.complete();
+                }
+            }
+            changed.push(id);
+        }
+    }
+
     /// Add a task with specific ID.
     /// Returns the ID of the newly created task.
     pub fn add_with_id(
@@ -184,6 +674,30 @@ impl TodoList {
         self.items.iter().find(|item| item.id == id)
     }
 
+    /// Replace `{task_N.data.key}` references with values extracted by
+    /// earlier tasks. Nested keys (`{task_1.data.order.id}`) are supported;
+    /// references that cannot be resolved are left untouched.
+    pub fn resolve_references(&self, text: &str) -> String {
+        DATA_REFERENCE
+            .replace_all(text, |caps: &regex::Captures| {
+                let resolved = self
+                    .get(&caps[1])
+                    .and_then(|task| task.data.as_ref())
+                    .and_then(|data| {
+                        caps[2]
+                            .split('.')
+                            .skip(1)
+                            .try_fold(data, |value, key| value.get(key))
+                    });
+                match resolved {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(other) => other.to_string(),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+
     /// Get a mutable task by ID.
     pub fn get_mut(&mut self, id: &str) -> Option<&mut TodoItem> {
         self.items.iter_mut().find(|item| item.id == id)
@@ -194,11 +708,216 @@ impl TodoList {
         &self.items
     }
 
-    /// Get the next pending task.
+    /// Get the next pending task that is due (scheduled tasks wait for their
+    /// time) and whose dependencies are done. Umbrella tasks are never picked,
+    /// only their steps.
     pub fn next_pending(&self) -> Option<&TodoItem> {
+        let now = current_timestamp();
+        self.items.iter().find(|item| {
+            item.status == TodoStatus::Pending
+                && item.is_due(now)
+                && self.dependencies_met(item)
+                && !self.has_children(&item.id)
+        })
+    }
+
+    /// Get the earliest scheduled pending task whose time has come and whose
+    /// dependencies are done.
+    pub fn next_due_scheduled(&self, now: u64) -> Option<&TodoItem> {
         self.items
             .iter()
-            .find(|item| item.status == TodoStatus::Pending)
+            .filter(|item| item.status == TodoStatus::Pending && item.scheduled_at.is_some())
+            .filter(|item| item.is_due(now) && self.dependencies_met(item))
+            .filter(|item| !self.has_children(&item.id))
+            .min_by_key(|item| item.scheduled_at)
+    }
+
+    /// Flag tasks that became overdue (see `TodoItem::overdue_reason`).
+    /// Returns the newly flagged tasks with the reason, each task only once
+    /// per run.
+    pub fn mark_overdue(&mut self, now: u64) -> Vec<(String, String)> {
+        let mut flagged = Vec::new();
+        for item in self.items.iter_mut().filter(|item| !item.overdue) {
+            if let Some(reason) = item.overdue_reason(now) {
+                item.overdue = true;
+                flagged.push((item.id.clone(), reason));
+            }
+        }
+        flagged
+    }
+
+    /// Whether every dependency of a task is done. Dependencies that were
+    /// removed from the list no longer hold the task back.
+    pub fn dependencies_met(&self, item: &TodoItem) -> bool {
+        item.depends_on.iter().all(|id| {
+            self.get(id)
+                .map(|dep| dep.status == TodoStatus::Done)
+                .unwrap_or(true)
+        })
+    }
+
+    /// Dependencies of a task that are not done yet.
+    pub fn unmet_dependencies(&self, id: &str) -> Vec<String> {
+        let Some(item) = self.get(id) else {
+            return Vec::new();
+        };
+        item.depends_on
+            .iter()
+            .filter(|dep| {
+                self.get(dep)
+                    .is_some_and(|dep| dep.status != TodoStatus::Done)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Set the dependencies of a task.
+    /// Returns false (and changes nothing) if the task or a dependency does
+    /// not exist, or if the dependencies would form a cycle.
+    pub fn set_depends_on(&mut self, id: &str, depends_on: Vec<String>) -> bool {
+        if self.get(id).is_none() || depends_on.iter().any(|dep| self.get(dep).is_none()) {
+            return false;
+        }
+
+        // A cycle exists if the task is reachable from its new dependencies
+        let mut stack: Vec<&str> = depends_on.iter().map(String::as_str).collect();
+        let mut visited = std::collections::HashSet::new();
+        while let Some(current) = stack.pop() {
+            if current == id {
+                return false;
+            }
+            if visited.insert(current) {
+                if let Some(item) = self.get(current) {
+                    stack.extend(item.depends_on.iter().map(String::as_str));
+                }
+            }
+        }
+
+        if let Some(item) = self.get_mut(id) {
+            item.depends_on = depends_on;
+        }
+        true
+    }
+
+    /// Make tasks that depend on `old_id` depend on `new_id` instead (e.g.
+    /// on the last step a failed task was re-planned into).
+    pub fn replace_dependency(&mut self, old_id: &str, new_id: &str) {
+        for item in &mut self.items {
+            if item.id == new_id {
+                continue;
+            }
+            for dep in &mut item.depends_on {
+                if dep == old_id {
+                    *dep = new_id.to_string();
+                }
+            }
+        }
+    }
+
+    /// Skip pending tasks whose dependencies failed for good or were
+    /// skipped, and transitively their dependents.
+    /// Returns the IDs of the skipped tasks.
+    pub fn skip_blocked(&mut self) -> Vec<String> {
+        let mut skipped = Vec::new();
+        loop {
+            let blocked: Vec<(String, String)> = self
+                .items
+                .iter()
+                .filter(|item| item.status == TodoStatus::Pending)
+                .filter_map(|item| {
+                    item.depends_on
+                        .iter()
+                        .find(|dep| {
+                            self.get(dep).is_some_and(|dep| {
+                                dep.status == TodoStatus::Skipped
+                                    || (dep.status == TodoStatus::Failed && !dep.can_retry())
+                            })
+                        })
+                        .map(|dep| (item.id.clone(), dep.clone()))
+                })
+                .collect();
+            if blocked.is_empty() {
+                return skipped;
+            }
+            for (id, dep) in blocked {
+                if let Some(item) = self.get_mut(&id) {
+                    item.add_note(format!("Skipped: dependency {} did not complete", dep));
+                    item.skip();
+                }
+                skipped.push(id);
+            }
+        }
+    }
+
+    /// Schedule the next instance of every finished recurring task.
+    ///
+    /// The recurrence moves to the new instance, so each series has exactly
+    /// one live task; removing it stops the series. Returns the new IDs.
+    pub fn respawn_recurring(&mut self, now: DateTime<Local>) -> Vec<String> {
+        let mut spawned = Vec::new();
+        for i in 0..self.items.len() {
+            if !self.items[i].is_terminal() {
+                continue;
+            }
+            let Some(rule) = self.items[i].recurrence.take() else {
+                continue;
+            };
+            let next = match Recurrence::parse(&rule).and_then(|r| r.next_after(now)) {
+                Ok(next) => next,
+                Err(e) => {
+                    tracing::warn!("Dropping recurrence of {}: {}", self.items[i].id, e);
+                    continue;
+                }
+            };
+
+            let previous = &self.items[i];
+            let id = format!("task_{}", self.next_id);
+            self.next_id += 1;
+            let mut item = TodoItem::new(id.clone(), &previous.description, &previous.task_type)
+                .with_max_retries(previous.max_retries)
+                .with_schedule(next)
+                .with_recurrence(rule);
+            item.max_duration_secs = previous.max_duration_secs;
+            item.tags = previous.tags.clone();
+            self.items.push(item);
+            spawned.push(id);
+        }
+        spawned
+    }
+
+    /// Get all tasks carrying a tag.
+    pub fn by_tag(&self, tag: &str) -> Vec<&TodoItem> {
+        self.items.iter().filter(|item| item.has_tag(tag)).collect()
+    }
+
+    /// Get all tasks with the given status and task type.
+    pub fn by_status_and_type(&self, status: TodoStatus, task_type: &str) -> Vec<&TodoItem> {
+        self.items
+            .iter()
+            .filter(|item| item.status == status && item.task_type == task_type)
+            .collect()
+    }
+
+    /// All tags in use with the number of tasks carrying them, most used
+    /// first.
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for tag in self.items.iter().flat_map(|item| &item.tags) {
+            match counts.iter_mut().find(|(t, _)| t.eq_ignore_ascii_case(tag)) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((tag.clone(), 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Get all pending tasks that are scheduled for a later time.
+    pub fn scheduled_tasks(&self) -> Vec<&TodoItem> {
+        self.items
+            .iter()
+            .filter(|item| item.status == TodoStatus::Pending && item.scheduled_at.is_some())
+            .collect()
     }
 
     /// Get the currently running task.
@@ -255,13 +974,36 @@ impl TodoList {
             .collect()
     }
 
-    /// Remove a task by ID.
+    /// Remove a task by ID, together with its steps.
     pub fn remove(&mut self, id: &str) -> Option<TodoItem> {
-        if let Some(pos) = self.items.iter().position(|item| item.id == id) {
-            Some(self.items.remove(pos))
-        } else {
-            None
+        let pos = self.items.iter().position(|item| item.id == id)?;
+        let descendants: Vec<String> = self
+            .items
+            .iter()
+            .filter(|item| self.is_descendant_of(&item.id, id))
+            .map(|item| item.id.clone())
+            .collect();
+        let removed = self.items.remove(pos);
+        self.items.retain(|item| !descendants.contains(&item.id));
+        Some(removed)
+    }
+
+    /// Update the description and/or task type of a pending task.
+    /// Returns false if the task does not exist or has already started.
+    pub fn update(&mut self, id: &str, description: Option<&str>, task_type: Option<&str>) -> bool {
+        let Some(item) = self.get_mut(id) else {
+            return false;
+        };
+        if item.status != TodoStatus::Pending {
+            return false;
+        }
+        if let Some(description) = description {
+            item.description = description.to_string();
+        }
+        if let Some(task_type) = task_type {
+            item.task_type = task_type.to_string();
         }
+        true
     }
 
     /// Clear all tasks.
@@ -271,9 +1013,19 @@ impl TodoList {
 
     /// Get statistics.
     pub fn stats(&self) -> TodoStats {
+        let now = current_timestamp();
         let mut stats = TodoStats::default();
         for item in &self.items {
             stats.total += 1;
+            let run_secs = item.run_time(now);
+            stats.total_steps += item.step_count;
+            stats.total_run_secs += run_secs;
+            if item.is_terminal() && item.status != TodoStatus::Skipped {
+                let by_type = stats.by_type.entry(item.task_type.clone()).or_default();
+                by_type.finished += 1;
+                by_type.steps += item.step_count;
+                by_type.run_secs += run_secs;
+            }
             match item.status {
                 TodoStatus::Pending => stats.pending += 1,
                 TodoStatus::Running => stats.running += 1,
@@ -306,10 +1058,202 @@ impl TodoList {
         }
         false
     }
+
+    /// Rearrange the given tasks into the given order. The tasks keep the
+    /// slots they occupied between them, so unlisted tasks stay in place.
+    /// Returns false (and changes nothing) on unknown or duplicate IDs.
+    pub fn reorder_many(&mut self, task_ids: &[String]) -> bool {
+        let mut slots = Vec::with_capacity(task_ids.len());
+        for id in task_ids {
+            match self.items.iter().position(|item| &item.id == id) {
+                Some(pos) if !slots.contains(&pos) => slots.push(pos),
+                _ => return false,
+            }
+        }
+
+        let moved: Vec<TodoItem> = slots.iter().map(|&pos| self.items[pos].clone()).collect();
+        slots.sort_unstable();
+        for (pos, item) in slots.into_iter().zip(moved) {
+            self.items[pos] = item;
+        }
+        true
+    }
+}
+
+/// A task of a plan file imported with [`TodoList::import`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlannedTask {
+    /// Name other tasks of the file use to refer to this one.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub description: String,
+    #[serde(default = "default_planned_task_type")]
+    pub task_type: String,
+    /// IDs (within the file) of the tasks that must be done first.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// ID (within the file) of the umbrella task this is a step of.
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Start time (same formats as `schedule_todo`).
+    #[serde(default)]
+    pub at: Option<String>,
+    /// Recurrence rule (see [`Recurrence`]).
+    #[serde(default)]
+    pub repeat: Option<String>,
+}
+
+fn default_planned_task_type() -> String {
+    "通用任务".to_string()
+}
+
+/// Top level of a plan file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PlanFile {
+    List(Vec<PlannedTask>),
+    Wrapped { tasks: Vec<PlannedTask> },
+}
+
+/// Parse the YAML subset used by plan files into JSON: a list (optionally
+/// under `tasks:`) of flat mappings whose values are scalars, `[a, b]` flow
+/// lists or indented `- item` block lists.
+fn parse_plan_yaml(text: &str) -> Result<Value, String> {
+    let mut tasks: Vec<serde_json::Map<String, Value>> = Vec::new();
+    let mut item_indent = None;
+    let mut list_key: Option<String> = None;
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = strip_yaml_comment(raw).trim_end();
+        let content = line.trim_start();
+        if content.is_empty() || (content == "tasks:" && line.len() == content.len()) {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        let error = || format!("Unsupported YAML at line {}: {}", i + 1, raw.trim());
+
+        if let Some(rest) = content
+            .strip_prefix('-')
+            .filter(|r| r.is_empty() || r.starts_with(' '))
+        {
+            let rest = rest.trim();
+            if item_indent.is_none_or(|item| indent <= item) {
+                // A new task
+                item_indent = Some(indent);
+                tasks.push(serde_json::Map::new());
+                list_key = None;
+                if !rest.is_empty() {
+                    let task = tasks.last_mut().ok_or_else(error)?;
+                    list_key = insert_yaml_pair(task, rest).ok_or_else(error)?;
+                }
+            } else {
+                // An item of a block list
+                let key = list_key.as_ref().ok_or_else(error)?;
+                let task = tasks.last_mut().ok_or_else(error)?;
+                if let Some(Value::Array(items)) = task.get_mut(key) {
+                    items.push(parse_yaml_scalar(rest));
+                }
+            }
+        } else {
+            match (item_indent, tasks.last_mut()) {
+                (Some(item), Some(task)) if indent > item => {
+                    list_key = insert_yaml_pair(task, content).ok_or_else(error)?;
+                }
+                _ => return Err(error()),
+            }
+        }
+    }
+    Ok(Value::Array(tasks.into_iter().map(Value::Object).collect()))
+}
+
+/// Insert a `key: value` pair. Returns the key when the value is an empty
+/// block list that the following lines fill.
+fn insert_yaml_pair(
+    task: &mut serde_json::Map<String, Value>,
+    pair: &str,
+) -> Option<Option<String>> {
+    let (key, value) = pair.split_once(':')?;
+    let (key, value) = (key.trim().to_string(), value.trim());
+    if value.is_empty() {
+        task.insert(key.clone(), Value::Array(Vec::new()));
+        return Some(Some(key));
+    }
+    let value = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(items) => Value::Array(
+            items
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(parse_yaml_scalar)
+                .collect(),
+        ),
+        None => parse_yaml_scalar(value),
+    };
+    task.insert(key, value);
+    Some(None)
+}
+
+fn parse_yaml_scalar(value: &str) -> Value {
+    let quoted = ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)));
+    if let Some(text) = quoted {
+        return Value::String(text.to_string());
+    }
+    if let Ok(n) = value.parse::<u64>() {
+        return Value::from(n);
+    }
+    match value {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(value.to_string()),
+    }
+}
+
+/// Drop a `#` comment that is not inside quotes.
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('#', None) if i == 0 || line[..i].ends_with(char::is_whitespace) => {
+                return &line[..i];
+            }
+            _ => {}
+        }
+    }
+    line
 }
 
+/// Todo list persistence errors.
+#[derive(Debug, Clone)]
+pub enum TodoError {
+    NotFound(String),
+    IoError(String),
+    ParseError(String),
+    SerializeError(String),
+}
+
+impl std::fmt::Display for TodoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(p) => write!(f, "Todo list file not found: {}", p),
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::ParseError(e) => write!(f, "Parse error: {}", e),
+            Self::SerializeError(e) => write!(f, "Serialize error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TodoError {}
+
 /// Todo list statistics.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TodoStats {
     pub total: usize,
     pub pending: usize,
@@ -317,9 +1261,60 @@ pub struct TodoStats {
     pub done: usize,
     pub failed: usize,
     pub skipped: usize,
+    /// Executor steps over all tasks.
+    #[serde(default)]
+    pub total_steps: u32,
+    /// Run time over all tasks (seconds).
+    #[serde(default)]
+    pub total_run_secs: u64,
+    /// Cost of finished (done or failed) tasks per task type.
+    #[serde(default)]
+    pub by_type: BTreeMap<String, TaskTypeStats>,
+}
+
+/// Run time and steps of the finished tasks of one task type.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskTypeStats {
+    /// Number of finished tasks.
+    pub finished: usize,
+    /// Executor steps over those tasks.
+    pub steps: u32,
+    /// Run time over those tasks (seconds).
+    pub run_secs: u64,
+}
+
+impl TaskTypeStats {
+    /// Average executor steps per task.
+    pub fn avg_steps(&self) -> f64 {
+        if self.finished == 0 {
+            0.0
+        } else {
+            self.steps as f64 / self.finished as f64
+        }
+    }
+
+    /// Average run time per task (seconds).
+    pub fn avg_run_secs(&self) -> f64 {
+        if self.finished == 0 {
+            0.0
+        } else {
+            self.run_secs as f64 / self.finished as f64
+        }
+    }
 }
 
 impl TodoStats {
+    /// Task types ordered by average run time, slowest first.
+    pub fn slowest_types(&self) -> Vec<(&str, &TaskTypeStats)> {
+        let mut types: Vec<(&str, &TaskTypeStats)> = self
+            .by_type
+            .iter()
+            .map(|(task_type, stats)| (task_type.as_str(), stats))
+            .collect();
+        types.sort_by(|a, b| b.1.avg_run_secs().total_cmp(&a.1.avg_run_secs()));
+        types
+    }
+
     /// Calculate completion percentage.
     pub fn completion_percentage(&self) -> f32 {
         if self.total == 0 {
@@ -330,6 +1325,255 @@ impl TodoStats {
     }
 }
 
+/// Resolve a schedule into a Unix timestamp.
+///
+/// `at` accepts RFC 3339 (`2025-01-01T21:00:00+08:00`), local
+/// `YYYY-MM-DD HH:MM[:SS]`, or local `HH:MM` (today, or tomorrow if that
+/// time has already passed). `delay_secs` is relative to `now` and is
+/// used when `at` is not given.
+pub fn parse_schedule_time(
+    at: Option<&str>,
+    delay_secs: Option<u64>,
+    now: DateTime<Local>,
+) -> Result<u64, String> {
+    if let Some(at) = at.map(str::trim).filter(|s| !s.is_empty()) {
+        if let Ok(dt) = DateTime::parse_from_rfc3339(at) {
+            return Ok(dt.timestamp().max(0) as u64);
+        }
+
+        for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(at, fmt) {
+                return Local
+                    .from_local_datetime(&naive)
+                    .earliest()
+                    .map(|dt| dt.timestamp().max(0) as u64)
+                    .ok_or_else(|| format!("Invalid local time: {}", at));
+            }
+        }
+
+        for fmt in ["%H:%M:%S", "%H:%M"] {
+            if let Ok(time) = NaiveTime::parse_from_str(at, fmt) {
+                let mut date = now.date_naive();
+                if time <= now.time() {
+                    date = date.succ_opt().ok_or("Date out of range")?;
+                }
+                return Local
+                    .from_local_datetime(&date.and_time(time))
+                    .earliest()
+                    .map(|dt| dt.timestamp().max(0) as u64)
+                    .ok_or_else(|| format!("Invalid local time: {}", at));
+            }
+        }
+
+        return Err(format!("Unrecognized time format: {}", at));
+    }
+
+    match delay_secs {
+        Some(delay) => Ok(now.timestamp().max(0) as u64 + delay),
+        None => Err("Either 'at' or 'delay_secs' is required".to_string()),
+    }
+}
+
+/// Format a Unix timestamp as local time for display.
+pub fn format_schedule_time(timestamp: u64) -> String {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|dt| {
+            dt.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// How often a recurring task repeats.
+///
+/// Rules are written as text so the planner can produce them:
+/// `every 30m` (also `s`, `h`, `d`), `daily 08:00`, `weekly mon,thu 08:00`,
+/// or a five-field cron expression (`minute hour day month weekday`, with
+/// `*`, lists, ranges and `*/n` steps; weekday 0 or 7 is Sunday).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recurrence {
+    /// A fixed interval in seconds, counted from when the last run finished.
+    Interval(u64),
+    /// Every day at the given local time.
+    Daily(NaiveTime),
+    /// On the given weekdays at the given local time.
+    Weekly(Vec<Weekday>, NaiveTime),
+    /// A cron expression.
+    Cron(CronSchedule),
+}
+
+impl Recurrence {
+    /// Parse a recurrence rule.
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = rule.split_whitespace().collect();
+        match parts.as_slice() {
+            ["every", interval] => parse_interval(interval).map(Self::Interval),
+            ["daily", time] => parse_time_of_day(time).map(Self::Daily),
+            ["weekly", days, time] => {
+                let days = days
+                    .split(',')
+                    .map(|d| {
+                        d.parse::<Weekday>()
+                            .map_err(|_| format!("Unknown weekday: {}", d))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::Weekly(days, parse_time_of_day(time)?))
+            }
+            [_, _, _, _, _] => CronSchedule::parse(&parts).map(Self::Cron),
+            _ => Err(format!("Unrecognized recurrence rule: {}", rule)),
+        }
+    }
+
+    /// The first occurrence strictly after `after`, as a Unix timestamp.
+    pub fn next_after(&self, after: DateTime<Local>) -> Result<u64, String> {
+        let next = match self {
+            Self::Interval(secs) => {
+                return Ok(after.timestamp().max(0) as u64 + secs);
+            }
+            Self::Daily(time) => next_matching(after, |_| true, |t| t == *time),
+            Self::Weekly(days, time) => {
+                next_matching(after, |d| days.contains(&d.weekday()), |t| t == *time)
+            }
+            Self::Cron(cron) => {
+                next_matching(after, |d| cron.matches_date(d), |t| cron.matches_time(t))
+            }
+        };
+        next.ok_or_else(|| "No occurrence within a year".to_string())
+    }
+}
+
+/// A parsed five-field cron expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days: Vec<u32>,
+    months: Vec<u32>,
+    weekdays: Vec<u32>,
+    /// Whether day and weekday were both restricted (either may match).
+    day_or_weekday: bool,
+}
+
+impl CronSchedule {
+    fn parse(fields: &[&str]) -> Result<Self, String> {
+        let weekdays: Vec<u32> = parse_cron_field(fields[4], 0, 7)?
+            .into_iter()
+            .map(|d| d % 7)
+            .collect();
+        Ok(Self {
+            minutes: parse_cron_field(fields[0], 0, 59)?,
+            hours: parse_cron_field(fields[1], 0, 23)?,
+            days: parse_cron_field(fields[2], 1, 31)?,
+            months: parse_cron_field(fields[3], 1, 12)?,
+            weekdays,
+            day_or_weekday: fields[2] != "*" && fields[4] != "*",
+        })
+    }
+
+    fn matches_date(&self, date: chrono::NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let day = self.days.contains(&date.day());
+        let weekday = self
+            .weekdays
+            .contains(&date.weekday().num_days_from_sunday());
+        if self.day_or_weekday {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    fn matches_time(&self, time: NaiveTime) -> bool {
+        self.hours.contains(&time.hour()) && self.minutes.contains(&time.minute())
+    }
+}
+
+/// Parse one cron field into the sorted values it allows.
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+    let invalid = || format!("Invalid cron field: {}", field);
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (
+                    a.parse().map_err(|_| invalid())?,
+                    b.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let v = range.parse().map_err(|_| invalid())?;
+                    (v, v)
+                }
+            },
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+/// Parse an interval such as `90s`, `30m`, `2h` or `1d` into seconds.
+fn parse_interval(interval: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid interval: {}", interval);
+    let split = interval.len().saturating_sub(1);
+    let (value, unit) = interval.split_at(split);
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(invalid()),
+    };
+    match value * unit {
+        0 => Err(invalid()),
+        secs => Ok(secs),
+    }
+}
+
+fn parse_time_of_day(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("Invalid time: {}", time))
+}
+
+/// Find the first local minute after `after` whose date and time match.
+fn next_matching(
+    after: DateTime<Local>,
+    date_matches: impl Fn(chrono::NaiveDate) -> bool,
+    time_matches: impl Fn(NaiveTime) -> bool,
+) -> Option<u64> {
+    let start = after.naive_local().date();
+    for offset in 0..=366 {
+        let date = start + Duration::days(offset);
+        if !date_matches(date) {
+            continue;
+        }
+        for minute in 0..24 * 60 {
+            let time = NaiveTime::from_hms_opt(minute / 60, minute % 60, 0)?;
+            if !time_matches(time) {
+                continue;
+            }
+            let Some(dt) = Local.from_local_datetime(&date.and_time(time)).earliest() else {
+                continue;
+            };
+            if dt > after {
+                return Some(dt.timestamp().max(0) as u64);
+            }
+        }
+    }
+    None
+}
+
 /// Get current Unix timestamp.
 fn current_timestamp() -> u64 {
     SystemTime::now()
@@ -342,6 +1586,25 @@ fn current_timestamp() -> u64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resolve_references() {
+        let mut list = TodoList::new();
+        let id = list.add("查询订单", "购物");
+        list.get_mut(&id).unwrap().data =
+            Some(serde_json::json!({"price": "12.50", "order": {"id": "A123"}, "count": 2}));
+
+        assert_eq!(
+            list.resolve_references("价格是 {task_1.data.price}，订单 {task_1.data.order.id}"),
+            "价格是 12.50，订单 A123"
+        );
+        assert_eq!(list.resolve_references("{task_1.data.count} 件"), "2 件");
+        // Unknown keys and tasks stay as-is
+        assert_eq!(
+            list.resolve_references("{task_1.data.missing} {task_9.data.price}"),
+            "{task_1.data.missing} {task_9.data.price}"
+        );
+    }
+
     #[test]
     fn test_todo_item_lifecycle() {
         let mut item = TodoItem::new("test_1", "Test task", "general");
@@ -404,4 +1667,427 @@ mod tests {
         assert_eq!(stats.done, 1);
         assert_eq!(stats.pending, 2);
     }
+
+    #[test]
+    fn test_update_and_reorder_many() {
+        let mut list = TodoList::new();
+        let a = list.add("Task A", "general");
+        let b = list.add("Task B", "general");
+        let c = list.add("Task C", "general");
+        let d = list.add("Task D", "general");
+
+        assert!(list.update(&b, Some("Task B2"), None));
+        assert_eq!(list.get(&b).unwrap().description, "Task B2");
+        assert_eq!(list.get(&b).unwrap().task_type, "general");
+
+        list.get_mut(&a).unwrap().start();
+        assert!(!list.update(&a, Some("changed"), None));
+        assert!(!list.update("missing", Some("changed"), None));
+
+        // D and B swap slots, A and C stay where they are
+        assert!(list.reorder_many(&[d.clone(), b.clone()]));
+        let order: Vec<_> = list.items().iter().map(|t| t.id.clone()).collect();
+        assert_eq!(order, vec![a.clone(), d.clone(), c.clone(), b.clone()]);
+
+        assert!(!list.reorder_many(&[c.clone(), c.clone()]));
+        assert!(!list.reorder_many(&[c, "missing".to_string()]));
+        let unchanged: Vec<_> = list.items().iter().map(|t| t.id.clone()).collect();
+        assert_eq!(unchanged, order);
+    }
+
+    #[test]
+    fn test_insert_after() {
+        let mut list = TodoList::new();
+        list.add("Task 1", "general");
+        list.add("Task 2", "general");
+
+        let id = list.insert_after("task_1", "Task 1b", "general");
+        assert_eq!(id, "task_3");
+        let ids: Vec<_> = list.items().iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["task_1", "task_3", "task_2"]);
+
+        list.insert_after("missing", "Task end", "general");
+        assert_eq!(list.items().last().unwrap().id, "task_4");
+    }
+
+    #[test]
+    fn test_scheduled_tasks() {
+        let mut list = TodoList::new();
+        let now = current_timestamp();
+
+        let later = list.add_scheduled("Post at 9pm", "social", now + 3600);
+        let due = list.add_scheduled("Overdue post", "social", now - 10);
+
+        // The future task is skipped, the overdue one is picked
+        assert_eq!(list.next_pending().unwrap().id, due);
+        assert_eq!(list.next_due_scheduled(now).unwrap().id, due);
+        assert_eq!(list.scheduled_tasks().len(), 2);
+
+        list.get_mut(&due).unwrap().complete();
+        assert!(list.next_pending().is_none());
+        assert!(list.next_due_scheduled(now).is_none());
+        assert_eq!(list.next_due_scheduled(now + 3600).unwrap().id, later);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join("test_todo_save_and_load.json");
+        let _ = fs::remove_file(&path);
+        assert!(matches!(TodoList::load(&path), Err(TodoError::NotFound(_))));
+
+        let mut list = TodoList::new();
+        let t1 = list.add("Open WeChat", "wechat");
+        let t2 = list.add("Send message", "wechat");
+        list.get_mut(&t1).unwrap().complete();
+        list.get_mut(&t2).unwrap().start();
+        list.get_mut(&t2).unwrap().retry();
+        list.save(&path).unwrap();
+
+        let mut loaded = TodoList::load(&path).unwrap();
+        assert_eq!(loaded.get(&t1).unwrap().status, TodoStatus::Done);
+        assert_eq!(loaded.get(&t2).unwrap().retry_count, 1);
+        // IDs keep counting from where the saved list stopped
+        assert_eq!(loaded.add("Check moments", "wechat"), "task_3");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_subtasks() {
+        let mut list = TodoList::new();
+        let post = list.add("发布一条小红书", "小红书发布");
+        let other = list.add("查看消息", "小红书浏览");
+        let photo = list.add_subtask(&post, "选择照片", "小红书发布").unwrap();
+        let caption = list.add_subtask(&post, "填写文案", "小红书发布").unwrap();
+        let publish = list.add_subtask(&post, "点击发布", "小红书发布").unwrap();
+        assert!(list.add_subtask("task_99", "x", "y").is_none());
+
+        // Steps sit under their parent in order; the parent itself never runs
+        let ids: Vec<_> = list.items().iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec![&post, &photo, &caption, &publish, &other]);
+        assert_eq!(list.children(&post).len(), 3);
+        assert_eq!(list.next_pending().unwrap().id, photo);
+
+        list.get_mut(&photo).unwrap().complete();
+        list.get_mut(&caption).unwrap().complete();
+        assert!(list.sync_parents().is_empty());
+        assert_eq!(list.get(&post).unwrap().status, TodoStatus::Pending);
+
+        list.get_mut(&publish).unwrap().complete();
+        assert_eq!(list.sync_parents(), vec![post.clone()]);
+        assert_eq!(list.get(&post).unwrap().status, TodoStatus::Done);
+        assert_eq!(list.next_pending().unwrap().id, other);
+
+        // A step that failed for good fails its parent, which is final
+        let share = list.add("分享笔记", "小红书发布");
+        let nested = list
+            .add_subtask(&share, "打开分享菜单", "小红书发布")
+            .unwrap();
+        let step = list.add_subtask(&nested, "点击分享", "小红书发布").unwrap();
+        assert!(list.is_descendant_of(&step, &share));
+        list.get_mut(&step).unwrap().max_retries = 0;
+        list.get_mut(&step).unwrap().fail("button missing");
+        assert_eq!(list.sync_parents(), vec![nested.clone(), share.clone()]);
+        assert!(list.get(&share).unwrap().is_terminal());
+
+        // Re-planning the failed step reopens its parents until the new step ends
+        let retry = list.insert_after(&step, "长按后点击分享", "小红书发布");
+        let item = list.get_mut(&retry).unwrap();
+        item.replanned_from = Some(step.clone());
+        item.parent_id = Some(nested.clone());
+        list.reopen_parents(&step);
+        assert_eq!(list.get(&share).unwrap().status, TodoStatus::Pending);
+        assert!(list.sync_parents().is_empty());
+        list.get_mut(&retry).unwrap().complete();
+        assert_eq!(list.sync_parents(), vec![nested.clone(), share.clone()]);
+        assert_eq!(list.get(&share).unwrap().status, TodoStatus::Done);
+        assert_eq!(list.depth(&retry), 2);
+
+        // Removing a parent removes its steps
+        list.remove(&share);
+        assert!(list.get(&nested).is_none() && list.get(&step).is_none());
+    }
+
+    #[test]
+    fn test_overdue_tasks() {
+        let mut list = TodoList::new();
+        let now = current_timestamp();
+        let late = list.add("Sign in before noon", "checkin");
+        let slow = list.add("Browse feed", "browse");
+        list.get_mut(&late).unwrap().deadline = Some(now - 10);
+        list.get_mut(&slow).unwrap().max_duration_secs = Some(300);
+        list.get_mut(&slow).unwrap().start();
+        assert!(list.mark_overdue(now - 100).is_empty());
+
+        // Past the deadline and the run budget; each is reported once
+        let flagged = list.mark_overdue(now + 600);
+        let ids: Vec<&str> = flagged.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec![late.as_str(), slow.as_str()]);
+        assert!(flagged[1].1.contains("max 300s"));
+        assert!(list.mark_overdue(now + 700).is_empty());
+
+        // A retry gets a fresh budget, a missed deadline stays reported
+        list.get_mut(&slow).unwrap().start();
+        list.get_mut(&late).unwrap().start();
+        assert!(!list.get(&slow).unwrap().overdue);
+        assert!(list.get(&late).unwrap().overdue);
+
+        list.get_mut(&slow).unwrap().complete();
+        assert!(list
+            .get(&slow)
+            .unwrap()
+            .overdue_reason(now + 9999)
+            .is_none());
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let mut list = TodoList::new();
+        let t1 = list.add("Open WeChat", "wechat");
+        let t2 = list.add("Open Maps", "maps");
+        let t3 = list.add("Send the route", "wechat");
+        let t4 = list.add("Post a moment", "wechat");
+
+        // task_3 only after task_1 and task_2, even though it comes first
+        assert!(list.set_depends_on(&t3, vec![t1.clone(), t2.clone()]));
+        list.reorder_many(&[t3.clone(), t1.clone(), t2.clone()]);
+        assert_eq!(list.next_pending().unwrap().id, t1);
+        assert_eq!(list.unmet_dependencies(&t3), vec![t1.clone(), t2.clone()]);
+
+        list.get_mut(&t1).unwrap().complete();
+        assert_eq!(list.next_pending().unwrap().id, t2);
+        list.get_mut(&t2).unwrap().complete();
+        assert_eq!(list.next_pending().unwrap().id, t3);
+
+        // Unknown dependencies and cycles are rejected
+        assert!(!list.set_depends_on(&t4, vec!["task_99".to_string()]));
+        assert!(list.set_depends_on(&t4, vec![t3.clone()]));
+        assert!(!list.set_depends_on(&t3, vec![t4.clone()]));
+        assert_eq!(list.get(&t3).unwrap().depends_on, vec![t1, t2]);
+
+        // A failed prerequisite skips its dependents transitively
+        let t5 = list.add("Share the post", "wechat");
+        assert!(list.set_depends_on(&t5, vec![t4.clone()]));
+        list.get_mut(&t3).unwrap().max_retries = 0;
+        list.get_mut(&t3).unwrap().fail("no route");
+        assert_eq!(list.skip_blocked(), vec![t4.clone(), t5.clone()]);
+        assert_eq!(list.get(&t5).unwrap().status, TodoStatus::Skipped);
+        assert!(list.is_all_done());
+
+        // Re-planned steps take over a failed task's dependents
+        let t6 = list.add("Retry sharing", "wechat");
+        let t7 = list.add("Report", "general");
+        assert!(list.set_depends_on(&t7, vec![t3.clone()]));
+        list.replace_dependency(&t3, &t6);
+        assert_eq!(list.get(&t7).unwrap().depends_on, vec![t6]);
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = std::env::temp_dir().join("test_todo_import");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let yaml = dir.join("plan.yaml");
+        fs::write(
+            &yaml,
+            r#"# 周末采购
+tasks:
+  - id: milk
+    description: "比较牛奶价格: 京东和淘宝"
+    task_type: 购物
+    tags: [比价, 周末]
+  - id: order
+    description: 下单最便宜的牛奶
+    task_type: 购物
+    depends_on:
+      - milk
+    max_retries: 1
+  - description: 签到  # 每天一次
+    task_type: 签到
+    repeat: daily 08:00
+"#,
+        )
+        .unwrap();
+
+        let mut list = TodoList::new();
+        list.add("打开微信", "微信操作");
+        let ids = list.import(&yaml).unwrap();
+        assert_eq!(ids, vec!["task_2", "task_3", "task_4"]);
+        let milk = list.get("task_2").unwrap();
+        assert_eq!(milk.description, "比较牛奶价格: 京东和淘宝");
+        assert_eq!(milk.tags, vec!["比价", "周末"]);
+        let order = list.get("task_3").unwrap();
+        assert_eq!(order.depends_on, vec!["task_2"]);
+        assert_eq!(order.max_retries, 1);
+        let check_in = list.get("task_4").unwrap();
+        assert_eq!(check_in.recurrence.as_deref(), Some("daily 08:00"));
+        assert!(check_in.scheduled_at.is_some());
+
+        // JSON plans, and nothing is added when a reference is wrong
+        let json = dir.join("plan.json");
+        fs::write(
+            &json,
+            r#"{"tasks": [
+                {"id": "post", "description": "发布一条小红书", "task_type": "小红书发布"},
+                {"description": "选择照片", "task_type": "小红书发布", "parent": "post"}
+            ]}"#,
+        )
+        .unwrap();
+        let ids = list.import(&json).unwrap();
+        assert_eq!(
+            list.get(&ids[1]).unwrap().parent_id.as_deref(),
+            Some("task_5")
+        );
+
+        fs::write(
+            &json,
+            r#"[{"description": "下单", "depends_on": ["missing"]}]"#,
+        )
+        .unwrap();
+        assert!(matches!(list.import(&json), Err(TodoError::ParseError(_))));
+        assert_eq!(list.items().len(), 6);
+        assert!(matches!(
+            list.import(dir.join("missing.yaml")),
+            Err(TodoError::NotFound(_))
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_execution_tracking() {
+        let mut list = TodoList::new();
+        let slow = list.add("比较牛奶价格", "购物");
+        let fast = list.add("签到", "签到");
+
+        // Two runs of the slow task: a failed one and a successful retry
+        let item = list.get_mut(&slow).unwrap();
+        item.start();
+        item.started_at = Some(item.started_at.unwrap() - 40);
+        item.record_steps(8);
+        item.fail("找不到按钮");
+        assert!(item.finished_at.is_some());
+        assert!(item.retry());
+        item.start();
+        item.started_at = Some(item.started_at.unwrap() - 20);
+        assert!(item.run_time(current_timestamp()) >= 60);
+        item.record_steps(4);
+        item.complete();
+        assert_eq!(item.step_count, 12);
+        assert!((60..62).contains(&item.run_secs));
+
+        let item = list.get_mut(&fast).unwrap();
+        item.start();
+        item.record_steps(2);
+        item.complete();
+
+        let stats = list.stats();
+        assert_eq!(stats.total_steps, 14);
+        assert_eq!(stats.by_type["购物"].finished, 1);
+        assert_eq!(stats.by_type["购物"].avg_steps(), 12.0);
+        let slowest: Vec<&str> = stats.slowest_types().iter().map(|(t, _)| *t).collect();
+        assert_eq!(slowest, vec!["购物", "签到"]);
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut list = TodoList::new();
+        let first = list.add("买牛奶", "购物");
+        let second = list.add("买面包", "购物");
+        let third = list.add("签到", "签到");
+        list.get_mut(&first).unwrap().set_tags(vec![
+            "Daily".to_string(),
+            " daily ".to_string(),
+            "".to_string(),
+        ]);
+        list.get_mut(&third)
+            .unwrap()
+            .set_tags(vec!["daily".to_string(), "app".to_string()]);
+        assert_eq!(list.get(&first).unwrap().tags, vec!["Daily"]);
+
+        let daily: Vec<&str> = list.by_tag("DAILY").iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(daily, vec![first.as_str(), third.as_str()]);
+        assert!(list.by_tag("missing").is_empty());
+        assert_eq!(
+            list.tag_counts(),
+            vec![("Daily".to_string(), 2), ("app".to_string(), 1)]
+        );
+
+        list.get_mut(&second).unwrap().complete();
+        let done = list.by_status_and_type(TodoStatus::Done, "购物");
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].id, second);
+        assert_eq!(
+            list.by_status_and_type(TodoStatus::Pending, "购物").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_recurrence() {
+        let now = Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap(); // Wednesday
+        let ts = |y, m, d, h, min| {
+            Local
+                .with_ymd_and_hms(y, m, d, h, min, 0)
+                .unwrap()
+                .timestamp() as u64
+        };
+
+        let daily = Recurrence::parse("daily 08:00").unwrap();
+        assert_eq!(daily.next_after(now).unwrap(), ts(2025, 1, 2, 8, 0));
+        let weekly = Recurrence::parse("weekly mon,thu 09:30").unwrap();
+        assert_eq!(weekly.next_after(now).unwrap(), ts(2025, 1, 2, 9, 30));
+        let every = Recurrence::parse("every 2h").unwrap();
+        assert_eq!(
+            every.next_after(now).unwrap(),
+            now.timestamp() as u64 + 7200
+        );
+        let cron = Recurrence::parse("*/15 8-9 * * 1-5").unwrap();
+        assert_eq!(cron.next_after(now).unwrap(), ts(2025, 1, 2, 8, 0));
+        let cron = Recurrence::parse("0 12 1 * *").unwrap();
+        assert_eq!(cron.next_after(now).unwrap(), ts(2025, 2, 1, 12, 0));
+        assert!(Recurrence::parse("every 0m").is_err());
+        assert!(Recurrence::parse("0 25 * * *").is_err());
+        assert!(Recurrence::parse("sometimes").is_err());
+
+        // A finished instance hands the series over to a new scheduled task
+        let mut list = TodoList::new();
+        let id = list.add("签到", "签到");
+        list.get_mut(&id).unwrap().recurrence = Some("daily 08:00".to_string());
+        assert!(list.respawn_recurring(now).is_empty());
+        list.get_mut(&id).unwrap().complete();
+        let spawned = list.respawn_recurring(now);
+        assert_eq!(spawned.len(), 1);
+        let next = list.get(&spawned[0]).unwrap();
+        assert_eq!(next.status, TodoStatus::Pending);
+        assert_eq!(next.scheduled_at, Some(ts(2025, 1, 2, 8, 0)));
+        assert_eq!(next.recurrence.as_deref(), Some("daily 08:00"));
+        assert!(list.get(&id).unwrap().recurrence.is_none());
+        assert!(list.respawn_recurring(now).is_empty());
+    }
+
+    #[test]
+    fn test_parse_schedule_time() {
+        let now = Local.with_ymd_and_hms(2025, 1, 1, 20, 0, 0).unwrap();
+        let base = now.timestamp() as u64;
+
+        assert_eq!(parse_schedule_time(None, Some(60), now), Ok(base + 60));
+        assert_eq!(
+            parse_schedule_time(Some("21:00"), None, now),
+            Ok(base + 3600)
+        );
+        // A time that already passed today rolls over to tomorrow
+        assert_eq!(
+            parse_schedule_time(Some("19:00"), None, now),
+            Ok(base + 23 * 3600)
+        );
+        assert_eq!(
+            parse_schedule_time(Some("2025-01-01 22:30"), None, now),
+            Ok(base + 9000)
+        );
+        assert!(parse_schedule_time(Some("2025-01-01T12:00:00+00:00"), None, now).is_ok());
+        assert!(parse_schedule_time(Some("tonight"), None, now).is_err());
+        assert!(parse_schedule_time(None, None, now).is_err());
+    }
 }
@@ -3,125 +3,23 @@
 //! This is the CLI entry point for the phone-agent tool.
 //! Run with: cargo run --bin phone-agent
 
-use anyhow::anyhow;
-use phone_agent::calibration::{CalibrationConfig, CalibrationMode, CoordinateCalibrator};
+use anyhow::{anyhow, Context};
+use phone_agent::adb::ADBConnection;
+use phone_agent::calibration::{
+    load_device_calibrations, save_device_profile, CalibrationConfig, CalibrationError,
+    CalibrationMode, ComplexScene, CoordinateCalibrator, Orientation, OrientedCalibrations,
+};
+use phone_agent::config::{Config, PlannerPreset};
 use phone_agent::model::ModelClient;
 use phone_agent::{
-    AgentConfig, AppSettings, CoordinateSystem, ModelConfig, PhoneAgent, DEFAULT_COORDINATE_SCALE,
+    AppSettings, CoordinateSystem, EnvFormat, ErrorCategory, ModelConfig, Notifier, PhoneAgent,
+    PlannerProfile, DEFAULT_COORDINATE_SCALE,
 };
 use std::env;
 use std::io::{self, BufRead, Write};
 
-/// Merge stored settings with environment overrides.
-fn load_settings_with_env() -> AppSettings {
-    let mut settings = AppSettings::load();
-
-    if let Ok(v) = env::var("MODEL_BASE_URL") {
-        settings.base_url = v;
-    }
-    if let Ok(v) = env::var("MODEL_API_KEY") {
-        settings.api_key = v;
-    }
-    if let Ok(v) = env::var("MODEL_NAME") {
-        settings.model_name = v;
-    }
-    if let Ok(v) = env::var("ADB_DEVICE_ID") {
-        settings.device_id = v;
-    }
-    if let Ok(v) = env::var("AGENT_LANG") {
-        settings.lang = v;
-    }
-    if let Ok(v) = env::var("COORDINATE_SYSTEM") {
-        settings.coordinate_system = v;
-    }
-
-    // Numbers with fallbacks to current settings value
-    if let Ok(v) = env::var("MODEL_MAX_RETRIES") {
-        if let Ok(parsed) = v.parse() {
-            settings.max_retries = parsed;
-        }
-    }
-    if let Ok(v) = env::var("MODEL_RETRY_DELAY") {
-        if let Ok(parsed) = v.parse() {
-            settings.retry_delay = parsed;
-        }
-    }
-    if let Ok(v) = env::var("MAX_STEPS") {
-        if let Ok(parsed) = v.parse() {
-            settings.max_steps = parsed;
-        }
-    }
-    if let Ok(v) = env::var("COORDINATE_SCALE_X") {
-        if let Ok(parsed) = v.parse() {
-            settings.scale_x = parsed;
-        }
-    }
-    if let Ok(v) = env::var("COORDINATE_SCALE_Y") {
-        if let Ok(parsed) = v.parse() {
-            settings.scale_y = parsed;
-        }
-    }
-
-    // Unified scale overrides individual values
-    if let Ok(v) = env::var("COORDINATE_SCALE") {
-        if let Ok(parsed) = v.parse() {
-            settings.scale_x = parsed;
-            settings.scale_y = parsed;
-        }
-    }
-
-    if let Ok(v) = env::var("ENABLE_CALIBRATION") {
-        settings.enable_calibration = v == "1" || v.to_lowercase() == "true";
-    }
-    if let Ok(v) = env::var("CALIBRATION_MODE") {
-        settings.calibration_mode = v;
-    }
-    if let Ok(v) = env::var("CALIBRATION_COMPLEX_ROUNDS") {
-        if let Ok(parsed) = v.parse() {
-            settings.calibration_rounds = parsed;
-        }
-    }
-
-    // Planner model settings
-    if let Ok(v) = env::var("PLANNER_MODEL_BASE_URL") {
-        settings.planner_base_url = v;
-    }
-    if let Ok(v) = env::var("PLANNER_MODEL_API_KEY") {
-        settings.planner_api_key = v;
-    }
-    if let Ok(v) = env::var("PLANNER_MODEL_NAME") {
-        settings.planner_model_name = v;
-    }
-    if let Ok(v) = env::var("MAX_EXECUTOR_FEEDBACK_HISTORY") {
-        if let Ok(parsed) = v.parse() {
-            settings.max_executor_feedback_history = parsed;
-        }
-    }
-    if let Ok(v) = env::var("STUCK_THRESHOLD") {
-        if let Ok(parsed) = v.parse() {
-            settings.stuck_threshold = parsed;
-        }
-    }
-    if let Ok(v) = env::var("PROMPT_MEMORY_PATH") {
-        settings.prompt_memory_path = v;
-    }
-    if let Ok(v) = env::var("PLANNER_INTERVAL_MS") {
-        if let Ok(parsed) = v.parse() {
-            settings.planner_interval_ms = parsed;
-        }
-    }
-    if let Ok(v) = env::var("EXECUTOR_INTERVAL_MS") {
-        if let Ok(parsed) = v.parse() {
-            settings.executor_interval_ms = parsed;
-        }
-    }
-
-    if let Ok(v) = env::var("DUAL_LOOP_MODE") {
-        settings.dual_loop_mode = v == "1" || v.to_lowercase() == "true";
-    }
-
-    settings
-}
+/// How often the dual loop checks the settings file for changes.
+const SETTINGS_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
 
 fn prompt_with_default(label: &str, default: &str) -> anyhow::Result<String> {
     print!("{} [{}]: ", label, default);
@@ -216,19 +114,67 @@ fn run_config_wizard(mut settings: AppSettings) -> anyhow::Result<()> {
     )?;
 
     println!("\nPlanner (dual-loop) settings");
+    settings.planner_preset = prompt_with_default(
+        &format!(
+            "Planner preset, sets the model, thresholds, intervals and memory files ({}; empty = none)",
+            PlannerPreset::available(&settings.planner_presets).join("/")
+        ),
+        &settings.planner_preset,
+    )?;
     settings.planner_base_url =
         prompt_with_default("Planner model base URL", &settings.planner_base_url)?;
     settings.planner_api_key =
         prompt_with_default("Planner model API key", &settings.planner_api_key)?;
+    settings.plaintext_api_keys = prompt_bool(
+        "Keep API keys in the settings file instead of the system keychain? (y/n)",
+        settings.plaintext_api_keys,
+    )?;
     settings.planner_model_name =
         prompt_with_default("Planner model name", &settings.planner_model_name)?;
+    settings.planner_profile = prompt_with_default(
+        &format!(
+            "Planner profile ({}; empty = none)",
+            PlannerProfile::available(&settings.planner_profiles).join("/")
+        ),
+        &settings.planner_profile,
+    )?;
     settings.max_executor_feedback_history = prompt_number(
         "Max executor feedback history",
         settings.max_executor_feedback_history,
     )?;
     settings.stuck_threshold = prompt_number("Stuck threshold", settings.stuck_threshold)?;
+    settings.screen_similarity_threshold = prompt_number(
+        "Screen similarity threshold (0.0-1.0)",
+        settings.screen_similarity_threshold,
+    )?;
+    settings.step_timeout_secs = prompt_number(
+        "Executor step timeout in seconds (0 = disabled)",
+        settings.step_timeout_secs,
+    )?;
     settings.prompt_memory_path =
         prompt_with_default("Prompt memory path", &settings.prompt_memory_path)?;
+    settings.prompt_memory_max_task_types = prompt_number(
+        "Max task types in prompt memory (0 = unlimited)",
+        settings.prompt_memory_max_task_types,
+    )?;
+    settings.prompt_memory_max_corrections = prompt_number(
+        "Max pending corrections per task type (0 = unlimited)",
+        settings.prompt_memory_max_corrections,
+    )?;
+    settings.prompt_memory_max_trajectories = prompt_number(
+        "Max example trajectories per task type (0 = unlimited)",
+        settings.prompt_memory_max_trajectories,
+    )?;
+    settings.prompt_success_half_life_days = prompt_number(
+        "Half-life of prompt success rates in days (0 = no decay)",
+        settings.prompt_success_half_life_days,
+    )?;
+    settings.memory_sync_url = prompt_with_default(
+        "Prompt memory sync URL (empty = off)",
+        &settings.memory_sync_url,
+    )?;
+    settings.episodic_memory_path =
+        prompt_with_default("Episodic memory path", &settings.episodic_memory_path)?;
     settings.planner_interval_ms =
         prompt_number("Planner interval (ms)", settings.planner_interval_ms)?;
     settings.executor_interval_ms =
@@ -238,24 +184,115 @@ fn run_config_wizard(mut settings: AppSettings) -> anyhow::Result<()> {
         "Enable dual-loop mode by default? (y/n)",
         settings.dual_loop_mode,
     )?;
+    settings.require_approval = prompt_bool(
+        "Require approval for payment/deletion/posting tasks? (y/n)",
+        settings.require_approval,
+    )?;
+    settings.propose_only = prompt_bool(
+        "Propose-only mode (executor actions need approval)? (y/n)",
+        settings.propose_only,
+    )?;
+    if settings.propose_only {
+        settings.user_reviews_proposals = prompt_bool(
+            "Review proposals yourself instead of the planner? (y/n)",
+            settings.user_reviews_proposals,
+        )?;
+    }
+    settings.event_log = prompt_bool(
+        "Write a JSONL event log per dual-loop session? (y/n)",
+        settings.event_log,
+    )?;
+    settings.event_stream_addr = prompt_with_default(
+        "Serve live events over WebSocket at [host:]port (empty = off)",
+        &settings.event_stream_addr,
+    )?;
+    settings.notify_webhook_url = prompt_with_default(
+        "Notification webhook URL (empty = off)",
+        &settings.notify_webhook_url,
+    )?;
+    settings.notify_telegram_bot_token = prompt_with_default(
+        "Telegram bot token for notifications (empty = off)",
+        &settings.notify_telegram_bot_token,
+    )?;
+    if !settings.notify_telegram_bot_token.is_empty() {
+        settings.notify_telegram_chat_id =
+            prompt_with_default("Telegram chat ID", &settings.notify_telegram_chat_id)?;
+    }
+    settings.notify_bark_url =
+        prompt_with_default("Bark push URL (empty = off)", &settings.notify_bark_url)?;
+    settings.max_total_tokens = prompt_number(
+        "Max total tokens before pausing (0 = unlimited)",
+        settings.max_total_tokens,
+    )?;
+    settings.max_cost = prompt_number(
+        "Max estimated cost before pausing (0 = unlimited)",
+        settings.max_cost,
+    )?;
+    settings.planner_price_per_million = prompt_number(
+        "Planner price per million tokens",
+        settings.planner_price_per_million,
+    )?;
+    settings.executor_price_per_million = prompt_number(
+        "Executor price per million tokens",
+        settings.executor_price_per_million,
+    )?;
 
-    settings.save().map_err(|e| anyhow!(e))?;
+    settings.save()?;
 
     println!("\n✅ Settings saved. They will be used by both CLI and GUI.");
     Ok(())
 }
 
+/// Print the problems [`Config::validate`] finds, failing if any is an error.
+fn check_config(config: &Config) -> anyhow::Result<()> {
+    let issues = config.validate();
+    for issue in &issues {
+        if issue.is_error() {
+            eprintln!("❌ {}", issue);
+        } else {
+            eprintln!("⚠️ {}", issue);
+        }
+    }
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+    if errors > 0 {
+        return Err(anyhow!(
+            "{} configuration problem(s); fix them in the settings file (phone-agent config), \
+             the environment or the command line",
+            errors
+        ));
+    }
+    Ok(())
+}
+
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> std::process::ExitCode {
+    match run().await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("\n❌ Error: {:#}", e);
+            ErrorCategory::of(e.as_ref()).into()
+        }
+    }
+}
+
+/// Run the CLI; the error decides the exit code (see [`ErrorCategory`]).
+async fn run() -> anyhow::Result<()> {
     // Load .env file if present (ignore errors if file doesn't exist)
     let _ = dotenvy::dotenv();
 
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
-
     // Parse command line arguments
     let args: Vec<String> = env::args().collect();
 
+    // Initialize tracing. In MCP mode stdout carries the protocol, so logs
+    // go to stderr.
+    if args.get(1).map(String::as_str) == Some("mcp") {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
     // Allow running interactive setup before anything else
     if args
         .iter()
@@ -265,87 +302,88 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
-    // Merge stored settings with environment overrides
-    let settings = load_settings_with_env();
+    // settings export|import|env: copy the settings file to or from another
+    // machine, or print it as environment variables
+    if args.get(1).map(String::as_str) == Some("settings") {
+        return run_settings_command(&args[2..]);
+    }
 
-    let coordinate_system = match settings.coordinate_system.to_lowercase().as_str() {
-        "absolute" | "abs" => CoordinateSystem::Absolute,
-        _ => CoordinateSystem::Relative,
-    };
+    // Settings file (or --profile), overridden by the environment, then by flags
+    let config = Config::load(&args);
+    let args = config.args().to_vec();
+    let settings = config.settings().clone();
 
-    let lang = settings.lang.clone();
+    // Extra UI languages (ja.json, zh-TW.ftl, ...) next to the settings file
+    if let Some(dir) = AppSettings::languages_dir() {
+        phone_agent::config::load_languages_dir(&dir);
+    }
+    if env::var_os("PROMPTS_DIR").is_none() {
+        phone_agent::config::set_prompts_dir(AppSettings::prompts_dir());
+    }
 
-    let default_scale = match coordinate_system {
-        CoordinateSystem::Relative => 1.0,
-        CoordinateSystem::Absolute => DEFAULT_COORDINATE_SCALE,
-    };
+    // prompts list|report|show|diff|prune|alias|unalias|export|import: manage learned prompts
+    if args.get(1).map(String::as_str) == Some("prompts") {
+        return run_prompts_command(&settings.prompt_memory_path, &args[2..]);
+    }
 
-    let mut scale_x = if settings.scale_x == 0.0 {
-        default_scale
-    } else {
-        settings.scale_x
-    };
-    let mut scale_y = if settings.scale_y == 0.0 {
-        default_scale
-    } else {
-        settings.scale_y
-    };
+    // Report every configuration problem now rather than halfway through a task
+    check_config(&config)?;
 
-    // If coordinate system changed from stored value, reset to sensible defaults
-    if coordinate_system == CoordinateSystem::Relative {
-        scale_x = 1.0;
-        scale_y = 1.0;
-    } else if coordinate_system == CoordinateSystem::Absolute
-        && (settings.scale_x == 1.0 && settings.scale_y == 1.0)
-    {
-        scale_x = DEFAULT_COORDINATE_SCALE;
-        scale_y = DEFAULT_COORDINATE_SCALE;
+    // mcp: serve the phone-control tools to an MCP client over stdin/stdout
+    if args.get(1).map(String::as_str) == Some("mcp") {
+        phone_agent::mcp::McpServer::new(config)
+            .serve_stdio()
+            .await?;
+        return Ok(());
+    }
+
+    // Fail early, with its own exit code, when the phone isn't there
+    let device_id = settings.device_id.trim();
+    ADBConnection::new()
+        .find_device((!device_id.is_empty()).then_some(device_id))
+        .context("No phone available over ADB (check `adb devices`)")?;
+
+    // calibrate [--mode <mode>] [--json] ...: standalone calibration for provisioning scripts
+    if args.get(1).map(String::as_str) == Some("calibrate") {
+        return run_calibrate_command(&settings, &args[2..]).await;
     }
 
-    // Check if calibration is requested
-    let enable_calibration = settings.enable_calibration;
+    // --dry-run: perceive and decide as usual, but only print the actions
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let args: Vec<String> = args.into_iter().filter(|arg| arg != "--dry-run").collect();
+
+    let coordinate_system = config.coordinate_system();
+    let lang = settings.lang.clone();
+
+    // Check if calibration is requested (calibrating at startup taps the
+    // phone, so a dry run skips it)
+    let enable_calibration = settings.enable_calibration && !dry_run;
     let calibration_simple = args.iter().any(|arg| arg == "--calibrate");
     let calibration_complex = args.iter().any(|arg| arg == "--calibrate-complex");
-    let calibration_only = calibration_simple || calibration_complex;
+    let calibration_grid = args.iter().any(|arg| arg == "--calibrate-grid");
+    let calibration_only = calibration_simple || calibration_complex || calibration_grid;
 
     // Determine calibration mode
     let calibration_mode = if calibration_complex {
         CalibrationMode::Complex
+    } else if calibration_grid {
+        CalibrationMode::Grid
     } else {
-        let mode_env = settings.calibration_mode.to_lowercase();
-        if mode_env == "complex" {
-            CalibrationMode::Complex
-        } else {
-            CalibrationMode::Simple
+        match settings.calibration_mode.to_lowercase().as_str() {
+            "complex" => CalibrationMode::Complex,
+            "grid" => CalibrationMode::Grid,
+            _ => CalibrationMode::Simple,
         }
     };
 
     let complex_rounds: usize = settings.calibration_rounds;
 
-    // Build model config
-    let model_config = ModelConfig::default()
-        .with_base_url(&settings.base_url)
-        .with_api_key(&settings.api_key)
-        .with_model_name(&settings.model_name)
-        .with_max_retries(settings.max_retries)
-        .with_retry_delay(settings.retry_delay);
-
-    // Build agent config with coordinate system
-    let mut agent_config = AgentConfig::default()
-        .with_lang(&lang)
-        .with_coordinate_system(coordinate_system)
-        .with_scale(scale_x, scale_y)
-        .with_max_steps(settings.max_steps);
-
-    let device_id = if settings.device_id.trim().is_empty() {
-        None
-    } else {
-        Some(settings.device_id.clone())
-    };
-    let device_id_clone = device_id.clone();
-    if let Some(id) = device_id {
-        agent_config = agent_config.with_device_id(id);
-    }
+    let model_config = config.model_config();
+    let agent_config = config.agent_config();
+    let (mut scale_x, mut scale_y) = (agent_config.scale_x, agent_config.scale_y);
+    let (mut offset_x, mut offset_y) = (agent_config.offset_x, agent_config.offset_y);
+    let mut coordinate_mapping = None;
+    let device_id_clone = agent_config.device_id.clone();
 
     let coord_system_name = match coordinate_system {
         CoordinateSystem::Relative => "Relative (0-999)",
@@ -354,11 +392,21 @@ async fn main() -> anyhow::Result<()> {
 
     println!("🤖 Phone Agent - AI-powered Android Automation");
     println!("================================================");
+    if !settings.active_profile.is_empty() {
+        println!("Profile: {}", settings.active_profile);
+    }
     println!("Model: {} @ {}", settings.model_name, settings.base_url);
     println!("Language: {}", lang);
     println!("Coordinate System: {}", coord_system_name);
+    println!(
+        "Prompt: {} ({})",
+        settings.prompt_variant, settings.prompt_style
+    );
     if coordinate_system == CoordinateSystem::Absolute {
         println!("Coordinate Scale: X={:.2}, Y={:.2}", scale_x, scale_y);
+        if offset_x != 0.0 || offset_y != 0.0 {
+            println!("Coordinate Offset: X={:.0}, Y={:.0}", offset_x, offset_y);
+        }
     }
     println!(
         "Retry: max {} attempts, {}s delay",
@@ -370,10 +418,55 @@ async fn main() -> anyhow::Result<()> {
     if enable_calibration || calibration_only {
         println!("Calibration: enabled ({:?})", calibration_mode);
     }
+    if dry_run {
+        println!("Dry Run: actions are printed, not performed");
+    }
     println!("================================================\n");
 
+    // A stored calibration of this phone replaces the configured scale,
+    // unless the scale is set explicitly through the environment or flags
+    let scale_from_env = ["scale_x", "scale_y", "offset_x", "offset_y"]
+        .iter()
+        .any(|key| config.is_overridden(key));
+    let profiles_path = AppSettings::calibration_profiles_path();
+    let mut calibrations = OrientedCalibrations::default();
+    if coordinate_system == CoordinateSystem::Absolute && !scale_from_env {
+        if let Some(path) = &profiles_path {
+            calibrations = load_device_calibrations(path, device_id_clone.as_deref());
+        }
+        if !(enable_calibration || calibration_only) {
+            if let Some(result) = calibrations.preferred() {
+                scale_x = result.scale_x;
+                scale_y = result.scale_y;
+                offset_x = result.offset_x;
+                offset_y = result.offset_y;
+                coordinate_mapping = result.mapping.clone();
+            }
+            for orientation in [Orientation::Portrait, Orientation::Landscape] {
+                if let Some(result) = calibrations.get(orientation) {
+                    println!(
+                        "📐 已加载 {} ({}x{}) 的校准配置: X={:.4}{:+.0}, Y={:.4}{:+.0}",
+                        orientation.as_str(),
+                        result.screen_width,
+                        result.screen_height,
+                        result.scale_x,
+                        result.offset_x,
+                        result.scale_y,
+                        result.offset_y
+                    );
+                }
+            }
+            if !calibrations.is_empty() {
+                println!();
+            }
+        }
+    }
+    let orientations =
+        Orientation::parse_selection(&settings.calibration_orientation).unwrap_or_default();
+
     // Run calibration if requested
-    let (scale_x, scale_y) = if enable_calibration || calibration_only {
+    let mut settings_saved = false;
+    let (scale_x, scale_y, offset_x, offset_y) = if enable_calibration || calibration_only {
         println!(
             "🎯 Starting coordinate calibration ({:?} mode)...\n",
             calibration_mode
@@ -383,7 +476,16 @@ async fn main() -> anyhow::Result<()> {
         let mut calibration_config = CalibrationConfig::default()
             .with_mode(calibration_mode)
             .with_lang(&lang)
-            .with_complex_rounds(complex_rounds);
+            .with_complex_rounds(complex_rounds)
+            .with_complex_scenes(
+                ComplexScene::parse_list(&settings.calibration_scenes).unwrap_or_default(),
+            )
+            .with_max_concurrency(settings.calibration_concurrency)
+            .with_max_residual(settings.calibration_max_residual)
+            .with_on_device(
+                settings.calibration_on_device
+                    || args.iter().any(|arg| arg == "--calibrate-on-device"),
+            );
 
         if let Some(ref id) = device_id_clone {
             calibration_config = calibration_config.with_device_id(id);
@@ -392,7 +494,20 @@ async fn main() -> anyhow::Result<()> {
         let calibrator = CoordinateCalibrator::new(calibration_config);
         let model_client = ModelClient::new(model_config.clone());
 
-        let result = calibrator.calibrate(&model_client).await;
+        let results = calibrator
+            .calibrate_orientations(&model_client, &orientations)
+            .await;
+        for result in &results {
+            calibrations.insert(result.clone());
+        }
+        // Start in portrait when it was calibrated
+        let result = results
+            .iter()
+            .filter(|r| r.success)
+            .min_by_key(|r| r.orientation() != Orientation::Portrait)
+            .or_else(|| results.first())
+            .cloned()
+            .ok_or_else(|| CalibrationError("no orientation was calibrated".to_string()))?;
 
         if result.success {
             println!("\n🎯 Calibration mode: {:?}", result.mode);
@@ -401,50 +516,440 @@ async fn main() -> anyhow::Result<()> {
                 result.screen_width, result.screen_height
             );
             println!(
-                "🎯 Using calibrated scale factors: X={:.4}, Y={:.4}\n",
+                "🎯 Using calibrated scale factors: X={:.4}, Y={:.4}",
                 result.scale_x, result.scale_y
             );
-            (result.scale_x, result.scale_y)
+            println!(
+                "🎯 Using calibrated offsets: X={:.1}, Y={:.1}\n",
+                result.offset_x, result.offset_y
+            );
+            if result.mapping.is_some() {
+                println!("🎯 Using grid calibration mapping\n");
+            }
+            coordinate_mapping = result.mapping.clone();
+            match AppSettings::save_calibration(&result) {
+                Ok(()) => {
+                    settings_saved = true;
+                    if let Some(path) = AppSettings::settings_path() {
+                        println!("💾 校准参数已写入设置文件 {}\n", path.display());
+                    }
+                }
+                Err(e) => eprintln!("⚠️ Failed to save calibration to settings: {}", e),
+            }
+            if let Some(landscape) = calibrations
+                .get(Orientation::Landscape)
+                .filter(|_| result.orientation() != Orientation::Landscape)
+            {
+                println!(
+                    "🎯 Landscape scale factors: X={:.4}, Y={:.4}\n",
+                    landscape.scale_x, landscape.scale_y
+                );
+            }
+            if let Some(path) = &profiles_path {
+                for result in &results {
+                    match save_device_profile(path, device_id_clone.as_deref(), result) {
+                        Ok(true) => println!("💾 校准结果已保存到 {}\n", path.display()),
+                        Ok(false) => {}
+                        Err(e) => eprintln!("⚠️ Failed to save calibration profile: {}", e),
+                    }
+                }
+            }
+            (
+                result.scale_x,
+                result.scale_y,
+                result.offset_x,
+                result.offset_y,
+            )
+        } else if let (true, Some(error)) = (calibration_only, result.failure()) {
+            return Err(error.into());
         } else {
             println!("\n⚠️ Calibration failed: {:?}", result.error);
             println!(
                 "   Using default scale factors: X={:.4}, Y={:.4}\n",
                 scale_x, scale_y
             );
-            (scale_x, scale_y)
+            (scale_x, scale_y, offset_x, offset_y)
         }
     } else {
-        (scale_x, scale_y)
+        (scale_x, scale_y, offset_x, offset_y)
     };
 
     // Exit if calibration-only mode
     if calibration_only {
-        println!("Calibration complete. Suggested environment variables:");
+        if settings_saved {
+            println!("Calibration saved to settings; CLI and GUI will use it from now on.");
+            println!("Environment variables still override it:");
+        } else {
+            println!("Calibration complete. Suggested environment variables:");
+        }
         println!("  COORDINATE_SCALE_X={:.4}", scale_x);
         println!("  COORDINATE_SCALE_Y={:.4}", scale_y);
+        println!("  COORDINATE_OFFSET_X={:.1}", offset_x);
+        println!("  COORDINATE_OFFSET_Y={:.1}", offset_y);
         println!("\nOr use unified scale:");
         let avg_scale = (scale_x + scale_y) / 2.0;
         println!("  COORDINATE_SCALE={:.4}", avg_scale);
+        if coordinate_mapping.is_some() {
+            println!("\nThe grid mapping can't be expressed as environment variables;");
+            println!("it is applied from the saved calibration profile on later runs.");
+        }
         return Ok(());
     }
 
     // Update agent config with calibrated scale factors
-    let agent_config = agent_config.with_scale(scale_x, scale_y);
+    let agent_config = agent_config
+        .with_scale(scale_x, scale_y)
+        .with_offset(offset_x, offset_y)
+        .with_coordinate_mapping(coordinate_mapping)
+        .with_orientation_calibrations(calibrations)
+        .with_dry_run(dry_run);
 
     // Check for dual loop mode
     let dual_loop_mode = settings.dual_loop_mode;
 
     if dual_loop_mode {
         // Dual loop mode
-        run_dual_loop_mode(model_config, agent_config, lang.clone(), settings.clone()).await?;
+        let resume = args.iter().any(|arg| arg == "--resume");
+        let plan = args
+            .iter()
+            .position(|arg| arg == "--plan")
+            .map(|pos| {
+                args.get(pos + 1)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Usage: --plan <file.json|file.yaml>"))
+            })
+            .transpose()?;
+        run_dual_loop_mode(
+            model_config,
+            agent_config,
+            lang.clone(),
+            config,
+            resume,
+            plan,
+        )
+        .await?;
     } else {
-        // Single loop mode (original)
+        // Single loop mode (original); dual-loop flags would end up in the task
+        if let Some(flag) = args
+            .iter()
+            .find(|arg| *arg == "--plan" || *arg == "--resume")
+        {
+            return Err(anyhow!(
+                "{} only works in dual-loop mode (set DUAL_LOOP_MODE=true or dual_loop_mode = true)",
+                flag
+            ));
+        }
         run_single_loop_mode(model_config, agent_config, args).await?;
     }
 
     Ok(())
 }
 
+/// Run a calibration and print the result, either as a summary or as the full
+/// `CalibrationResult` in JSON. Progress goes to stderr in JSON mode so stdout
+/// can be piped straight into other tools.
+async fn run_calibrate_command(settings: &AppSettings, args: &[String]) -> anyhow::Result<()> {
+    let usage = "Usage: phone-agent calibrate [--mode simple|complex|grid] [--json] \
+                 [--rounds <n>] [--scenes <list>] [--concurrency <n>] \
+                 [--orientation portrait|landscape|both] [--device <id>] \
+                 [--on-device] [--no-save]";
+
+    let mut mode =
+        CalibrationMode::from_name(&settings.calibration_mode).unwrap_or(CalibrationMode::Simple);
+    let mut rounds = settings.calibration_rounds;
+    let mut concurrency = settings.calibration_concurrency;
+    let mut scenes = ComplexScene::parse_list(&settings.calibration_scenes).unwrap_or_default();
+    let mut device_id = Some(settings.device_id.trim().to_string()).filter(|id| !id.is_empty());
+    let mut on_device = settings.calibration_on_device;
+    let mut orientations =
+        Orientation::parse_selection(&settings.calibration_orientation).unwrap_or_default();
+    let (mut json, mut save) = (false, true);
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| anyhow!(usage));
+        match arg.as_str() {
+            "--mode" => {
+                let name = value()?;
+                mode = CalibrationMode::from_name(name)
+                    .ok_or_else(|| anyhow!("Unknown calibration mode: {}", name))?;
+            }
+            "--rounds" => {
+                let n = value()?;
+                rounds = n
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid number of rounds: {}", n))?;
+            }
+            "--scenes" => scenes = ComplexScene::parse_list(value()?).map_err(|e| anyhow!(e))?,
+            "--concurrency" => {
+                let n = value()?;
+                concurrency = n
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid concurrency: {}", n))?;
+            }
+            "--orientation" => {
+                let name = value()?;
+                orientations = Orientation::parse_selection(name)
+                    .ok_or_else(|| anyhow!("Unknown orientation: {}", name))?;
+            }
+            "--device" => device_id = Some(value()?.clone()),
+            "--json" => json = true,
+            "--on-device" => on_device = true,
+            "--no-save" => save = false,
+            _ => return Err(anyhow!(usage)),
+        }
+    }
+
+    let model_config = ModelConfig::default()
+        .with_base_url(&settings.base_url)
+        .with_api_key(&settings.api_key)
+        .with_model_name(&settings.model_name)
+        .with_max_retries(settings.max_retries)
+        .with_retry_delay(settings.retry_delay);
+
+    let mut calibration_config = CalibrationConfig::default()
+        .with_mode(mode)
+        .with_lang(&settings.lang)
+        .with_complex_rounds(rounds)
+        .with_complex_scenes(scenes)
+        .with_max_concurrency(concurrency)
+        .with_max_residual(settings.calibration_max_residual)
+        .with_on_device(on_device)
+        .with_progress_to_stderr(json);
+    if let Some(ref id) = device_id {
+        calibration_config = calibration_config.with_device_id(id);
+    }
+
+    let calibrator = CoordinateCalibrator::new(calibration_config);
+    let results = calibrator
+        .calibrate_orientations(&ModelClient::new(model_config), &orientations)
+        .await;
+
+    if save {
+        let mut calibrations = OrientedCalibrations::default();
+        for result in &results {
+            calibrations.insert(result.clone());
+        }
+        if let Some(result) = calibrations.preferred() {
+            if let Err(e) = AppSettings::save_calibration(result) {
+                eprintln!("⚠️ Failed to save calibration to settings: {}", e);
+            }
+        }
+        if let Some(path) = AppSettings::calibration_profiles_path() {
+            for result in results.iter().filter(|r| r.success) {
+                if let Err(e) = save_device_profile(&path, device_id.as_deref(), result) {
+                    eprintln!("⚠️ Failed to save calibration profile: {}", e);
+                }
+            }
+        }
+    }
+
+    // A single calibration prints as an object, several as an array
+    if json && results.len() == 1 {
+        println!("{}", serde_json::to_string_pretty(&results[0])?);
+    } else if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        for result in results.iter().filter(|r| r.success) {
+            println!(
+                "{}: Scale: X={:.4}, Y={:.4}  Offset: X={:.1}, Y={:.1}",
+                result.orientation().as_str(),
+                result.scale_x,
+                result.scale_y,
+                result.offset_x,
+                result.offset_y
+            );
+        }
+    }
+
+    match results.iter().find_map(|r| r.failure()) {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
+}
+
+/// Manage the prompt memory: list, report on, show, diff or prune task types, edit
+/// aliases, export it to a file, or merge an exported file into it.
+/// Store the prompt memory encryption key in the OS keychain, or remove it.
+fn run_prompt_key_command(remove: bool) -> anyhow::Result<()> {
+    use phone_agent::agent::PROMPT_MEMORY_KEY_ACCOUNT;
+    use phone_agent::keychain::{Keychain, SecretStore};
+
+    if remove {
+        Keychain
+            .delete(PROMPT_MEMORY_KEY_ACCOUNT)
+            .map_err(|e| anyhow!("Failed to remove the key from the keychain: {}", e))?;
+        println!("🔑 已从系统钥匙串移除提示词记忆密钥");
+        return Ok(());
+    }
+    let key = prompt_with_default("Prompt memory key (passphrase or base64 32-byte key)", "")?;
+    if key.is_empty() {
+        return Err(anyhow!("No key given"));
+    }
+    Keychain
+        .set(PROMPT_MEMORY_KEY_ACCOUNT, &key)
+        .map_err(|e| anyhow!("Failed to store the key in the keychain: {}", e))?;
+    println!("🔑 已将提示词记忆密钥保存到系统钥匙串, 下次保存时加密 (PROMPT_MEMORY_KEY 优先)");
+    Ok(())
+}
+
+fn run_prompts_command(memory_path: &str, args: &[String]) -> anyhow::Result<()> {
+    let usage = "Usage: phone-agent prompts <list | report [--json] | show <type> | \
+                 diff <type> <file> | \
+                 prune <days> | alias <name> <type> | unalias <name> | \
+                 export <file> | import <file> | key [--remove]>";
+    let Some(command) = args.first() else {
+        return Err(anyhow!(usage));
+    };
+    let arg = |i: usize| args.get(i).ok_or_else(|| anyhow!(usage));
+    if command == "key" {
+        return run_prompt_key_command(args.get(1).map(String::as_str) == Some("--remove"));
+    }
+
+    let mut memory = phone_agent::PromptMemory::load(memory_path)?;
+    match command.as_str() {
+        "list" => {
+            if memory.is_empty() {
+                println!("（暂无已保存的任务类型记忆）");
+            }
+            for summary in memory.list() {
+                println!(
+                    "- {}: 使用{}次{}, 待整合纠偏 {} 条, 最近活跃 {}",
+                    summary.task_type,
+                    summary.usage_count,
+                    summary
+                        .success_rate
+                        .map(|r| format!(", 成功率{:.0}%", r * 100.0))
+                        .unwrap_or_default(),
+                    summary.pending_corrections,
+                    summary.last_active
+                );
+            }
+        }
+        "report" => {
+            let report = memory.report();
+            if args.get(1).map(String::as_str) == Some("--json") {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report);
+            }
+        }
+        "show" => {
+            let task_type = arg(1)?;
+            let details = memory
+                .show(task_type)
+                .ok_or_else(|| anyhow!("Unknown task type: {}", task_type))?;
+            println!("{}", details);
+        }
+        "diff" => {
+            let (task_type, file) = (arg(1)?, arg(2)?);
+            let other = phone_agent::PromptMemory::load(file)?;
+            match memory.diff(task_type, &other) {
+                None => return Err(anyhow!("Unknown task type: {}", task_type)),
+                Some(diff) if diff.is_unchanged() => println!("✅ 提示词相同: {}", task_type),
+                Some(diff) => print!("{}", diff),
+            }
+        }
+        "prune" => {
+            let days: u64 = arg(1)?
+                .parse()
+                .map_err(|_| anyhow!("Invalid number of days: {}", args[1]))?;
+            let pruned = memory.prune_unused(days);
+            memory.save(memory_path)?;
+            println!(
+                "🧹 已清理 {} 个超过 {} 天未使用的任务类型: {}",
+                pruned.len(),
+                days,
+                pruned.join(", ")
+            );
+        }
+        "alias" => {
+            let (alias, task_type) = (arg(1)?, arg(2)?);
+            if !memory.contains(task_type) {
+                return Err(anyhow!("Unknown task type: {}", task_type));
+            }
+            memory.add_alias(alias, task_type);
+            memory.save(memory_path)?;
+            println!(
+                "🔗 {} 现在指向任务类型 {}",
+                alias,
+                memory.resolve(task_type)
+            );
+        }
+        "unalias" => {
+            let alias = arg(1)?;
+            let target = memory
+                .remove_alias(alias)
+                .ok_or_else(|| anyhow!("Unknown alias: {}", alias))?;
+            memory.save(memory_path)?;
+            println!("🔗 已移除别名 {} (原指向 {})", alias, target);
+        }
+        "export" => {
+            let file = arg(1)?;
+            memory.export(file)?;
+            println!(
+                "📤 已导出 {} 个任务类型的提示词记忆到 {}",
+                memory.len(),
+                file
+            );
+        }
+        "import" => {
+            let file = arg(1)?;
+            let changed = memory.import_and_merge(file)?;
+            memory.save(memory_path)?;
+            println!(
+                "📥 已从 {} 合并 {} 个任务类型到 {} (共 {} 个)",
+                file,
+                changed,
+                memory_path,
+                memory.len()
+            );
+        }
+        _ => return Err(anyhow!(usage)),
+    }
+    Ok(())
+}
+
+fn run_settings_command(args: &[String]) -> anyhow::Result<()> {
+    let usage = "Usage: phone-agent settings <export <file> [--with-secrets] | import <file> | \
+                 env [--with-secrets] [--shell]>";
+    if args.first().map(String::as_str) == Some("env") {
+        let mut with_secrets = false;
+        let mut format = EnvFormat::Dotenv;
+        for arg in &args[1..] {
+            match arg.as_str() {
+                "--with-secrets" => with_secrets = true,
+                "--shell" => format = EnvFormat::Shell,
+                _ => return Err(anyhow!(usage)),
+            }
+        }
+        print!("{}", AppSettings::load().to_env_file(format, with_secrets));
+        return Ok(());
+    }
+    let (Some(command), Some(file)) = (args.first(), args.get(1)) else {
+        return Err(anyhow!(usage));
+    };
+
+    let mut settings = AppSettings::load();
+    match command.as_str() {
+        "export" if args.get(2).map(String::as_str) == Some("--with-secrets") => {
+            settings.export_with_secrets(file)?;
+            println!("📤 Settings exported to {} (including API keys)", file);
+        }
+        "export" => {
+            settings.export(file)?;
+            println!("📤 Settings exported to {} (without API keys)", file);
+        }
+        "import" => {
+            settings.import(file)?;
+            settings.save()?;
+            println!("📥 Settings imported from {}", file);
+        }
+        _ => return Err(anyhow!(usage)),
+    }
+    Ok(())
+}
+
 /// Run single loop mode (original behavior).
 async fn run_single_loop_mode(
     model_config: phone_agent::ModelConfig,
@@ -459,14 +964,12 @@ async fn run_single_loop_mode(
         let task = args[1..].join(" ");
         println!("📝 Task: {}\n", task);
 
-        match agent.run(&task).await {
-            Ok(result) => {
-                println!("\n✅ Result: {}", result);
-            }
-            Err(e) => {
-                eprintln!("\n❌ Error: {}", e);
-            }
+        // A failure ends the process with the exit code of its cause
+        let result = agent.run(&task).await?;
+        if let Some(failure) = agent.take_failure() {
+            return Err(failure.into());
         }
+        println!("\n✅ Result: {}", result);
     } else {
         // Interactive mode
         println!("Interactive mode. Type your task and press Enter.");
@@ -510,93 +1013,181 @@ async fn run_dual_loop_mode(
     executor_model_config: phone_agent::ModelConfig,
     executor_agent_config: phone_agent::AgentConfig,
     lang: String,
-    settings: AppSettings,
+    config: Config,
+    resume: bool,
+    plan: Option<String>,
 ) -> anyhow::Result<()> {
-    use phone_agent::{DualLoopConfig, DualLoopRunner, PlannerAgent, PlannerConfig};
+    use phone_agent::{DualLoopRunner, PlannerAgent, PromptScope, SettingsWatcher};
+
+    let settings = config.settings().clone();
 
     println!("\n🔄 Dual Loop Mode Enabled");
     println!("================================================\n");
 
     // Planner and dual-loop configuration from shared settings
-    let planner_base_url = settings.planner_base_url;
-    let planner_api_key = settings.planner_api_key;
-    let planner_model_name = settings.planner_model_name;
-
-    let max_feedback_history: usize = settings.max_executor_feedback_history;
-    let stuck_threshold: u32 = settings.stuck_threshold;
-    let prompt_memory_path = settings.prompt_memory_path;
-    let planner_interval: u64 = settings.planner_interval_ms;
-    let executor_interval: u64 = settings.executor_interval_ms;
+    let notification_channels = settings.notification_channels();
+    // Learned prompts are kept per device model (layouts differ between phones)
+    let device_model =
+        phone_agent::adb::get_device_model(executor_agent_config.device_id.as_deref());
+    let prompt_scope = match &device_model {
+        Some(model) => PromptScope::new().with_device_model(model),
+        None => PromptScope::new(),
+    };
+    let profile = settings.planner_profile(&lang);
+    if profile.is_none() && !settings.planner_profile.is_empty() {
+        eprintln!(
+            "⚠️ Unknown planner profile '{}', available: {}",
+            settings.planner_profile,
+            PlannerProfile::available(&settings.planner_profiles).join(", ")
+        );
+    }
 
+    if let Some(preset) = PlannerPreset::find(&settings.planner_preset, &settings.planner_presets) {
+        println!("Planner Preset: {} ({})", preset.name, preset.description);
+    }
+    if let Some(ref profile) = profile {
+        println!(
+            "Planner Profile: {} ({})",
+            profile.name, profile.description
+        );
+    }
     println!(
         "Planner Model: {} @ {}",
-        planner_model_name, planner_base_url
+        settings.planner_model_name, settings.planner_base_url
+    );
+    println!(
+        "Feedback History: {} entries",
+        settings.max_executor_feedback_history
     );
-    println!("Feedback History: {} entries", max_feedback_history);
-    println!("Stuck Threshold: {} consecutive", stuck_threshold);
-    println!("Prompt Memory: {}", prompt_memory_path);
+    println!("Stuck Threshold: {} consecutive", settings.stuck_threshold);
+    println!("Prompt Memory: {}", settings.prompt_memory_path);
+    if let Some(model) = &device_model {
+        println!("Device Model: {}", model);
+    }
+    println!("Episodic Memory: {}", settings.episodic_memory_path);
     println!(
         "Intervals: Planner={}ms, Executor={}ms",
-        planner_interval, executor_interval
+        settings.planner_interval_ms(profile.as_ref()),
+        settings.executor_interval_ms
     );
     println!("================================================\n");
 
-    // Build planner config
-    let planner_model_config = phone_agent::ModelConfig::default()
-        .with_base_url(&planner_base_url)
-        .with_api_key(&planner_api_key)
-        .with_model_name(&planner_model_name);
-
-    let planner_config = PlannerConfig::default()
-        .with_model_config(planner_model_config)
-        .with_max_feedback_history(max_feedback_history)
-        .with_stuck_threshold(stuck_threshold)
-        .with_prompt_memory_path(&prompt_memory_path)
-        .with_lang(&lang);
+    let planner_config = settings.planner_config(&lang, profile.as_ref(), prompt_scope);
 
     // Create planner
-    let planner = PlannerAgent::new(planner_config, executor_model_config, executor_agent_config);
+    let mut planner =
+        PlannerAgent::new(planner_config, executor_model_config, executor_agent_config);
+    if let Some(path) = &plan {
+        planner.import_plan(path)?;
+    }
 
     // Create dual loop runner
-    let loop_config = DualLoopConfig::default()
-        .with_planner_interval(planner_interval)
-        .with_executor_interval(executor_interval);
+    let loop_config = settings.dual_loop_config(profile.as_ref());
+    let session_path = AppSettings::session_path();
 
     // Track last status to avoid duplicate prints
     use std::sync::{Arc, Mutex};
     let last_status: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
     let last_status_clone = last_status.clone();
 
-    let runner =
-        DualLoopRunner::new(planner, loop_config).with_feedback_callback(move |feedback| {
-            // Only print on status change
-            let status_str = format!("{:?}", feedback.status);
-            let mut last = last_status_clone.lock().unwrap();
-
-            if last.as_ref() != Some(&status_str) {
-                // Status changed, print it
-                if matches!(
-                    feedback.status,
-                    phone_agent::ExecutorStatus::Completed
-                        | phone_agent::ExecutorStatus::Failed(_)
-                        | phone_agent::ExecutorStatus::Stuck
-                        | phone_agent::ExecutorStatus::Running
-                ) {
-                    println!(
-                        "📡 Executor: {:?} (step {})",
-                        feedback.status, feedback.step_count
-                    );
-                }
-                *last = Some(status_str);
+    let mut runner = DualLoopRunner::new(planner, loop_config);
+    if resume {
+        match session_path {
+            Some(ref path) => {
+                runner = runner.resume(path)?;
+                println!("🔁 已恢复上次会话: {}", path.display());
             }
-        });
+            None => eprintln!("⚠️ Cannot determine session path, starting a new session"),
+        }
+    }
+    if let Some(path) = runner.event_log_path() {
+        println!("📝 事件日志: {}", path.display());
+    }
+    if !settings.event_stream_addr.trim().is_empty() {
+        println!(
+            "📡 实时事件流: ws://{}{}",
+            phone_agent::agent::event_stream_bind_addr(&settings.event_stream_addr),
+            phone_agent::agent::EVENT_STREAM_PATH
+        );
+    }
+    let notifier = Notifier::new(notification_channels).with_lang(&lang);
+    if !notifier.is_empty() {
+        notifier.spawn(runner.subscribe());
+        println!("🔔 已启用完成/接管通知");
+    }
+
+    let runner = runner.with_feedback_callback(move |feedback| {
+        // Only print on status change
+        let status_str = format!("{:?}", feedback.status);
+        let mut last = last_status_clone.lock().unwrap();
+
+        if last.as_ref() != Some(&status_str) {
+            // Status changed, print it
+            if matches!(
+                feedback.status,
+                phone_agent::ExecutorStatus::Completed
+                    | phone_agent::ExecutorStatus::Failed(_)
+                    | phone_agent::ExecutorStatus::Stuck
+                    | phone_agent::ExecutorStatus::TimedOut
+                    | phone_agent::ExecutorStatus::AwaitingApproval
+                    | phone_agent::ExecutorStatus::Running
+            ) {
+                println!(
+                    "📡 Executor: {:?} (step {})",
+                    feedback.status, feedback.step_count
+                );
+            }
+            *last = Some(status_str);
+        }
+    });
 
     // Run the dual loop
     let handle = runner.run().await;
 
+    // Ctrl+C: let the current step finish and flush state before exiting
+    let signal_handle = handle.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            println!("\n🛑 收到中断信号，正在保存状态并退出...");
+            let _ = signal_handle.stop().await;
+            std::process::exit(ErrorCategory::Cancelled.exit_code().into());
+        }
+    });
+
+    // Edits to the settings file (scale, intervals, thresholds, language)
+    // apply to the running session
+    let watch_handle = handle.clone();
+    tokio::spawn(async move {
+        let mut config = config;
+        let mut watcher = SettingsWatcher::new(config.profile());
+        let mut ticker = tokio::time::interval(SETTINGS_WATCH_INTERVAL);
+        while watch_handle.is_running() {
+            ticker.tick().await;
+            let stored = match watcher.poll() {
+                Some(Ok(stored)) => stored,
+                Some(Err(e)) => {
+                    eprintln!("⚠️ Settings file not reloaded: {}", e);
+                    continue;
+                }
+                None => continue,
+            };
+            let changes = config.reload_file(stored);
+            if !changes.is_empty() && watch_handle.apply_settings(changes).await.is_err() {
+                break;
+            }
+        }
+    });
+
     // Interactive input loop
     println!("Dual Loop Interactive Mode");
     println!("Type your task and press Enter. User input is queued to Planner.");
+    println!("Type 'approve <task_id>' or 'reject <task_id>' to answer approval requests.");
+    println!("Type 'nudge <text>' to send a hint straight to the executor.");
+    if settings.propose_only {
+        println!(
+            "Propose-only mode: 'approve-step [n]', 'reject-step [reason]', 'propose off' for full autonomy."
+        );
+    }
     println!("Type 'quit' or 'exit' to stop.\n");
 
     let stdin = io::stdin();
@@ -631,6 +1222,39 @@ async fn run_dual_loop_mode(
             continue;
         }
 
+        if input == "approve-step" || input.starts_with("approve-step ") {
+            let steps = input["approve-step".len()..].trim().parse().unwrap_or(1);
+            let _ = handle.approve_proposal(steps).await;
+            continue;
+        }
+
+        if input == "reject-step" || input.starts_with("reject-step ") {
+            let reason = input["reject-step".len()..].trim();
+            let reason = (!reason.is_empty()).then(|| reason.to_string());
+            let _ = handle.reject_proposal(reason).await;
+            continue;
+        }
+
+        if let Some(mode) = input.strip_prefix("propose ") {
+            let _ = handle.set_propose_only(mode.trim() == "on").await;
+            continue;
+        }
+
+        if let Some(text) = input.strip_prefix("nudge ") {
+            let _ = handle.inject_executor_prompt(text.trim()).await;
+            continue;
+        }
+
+        if let Some(task_id) = input.strip_prefix("approve ") {
+            let _ = handle.approve(task_id.trim()).await;
+            continue;
+        }
+
+        if let Some(task_id) = input.strip_prefix("reject ") {
+            let _ = handle.reject(task_id.trim()).await;
+            continue;
+        }
+
         // Send input to planner
         match handle.send_user_input(input.to_string()).await {
             Ok(_) => println!("✅ Input queued to Planner\n"),
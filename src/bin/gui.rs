@@ -17,8 +17,9 @@ fn main() -> iced::Result {
         PhoneAgentApp::view,
     )
     .theme(PhoneAgentApp::theme)
+    .subscription(PhoneAgentApp::subscription)
     .window_size(Size::new(900.0, 700.0))
     .default_font(Font::with_name("Noto Sans SC"))
     .font(NOTO_SANS_SC)
-    .run_with(|| (PhoneAgentApp::new(), iced::Task::none()))
+    .run_with(PhoneAgentApp::boot)
 }
@@ -4,28 +4,124 @@
 //! scale factors by generating test images with known marker positions and
 //! asking the LLM to identify those positions.
 //!
-//! Two calibration modes are available:
+//! Three calibration modes are available:
 //! - **Simple mode**: Uses colored markers at specific positions
 //! - **Complex mode**: Simulates real UI layouts (comment lists, etc.)
+//! - **Grid mode**: Markers on a grid reaching toward the screen edges, fitted
+//!   with a nonlinear [`CoordinateMapping`]
+//!
+//! Marker images can optionally be shown on the device itself and captured back
+//! with a screenshot (`CalibrationConfig::with_on_device`), so calibration goes
+//! through the same screenshot pipeline as a real task.
 
 use ab_glyph::{FontRef, PxScale};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use image::{Rgb, RgbImage};
 use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_rect_mut, draw_text_mut};
 use imageproc::rect::Rect;
+use serde::{Deserialize, Serialize};
 use std::io::Cursor;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Semaphore;
 
-use crate::adb::get_screenshot;
+use super::{CalibrationTheme, CoordinateMapping, GridStyle, MappingSample, Orientation};
+use crate::adb::{back, get_screenshot, push_file, remove_file, show_image};
 use crate::model::{MessageBuilder, ModelClient};
 
 /// Calibration mode
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum CalibrationMode {
     /// Simple mode: colored markers at specific positions
     #[default]
     Simple,
     /// Complex mode: simulates real UI with comment-like layouts
     Complex,
+    /// Grid mode: markers on an N×N grid, fitted with a nonlinear mapping
+    Grid,
+}
+
+impl CalibrationMode {
+    /// Name used in settings and `CALIBRATION_MODE`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CalibrationMode::Simple => "simple",
+            CalibrationMode::Complex => "complex",
+            CalibrationMode::Grid => "grid",
+        }
+    }
+
+    /// Parse a mode name as accepted by [`CalibrationMode::as_str`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "simple" => Some(CalibrationMode::Simple),
+            "complex" => Some(CalibrationMode::Complex),
+            "grid" => Some(CalibrationMode::Grid),
+            _ => None,
+        }
+    }
+}
+
+/// Mock UI drawn for a complex calibration round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplexScene {
+    /// Comment list with avatars, usernames, like and reply buttons
+    CommentList,
+    /// Chat conversation with message bubbles and an input bar
+    Chat,
+    /// Settings list with switches and arrows
+    Settings,
+    /// Photo grid with a select button and a tab bar
+    PhotoGrid,
+}
+
+impl ComplexScene {
+    /// All scenes, in the order they are rotated through.
+    pub const ALL: [ComplexScene; 4] = [
+        ComplexScene::CommentList,
+        ComplexScene::Chat,
+        ComplexScene::Settings,
+        ComplexScene::PhotoGrid,
+    ];
+
+    /// Name used in settings and `CALIBRATION_SCENES`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComplexScene::CommentList => "comments",
+            ComplexScene::Chat => "chat",
+            ComplexScene::Settings => "settings",
+            ComplexScene::PhotoGrid => "photos",
+        }
+    }
+
+    /// Parse a comma-separated list of scene names; an empty list means all scenes.
+    pub fn parse_list(names: &str) -> Result<Vec<Self>, String> {
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                Self::ALL
+                    .into_iter()
+                    .find(|scene| scene.as_str().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| format!("Unknown calibration scene: {}", name))
+            })
+            .collect()
+    }
+
+    /// What the screenshot shows, as described in the prompt.
+    fn description(&self, lang: &str) -> &'static str {
+        match (self, lang == "cn") {
+            (ComplexScene::CommentList, true) => "一个评论区界面",
+            (ComplexScene::CommentList, false) => "a comment section UI",
+            (ComplexScene::Chat, true) => "一个聊天对话界面",
+            (ComplexScene::Chat, false) => "a chat conversation UI",
+            (ComplexScene::Settings, true) => "一个设置列表界面",
+            (ComplexScene::Settings, false) => "a settings list UI",
+            (ComplexScene::PhotoGrid, true) => "一个相册网格界面",
+            (ComplexScene::PhotoGrid, false) => "a photo grid UI",
+        }
+    }
 }
 
 /// Default calibration points as (x_ratio, y_ratio) where 0.0-1.0 represents screen percentage
@@ -37,6 +133,36 @@ pub const DEFAULT_CALIBRATION_POINTS: [(f64, f64); 5] = [
     (0.75, 0.75), // Bottom-right quadrant
 ];
 
+/// Points used to verify a computed transform, as (x_ratio, y_ratio). They
+/// differ from the calibration points so the check isn't fitted to itself.
+pub const DEFAULT_VERIFICATION_POINTS: [(f64, f64); 4] = [
+    (0.4, 0.35),  // Upper center
+    (0.6, 0.65),  // Lower center
+    (0.15, 0.55), // Left edge
+    (0.85, 0.45), // Right edge
+];
+
+/// Default largest residual, in pixels, a verified calibration may have.
+pub const DEFAULT_MAX_RESIDUAL: f64 = 50.0;
+
+/// Default number of model requests in flight at the same time.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+/// Height in pixels of the title bar drawn at the top of complex scenes.
+const SCENE_HEADER_HEIGHT: u32 = 120;
+
+/// Where marker images are pushed on the device for on-device calibration.
+const ON_DEVICE_IMAGE_PATH: &str = "/sdcard/Download/phone_agent_calibration.png";
+
+/// Grid points as (x_ratio, y_ratio), spread from 10% to 90% of the screen on each axis.
+pub fn grid_calibration_points(size: usize) -> Vec<(f64, f64)> {
+    let size = size.max(3);
+    let step = |i: usize| 0.1 + 0.8 * i as f64 / (size - 1) as f64;
+    (0..size)
+        .flat_map(|row| (0..size).map(move |col| (step(col), step(row))))
+        .collect()
+}
+
 /// A simulated comment for complex calibration
 #[derive(Debug, Clone)]
 pub struct MockComment {
@@ -167,12 +293,33 @@ pub struct CalibrationConfig {
     pub calibration_points: Vec<(f64, f64)>,
     /// Number of calibration rounds for complex mode
     pub complex_rounds: usize,
+    /// Scenes rotated through in complex mode (empty = all)
+    pub complex_scenes: Vec<ComplexScene>,
+    /// Points per row and column for grid mode (minimum 3)
+    pub grid_size: usize,
+    /// Points checked against the computed transform (empty = skip verification)
+    pub verification_points: Vec<(f64, f64)>,
+    /// Largest residual in pixels before the calibration is marked as failed
+    pub max_residual: f64,
+    /// Display marker images on the device and screenshot them back (marker modes only)
+    pub on_device: bool,
+    /// Rotate image themes across points and rounds instead of using one look
+    pub vary_theme: bool,
+    /// Where the theme rotation starts (random by default)
+    pub theme_seed: u64,
+    /// Print progress to stderr, keeping stdout free for machine-readable output
+    pub progress_to_stderr: bool,
+    /// Model requests in flight at the same time
+    pub max_concurrency: usize,
     /// Language for prompts ("cn" or "en")
     pub lang: String,
     /// Marker size in pixels (will be scaled based on screen size)
     pub marker_size_ratio: f64,
     /// ADB device ID (optional)
     pub device_id: Option<String>,
+    /// Calibrate for this orientation, generating rotated images if the phone
+    /// is held the other way (`None` = the phone's current orientation)
+    pub orientation: Option<Orientation>,
 }
 
 impl Default for CalibrationConfig {
@@ -181,9 +328,22 @@ impl Default for CalibrationConfig {
             mode: CalibrationMode::Simple,
             calibration_points: DEFAULT_CALIBRATION_POINTS.to_vec(),
             complex_rounds: 5,
+            complex_scenes: ComplexScene::ALL.to_vec(),
+            grid_size: 3,
+            verification_points: DEFAULT_VERIFICATION_POINTS.to_vec(),
+            max_residual: DEFAULT_MAX_RESIDUAL,
+            on_device: false,
+            vary_theme: true,
+            theme_seed: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            progress_to_stderr: false,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
             lang: "cn".to_string(),
             marker_size_ratio: 0.05,
             device_id: None,
+            orientation: None,
         }
     }
 }
@@ -204,19 +364,113 @@ impl CalibrationConfig {
         self
     }
 
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = Some(orientation);
+        self
+    }
+
     pub fn with_complex_rounds(mut self, rounds: usize) -> Self {
         self.complex_rounds = rounds;
         self
     }
+
+    /// Restrict complex mode to the given scenes; an empty list uses all of them.
+    pub fn with_complex_scenes(mut self, scenes: Vec<ComplexScene>) -> Self {
+        self.complex_scenes = scenes;
+        self
+    }
+
+    pub fn with_grid_size(mut self, size: usize) -> Self {
+        self.grid_size = size.max(3);
+        self
+    }
+
+    /// Set the residual threshold in pixels; `0` skips the verification pass.
+    pub fn with_max_residual(mut self, max_residual: f64) -> Self {
+        self.max_residual = max_residual;
+        if max_residual <= 0.0 {
+            self.verification_points.clear();
+        }
+        self
+    }
+
+    pub fn with_verification_points(mut self, points: Vec<(f64, f64)>) -> Self {
+        self.verification_points = points;
+        self
+    }
+
+    /// Show marker images on the device and calibrate against a real screenshot.
+    pub fn with_on_device(mut self, on_device: bool) -> Self {
+        self.on_device = on_device;
+        self
+    }
+
+    /// Vary background, marker colors and grid style between images.
+    pub fn with_theme_variation(mut self, vary_theme: bool) -> Self {
+        self.vary_theme = vary_theme;
+        self
+    }
+
+    /// Fix the theme rotation, e.g. to reproduce a run.
+    pub fn with_theme_seed(mut self, seed: u64) -> Self {
+        self.theme_seed = seed;
+        self
+    }
+
+    /// Limit how many model requests run at once; `1` queries one image at a time.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    pub fn with_progress_to_stderr(mut self, progress_to_stderr: bool) -> Self {
+        self.progress_to_stderr = progress_to_stderr;
+        self
+    }
+
+    /// Scene drawn in complex `round`.
+    fn complex_scene(&self, round: usize) -> ComplexScene {
+        let scenes: &[ComplexScene] = if self.complex_scenes.is_empty() {
+            &ComplexScene::ALL
+        } else {
+            &self.complex_scenes
+        };
+        scenes[round % scenes.len()]
+    }
+
+    /// Theme for the `index`-th marker image.
+    fn theme(&self, index: usize) -> CalibrationTheme {
+        if self.vary_theme {
+            CalibrationTheme::rotating(self.theme_seed, index)
+        } else {
+            CalibrationTheme::default()
+        }
+    }
+
+    /// Whether the complex UI of `round` is drawn in dark mode.
+    fn dark_round(&self, round: usize) -> bool {
+        self.vary_theme && (self.theme_seed as usize).wrapping_add(round) % 2 == 1
+    }
 }
 
+/// A calibration that produced no usable factors.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("Calibration failed: {0}")]
+pub struct CalibrationError(pub String);
+
 /// Result of a calibration process.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CalibrationResult {
     /// Calculated X scale factor
     pub scale_x: f64,
     /// Calculated Y scale factor
     pub scale_y: f64,
+    /// Calculated X offset in pixels, applied after scaling
+    #[serde(default)]
+    pub offset_x: f64,
+    /// Calculated Y offset in pixels, applied after scaling
+    #[serde(default)]
+    pub offset_y: f64,
     /// Detected screen width
     pub screen_width: u32,
     /// Detected screen height
@@ -229,10 +483,62 @@ pub struct CalibrationResult {
     pub error: Option<String>,
     /// Calibration mode used
     pub mode: CalibrationMode,
+    /// Nonlinear mapping fitted in grid mode; replaces scale and offset when set
+    #[serde(default)]
+    pub mapping: Option<CoordinateMapping>,
+    /// Residuals of the verification pass, if one was run
+    #[serde(default)]
+    pub verification: Option<CalibrationVerification>,
+}
+
+impl CalibrationResult {
+    /// Why the calibration failed, if it did.
+    pub fn failure(&self) -> Option<CalibrationError> {
+        (!self.success).then(|| {
+            CalibrationError(
+                self.error
+                    .clone()
+                    .unwrap_or_else(|| "no usable result".to_string()),
+            )
+        })
+    }
+
+    /// Orientation the calibration was made in.
+    pub fn orientation(&self) -> Orientation {
+        Orientation::from_size(self.screen_width, self.screen_height)
+    }
+
+    /// Map a reported coordinate to actual pixels, the same way `ActionHandler` does.
+    pub fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        match &self.mapping {
+            Some(mapping) => mapping.apply(x, y),
+            None => (
+                x * self.scale_x + self.offset_x,
+                y * self.scale_y + self.offset_y,
+            ),
+        }
+    }
+}
+
+/// Outcome of checking a computed transform against fresh points.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationVerification {
+    /// Verification points with the coordinates the LLM reported
+    pub point_results: Vec<PointCalibrationResult>,
+    /// Distance in pixels between each transformed report and its marker
+    pub residuals: Vec<f64>,
+    /// Mean residual in pixels
+    pub mean_residual: f64,
+    /// Largest residual in pixels
+    pub max_residual: f64,
+    /// Threshold the largest residual was checked against
+    pub threshold: f64,
+    /// Whether every residual was within the threshold
+    pub passed: bool,
 }
 
 /// Result for a single calibration point.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PointCalibrationResult {
     /// Description of what was being located
     pub description: String,
@@ -248,6 +554,12 @@ pub struct PointCalibrationResult {
     pub ratio_x: f64,
     /// Y ratio (expected / reported)
     pub ratio_y: f64,
+    /// Distance in pixels between the transformed report and the expected position
+    #[serde(default)]
+    pub residual: Option<f64>,
+    /// Whether the point was rejected as an outlier
+    #[serde(default)]
+    pub outlier: bool,
 }
 
 /// Target element in complex calibration
@@ -270,9 +582,20 @@ impl CoordinateCalibrator {
         Self { config }
     }
 
+    /// Print a progress message, to stderr if so configured.
+    fn progress(&self, message: std::fmt::Arguments) {
+        if self.config.progress_to_stderr {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
     /// Get screen dimensions by taking a screenshot from the device.
     fn get_screen_dimensions(&self) -> Result<(u32, u32), String> {
-        println!("📱 Taking screenshot to detect screen dimensions...");
+        self.progress(format_args!(
+            "📱 Taking screenshot to detect screen dimensions..."
+        ));
 
         let screenshot = get_screenshot(self.config.device_id.as_deref());
 
@@ -287,7 +610,10 @@ impl CoordinateCalibrator {
             return Err("Invalid screen dimensions".to_string());
         }
 
-        println!("   Detected screen size: {}x{}", width, height);
+        self.progress(format_args!(
+            "   Detected screen size: {}x{}",
+            width, height
+        ));
 
         Ok((width, height))
     }
@@ -300,75 +626,339 @@ impl CoordinateCalibrator {
                 return CalibrationResult {
                     scale_x: 1.0,
                     scale_y: 1.0,
+                    offset_x: 0.0,
+                    offset_y: 0.0,
                     screen_width: 0,
                     screen_height: 0,
                     point_results: Vec::new(),
                     success: false,
                     error: Some(format!("Failed to get screen dimensions: {}", e)),
                     mode: self.config.mode,
+                    mapping: None,
+                    verification: None,
                 };
             }
         };
 
-        match self.config.mode {
+        // Calibrate for the other orientation by generating rotated images
+        if let Some(orientation) = self.config.orientation {
+            let (width, height) = orientation.fit(screen_width, screen_height);
+            if (width, height) != (screen_width, screen_height) {
+                self.progress(format_args!(
+                    "   Calibrating {} with generated {}x{} images",
+                    orientation.as_str(),
+                    width,
+                    height
+                ));
+                if self.config.on_device {
+                    self.progress(format_args!(
+                        "   ⚠️ On-device display follows the phone's orientation, using generated images"
+                    ));
+                    let config = CalibrationConfig {
+                        on_device: false,
+                        ..self.config.clone()
+                    };
+                    return CoordinateCalibrator::new(config)
+                        .run(model_client, width, height)
+                        .await;
+                }
+                return self.run(model_client, width, height).await;
+            }
+        }
+
+        self.run(model_client, screen_width, screen_height).await
+    }
+
+    /// Calibrate once per orientation; an empty list calibrates the phone's
+    /// current orientation. Always returns at least one result.
+    pub async fn calibrate_orientations(
+        &self,
+        model_client: &ModelClient,
+        orientations: &[Orientation],
+    ) -> Vec<CalibrationResult> {
+        if orientations.is_empty() {
+            return vec![self.calibrate(model_client).await];
+        }
+
+        let mut results = Vec::new();
+        for &orientation in orientations {
+            self.progress(format_args!(
+                "\n🔄 Calibrating {} orientation...",
+                orientation.as_str()
+            ));
+            let calibrator =
+                CoordinateCalibrator::new(self.config.clone().with_orientation(orientation));
+            results.push(calibrator.calibrate(model_client).await);
+        }
+        results
+    }
+
+    /// Calibrate against a screen of the given size.
+    async fn run(
+        &self,
+        model_client: &ModelClient,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> CalibrationResult {
+        let mut result = match self.config.mode {
             CalibrationMode::Simple => {
-                self.calibrate_simple(model_client, screen_width, screen_height)
+                self.calibrate_simple(
+                    model_client,
+                    &self.config.calibration_points,
+                    screen_width,
+                    screen_height,
+                )
+                .await
+            }
+            CalibrationMode::Grid => {
+                let points = grid_calibration_points(self.config.grid_size);
+                self.calibrate_simple(model_client, &points, screen_width, screen_height)
                     .await
             }
             CalibrationMode::Complex => {
                 self.calibrate_complex(model_client, screen_width, screen_height)
                     .await
             }
+        };
+
+        if result.success && !self.config.verification_points.is_empty() {
+            self.verify(model_client, &mut result).await;
+        }
+
+        if self.config.on_device {
+            remove_file(ON_DEVICE_IMAGE_PATH, self.config.device_id.as_deref());
+        }
+        result
+    }
+
+    /// Image shown to the LLM for a marker at `(x, y)`, with the marker position in it.
+    ///
+    /// In on-device mode the image is displayed on the phone and captured with a
+    /// screenshot; the marker is then located in the screenshot, since the viewer
+    /// may scale, letterbox or crop it. Falls back to the generated image if any
+    /// step fails.
+    fn marker_image(
+        &self,
+        x: i32,
+        y: i32,
+        point_num: usize,
+        width: u32,
+        height: u32,
+        theme: &CalibrationTheme,
+    ) -> (String, i32, i32) {
+        let generated =
+            self.generate_simple_calibration_image(x, y, point_num, width, height, theme);
+        if !self.config.on_device {
+            return (generated, x, y);
+        }
+
+        match self.capture_on_device(&generated, theme) {
+            Ok((screenshot, found_x, found_y)) => {
+                if (found_x, found_y) != (x, y) {
+                    self.progress(format_args!(
+                        "   Marker displayed at ({}, {}) on device",
+                        found_x, found_y
+                    ));
+                }
+                (screenshot, found_x, found_y)
+            }
+            Err(e) => {
+                self.progress(format_args!(
+                    "   ⚠️ On-device display failed ({}), using generated image",
+                    e
+                ));
+                (generated, x, y)
+            }
+        }
+    }
+
+    /// Push an image to the device, open it full-screen and screenshot it back.
+    fn capture_on_device(
+        &self,
+        image_base64: &str,
+        theme: &CalibrationTheme,
+    ) -> Result<(String, i32, i32), String> {
+        let device_id = self.config.device_id.as_deref();
+        let png = STANDARD.decode(image_base64).map_err(|e| e.to_string())?;
+        let local = std::env::temp_dir().join("phone_agent_calibration.png");
+        std::fs::write(&local, png).map_err(|e| e.to_string())?;
+
+        push_file(&local, ON_DEVICE_IMAGE_PATH, device_id)?;
+        let _ = std::fs::remove_file(&local);
+        if !show_image(ON_DEVICE_IMAGE_PATH, device_id, None) {
+            return Err("no image viewer could open the marker".to_string());
+        }
+
+        let screenshot = get_screenshot(device_id);
+        back(device_id, Some(500));
+        if screenshot.is_sensitive {
+            return Err("screenshot failed".to_string());
+        }
+
+        let captured = STANDARD
+            .decode(&screenshot.base64_data)
+            .map_err(|e| e.to_string())?;
+        let img = image::load_from_memory(&captured)
+            .map_err(|e| e.to_string())?
+            .to_rgb8();
+        let (x, y) = locate_marker(&img, theme).ok_or("marker not visible in screenshot")?;
+        Ok((screenshot.base64_data, x, y))
+    }
+
+    /// Run the verification points through the computed transform and mark the
+    /// calibration as failed if any residual exceeds the threshold.
+    async fn verify(&self, model_client: &ModelClient, result: &mut CalibrationResult) {
+        self.progress(format_args!("\n🔍 Verifying calibration...\n"));
+
+        let (screen_width, screen_height) = (result.screen_width, result.screen_height);
+        let points = &self.config.verification_points;
+
+        let mut point_results = Vec::new();
+        let mut residuals = Vec::new();
+
+        let mut requests = Vec::new();
+        let mut markers = Vec::new();
+        for (i, &(x_ratio, y_ratio)) in points.iter().enumerate() {
+            let expected_x = (x_ratio * screen_width as f64) as i32;
+            let expected_y = (y_ratio * screen_height as f64) as i32;
+            // Continue the rotation after the calibration points
+            let theme = self.config.theme(self.config.calibration_points.len() + i);
+
+            let (image_base64, expected_x, expected_y) = self.marker_image(
+                expected_x,
+                expected_y,
+                i + 1,
+                screen_width,
+                screen_height,
+                &theme,
+            );
+            requests.push((
+                self.simple_prompt(i + 1, screen_width, screen_height, &theme),
+                image_base64,
+            ));
+            markers.push((expected_x, expected_y, theme));
+        }
+        let answers = self.query_all(model_client, requests).await;
+
+        for (i, ((expected_x, expected_y, theme), answer)) in
+            markers.into_iter().zip(answers).enumerate()
+        {
+            match answer {
+                Ok((reported_x, reported_y)) => {
+                    let (actual_x, actual_y) =
+                        result.transform(reported_x as f64, reported_y as f64);
+                    let residual = ((actual_x - expected_x as f64).powi(2)
+                        + (actual_y - expected_y as f64).powi(2))
+                    .sqrt();
+                    self.progress(format_args!(
+                        "📍 Verification point {}/{}: expected ({}, {}), transformed ({:.0}, {:.0}), residual {:.1}px",
+                        i + 1,
+                        points.len(),
+                        expected_x,
+                        expected_y,
+                        actual_x,
+                        actual_y,
+                        residual
+                    ));
+
+                    residuals.push(residual);
+                    point_results.push(PointCalibrationResult {
+                        description: format!("Verification {} ({})", i + 1, theme.name),
+                        expected_x,
+                        expected_y,
+                        reported_x,
+                        reported_y,
+                        ratio_x: expected_x as f64 / reported_x.max(1) as f64,
+                        ratio_y: expected_y as f64 / reported_y.max(1) as f64,
+                        residual: Some(residual),
+                        outlier: false,
+                    });
+                }
+                Err(e) => self.progress(format_args!(
+                    "   ❌ Verification point {} failed: {}",
+                    i + 1,
+                    e
+                )),
+            }
         }
+
+        let verification =
+            summarize_verification(point_results, residuals, self.config.max_residual);
+        if verification.residuals.is_empty() {
+            result.success = false;
+            result.error = Some("Verification failed: no verification point answered".to_string());
+        } else if !verification.passed {
+            result.success = false;
+            result.error = Some(format!(
+                "Verification residual {:.1}px exceeds threshold {:.1}px",
+                verification.max_residual, verification.threshold
+            ));
+        }
+
+        self.progress(format_args!(
+            "\n{} Verification: mean residual {:.1}px, max {:.1}px (threshold {:.1}px)",
+            if result.success { "✅" } else { "❌" },
+            verification.mean_residual,
+            verification.max_residual,
+            verification.threshold
+        ));
+        result.verification = Some(verification);
     }
 
-    /// Simple calibration with colored markers
+    /// Simple calibration with colored markers (also used by grid mode)
     async fn calibrate_simple(
         &self,
         model_client: &ModelClient,
+        points: &[(f64, f64)],
         screen_width: u32,
         screen_height: u32,
     ) -> CalibrationResult {
-        println!("\n🎯 Running SIMPLE calibration mode...\n");
-
-        let marker_size = (screen_width as f64 * self.config.marker_size_ratio) as u32;
+        self.progress(format_args!(
+            "\n🎯 Running {} calibration mode...\n",
+            format!("{:?}", self.config.mode).to_uppercase()
+        ));
 
         let mut point_results = Vec::new();
-        let mut total_ratio_x = 0.0;
-        let mut total_ratio_y = 0.0;
-        let mut valid_points = 0;
+        let mut valid_points = Vec::new();
 
-        for (i, &(x_ratio, y_ratio)) in self.config.calibration_points.iter().enumerate() {
+        // Generate every image first, then ask about all of them at once
+        let mut requests = Vec::new();
+        let mut markers = Vec::new();
+        for (i, &(x_ratio, y_ratio)) in points.iter().enumerate() {
             let expected_x = (x_ratio * screen_width as f64) as i32;
             let expected_y = (y_ratio * screen_height as f64) as i32;
 
-            println!(
-                "📍 Calibrating point {}/{}: expected ({}, {})",
-                i + 1,
-                self.config.calibration_points.len(),
-                expected_x,
-                expected_y
-            );
+            let theme = self.config.theme(i);
 
-            let image_base64 = self.generate_simple_calibration_image(
+            let (image_base64, expected_x, expected_y) = self.marker_image(
                 expected_x,
                 expected_y,
                 i + 1,
                 screen_width,
                 screen_height,
-                marker_size,
+                &theme,
             );
+            requests.push((
+                self.simple_prompt(i + 1, screen_width, screen_height, &theme),
+                image_base64,
+            ));
+            markers.push((expected_x, expected_y, theme));
+        }
+        let answers = self.query_all(model_client, requests).await;
 
-            match self
-                .ask_llm_for_simple_position(
-                    model_client,
-                    &image_base64,
-                    i + 1,
-                    screen_width,
-                    screen_height,
-                )
-                .await
-            {
+        for (i, (((x_ratio, y_ratio), (expected_x, expected_y, theme)), answer)) in
+            points.iter().zip(markers).zip(answers).enumerate()
+        {
+            self.progress(format_args!(
+                "📍 Calibrating point {}/{}: expected ({}, {}), theme {}",
+                i + 1,
+                points.len(),
+                expected_x,
+                expected_y,
+                theme.name
+            ));
+
+            match answer {
                 Ok((reported_x, reported_y)) => {
                     let ratio_x = if reported_x != 0 {
                         expected_x as f64 / reported_x as f64
@@ -381,25 +971,26 @@ impl CoordinateCalibrator {
                         1.0
                     };
 
-                    println!(
+                    self.progress(format_args!(
                         "   LLM reported: ({}, {}), ratios: X={:.3}, Y={:.3}",
                         reported_x, reported_y, ratio_x, ratio_y
-                    );
+                    ));
 
                     if ratio_x > 0.5 && ratio_x < 2.0 && ratio_y > 0.5 && ratio_y < 2.0 {
-                        total_ratio_x += ratio_x;
-                        total_ratio_y += ratio_y;
-                        valid_points += 1;
+                        valid_points.push(point_results.len());
                     } else {
-                        println!("   ⚠️ Ratio out of reasonable range, skipping this point");
+                        self.progress(format_args!(
+                            "   ⚠️ Ratio out of reasonable range, skipping this point"
+                        ));
                     }
 
                     point_results.push(PointCalibrationResult {
                         description: format!(
-                            "Point {} ({:.0}%, {:.0}%)",
+                            "Point {} ({:.0}%, {:.0}%, {})",
                             i + 1,
                             x_ratio * 100.0,
-                            y_ratio * 100.0
+                            y_ratio * 100.0,
+                            theme.name
                         ),
                         expected_x,
                         expected_y,
@@ -407,10 +998,12 @@ impl CoordinateCalibrator {
                         reported_y,
                         ratio_x,
                         ratio_y,
+                        residual: None,
+                        outlier: false,
                     });
                 }
                 Err(e) => {
-                    println!("   ❌ Failed to get LLM response: {}", e);
+                    self.progress(format_args!("   ❌ Failed to get LLM response: {}", e));
                     point_results.push(PointCalibrationResult {
                         description: format!("Point {} (failed)", i + 1),
                         expected_x,
@@ -419,19 +1012,14 @@ impl CoordinateCalibrator {
                         reported_y: 0,
                         ratio_x: 1.0,
                         ratio_y: 1.0,
+                        residual: None,
+                        outlier: false,
                     });
                 }
             }
         }
 
-        self.build_result(
-            point_results,
-            total_ratio_x,
-            total_ratio_y,
-            valid_points,
-            screen_width,
-            screen_height,
-        )
+        self.build_result(point_results, &valid_points, screen_width, screen_height)
     }
 
     /// Complex calibration with simulated UI layouts
@@ -441,12 +1029,12 @@ impl CoordinateCalibrator {
         screen_width: u32,
         screen_height: u32,
     ) -> CalibrationResult {
-        println!("\n🎯 Running COMPLEX calibration mode (comment list simulation)...\n");
+        self.progress(format_args!(
+            "\n🎯 Running COMPLEX calibration mode (mock app UIs)...\n"
+        ));
 
         let mut point_results = Vec::new();
-        let mut total_ratio_x = 0.0;
-        let mut total_ratio_y = 0.0;
-        let mut valid_points = 0;
+        let mut valid_points = Vec::new();
 
         let comments = if self.config.lang == "cn" {
             MockComment::random_comments_cn()
@@ -454,32 +1042,42 @@ impl CoordinateCalibrator {
             MockComment::random_comments_en()
         };
 
+        // Generate every round's UI image first, then ask about all of them at once
+        let mut requests = Vec::new();
+        let mut rounds = Vec::new();
         for round in 0..self.config.complex_rounds {
-            println!("📋 Round {}/{}:", round + 1, self.config.complex_rounds);
-
-            // Generate complex UI image and get a random target
+            let scene = self.config.complex_scene(round);
             let (image_base64, target) = self.generate_complex_calibration_image(
                 screen_width,
                 screen_height,
                 &comments,
                 round,
             );
-
-            println!(
-                "   Target: {} \"{}\" at ({}, {})",
-                target.element_type, target.description, target.x, target.y
-            );
-
-            match self
-                .ask_llm_for_complex_position(
-                    model_client,
-                    &image_base64,
-                    &target,
-                    screen_width,
-                    screen_height,
-                )
-                .await
-            {
+            requests.push((
+                self.complex_prompt(scene, &target, screen_width, screen_height),
+                image_base64,
+            ));
+            rounds.push((scene, target));
+        }
+        let answers = self.query_all(model_client, requests).await;
+
+        for (round, ((scene, target), answer)) in rounds.into_iter().zip(answers).enumerate() {
+            self.progress(format_args!(
+                "📋 Round {}/{}:",
+                round + 1,
+                self.config.complex_rounds
+            ));
+
+            self.progress(format_args!(
+                "   Scene: {}, target: {} \"{}\" at ({}, {})",
+                scene.as_str(),
+                target.element_type,
+                target.description,
+                target.x,
+                target.y
+            ));
+
+            match answer {
                 Ok((reported_x, reported_y)) => {
                     let ratio_x = if reported_x != 0 {
                         target.x as f64 / reported_x as f64
@@ -492,35 +1090,44 @@ impl CoordinateCalibrator {
                         1.0
                     };
 
-                    println!(
+                    self.progress(format_args!(
                         "   LLM reported: ({}, {}), ratios: X={:.3}, Y={:.3}",
                         reported_x, reported_y, ratio_x, ratio_y
-                    );
+                    ));
 
                     if ratio_x > 0.5 && ratio_x < 2.0 && ratio_y > 0.5 && ratio_y < 2.0 {
-                        total_ratio_x += ratio_x;
-                        total_ratio_y += ratio_y;
-                        valid_points += 1;
+                        valid_points.push(point_results.len());
                     } else {
-                        println!("   ⚠️ Ratio out of reasonable range, skipping");
+                        self.progress(format_args!(
+                            "   ⚠️ Ratio out of reasonable range, skipping"
+                        ));
                     }
 
                     point_results.push(PointCalibrationResult {
-                        description: format!("{}: {}", target.element_type, target.description),
+                        description: format!(
+                            "{}/{}: {}",
+                            scene.as_str(),
+                            target.element_type,
+                            target.description
+                        ),
                         expected_x: target.x,
                         expected_y: target.y,
                         reported_x,
                         reported_y,
                         ratio_x,
                         ratio_y,
+                        residual: None,
+                        outlier: false,
                     });
                 }
                 Err(e) => {
-                    println!("   ❌ Failed: {}", e);
+                    self.progress(format_args!("   ❌ Failed: {}", e));
                     point_results.push(PointCalibrationResult {
                         description: format!(
-                            "{}: {} (failed)",
-                            target.element_type, target.description
+                            "{}/{}: {} (failed)",
+                            scene.as_str(),
+                            target.element_type,
+                            target.description
                         ),
                         expected_x: target.x,
                         expected_y: target.y,
@@ -528,66 +1135,144 @@ impl CoordinateCalibrator {
                         reported_y: 0,
                         ratio_x: 1.0,
                         ratio_y: 1.0,
+                        residual: None,
+                        outlier: false,
                     });
                 }
             }
         }
 
-        self.build_result(
-            point_results,
-            total_ratio_x,
-            total_ratio_y,
-            valid_points,
-            screen_width,
-            screen_height,
-        )
+        self.build_result(point_results, &valid_points, screen_width, screen_height)
     }
 
     /// Build calibration result from collected data
     fn build_result(
         &self,
-        point_results: Vec<PointCalibrationResult>,
-        total_ratio_x: f64,
-        total_ratio_y: f64,
-        valid_points: usize,
+        mut point_results: Vec<PointCalibrationResult>,
+        valid_points: &[usize],
         screen_width: u32,
         screen_height: u32,
     ) -> CalibrationResult {
-        if valid_points == 0 {
+        if valid_points.is_empty() {
             return CalibrationResult {
                 scale_x: 1.0,
                 scale_y: 1.0,
+                offset_x: 0.0,
+                offset_y: 0.0,
                 screen_width,
                 screen_height,
                 point_results,
                 success: false,
                 error: Some("No valid calibration points".to_string()),
                 mode: self.config.mode,
+                mapping: None,
+                verification: None,
             };
         }
 
-        let scale_x = total_ratio_x / valid_points as f64;
-        let scale_y = total_ratio_y / valid_points as f64;
+        // Reject wild answers before fitting, so one hallucinated point
+        // doesn't skew the result
+        let samples: Vec<MappingSample> = valid_points
+            .iter()
+            .map(|&i| {
+                let p = &point_results[i];
+                (
+                    (p.reported_x as f64, p.reported_y as f64),
+                    (p.expected_x as f64, p.expected_y as f64),
+                )
+            })
+            .collect();
+        let outliers = find_outliers(&samples);
+        let mut inliers = Vec::new();
+        for ((&i, sample), &outlier) in valid_points.iter().zip(&samples).zip(&outliers) {
+            if outlier {
+                let p = &mut point_results[i];
+                p.outlier = true;
+                self.progress(format_args!(
+                    "   ⚠️ Rejected outlier: {} (reported ({}, {}))",
+                    p.description, p.reported_x, p.reported_y
+                ));
+            } else {
+                inliers.push(*sample);
+            }
+        }
+
+        let samples_x: Vec<(f64, f64)> =
+            inliers.iter().map(|&((rx, _), (ex, _))| (rx, ex)).collect();
+        let samples_y: Vec<(f64, f64)> =
+            inliers.iter().map(|&((_, ry), (_, ey))| (ry, ey)).collect();
+        let (scale_x, offset_x) = fit_axis(&samples_x);
+        let (scale_y, offset_y) = fit_axis(&samples_y);
+
+        // Grid mode additionally fits a nonlinear mapping; scale and offset
+        // stay as the fallback for callers that can't use it
+        let mapping = if self.config.mode == CalibrationMode::Grid {
+            let mapping = CoordinateMapping::fit(&inliers);
+            match &mapping {
+                Some(mapping) => self.progress(format_args!(
+                    "   Grid mapping fitted, max residual: {:.1}px",
+                    mapping.max_residual(&inliers)
+                )),
+                None => self.progress(format_args!(
+                    "   ⚠️ Not enough valid grid points for a nonlinear mapping, using scale + offset"
+                )),
+            }
+            mapping
+        } else {
+            None
+        };
 
-        println!("\n✅ Calibration complete!");
-        println!("   Mode: {:?}", self.config.mode);
-        println!("   Screen size: {}x{}", screen_width, screen_height);
-        println!("   Valid points: {}/{}", valid_points, point_results.len());
-        println!(
+        self.progress(format_args!("\n✅ Calibration complete!"));
+        self.progress(format_args!("   Mode: {:?}", self.config.mode));
+        self.progress(format_args!(
+            "   Screen size: {}x{}",
+            screen_width, screen_height
+        ));
+        self.progress(format_args!(
+            "   Valid points: {}/{} ({} outliers rejected)",
+            inliers.len(),
+            point_results.len(),
+            valid_points.len() - inliers.len()
+        ));
+        self.progress(format_args!(
             "   Calculated scale factors: X={:.4}, Y={:.4}",
             scale_x, scale_y
-        );
+        ));
+        self.progress(format_args!(
+            "   Calculated offsets: X={:.1}px, Y={:.1}px",
+            offset_x, offset_y
+        ));
 
-        CalibrationResult {
+        let mut result = CalibrationResult {
             scale_x,
             scale_y,
+            offset_x,
+            offset_y,
             screen_width,
             screen_height,
             point_results,
             success: true,
             error: None,
             mode: self.config.mode,
+            mapping,
+            verification: None,
+        };
+
+        self.progress(format_args!("   Per-point residuals:"));
+        for (&i, &((rx, ry), (ex, ey))) in valid_points.iter().zip(&samples) {
+            let (x, y) = result.transform(rx, ry);
+            let residual = ((x - ex).powi(2) + (y - ey).powi(2)).sqrt();
+            let point = &mut result.point_results[i];
+            point.residual = Some(residual);
+            self.progress(format_args!(
+                "     {}: {:.1}px{}",
+                point.description,
+                residual,
+                if point.outlier { " (outlier)" } else { "" }
+            ));
         }
+
+        result
     }
 
     /// Generate a simple calibration image with a marker at the specified position.
@@ -598,28 +1283,55 @@ impl CoordinateCalibrator {
         point_num: usize,
         width: u32,
         height: u32,
-        marker_size: u32,
+        theme: &CalibrationTheme,
     ) -> String {
-        let mut img = RgbImage::from_fn(width, height, |_, _| Rgb([30u8, 30u8, 30u8]));
-
-        // Draw grid lines
-        let grid_color = Rgb([60u8, 60u8, 60u8]);
-        for i in 1..4 {
-            let x_line = (width * i / 4) as i32;
-            let y_line = (height * i / 4) as i32;
-            draw_filled_rect_mut(&mut img, Rect::at(x_line, 0).of_size(2, height), grid_color);
-            draw_filled_rect_mut(&mut img, Rect::at(0, y_line).of_size(width, 2), grid_color);
+        let mut img = RgbImage::from_fn(width, height, |_, _| Rgb(theme.background));
+
+        // Draw grid
+        let grid_color = Rgb(theme.grid);
+        match theme.grid_style {
+            GridStyle::Lines => {
+                for i in 1..4 {
+                    let x_line = (width * i / 4) as i32;
+                    let y_line = (height * i / 4) as i32;
+                    draw_filled_rect_mut(
+                        &mut img,
+                        Rect::at(x_line, 0).of_size(2, height),
+                        grid_color,
+                    );
+                    draw_filled_rect_mut(
+                        &mut img,
+                        Rect::at(0, y_line).of_size(width, 2),
+                        grid_color,
+                    );
+                }
+            }
+            GridStyle::Dots => {
+                for i in 1..4 {
+                    for j in 1..4 {
+                        let dot_x = (width * i / 4) as i32;
+                        let dot_y = (height * j / 4) as i32;
+                        draw_filled_rect_mut(
+                            &mut img,
+                            Rect::at(dot_x - 5, dot_y - 5).of_size(10, 10),
+                            grid_color,
+                        );
+                    }
+                }
+            }
+            GridStyle::None => {}
         }
 
         // Draw the main marker
+        let marker_size = (width as f64 * self.config.marker_size_ratio) as u32;
         let marker_x = (x - marker_size as i32 / 2).max(0);
         let marker_y = (y - marker_size as i32 / 2).max(0);
 
-        // Red filled marker
+        // Filled marker
         draw_filled_rect_mut(
             &mut img,
             Rect::at(marker_x, marker_y).of_size(marker_size, marker_size),
-            Rgb([255u8, 50u8, 50u8]),
+            Rgb(theme.marker.rgb),
         );
 
         // White border
@@ -632,27 +1344,27 @@ impl CoordinateCalibrator {
             );
         }
 
-        // Yellow crosshair
+        // Crosshair
         let cross_thickness = 4;
         draw_filled_rect_mut(
             &mut img,
             Rect::at(x - cross_thickness as i32 / 2, marker_y)
                 .of_size(cross_thickness, marker_size),
-            Rgb([255u8, 255u8, 0u8]),
+            Rgb(theme.crosshair.rgb),
         );
         draw_filled_rect_mut(
             &mut img,
             Rect::at(marker_x, y - cross_thickness as i32 / 2)
                 .of_size(marker_size, cross_thickness),
-            Rgb([255u8, 255u8, 0u8]),
+            Rgb(theme.crosshair.rgb),
         );
 
-        // Black center dot
+        // Center dot
         let dot_size = 8;
         draw_filled_rect_mut(
             &mut img,
             Rect::at(x - dot_size as i32 / 2, y - dot_size as i32 / 2).of_size(dot_size, dot_size),
-            Rgb([0u8, 0u8, 0u8]),
+            Rgb(theme.center_dot.rgb),
         );
 
         // Point number indicator
@@ -672,7 +1384,7 @@ impl CoordinateCalibrator {
         STANDARD.encode(buffer.into_inner())
     }
 
-    /// Generate a complex calibration image simulating a comment list UI
+    /// Generate a complex calibration image of the scene for this round
     fn generate_complex_calibration_image(
         &self,
         width: u32,
@@ -682,43 +1394,101 @@ impl CoordinateCalibrator {
     ) -> (String, ComplexTarget) {
         let mut img = RgbImage::from_fn(width, height, |_, _| Rgb([255u8, 255u8, 255u8]));
 
-        // UI dimensions based on screen size
-        let padding = (width as f64 * 0.04) as i32;
-        let avatar_size = (width as f64 * 0.10) as u32;
-        let font_size_username = (width as f64 * 0.035) as f32;
-        let font_size_time = (width as f64 * 0.028) as f32;
-        let font_size_content = (width as f64 * 0.038) as f32;
-        let font_size_button = (width as f64 * 0.030) as f32;
-        let line_height = (height as f64 * 0.022) as i32;
-        let comment_spacing = (height as f64 * 0.025) as i32;
-
         // Load font
         let font_data = include_bytes!("../../resources/NotoSansSC-Regular.ttf");
         let font = FontRef::try_from_slice(font_data).ok();
 
-        // Draw header bar
-        draw_filled_rect_mut(
-            &mut img,
-            Rect::at(0, 0).of_size(width, 120),
-            Rgb([245u8, 245u8, 245u8]),
-        );
+        let targets = match self.config.complex_scene(round) {
+            ComplexScene::CommentList => {
+                self.draw_comment_list_scene(&mut img, font.as_ref(), comments)
+            }
+            ComplexScene::Chat => self.draw_chat_scene(&mut img, font.as_ref()),
+            ComplexScene::Settings => self.draw_settings_scene(&mut img, font.as_ref()),
+            ComplexScene::PhotoGrid => self.draw_photo_grid_scene(&mut img, font.as_ref()),
+        };
 
-        if let Some(ref f) = font {
-            let title = if self.config.lang == "cn" {
-                "评论区"
-            } else {
-                "Comments"
-            };
-            draw_text_mut(
-                &mut img,
-                Rgb([33u8, 33u8, 33u8]),
-                padding,
-                40,
-                PxScale::from(font_size_username * 1.2),
-                f,
-                title,
-            );
+        // Select a target based on round number
+        let target_idx = round % targets.len().max(1);
+        let target = if targets.is_empty() {
+            ComplexTarget {
+                description: "fallback".to_string(),
+                element_type: "Unknown".to_string(),
+                x: (width / 2) as i32,
+                y: (height / 2) as i32,
+            }
+        } else {
+            targets[target_idx].clone()
+        };
+
+        // Dark mode: invert the finished UI, keeping the highlight red
+        if self.config.dark_round(round) {
+            image::imageops::invert(&mut img);
+        }
+
+        // Highlight the target with a red dot
+        draw_filled_rect_mut(
+            &mut img,
+            Rect::at(target.x - 5, target.y - 5).of_size(10, 10),
+            Rgb([255u8, 0u8, 0u8]),
+        );
+
+        let mut buffer = Cursor::new(Vec::new());
+        img.write_to(&mut buffer, image::ImageFormat::Png).unwrap();
+        (STANDARD.encode(buffer.into_inner()), target)
+    }
+
+    /// Draw the title bar shared by all complex scenes.
+    fn draw_scene_header(&self, img: &mut RgbImage, font: Option<&FontRef>, title: &str) {
+        let width = img.width();
+        draw_filled_rect_mut(
+            img,
+            Rect::at(0, 0).of_size(width, SCENE_HEADER_HEIGHT),
+            Rgb([245u8, 245u8, 245u8]),
+        );
+
+        if let Some(f) = font {
+            draw_text_mut(
+                img,
+                Rgb([33u8, 33u8, 33u8]),
+                (width as f64 * 0.04) as i32,
+                40,
+                PxScale::from(width as f32 * 0.042),
+                f,
+                title,
+            );
         }
+    }
+
+    /// Localized label for a target element type.
+    fn label(&self, cn: &str, en: &str) -> String {
+        if self.config.lang == "cn" { cn } else { en }.to_string()
+    }
+
+    /// Comment list: avatars, usernames, comment text, like and reply buttons.
+    fn draw_comment_list_scene(
+        &self,
+        img: &mut RgbImage,
+        font: Option<&FontRef>,
+        comments: &[MockComment],
+    ) -> Vec<ComplexTarget> {
+        let (width, height) = img.dimensions();
+
+        // UI dimensions based on screen size
+        let padding = (width as f64 * 0.04) as i32;
+        let avatar_size = (width as f64 * 0.10) as u32;
+        let font_size_username = (width as f64 * 0.035) as f32;
+        let font_size_time = (width as f64 * 0.028) as f32;
+        let font_size_content = (width as f64 * 0.038) as f32;
+        let font_size_button = (width as f64 * 0.030) as f32;
+        let line_height = (height as f64 * 0.022) as i32;
+        let comment_spacing = (height as f64 * 0.025) as i32;
+
+        let title = if self.config.lang == "cn" {
+            "评论区"
+        } else {
+            "Comments"
+        };
+        self.draw_scene_header(img, font, title);
 
         // Store all clickable targets
         let mut targets: Vec<ComplexTarget> = Vec::new();
@@ -726,11 +1496,17 @@ impl CoordinateCalibrator {
         // Draw comments
         let mut y_offset = 140;
 
+        let comment_height = avatar_size as i32 + comment_spacing * 2 + line_height * 3;
         for (idx, comment) in comments.iter().take(6).enumerate() {
+            // Only as many comments as fit on the screen
+            if y_offset + comment_height > height as i32 {
+                break;
+            }
+
             // Comment background (alternating)
             if idx % 2 == 0 {
                 draw_filled_rect_mut(
-                    &mut img,
+                    img,
                     Rect::at(0, y_offset).of_size(
                         width,
                         avatar_size + comment_spacing as u32 * 2 + line_height as u32 * 3,
@@ -755,7 +1531,7 @@ impl CoordinateCalibrator {
                 Rgb([255u8, 112u8, 67u8]),
             ];
             draw_filled_rect_mut(
-                &mut img,
+                img,
                 Rect::at(avatar_x, avatar_y).of_size(avatar_size, avatar_size),
                 avatar_colors[idx % avatar_colors.len()],
             );
@@ -774,10 +1550,10 @@ impl CoordinateCalibrator {
             let text_x = avatar_x + avatar_size as i32 + padding;
             let mut text_y = avatar_y;
 
-            if let Some(ref f) = font {
+            if let Some(f) = font {
                 // Username
                 draw_text_mut(
-                    &mut img,
+                    img,
                     Rgb([33u8, 33u8, 33u8]),
                     text_x,
                     text_y,
@@ -804,7 +1580,7 @@ impl CoordinateCalibrator {
                     - padding
                     - (comment.time.chars().count() as i32 * font_size_time as i32 / 2);
                 draw_text_mut(
-                    &mut img,
+                    img,
                     Rgb([150u8, 150u8, 150u8]),
                     time_x,
                     text_y,
@@ -817,7 +1593,7 @@ impl CoordinateCalibrator {
 
                 // Content
                 draw_text_mut(
-                    &mut img,
+                    img,
                     Rgb([66u8, 66u8, 66u8]),
                     text_x,
                     text_y,
@@ -849,7 +1625,7 @@ impl CoordinateCalibrator {
                 // Like button
                 let like_text = format!("👍 {}", comment.likes);
                 draw_text_mut(
-                    &mut img,
+                    img,
                     Rgb([100u8, 100u8, 100u8]),
                     text_x,
                     text_y,
@@ -879,7 +1655,7 @@ impl CoordinateCalibrator {
                     };
                     let reply_x = text_x + 150;
                     draw_text_mut(
-                        &mut img,
+                        img,
                         Rgb([100u8, 100u8, 100u8]),
                         reply_x,
                         text_y,
@@ -902,126 +1678,570 @@ impl CoordinateCalibrator {
             }
 
             // Draw separator line
-            y_offset += avatar_size as i32 + comment_spacing * 2 + line_height * 3;
+            y_offset += comment_height;
             draw_filled_rect_mut(
-                &mut img,
+                img,
                 Rect::at(padding, y_offset).of_size(width - padding as u32 * 2, 1),
                 Rgb([230u8, 230u8, 230u8]),
             );
             y_offset += 10;
         }
 
-        // Select a target based on round number
-        let target_idx = round % targets.len().max(1);
-        let target = if targets.is_empty() {
-            ComplexTarget {
-                description: "fallback".to_string(),
-                element_type: "Unknown".to_string(),
-                x: (width / 2) as i32,
-                y: (height / 2) as i32,
+        targets
+    }
+
+    /// Chat conversation: incoming and outgoing bubbles, input field and send button.
+    fn draw_chat_scene(&self, img: &mut RgbImage, font: Option<&FontRef>) -> Vec<ComplexTarget> {
+        let (width, height) = img.dimensions();
+        let cn = self.config.lang == "cn";
+        let (contact, messages): (&str, [(&str, bool); 6]) = if cn {
+            (
+                "王小明",
+                [
+                    ("在吗？明天的会议改到几点了？", false),
+                    ("改到下午三点了", true),
+                    ("好的，会议室还是原来那间吗？", false),
+                    ("对，还是302", true),
+                    ("收到，谢谢！", false),
+                    ("不客气", true),
+                ],
+            )
+        } else {
+            (
+                "Alex",
+                [
+                    ("Are we still on for tomorrow?", false),
+                    ("Yes, 3 PM works", true),
+                    ("Same meeting room as last time?", false),
+                    ("Yes, room 302", true),
+                    ("Got it, thanks!", false),
+                    ("See you then", true),
+                ],
+            )
+        };
+
+        let padding = (width as f64 * 0.04) as i32;
+        let avatar_size = (width as f64 * 0.09) as u32;
+        let font_size = (width as f64 * 0.038) as f32;
+        let bubble_height = (font_size * 2.2) as u32;
+        let spacing = (height as f64 * 0.03) as i32;
+        let char_width = if cn { font_size } else { font_size * 0.5 };
+
+        self.draw_scene_header(img, font, contact);
+        draw_filled_rect_mut(
+            img,
+            Rect::at(0, SCENE_HEADER_HEIGHT as i32).of_size(width, height - SCENE_HEADER_HEIGHT),
+            Rgb([237u8, 237u8, 237u8]),
+        );
+
+        let mut targets = Vec::new();
+        let mut y = SCENE_HEADER_HEIGHT as i32 + spacing;
+        let bar_height = (height as f64 * 0.07) as u32;
+        for (text, outgoing) in messages {
+            if y + avatar_size.max(bubble_height) as i32 > (height - bar_height) as i32 {
+                break;
+            }
+            let bubble_width = ((text.chars().count() as f32 * char_width) as u32
+                + padding as u32 * 2)
+                .min((width as f64 * 0.65) as u32);
+            let (avatar_x, bubble_x, bubble_color, avatar_color) = if outgoing {
+                let avatar_x = width as i32 - padding - avatar_size as i32;
+                (
+                    avatar_x,
+                    avatar_x - padding / 2 - bubble_width as i32,
+                    Rgb([149u8, 236u8, 105u8]),
+                    Rgb([66u8, 133u8, 244u8]),
+                )
+            } else {
+                (
+                    padding,
+                    padding + avatar_size as i32 + padding / 2,
+                    Rgb([255u8, 255u8, 255u8]),
+                    Rgb([244u8, 180u8, 0u8]),
+                )
+            };
+
+            draw_filled_rect_mut(
+                img,
+                Rect::at(avatar_x, y).of_size(avatar_size, avatar_size),
+                avatar_color,
+            );
+            draw_filled_rect_mut(
+                img,
+                Rect::at(bubble_x, y).of_size(bubble_width, bubble_height),
+                bubble_color,
+            );
+            if let Some(f) = font {
+                draw_text_mut(
+                    img,
+                    Rgb([33u8, 33u8, 33u8]),
+                    bubble_x + padding,
+                    y + (bubble_height as f32 - font_size) as i32 / 2,
+                    PxScale::from(font_size),
+                    f,
+                    text,
+                );
             }
+
+            if !outgoing {
+                targets.push(ComplexTarget {
+                    description: contact.to_string(),
+                    element_type: self.label("头像", "Avatar"),
+                    x: avatar_x + avatar_size as i32 / 2,
+                    y: y + avatar_size as i32 / 2,
+                });
+            }
+            targets.push(ComplexTarget {
+                description: text.to_string(),
+                element_type: self.label("消息气泡", "Message bubble"),
+                x: bubble_x + bubble_width as i32 / 2,
+                y: y + bubble_height as i32 / 2,
+            });
+
+            y += avatar_size.max(bubble_height) as i32 + spacing;
+        }
+
+        // Input bar
+        let bar_y = (height - bar_height) as i32;
+        draw_filled_rect_mut(
+            img,
+            Rect::at(0, bar_y).of_size(width, bar_height),
+            Rgb([247u8, 247u8, 247u8]),
+        );
+        let field_height = bar_height * 6 / 10;
+        let field_y = bar_y + (bar_height - field_height) as i32 / 2;
+        let field_width = (width as f64 * 0.7) as u32;
+        draw_filled_rect_mut(
+            img,
+            Rect::at(padding, field_y).of_size(field_width, field_height),
+            Rgb([255u8, 255u8, 255u8]),
+        );
+        draw_hollow_rect_mut(
+            img,
+            Rect::at(padding, field_y).of_size(field_width, field_height),
+            Rgb([210u8, 210u8, 210u8]),
+        );
+        targets.push(ComplexTarget {
+            description: self.label("消息输入", "Message input"),
+            element_type: self.label("输入框", "Input field"),
+            x: padding + field_width as i32 / 2,
+            y: field_y + field_height as i32 / 2,
+        });
+
+        let button_x = padding * 2 + field_width as i32;
+        let button_width = (width as i32 - padding - button_x).max(1) as u32;
+        draw_filled_rect_mut(
+            img,
+            Rect::at(button_x, field_y).of_size(button_width, field_height),
+            Rgb([7u8, 193u8, 96u8]),
+        );
+        let send = self.label("发送", "Send");
+        if let Some(f) = font {
+            draw_text_mut(
+                img,
+                Rgb([255u8, 255u8, 255u8]),
+                button_x + padding / 2,
+                field_y + (field_height as f32 - font_size) as i32 / 2,
+                PxScale::from(font_size),
+                f,
+                &send,
+            );
+        }
+        targets.push(ComplexTarget {
+            description: send,
+            element_type: self.label("按钮", "Button"),
+            x: button_x + button_width as i32 / 2,
+            y: field_y + field_height as i32 / 2,
+        });
+
+        targets
+    }
+
+    /// Settings list: rows with an icon and a label, ending in a switch or an arrow.
+    fn draw_settings_scene(
+        &self,
+        img: &mut RgbImage,
+        font: Option<&FontRef>,
+    ) -> Vec<ComplexTarget> {
+        let (width, height) = img.dimensions();
+        // (label, Some(switch state) or None for an arrow)
+        let rows: [(&str, Option<bool>); 8] = if self.config.lang == "cn" {
+            [
+                ("WLAN", None),
+                ("蓝牙", Some(true)),
+                ("飞行模式", Some(false)),
+                ("显示与亮度", None),
+                ("声音与振动", None),
+                ("通知", None),
+                ("深色模式", Some(true)),
+                ("关于手机", None),
+            ]
         } else {
-            targets[target_idx].clone()
+            [
+                ("Wi-Fi", None),
+                ("Bluetooth", Some(true)),
+                ("Airplane mode", Some(false)),
+                ("Display & brightness", None),
+                ("Sounds & vibration", None),
+                ("Notifications", None),
+                ("Dark mode", Some(true)),
+                ("About phone", None),
+            ]
         };
 
-        // Highlight the target with a red dot
+        let padding = (width as f64 * 0.04) as i32;
+        let row_height = (height as f64 * 0.065) as i32;
+        let icon_size = (row_height as f64 * 0.55) as u32;
+        let font_size = (width as f64 * 0.04) as f32;
+        let switch_width = (width as f64 * 0.12) as u32;
+        let switch_height = (row_height as f64 * 0.4) as u32;
+        let icon_colors = [
+            Rgb([66u8, 133u8, 244u8]),
+            Rgb([15u8, 157u8, 88u8]),
+            Rgb([255u8, 149u8, 0u8]),
+            Rgb([88u8, 86u8, 214u8]),
+        ];
+
+        let title = self.label("设置", "Settings");
+        self.draw_scene_header(img, font, &title);
+
+        let mut targets = Vec::new();
+        let mut y = SCENE_HEADER_HEIGHT as i32 + 20;
+        for (idx, (label, switch)) in rows.into_iter().enumerate() {
+            let center_y = y + row_height / 2;
+
+            draw_filled_rect_mut(
+                img,
+                Rect::at(padding, center_y - icon_size as i32 / 2).of_size(icon_size, icon_size),
+                icon_colors[idx % icon_colors.len()],
+            );
+            let label_x = padding * 2 + icon_size as i32;
+            if let Some(f) = font {
+                draw_text_mut(
+                    img,
+                    Rgb([33u8, 33u8, 33u8]),
+                    label_x,
+                    center_y - font_size as i32 / 2,
+                    PxScale::from(font_size),
+                    f,
+                    label,
+                );
+            }
+            targets.push(ComplexTarget {
+                description: label.to_string(),
+                element_type: self.label("设置项", "Setting"),
+                x: label_x + (width as i32 / 5),
+                y: center_y,
+            });
+
+            let right = width as i32 - padding;
+            match switch {
+                Some(on) => {
+                    let switch_x = right - switch_width as i32;
+                    let switch_y = center_y - switch_height as i32 / 2;
+                    draw_filled_rect_mut(
+                        img,
+                        Rect::at(switch_x, switch_y).of_size(switch_width, switch_height),
+                        if on {
+                            Rgb([52u8, 199u8, 89u8])
+                        } else {
+                            Rgb([200u8, 200u8, 200u8])
+                        },
+                    );
+                    let knob = switch_height - 6;
+                    let knob_x = if on {
+                        switch_x + switch_width as i32 - knob as i32 - 3
+                    } else {
+                        switch_x + 3
+                    };
+                    draw_filled_rect_mut(
+                        img,
+                        Rect::at(knob_x, switch_y + 3).of_size(knob, knob),
+                        Rgb([255u8, 255u8, 255u8]),
+                    );
+                    targets.push(ComplexTarget {
+                        description: label.to_string(),
+                        element_type: self.label("开关", "Switch"),
+                        x: switch_x + switch_width as i32 / 2,
+                        y: center_y,
+                    });
+                }
+                None => {
+                    let arrow_x = right - font_size as i32 / 2;
+                    if let Some(f) = font {
+                        draw_text_mut(
+                            img,
+                            Rgb([170u8, 170u8, 170u8]),
+                            arrow_x,
+                            center_y - font_size as i32 / 2,
+                            PxScale::from(font_size),
+                            f,
+                            ">",
+                        );
+                    }
+                    targets.push(ComplexTarget {
+                        description: label.to_string(),
+                        element_type: self.label("箭头", "Arrow"),
+                        x: arrow_x + font_size as i32 / 4,
+                        y: center_y,
+                    });
+                }
+            }
+
+            y += row_height;
+            draw_filled_rect_mut(
+                img,
+                Rect::at(label_x, y).of_size(width - label_x as u32, 1),
+                Rgb([230u8, 230u8, 230u8]),
+            );
+        }
+
+        targets
+    }
+
+    /// Photo grid: three columns of thumbnails with a select button and a tab bar.
+    fn draw_photo_grid_scene(
+        &self,
+        img: &mut RgbImage,
+        font: Option<&FontRef>,
+    ) -> Vec<ComplexTarget> {
+        let (width, height) = img.dimensions();
+        let cn = self.config.lang == "cn";
+        let padding = (width as f64 * 0.04) as i32;
+        let font_size = (width as f64 * 0.038) as f32;
+        let gap = 6;
+        let cell = (width as i32 - gap * 2) / 3;
+        let tab_height = (height as f64 * 0.07) as i32;
+        // Sky and ground colors of each thumbnail; no reds, which mark the target
+        let palette = [
+            (Rgb([135u8, 206u8, 235u8]), Rgb([34u8, 139u8, 34u8])),
+            (Rgb([255u8, 204u8, 128u8]), Rgb([120u8, 90u8, 60u8])),
+            (Rgb([70u8, 90u8, 160u8]), Rgb([30u8, 40u8, 70u8])),
+            (Rgb([200u8, 230u8, 255u8]), Rgb([240u8, 240u8, 240u8])),
+            (Rgb([180u8, 160u8, 220u8]), Rgb([90u8, 140u8, 90u8])),
+            (Rgb([250u8, 230u8, 140u8]), Rgb([60u8, 120u8, 180u8])),
+            (Rgb([160u8, 160u8, 160u8]), Rgb([90u8, 90u8, 90u8])),
+        ];
+
+        let title = self.label("相册", "Photos");
+        self.draw_scene_header(img, font, &title);
+
+        let mut targets = Vec::new();
+        let select = self.label("选择", "Select");
+        let select_x = width as i32 - padding - (font_size * if cn { 2.0 } else { 3.0 }) as i32;
+        if let Some(f) = font {
+            draw_text_mut(
+                img,
+                Rgb([0u8, 122u8, 255u8]),
+                select_x,
+                45,
+                PxScale::from(font_size),
+                f,
+                &select,
+            );
+        }
+        targets.push(ComplexTarget {
+            description: select,
+            element_type: self.label("按钮", "Button"),
+            x: select_x + (font_size * if cn { 1.0 } else { 1.5 }) as i32,
+            y: 45 + font_size as i32 / 2,
+        });
+
+        let grid_top = SCENE_HEADER_HEIGHT as i32 + gap;
+        let rows = ((height as i32 - tab_height - grid_top) / (cell + gap)).max(0);
+        for row in 0..rows {
+            for col in 0..3 {
+                let x = col * (cell + gap);
+                let y = grid_top + row * (cell + gap);
+                let (sky, ground) = palette[(row * 3 + col) as usize % palette.len()];
+                let horizon = cell * 3 / 5;
+                draw_filled_rect_mut(
+                    img,
+                    Rect::at(x, y).of_size(cell as u32, horizon as u32),
+                    sky,
+                );
+                draw_filled_rect_mut(
+                    img,
+                    Rect::at(x, y + horizon).of_size(cell as u32, (cell - horizon) as u32),
+                    ground,
+                );
+                targets.push(ComplexTarget {
+                    description: if cn {
+                        format!("第{}行第{}列", row + 1, col + 1)
+                    } else {
+                        format!("Row {}, column {}", row + 1, col + 1)
+                    },
+                    element_type: self.label("照片", "Photo"),
+                    x: x + cell / 2,
+                    y: y + cell / 2,
+                });
+            }
+        }
+
+        // Tab bar
+        let tab_y = height as i32 - tab_height;
         draw_filled_rect_mut(
-            &mut img,
-            Rect::at(target.x - 5, target.y - 5).of_size(10, 10),
-            Rgb([255u8, 0u8, 0u8]),
+            img,
+            Rect::at(0, tab_y).of_size(width, tab_height as u32),
+            Rgb([248u8, 248u8, 248u8]),
         );
+        let tabs = if cn {
+            ["照片", "相簿", "搜索"]
+        } else {
+            ["Photos", "Albums", "Search"]
+        };
+        let tab_width = width as i32 / tabs.len() as i32;
+        for (i, tab) in tabs.into_iter().enumerate() {
+            let center_x = tab_width * i as i32 + tab_width / 2;
+            if let Some(f) = font {
+                let text_width = tab.chars().count() as f32 * if cn { 1.0 } else { 0.5 };
+                draw_text_mut(
+                    img,
+                    if i == 0 {
+                        Rgb([0u8, 122u8, 255u8])
+                    } else {
+                        Rgb([120u8, 120u8, 120u8])
+                    },
+                    center_x - (text_width * font_size / 2.0) as i32,
+                    tab_y + (tab_height - font_size as i32) / 2,
+                    PxScale::from(font_size),
+                    f,
+                    tab,
+                );
+            }
+            targets.push(ComplexTarget {
+                description: tab.to_string(),
+                element_type: self.label("标签", "Tab"),
+                x: center_x,
+                y: tab_y + tab_height / 2,
+            });
+        }
 
-        let mut buffer = Cursor::new(Vec::new());
-        img.write_to(&mut buffer, image::ImageFormat::Png).unwrap();
-        (STANDARD.encode(buffer.into_inner()), target)
+        targets
     }
 
-    /// Ask the LLM to identify the marker position in a simple calibration image.
-    async fn ask_llm_for_simple_position(
+    /// Prompt asking the LLM to identify the marker position in a simple calibration image.
+    fn simple_prompt(
         &self,
-        model_client: &ModelClient,
-        image_base64: &str,
         point_num: usize,
         screen_width: u32,
         screen_height: u32,
-    ) -> Result<(i32, i32), String> {
-        let prompt = if self.config.lang == "cn" {
+        theme: &CalibrationTheme,
+    ) -> String {
+        let lang = self.config.lang.as_str();
+        let marker = theme.marker.name(lang);
+        let crosshair = theme.crosshair.name(lang);
+        let dot = theme.center_dot.name(lang);
+        if lang == "cn" {
             format!(
-                "这是一张坐标校准图片。图片中有一个红色方块，方块中间有一个黄色十字和黑色中心点。\n\
+                "这是一张坐标校准图片。图片中有一个{}方块，方块中间有一个{}十字和{}中心点。\n\
                 这是第 {} 个校准点（图片左上角有 {} 个绿色方块表示）。\n\
                 屏幕尺寸为 {}x{} 像素（宽x高）。\n\n\
-                请仔细观察红色方块中心（黑色点）的位置，输出其精确的像素坐标。\n\
+                请仔细观察{}方块中心（{}点）的位置，输出其精确的像素坐标。\n\
                 坐标原点在左上角，X向右增加，Y向下增加。\n\n\
                 只需要输出坐标，格式为: [x, y]\n\
                 例如: [540, 960]",
-                point_num, point_num, screen_width, screen_height
+                marker,
+                crosshair,
+                dot,
+                point_num,
+                point_num,
+                screen_width,
+                screen_height,
+                marker,
+                dot
             )
         } else {
             format!(
-                "This is a coordinate calibration image. There is a red square with a yellow crosshair and a black center dot.\n\
+                "This is a coordinate calibration image. There is a {} square with a {} crosshair and a {} center dot.\n\
                 This is calibration point {} (indicated by {} green squares at top-left).\n\
                 Screen size is {}x{} pixels (width x height).\n\n\
-                Please observe the center of the red marker (black dot) carefully and report its exact pixel coordinates.\n\
+                Please observe the center of the {} marker ({} dot) carefully and report its exact pixel coordinates.\n\
                 Origin is at top-left, X increases rightward, Y increases downward.\n\n\
                 Only output the coordinates in format: [x, y]\n\
                 Example: [540, 960]",
-                point_num, point_num, screen_width, screen_height
+                marker, crosshair, dot, point_num, point_num, screen_width, screen_height, marker, dot
             )
-        };
-
-        let messages = vec![MessageBuilder::create_user_message(
-            &prompt,
-            Some(image_base64),
-        )];
-        let response = model_client
-            .request(&messages)
-            .await
-            .map_err(|e| e.to_string())?;
-        self.parse_coordinates(&response.raw_content)
+        }
     }
 
-    /// Ask the LLM to find a specific element in a complex UI image.
-    async fn ask_llm_for_complex_position(
+    /// Prompt asking the LLM to find a specific element in a complex UI image.
+    fn complex_prompt(
         &self,
-        model_client: &ModelClient,
-        image_base64: &str,
+        scene: ComplexScene,
         target: &ComplexTarget,
         screen_width: u32,
         screen_height: u32,
-    ) -> Result<(i32, i32), String> {
-        let prompt = if self.config.lang == "cn" {
+    ) -> String {
+        let scene = scene.description(&self.config.lang);
+        if self.config.lang == "cn" {
             format!(
-                "这是一个评论区界面的截图。屏幕尺寸为 {}x{} 像素（宽x高）。\n\n\
+                "这是{}的截图。屏幕尺寸为 {}x{} 像素（宽x高）。\n\n\
                 界面中有一个红色小圆点标记了目标位置。\n\
                 目标是: {} - \"{}\"\n\n\
                 请找到这个红色标记点的精确像素坐标。\n\
                 坐标原点在左上角，X向右增加，Y向下增加。\n\n\
                 只需要输出坐标，格式为: [x, y]\n\
                 例如: [540, 960]",
-                screen_width, screen_height, target.element_type, target.description
+                scene, screen_width, screen_height, target.element_type, target.description
             )
         } else {
             format!(
-                "This is a screenshot of a comment section UI. Screen size is {}x{} pixels (width x height).\n\n\
+                "This is a screenshot of {}. Screen size is {}x{} pixels (width x height).\n\n\
                 There is a small red dot marking the target position.\n\
                 Target: {} - \"{}\"\n\n\
                 Please find the exact pixel coordinates of this red marker.\n\
                 Origin is at top-left, X increases rightward, Y increases downward.\n\n\
                 Only output the coordinates in format: [x, y]\n\
                 Example: [540, 960]",
-                screen_width, screen_height, target.element_type, target.description
+                scene, screen_width, screen_height, target.element_type, target.description
             )
-        };
+        }
+    }
 
-        let messages = vec![MessageBuilder::create_user_message(
-            &prompt,
-            Some(image_base64),
-        )];
-        let response = model_client
-            .request(&messages)
-            .await
-            .map_err(|e| e.to_string())?;
-        self.parse_coordinates(&response.raw_content)
+    /// Send each `(prompt, image)` request to the model, at most
+    /// `max_concurrency` at a time, and parse the answers in request order.
+    async fn query_all(
+        &self,
+        model_client: &ModelClient,
+        requests: Vec<(String, String)>,
+    ) -> Vec<Result<(i32, i32), String>> {
+        let limit = self.config.max_concurrency.max(1);
+        if requests.len() > 1 {
+            self.progress(format_args!(
+                "⏳ Sending {} requests, up to {} at a time...\n",
+                requests.len(),
+                limit
+            ));
+        }
+
+        let semaphore = Arc::new(Semaphore::new(limit));
+        let handles: Vec<_> = requests
+            .into_iter()
+            .map(|(prompt, image_base64)| {
+                let model_client = model_client.clone();
+                let semaphore = Arc::clone(&semaphore);
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+                    let messages = vec![MessageBuilder::create_user_message(
+                        &prompt,
+                        Some(&image_base64),
+                    )];
+                    model_client
+                        .request(&messages)
+                        .await
+                        .map(|response| response.raw_content)
+                        .map_err(|e| e.to_string())
+                })
+            })
+            .collect();
+
+        let mut answers = Vec::with_capacity(handles.len());
+        for handle in handles {
+            let response = handle.await.map_err(|e| e.to_string()).and_then(|r| r);
+            answers.push(response.and_then(|raw| self.parse_coordinates(&raw)));
+        }
+        answers
     }
 
     /// Parse coordinates from LLM response.
@@ -1102,9 +2322,174 @@ impl CoordinateCalibrator {
     }
 }
 
+/// Center of the theme's calibration marker in an image, if one is visible.
+fn locate_marker(img: &RgbImage, theme: &CalibrationTheme) -> Option<(i32, i32)> {
+    let (mut sum_x, mut sum_y, mut count) = (0u64, 0u64, 0u64);
+    for (x, y, pixel) in img.enumerate_pixels() {
+        if theme.is_marker_pixel(pixel.0) {
+            sum_x += x as u64;
+            sum_y += y as u64;
+            count += 1;
+        }
+    }
+
+    // Ignore stray marker-colored pixels from the viewer's own UI
+    (count >= 50).then(|| ((sum_x / count) as i32, (sum_y / count) as i32))
+}
+
+/// Points further than this many scaled MADs above the median residual are outliers.
+const OUTLIER_MADS: f64 = 3.0;
+
+/// Residual in pixels below which a point is never rejected, so tightly
+/// clustered answers aren't thrown out over a few pixels.
+const MIN_OUTLIER_RESIDUAL: f64 = 15.0;
+
+/// Median of a list of values (0.0 when empty).
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Theil–Sen fit of `expected = scale * reported + offset` for one axis: the
+/// median of pairwise slopes, which a minority of wild answers can't skew.
+/// Falls back to the median ratio with no offset when no two points differ.
+fn robust_fit_axis(samples: &[(f64, f64)]) -> (f64, f64) {
+    let mut slopes = Vec::new();
+    for (i, &(r1, e1)) in samples.iter().enumerate() {
+        for &(r2, e2) in &samples[i + 1..] {
+            if (r2 - r1).abs() > f64::EPSILON {
+                slopes.push((e2 - e1) / (r2 - r1));
+            }
+        }
+    }
+
+    if slopes.is_empty() {
+        let ratios: Vec<f64> = samples
+            .iter()
+            .filter(|(r, _)| *r != 0.0)
+            .map(|(r, e)| e / r)
+            .collect();
+        return (
+            if ratios.is_empty() {
+                1.0
+            } else {
+                median(&ratios)
+            },
+            0.0,
+        );
+    }
+
+    let scale = median(&slopes);
+    let intercepts: Vec<f64> = samples.iter().map(|(r, e)| e - scale * r).collect();
+    (scale, median(&intercepts))
+}
+
+/// Flag samples whose residual under a robust fit lies far above the median
+/// residual, measured in median absolute deviations (MAD).
+fn find_outliers(samples: &[MappingSample]) -> Vec<bool> {
+    if samples.len() < 3 {
+        return vec![false; samples.len()];
+    }
+
+    let xs: Vec<(f64, f64)> = samples.iter().map(|&((rx, _), (ex, _))| (rx, ex)).collect();
+    let ys: Vec<(f64, f64)> = samples.iter().map(|&((_, ry), (_, ey))| (ry, ey)).collect();
+    let (scale_x, offset_x) = robust_fit_axis(&xs);
+    let (scale_y, offset_y) = robust_fit_axis(&ys);
+
+    let residuals: Vec<f64> = samples
+        .iter()
+        .map(|&((rx, ry), (ex, ey))| {
+            ((rx * scale_x + offset_x - ex).powi(2) + (ry * scale_y + offset_y - ey).powi(2)).sqrt()
+        })
+        .collect();
+    let center = median(&residuals);
+    let deviations: Vec<f64> = residuals.iter().map(|r| (r - center).abs()).collect();
+    // 1.4826 scales the MAD to a standard deviation for normally distributed errors
+    let limit = (center + OUTLIER_MADS * 1.4826 * median(&deviations)).max(MIN_OUTLIER_RESIDUAL);
+
+    residuals.iter().map(|&r| r > limit).collect()
+}
+
+/// Collect verification residuals into a report checked against `threshold`.
+fn summarize_verification(
+    point_results: Vec<PointCalibrationResult>,
+    residuals: Vec<f64>,
+    threshold: f64,
+) -> CalibrationVerification {
+    let max_residual = residuals.iter().copied().fold(0.0, f64::max);
+    let mean_residual = if residuals.is_empty() {
+        0.0
+    } else {
+        residuals.iter().sum::<f64>() / residuals.len() as f64
+    };
+
+    CalibrationVerification {
+        point_results,
+        passed: !residuals.is_empty() && max_residual <= threshold,
+        residuals,
+        mean_residual,
+        max_residual,
+        threshold,
+    }
+}
+
+/// Fit `expected = scale * reported + offset` for one axis by least squares.
+///
+/// Samples are `(reported, expected)` pairs. Falls back to the mean of the
+/// `expected / reported` ratios with no offset when there are too few distinct
+/// points to solve for both terms, or when the fitted scale is implausible.
+fn fit_axis(samples: &[(f64, f64)]) -> (f64, f64) {
+    let ratios: Vec<f64> = samples
+        .iter()
+        .filter(|(reported, _)| *reported != 0.0)
+        .map(|(reported, expected)| expected / reported)
+        .collect();
+    let mean_ratio = if ratios.is_empty() {
+        1.0
+    } else {
+        ratios.iter().sum::<f64>() / ratios.len() as f64
+    };
+
+    let n = samples.len() as f64;
+    if samples.len() < 2 {
+        return (mean_ratio, 0.0);
+    }
+
+    let mean_reported = samples.iter().map(|(r, _)| r).sum::<f64>() / n;
+    let mean_expected = samples.iter().map(|(_, e)| e).sum::<f64>() / n;
+    let variance: f64 = samples
+        .iter()
+        .map(|(r, _)| (r - mean_reported).powi(2))
+        .sum();
+    if variance < f64::EPSILON {
+        return (mean_ratio, 0.0);
+    }
+
+    let covariance: f64 = samples
+        .iter()
+        .map(|(r, e)| (r - mean_reported) * (e - mean_expected))
+        .sum();
+    let scale = covariance / variance;
+    if !(0.5..2.0).contains(&scale) {
+        return (mean_ratio, 0.0);
+    }
+
+    (scale, mean_expected - scale * mean_reported)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::calibration::CALIBRATION_THEMES;
 
     #[test]
     fn test_parse_coordinates_bracket() {
@@ -1155,4 +2540,263 @@ mod tests {
         assert_eq!(config.complex_rounds, 10);
         assert_eq!(config.device_id, Some("device123".to_string()));
     }
+
+    #[test]
+    fn test_grid_calibration_points() {
+        let points = grid_calibration_points(3);
+        assert_eq!(points.len(), 9);
+        assert_eq!(points[0], (0.1, 0.1));
+        assert!((points[4].0 - 0.5).abs() < 1e-9 && (points[4].1 - 0.5).abs() < 1e-9);
+        assert!((points[8].0 - 0.9).abs() < 1e-9 && (points[8].1 - 0.9).abs() < 1e-9);
+
+        // Smaller grids can't determine a quadratic mapping
+        assert_eq!(grid_calibration_points(2).len(), 9);
+        assert_eq!(CalibrationConfig::default().with_grid_size(4).grid_size, 4);
+    }
+
+    #[test]
+    fn test_locate_marker() {
+        let calibrator = CoordinateCalibrator::new(CalibrationConfig::default());
+        for theme in &CALIBRATION_THEMES {
+            let image =
+                calibrator.generate_simple_calibration_image(270, 1800, 2, 1080, 2400, theme);
+            let img = image::load_from_memory(&STANDARD.decode(image).unwrap())
+                .unwrap()
+                .to_rgb8();
+
+            let (x, y) = locate_marker(&img, theme).unwrap();
+            assert!((x - 270).abs() <= 2, "{}: x = {}", theme.name, x);
+            assert!((y - 1800).abs() <= 2, "{}: y = {}", theme.name, y);
+
+            let blank = RgbImage::from_fn(100, 100, |_, _| Rgb(theme.background));
+            assert_eq!(locate_marker(&blank, theme), None);
+        }
+    }
+
+    #[test]
+    fn test_complex_scenes() {
+        let calibrator = CoordinateCalibrator::new(CalibrationConfig::default());
+        let font =
+            FontRef::try_from_slice(include_bytes!("../../resources/NotoSansSC-Regular.ttf")).ok();
+        let comments = MockComment::random_comments_cn();
+
+        for (width, height) in [(1080, 2400), (720, 1280)] {
+            let mut img = RgbImage::new(width, height);
+            for targets in [
+                calibrator.draw_comment_list_scene(&mut img, font.as_ref(), &comments),
+                calibrator.draw_chat_scene(&mut img, font.as_ref()),
+                calibrator.draw_settings_scene(&mut img, font.as_ref()),
+                calibrator.draw_photo_grid_scene(&mut img, font.as_ref()),
+            ] {
+                assert!(targets.len() >= 5);
+                for target in targets {
+                    assert!(
+                        (0..width as i32).contains(&target.x)
+                            && (0..height as i32).contains(&target.y),
+                        "{:?} off screen",
+                        target
+                    );
+                }
+            }
+        }
+
+        // Scenes rotate per round, or stick to the selected ones
+        let config = CalibrationConfig::default();
+        assert_eq!(config.complex_scene(1), ComplexScene::Chat);
+        assert_eq!(config.complex_scene(4), ComplexScene::CommentList);
+        let config = config.with_complex_scenes(vec![ComplexScene::PhotoGrid]);
+        assert_eq!(config.complex_scene(3), ComplexScene::PhotoGrid);
+
+        assert_eq!(
+            ComplexScene::parse_list("chat, Settings"),
+            Ok(vec![ComplexScene::Chat, ComplexScene::Settings])
+        );
+        assert_eq!(ComplexScene::parse_list(""), Ok(vec![]));
+        assert!(ComplexScene::parse_list("chat,maps").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_queries() {
+        use crate::model::ModelConfig;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        // Mock model that echoes the coordinates in the prompt after a delay,
+        // recording how many requests were in flight at once
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (server_active, server_peak) = (Arc::clone(&active), Arc::clone(&peak));
+        tokio::spawn(async move {
+            let re = regex::Regex::new(r"\[\d+, \d+\]").unwrap();
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (active, peak) = (Arc::clone(&server_active), Arc::clone(&server_peak));
+                let re = re.clone();
+                tokio::spawn(async move {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    let answer = loop {
+                        let n = socket.read(&mut buf).await.unwrap();
+                        request.extend_from_slice(&buf[..n]);
+                        if let Some(m) = re.find(&String::from_utf8_lossy(&request)) {
+                            break m.as_str().to_string();
+                        }
+                    };
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+                    let body = format!(
+                        r#"{{"choices":[{{"message":{{"content":"{}"}}}}]}}"#,
+                        answer
+                    );
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        let calibrator = CoordinateCalibrator::new(
+            CalibrationConfig::default()
+                .with_max_concurrency(3)
+                .with_progress_to_stderr(true),
+        );
+        let client = ModelClient::new(ModelConfig::default().with_base_url(base_url));
+        let requests = (1..=7)
+            .map(|i| (format!("[{}, {}]", i * 10, i), String::new()))
+            .collect();
+
+        let answers = calibrator.query_all(&client, requests).await;
+        let expected: Vec<_> = (1..=7).map(|i| Ok((i * 10, i))).collect();
+        assert_eq!(answers, expected);
+        assert!(peak.load(Ordering::SeqCst) > 1);
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn test_mode_names() {
+        for mode in [
+            CalibrationMode::Simple,
+            CalibrationMode::Complex,
+            CalibrationMode::Grid,
+        ] {
+            assert_eq!(CalibrationMode::from_name(mode.as_str()), Some(mode));
+        }
+        assert_eq!(
+            CalibrationMode::from_name("GRID"),
+            Some(CalibrationMode::Grid)
+        );
+        assert_eq!(CalibrationMode::from_name("fancy"), None);
+    }
+
+    #[test]
+    fn test_theme_rotation() {
+        let config = CalibrationConfig::default().with_theme_seed(3);
+        assert_ne!(config.theme(0), config.theme(1));
+        assert_ne!(config.dark_round(0), config.dark_round(1));
+
+        let fixed = config.with_theme_variation(false);
+        assert_eq!(fixed.theme(0), CalibrationTheme::default());
+        assert_eq!(fixed.theme(1), CalibrationTheme::default());
+        assert!(!fixed.dark_round(1));
+    }
+
+    #[test]
+    fn test_outlier_rejection() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+
+        // True transform: actual = 1.5 * reported + 20, one hallucinated answer
+        let mut samples: Vec<MappingSample> = [
+            (100.0, 200.0),
+            (400.0, 300.0),
+            (600.0, 900.0),
+            (300.0, 1200.0),
+        ]
+        .iter()
+        .map(|&(rx, ry)| ((rx, ry), (rx * 1.5 + 20.0, ry * 1.5 + 20.0)))
+        .collect();
+        samples.push(((50.0, 50.0), (900.0, 1500.0)));
+
+        let outliers = find_outliers(&samples);
+        assert_eq!(outliers, vec![false, false, false, false, true]);
+
+        let (scale, offset) = robust_fit_axis(
+            &samples
+                .iter()
+                .map(|&((rx, _), (ex, _))| (rx, ex))
+                .collect::<Vec<_>>(),
+        );
+        assert!((scale - 1.5).abs() < 1e-9);
+        assert!((offset - 20.0).abs() < 1e-9);
+
+        // Answers a few pixels apart are never rejected
+        let jittered: Vec<MappingSample> = (0..5)
+            .map(|i| {
+                let r = 100.0 + i as f64 * 200.0;
+                ((r, r), (r + (i % 2) as f64 * 3.0, r))
+            })
+            .collect();
+        assert!(find_outliers(&jittered).iter().all(|o| !o));
+    }
+
+    #[test]
+    fn test_verification() {
+        let result = CalibrationResult {
+            scale_x: 1.5,
+            scale_y: 1.5,
+            offset_x: 0.0,
+            offset_y: 72.0,
+            screen_width: 1080,
+            screen_height: 2400,
+            point_results: Vec::new(),
+            success: true,
+            error: None,
+            mode: CalibrationMode::Simple,
+            mapping: None,
+            verification: None,
+        };
+        assert_eq!(result.transform(100.0, 100.0), (150.0, 222.0));
+
+        let passed = summarize_verification(Vec::new(), vec![10.0, 30.0], 50.0);
+        assert!(passed.passed);
+        assert_eq!(passed.mean_residual, 20.0);
+        assert_eq!(passed.max_residual, 30.0);
+
+        // One far-off point fails the whole calibration
+        let failed = summarize_verification(Vec::new(), vec![10.0, 80.0], 50.0);
+        assert!(!failed.passed);
+
+        // Nothing verified is not a pass
+        assert!(!summarize_verification(Vec::new(), Vec::new(), 50.0).passed);
+
+        let config = CalibrationConfig::default().with_max_residual(0.0);
+        assert!(config.verification_points.is_empty());
+    }
+
+    #[test]
+    fn test_fit_axis_scale_and_offset() {
+        // Model reports coordinates shifted up by a 72px status bar
+        let samples = [(200.0, 272.0), (500.0, 572.0), (800.0, 872.0)];
+        let (scale, offset) = fit_axis(&samples);
+        assert!((scale - 1.0).abs() < 1e-9);
+        assert!((offset - 72.0).abs() < 1e-9);
+
+        let samples = [(100.0, 161.0), (400.0, 644.0), (600.0, 966.0)];
+        let (scale, offset) = fit_axis(&samples);
+        assert!((scale - 1.61).abs() < 1e-9);
+        assert!(offset.abs() < 1e-9);
+
+        // A single point can only determine a ratio
+        assert_eq!(fit_axis(&[(500.0, 1000.0)]), (2.0, 0.0));
+        assert_eq!(fit_axis(&[]), (1.0, 0.0));
+    }
 }
@@ -0,0 +1,126 @@
+//! Runtime detection of coordinate drift.
+//!
+//! A calibration that was accurate when it was made can go stale: the model
+//! changes, the phone switches resolution, or the first calibration was simply
+//! unlucky. [`DriftMonitor`] keeps a sliding window of recent tap outcomes
+//! and reports drift once too many of them missed.
+
+use std::collections::VecDeque;
+
+/// Default number of recent taps considered.
+pub const DEFAULT_DRIFT_WINDOW: usize = 10;
+
+/// Default share of missed taps in the window that counts as drift.
+pub const DEFAULT_DRIFT_THRESHOLD: f64 = 0.5;
+
+/// Minimum taps in the window before drift is reported.
+const MIN_DRIFT_SAMPLES: usize = 5;
+
+/// Screens at least this similar before and after a tap count as unchanged.
+pub const INEFFECTIVE_TAP_SIMILARITY: f64 = 0.98;
+
+/// Outcome of a single tap-like action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapOutcome {
+    /// The tap landed on the screen and the screen changed afterwards.
+    Effective,
+    /// The transformed coordinates were outside the screen.
+    OutOfBounds,
+    /// The tap was executed but the screen didn't change.
+    Ineffective,
+}
+
+impl TapOutcome {
+    fn is_miss(self) -> bool {
+        !matches!(self, TapOutcome::Effective)
+    }
+}
+
+/// Sliding window of recent tap outcomes.
+#[derive(Debug, Clone)]
+pub struct DriftMonitor {
+    outcomes: VecDeque<TapOutcome>,
+    window: usize,
+    threshold: f64,
+}
+
+impl Default for DriftMonitor {
+    fn default() -> Self {
+        Self::new(DEFAULT_DRIFT_WINDOW, DEFAULT_DRIFT_THRESHOLD)
+    }
+}
+
+impl DriftMonitor {
+    /// Create a monitor over the last `window` taps that reports drift when the
+    /// share of misses reaches `threshold` (0.0-1.0).
+    pub fn new(window: usize, threshold: f64) -> Self {
+        let window = window.max(MIN_DRIFT_SAMPLES);
+        Self {
+            outcomes: VecDeque::with_capacity(window),
+            window,
+            threshold: threshold.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Record the outcome of a tap.
+    pub fn record(&mut self, outcome: TapOutcome) {
+        if self.outcomes.len() == self.window {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(outcome);
+    }
+
+    /// Share of missed taps in the window (0.0 when empty).
+    pub fn miss_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        let misses = self.outcomes.iter().filter(|o| o.is_miss()).count();
+        misses as f64 / self.outcomes.len() as f64
+    }
+
+    /// Number of taps currently in the window.
+    pub fn samples(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /// Whether enough taps missed to suspect the calibration is off.
+    pub fn is_drifting(&self) -> bool {
+        self.outcomes.len() >= MIN_DRIFT_SAMPLES && self.miss_rate() >= self.threshold
+    }
+
+    /// Forget all outcomes, e.g. after recalibrating.
+    pub fn reset(&mut self) {
+        self.outcomes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_monitor() {
+        let mut monitor = DriftMonitor::new(6, 0.5);
+
+        // Too few samples to judge
+        for _ in 0..4 {
+            monitor.record(TapOutcome::OutOfBounds);
+        }
+        assert!(!monitor.is_drifting());
+
+        monitor.record(TapOutcome::Ineffective);
+        assert!(monitor.is_drifting());
+        assert_eq!(monitor.miss_rate(), 1.0);
+
+        // Old misses slide out of the window
+        for _ in 0..5 {
+            monitor.record(TapOutcome::Effective);
+        }
+        assert_eq!(monitor.samples(), 6);
+        assert!(!monitor.is_drifting());
+
+        monitor.reset();
+        assert_eq!(monitor.miss_rate(), 0.0);
+    }
+}
@@ -0,0 +1,163 @@
+//! Nonlinear coordinate mapping fitted by grid calibration.
+//!
+//! Some models drift further from the true position the closer a target is to
+//! the screen edge, which a single scale + offset cannot describe. A
+//! [`CoordinateMapping`] is a quadratic polynomial per axis over the reported
+//! coordinates, fitted by least squares over a grid of calibration points.
+
+use serde::{Deserialize, Serialize};
+
+/// Number of polynomial terms: `1, u, v, u², uv, v²`.
+const TERMS: usize = 6;
+
+/// A `((reported_x, reported_y), (expected_x, expected_y))` calibration sample.
+pub type MappingSample = ((f64, f64), (f64, f64));
+
+/// Quadratic mapping from model-reported to actual pixel coordinates.
+///
+/// Reported coordinates are normalized by `norm_x` / `norm_y` before the
+/// polynomial is evaluated to keep the fit well conditioned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoordinateMapping {
+    /// Coefficients for the actual X coordinate over `[1, u, v, u², uv, v²]`
+    pub x_coeffs: [f64; TERMS],
+    /// Coefficients for the actual Y coordinate over `[1, u, v, u², uv, v²]`
+    pub y_coeffs: [f64; TERMS],
+    /// Divisor applied to reported X before evaluation
+    pub norm_x: f64,
+    /// Divisor applied to reported Y before evaluation
+    pub norm_y: f64,
+}
+
+impl CoordinateMapping {
+    /// Fit a mapping from `(reported, expected)` point pairs.
+    ///
+    /// Returns `None` when there are fewer than six points or they don't span
+    /// enough distinct rows and columns to determine every term.
+    pub fn fit(samples: &[MappingSample]) -> Option<Self> {
+        if samples.len() < TERMS {
+            return None;
+        }
+
+        let norm_x = samples
+            .iter()
+            .map(|((x, _), _)| x.abs())
+            .fold(0.0, f64::max);
+        let norm_y = samples
+            .iter()
+            .map(|((_, y), _)| y.abs())
+            .fold(0.0, f64::max);
+        if norm_x == 0.0 || norm_y == 0.0 {
+            return None;
+        }
+
+        // Normal equations (AᵀA)c = Aᵀb, shared matrix for both axes
+        let mut ata = [[0.0; TERMS]; TERMS];
+        let mut atb_x = [0.0; TERMS];
+        let mut atb_y = [0.0; TERMS];
+        for &((rx, ry), (ex, ey)) in samples {
+            let row = terms(rx / norm_x, ry / norm_y);
+            for i in 0..TERMS {
+                for j in 0..TERMS {
+                    ata[i][j] += row[i] * row[j];
+                }
+                atb_x[i] += row[i] * ex;
+                atb_y[i] += row[i] * ey;
+            }
+        }
+
+        Some(Self {
+            x_coeffs: solve(ata, atb_x)?,
+            y_coeffs: solve(ata, atb_y)?,
+            norm_x,
+            norm_y,
+        })
+    }
+
+    /// Map a reported coordinate to actual pixels.
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let row = terms(x / self.norm_x, y / self.norm_y);
+        let dot = |coeffs: &[f64; TERMS]| coeffs.iter().zip(row).map(|(c, t)| c * t).sum();
+        (dot(&self.x_coeffs), dot(&self.y_coeffs))
+    }
+
+    /// Largest distance in pixels between a mapped point and its expected position.
+    pub fn max_residual(&self, samples: &[MappingSample]) -> f64 {
+        samples
+            .iter()
+            .map(|&((rx, ry), (ex, ey))| {
+                let (mx, my) = self.apply(rx, ry);
+                ((mx - ex).powi(2) + (my - ey).powi(2)).sqrt()
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+fn terms(u: f64, v: f64) -> [f64; TERMS] {
+    [1.0, u, v, u * u, u * v, v * v]
+}
+
+/// Solve a linear system by Gaussian elimination with partial pivoting.
+fn solve(mut a: [[f64; TERMS]; TERMS], mut b: [f64; TERMS]) -> Option<[f64; TERMS]> {
+    for col in 0..TERMS {
+        let pivot = (col..TERMS).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col];
+        for row in col + 1..TERMS {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot) in a[row].iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = [0.0; TERMS];
+    for row in (0..TERMS).rev() {
+        let sum: f64 = (row + 1..TERMS).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_quadratic_mapping() {
+        // Error grows toward the edges: actual = r + 0.0001 * (r - 500)²
+        let distort = |r: f64| r + 0.0001 * (r - 500.0).powi(2);
+        let mut samples = Vec::new();
+        for &rx in &[100.0, 500.0, 900.0] {
+            for &ry in &[200.0, 1000.0, 1800.0] {
+                samples.push(((rx, ry), (distort(rx), ry * 1.2 + 30.0)));
+            }
+        }
+
+        let mapping = CoordinateMapping::fit(&samples).unwrap();
+        assert!(mapping.max_residual(&samples) < 1e-6);
+
+        let (x, y) = mapping.apply(300.0, 600.0);
+        assert!((x - distort(300.0)).abs() < 1e-6);
+        assert!((y - 750.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fit_needs_a_grid() {
+        // Too few points
+        let samples = [((100.0, 100.0), (100.0, 100.0)); 5];
+        assert!(CoordinateMapping::fit(&samples).is_none());
+
+        // Enough points, but all on one row
+        let samples: Vec<_> = (1..=8)
+            .map(|i| ((i as f64 * 100.0, 500.0), (i as f64 * 100.0, 500.0)))
+            .collect();
+        assert!(CoordinateMapping::fit(&samples).is_none());
+    }
+}
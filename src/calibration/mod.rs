@@ -1,8 +1,26 @@
 //! Coordinate calibration module for automatic scale factor detection.
 
 mod calibrator;
+mod drift;
+mod mapping;
+mod orientation;
+mod profiles;
+mod theme;
 
 pub use calibrator::{
-    CalibrationConfig, CalibrationMode, CalibrationResult, CoordinateCalibrator,
-    DEFAULT_CALIBRATION_POINTS,
+    grid_calibration_points, CalibrationConfig, CalibrationError, CalibrationMode,
+    CalibrationResult, CalibrationVerification, ComplexScene, CoordinateCalibrator,
+    DEFAULT_CALIBRATION_POINTS, DEFAULT_MAX_CONCURRENCY, DEFAULT_MAX_RESIDUAL,
+    DEFAULT_VERIFICATION_POINTS,
 };
+pub use drift::{
+    DriftMonitor, TapOutcome, DEFAULT_DRIFT_THRESHOLD, DEFAULT_DRIFT_WINDOW,
+    INEFFECTIVE_TAP_SIMILARITY,
+};
+pub use mapping::{CoordinateMapping, MappingSample};
+pub use orientation::{Orientation, OrientedCalibrations};
+pub use profiles::{
+    device_key, load_device_calibrations, save_device_profile, CalibrationProfile,
+    CalibrationProfileError, CalibrationProfiles,
+};
+pub use theme::{CalibrationTheme, GridStyle, ThemeColor, CALIBRATION_THEMES};
@@ -0,0 +1,157 @@
+//! Per-orientation calibration.
+//!
+//! A model can be off by different amounts in portrait and landscape, since
+//! the screenshot it sees has a different aspect ratio. Each calibration
+//! therefore belongs to the orientation it was made in, and the agent switches
+//! to the matching one when the phone rotates.
+
+use serde::{Deserialize, Serialize};
+
+use super::calibrator::CalibrationResult;
+
+/// Screen orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    /// Orientation of a screen or screenshot of the given size.
+    pub fn from_size(width: u32, height: u32) -> Self {
+        if width > height {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        }
+    }
+
+    /// Name used on the command line and in profiles.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Orientation::Portrait => "portrait",
+            Orientation::Landscape => "landscape",
+        }
+    }
+
+    /// Parse a name as accepted by [`Orientation::as_str`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "portrait" => Some(Orientation::Portrait),
+            "landscape" => Some(Orientation::Landscape),
+            _ => None,
+        }
+    }
+
+    /// Parse which orientations to calibrate: `portrait`, `landscape` or `both`.
+    /// An empty string or `current` yields an empty list, meaning the phone's
+    /// current orientation.
+    pub fn parse_selection(name: &str) -> Option<Vec<Self>> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "current" => Some(Vec::new()),
+            "both" => Some(vec![Orientation::Portrait, Orientation::Landscape]),
+            other => Orientation::from_name(other).map(|o| vec![o]),
+        }
+    }
+
+    /// `(width, height)` turned to this orientation.
+    pub fn fit(self, width: u32, height: u32) -> (u32, u32) {
+        if Orientation::from_size(width, height) == self {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+}
+
+/// Calibrations of one device, at most one per orientation.
+#[derive(Debug, Clone, Default)]
+pub struct OrientedCalibrations {
+    portrait: Option<CalibrationResult>,
+    landscape: Option<CalibrationResult>,
+}
+
+impl OrientedCalibrations {
+    /// Store a calibration under the orientation it was made in, replacing
+    /// an older one. Failed calibrations are ignored.
+    pub fn insert(&mut self, result: CalibrationResult) {
+        if !result.success {
+            return;
+        }
+        match result.orientation() {
+            Orientation::Portrait => self.portrait = Some(result),
+            Orientation::Landscape => self.landscape = Some(result),
+        }
+    }
+
+    /// The calibration made in the given orientation.
+    pub fn get(&self, orientation: Orientation) -> Option<&CalibrationResult> {
+        match orientation {
+            Orientation::Portrait => self.portrait.as_ref(),
+            Orientation::Landscape => self.landscape.as_ref(),
+        }
+    }
+
+    /// Calibration to start with before the orientation is known: portrait
+    /// if there is one.
+    pub fn preferred(&self) -> Option<&CalibrationResult> {
+        self.portrait.as_ref().or(self.landscape.as_ref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.portrait.is_none() && self.landscape.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::CalibrationMode;
+
+    fn result(width: u32, height: u32, scale: f64) -> CalibrationResult {
+        CalibrationResult {
+            scale_x: scale,
+            scale_y: scale,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            screen_width: width,
+            screen_height: height,
+            point_results: Vec::new(),
+            success: true,
+            error: None,
+            mode: CalibrationMode::Simple,
+            mapping: None,
+            verification: None,
+        }
+    }
+
+    #[test]
+    fn test_oriented_calibrations() {
+        assert_eq!(Orientation::from_size(1080, 2400), Orientation::Portrait);
+        assert_eq!(Orientation::from_size(2400, 1080), Orientation::Landscape);
+        assert_eq!(Orientation::Landscape.fit(1080, 2400), (2400, 1080));
+        assert_eq!(Orientation::Portrait.fit(1080, 2400), (1080, 2400));
+        assert_eq!(Orientation::parse_selection(""), Some(vec![]));
+        assert_eq!(
+            Orientation::parse_selection("Both"),
+            Some(vec![Orientation::Portrait, Orientation::Landscape])
+        );
+        assert_eq!(Orientation::parse_selection("upside-down"), None);
+
+        let mut calibrations = OrientedCalibrations::default();
+        assert!(calibrations.is_empty());
+
+        calibrations.insert(result(2400, 1080, 1.2));
+        assert_eq!(calibrations.preferred().unwrap().scale_x, 1.2);
+        assert!(calibrations.get(Orientation::Portrait).is_none());
+
+        calibrations.insert(result(1080, 2400, 1.05));
+        assert_eq!(calibrations.preferred().unwrap().scale_x, 1.05);
+        assert_eq!(
+            calibrations.get(Orientation::Landscape).unwrap().scale_x,
+            1.2
+        );
+    }
+}
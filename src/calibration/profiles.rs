@@ -0,0 +1,289 @@
+//! Per-device calibration profiles.
+//!
+//! A successful calibration is stored keyed by the device's hardware serial
+//! number, screen resolution and orientation, so the next run on the same
+//! phone can pick up its scale factors instead of calibrating again.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::calibrator::CalibrationResult;
+use super::orientation::{Orientation, OrientedCalibrations};
+use crate::adb::{get_screen_size, get_serial_number};
+
+/// A stored calibration of one device at one resolution and orientation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationProfile {
+    /// Hardware serial number (or ADB device ID if it has none).
+    pub serial: String,
+    /// Screen width in pixels.
+    pub screen_width: u32,
+    /// Screen height in pixels.
+    pub screen_height: u32,
+    /// Orientation the calibration was made in.
+    #[serde(default)]
+    pub orientation: Orientation,
+    /// When the calibration ran (RFC 3339).
+    pub calibrated_at: String,
+    /// The calibration itself.
+    pub result: CalibrationResult,
+}
+
+impl CalibrationProfile {
+    /// Whether this profile belongs to the given device and resolution.
+    pub fn matches(&self, serial: &str, screen_width: u32, screen_height: u32) -> bool {
+        self.serial == serial
+            && self.screen_width == screen_width
+            && self.screen_height == screen_height
+    }
+
+    /// Whether this profile belongs to the given device, resolution and orientation.
+    fn matches_oriented(
+        &self,
+        serial: &str,
+        screen_width: u32,
+        screen_height: u32,
+        orientation: Orientation,
+    ) -> bool {
+        self.matches(serial, screen_width, screen_height) && self.orientation == orientation
+    }
+}
+
+/// All stored calibration profiles.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CalibrationProfiles {
+    pub profiles: Vec<CalibrationProfile>,
+}
+
+impl CalibrationProfiles {
+    /// Load profiles from a JSON file. A missing file yields no profiles.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CalibrationProfileError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| CalibrationProfileError::IoError(e.to_string()))?;
+        serde_json::from_str(&content)
+            .map_err(|e| CalibrationProfileError::ParseError(e.to_string()))
+    }
+
+    /// Save profiles to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CalibrationProfileError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| CalibrationProfileError::IoError(e.to_string()))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| CalibrationProfileError::SerializeError(e.to_string()))?;
+        fs::write(path, content).map_err(|e| CalibrationProfileError::IoError(e.to_string()))
+    }
+
+    /// Find the profile of a device at a resolution and orientation.
+    pub fn get(
+        &self,
+        serial: &str,
+        screen_width: u32,
+        screen_height: u32,
+        orientation: Orientation,
+    ) -> Option<&CalibrationProfile> {
+        self.profiles
+            .iter()
+            .find(|p| p.matches_oriented(serial, screen_width, screen_height, orientation))
+    }
+
+    /// All calibrations of a device at a resolution, one per orientation.
+    pub fn calibrations(
+        &self,
+        serial: &str,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> OrientedCalibrations {
+        let mut calibrations = OrientedCalibrations::default();
+        for profile in self
+            .profiles
+            .iter()
+            .filter(|p| p.matches(serial, screen_width, screen_height))
+        {
+            calibrations.insert(profile.result.clone());
+        }
+        calibrations
+    }
+
+    /// Store a successful calibration of a device at a resolution, replacing
+    /// an older one of the same orientation. Failed calibrations are not stored.
+    pub fn insert(
+        &mut self,
+        serial: impl Into<String>,
+        screen_width: u32,
+        screen_height: u32,
+        result: CalibrationResult,
+    ) -> bool {
+        if !result.success {
+            return false;
+        }
+        let serial = serial.into();
+        let orientation = result.orientation();
+        self.profiles
+            .retain(|p| !p.matches_oriented(&serial, screen_width, screen_height, orientation));
+        self.profiles.push(CalibrationProfile {
+            serial,
+            screen_width,
+            screen_height,
+            orientation,
+            calibrated_at: chrono::Utc::now().to_rfc3339(),
+            result,
+        });
+        true
+    }
+}
+
+/// Identify a connected device: its serial number and current resolution.
+pub fn device_key(device_id: Option<&str>) -> Option<(String, u32, u32)> {
+    let serial = get_serial_number(device_id).or_else(|| device_id.map(str::to_string))?;
+    let (width, height) = get_screen_size(device_id)?;
+    Some((serial, width, height))
+}
+
+/// Load the stored calibrations of the connected device, one per orientation.
+pub fn load_device_calibrations(
+    path: impl AsRef<Path>,
+    device_id: Option<&str>,
+) -> OrientedCalibrations {
+    let profiles = match CalibrationProfiles::load(path) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            tracing::warn!("Failed to load calibration profiles: {}", e);
+            return OrientedCalibrations::default();
+        }
+    };
+    match device_key(device_id) {
+        Some((serial, width, height)) => profiles.calibrations(&serial, width, height),
+        None => OrientedCalibrations::default(),
+    }
+}
+
+/// Store a calibration of the connected device. Returns whether it was
+/// saved.
+pub fn save_device_profile(
+    path: impl AsRef<Path>,
+    device_id: Option<&str>,
+    result: &CalibrationResult,
+) -> Result<bool, CalibrationProfileError> {
+    let path = path.as_ref();
+    let (serial, width, height) =
+        device_key(device_id).ok_or(CalibrationProfileError::DeviceUnknown)?;
+    let mut profiles = CalibrationProfiles::load(path)?;
+    if !profiles.insert(serial, width, height, result.clone()) {
+        return Ok(false);
+    }
+    profiles.save(path)?;
+    Ok(true)
+}
+
+/// Calibration profile errors.
+#[derive(Debug, Clone)]
+pub enum CalibrationProfileError {
+    DeviceUnknown,
+    IoError(String),
+    ParseError(String),
+    SerializeError(String),
+}
+
+impl std::fmt::Display for CalibrationProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeviceUnknown => write!(f, "Could not identify the device"),
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::ParseError(e) => write!(f, "Parse error: {}", e),
+            Self::SerializeError(e) => write!(f, "Serialize error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CalibrationProfileError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::CalibrationMode;
+
+    fn result(scale: f64, success: bool) -> CalibrationResult {
+        CalibrationResult {
+            scale_x: scale,
+            scale_y: scale,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            screen_width: 1080,
+            screen_height: 2400,
+            point_results: Vec::new(),
+            success,
+            error: None,
+            mode: CalibrationMode::Simple,
+            mapping: None,
+            verification: None,
+        }
+    }
+
+    #[test]
+    fn test_profiles_keyed_by_serial_and_resolution() {
+        let path = std::env::temp_dir().join("test_calibration_profiles.json");
+        let _ = fs::remove_file(&path);
+
+        let mut profiles = CalibrationProfiles::load(&path).unwrap();
+        assert!(profiles.insert("R5CT1", 1080, 2400, result(1.05, true)));
+        assert!(profiles.insert("R5CT1", 1440, 3200, result(1.10, true)));
+        assert!(!profiles.insert("R5CT1", 1080, 2400, result(0.9, false)));
+        // Recalibrating replaces the old profile
+        assert!(profiles.insert("R5CT1", 1080, 2400, result(1.02, true)));
+        profiles.save(&path).unwrap();
+
+        let profiles = CalibrationProfiles::load(&path).unwrap();
+        assert_eq!(profiles.profiles.len(), 2);
+        assert_eq!(
+            profiles
+                .get("R5CT1", 1080, 2400, Orientation::Portrait)
+                .unwrap()
+                .result
+                .scale_x,
+            1.02
+        );
+        assert_eq!(
+            profiles
+                .get("R5CT1", 1440, 3200, Orientation::Portrait)
+                .unwrap()
+                .result
+                .scale_x,
+            1.10
+        );
+        assert!(profiles
+            .get("other", 1080, 2400, Orientation::Portrait)
+            .is_none());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_profiles_keep_one_per_orientation() {
+        let mut landscape = result(1.2, true);
+        (landscape.screen_width, landscape.screen_height) = (2400, 1080);
+
+        let mut profiles = CalibrationProfiles::default();
+        assert!(profiles.insert("R5CT1", 1080, 2400, result(1.05, true)));
+        assert!(profiles.insert("R5CT1", 1080, 2400, landscape));
+        assert_eq!(profiles.profiles.len(), 2);
+
+        let calibrations = profiles.calibrations("R5CT1", 1080, 2400);
+        assert_eq!(
+            calibrations.get(Orientation::Portrait).unwrap().scale_x,
+            1.05
+        );
+        assert_eq!(
+            calibrations.get(Orientation::Landscape).unwrap().scale_x,
+            1.2
+        );
+        assert!(profiles.calibrations("R5CT1", 1440, 3200).is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+//! Visual themes for calibration images.
+//!
+//! A model can be better at reading one particular look than another, e.g. a
+//! red marker on a dark background. Calibrating against a single look bakes
+//! that bias into the measured scale, so the calibrator rotates through
+//! several light and dark [`CalibrationTheme`]s from point to point.
+
+/// How the background grid of a marker image is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridStyle {
+    /// Solid lines at every quarter of the screen
+    Lines,
+    /// Dots where the quarter lines would cross
+    Dots,
+    /// No grid
+    None,
+}
+
+/// A color together with its name in the calibration prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeColor {
+    pub rgb: [u8; 3],
+    /// Name used in Chinese prompts
+    pub name_cn: &'static str,
+    /// Name used in English prompts
+    pub name_en: &'static str,
+}
+
+impl ThemeColor {
+    const fn new(rgb: [u8; 3], name_cn: &'static str, name_en: &'static str) -> Self {
+        Self {
+            rgb,
+            name_cn,
+            name_en,
+        }
+    }
+
+    /// Name of the color in the prompt language ("cn" or "en").
+    pub fn name(&self, lang: &str) -> &'static str {
+        if lang == "cn" {
+            self.name_cn
+        } else {
+            self.name_en
+        }
+    }
+}
+
+/// Colors and grid style of a marker calibration image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalibrationTheme {
+    /// Short identifier shown in per-point results
+    pub name: &'static str,
+    /// Whether the background is dark
+    pub dark: bool,
+    pub background: [u8; 3],
+    pub grid: [u8; 3],
+    pub grid_style: GridStyle,
+    /// Filled square around the target
+    pub marker: ThemeColor,
+    /// Crosshair drawn through the marker
+    pub crosshair: ThemeColor,
+    /// Dot at the exact target position
+    pub center_dot: ThemeColor,
+}
+
+const RED: ThemeColor = ThemeColor::new([255, 50, 50], "红色", "red");
+const BLUE: ThemeColor = ThemeColor::new([30, 90, 230], "蓝色", "blue");
+const MAGENTA: ThemeColor = ThemeColor::new([230, 40, 200], "紫红色", "magenta");
+const ORANGE: ThemeColor = ThemeColor::new([255, 140, 0], "橙色", "orange");
+const CYAN: ThemeColor = ThemeColor::new([0, 200, 220], "青色", "cyan");
+const YELLOW: ThemeColor = ThemeColor::new([255, 255, 0], "黄色", "yellow");
+const WHITE: ThemeColor = ThemeColor::new([255, 255, 255], "白色", "white");
+const BLACK: ThemeColor = ThemeColor::new([0, 0, 0], "黑色", "black");
+const PURE_RED: ThemeColor = ThemeColor::new([255, 0, 0], "红色", "red");
+const DEEP_BLUE: ThemeColor = ThemeColor::new([0, 80, 255], "蓝色", "blue");
+
+/// Built-in themes. The first one is the classic look used when themes don't vary.
+///
+/// Marker colors are kept apart from each other and from the green point
+/// indicator so the marker can be located by color in a screenshot.
+pub const CALIBRATION_THEMES: [CalibrationTheme; 5] = [
+    CalibrationTheme {
+        name: "classic",
+        dark: true,
+        background: [30, 30, 30],
+        grid: [60, 60, 60],
+        grid_style: GridStyle::Lines,
+        marker: RED,
+        crosshair: YELLOW,
+        center_dot: BLACK,
+    },
+    CalibrationTheme {
+        name: "light",
+        dark: false,
+        background: [245, 245, 245],
+        grid: [200, 200, 200],
+        grid_style: GridStyle::Lines,
+        marker: BLUE,
+        crosshair: YELLOW,
+        center_dot: BLACK,
+    },
+    CalibrationTheme {
+        name: "night",
+        dark: true,
+        background: [15, 20, 35],
+        grid: [70, 80, 100],
+        grid_style: GridStyle::Dots,
+        marker: MAGENTA,
+        crosshair: WHITE,
+        center_dot: BLACK,
+    },
+    CalibrationTheme {
+        name: "paper",
+        dark: false,
+        background: [235, 225, 200],
+        grid: [200, 190, 165],
+        grid_style: GridStyle::None,
+        marker: ORANGE,
+        crosshair: DEEP_BLUE,
+        center_dot: BLACK,
+    },
+    CalibrationTheme {
+        name: "black",
+        dark: true,
+        background: [0, 0, 0],
+        grid: [40, 40, 40],
+        grid_style: GridStyle::Lines,
+        marker: CYAN,
+        crosshair: PURE_RED,
+        center_dot: WHITE,
+    },
+];
+
+impl Default for CalibrationTheme {
+    fn default() -> Self {
+        CALIBRATION_THEMES[0]
+    }
+}
+
+impl CalibrationTheme {
+    /// Theme for the `index`-th image of a run; consecutive indices get
+    /// different themes, `seed` picks where the rotation starts.
+    pub fn rotating(seed: u64, index: usize) -> Self {
+        let start = (seed % CALIBRATION_THEMES.len() as u64) as usize;
+        CALIBRATION_THEMES[(start + index) % CALIBRATION_THEMES.len()]
+    }
+
+    /// Whether a screenshot pixel is close enough to the marker color.
+    pub fn is_marker_pixel(&self, rgb: [u8; 3]) -> bool {
+        rgb.iter()
+            .zip(self.marker.rgb)
+            .all(|(&a, b)| a.abs_diff(b) <= MARKER_TOLERANCE)
+    }
+}
+
+/// Per-channel difference tolerated when matching the marker color, to allow
+/// for the viewer's color management and compression.
+const MARKER_TOLERANCE: u8 = 60;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_themes_are_distinguishable() {
+        // Every rotation covers all themes before repeating
+        let names: Vec<_> = (0..CALIBRATION_THEMES.len())
+            .map(|i| CalibrationTheme::rotating(7, i).name)
+            .collect();
+        for theme in &CALIBRATION_THEMES {
+            assert!(names.contains(&theme.name));
+        }
+        assert!(CALIBRATION_THEMES.iter().any(|t| t.dark));
+        assert!(CALIBRATION_THEMES.iter().any(|t| !t.dark));
+
+        // A marker never matches another theme's marker, its own background,
+        // crosshair or the green point indicator
+        for theme in &CALIBRATION_THEMES {
+            assert!(theme.is_marker_pixel(theme.marker.rgb));
+            assert!(!theme.is_marker_pixel(theme.background));
+            assert!(!theme.is_marker_pixel(theme.crosshair.rgb));
+            assert!(!theme.is_marker_pixel([0, 255, 0]));
+            for other in CALIBRATION_THEMES.iter().filter(|t| t.name != theme.name) {
+                assert!(!theme.is_marker_pixel(other.marker.rgb), "{}", theme.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Per-app prompt hints.
+//!
+//! Short notes about an app's layout ("在微信里，发送按钮在输入框右侧") keyed
+//! by package name. The hint of the app in the foreground is appended to the
+//! executor's system prompt.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Hints keyed by package name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppHints {
+    pub hints: BTreeMap<String, String>,
+}
+
+impl AppHints {
+    /// Load hints from a JSON file. A missing file yields no hints.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    /// Save hints to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    /// Hint for a package, if it has one.
+    pub fn get(&self, package: &str) -> Option<&str> {
+        self.hints
+            .get(package)
+            .map(String::as_str)
+            .filter(|hint| !hint.trim().is_empty())
+    }
+
+    /// Set the hint of a package; a blank hint removes it.
+    pub fn set(&mut self, package: &str, hint: &str) {
+        let package = package.trim();
+        if package.is_empty() {
+            return;
+        }
+        if hint.trim().is_empty() {
+            self.hints.remove(package);
+        } else {
+            self.hints.insert(package.to_string(), hint.to_string());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hints.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_hints() {
+        let mut hints = AppHints::default();
+        hints.set(" com.tencent.mm ", "在微信里，发送按钮在输入框右侧");
+        assert_eq!(
+            hints.get("com.tencent.mm"),
+            Some("在微信里，发送按钮在输入框右侧")
+        );
+        assert_eq!(hints.get("com.android.chrome"), None);
+
+        hints.set("com.tencent.mm", " ");
+        assert!(hints.is_empty());
+    }
+}
@@ -1,8 +1,60 @@
 //! App name to package name mapping for supported applications.
+//!
+//! The default catalog covers Chinese apps plus a few common ones. Outside
+//! China, select [`AppRegion::International`] to add the catalog of apps used
+//! there (WhatsApp, Instagram, Telegram, ...).
 
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Which app catalogs `Launch` looks names up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppRegion {
+    /// [`APP_PACKAGES`] only.
+    #[default]
+    China,
+    /// [`INTERNATIONAL_APP_PACKAGES`] first, then [`APP_PACKAGES`].
+    International,
+}
+
+impl AppRegion {
+    /// Name used in settings and on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AppRegion::China => "china",
+            AppRegion::International => "international",
+        }
+    }
+
+    /// Parse a name as accepted by [`AppRegion::as_str`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "china" | "cn" => Some(AppRegion::China),
+            "international" | "intl" | "global" => Some(AppRegion::International),
+            _ => None,
+        }
+    }
+
+    /// Package of an app in this region's catalogs. International names
+    /// also match ignoring case ("whatsapp", "YOUTUBE").
+    pub fn find_package(&self, app_name: &str) -> Option<&'static str> {
+        if *self == AppRegion::International {
+            let international = INTERNATIONAL_APP_PACKAGES.get(app_name).or_else(|| {
+                INTERNATIONAL_APP_PACKAGES
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(app_name))
+                    .map(|(_, package)| package)
+            });
+            if let Some(package) = international {
+                return Some(package);
+            }
+        }
+        APP_PACKAGES.get(app_name).copied()
+    }
+}
+
 /// Mapping from app display names to Android package names.
 pub static APP_PACKAGES: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
     let mut m = HashMap::new();
@@ -145,6 +197,75 @@ pub static APP_PACKAGES: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|
     m
 });
 
+/// Apps common outside China, by display name.
+pub static INTERNATIONAL_APP_PACKAGES: Lazy<HashMap<&'static str, &'static str>> =
+    Lazy::new(|| {
+        let mut m = HashMap::new();
+
+        // Messaging
+        m.insert("WhatsApp", "com.whatsapp");
+        m.insert("WhatsApp Business", "com.whatsapp.w4b");
+        m.insert("Telegram", "org.telegram.messenger");
+        m.insert("Signal", "org.thoughtcrime.securesms");
+        m.insert("Messenger", "com.facebook.orca");
+        m.insert("Discord", "com.discord");
+        m.insert("Slack", "com.Slack");
+        m.insert("Line", "jp.naver.line.android");
+        m.insert("Viber", "com.viber.voip");
+        m.insert("Snapchat", "com.snapchat.android");
+
+        // Social
+        m.insert("Instagram", "com.instagram.android");
+        m.insert("Facebook", "com.facebook.katana");
+        m.insert("X", "com.twitter.android");
+        m.insert("Twitter", "com.twitter.android");
+        m.insert("TikTok", "com.zhiliaoapp.musically");
+        m.insert("Reddit", "com.reddit.frontpage");
+        m.insert("LinkedIn", "com.linkedin.android");
+        m.insert("Pinterest", "com.pinterest");
+        m.insert("Threads", "com.instagram.barcelona");
+
+        // Google
+        m.insert("Gmail", "com.google.android.gm");
+        m.insert("YouTube", "com.google.android.youtube");
+        m.insert("YouTube Music", "com.google.android.apps.youtube.music");
+        m.insert("Maps", "com.google.android.apps.maps");
+        m.insert("Google Maps", "com.google.android.apps.maps");
+        m.insert("Google", "com.google.android.googlequicksearchbox");
+        m.insert("Google Drive", "com.google.android.apps.docs");
+        m.insert("Google Photos", "com.google.android.apps.photos");
+        m.insert("Google Calendar", "com.google.android.calendar");
+        m.insert("Google Play", "com.android.vending");
+        m.insert("Play Store", "com.android.vending");
+        m.insert("Google Translate", "com.google.android.apps.translate");
+        m.insert("Google Meet", "com.google.android.apps.tachyon");
+
+        // Productivity
+        m.insert("Outlook", "com.microsoft.office.outlook");
+        m.insert("Microsoft Teams", "com.microsoft.teams");
+        m.insert("Teams", "com.microsoft.teams");
+        m.insert("Zoom", "us.zoom.videomeetings");
+        m.insert("Notion", "notion.id");
+
+        // Shopping, travel & delivery
+        m.insert("Amazon", "com.amazon.mShop.android.shopping");
+        m.insert("eBay", "com.ebay.mobile");
+        m.insert("Uber", "com.ubercab");
+        m.insert("Uber Eats", "com.ubercab.eats");
+        m.insert("Lyft", "me.lyft.android");
+        m.insert("Airbnb", "com.airbnb.android");
+        m.insert("DoorDash", "com.dd.doordash");
+        m.insert("PayPal", "com.paypal.android.p2pmobile");
+
+        // Entertainment
+        m.insert("Spotify", "com.spotify.music");
+        m.insert("Netflix", "com.netflix.mediaclient");
+        m.insert("Prime Video", "com.amazon.avod.thirdpartyclient");
+        m.insert("Disney+", "com.disney.disneyplus");
+
+        m
+    });
+
 /// Get the package name for an app by its display name.
 #[allow(dead_code)]
 pub fn get_package(app_name: &str) -> Option<&'static str> {
@@ -161,4 +282,22 @@ mod tests {
         assert_eq!(get_package("Chrome"), Some("com.android.chrome"));
         assert_eq!(get_package("NonExistent"), None);
     }
+
+    #[test]
+    fn test_app_region() {
+        assert_eq!(AppRegion::China.find_package("WhatsApp"), None);
+        assert_eq!(
+            AppRegion::International.find_package("whatsapp"),
+            Some("com.whatsapp")
+        );
+        // The default catalog is still searched
+        assert_eq!(
+            AppRegion::International.find_package("微信"),
+            Some("com.tencent.mm")
+        );
+        assert_eq!(
+            AppRegion::from_name(AppRegion::International.as_str()),
+            Some(AppRegion::International)
+        );
+    }
 }
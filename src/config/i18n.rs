@@ -1,4 +1,14 @@
 //! Internationalization (i18n) module for Phone Agent UI messages.
+//!
+//! Chinese and English are built in. Further languages are registered at
+//! runtime from JSON or Fluent files; keys they don't translate fall back to
+//! the built-in text.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
 
 /// UI messages structure
 #[derive(Debug, Clone)]
@@ -20,6 +30,234 @@ pub struct Messages {
     pub step: &'static str,
     pub task: &'static str,
     pub result: &'static str,
+
+    // GUI
+    pub app_title: &'static str,
+    pub app_started: &'static str,
+    pub tab_main: &'static str,
+    pub tab_timeline: &'static str,
+    pub tab_history: &'static str,
+    pub tab_preview: &'static str,
+    pub tab_settings: &'static str,
+    pub tab_logs: &'static str,
+    pub state_idle: &'static str,
+    pub state_running: &'static str,
+    pub state_calibrating: &'static str,
+    pub state_replaying: &'static str,
+    pub ready: &'static str,
+    pub running: &'static str,
+    pub stopping: &'static str,
+    pub stopping_task: &'static str,
+    pub cancelled: &'static str,
+    pub task_cancelled: &'static str,
+    pub task_failed: &'static str,
+    pub failed: &'static str,
+    pub enter_task: &'static str,
+    pub wait_for_task: &'static str,
+    pub task_placeholder: &'static str,
+    pub run: &'static str,
+    pub stop: &'static str,
+    pub calibrate: &'static str,
+    pub execution_log: &'static str,
+    pub steps_unit: &'static str,
+    pub none: &'static str,
+    pub no_screenshot: &'static str,
+    pub timeline_empty: &'static str,
+    pub history_title: &'static str,
+    pub history_empty: &'static str,
+    pub history_save_failed: &'static str,
+    pub report_write_failed: &'static str,
+    pub run_again: &'static str,
+    pub started: &'static str,
+    pub duration: &'static str,
+    pub message: &'static str,
+    pub clear: &'static str,
+    pub devices_found: &'static str,
+    pub device_list_failed: &'static str,
+    pub auto_detect: &'static str,
+    pub not_connected: &'static str,
+    pub preview_title: &'static str,
+    pub no_preview: &'static str,
+    pub refresh: &'static str,
+    pub back: &'static str,
+    pub home: &'static str,
+    pub gesture_hint: &'static str,
+    pub preview_action: &'static str,
+    pub action_failed: &'static str,
+    pub macros_title: &'static str,
+    pub macro_name: &'static str,
+    pub select_macro: &'static str,
+    pub record: &'static str,
+    pub stop_and_save: &'static str,
+    pub replay: &'static str,
+    pub recording_started: &'static str,
+    pub refresh_preview_first: &'static str,
+    pub enter_macro_name: &'static str,
+    pub macro_saved: &'static str,
+    pub macro_save_failed: &'static str,
+    pub replaying_macro: &'static str,
+    pub macro_replayed: &'static str,
+    pub macro_replay_failed: &'static str,
+    pub calibration_starting: &'static str,
+    pub calibrating: &'static str,
+    pub calibration_completed: &'static str,
+    pub calibration_failed: &'static str,
+    pub logs_cleared: &'static str,
+    pub clear_logs: &'static str,
+    pub log_file: &'static str,
+    pub not_created: &'static str,
+    pub settings_saved: &'static str,
+    pub settings_save_failed: &'static str,
+    pub save_failed: &'static str,
+    pub settings_reset: &'static str,
+    pub save_settings: &'static str,
+    pub reset_defaults: &'static str,
+    pub profiles_title: &'static str,
+    pub profile: &'static str,
+    pub current_profile: &'static str,
+    pub no_profile: &'static str,
+    pub save_as: &'static str,
+    pub profile_placeholder: &'static str,
+    pub save_profile: &'static str,
+    pub delete: &'static str,
+    pub profile_switched: &'static str,
+    pub profile_load_failed: &'static str,
+    pub profile_saved: &'static str,
+    pub profile_save_failed: &'static str,
+    pub profile_deleted: &'static str,
+    pub profile_delete_failed: &'static str,
+    pub model_section: &'static str,
+    pub api_url: &'static str,
+    pub api_key: &'static str,
+    pub model_name: &'static str,
+    pub device_section: &'static str,
+    pub device: &'static str,
+    pub language: &'static str,
+    pub coordinate_section: &'static str,
+    pub coordinate_system: &'static str,
+    pub coord_relative: &'static str,
+    pub coord_absolute: &'static str,
+    pub scale_x: &'static str,
+    pub scale_y: &'static str,
+    pub no_scale_needed: &'static str,
+    pub retry_section: &'static str,
+    pub max_retries: &'static str,
+    pub retry_delay: &'static str,
+    pub max_steps: &'static str,
+    pub calibration_section: &'static str,
+    pub auto_calibration: &'static str,
+    pub calibration_mode: &'static str,
+    pub calib_simple: &'static str,
+    pub calib_complex: &'static str,
+    pub calib_grid: &'static str,
+    pub complex_rounds: &'static str,
+    pub show_markers: &'static str,
+    pub recalibrate_on_drift: &'static str,
+    pub appearance_section: &'static str,
+    pub theme: &'static str,
+    pub theme_system: &'static str,
+    pub theme_light: &'static str,
+    pub theme_dark: &'static str,
+    pub accent_color: &'static str,
+    pub accent_placeholder: &'static str,
+    pub usage_title: &'static str,
+    pub usage_task: &'static str,
+    pub usage_session: &'static str,
+    pub usage_input: &'static str,
+    pub usage_output: &'static str,
+    pub usage_calls: &'static str,
+    pub estimated_cost: &'static str,
+    pub price_per_million: &'static str,
+    pub control_pad: &'static str,
+    pub recents: &'static str,
+    pub type_placeholder: &'static str,
+    pub type_text: &'static str,
+    pub resume_agent: &'static str,
+    pub open_control_pad: &'static str,
+    pub control_returned: &'static str,
+    pub dual_loop_section: &'static str,
+    pub dual_loop_mode: &'static str,
+    pub planner_preset: &'static str,
+    pub dual_loop_started: &'static str,
+    pub dual_loop_stopped: &'static str,
+    pub dual_loop_failed: &'static str,
+    pub dual_loop_command_failed: &'static str,
+    pub sent_to_planner: &'static str,
+    pub send: &'static str,
+    pub todo_title: &'static str,
+    pub todo_hint: &'static str,
+    pub edit: &'static str,
+    pub save: &'static str,
+    pub cancel: &'static str,
+    pub confirmation_hint: &'static str,
+    pub confirm: &'static str,
+    pub reject: &'static str,
+    pub action_confirmed: &'static str,
+    pub action_rejected: &'static str,
+    pub confirmation_timeout: &'static str,
+    pub auto_deny_in: &'static str,
+    pub confirmation_timed_out: &'static str,
+    pub report_title: &'static str,
+    pub export_report: &'static str,
+    pub export_dir: &'static str,
+    pub report_exported: &'static str,
+    pub export_failed: &'static str,
+    pub model: &'static str,
+    pub checking: &'static str,
+    pub reachable: &'static str,
+    pub model_not_listed: &'static str,
+    pub no_device: &'static str,
+    pub planner_chat_title: &'static str,
+    pub planner_chat_placeholder: &'static str,
+    pub you: &'static str,
+    pub planner: &'static str,
+    pub system: &'static str,
+    pub app_hints_section: &'static str,
+    pub app_hints_hint: &'static str,
+    pub package_name: &'static str,
+    pub hint_placeholder: &'static str,
+    pub current_app: &'static str,
+    pub add: &'static str,
+    pub prompt_style: &'static str,
+    pub prompt_full: &'static str,
+    pub prompt_concise: &'static str,
+    pub app_region: &'static str,
+    pub region_china: &'static str,
+    pub region_international: &'static str,
+    pub disabled_actions: &'static str,
+    pub prompt_variant: &'static str,
+    pub err_unknown_action_type: &'static str,
+    pub err_unknown_action: &'static str,
+    pub err_action_disabled: &'static str,
+    pub err_missing_parameter: &'static str,
+    pub err_action_failed: &'static str,
+    pub err_parse: &'static str,
+    pub err_parse_retry: &'static str,
+    pub err_no_app_name: &'static str,
+    pub err_app_not_found: &'static str,
+    pub err_invalid_coordinates: &'static str,
+    pub err_missing_coordinates: &'static str,
+    pub err_coordinate: &'static str,
+    pub err_relative_out_of_bounds: &'static str,
+    pub err_scaled_out_of_bounds: &'static str,
+    pub grid_mapping: &'static str,
+    pub user_cancelled_sensitive: &'static str,
+    pub user_interaction_required: &'static str,
+    pub user_intervention_required: &'static str,
+    pub dry_run_action: &'static str,
+    pub dry_run_needs_confirmation: &'static str,
+    pub config_invalid: &'static str,
+    pub plaintext_api_keys: &'static str,
+    pub settings_file: &'static str,
+    pub settings_file_placeholder: &'static str,
+    pub export_settings: &'static str,
+    pub import_settings: &'static str,
+    pub settings_exported: &'static str,
+    pub settings_imported: &'static str,
+    pub settings_import_failed: &'static str,
+    pub settings_reloaded: &'static str,
+    pub settings_load_failed: &'static str,
+    pub invalid_settings: &'static str,
 }
 
 /// Chinese messages
@@ -41,6 +279,232 @@ pub static MESSAGES_ZH: Messages = Messages {
     step: "步骤",
     task: "任务",
     result: "结果",
+    app_title: "Phone Agent - AI 手机自动化",
+    app_started: "Phone Agent GUI 启动",
+    tab_main: "🏠 主页",
+    tab_timeline: "🕒 时间线",
+    tab_history: "📜 历史",
+    tab_preview: "📺 预览",
+    tab_settings: "⚙️ 设置",
+    tab_logs: "📋 日志",
+    state_idle: "🟢 就绪",
+    state_running: "🔵 运行中",
+    state_calibrating: "🟡 校准中",
+    state_replaying: "🟣 回放中",
+    ready: "就绪",
+    running: "正在执行...",
+    stopping: "正在停止...",
+    stopping_task: "正在停止任务...",
+    cancelled: "已取消",
+    task_cancelled: "任务已取消",
+    task_failed: "任务失败",
+    failed: "失败",
+    enter_task: "请输入任务",
+    wait_for_task: "请等待当前任务结束",
+    task_placeholder: "输入任务，例如: 打开微信",
+    run: "▶️ 运行",
+    stop: "⏹️ 停止",
+    calibrate: "🎯 校准",
+    execution_log: "📜 执行日志",
+    steps_unit: "步",
+    none: "无",
+    no_screenshot: "🔒 无截图",
+    timeline_empty: "运行任务后，这里会逐步显示截图、思考和动作",
+    history_title: "📜 任务历史",
+    history_empty: "完成的任务会保存在这里",
+    history_save_failed: "保存历史失败",
+    report_write_failed: "写入报告失败",
+    run_again: "🔁 再次运行",
+    started: "开始",
+    duration: "耗时",
+    message: "消息",
+    clear: "🗑️ 清空",
+    devices_found: "发现设备",
+    device_list_failed: "获取设备列表失败",
+    auto_detect: "🔍 自动检测",
+    not_connected: "未连接",
+    preview_title: "📺 设备预览",
+    no_preview: "暂无画面（敏感页面或设备未连接），点击刷新",
+    refresh: "🔄 刷新",
+    back: "◀️ 返回",
+    home: "🏠 主屏幕",
+    gesture_hint: "点击 = 点击，按住 = 长按，拖动 = 滑动",
+    preview_action: "预览动作",
+    action_failed: "动作执行失败",
+    macros_title: "🎬 宏",
+    macro_name: "宏名称",
+    select_macro: "选择宏",
+    record: "⏺️ 录制",
+    stop_and_save: "⏹️ 停止并保存",
+    replay: "▶️ 回放",
+    recording_started: "开始录制宏，在预览上点击或拖动",
+    refresh_preview_first: "请先刷新预览",
+    enter_macro_name: "请输入宏名称",
+    macro_saved: "宏已保存",
+    macro_save_failed: "保存宏失败",
+    replaying_macro: "正在回放宏",
+    macro_replayed: "宏回放完成",
+    macro_replay_failed: "宏回放失败",
+    calibration_starting: "开始坐标校准...",
+    calibrating: "正在校准...",
+    calibration_completed: "校准完成",
+    calibration_failed: "校准失败",
+    logs_cleared: "日志已清空",
+    clear_logs: "🗑️ 清空日志",
+    log_file: "日志文件",
+    not_created: "未创建",
+    settings_saved: "设置已保存",
+    settings_save_failed: "保存设置失败",
+    save_failed: "保存失败",
+    settings_reset: "设置已重置为默认值",
+    save_settings: "💾 保存设置",
+    reset_defaults: "🔄 重置默认",
+    profiles_title: "🗂️ 配置档案",
+    profile: "配置档案",
+    current_profile: "当前档案",
+    no_profile: "未使用档案",
+    save_as: "另存为",
+    profile_placeholder: "例如: 家里 vLLM",
+    save_profile: "💾 保存档案",
+    delete: "🗑️ 删除",
+    profile_switched: "已切换到配置档案",
+    profile_load_failed: "加载配置档案失败",
+    profile_saved: "配置档案已保存",
+    profile_save_failed: "保存配置档案失败",
+    profile_deleted: "配置档案已删除",
+    profile_delete_failed: "删除配置档案失败",
+    model_section: "🤖 模型配置",
+    api_url: "API 地址",
+    api_key: "API 密钥",
+    model_name: "模型名称",
+    device_section: "📱 设备配置",
+    device: "设备",
+    language: "语言",
+    coordinate_section: "📐 坐标系统",
+    coordinate_system: "坐标系统",
+    coord_relative: "相对坐标 (0-999)",
+    coord_absolute: "绝对坐标 (像素)",
+    scale_x: "缩放比例 X",
+    scale_y: "缩放比例 Y",
+    no_scale_needed: "相对坐标模式下不需要缩放设置",
+    retry_section: "🔄 重试配置",
+    max_retries: "最大重试次数",
+    retry_delay: "重试延迟(秒)",
+    max_steps: "最大步骤数",
+    calibration_section: "🎯 校准配置",
+    auto_calibration: "启用自动校准",
+    calibration_mode: "校准模式",
+    calib_simple: "简单模式",
+    calib_complex: "复杂模式",
+    calib_grid: "网格模式",
+    complex_rounds: "复杂模式轮数",
+    show_markers: "设备上显示标记",
+    recalibrate_on_drift: "漂移时自动重新校准",
+    appearance_section: "🎨 外观",
+    theme: "主题",
+    theme_system: "跟随系统",
+    theme_light: "浅色",
+    theme_dark: "深色",
+    accent_color: "强调色",
+    accent_placeholder: "#RRGGBB，留空为默认",
+    usage_title: "📊 用量",
+    usage_task: "本次任务",
+    usage_session: "本次会话",
+    usage_input: "输入",
+    usage_output: "输出",
+    usage_calls: "次调用",
+    estimated_cost: "预估花费",
+    price_per_million: "价格/百万 tokens",
+    control_pad: "🎮 控制面板",
+    recents: "🗂️ 最近任务",
+    type_placeholder: "输入要发送到设备的文字",
+    type_text: "⌨️ 输入",
+    resume_agent: "✅ 完成，继续任务",
+    open_control_pad: "🎮 打开控制面板",
+    control_returned: "已交还控制，继续执行",
+    dual_loop_section: "🔄 双循环模式",
+    dual_loop_mode: "启用双循环",
+    planner_preset: "规划预设",
+    dual_loop_started: "双循环已启动",
+    dual_loop_stopped: "双循环已停止",
+    dual_loop_failed: "启动双循环失败",
+    dual_loop_command_failed: "双循环命令失败",
+    sent_to_planner: "已发送给规划器",
+    send: "发送",
+    todo_title: "任务列表",
+    todo_hint: "拖动任务调整顺序，等待中的任务可以修改",
+    edit: "编辑",
+    save: "保存",
+    cancel: "取消",
+    confirmation_hint: "智能体要执行一个敏感操作，是否允许？",
+    confirm: "✅ 批准",
+    reject: "🚫 拒绝",
+    action_confirmed: "已批准敏感操作",
+    action_rejected: "已拒绝敏感操作",
+    confirmation_timeout: "确认超时 (秒)",
+    auto_deny_in: "自动拒绝倒计时",
+    confirmation_timed_out: "确认超时，已自动拒绝",
+    report_title: "Phone Agent 运行报告",
+    export_report: "📤 导出报告",
+    export_dir: "导出目录",
+    report_exported: "报告已导出",
+    export_failed: "导出失败",
+    model: "模型",
+    checking: "检查中…",
+    reachable: "可连接",
+    model_not_listed: "服务器未提供此模型",
+    no_device: "未找到设备",
+    planner_chat_title: "规划器对话",
+    planner_chat_placeholder: "给规划器发消息…",
+    you: "你",
+    planner: "规划器",
+    system: "系统",
+    app_hints_section: "💡 应用提示",
+    app_hints_hint: "前台应用的提示会附加到执行器的系统提示词",
+    package_name: "包名",
+    hint_placeholder: "例如：在微信里，发送按钮在输入框右侧",
+    current_app: "📍 当前应用",
+    add: "➕ 添加",
+    prompt_style: "提示词",
+    prompt_full: "完整",
+    prompt_concise: "精简（小模型）",
+    app_region: "应用目录",
+    region_china: "中国",
+    region_international: "国际",
+    disabled_actions: "禁用操作",
+    prompt_variant: "提示词版本",
+    err_unknown_action_type: "未知的操作类型: {0}",
+    err_unknown_action: "未知操作: {0}",
+    err_action_disabled: "操作已禁用: {0}",
+    err_missing_parameter: "缺少必需参数: {0}",
+    err_action_failed: "操作失败: {0}",
+    err_parse: "解析错误: {0}",
+    err_parse_retry: "无法解析动作指令，请重新输出完整的 do(...) 或 finish(...) 指令。原始输出: {0}",
+    err_no_app_name: "未指定应用名称",
+    err_app_not_found: "未找到应用: {0}",
+    err_invalid_coordinates: "{0} 坐标无效",
+    err_missing_coordinates: "缺少 {0} 坐标",
+    err_coordinate: "{0} 坐标错误: {1}",
+    err_relative_out_of_bounds: "相对 {0} 坐标 {1} 超出范围，有效范围为 [0, 999]。",
+    err_scaled_out_of_bounds: "换算后的 {0} 坐标 {1}（原始值: {2} {3}）超出范围，有效范围为 [0, {4})。请提供屏幕范围内的坐标。",
+    grid_mapping: "网格映射",
+    user_cancelled_sensitive: "用户取消了敏感操作",
+    user_interaction_required: "需要用户交互",
+    user_intervention_required: "需要用户介入",
+    dry_run_action: "演练模式，未执行: {0}",
+    dry_run_needs_confirmation: "{0}（需要确认: {1}）",
+    config_invalid: "配置有误，详见日志",
+    plaintext_api_keys: "明文保存密钥",
+    settings_file: "设置文件",
+    settings_file_placeholder: "导出时留空则存到导出目录",
+    export_settings: "导出",
+    import_settings: "导入",
+    settings_exported: "设置已导出（不含密钥）",
+    settings_imported: "设置已导入",
+    settings_import_failed: "导入设置失败",
+    settings_reloaded: "设置文件已更改，已应用到当前会话",
+    settings_load_failed: "读取设置文件失败",
+    invalid_settings: "部分设置无效，请查看标出的字段",
 };
 
 /// English messages
@@ -62,19 +526,692 @@ pub static MESSAGES_EN: Messages = Messages {
     step: "Step",
     task: "Task",
     result: "Result",
+    app_title: "Phone Agent - AI Phone Automation",
+    app_started: "Phone Agent GUI started",
+    tab_main: "🏠 Home",
+    tab_timeline: "🕒 Timeline",
+    tab_history: "📜 History",
+    tab_preview: "📺 Preview",
+    tab_settings: "⚙️ Settings",
+    tab_logs: "📋 Logs",
+    state_idle: "🟢 Ready",
+    state_running: "🔵 Running",
+    state_calibrating: "🟡 Calibrating",
+    state_replaying: "🟣 Replaying",
+    ready: "Ready",
+    running: "Running...",
+    stopping: "Stopping...",
+    stopping_task: "Stopping task...",
+    cancelled: "Cancelled",
+    task_cancelled: "Task cancelled",
+    task_failed: "Task failed",
+    failed: "Failed",
+    enter_task: "Please enter a task",
+    wait_for_task: "Wait for the current task to finish",
+    task_placeholder: "Enter a task, e.g. Open WeChat",
+    run: "▶️ Run",
+    stop: "⏹️ Stop",
+    calibrate: "🎯 Calibrate",
+    execution_log: "📜 Execution Log",
+    steps_unit: "steps",
+    none: "None",
+    no_screenshot: "🔒 No screenshot",
+    timeline_empty: "Run a task to see its screenshots, thinking and actions here step by step",
+    history_title: "📜 Task History",
+    history_empty: "Finished tasks are kept here",
+    history_save_failed: "Failed to save history",
+    report_write_failed: "Failed to write report",
+    run_again: "🔁 Run Again",
+    started: "Started",
+    duration: "Duration",
+    message: "Message",
+    clear: "🗑️ Clear",
+    devices_found: "Devices found",
+    device_list_failed: "Failed to list devices",
+    auto_detect: "🔍 Auto-detect",
+    not_connected: "not connected",
+    preview_title: "📺 Device Preview",
+    no_preview: "No picture (sensitive screen or no device), press refresh",
+    refresh: "🔄 Refresh",
+    back: "◀️ Back",
+    home: "🏠 Home Screen",
+    gesture_hint: "Click = tap, hold = long press, drag = swipe",
+    preview_action: "Preview action",
+    action_failed: "Action failed",
+    macros_title: "🎬 Macros",
+    macro_name: "Macro name",
+    select_macro: "Select macro",
+    record: "⏺️ Record",
+    stop_and_save: "⏹️ Stop and Save",
+    replay: "▶️ Replay",
+    recording_started: "Recording macro, click or drag on the preview",
+    refresh_preview_first: "Refresh the preview first",
+    enter_macro_name: "Please enter a macro name",
+    macro_saved: "Macro saved",
+    macro_save_failed: "Failed to save macro",
+    replaying_macro: "Replaying macro",
+    macro_replayed: "Macro replayed",
+    macro_replay_failed: "Macro replay failed",
+    calibration_starting: "Starting coordinate calibration...",
+    calibrating: "Calibrating...",
+    calibration_completed: "Calibration completed",
+    calibration_failed: "Calibration failed",
+    logs_cleared: "Logs cleared",
+    clear_logs: "🗑️ Clear Logs",
+    log_file: "Log file",
+    not_created: "not created",
+    settings_saved: "Settings saved",
+    settings_save_failed: "Failed to save settings",
+    save_failed: "Save failed",
+    settings_reset: "Settings reset to defaults",
+    save_settings: "💾 Save Settings",
+    reset_defaults: "🔄 Reset to Defaults",
+    profiles_title: "🗂️ Profiles",
+    profile: "Profile",
+    current_profile: "Current profile",
+    no_profile: "No profile",
+    save_as: "Save as",
+    profile_placeholder: "e.g. Home vLLM",
+    save_profile: "💾 Save Profile",
+    delete: "🗑️ Delete",
+    profile_switched: "Switched to profile",
+    profile_load_failed: "Failed to load profile",
+    profile_saved: "Profile saved",
+    profile_save_failed: "Failed to save profile",
+    profile_deleted: "Profile deleted",
+    profile_delete_failed: "Failed to delete profile",
+    model_section: "🤖 Model",
+    api_url: "API URL",
+    api_key: "API key",
+    model_name: "Model name",
+    device_section: "📱 Device",
+    device: "Device",
+    language: "Language",
+    coordinate_section: "📐 Coordinates",
+    coordinate_system: "Coordinate system",
+    coord_relative: "Relative (0-999)",
+    coord_absolute: "Absolute (pixels)",
+    scale_x: "Scale X",
+    scale_y: "Scale Y",
+    no_scale_needed: "Relative coordinates need no scaling",
+    retry_section: "🔄 Retries",
+    max_retries: "Max retries",
+    retry_delay: "Retry delay (s)",
+    max_steps: "Max steps",
+    calibration_section: "🎯 Calibration",
+    auto_calibration: "Auto calibration",
+    calibration_mode: "Calibration mode",
+    calib_simple: "Simple",
+    calib_complex: "Complex",
+    calib_grid: "Grid",
+    complex_rounds: "Complex rounds",
+    show_markers: "Markers on device",
+    recalibrate_on_drift: "Recalibrate on drift",
+    appearance_section: "🎨 Appearance",
+    theme: "Theme",
+    theme_system: "System",
+    theme_light: "Light",
+    theme_dark: "Dark",
+    accent_color: "Accent color",
+    accent_placeholder: "#RRGGBB, empty for default",
+    usage_title: "📊 Usage",
+    usage_task: "This task",
+    usage_session: "Session",
+    usage_input: "in",
+    usage_output: "out",
+    usage_calls: "calls",
+    estimated_cost: "Est. cost",
+    price_per_million: "Price / 1M tokens",
+    control_pad: "🎮 Control Pad",
+    recents: "🗂️ Recents",
+    type_placeholder: "Text to type on the device",
+    type_text: "⌨️ Type",
+    resume_agent: "✅ Done, resume task",
+    open_control_pad: "🎮 Open Control Pad",
+    control_returned: "Control handed back, resuming",
+    dual_loop_section: "🔄 Dual-Loop Mode",
+    dual_loop_mode: "Enable dual loop",
+    planner_preset: "Planner preset",
+    dual_loop_started: "Dual loop started",
+    dual_loop_stopped: "Dual loop stopped",
+    dual_loop_failed: "Failed to start dual loop",
+    dual_loop_command_failed: "Dual-loop command failed",
+    sent_to_planner: "Sent to planner",
+    send: "Send",
+    todo_title: "Todo List",
+    todo_hint: "Drag tasks to reorder; pending tasks can be edited",
+    edit: "Edit",
+    save: "Save",
+    cancel: "Cancel",
+    confirmation_hint: "The agent wants to perform a sensitive operation. Allow it?",
+    confirm: "✅ Approve",
+    reject: "🚫 Deny",
+    action_confirmed: "Sensitive operation approved",
+    action_rejected: "Sensitive operation denied",
+    confirmation_timeout: "Confirm timeout (s)",
+    auto_deny_in: "Denied automatically in",
+    confirmation_timed_out: "Confirmation timed out, denied",
+    report_title: "Phone Agent Run Report",
+    export_report: "📤 Export Report",
+    export_dir: "Export folder",
+    report_exported: "Report exported",
+    export_failed: "Export failed",
+    model: "Model",
+    checking: "Checking…",
+    reachable: "Reachable",
+    model_not_listed: "Model not offered by the server",
+    no_device: "No device found",
+    planner_chat_title: "Planner Conversation",
+    planner_chat_placeholder: "Message the planner…",
+    you: "You",
+    planner: "Planner",
+    system: "System",
+    app_hints_section: "💡 App Hints",
+    app_hints_hint: "The foreground app's hint is appended to the executor's system prompt",
+    package_name: "Package",
+    hint_placeholder: "e.g. In WeChat the send button is right of the input box",
+    current_app: "📍 Current app",
+    add: "➕ Add",
+    prompt_style: "Prompt",
+    prompt_full: "Full",
+    prompt_concise: "Concise (small models)",
+    app_region: "App catalog",
+    region_china: "China",
+    region_international: "International",
+    disabled_actions: "Disabled actions",
+    prompt_variant: "Prompt version",
+    err_unknown_action_type: "Unknown action type: {0}",
+    err_unknown_action: "Unknown action: {0}",
+    err_action_disabled: "Action disabled: {0}",
+    err_missing_parameter: "Missing required parameter: {0}",
+    err_action_failed: "Action failed: {0}",
+    err_parse: "Parse error: {0}",
+    err_parse_retry: "Could not parse the action. Output a complete do(...) or finish(...) instruction again. Raw output: {0}",
+    err_no_app_name: "No app name specified",
+    err_app_not_found: "App not found: {0}",
+    err_invalid_coordinates: "Invalid {0} coordinates",
+    err_missing_coordinates: "Missing {0} coordinates",
+    err_coordinate: "Coordinate error for {0}: {1}",
+    err_relative_out_of_bounds: "Relative {0} coordinate {1} is out of bounds. Valid range is [0, 999].",
+    err_scaled_out_of_bounds: "Scaled {0} coordinate {1} (raw: {2} {3}) is out of bounds. Valid range is [0, {4}). Please provide coordinates within the screen area.",
+    grid_mapping: "grid mapping",
+    user_cancelled_sensitive: "User cancelled sensitive operation",
+    user_interaction_required: "User interaction required",
+    user_intervention_required: "User intervention required",
+    dry_run_action: "Dry run, not performed: {0}",
+    dry_run_needs_confirmation: "{0} (needs confirmation: {1})",
+    config_invalid: "The configuration has problems, see the log",
+    plaintext_api_keys: "Keys in file",
+    settings_file: "Settings file",
+    settings_file_placeholder: "Empty = export folder",
+    export_settings: "Export",
+    import_settings: "Import",
+    settings_exported: "Settings exported without secrets",
+    settings_imported: "Settings imported",
+    settings_import_failed: "Failed to import settings",
+    settings_reloaded: "Settings file changed, applied to the running session",
+    settings_load_failed: "Failed to read the settings file",
+    invalid_settings: "Some settings are invalid; see the marked fields",
 };
 
+/// Generates by-key access to the fields of [`Messages`], so locale files
+/// can override them and [`get_message`] can look them up.
+macro_rules! message_keys {
+    ($($key:ident),* $(,)?) => {
+        impl Messages {
+            /// Every message key, in declaration order.
+            pub const KEYS: &'static [&'static str] = &[$(stringify!($key)),*];
+
+            /// Get a message by key.
+            pub fn get(&self, key: &str) -> Option<&'static str> {
+                match key {
+                    $(stringify!($key) => Some(self.$key),)*
+                    _ => None,
+                }
+            }
+
+            /// Replace a message by key. Returns false for unknown keys.
+            fn set(&mut self, key: &str, value: &'static str) -> bool {
+                match key {
+                    $(stringify!($key) => self.$key = value,)*
+                    _ => return false,
+                }
+                true
+            }
+        }
+
+        // Fails to compile when a `Messages` field is missing from the list.
+        const _: fn(&Messages) = |messages| {
+            let Messages { $($key: _),* } = messages;
+        };
+    };
+}
+
+message_keys!(
+    thinking,
+    action,
+    task_completed,
+    done,
+    starting_task,
+    final_result,
+    task_result,
+    confirmation_required,
+    continue_prompt,
+    manual_operation_required,
+    manual_operation_hint,
+    press_enter_when_done,
+    connection_failed,
+    connection_successful,
+    step,
+    task,
+    result,
+    app_title,
+    app_started,
+    tab_main,
+    tab_timeline,
+    tab_history,
+    tab_preview,
+    tab_settings,
+    tab_logs,
+    state_idle,
+    state_running,
+    state_calibrating,
+    state_replaying,
+    ready,
+    running,
+    stopping,
+    stopping_task,
+    cancelled,
+    task_cancelled,
+    task_failed,
+    failed,
+    enter_task,
+    wait_for_task,
+    task_placeholder,
+    run,
+    stop,
+    calibrate,
+    execution_log,
+    steps_unit,
+    none,
+    no_screenshot,
+    timeline_empty,
+    history_title,
+    history_empty,
+    history_save_failed,
+    report_write_failed,
+    run_again,
+    started,
+    duration,
+    message,
+    clear,
+    devices_found,
+    device_list_failed,
+    auto_detect,
+    not_connected,
+    preview_title,
+    no_preview,
+    refresh,
+    back,
+    home,
+    gesture_hint,
+    preview_action,
+    action_failed,
+    macros_title,
+    macro_name,
+    select_macro,
+    record,
+    stop_and_save,
+    replay,
+    recording_started,
+    refresh_preview_first,
+    enter_macro_name,
+    macro_saved,
+    macro_save_failed,
+    replaying_macro,
+    macro_replayed,
+    macro_replay_failed,
+    calibration_starting,
+    calibrating,
+    calibration_completed,
+    calibration_failed,
+    logs_cleared,
+    clear_logs,
+    log_file,
+    not_created,
+    settings_saved,
+    settings_save_failed,
+    save_failed,
+    settings_reset,
+    save_settings,
+    reset_defaults,
+    profiles_title,
+    profile,
+    current_profile,
+    no_profile,
+    save_as,
+    profile_placeholder,
+    save_profile,
+    delete,
+    profile_switched,
+    profile_load_failed,
+    profile_saved,
+    profile_save_failed,
+    profile_deleted,
+    profile_delete_failed,
+    model_section,
+    api_url,
+    api_key,
+    model_name,
+    device_section,
+    device,
+    language,
+    coordinate_section,
+    coordinate_system,
+    coord_relative,
+    coord_absolute,
+    scale_x,
+    scale_y,
+    no_scale_needed,
+    retry_section,
+    max_retries,
+    retry_delay,
+    max_steps,
+    calibration_section,
+    auto_calibration,
+    calibration_mode,
+    calib_simple,
+    calib_complex,
+    calib_grid,
+    complex_rounds,
+    show_markers,
+    recalibrate_on_drift,
+    appearance_section,
+    theme,
+    theme_system,
+    theme_light,
+    theme_dark,
+    accent_color,
+    accent_placeholder,
+    usage_title,
+    usage_task,
+    usage_session,
+    usage_input,
+    usage_output,
+    usage_calls,
+    estimated_cost,
+    price_per_million,
+    control_pad,
+    recents,
+    type_placeholder,
+    type_text,
+    resume_agent,
+    open_control_pad,
+    control_returned,
+    dual_loop_section,
+    dual_loop_mode,
+    planner_preset,
+    dual_loop_started,
+    dual_loop_stopped,
+    dual_loop_failed,
+    dual_loop_command_failed,
+    sent_to_planner,
+    send,
+    todo_title,
+    todo_hint,
+    edit,
+    save,
+    cancel,
+    confirmation_hint,
+    confirm,
+    reject,
+    action_confirmed,
+    action_rejected,
+    confirmation_timeout,
+    auto_deny_in,
+    confirmation_timed_out,
+    report_title,
+    export_report,
+    export_dir,
+    report_exported,
+    export_failed,
+    model,
+    checking,
+    reachable,
+    model_not_listed,
+    no_device,
+    planner_chat_title,
+    planner_chat_placeholder,
+    you,
+    planner,
+    system,
+    app_hints_section,
+    app_hints_hint,
+    package_name,
+    hint_placeholder,
+    current_app,
+    add,
+    prompt_style,
+    prompt_full,
+    prompt_concise,
+    app_region,
+    region_china,
+    region_international,
+    disabled_actions,
+    prompt_variant,
+    err_unknown_action_type,
+    err_unknown_action,
+    err_action_disabled,
+    err_missing_parameter,
+    err_action_failed,
+    err_parse,
+    err_parse_retry,
+    err_no_app_name,
+    err_app_not_found,
+    err_invalid_coordinates,
+    err_missing_coordinates,
+    err_coordinate,
+    err_relative_out_of_bounds,
+    err_scaled_out_of_bounds,
+    grid_mapping,
+    user_cancelled_sensitive,
+    user_interaction_required,
+    user_intervention_required,
+    dry_run_action,
+    dry_run_needs_confirmation,
+    config_invalid,
+    plaintext_api_keys,
+    settings_file,
+    settings_file_placeholder,
+    export_settings,
+    import_settings,
+    settings_exported,
+    settings_imported,
+    settings_import_failed,
+    settings_reloaded,
+    settings_load_failed,
+    invalid_settings,
+);
+
+/// A language registered at runtime.
+#[derive(Debug, Clone, Copy)]
+pub struct RegisteredLanguage {
+    /// Language code, e.g. "ja" or "zh-TW".
+    pub code: &'static str,
+    /// Name shown in language pickers, e.g. "日本語".
+    pub name: &'static str,
+    pub messages: &'static Messages,
+}
+
+/// Languages registered at runtime, in registration order.
+static REGISTRY: Lazy<RwLock<Vec<RegisteredLanguage>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Built-in messages a language falls back to for keys it doesn't translate:
+/// Chinese for "zh*" codes, English for everything else.
+fn fallback_messages(code: &str) -> &'static Messages {
+    if code == "cn" || code.starts_with("zh") {
+        &MESSAGES_ZH
+    } else {
+        &MESSAGES_EN
+    }
+}
+
+/// Register (or replace) a language from translated messages.
+///
+/// Keys missing from `translations`, or translated to blank text, keep their
+/// built-in fallback text (see [`fallback_messages`]). The "language_name" key sets the name shown in
+/// language pickers. Registered messages live for the rest of the program.
+///
+/// # Returns
+/// Keys in `translations` that aren't message keys, so typos can be reported.
+pub fn register_language(code: &str, translations: &HashMap<String, String>) -> Vec<String> {
+    let mut messages = fallback_messages(code).clone();
+    let mut name = code.to_string();
+    let mut unknown = Vec::new();
+    for (key, value) in translations {
+        let key = key.replace('-', "_");
+        if key == "language_name" {
+            name = value.clone();
+        } else if value.trim().is_empty() {
+            // Keep the fallback text rather than showing a blank label
+            if messages.get(&key).is_none() {
+                unknown.push(key);
+            }
+        } else if !messages.set(&key, Box::leak(value.clone().into_boxed_str())) {
+            unknown.push(key);
+        }
+    }
+    unknown.sort();
+
+    let language = RegisteredLanguage {
+        code: Box::leak(code.to_string().into_boxed_str()),
+        name: Box::leak(name.into_boxed_str()),
+        messages: Box::leak(Box::new(messages)),
+    };
+    let mut registry = REGISTRY.write().unwrap();
+    match registry.iter_mut().find(|existing| existing.code == code) {
+        Some(existing) => *existing = language,
+        None => registry.push(language),
+    }
+    unknown
+}
+
+/// Register a language from a JSON file (an object of key → text) or a
+/// Fluent file (`key = text` lines). The file name without its extension is
+/// the language code, e.g. `ja.json` or `zh-TW.ftl`.
+///
+/// Only plain Fluent messages are read: placeables, variants and attributes
+/// are not supported.
+///
+/// # Returns
+/// The language code and the unknown keys in the file.
+pub fn load_language_file(path: &Path) -> Result<(String, Vec<String>), String> {
+    let code = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty())
+        .ok_or_else(|| format!("No language code in file name: {}", path.display()))?
+        .to_string();
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let translations = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string())?,
+        Some("ftl") => parse_fluent(&content),
+        _ => return Err(format!("Unsupported language file: {}", path.display())),
+    };
+    let unknown = register_language(&code, &translations);
+    Ok((code, unknown))
+}
+
+/// Register every `.json` and `.ftl` language file in a directory. Files
+/// that fail to load are logged and skipped; a missing directory is fine.
+///
+/// # Returns
+/// Codes of the languages loaded.
+pub fn load_languages_dir(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("json" | "ftl")
+            )
+        })
+        .collect();
+    paths.sort();
+
+    let mut codes = Vec::new();
+    for path in paths {
+        match load_language_file(&path) {
+            Ok((code, unknown)) => {
+                if !unknown.is_empty() {
+                    tracing::warn!(
+                        "Unknown message keys in {}: {}",
+                        path.display(),
+                        unknown.join(", ")
+                    );
+                }
+                codes.push(code);
+            }
+            Err(e) => tracing::warn!("Failed to load language {}: {}", path.display(), e),
+        }
+    }
+    codes
+}
+
+/// Languages registered at runtime, in registration order.
+pub fn registered_languages() -> Vec<RegisteredLanguage> {
+    REGISTRY.read().unwrap().clone()
+}
+
+/// Read `key = text` lines of a Fluent file, skipping comments and blank
+/// lines. Indented lines continue the previous message.
+fn parse_fluent(content: &str) -> HashMap<String, String> {
+    let mut translations: HashMap<String, String> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = current.as_ref().and_then(|key| translations.get_mut(key)) {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+            translations.insert(key.clone(), value.trim().to_string());
+            current = Some(key);
+        }
+    }
+    translations
+}
+
 /// Get UI messages by language.
 ///
 /// # Arguments
-/// * `lang` - Language code, "cn" for Chinese, "en" for English.
+/// * `lang` - Language code, "cn" for Chinese, "en" for English, or the
+///   code of a language registered with [`register_language`].
 ///
 /// # Returns
-/// Reference to Messages struct.
+/// Reference to Messages struct. Unknown codes get Chinese.
 pub fn get_messages(lang: &str) -> &'static Messages {
     match lang {
         "en" => &MESSAGES_EN,
-        _ => &MESSAGES_ZH,
+        "cn" => &MESSAGES_ZH,
+        _ => REGISTRY
+            .read()
+            .unwrap()
+            .iter()
+            .find(|language| language.code == lang)
+            .map(|language| language.messages)
+            .unwrap_or(&MESSAGES_ZH),
     }
 }
 
@@ -82,32 +1219,51 @@ pub fn get_messages(lang: &str) -> &'static Messages {
 ///
 /// # Arguments
 /// * `key` - Message key.
-/// * `lang` - Language code, "cn" for Chinese, "en" for English.
+/// * `lang` - Language code (see [`get_messages`]).
 ///
 /// # Returns
-/// Message string.
-pub fn get_message(key: &str, lang: &str) -> &'static str {
-    let messages = get_messages(lang);
-    match key {
-        "thinking" => messages.thinking,
-        "action" => messages.action,
-        "task_completed" => messages.task_completed,
-        "done" => messages.done,
-        "starting_task" => messages.starting_task,
-        "final_result" => messages.final_result,
-        "task_result" => messages.task_result,
-        "confirmation_required" => messages.confirmation_required,
-        "continue_prompt" => messages.continue_prompt,
-        "manual_operation_required" => messages.manual_operation_required,
-        "manual_operation_hint" => messages.manual_operation_hint,
-        "press_enter_when_done" => messages.press_enter_when_done,
-        "connection_failed" => messages.connection_failed,
-        "connection_successful" => messages.connection_successful,
-        "step" => messages.step,
-        "task" => messages.task,
-        "result" => messages.result,
-        _ => "unknown",
+/// The message in `lang`, else in English, else the key itself, so a missing
+/// translation never shows up as an empty string.
+pub fn get_message<'a>(key: &'a str, lang: &str) -> &'a str {
+    [get_messages(lang), &MESSAGES_EN]
+        .into_iter()
+        .filter_map(|messages| messages.get(key))
+        .find(|message| !message.trim().is_empty())
+        .unwrap_or(key)
+}
+
+/// Fill the `{0}`, `{1}`, ... placeholders of a message with `args`.
+/// Placeholders without an argument are kept.
+///
+/// # Example
+/// ```
+/// use phone_agent::config::{format_message, get_messages};
+///
+/// let text = format_message(get_messages("en").err_app_not_found, &[&"Telegram"]);
+/// assert_eq!(text, "App not found: Telegram");
+/// ```
+pub fn format_message(template: &str, args: &[&dyn std::fmt::Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let arg = rest[start + 1..].find('}').and_then(|len| {
+            let index: usize = rest[start + 1..start + 1 + len].parse().ok()?;
+            Some((args.get(index)?, len))
+        });
+        match arg {
+            Some((arg, len)) => {
+                out.push_str(&arg.to_string());
+                rest = &rest[start + len + 2..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
     }
+    out.push_str(rest);
+    out
 }
 
 #[cfg(test)]
@@ -127,5 +1283,77 @@ mod tests {
     fn test_get_message() {
         assert_eq!(get_message("thinking", "cn"), "思考过程");
         assert_eq!(get_message("thinking", "en"), "Thinking");
+        assert_eq!(get_message("tab_settings", "en"), "⚙️ Settings");
+        assert_eq!(get_message("tab_settings", "cn"), "⚙️ 设置");
+        assert_eq!(get_message("no_such_key", "en"), "no_such_key");
+    }
+
+    #[test]
+    fn test_format_message() {
+        assert_eq!(
+            format_message(MESSAGES_ZH.err_coordinate, &[&"start", &"x"]),
+            "start 坐标错误: x"
+        );
+        assert_eq!(format_message("{0} {1} {", &[&1]), "1 {1} {");
+    }
+
+    #[test]
+    fn test_catalog_complete() {
+        register_language("xx", &HashMap::new());
+        let languages = [("cn", &MESSAGES_ZH), ("en", &MESSAGES_EN)]
+            .into_iter()
+            .chain(
+                registered_languages()
+                    .into_iter()
+                    .map(|language| (language.code, language.messages)),
+            );
+        for (code, messages) in languages {
+            for key in Messages::KEYS {
+                let message = messages.get(key);
+                assert!(
+                    message.is_some_and(|m| !m.trim().is_empty()),
+                    "{} has no text for {}",
+                    code,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_blank_translation_falls_back() {
+        let translations = HashMap::from([("thinking".to_string(), " ".to_string())]);
+        assert!(register_language("xb", &translations).is_empty());
+        assert_eq!(get_message("thinking", "xb"), MESSAGES_EN.thinking);
+    }
+
+    #[test]
+    fn test_register_language() {
+        let translations = HashMap::from([
+            ("language_name".to_string(), "日本語".to_string()),
+            ("thinking".to_string(), "思考".to_string()),
+            ("thinkng".to_string(), "typo".to_string()),
+        ]);
+        assert_eq!(register_language("ja", &translations), vec!["thinkng"]);
+
+        let ja = get_messages("ja");
+        assert_eq!(ja.thinking, "思考");
+        // Untranslated keys fall back to English
+        assert_eq!(ja.action, MESSAGES_EN.action);
+        assert!(registered_languages()
+            .iter()
+            .any(|language| language.code == "ja" && language.name == "日本語"));
+
+        // Chinese variants fall back to Chinese
+        register_language("zh-TW", &HashMap::new());
+        assert_eq!(get_messages("zh-TW").thinking, MESSAGES_ZH.thinking);
+    }
+
+    #[test]
+    fn test_parse_fluent() {
+        let translations =
+            parse_fluent("# Korean\nthinking = 생각\ntask-completed = 작업 완료\n  두 번째 줄\n\n");
+        assert_eq!(translations["thinking"], "생각");
+        assert_eq!(translations["task-completed"], "작업 완료\n두 번째 줄");
     }
 }
@@ -0,0 +1,716 @@
+//! Layered configuration shared by every entry point.
+//!
+//! Each setting comes from the highest layer that sets it: command-line
+//! flags, then environment variables, then the selected planner preset, then
+//! the settings file (or a named settings profile picked with `--profile`),
+//! then the defaults. The CLI and the GUI both build their model and agent
+//! configuration through [`Config`], so a task behaves the same whichever
+//! one starts it.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+
+use crate::actions::{CoordinateSystem, DEFAULT_COORDINATE_SCALE};
+use crate::agent::{AgentConfig, LiveSettings, PlannerProfile};
+use crate::config::{AppRegion, ConfigIssue, PlannerPreset, PromptStyle, Severity};
+use crate::model::ModelConfig;
+use crate::settings::AppSettings;
+
+/// Environment variables and the setting each one sets, applied in order
+/// (so `COORDINATE_SCALE` wins over `COORDINATE_SCALE_X`/`_Y`).
+pub const ENV_VARS: &[(&str, &str)] = &[
+    ("MODEL_BASE_URL", "base_url"),
+    ("MODEL_API_KEY", "api_key"),
+    ("MODEL_NAME", "model_name"),
+    ("ADB_DEVICE_ID", "device_id"),
+    ("AGENT_LANG", "lang"),
+    ("APP_REGION", "app_region"),
+    ("COORDINATE_SYSTEM", "coordinate_system"),
+    ("MODEL_MAX_RETRIES", "max_retries"),
+    ("MODEL_RETRY_DELAY", "retry_delay"),
+    ("MAX_STEPS", "max_steps"),
+    ("PROMPT_STYLE", "prompt_style"),
+    ("PROMPT_VARIANT", "prompt_variant"),
+    ("DISABLED_ACTIONS", "disabled_actions"),
+    ("COORDINATE_SCALE_X", "scale_x"),
+    ("COORDINATE_SCALE_Y", "scale_y"),
+    ("COORDINATE_SCALE", "scale"),
+    ("COORDINATE_OFFSET_X", "offset_x"),
+    ("COORDINATE_OFFSET_Y", "offset_y"),
+    ("ENABLE_CALIBRATION", "enable_calibration"),
+    ("CALIBRATION_MODE", "calibration_mode"),
+    ("CALIBRATION_COMPLEX_ROUNDS", "calibration_rounds"),
+    ("CALIBRATION_CONCURRENCY", "calibration_concurrency"),
+    ("CALIBRATION_SCENES", "calibration_scenes"),
+    ("CALIBRATION_ON_DEVICE", "calibration_on_device"),
+    ("CALIBRATION_ORIENTATION", "calibration_orientation"),
+    ("AUTO_RECALIBRATE", "auto_recalibrate"),
+    ("CALIBRATION_MAX_RESIDUAL", "calibration_max_residual"),
+    ("PLANNER_MODEL_BASE_URL", "planner_base_url"),
+    ("PLANNER_MODEL_API_KEY", "planner_api_key"),
+    ("PLANNER_MODEL_NAME", "planner_model_name"),
+    ("PLANNER_PROFILE", "planner_profile"),
+    ("PLANNER_PRESET", "planner_preset"),
+    (
+        "MAX_EXECUTOR_FEEDBACK_HISTORY",
+        "max_executor_feedback_history",
+    ),
+    ("STUCK_THRESHOLD", "stuck_threshold"),
+    ("SCREEN_SIMILARITY_THRESHOLD", "screen_similarity_threshold"),
+    ("STEP_TIMEOUT_SECS", "step_timeout_secs"),
+    ("EXECUTOR_OVERRIDES", "executor_overrides"),
+    ("PROMPT_MEMORY_PATH", "prompt_memory_path"),
+    (
+        "PROMPT_MEMORY_MAX_TASK_TYPES",
+        "prompt_memory_max_task_types",
+    ),
+    (
+        "PROMPT_MEMORY_MAX_CORRECTIONS",
+        "prompt_memory_max_corrections",
+    ),
+    (
+        "PROMPT_MEMORY_MAX_TRAJECTORIES",
+        "prompt_memory_max_trajectories",
+    ),
+    (
+        "PROMPT_SUCCESS_HALF_LIFE_DAYS",
+        "prompt_success_half_life_days",
+    ),
+    ("MEMORY_SYNC_URL", "memory_sync_url"),
+    ("EPISODIC_MEMORY_PATH", "episodic_memory_path"),
+    ("PLANNER_INTERVAL_MS", "planner_interval_ms"),
+    ("EXECUTOR_INTERVAL_MS", "executor_interval_ms"),
+    ("DUAL_LOOP_MODE", "dual_loop_mode"),
+    ("REQUIRE_APPROVAL", "require_approval"),
+    ("PROPOSE_ONLY", "propose_only"),
+    ("USER_REVIEWS_PROPOSALS", "user_reviews_proposals"),
+    ("EVENT_LOG", "event_log"),
+    ("EVENT_STREAM_ADDR", "event_stream_addr"),
+    ("NOTIFY_WEBHOOK_URL", "notify_webhook_url"),
+    ("NOTIFY_TELEGRAM_BOT_TOKEN", "notify_telegram_bot_token"),
+    ("NOTIFY_TELEGRAM_CHAT_ID", "notify_telegram_chat_id"),
+    ("NOTIFY_BARK_URL", "notify_bark_url"),
+    ("MAX_TOTAL_TOKENS", "max_total_tokens"),
+    ("MAX_COST", "max_cost"),
+    ("PLANNER_PRICE_PER_MILLION", "planner_price_per_million"),
+    ("EXECUTOR_PRICE_PER_MILLION", "executor_price_per_million"),
+];
+
+/// Short command-line flags and the setting each one sets.
+const FLAG_ALIASES: &[(&str, &str)] = &[
+    ("model", "model_name"),
+    ("device", "device_id"),
+    ("coordinates", "coordinate_system"),
+];
+
+/// Layer a setting's value came from, lowest precedence first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigSource {
+    Default,
+    File,
+    /// The selected planner preset (see [`PlannerPreset`]).
+    Preset,
+    Env,
+    Cli,
+}
+
+/// Builds a [`Config`] from its layers.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    base: Option<(AppSettings, ConfigSource)>,
+    profile: Option<String>,
+    env: Vec<(String, String)>,
+    args: Vec<String>,
+}
+
+impl ConfigBuilder {
+    /// Start from the settings file.
+    pub fn with_file(mut self) -> Self {
+        self.base = Some((AppSettings::load(), ConfigSource::File));
+        self
+    }
+
+    /// Start from settings in memory (e.g. edited in the GUI) instead of
+    /// the file.
+    pub fn with_settings(mut self, settings: AppSettings) -> Self {
+        self.base = Some((settings, ConfigSource::File));
+        self
+    }
+
+    /// Start from a named settings profile (see [`AppSettings::load_profile`])
+    /// instead of the settings file. Also set by a `--profile <name>` flag.
+    pub fn with_profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Apply the process environment (see [`ENV_VARS`]).
+    pub fn with_env(self) -> Self {
+        self.with_env_vars(env::vars())
+    }
+
+    /// Apply environment variables from a list instead of the process.
+    pub fn with_env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env.extend(vars);
+        self
+    }
+
+    /// Apply command-line flags: `--<setting> <value>` or `--<setting>=<value>`
+    /// for any setting, with dashes or underscores (`--max-steps 50`), plus
+    /// `--model`, `--device` and `--coordinates`. A yes/no setting given
+    /// without a value is switched on. Other arguments are kept in
+    /// [`Config::args`].
+    pub fn with_args(mut self, args: &[String]) -> Self {
+        self.args = args.to_vec();
+        self
+    }
+
+    /// Merge the layers.
+    pub fn build(self) -> Config {
+        let (settings, base_source) = self
+            .base
+            .unwrap_or_else(|| (AppSettings::default(), ConfigSource::Default));
+        let base_origin = match base_source {
+            ConfigSource::Default => "default",
+            _ => "settings file",
+        };
+        let mut config = Config {
+            base_source,
+            base_origin: base_origin.to_string(),
+            profile: None,
+            settings,
+            sources: BTreeMap::new(),
+            args: Vec::new(),
+            ignored: Vec::new(),
+        };
+
+        let mut args = self.args;
+        let mut planner_profile = None;
+        if let Some(name) = take_profile_flag(&mut args).or(self.profile) {
+            let name = name.trim().to_string();
+            match AppSettings::load_profile(&name) {
+                Ok(profile) => {
+                    config.base_source = ConfigSource::File;
+                    config.base_origin = format!("profile {}", name);
+                    config.profile = Some(name);
+                    config.settings = profile;
+                }
+                // `--profile` used to pick the planner profile
+                Err(_) if config.is_planner_profile(&name) => planner_profile = Some(name),
+                Err(e) => {
+                    let available = AppSettings::list_profiles();
+                    config.ignored.push(ConfigIssue {
+                        severity: Severity::Error,
+                        key: "active_profile".to_string(),
+                        origin: "--profile".to_string(),
+                        message: format!(
+                            "{}; available: {}",
+                            e,
+                            if available.is_empty() {
+                                "none".to_string()
+                            } else {
+                                available.join(", ")
+                            }
+                        ),
+                    });
+                }
+            }
+        }
+
+        let env: BTreeMap<String, String> = self.env.into_iter().collect();
+        for (name, key) in ENV_VARS {
+            if let Some(value) = env.get(*name) {
+                config.set(key, value, ConfigSource::Env, name);
+            }
+        }
+
+        if let Some(name) = planner_profile {
+            config.set("planner_profile", &name, ConfigSource::Cli, "--profile");
+            config.ignored.push(ConfigIssue {
+                severity: Severity::Warning,
+                key: "planner_profile".to_string(),
+                origin: "--profile".to_string(),
+                message: format!(
+                    "\"{}\" is a planner profile; use --planner-profile {} to pick it",
+                    name, name
+                ),
+            });
+        }
+
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let Some((key, inline)) = flag_key(&arg) else {
+                config.args.push(arg);
+                continue;
+            };
+            let value = match inline {
+                Some(value) => value,
+                None if config.is_bool(&key) => match args.peek() {
+                    Some(next) if next.parse::<bool>().is_ok() || next == "0" || next == "1" => {
+                        args.next().unwrap_or_default()
+                    }
+                    _ => "true".to_string(),
+                },
+                None => match args.next() {
+                    Some(value) => value,
+                    None => {
+                        config.ignored.push(ConfigIssue {
+                            severity: Severity::Warning,
+                            key,
+                            origin: arg,
+                            message: "missing value, ignored".to_string(),
+                        });
+                        continue;
+                    }
+                },
+            };
+            let flag = arg.split('=').next().unwrap_or_default();
+            config.set(&key, &value, ConfigSource::Cli, flag);
+        }
+        config.apply_planner_preset();
+        config
+    }
+}
+
+/// Remove `--profile <name>` or `--profile=<name>` from the arguments,
+/// returning the name.
+fn take_profile_flag(args: &mut Vec<String>) -> Option<String> {
+    let pos = args
+        .iter()
+        .position(|arg| arg == "--profile" || arg.starts_with("--profile="))?;
+    let flag = args.remove(pos);
+    match flag.strip_prefix("--profile=") {
+        Some(name) => Some(name.to_string()),
+        None if pos < args.len() => Some(args.remove(pos)),
+        None => None,
+    }
+}
+
+/// Setting named by a command-line flag, with its inline `=value`.
+fn flag_key(arg: &str) -> Option<(String, Option<String>)> {
+    let flag = arg.strip_prefix("--")?;
+    let (name, inline) = match flag.split_once('=') {
+        Some((name, value)) => (name, Some(value.to_string())),
+        None => (flag, None),
+    };
+    let key = name.replace('-', "_");
+    let key = FLAG_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map(|(_, key)| key.to_string())
+        .unwrap_or(key);
+    AppSettings::has_key(&key).then_some((key, inline))
+}
+
+/// Effective settings with the layer each one came from.
+#[derive(Debug, Clone)]
+pub struct Config {
+    base_source: ConfigSource,
+    base_origin: String,
+    profile: Option<String>,
+    settings: AppSettings,
+    sources: BTreeMap<String, (ConfigSource, String)>,
+    args: Vec<String>,
+    ignored: Vec<ConfigIssue>,
+}
+
+impl Config {
+    /// Start building a configuration.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Settings file, environment and command-line flags: what the binaries use.
+    pub fn load(args: &[String]) -> Self {
+        Self::builder()
+            .with_file()
+            .with_env()
+            .with_args(args)
+            .build()
+    }
+
+    /// Name of the settings profile picked with `--profile` in `args`, if any.
+    pub fn profile_arg(args: &[String]) -> Option<String> {
+        take_profile_flag(&mut args.to_vec())
+    }
+
+    /// Name of the settings profile the file layer was loaded from, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// The merged settings.
+    pub fn settings(&self) -> &AppSettings {
+        &self.settings
+    }
+
+    /// The merged settings, by value.
+    pub fn into_settings(self) -> AppSettings {
+        self.settings
+    }
+
+    /// Layer the setting `key` came from.
+    pub fn source(&self, key: &str) -> ConfigSource {
+        self.sources
+            .get(key)
+            .map(|(source, _)| *source)
+            .unwrap_or(self.base_source)
+    }
+
+    /// Where the setting `key` came from, for messages: the environment
+    /// variable or flag that set it, "settings file", "profile <name>" or
+    /// "default".
+    pub fn origin(&self, key: &str) -> String {
+        match self.sources.get(key) {
+            Some((_, origin)) => origin.clone(),
+            None => self.base_origin.clone(),
+        }
+    }
+
+    /// Whether `key` was set by an environment variable or a flag.
+    pub fn is_overridden(&self, key: &str) -> bool {
+        self.source(key) >= ConfigSource::Env
+    }
+
+    /// Command-line arguments that aren't setting flags (subcommands, the
+    /// task, other flags), in order.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Values that couldn't be applied, e.g. `MAX_STEPS=many`. The setting
+    /// keeps its value from the layer below. [`Config::validate`] reports
+    /// these too.
+    pub fn ignored(&self) -> &[ConfigIssue] {
+        &self.ignored
+    }
+
+    /// The configured coordinate system ("absolute"/"abs", otherwise relative).
+    pub fn coordinate_system(&self) -> CoordinateSystem {
+        match self.settings.coordinate_system.to_lowercase().as_str() {
+            "absolute" | "abs" => CoordinateSystem::Absolute,
+            _ => CoordinateSystem::Relative,
+        }
+    }
+
+    /// Executor model configuration.
+    pub fn model_config(&self) -> ModelConfig {
+        let settings = &self.settings;
+        ModelConfig::default()
+            .with_base_url(&settings.base_url)
+            .with_api_key(&settings.api_key)
+            .with_model_name(&settings.model_name)
+            .with_max_retries(settings.max_retries)
+            .with_retry_delay(settings.retry_delay)
+    }
+
+    /// Agent configuration, before any calibration.
+    ///
+    /// Relative coordinates always use scale 1 and no offset. In absolute
+    /// mode a scale of 0, or 1 on both axes, means the default
+    /// [`DEFAULT_COORDINATE_SCALE`].
+    pub fn agent_config(&self) -> AgentConfig {
+        let settings = &self.settings;
+        let coordinate_system = self.coordinate_system();
+        let (scale_x, scale_y, offset_x, offset_y) = match coordinate_system {
+            CoordinateSystem::Relative => (1.0, 1.0, 0.0, 0.0),
+            CoordinateSystem::Absolute => {
+                let unset = settings.scale_x == 1.0 && settings.scale_y == 1.0;
+                let scale = |value: f64| {
+                    if value == 0.0 || unset {
+                        DEFAULT_COORDINATE_SCALE
+                    } else {
+                        value
+                    }
+                };
+                (
+                    scale(settings.scale_x),
+                    scale(settings.scale_y),
+                    settings.offset_x,
+                    settings.offset_y,
+                )
+            }
+        };
+
+        let mut agent_config = AgentConfig::default()
+            .with_lang(&settings.lang)
+            .with_coordinate_system(coordinate_system)
+            .with_scale(scale_x, scale_y)
+            .with_offset(offset_x, offset_y)
+            .with_max_steps(settings.max_steps)
+            .with_prompt_style(PromptStyle::from_name(&settings.prompt_style).unwrap_or_default())
+            .with_app_region(AppRegion::from_name(&settings.app_region).unwrap_or_default())
+            .with_prompt_variant(settings.prompt_variant.clone())
+            .with_disabled_actions(settings.disabled_actions.clone())
+            .with_auto_recalibrate(settings.auto_recalibrate)
+            .with_app_hints(AppSettings::load_app_hints());
+        if !settings.device_id.trim().is_empty() {
+            agent_config = agent_config.with_device_id(settings.device_id.trim());
+        }
+        agent_config
+    }
+
+    /// Replace the file layer with `settings`, e.g. after the settings file
+    /// changed on disk. Values set by environment variables or flags stay.
+    /// Returns the [`LiveSettings`] that changed.
+    pub fn reload_file(&mut self, settings: AppSettings) -> LiveSettings {
+        let previous = self.live_settings();
+        let (Ok(Value::Object(mut merged)), Ok(Value::Object(current))) = (
+            serde_json::to_value(settings),
+            serde_json::to_value(&self.settings),
+        ) else {
+            return LiveSettings::default();
+        };
+        for (key, value) in current {
+            if self.is_overridden(&key) {
+                merged.insert(key, value);
+            }
+        }
+        match serde_json::from_value(Value::Object(merged)) {
+            Ok(settings) => self.settings = settings,
+            Err(e) => tracing::warn!("Failed to reload settings: {}", e),
+        }
+        self.apply_planner_preset();
+        self.live_settings().changes_since(&previous)
+    }
+
+    /// The settings a running session takes over when the settings file
+    /// changes. Scale and offsets are the effective ones, as in
+    /// [`Config::agent_config`].
+    pub fn live_settings(&self) -> LiveSettings {
+        let settings = &self.settings;
+        let agent_config = self.agent_config();
+        let profile = settings.planner_profile(&settings.lang);
+        LiveSettings {
+            scale_x: Some(agent_config.scale_x),
+            scale_y: Some(agent_config.scale_y),
+            offset_x: Some(agent_config.offset_x),
+            offset_y: Some(agent_config.offset_y),
+            planner_interval_ms: Some(settings.planner_interval_ms(profile.as_ref())),
+            executor_interval_ms: Some(settings.executor_interval_ms),
+            stuck_threshold: Some(
+                profile
+                    .as_ref()
+                    .and_then(|p| p.stuck_threshold)
+                    .unwrap_or(settings.stuck_threshold),
+            ),
+            screen_similarity_threshold: Some(settings.screen_similarity_threshold),
+            lang: Some(settings.lang.clone()),
+        }
+    }
+
+    /// Apply one value, recording where it came from or why it was ignored.
+    fn set(&mut self, key: &str, value: &str, source: ConfigSource, origin: &str) {
+        match self.settings.set_value(key, value) {
+            Ok(()) => {
+                let keys = match key {
+                    "scale" => vec!["scale_x", "scale_y"],
+                    key => vec![key],
+                };
+                for key in keys {
+                    self.sources
+                        .insert(key.to_string(), (source, origin.to_string()));
+                }
+            }
+            Err(e) => self.ignored.push(ConfigIssue {
+                severity: Severity::Warning,
+                key: key.to_string(),
+                origin: origin.to_string(),
+                message: format!("{}, ignored", e),
+            }),
+        }
+    }
+
+    /// Apply the selected planner preset to the settings no environment
+    /// variable or flag set. An unknown preset changes nothing;
+    /// [`Config::validate`] reports it.
+    fn apply_planner_preset(&mut self) {
+        self.sources
+            .retain(|_, (source, _)| *source != ConfigSource::Preset);
+        let name = self.settings.planner_preset.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let Some(preset) = PlannerPreset::find(&name, &self.settings.planner_presets) else {
+            return;
+        };
+        let Ok(Value::Object(mut settings)) = serde_json::to_value(&self.settings) else {
+            return;
+        };
+        let origin = format!("preset {}", name);
+        let mut applied = Vec::new();
+        for (key, value) in preset.values() {
+            if settings.contains_key(&key) && !self.is_overridden(&key) {
+                settings.insert(key.clone(), value);
+                applied.push(key);
+            }
+        }
+        match serde_json::from_value(Value::Object(settings)) {
+            Ok(settings) => self.settings = settings,
+            Err(e) => {
+                tracing::warn!("Failed to apply planner preset {}: {}", name, e);
+                return;
+            }
+        }
+        for key in applied {
+            self.sources
+                .insert(key, (ConfigSource::Preset, origin.clone()));
+        }
+    }
+
+    /// Whether `name` is a built-in or configured planner profile.
+    fn is_planner_profile(&self, name: &str) -> bool {
+        let settings = &self.settings;
+        PlannerProfile::find(name, &settings.planner_profiles, &settings.lang).is_some()
+    }
+
+    /// Whether the setting `key` is a yes/no setting.
+    fn is_bool(&self, key: &str) -> bool {
+        serde_json::to_value(&self.settings)
+            .ok()
+            .and_then(|settings| settings.get(key).cloned())
+            .is_some_and(|value| value.is_boolean())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_layer_precedence() {
+        let file = AppSettings {
+            max_steps: 30,
+            model_name: "from-file".to_string(),
+            ..AppSettings::default()
+        };
+        let config = Config::builder()
+            .with_settings(file)
+            .with_env_vars([
+                ("MAX_STEPS".to_string(), "40".to_string()),
+                ("MODEL_NAME".to_string(), "from-env".to_string()),
+                ("MODEL_RETRY_DELAY".to_string(), "soon".to_string()),
+            ])
+            .with_args(&strings(&[
+                "phone-agent",
+                "--max-steps=50",
+                "--event-log",
+                "--coordinates",
+                "absolute",
+                "打开微信",
+            ]))
+            .build();
+
+        let settings = config.settings();
+        assert_eq!(settings.max_steps, 50);
+        assert_eq!(settings.model_name, "from-env");
+        assert!(settings.event_log);
+        assert_eq!(config.coordinate_system(), CoordinateSystem::Absolute);
+        assert_eq!(config.source("max_steps"), ConfigSource::Cli);
+        assert_eq!(config.source("model_name"), ConfigSource::Env);
+        assert_eq!(config.source("lang"), ConfigSource::File);
+        assert_eq!(config.args(), strings(&["phone-agent", "打开微信"]));
+
+        // Bad values are reported and leave the lower layer's value
+        assert_eq!(settings.retry_delay, AppSettings::default().retry_delay);
+        assert_eq!(config.ignored().len(), 1);
+        assert_eq!(config.ignored()[0].origin, "MODEL_RETRY_DELAY");
+        assert_eq!(config.origin("max_steps"), "--max-steps");
+    }
+
+    #[test]
+    fn test_profile_flag() {
+        let mut args = strings(&["phone-agent", "--profile", "work", "打开微信"]);
+        assert_eq!(take_profile_flag(&mut args).as_deref(), Some("work"));
+        assert_eq!(args, strings(&["phone-agent", "打开微信"]));
+        let mut args = strings(&["phone-agent", "--profile=home"]);
+        assert_eq!(take_profile_flag(&mut args).as_deref(), Some("home"));
+        assert_eq!(take_profile_flag(&mut args), None);
+
+        // A planner profile name still picks the planner profile, with a hint
+        let config = Config::builder()
+            .with_args(&strings(&["--profile", "fast"]))
+            .build();
+        assert_eq!(config.settings().planner_profile, "fast");
+        assert_eq!(config.ignored()[0].severity, Severity::Warning);
+
+        let config = Config::builder().with_profile("no such profile").build();
+        assert!(config.ignored()[0].is_error());
+        assert_eq!(config.ignored()[0].origin, "--profile");
+    }
+
+    #[test]
+    fn test_reload_file_keeps_overrides() {
+        let mut config = Config::builder()
+            .with_settings(AppSettings::default())
+            .with_env_vars([("STUCK_THRESHOLD".to_string(), "7".to_string())])
+            .build();
+        let changes = config.reload_file(AppSettings {
+            stuck_threshold: 2,
+            executor_interval_ms: 800,
+            lang: "en".to_string(),
+            ..AppSettings::default()
+        });
+
+        assert_eq!(config.settings().stuck_threshold, 7);
+        assert_eq!(config.settings().lang, "en");
+        assert_eq!(changes.executor_interval_ms, Some(800));
+        assert_eq!(changes.lang.as_deref(), Some("en"));
+        assert_eq!(changes.stuck_threshold, None);
+        assert_eq!(changes.scale_x, None);
+
+        assert!(config.reload_file(config.settings().clone()).is_empty());
+    }
+
+    #[test]
+    fn test_planner_preset() {
+        let settings = AppSettings {
+            planner_preset: "gpt4o-careful".to_string(),
+            planner_interval_ms: 5000,
+            ..AppSettings::default()
+        };
+        let mut config = Config::builder()
+            .with_settings(settings.clone())
+            .with_env_vars([("STUCK_THRESHOLD".to_string(), "7".to_string())])
+            .build();
+        let applied = config.settings();
+        assert_eq!(applied.planner_model_name, "gpt-4o");
+        assert_eq!(applied.planner_profile, "cautious");
+        assert_eq!(applied.planner_interval_ms, 1000);
+        assert_eq!(applied.stuck_threshold, 7);
+        assert_eq!(config.source("planner_interval_ms"), ConfigSource::Preset);
+        assert_eq!(config.origin("planner_interval_ms"), "preset gpt4o-careful");
+        assert_eq!(config.source("stuck_threshold"), ConfigSource::Env);
+        assert_eq!(config.source("lang"), ConfigSource::File);
+
+        // Dropping the preset brings the file's values back
+        let changes = config.reload_file(AppSettings {
+            planner_preset: String::new(),
+            ..settings
+        });
+        assert_eq!(config.settings().planner_interval_ms, 5000);
+        assert_eq!(config.settings().planner_model_name, "deepseek-chat");
+        assert_eq!(config.source("planner_interval_ms"), ConfigSource::File);
+        assert_eq!(changes.planner_interval_ms, Some(5000));
+    }
+
+    #[test]
+    fn test_agent_config_scale() {
+        let config = Config::builder()
+            .with_env_vars([("COORDINATE_SCALE".to_string(), "2".to_string())])
+            .build();
+        // Relative coordinates ignore the scale
+        assert_eq!(config.agent_config().scale_x, 1.0);
+        assert!(config.is_overridden("scale_y"));
+
+        let config = Config::builder()
+            .with_args(&strings(&["--coordinate-system", "absolute"]))
+            .build();
+        assert_eq!(config.agent_config().scale_x, DEFAULT_COORDINATE_SCALE);
+    }
+}
@@ -1,12 +1,26 @@
 //! Configuration module for Phone Agent.
 
+mod app_hints;
 mod apps;
 mod i18n;
+mod layered;
+mod planner_presets;
 mod prompts;
+mod validate;
 
-pub use apps::APP_PACKAGES;
-pub use i18n::{get_message, get_messages, Messages};
+pub use app_hints::AppHints;
+pub use apps::{AppRegion, APP_PACKAGES, INTERNATIONAL_APP_PACKAGES};
+pub use i18n::{
+    format_message, get_message, get_messages, load_language_file, load_languages_dir,
+    register_language, registered_languages, Messages, RegisteredLanguage,
+};
+pub use layered::{Config, ConfigBuilder, ConfigSource, ENV_VARS};
+pub use planner_presets::{PlannerPreset, BUILTIN_PLANNER_PRESETS};
 pub use prompts::{
-    get_system_prompt, get_system_prompt_relative, get_system_prompt_with_resolution,
-    SYSTEM_PROMPT_EN, SYSTEM_PROMPT_EN_RELATIVE, SYSTEM_PROMPT_ZH, SYSTEM_PROMPT_ZH_RELATIVE,
+    executor_prompt, get_concise_system_prompt_relative, get_concise_system_prompt_with_resolution,
+    get_system_prompt, get_system_prompt_relative, get_system_prompt_with_resolution, omit_actions,
+    prompt_text, prompt_variants, prompts_dir, register_prompt_variant, set_prompts_dir,
+    PromptStyle, PromptVars, DEFAULT_PROMPT_VARIANT, SYSTEM_PROMPT_EN, SYSTEM_PROMPT_EN_RELATIVE,
+    SYSTEM_PROMPT_ZH, SYSTEM_PROMPT_ZH_RELATIVE,
 };
+pub use validate::{ConfigIssue, Severity, MAX_COORDINATE_SCALE, MAX_STEPS_LIMIT};
@@ -0,0 +1,157 @@
+//! Named bundles of dual-loop settings.
+//!
+//! A preset sets the planner model, its thresholds, the loop intervals and
+//! the memory files in one go (`planner_preset = "gpt4o-careful"`), so these
+//! don't have to be tuned one by one. [`Config`](super::Config) applies it on
+//! top of the settings file; environment variables and flags still win.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Names of the built-in planner presets.
+pub const BUILTIN_PLANNER_PRESETS: &[&str] = &["deepseek-default", "gpt4o-careful"];
+
+/// A named set of dual-loop settings. Each field is the setting of the same
+/// name; unset fields keep the configured value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlannerPreset {
+    /// Preset name used for selection.
+    pub name: String,
+    /// Short description shown to the user.
+    pub description: String,
+    /// Planner model API base URL (the API key is unchanged).
+    pub planner_base_url: Option<String>,
+    /// Planner model name.
+    pub planner_model_name: Option<String>,
+    /// Planner profile (supervision style).
+    pub planner_profile: Option<String>,
+    /// Maximum number of Executor feedback entries to keep in history.
+    pub max_executor_feedback_history: Option<usize>,
+    /// Consecutive unchanged screens that count as stuck.
+    pub stuck_threshold: Option<u32>,
+    /// Screenshot similarity at or above which the screen counts as unchanged.
+    pub screen_similarity_threshold: Option<f64>,
+    /// Executor step timeout in seconds.
+    pub step_timeout_secs: Option<u64>,
+    /// Planner loop interval in milliseconds.
+    pub planner_interval_ms: Option<u64>,
+    /// Executor loop interval in milliseconds.
+    pub executor_interval_ms: Option<u64>,
+    /// Prompt memory file.
+    pub prompt_memory_path: Option<String>,
+    /// Episodic memory file.
+    pub episodic_memory_path: Option<String>,
+}
+
+impl PlannerPreset {
+    /// Get a built-in preset by name.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let preset = match name {
+            "deepseek-default" => Self {
+                description: "DeepSeek planner with the default thresholds and intervals"
+                    .to_string(),
+                planner_base_url: Some("https://api.deepseek.com/v1".to_string()),
+                planner_model_name: Some("deepseek-chat".to_string()),
+                max_executor_feedback_history: Some(2),
+                stuck_threshold: Some(3),
+                screen_similarity_threshold: Some(0.95),
+                step_timeout_secs: Some(120),
+                planner_interval_ms: Some(2000),
+                executor_interval_ms: Some(500),
+                prompt_memory_path: Some("prompt_memory.json".to_string()),
+                episodic_memory_path: Some("episodic_memory.json".to_string()),
+                ..Self::default()
+            },
+            "gpt4o-careful" => Self {
+                description: "GPT-4o planner that checks often and steps in early, \
+                              with its own memory files"
+                    .to_string(),
+                planner_base_url: Some("https://api.openai.com/v1".to_string()),
+                planner_model_name: Some("gpt-4o".to_string()),
+                planner_profile: Some("cautious".to_string()),
+                max_executor_feedback_history: Some(4),
+                stuck_threshold: Some(2),
+                screen_similarity_threshold: Some(0.97),
+                step_timeout_secs: Some(180),
+                planner_interval_ms: Some(1000),
+                executor_interval_ms: Some(800),
+                prompt_memory_path: Some("prompt_memory_gpt4o.json".to_string()),
+                episodic_memory_path: Some("episodic_memory_gpt4o.json".to_string()),
+                ..Self::default()
+            },
+            _ => return None,
+        };
+        Some(Self {
+            name: name.to_string(),
+            ..preset
+        })
+    }
+
+    /// Find a preset by name. User-defined presets take precedence over the
+    /// built-in ones with the same name.
+    pub fn find(name: &str, custom: &[PlannerPreset]) -> Option<Self> {
+        custom
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .or_else(|| Self::builtin(name))
+    }
+
+    /// Names of all available presets (built-in first).
+    pub fn available(custom: &[PlannerPreset]) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_PLANNER_PRESETS
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        for preset in custom {
+            if !names.contains(&preset.name) {
+                names.push(preset.name.clone());
+            }
+        }
+        names
+    }
+
+    /// The settings this preset sets, by setting name.
+    pub fn values(&self) -> Map<String, Value> {
+        let Ok(Value::Object(mut values)) = serde_json::to_value(self) else {
+            return Map::new();
+        };
+        values.remove("name");
+        values.remove("description");
+        values.retain(|_, value| !value.is_null());
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::AppSettings;
+
+    #[test]
+    fn test_builtin_presets() {
+        for name in BUILTIN_PLANNER_PRESETS {
+            let preset = PlannerPreset::builtin(name).unwrap();
+            assert_eq!(preset.name, *name);
+            // Every value names a setting
+            for key in preset.values().keys() {
+                assert!(AppSettings::has_key(key), "{}: {}", name, key);
+            }
+        }
+        assert!(PlannerPreset::builtin("unknown").is_none());
+
+        let custom = PlannerPreset {
+            name: "gpt4o-careful".to_string(),
+            stuck_threshold: Some(5),
+            ..PlannerPreset::default()
+        };
+        let found = PlannerPreset::find("gpt4o-careful", std::slice::from_ref(&custom));
+        assert_eq!(found, Some(custom.clone()));
+        assert_eq!(custom.values().len(), 1);
+        assert_eq!(
+            PlannerPreset::available(&[custom]),
+            ["deepseek-default", "gpt4o-careful"]
+        );
+    }
+}
@@ -1,9 +1,176 @@
 //! System prompts for the AI agent.
-
+//!
+//! Every built-in prompt can be overridden by a file in the prompts directory
+//! (see [`set_prompts_dir`]), named after the prompt: `system_prompt_zh.md`,
+//! `system_prompt_en_relative.md`, `planner_system_prompt_cn.md`, ... Files
+//! are checked for changes each time a prompt is built, so edits apply to the
+//! next task without a rebuild or restart.
+//!
+//! Named prompt variants ("v2", "experimental", ...) can replace any executor
+//! prompt, so versions can be compared side by side: files go in
+//! `variants/<variant>/` of the prompts directory, or are registered with
+//! [`register_prompt_variant`]. See [`executor_prompt`].
+//!
+//! Prompts are templates: `{{date}}`, `{{lang}}`, `{{screen_width}}`,
+//! `{{screen_height}}` and `{{current_app}}` are replaced when the message is
+//! built (see [`PromptVars`]). Unknown placeholders are left as they are.
+
+use crate::actions::is_action_disabled;
 use chrono::{Datelike, Local};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
+
+/// Directory prompt overrides are read from; `PROMPTS_DIR` by default.
+static PROMPTS_DIR: Lazy<RwLock<Option<PathBuf>>> =
+    Lazy::new(|| RwLock::new(env::var_os("PROMPTS_DIR").map(PathBuf::from)));
+
+/// Override files read so far, with their modification time.
+static OVERRIDES: Lazy<Mutex<HashMap<PathBuf, (SystemTime, String)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Set the directory prompt overrides are read from (`None` = built-in
+/// prompts only).
+pub fn set_prompts_dir(dir: Option<PathBuf>) {
+    *PROMPTS_DIR.write().unwrap() = dir;
+}
 
-/// Get the Chinese system prompt with current date and screen resolution.
-pub fn get_system_prompt_zh_with_resolution(width: u32, height: u32) -> String {
+/// Directory prompt overrides are read from, if any.
+pub fn prompts_dir() -> Option<PathBuf> {
+    PROMPTS_DIR.read().unwrap().clone()
+}
+
+/// Text of the prompt called `name`: the content of `<name>.md` in the
+/// prompts directory if it exists and isn't blank, otherwise `default`.
+/// A changed file is read again.
+pub fn prompt_text(name: &str, default: &str) -> String {
+    prompts_dir()
+        .and_then(|dir| read_override(&dir.join(format!("{}.md", name))))
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Content of an override file, re-read only when its modification time changes.
+fn read_override(path: &Path) -> Option<String> {
+    let mut overrides = OVERRIDES.lock().unwrap();
+    let Ok(modified) = fs::metadata(path).and_then(|meta| meta.modified()) else {
+        overrides.remove(path);
+        return None;
+    };
+    if let Some((cached_at, text)) = overrides.get(path) {
+        if *cached_at == modified {
+            return Some(text.clone()).filter(|text| !text.trim().is_empty());
+        }
+    }
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::warn!("Failed to read prompt {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    tracing::info!("Loaded prompt override {}", path.display());
+    overrides.insert(path.to_path_buf(), (modified, text.clone()));
+    Some(text).filter(|text| !text.trim().is_empty())
+}
+
+/// How much instruction the executor's system prompt carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptStyle {
+    /// The full prompt with every action explained and all the rules.
+    #[default]
+    Full,
+    /// A short prompt for small local models with limited context: the
+    /// output format, one line per action and a few core rules.
+    Concise,
+}
+
+impl PromptStyle {
+    /// Name used in settings and on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptStyle::Full => "full",
+            PromptStyle::Concise => "concise",
+        }
+    }
+
+    /// Parse a name as accepted by [`PromptStyle::as_str`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "full" | "verbose" => Some(PromptStyle::Full),
+            "concise" | "short" => Some(PromptStyle::Concise),
+            _ => None,
+        }
+    }
+}
+
+/// Values for the `{{name}}` placeholders of a prompt template.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptVars {
+    values: BTreeMap<String, String>,
+}
+
+impl PromptVars {
+    /// Variables every prompt can use: `date` (today, in the language of
+    /// `lang`) and `lang`.
+    pub fn new(lang: &str) -> Self {
+        let date = if lang == "en" { today_en() } else { today_zh() };
+        Self::default().with("date", date).with("lang", lang)
+    }
+
+    /// Add `screen_width` and `screen_height`.
+    pub fn with_screen(self, width: u32, height: u32) -> Self {
+        self.with("screen_width", width.to_string())
+            .with("screen_height", height.to_string())
+    }
+
+    /// Add `current_app`, the app in the foreground.
+    pub fn with_current_app(self, app: &str) -> Self {
+        self.with("current_app", app)
+    }
+
+    /// Add (or replace) a variable.
+    pub fn with(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.values.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Value of a variable.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Replace the `{{name}}` placeholders of `template` whose variable is
+    /// set. Others are kept, so a template can be rendered again once more
+    /// variables are known.
+    pub fn render(&self, template: &str) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let placeholder = &rest[start..start + 2 + len + 2];
+            out.push_str(&rest[..start]);
+            match self.get(rest[start + 2..start + 2 + len].trim()) {
+                Some(value) => out.push_str(value),
+                None => out.push_str(placeholder),
+            }
+            rest = &rest[start + placeholder.len()..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Today's date in Chinese, e.g. "2024年05月01日 星期三".
+fn today_zh() -> String {
     let today = Local::now();
     let weekday_names = [
         "星期一",
@@ -15,28 +182,169 @@ pub fn get_system_prompt_zh_with_resolution(width: u32, height: u32) -> String {
         "星期日",
     ];
     let weekday = weekday_names[today.weekday().num_days_from_monday() as usize];
-    let formatted_date = format!(
+    format!(
         "{}年{}月{}日 {}",
         today.format("%Y"),
         today.format("%m"),
         today.format("%d"),
         weekday
+    )
+}
+
+/// Today's date in English, e.g. "May 01, 2024".
+fn today_en() -> String {
+    Local::now().format("%B %d, %Y").to_string()
+}
+
+/// Header of the executor prompts for absolute coordinates.
+const HEADER_ZH: &str =
+    "今天的日期是: {{date}}\n当前屏幕分辨率: {{screen_width}}x{{screen_height}} (宽x高)\n";
+const HEADER_EN: &str = "Today's date is: {{date}}\nCurrent screen resolution: {{screen_width}}x{{screen_height}} (width x height)\n";
+
+/// Header of the executor prompts for relative coordinates.
+const HEADER_ZH_RELATIVE: &str = "今天的日期是: {{date}}\n";
+const HEADER_EN_RELATIVE: &str = "Today's date is: {{date}}\n";
+
+/// Name of the built-in executor prompts as a variant.
+pub const DEFAULT_PROMPT_VARIANT: &str = "default";
+
+/// Executor prompt texts registered in code, by variant and prompt name.
+static VARIANTS: Lazy<RwLock<BTreeMap<String, HashMap<String, String>>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// Register (or replace) a named executor prompt variant, e.g. "v2" or
+/// "experimental". `prompts` maps prompt names as used for override files
+/// ("system_prompt_zh", "system_prompt_en_relative_concise", ...) to their
+/// text. Prompts a variant doesn't define fall back to the default ones.
+pub fn register_prompt_variant(name: &str, prompts: HashMap<String, String>) {
+    VARIANTS
+        .write()
+        .unwrap()
+        .insert(name.trim().to_string(), prompts);
+}
+
+/// Names of the available prompt variants: the default one, those
+/// registered in code and the folders under `variants` in the prompts
+/// directory. Sorted, with the default first.
+pub fn prompt_variants() -> Vec<String> {
+    let mut names: Vec<String> = VARIANTS.read().unwrap().keys().cloned().collect();
+    if let Some(entries) = prompts_dir().and_then(|dir| fs::read_dir(dir.join("variants")).ok()) {
+        names.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned()),
+        );
+    }
+    names.retain(|name| name != DEFAULT_PROMPT_VARIANT);
+    names.sort();
+    names.dedup();
+    names.insert(0, DEFAULT_PROMPT_VARIANT.to_string());
+    names
+}
+
+/// Text of the prompt called `name` in a variant: the variant's file
+/// `variants/<variant>/<name>.md` in the prompts directory, then the text
+/// registered in code, then [`prompt_text`].
+fn variant_prompt_text(variant: &str, name: &str, default: &str) -> String {
+    let variant = variant.trim();
+    if variant.is_empty()
+        || variant == DEFAULT_PROMPT_VARIANT
+        || variant.starts_with('.')
+        || variant.contains(['/', '\\'])
+    {
+        return prompt_text(name, default);
+    }
+    prompts_dir()
+        .and_then(|dir| {
+            read_override(
+                &dir.join("variants")
+                    .join(variant)
+                    .join(format!("{}.md", name)),
+            )
+        })
+        .or_else(|| {
+            VARIANTS
+                .read()
+                .unwrap()
+                .get(variant)
+                .and_then(|prompts| prompts.get(name))
+                .filter(|text| !text.trim().is_empty())
+                .cloned()
+        })
+        .unwrap_or_else(|| prompt_text(name, default))
+}
+
+/// Executor system prompt of a prompt variant.
+///
+/// # Arguments
+/// * `variant` - Variant name (see [`prompt_variants`]); unknown names get
+///   the default prompts.
+/// * `lang` - "en" for English, anything else for Chinese.
+/// * `style` - Full or concise prompt.
+/// * `screen` - Screen width and height for absolute coordinates, `None` for
+///   relative coordinates (0-999).
+pub fn executor_prompt(
+    variant: &str,
+    lang: &str,
+    style: PromptStyle,
+    screen: Option<(u32, u32)>,
+) -> String {
+    let en = lang == "en";
+    let mut vars = PromptVars::new(if en { "en" } else { "cn" });
+    let header = match (screen, en) {
+        (Some((width, height)), _) => {
+            vars = vars.with_screen(width, height);
+            if en {
+                HEADER_EN
+            } else {
+                HEADER_ZH
+            }
+        }
+        (None, true) => HEADER_EN_RELATIVE,
+        (None, false) => HEADER_ZH_RELATIVE,
+    };
+    let name = format!(
+        "system_prompt_{}{}{}",
+        if en { "en" } else { "zh" },
+        if screen.is_none() { "_relative" } else { "" },
+        if style == PromptStyle::Concise {
+            "_concise"
+        } else {
+            ""
+        }
     );
+    let default = match (style, screen.is_some(), en) {
+        (PromptStyle::Concise, absolute, _) => concise_prompt(lang, !absolute),
+        (PromptStyle::Full, true, true) => SYSTEM_PROMPT_EN.to_string(),
+        (PromptStyle::Full, true, false) => SYSTEM_PROMPT_ZH.to_string(),
+        (PromptStyle::Full, false, true) => SYSTEM_PROMPT_EN_RELATIVE.to_string(),
+        (PromptStyle::Full, false, false) => SYSTEM_PROMPT_ZH_RELATIVE.to_string(),
+    };
+    vars.render(&format!(
+        "{}{}",
+        header,
+        variant_prompt_text(variant, &name, &default)
+    ))
+}
 
-    format!(
-        "今天的日期是: {}\n当前屏幕分辨率: {}x{} (宽x高)\n{}",
-        formatted_date, width, height, SYSTEM_PROMPT_ZH
+/// Get the Chinese system prompt with current date and screen resolution.
+pub fn get_system_prompt_zh_with_resolution(width: u32, height: u32) -> String {
+    executor_prompt(
+        DEFAULT_PROMPT_VARIANT,
+        "cn",
+        PromptStyle::Full,
+        Some((width, height)),
     )
 }
 
 /// Get the English system prompt with current date and screen resolution.
 pub fn get_system_prompt_en_with_resolution(width: u32, height: u32) -> String {
-    let today = Local::now();
-    let formatted_date = today.format("%B %d, %Y").to_string();
-
-    format!(
-        "Today's date is: {}\nCurrent screen resolution: {}x{} (width x height)\n{}",
-        formatted_date, width, height, SYSTEM_PROMPT_EN
+    executor_prompt(
+        DEFAULT_PROMPT_VARIANT,
+        "en",
+        PromptStyle::Full,
+        Some((width, height)),
     )
 }
 
@@ -69,41 +377,13 @@ pub fn get_system_prompt(lang: &str) -> String {
 /// Get the Chinese system prompt with relative coordinates (0-999 range).
 /// This is the original AutoGLM-Phone coordinate system.
 pub fn get_system_prompt_zh_relative() -> String {
-    let today = Local::now();
-    let weekday_names = [
-        "星期一",
-        "星期二",
-        "星期三",
-        "星期四",
-        "星期五",
-        "星期六",
-        "星期日",
-    ];
-    let weekday = weekday_names[today.weekday().num_days_from_monday() as usize];
-    let formatted_date = format!(
-        "{}年{}月{}日 {}",
-        today.format("%Y"),
-        today.format("%m"),
-        today.format("%d"),
-        weekday
-    );
-
-    format!(
-        "今天的日期是: {}\n{}",
-        formatted_date, SYSTEM_PROMPT_ZH_RELATIVE
-    )
+    executor_prompt(DEFAULT_PROMPT_VARIANT, "cn", PromptStyle::Full, None)
 }
 
 /// Get the English system prompt with relative coordinates (0-999 range).
 /// This is the original AutoGLM-Phone coordinate system.
 pub fn get_system_prompt_en_relative() -> String {
-    let today = Local::now();
-    let formatted_date = today.format("%B %d, %Y").to_string();
-
-    format!(
-        "Today's date is: {}\n{}",
-        formatted_date, SYSTEM_PROMPT_EN_RELATIVE
-    )
+    executor_prompt(DEFAULT_PROMPT_VARIANT, "en", PromptStyle::Full, None)
 }
 
 /// Get the system prompt with relative coordinates by language.
@@ -114,6 +394,122 @@ pub fn get_system_prompt_relative(lang: &str) -> String {
     }
 }
 
+/// Get the concise system prompt with current date and screen resolution
+/// (for absolute coordinate system).
+pub fn get_concise_system_prompt_with_resolution(lang: &str, width: u32, height: u32) -> String {
+    executor_prompt(
+        DEFAULT_PROMPT_VARIANT,
+        lang,
+        PromptStyle::Concise,
+        Some((width, height)),
+    )
+}
+
+/// Get the concise system prompt with relative coordinates (0-999 range).
+pub fn get_concise_system_prompt_relative(lang: &str) -> String {
+    executor_prompt(DEFAULT_PROMPT_VARIANT, lang, PromptStyle::Concise, None)
+}
+
+/// Remove the disabled actions (see [`is_action_disabled`]) from the action
+/// list of a prompt: each `- do(action="...")` line and the indented lines
+/// explaining it.
+pub fn omit_actions(prompt: &str, disabled: &[String]) -> String {
+    if disabled.is_empty() {
+        return prompt.to_string();
+    }
+    let mut out = Vec::new();
+    let mut skipping = false;
+    for line in prompt.lines() {
+        if skipping && line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+            continue;
+        }
+        skipping = line
+            .strip_prefix("- do(action=\"")
+            .and_then(|rest| rest.split('"').next())
+            .is_some_and(|action| is_action_disabled(action, disabled));
+        if !skipping {
+            out.push(line);
+        }
+    }
+    let mut text = out.join("\n");
+    if prompt.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+/// Build the concise prompt body: output format, coordinates, one line per
+/// action and the rules small models most often break.
+fn concise_prompt(lang: &str, relative: bool) -> String {
+    if lang == "en" {
+        let coordinates = if relative {
+            "relative coordinates, X and Y in [0, 999]; (0,0) is the top-left corner and (999,999) the bottom-right"
+        } else {
+            "absolute pixel coordinates from the top-left corner; X in [0, screen width], Y in [0, screen height]"
+        };
+        format!(
+            r#"You operate an Android phone step by step from screenshots to complete the task.
+Output exactly:
+<think>short reason</think>
+<answer>action</answer>
+
+Coordinates: {coordinates}.
+Actions:
+- do(action="Launch", app="name")
+- do(action="Tap", element=[x,y]) (add message="reason" for payments or other sensitive taps)
+- do(action="Type", text="text") (tap the input first; existing text is cleared)
+- do(action="Swipe", start=[x1,y1], end=[x2,y2])
+- do(action="Long Press", element=[x,y])
+- do(action="Double Tap", element=[x,y])
+- do(action="Back")
+- do(action="Home")
+- do(action="Wait", duration="2 seconds")
+- do(action="Take_over", message="reason") (login or verification needs the user)
+- finish(message="result") (add data={{"key": "value"}} to return requested values)
+
+Rules:
+1. Launch the target app first if it isn't open.
+2. If an action had no effect, try another position; don't repeat it.
+3. Start swipes in the middle of the content, away from fixed bars.
+4. Check the result is correct and complete before finish.
+"#
+        )
+    } else {
+        let coordinates = if relative {
+            "相对坐标，X 和 Y 都在 [0, 999] 范围内，(0,0) 为左上角，(999,999) 为右下角"
+        } else {
+            "绝对像素坐标，原点在左上角，X 在 [0, 屏幕宽度] 范围内，Y 在 [0, 屏幕高度] 范围内"
+        };
+        format!(
+            r#"你根据截图一步步操作安卓手机来完成任务。
+严格按以下格式输出：
+<think>简短理由</think>
+<answer>操作</answer>
+
+坐标：{coordinates}。
+操作：
+- do(action="Launch", app="应用名")
+- do(action="Tap", element=[x,y])（支付等敏感点击加 message="原因"）
+- do(action="Type", text="文本")（先点击输入框，原有内容会被清空）
+- do(action="Swipe", start=[x1,y1], end=[x2,y2])
+- do(action="Long Press", element=[x,y])
+- do(action="Double Tap", element=[x,y])
+- do(action="Back")
+- do(action="Home")
+- do(action="Wait", duration="2 seconds")
+- do(action="Take_over", message="原因")（需要用户登录或验证时）
+- finish(message="结果")（需要返回信息时加 data={{"key": "value"}}）
+
+规则：
+1. 当前不是目标应用时先 Launch。
+2. 操作没有生效就换个位置重试，不要重复同一操作。
+3. 滑动起点放在屏幕中部的内容区，避开固定栏。
+4. 结束前检查任务是否正确完整地完成。
+"#
+        )
+    }
+}
+
 /// Chinese system prompt (without date header)
 pub static SYSTEM_PROMPT_ZH: &str = r#"你是一个智能体分析专家，可以根据操作历史和当前状态图执行一系列操作来完成任务。
 你必须严格按照要求输出以下格式：
@@ -173,6 +569,8 @@ pub static SYSTEM_PROMPT_ZH: &str = r#"你是一个智能体分析专家，可
     等待页面加载，x为需要等待多少秒。
 - finish(message="xxx")  
     finish是结束任务的操作，表示准确完整完成任务，message是终止信息。 
+- finish(message="xxx", data={"key": "value"})  
+    如果任务要求获取信息（如订单号、验证码、价格），用 data 以 JSON 对象返回这些值。
 
 必须遵循的规则：
 1. 在执行任何操作前，先检查当前app是否是目标app，如果不是，先执行 Launch。
@@ -259,6 +657,8 @@ Operation instructions and their functions are as follows:
     Wait for page to load, x is the number of seconds to wait.
 - finish(message="xxx")  
     finish ends the task, indicating accurate and complete task completion, message is the termination information.
+- finish(message="xxx", data={"key": "value"})  
+    If the task asks you to obtain information (an order number, a captcha answer, a price), return the values as a JSON object in data.
 
 Rules that must be followed:
 1. Before executing any operation, first check if the current app is the target app. If not, execute Launch first.
@@ -496,4 +896,124 @@ mod tests {
         assert!(en.contains("relative coordinates"));
         assert!(en.contains("[0, 999]"));
     }
+
+    #[test]
+    fn test_concise_prompts() {
+        let full = get_system_prompt_with_resolution("cn", 1080, 1920);
+        let zh = get_concise_system_prompt_with_resolution("cn", 1080, 1920);
+        assert!(zh.contains("当前屏幕分辨率: 1080x1920"));
+        assert!(zh.contains("绝对像素坐标"));
+        assert!(zh.len() * 3 < full.len());
+
+        let en = get_concise_system_prompt_relative("en");
+        assert!(en.contains("Today's date is"));
+        assert!(en.contains("[0, 999]"));
+        assert!(en.contains(r#"data={"key": "value"}"#));
+
+        assert_eq!(
+            PromptStyle::from_name("Concise"),
+            Some(PromptStyle::Concise)
+        );
+        assert_eq!(
+            PromptStyle::from_name(PromptStyle::Full.as_str()),
+            Some(PromptStyle::Full)
+        );
+        assert_eq!(PromptStyle::from_name("tiny"), None);
+    }
+
+    #[test]
+    fn test_omit_actions() {
+        let disabled = vec!["Type".to_string(), "Launch".to_string()];
+        for prompt in [
+            get_system_prompt_with_resolution("cn", 1080, 1920),
+            get_system_prompt_relative("en"),
+            get_concise_system_prompt_relative("en"),
+        ] {
+            let omitted = omit_actions(&prompt, &disabled);
+            assert!(!omitted.contains(r#"do(action="Type""#));
+            assert!(!omitted.contains(r#"do(action="Launch""#));
+            assert!(omitted.contains(r#"do(action="Tap""#));
+            assert!(omitted.contains("finish(message="));
+        }
+        // Explanations of removed actions go with them
+        let zh = omit_actions(&get_system_prompt("cn"), &disabled);
+        assert!(!zh.contains("Type_Name是输入人名的操作"));
+        assert!(zh.contains("Tap是点击操作"));
+    }
+
+    #[test]
+    fn test_prompt_variants() {
+        register_prompt_variant(
+            "test-v2",
+            HashMap::from([(
+                "system_prompt_en_relative".to_string(),
+                "V2 prompt".to_string(),
+            )]),
+        );
+        let v2 = executor_prompt("test-v2", "en", PromptStyle::Full, None);
+        assert!(v2.starts_with("Today's date is"));
+        assert!(v2.ends_with("V2 prompt"));
+
+        // Prompts the variant doesn't define, and unknown variants, are the defaults
+        assert_eq!(
+            executor_prompt("test-v2", "cn", PromptStyle::Full, None),
+            get_system_prompt_relative("cn")
+        );
+        assert_eq!(
+            executor_prompt("nope", "en", PromptStyle::Full, Some((1080, 1920))),
+            get_system_prompt("en")
+        );
+
+        let variants = prompt_variants();
+        assert_eq!(variants[0], DEFAULT_PROMPT_VARIANT);
+        assert!(variants.contains(&"test-v2".to_string()));
+    }
+
+    #[test]
+    fn test_prompt_vars() {
+        let vars = PromptVars::new("en")
+            .with_screen(1080, 2400)
+            .with_current_app("Chrome");
+        assert_eq!(
+            vars.render("{{ current_app }} at {{screen_width}}x{{screen_height}}, {{unknown}}"),
+            "Chrome at 1080x2400, {{unknown}}"
+        );
+        assert_eq!(vars.render("{{lang}} {{"), "en {{");
+        assert!(!vars.render("{{date}}").contains("{{"));
+    }
+
+    #[test]
+    fn test_prompt_override() {
+        let dir = std::env::temp_dir().join(format!("prompts-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test_prompt.md");
+
+        // Read directly so other tests don't see this directory
+        assert_eq!(read_override(&path), None);
+        fs::write(&path, "first").unwrap();
+        assert_eq!(read_override(&path).as_deref(), Some("first"));
+
+        // A changed file is read again
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        fs::write(&path, "second").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(read_override(&path).as_deref(), Some("second"));
+
+        // Blank files don't override anything
+        fs::write(&path, "  \n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(read_override(&path), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,417 @@
+//! Checks of the merged configuration.
+//!
+//! [`Config::validate`] looks at every setting at once, so all problems can
+//! be reported when a binary starts instead of surfacing one by one halfway
+//! through a task.
+
+use std::fmt;
+use std::net::IpAddr;
+
+use reqwest::Url;
+
+use crate::actions::DEFAULT_COORDINATE_SCALE;
+use crate::agent::event_stream_bind_addr;
+use crate::calibration::{CalibrationMode, ComplexScene, Orientation};
+use crate::config::{prompt_variants, AppRegion, Config, PlannerPreset, PromptStyle, ENV_VARS};
+use crate::keychain::KEYCHAIN_MARKER;
+use crate::settings::{AppSettings, FieldError};
+
+/// Largest accepted `max_steps`.
+pub const MAX_STEPS_LIMIT: u32 = 1000;
+
+/// Largest accepted absolute-coordinate scale factor.
+pub const MAX_COORDINATE_SCALE: f64 = 10.0;
+
+/// How serious a [`ConfigIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The setting is used anyway (or ignored); the run can go ahead.
+    Warning,
+    /// A run with this setting would fail or misbehave.
+    Error,
+}
+
+/// A problem with one setting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    /// Setting name, e.g. "max_steps".
+    pub key: String,
+    /// Where the value came from: an environment variable, a flag,
+    /// "settings file" or "default".
+    pub origin: String,
+    /// What is wrong and how to fix it.
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (from {}): {}", self.key, self.origin, self.message)
+    }
+}
+
+impl Config {
+    /// Check the merged settings and return every problem found, including
+    /// values that couldn't be read ([`Config::ignored`]). An empty list
+    /// means the configuration is fine.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let settings = self.settings();
+        let mut issues = self.ignored().to_vec();
+        let mut report = |severity: Severity, key: &str, message: String| {
+            issues.push(ConfigIssue {
+                severity,
+                key: key.to_string(),
+                origin: self.origin(key),
+                message,
+            })
+        };
+
+        // Endpoints
+        let mut endpoints = vec![(&settings.base_url, "api_key", &settings.api_key)];
+        if settings.dual_loop_mode {
+            endpoints.push((
+                &settings.planner_base_url,
+                "planner_api_key",
+                &settings.planner_api_key,
+            ));
+        }
+        for (url, api_key_key, api_key) in endpoints {
+            if api_key == KEYCHAIN_MARKER {
+                report(
+                    Severity::Error,
+                    api_key_key,
+                    "couldn't be read from the system keychain; unlock it or enter the key again"
+                        .to_string(),
+                );
+                continue;
+            }
+            // A malformed URL is reported by `check_values`
+            match parse_url(url) {
+                Ok(url) if !is_local(&url) && is_blank_key(api_key) => report(
+                    Severity::Error,
+                    api_key_key,
+                    format!(
+                        "{} needs an API key; {}",
+                        url.host_str().unwrap_or_default(),
+                        how_to_set(api_key_key)
+                    ),
+                ),
+                _ => {}
+            }
+        }
+
+        for key in settings.unknown.keys() {
+            report(
+                Severity::Warning,
+                key,
+                "unknown setting, kept but not used; check the spelling".to_string(),
+            );
+        }
+
+        check_values(settings, &mut report);
+        issues
+    }
+}
+
+impl AppSettings {
+    /// Values that are wrong wherever they are used: malformed URLs, unknown
+    /// names and numbers out of range. [`AppSettings::save`] refuses
+    /// settings with any of them.
+    pub fn invalid_fields(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_values(self, &mut |severity, key, message| {
+            if severity == Severity::Error {
+                errors.push(FieldError::new(key, message));
+            }
+        });
+        errors
+    }
+}
+
+/// Report the problems of the values themselves, leaving out those that
+/// depend on the environment (API keys, the keychain).
+fn check_values(settings: &AppSettings, report: &mut dyn FnMut(Severity, &str, String)) {
+    let mut urls = vec![("base_url", &settings.base_url)];
+    if settings.dual_loop_mode {
+        urls.push(("planner_base_url", &settings.planner_base_url));
+    }
+    for (key, url) in urls {
+        if let Err(e) = parse_url(url) {
+            report(Severity::Error, key, e);
+        }
+    }
+    for (key, url) in [
+        ("memory_sync_url", &settings.memory_sync_url),
+        ("notify_webhook_url", &settings.notify_webhook_url),
+        ("notify_bark_url", &settings.notify_bark_url),
+    ] {
+        if !url.trim().is_empty() {
+            if let Err(e) = parse_url(url) {
+                report(Severity::Error, key, e);
+            }
+        }
+    }
+
+    let event_stream_addr = settings.event_stream_addr.trim();
+    if !event_stream_addr.is_empty() {
+        let port = event_stream_bind_addr(event_stream_addr)
+            .rsplit_once(':')
+            .and_then(|(host, port)| (!host.is_empty()).then_some(port))
+            .and_then(|port| port.parse::<u16>().ok());
+        if port.is_none() {
+            report(
+                Severity::Error,
+                "event_stream_addr",
+                format!(
+                    "\"{}\" is not a port or host:port address, e.g. 8765 or 127.0.0.1:8765",
+                    settings.event_stream_addr
+                ),
+            );
+        }
+    }
+
+    // Named choices
+    let coordinate_system = settings.coordinate_system.trim().to_lowercase();
+    if !matches!(coordinate_system.as_str(), "relative" | "absolute" | "abs") {
+        report(
+            Severity::Error,
+            "coordinate_system",
+            unknown(&settings.coordinate_system, "relative, absolute"),
+        );
+    }
+    if PromptStyle::from_name(&settings.prompt_style).is_none() {
+        report(
+            Severity::Error,
+            "prompt_style",
+            unknown(&settings.prompt_style, "full, concise"),
+        );
+    }
+    let variants = prompt_variants();
+    if !variants.contains(&settings.prompt_variant) {
+        report(
+            Severity::Error,
+            "prompt_variant",
+            unknown(&settings.prompt_variant, &variants.join(", ")),
+        );
+    }
+    if AppRegion::from_name(&settings.app_region).is_none() {
+        report(
+            Severity::Error,
+            "app_region",
+            unknown(&settings.app_region, "china, international"),
+        );
+    }
+    if CalibrationMode::from_name(&settings.calibration_mode).is_none() {
+        report(
+            Severity::Error,
+            "calibration_mode",
+            unknown(&settings.calibration_mode, "simple, complex, grid"),
+        );
+    }
+    let preset = settings.planner_preset.trim();
+    if !preset.is_empty() && PlannerPreset::find(preset, &settings.planner_presets).is_none() {
+        report(
+            Severity::Error,
+            "planner_preset",
+            unknown(
+                &settings.planner_preset,
+                &PlannerPreset::available(&settings.planner_presets).join(", "),
+            ),
+        );
+    }
+    if let Err(e) = ComplexScene::parse_list(&settings.calibration_scenes) {
+        report(Severity::Error, "calibration_scenes", e);
+    }
+    if Orientation::parse_selection(&settings.calibration_orientation).is_none() {
+        report(
+            Severity::Error,
+            "calibration_orientation",
+            unknown(
+                &settings.calibration_orientation,
+                "portrait, landscape, both, current",
+            ),
+        );
+    }
+
+    // Numbers
+    if !(1..=MAX_STEPS_LIMIT).contains(&settings.max_steps) {
+        report(
+            Severity::Error,
+            "max_steps",
+            format!(
+                "{} is out of range; use 1 to {}",
+                settings.max_steps, MAX_STEPS_LIMIT
+            ),
+        );
+    }
+    if coordinate_system == "relative" {
+        let scaled = settings.scale_x != 1.0 || settings.scale_y != 1.0;
+        let offset = settings.offset_x != 0.0 || settings.offset_y != 0.0;
+        if scaled || offset {
+            report(
+                Severity::Warning,
+                "coordinate_system",
+                format!(
+                    "relative coordinates ignore the scale ({}, {}) and offset ({}, {}); \
+                         set coordinate_system to absolute to use them",
+                    settings.scale_x, settings.scale_y, settings.offset_x, settings.offset_y
+                ),
+            );
+        }
+    } else {
+        for (key, scale) in [("scale_x", settings.scale_x), ("scale_y", settings.scale_y)] {
+            if !(0.0..=MAX_COORDINATE_SCALE).contains(&scale) {
+                report(
+                    Severity::Error,
+                    key,
+                    format!(
+                        "{} is out of range; use 0 to {} (0 means the default {})",
+                        scale, MAX_COORDINATE_SCALE, DEFAULT_COORDINATE_SCALE
+                    ),
+                );
+            }
+        }
+    }
+    if !(0.0..=1.0).contains(&settings.screen_similarity_threshold) {
+        report(
+            Severity::Error,
+            "screen_similarity_threshold",
+            format!(
+                "{} is out of range; use 0 to 1",
+                settings.screen_similarity_threshold
+            ),
+        );
+    }
+    if settings.calibration_concurrency == 0 {
+        report(
+            Severity::Error,
+            "calibration_concurrency",
+            "must be at least 1".to_string(),
+        );
+    }
+}
+
+/// Parse an http(s) URL, explaining what is wrong with it if it isn't one.
+fn parse_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("\"{}\" is not a URL: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!(
+            "\"{}\" is not an http:// or https:// URL with a host",
+            url
+        ));
+    }
+    Ok(parsed)
+}
+
+/// Whether a URL points at this machine or the local network, where model
+/// servers usually run without an API key.
+fn is_local(url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.eq_ignore_ascii_case("localhost") || host.ends_with(".local") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+        }
+        Ok(IpAddr::V6(ip)) => ip.is_loopback() || ip.is_unspecified(),
+        Err(_) => false,
+    }
+}
+
+/// Whether an API key is missing ("EMPTY" is the placeholder for none).
+fn is_blank_key(key: &str) -> bool {
+    let key = key.trim();
+    key.is_empty() || key.eq_ignore_ascii_case("EMPTY")
+}
+
+/// Message for a value that isn't one of the accepted names.
+fn unknown(value: &str, accepted: &str) -> String {
+    format!("unknown value \"{}\"; use one of: {}", value, accepted)
+}
+
+/// How to set a setting, e.g. "set MODEL_API_KEY or --api-key".
+fn how_to_set(key: &str) -> String {
+    let flag = format!("--{}", key.replace('_', "-"));
+    match ENV_VARS.iter().find(|(_, k)| *k == key) {
+        Some((name, _)) => format!("set {} or {}", name, flag),
+        None => format!("set {}", flag),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::AppSettings;
+
+    fn issues(settings: AppSettings) -> Vec<(String, Severity)> {
+        Config::builder()
+            .with_settings(settings)
+            .build()
+            .validate()
+            .into_iter()
+            .map(|issue| (issue.key, issue.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(issues(AppSettings::default()).is_empty());
+
+        let settings = AppSettings {
+            base_url: "https://api.example.com/v1".to_string(),
+            coordinate_system: "absolute".to_string(),
+            scale_x: 20.0,
+            max_steps: 0,
+            notify_bark_url: "api.day.app/key".to_string(),
+            event_stream_addr: "localhost".to_string(),
+            ..AppSettings::default()
+        };
+        assert_eq!(
+            issues(settings),
+            [
+                ("api_key".to_string(), Severity::Error),
+                ("notify_bark_url".to_string(), Severity::Error),
+                ("event_stream_addr".to_string(), Severity::Error),
+                ("max_steps".to_string(), Severity::Error),
+                ("scale_x".to_string(), Severity::Error),
+            ]
+        );
+
+        // Local servers need no key; relative coordinates ignore the scale
+        let settings = AppSettings {
+            base_url: "http://192.168.1.20:8000/v1".to_string(),
+            scale_x: 1.61,
+            event_stream_addr: "8765".to_string(),
+            ..AppSettings::default()
+        };
+        assert_eq!(
+            issues(settings),
+            [("coordinate_system".to_string(), Severity::Warning)]
+        );
+    }
+
+    #[test]
+    fn test_issue_message() {
+        let config = Config::builder()
+            .with_env_vars([(
+                "MODEL_BASE_URL".to_string(),
+                "https://api.example.com".to_string(),
+            )])
+            .build();
+        let issue = &config.validate()[0];
+        assert!(issue.is_error());
+        assert_eq!(
+            issue.to_string(),
+            "api_key (from default): api.example.com needs an API key; \
+             set MODEL_API_KEY or --api-key"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Error categories and the process exit codes derived from them.
+//!
+//! [`ErrorCategory::of`] looks through an error and its sources for the
+//! typed errors of this crate, so the binaries can exit with a code that
+//! tells shell scripts and CI why a run failed:
+//!
+//! ```bash
+//! phone-agent "打开微信" || case $? in
+//!     3) echo "no device" ;;
+//!     4) echo "model unreachable" ;;
+//! esac
+//! ```
+
+use std::error::Error;
+
+use crate::adb::AdbError;
+use crate::agent::AgentError;
+use crate::calibration::CalibrationError;
+use crate::model::ModelError;
+
+/// Why a run failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// Anything not covered below (arguments, configuration, I/O, ...).
+    Other,
+    /// No device is reachable over ADB, or the configured one isn't connected.
+    DeviceNotFound,
+    /// The model server couldn't be reached or kept answering with errors.
+    ModelUnreachable,
+    /// The task ended without success, e.g. after a declined sensitive action.
+    TaskFailed,
+    /// The task ran out of steps.
+    Timeout,
+    /// The run was cancelled (Ctrl+C or a cancellation flag).
+    Cancelled,
+    /// Coordinate calibration produced no usable result.
+    CalibrationFailed,
+}
+
+impl ErrorCategory {
+    /// All categories, in exit code order.
+    pub const ALL: [Self; 7] = [
+        Self::Other,
+        Self::DeviceNotFound,
+        Self::ModelUnreachable,
+        Self::TaskFailed,
+        Self::Timeout,
+        Self::CalibrationFailed,
+        Self::Cancelled,
+    ];
+
+    /// Category of the first typed error of this crate found in `error` or
+    /// its sources (`Other` if there is none).
+    pub fn of(error: &(dyn Error + 'static)) -> Self {
+        std::iter::successors(Some(error), |&e| e.source())
+            .find_map(Self::of_typed)
+            .unwrap_or(Self::Other)
+    }
+
+    fn of_typed(error: &(dyn Error + 'static)) -> Option<Self> {
+        if let Some(error) = error.downcast_ref::<AgentError>() {
+            return Some(match error {
+                AgentError::ModelError(_) => Self::ModelUnreachable,
+                AgentError::ActionError(_) | AgentError::TaskFailed(_) => Self::TaskFailed,
+                AgentError::MaxStepsReached => Self::Timeout,
+                AgentError::Cancelled => Self::Cancelled,
+                AgentError::TaskRequired => Self::Other,
+            });
+        }
+        if error.is::<ModelError>() {
+            Some(Self::ModelUnreachable)
+        } else if let Some(error) = error.downcast_ref::<AdbError>() {
+            // A failing command on a connected device says nothing about the connection
+            Some(match error {
+                AdbError::DeviceNotFound(_) | AdbError::Connection(_) | AdbError::Timeout(_) => {
+                    Self::DeviceNotFound
+                }
+                AdbError::CommandFailed(_) => Self::Other,
+            })
+        } else if error.is::<CalibrationError>() {
+            Some(Self::CalibrationFailed)
+        } else {
+            None
+        }
+    }
+
+    /// Process exit code. Cancellation uses 130, like a shell interrupted
+    /// with Ctrl+C.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::DeviceNotFound => 3,
+            Self::ModelUnreachable => 4,
+            Self::TaskFailed => 5,
+            Self::Timeout => 6,
+            Self::CalibrationFailed => 7,
+            Self::Cancelled => 130,
+        }
+    }
+
+    /// Name of the category, e.g. "device_not_found".
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::DeviceNotFound => "device_not_found",
+            Self::ModelUnreachable => "model_unreachable",
+            Self::TaskFailed => "task_failed",
+            Self::Timeout => "timeout",
+            Self::CalibrationFailed => "calibration_failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl From<ErrorCategory> for std::process::ExitCode {
+    fn from(category: ErrorCategory) -> Self {
+        Self::from(category.exit_code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_error_category() {
+        let category = |error: anyhow::Error| ErrorCategory::of(error.as_ref());
+
+        assert_eq!(category(anyhow!("bad argument")), ErrorCategory::Other);
+        assert_eq!(
+            category(anyhow::Error::new(AgentError::MaxStepsReached)),
+            ErrorCategory::Timeout
+        );
+        // Found behind added context
+        let error = Err::<(), _>(AdbError::DeviceNotFound("emulator-5554".to_string()))
+            .context("starting the agent")
+            .unwrap_err();
+        assert_eq!(category(error), ErrorCategory::DeviceNotFound);
+        assert_eq!(
+            category(CalibrationError("no points".to_string()).into()),
+            ErrorCategory::CalibrationFailed
+        );
+
+        assert_eq!(
+            category(AdbError::CommandFailed("input tap".to_string()).into()),
+            ErrorCategory::Other
+        );
+        assert_eq!(
+            category(AdbError::Connection("refused".to_string()).into()),
+            ErrorCategory::DeviceNotFound
+        );
+
+        let codes: HashSet<u8> = ErrorCategory::ALL.iter().map(|c| c.exit_code()).collect();
+        assert_eq!(codes.len(), ErrorCategory::ALL.len());
+        assert!(!codes.contains(&0));
+    }
+}
@@ -1,46 +1,135 @@
 //! Main Iced application for Phone Agent GUI.
 
+use chrono::{DateTime, Local};
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::keyboard::{self, key::Named, Key, Modifiers};
+use iced::theme::Palette;
 use iced::widget::{
-    button, column, container, horizontal_rule, horizontal_space, pick_list, row, scrollable, text,
-    text_input, toggler, vertical_space,
+    button, center, column, container, horizontal_rule, horizontal_space, image, mouse_area,
+    opaque, pick_list, row, scrollable, stack, text, text_input, toggler, tooltip, vertical_space,
 };
-use iced::{Element, Length, Task, Theme};
-
-use crate::calibration::{CalibrationConfig, CalibrationMode, CoordinateCalibrator};
-use crate::model::ModelClient;
-use crate::{AgentConfig, CoordinateSystem, ModelConfig, PhoneAgent, StepResult};
+use iced::{mouse, Color, Element, Length, Point, Subscription, Task, Theme};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::actions::{do_action, macro_handler, Macro};
+use crate::actions::{ConfirmationCallback, TakeoverCallback};
+use crate::adb::{
+    get_current_package, get_device_model, get_screenshot, recents, ADBConnection, ConnectionType,
+    DeviceInfo,
+};
+use crate::agent::{
+    DualLoopEvent, DualLoopHandle, DualLoopRunner, LiveSettings, PlannerAgent, PromptScope,
+    TodoItem, TodoStatus,
+};
+use crate::calibration::{
+    load_device_calibrations, save_device_profile, CalibrationConfig, CalibrationMode,
+    CalibrationResult, ComplexScene, CoordinateCalibrator, Orientation, OrientedCalibrations,
+};
+use crate::config::{
+    get_messages, load_languages_dir, prompt_variants, registered_languages, set_prompts_dir,
+    AppHints, AppRegion, Config, Messages, PlannerPreset, PromptStyle, Severity,
+};
+use crate::model::{ModelClient, TokenUsage};
+use crate::{AgentConfig, CoordinateSystem, ModelConfig, PhoneAgent};
 
+use super::export::export_report;
+use super::history::{write_report, HistoryEntry, TaskHistory, TaskOutcome};
 use super::logger::Logger;
-use super::settings::AppSettings;
+use super::preview::{PreviewFrame, PREVIEW_WIDTH};
+use super::requests::{
+    await_confirmation, await_takeover, confirmation_timeout, seconds_left, ConfirmationAnswer,
+};
+use super::settings::{AppSettings, SettingsError, SettingsWatcher};
+use super::timeline::{TimelineEntry, THUMBNAIL_WIDTH};
+use super::toast::Toasts;
+use super::usage::{estimate_cost, UsageStats};
 
 /// Current view/tab of the application.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum View {
     #[default]
     Main,
+    Timeline,
+    History,
+    Preview,
     Settings,
     Logs,
 }
 
+impl View {
+    /// Tabs in the order of the navigation bar.
+    const ALL: [View; 6] = [
+        View::Main,
+        View::Timeline,
+        View::History,
+        View::Preview,
+        View::Settings,
+        View::Logs,
+    ];
+}
+
+/// Keyboard shortcuts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    /// Enter: run the task typed on the main page.
+    Run,
+    /// Esc or Ctrl+C: stop the running task.
+    Stop,
+    /// Ctrl+1 to Ctrl+6: switch to the tab at that position.
+    SwitchView(View),
+    /// Ctrl+L: clear the logs.
+    ClearLogs,
+}
+
 /// Language options.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Language {
     Chinese,
     English,
+    /// A language loaded from the `languages` folder.
+    Registered {
+        code: &'static str,
+        name: &'static str,
+    },
 }
 
 impl Language {
+    /// The built-in languages followed by the registered ones.
+    fn all() -> Vec<Language> {
+        let mut all = vec![Language::Chinese, Language::English];
+        all.extend(
+            registered_languages()
+                .into_iter()
+                .map(|language| Language::Registered {
+                    code: language.code,
+                    name: language.name,
+                }),
+        );
+        all
+    }
+
     fn as_code(&self) -> &'static str {
         match self {
             Language::Chinese => "cn",
             Language::English => "en",
+            Language::Registered { code, .. } => code,
         }
     }
 
     fn from_code(code: &str) -> Self {
         match code {
             "en" => Language::English,
-            _ => Language::Chinese,
+            "cn" => Language::Chinese,
+            _ => Self::all()
+                .into_iter()
+                .find(|language| language.as_code() == code)
+                .unwrap_or(Language::Chinese),
         }
     }
 }
@@ -50,7 +139,111 @@ impl std::fmt::Display for Language {
         match self {
             Language::Chinese => write!(f, "中文"),
             Language::English => write!(f, "English"),
+            Language::Registered { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Theme options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeOption {
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemeOption {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ThemeOption::System => "system",
+            ThemeOption::Light => "light",
+            ThemeOption::Dark => "dark",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "system" => ThemeOption::System,
+            "light" => ThemeOption::Light,
+            _ => ThemeOption::Dark,
+        }
+    }
+
+    fn label(self, messages: &'static Messages) -> &'static str {
+        match self {
+            ThemeOption::System => messages.theme_system,
+            ThemeOption::Light => messages.theme_light,
+            ThemeOption::Dark => messages.theme_dark,
+        }
+    }
+
+    fn as_theme(self) -> Theme {
+        match self {
+            // Detected from the OS when the app starts
+            ThemeOption::System => Theme::default(),
+            ThemeOption::Light => Theme::Light,
+            ThemeOption::Dark => Theme::Dark,
+        }
+    }
+}
+
+/// Accent colors offered next to the hex input.
+const ACCENT_PRESETS: [&str; 6] = [
+    "#5E7CE2", "#12A37F", "#8E5CE2", "#E2875E", "#D9485F", "#3AA6D9",
+];
+
+/// Device choice in the device picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceOption {
+    /// ADB device ID (empty = let ADB pick the only connected device)
+    id: String,
+    /// Device as last listed by `adb devices` (`None` = not connected)
+    info: Option<DeviceInfo>,
+    /// Language the entry is shown in
+    language: Language,
+}
+
+impl DeviceOption {
+    fn auto(language: Language) -> Self {
+        Self {
+            id: String::new(),
+            info: None,
+            language,
+        }
+    }
+
+    fn is_auto(&self) -> bool {
+        self.id.is_empty()
+    }
+}
+
+impl std::fmt::Display for DeviceOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages = get_messages(self.language.as_code());
+        if self.is_auto() {
+            return write!(f, "{}", messages.auto_detect);
+        }
+        let Some(info) = &self.info else {
+            return write!(f, "⚪ {} ({})", self.id, messages.not_connected);
+        };
+
+        let state = match info.status.as_str() {
+            "device" => "🟢",
+            "unauthorized" => "🟡",
+            _ => "🔴",
+        };
+        let connection = match info.connection_type {
+            ConnectionType::Usb => "🔌",
+            ConnectionType::Wifi | ConnectionType::Remote => "📶",
+        };
+        write!(f, "{} {} {}", state, connection, self.id)?;
+        if let Some(model) = &info.model {
+            write!(f, " ({})", model)?;
         }
+        if !info.is_ready() {
+            write!(f, " - {}", info.status)?;
+        }
+        Ok(())
     }
 }
 
@@ -75,20 +268,13 @@ impl CoordSystemOption {
             _ => CoordSystemOption::Relative,
         }
     }
-
-    fn as_coordinate_system(self) -> CoordinateSystem {
-        match self {
-            CoordSystemOption::Relative => CoordinateSystem::Relative,
-            CoordSystemOption::Absolute => CoordinateSystem::Absolute,
-        }
-    }
 }
 
-impl std::fmt::Display for CoordSystemOption {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl CoordSystemOption {
+    fn label(self, messages: &'static Messages) -> &'static str {
         match self {
-            CoordSystemOption::Relative => write!(f, "相对坐标 (0-999)"),
-            CoordSystemOption::Absolute => write!(f, "绝对坐标 (像素)"),
+            CoordSystemOption::Relative => messages.coord_relative,
+            CoordSystemOption::Absolute => messages.coord_absolute,
         }
     }
 }
@@ -98,6 +284,7 @@ impl std::fmt::Display for CoordSystemOption {
 pub enum CalibModeOption {
     Simple,
     Complex,
+    Grid,
 }
 
 impl CalibModeOption {
@@ -105,12 +292,14 @@ impl CalibModeOption {
         match self {
             CalibModeOption::Simple => "simple",
             CalibModeOption::Complex => "complex",
+            CalibModeOption::Grid => "grid",
         }
     }
 
     fn from_str(s: &str) -> Self {
         match s {
             "complex" => CalibModeOption::Complex,
+            "grid" => CalibModeOption::Grid,
             _ => CalibModeOption::Simple,
         }
     }
@@ -119,19 +308,51 @@ impl CalibModeOption {
         match self {
             CalibModeOption::Simple => CalibrationMode::Simple,
             CalibModeOption::Complex => CalibrationMode::Complex,
+            CalibModeOption::Grid => CalibrationMode::Grid,
         }
     }
 }
 
-impl std::fmt::Display for CalibModeOption {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl CalibModeOption {
+    fn label(self, messages: &'static Messages) -> &'static str {
         match self {
-            CalibModeOption::Simple => write!(f, "简单模式"),
-            CalibModeOption::Complex => write!(f, "复杂模式"),
+            CalibModeOption::Simple => messages.calib_simple,
+            CalibModeOption::Complex => messages.calib_complex,
+            CalibModeOption::Grid => messages.calib_grid,
         }
     }
 }
 
+/// A picker entry shown with a translated label.
+#[derive(Debug, Clone, Copy)]
+pub struct Choice<T> {
+    value: T,
+    label: &'static str,
+}
+
+impl<T: PartialEq> PartialEq for Choice<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T> std::fmt::Display for Choice<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// Who said a line of the planner conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChatRole {
+    User,
+    Planner,
+    System,
+}
+
+/// Most planner conversation lines kept; older ones are dropped.
+const MAX_CHAT_LINES: usize = 200;
+
 /// Application state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum AppState {
@@ -139,6 +360,7 @@ pub enum AppState {
     Idle,
     Running,
     Calibrating,
+    Replaying,
 }
 
 /// Messages for the Iced application.
@@ -146,15 +368,28 @@ pub enum AppState {
 pub enum Message {
     // Navigation
     SwitchView(View),
+    Shortcut(Shortcut),
 
     // Settings - Model
     BaseUrlChanged(String),
     ApiKeyChanged(String),
+    PlaintextApiKeysToggled(bool),
     ModelNameChanged(String),
+    PriceChanged(String),
 
     // Settings - Device
-    DeviceIdChanged(String),
+    DeviceSelected(DeviceOption),
+    RefreshDevices,
+    DevicesLoaded(Result<Vec<DeviceInfo>, String>),
+    CheckConnections,
+    ModelChecked(Result<Vec<String>, String>),
+    DeviceChecked(Result<Vec<DeviceInfo>, String>),
     LanguageSelected(Language),
+    AppRegionSelected(AppRegion),
+
+    // Settings - Appearance
+    ThemeSelected(ThemeOption),
+    AccentColorChanged(String),
 
     // Settings - Coordinates
     CoordSystemSelected(CoordSystemOption),
@@ -167,28 +402,109 @@ pub enum Message {
 
     // Settings - Agent
     MaxStepsChanged(String),
+    PromptStyleSelected(PromptStyle),
+    PromptVariantSelected(String),
+    DisabledActionsChanged(String),
+    ConfirmationTimeoutChanged(String),
     EnableCalibrationToggled(bool),
+    CalibOnDeviceToggled(bool),
+    AutoRecalibrateToggled(bool),
     CalibModeSelected(CalibModeOption),
     CalibRoundsChanged(String),
 
+    // Settings - App hints
+    AppHintChanged(String, String),
+    RemoveAppHint(String),
+    NewHintPackageChanged(String),
+    NewHintTextChanged(String),
+    AddAppHint,
+    UseCurrentApp,
+    CurrentAppLoaded(Option<String>),
+
+    // Settings - Profiles
+    ProfileSelected(String),
+    ProfileNameChanged(String),
+    SaveProfile,
+    DeleteProfile,
+    SettingsFileChanged(String),
+    ExportSettings,
+    ImportSettings,
+
     // Settings actions
     SaveSettings,
     ResetSettings,
-    SettingsSaved(Result<(), String>),
+    SettingsSaved(Result<(), SettingsError>),
+    CheckSettingsFile,
 
     // Task execution
     TaskInputChanged(String),
     RunTask,
     StopTask,
-    TaskStep(StepResult),
+    AgentChannelReady(mpsc::UnboundedSender<Message>),
+    TaskStep(TimelineEntry),
     TaskCompleted(Result<String, String>),
 
+    // Sensitive operation confirmations and toasts
+    ConfirmationRequested(String, u64),
+    ConfirmAction(bool),
+    ConfirmationTimedOut,
+    DismissToast(u64),
+    Tick,
+
+    // Manual takeover
+    TakeoverRequested(String),
+    ResumeAgent,
+    ManualTextChanged(String),
+    ManualType,
+    Recents,
+
+    // Dual loop
+    DualLoopToggled(bool),
+    PlannerBaseUrlChanged(String),
+    PlannerApiKeyChanged(String),
+    PlannerModelNameChanged(String),
+    PlannerPresetSelected(String),
+    DualLoopStarted(Result<DualLoopHandle, String>),
+    DualLoop(DualLoopEvent),
+    DualLoopCommandDone(Result<(), String>),
+    TodoDragStart(usize),
+    TodoDragOver(usize),
+    TodoDrop,
+    TodoDragCancel,
+    EditTodo(usize),
+    TodoEditChanged(String),
+    SaveTodoEdit,
+    CancelTodoEdit,
+    PlannerInputChanged(String),
+    SendToPlanner,
+
+    // Task history
+    RerunTask(usize),
+    ClearHistory,
+
+    // Preview and macros
+    RefreshPreview,
+    PreviewCaptured(Option<PreviewFrame>),
+    PreviewMoved(Point),
+    PreviewPressed,
+    PreviewReleased,
+    PreviewKey(&'static str),
+    PreviewActionDone(Result<(), String>),
+    ToggleRecording,
+    MacroNameChanged(String),
+    MacroSelected(String),
+    PlayMacro,
+    MacroPlayed(Result<usize, String>),
+
     // Calibration
     RunCalibration,
-    CalibrationCompleted(Result<(f64, f64), String>),
+    CalibrationCompleted(Result<CalibrationResult, String>),
 
     // Logs
     ClearLogs,
+    ExportDirChanged(String),
+    ExportReport,
+    ReportExported(Result<PathBuf, String>),
 }
 
 /// Main application struct.
@@ -196,28 +512,113 @@ pub struct PhoneAgentApp {
     // Current view
     view: View,
 
-    // Settings
+    // Settings, the watcher of their file and the values running sessions
+    // last took over from it
     settings: AppSettings,
+    settings_watcher: SettingsWatcher,
+    live_settings: LiveSettings,
+    // Why the settings were last refused, by field, shown next to its input
+    field_errors: BTreeMap<String, String>,
 
     // Parsed settings for pick_list
     language: Language,
+    theme: ThemeOption,
     coord_system: CoordSystemOption,
     calib_mode: CalibModeOption,
 
     // Input fields as strings
+    price_input: String,
     scale_x_input: String,
     scale_y_input: String,
     max_retries_input: String,
     retry_delay_input: String,
     max_steps_input: String,
+    confirmation_timeout_input: String,
+    disabled_actions_input: String,
     calib_rounds_input: String,
 
+    // Devices from the last `adb devices`
+    devices: Vec<DeviceInfo>,
+
+    // Results of the last model and device checks (`None` before the first)
+    model_health: Option<Result<Vec<String>, String>>,
+    device_health: Option<Result<Vec<DeviceInfo>, String>>,
+
+    // Stored settings profiles and the name typed for saving one
+    profiles: Vec<String>,
+    profile_name_input: String,
+    /// File settings are exported to or imported from (empty = export folder)
+    settings_file_input: String,
+
     // Task input
     task_input: String,
 
     // Application state
     state: AppState,
 
+    // Steps of the current or last run
+    timeline: Vec<TimelineEntry>,
+
+    // Sending half of the agent subscription, handed to each run
+    agent_sender: Option<mpsc::UnboundedSender<Message>>,
+
+    // Cancellation flag of the running task
+    cancel: Option<Arc<AtomicBool>>,
+
+    // Wakes the running task after a manual takeover, and the takeover's
+    // message while the user has control
+    resume_agent: Option<std::sync::mpsc::Sender<()>>,
+    takeover: Option<String>,
+
+    // Text typed into the device from the control pad
+    manual_text: String,
+
+    // Answers a pending sensitive-operation confirmation of the running task,
+    // and the operation's message and deadline while the dialog is up
+    answer_confirmation: Option<std::sync::mpsc::Sender<bool>>,
+    confirmation: Option<(String, Option<Instant>)>,
+
+    // Notifications shown over the current page
+    toasts: Toasts,
+
+    // Per-app prompt hints, and the package and hint of the one being added
+    app_hints: AppHints,
+    new_hint_package: String,
+    new_hint_text: String,
+
+    // Running dual loop, its latest todo list, the todo being dragged and
+    // the row it is over, and the todo being edited with its new text
+    dual_loop: Option<DualLoopHandle>,
+    todo_items: Vec<TodoItem>,
+    todo_drag: Option<(usize, usize)>,
+    todo_edit: Option<(String, String)>,
+
+    // Planner conversation of the dual loop and the message being typed
+    planner_chat: Vec<(ChatRole, String)>,
+    planner_input: String,
+
+    // Text and start time of the running task
+    running_task: Option<(String, DateTime<Local>)>,
+
+    // Finished tasks
+    history: TaskHistory,
+
+    // Token usage of the current task and the session
+    usage: UsageStats,
+
+    // Device preview: latest frame, whether a capture is in flight, cursor
+    // position over it and where/when the current press started
+    preview: Option<PreviewFrame>,
+    preview_loading: bool,
+    preview_cursor: Point,
+    preview_press: Option<(Point, Instant)>,
+
+    // Macro being recorded and when its last action happened
+    recording: Option<(Macro, Instant)>,
+    macros: Vec<String>,
+    macro_name_input: String,
+    selected_macro: Option<String>,
+
     // Logger
     logger: Logger,
 
@@ -234,47 +635,379 @@ impl Default for PhoneAgentApp {
 impl PhoneAgentApp {
     /// Create a new application instance.
     pub fn new() -> Self {
-        let settings = AppSettings::load();
+        // `phone-agent-gui --profile <name>` starts with a settings profile
+        let args: Vec<String> = std::env::args().collect();
+        let profile = Config::profile_arg(&args);
+        let loaded = match &profile {
+            Some(name) => AppSettings::load_profile(name),
+            None => AppSettings::try_load(),
+        };
+        let (settings, load_error) = match loaded {
+            Ok(settings) => (settings, None),
+            // A broken profile falls back to the settings file
+            Err(e) if profile.is_some() => (AppSettings::load(), Some(e)),
+            Err(e) => (AppSettings::default(), Some(e)),
+        };
+        if let Some(dir) = AppSettings::languages_dir() {
+            load_languages_dir(&dir);
+        }
+        if std::env::var_os("PROMPTS_DIR").is_none() {
+            set_prompts_dir(AppSettings::prompts_dir());
+        }
+        let messages = get_messages(&settings.lang);
         let mut logger = Logger::new();
-        logger.info("Phone Agent GUI 启动");
+        logger.info(messages.app_started);
+        if let Some(e) = &load_error {
+            let failed = match profile {
+                Some(_) => messages.profile_load_failed,
+                None => messages.settings_load_failed,
+            };
+            logger.error(format!("{}: {}", failed, e));
+        }
+        let config = Config::builder()
+            .with_settings(settings.clone())
+            .with_env()
+            .build();
+        for issue in config.validate() {
+            match issue.severity {
+                Severity::Error => logger.error(issue.to_string()),
+                Severity::Warning => logger.warning(issue.to_string()),
+            }
+        }
 
         Self {
             view: View::Main,
             language: Language::from_code(&settings.lang),
+            theme: ThemeOption::from_str(&settings.theme),
             coord_system: CoordSystemOption::from_str(&settings.coordinate_system),
             calib_mode: CalibModeOption::from_str(&settings.calibration_mode),
+            price_input: settings.executor_price_per_million.to_string(),
             scale_x_input: settings.scale_x.to_string(),
             scale_y_input: settings.scale_y.to_string(),
             max_retries_input: settings.max_retries.to_string(),
             retry_delay_input: settings.retry_delay.to_string(),
             max_steps_input: settings.max_steps.to_string(),
+            confirmation_timeout_input: settings.confirmation_timeout_secs.to_string(),
+            disabled_actions_input: settings.disabled_actions.join(", "),
             calib_rounds_input: settings.calibration_rounds.to_string(),
+            profiles: AppSettings::list_profiles(),
+            profile_name_input: settings.active_profile.clone(),
+            settings_file_input: String::new(),
+            settings_watcher: SettingsWatcher::new(
+                profile.as_deref().filter(|_| load_error.is_none()),
+            ),
+            live_settings: config.live_settings(),
+            field_errors: load_error.as_ref().map(field_errors).unwrap_or_default(),
             settings,
+            devices: Vec::new(),
+            model_health: None,
+            device_health: None,
             task_input: String::new(),
             state: AppState::Idle,
+            timeline: Vec::new(),
+            agent_sender: None,
+            cancel: None,
+            resume_agent: None,
+            takeover: None,
+            manual_text: String::new(),
+            answer_confirmation: None,
+            confirmation: None,
+            toasts: Toasts::default(),
+            app_hints: AppSettings::load_app_hints(),
+            new_hint_package: String::new(),
+            new_hint_text: String::new(),
+            dual_loop: None,
+            todo_items: Vec::new(),
+            todo_drag: None,
+            todo_edit: None,
+            planner_chat: Vec::new(),
+            planner_input: String::new(),
+            running_task: None,
+            history: TaskHistory::load(),
+            usage: UsageStats::default(),
+            preview: None,
+            preview_loading: false,
+            preview_cursor: Point::ORIGIN,
+            preview_press: None,
+            recording: None,
+            macros: AppSettings::list_macros(),
+            macro_name_input: String::new(),
+            selected_macro: None,
             logger,
-            status: "就绪".to_string(),
+            status: messages.ready.to_string(),
+        }
+    }
+
+    /// Create the application, list the connected devices and check the
+    /// connections.
+    pub fn boot() -> (Self, Task<Message>) {
+        (
+            Self::new(),
+            Task::batch([
+                Task::done(Message::RefreshDevices),
+                Task::done(Message::CheckConnections),
+            ]),
+        )
+    }
+
+    /// UI messages in the selected language.
+    fn messages(&self) -> &'static Messages {
+        get_messages(self.language.as_code())
+    }
+
+    /// Settings with the environment's overrides applied, as the CLI sees
+    /// them. Used to run tasks; the settings page shows and saves the file's.
+    fn effective_settings(&self) -> AppSettings {
+        self.config().into_settings()
+    }
+
+    fn config(&self) -> Config {
+        Config::builder()
+            .with_settings(self.settings.clone())
+            .with_env()
+            .build()
+    }
+
+    /// Log the configuration errors, if any, so a task isn't started with them.
+    fn has_config_errors(&mut self) -> bool {
+        let errors: Vec<_> = self
+            .config()
+            .validate()
+            .into_iter()
+            .filter(|issue| issue.is_error())
+            .collect();
+        for issue in &errors {
+            self.logger.error(issue.to_string());
+        }
+        if !errors.is_empty() {
+            self.status = self.messages().config_invalid.to_string();
+        }
+        !errors.is_empty()
+    }
+
+    /// Configured device ID, `None` to let ADB pick.
+    fn device_id(&self) -> Option<String> {
+        Some(self.settings.device_id.clone()).filter(|id| !id.is_empty())
+    }
+
+    /// Execute an action from the preview, recording it if a macro is being recorded.
+    fn perform_preview_action(&mut self, action: Value) -> Task<Message> {
+        let Some(frame) = &self.preview else {
+            return Task::none();
+        };
+        let (width, height) = (frame.screen_width, frame.screen_height);
+        if let Some((recorded, last)) = &mut self.recording {
+            recorded.record(action.clone(), last.elapsed());
+            *last = Instant::now();
+        }
+        self.logger
+            .action(format!("{}: {}", self.messages().preview_action, action));
+
+        let device_id = self.device_id();
+        let lang = self.settings.lang.clone();
+        Task::perform(
+            async move {
+                let mut handler = macro_handler(device_id.as_deref());
+                handler.set_lang(&lang);
+                let result = handler.execute(&action, width, height);
+                if result.success {
+                    Ok(())
+                } else {
+                    Err(result.message.unwrap_or_default())
+                }
+            },
+            Message::PreviewActionDone,
+        )
+    }
+
+    /// Hand the task to the planner, starting the dual loop if it isn't running.
+    fn run_dual_loop_task(&mut self) -> Task<Message> {
+        let m = self.messages();
+        let task = self.task_input.clone();
+
+        if self.dual_loop.is_some() {
+            self.logger.info(format!("{}: {}", m.sent_to_planner, task));
+            return self
+                .dual_loop_command(|handle| async move { handle.send_user_input(task).await });
+        }
+
+        self.state = AppState::Running;
+        self.logger.info(format!("{}: {}", m.starting_task, task));
+        self.status = m.running.to_string();
+        self.todo_items.clear();
+        self.planner_chat.clear();
+        let (resume, resumed) = std::sync::mpsc::channel();
+        self.resume_agent = Some(resume);
+        let (answer, answers) = std::sync::mpsc::channel();
+        self.answer_confirmation = Some(answer);
+
+        Task::perform(
+            start_dual_loop(
+                self.effective_settings(),
+                task,
+                self.agent_sender.clone(),
+                resumed,
+                answers,
+            ),
+            Message::DualLoopStarted,
+        )
+    }
+
+    /// Add a line to the planner conversation.
+    fn push_chat(&mut self, role: ChatRole, content: String) {
+        self.planner_chat.push((role, content));
+        if self.planner_chat.len() > MAX_CHAT_LINES {
+            self.planner_chat.remove(0);
+        }
+    }
+
+    /// Run a command on the running dual loop, logging it if it fails.
+    fn dual_loop_command<F>(&self, command: impl FnOnce(DualLoopHandle) -> F) -> Task<Message>
+    where
+        F: std::future::Future<Output = Result<(), crate::agent::DualLoopError>> + Send + 'static,
+    {
+        match self.dual_loop.clone() {
+            Some(handle) => Task::perform(command(handle), |result| {
+                Message::DualLoopCommandDone(result.map_err(|e| e.to_string()))
+            }),
+            None => Task::none(),
+        }
+    }
+
+    /// Add a finished task to the history with a report of its steps.
+    fn record_history(
+        &mut self,
+        task: String,
+        started_at: DateTime<Local>,
+        outcome: TaskOutcome,
+        result: String,
+    ) {
+        let elapsed = Local::now() - started_at;
+        let mut entry = HistoryEntry {
+            task,
+            started_at,
+            duration_secs: elapsed.num_milliseconds() as f64 / 1000.0,
+            outcome,
+            result,
+            prompt_variant: Some(self.settings.prompt_variant.clone()),
+            report_path: None,
+        };
+
+        if let Some(dir) = AppSettings::reports_dir() {
+            match write_report(&dir, &entry, &self.timeline, self.messages()) {
+                Ok(path) => entry.report_path = Some(path),
+                Err(e) => {
+                    self.logger
+                        .warning(format!("{}: {}", self.messages().report_write_failed, e))
+                }
+            }
+        }
+
+        self.history.push(entry);
+        if let Err(e) = self.history.save() {
+            self.logger
+                .error(format!("{}: {}", self.messages().history_save_failed, e));
+        }
+    }
+
+    /// Refresh pickers and text inputs after `settings` was replaced.
+    /// Log a settings error and mark the fields it names.
+    fn settings_error(&mut self, failed: &str, e: &SettingsError) {
+        self.logger.error(format!("{}: {}", failed, e));
+        self.field_errors = field_errors(e);
+    }
+
+    /// An input with the reason its field was refused below it, if it was.
+    fn with_field_error<'a>(
+        &'a self,
+        field: &str,
+        input: impl Into<Element<'a, Message>>,
+    ) -> Element<'a, Message> {
+        match self.field_errors.get(field) {
+            Some(reason) => column![
+                input.into(),
+                row![
+                    horizontal_space().width(130),
+                    text(reason).size(12).style(text::danger)
+                ]
+            ]
+            .spacing(4)
+            .into(),
+            None => input.into(),
+        }
+    }
+
+    fn sync_inputs(&mut self) {
+        self.language = Language::from_code(&self.settings.lang);
+        self.theme = ThemeOption::from_str(&self.settings.theme);
+        self.coord_system = CoordSystemOption::from_str(&self.settings.coordinate_system);
+        self.calib_mode = CalibModeOption::from_str(&self.settings.calibration_mode);
+        self.price_input = self.settings.executor_price_per_million.to_string();
+        self.scale_x_input = self.settings.scale_x.to_string();
+        self.scale_y_input = self.settings.scale_y.to_string();
+        self.max_retries_input = self.settings.max_retries.to_string();
+        self.retry_delay_input = self.settings.retry_delay.to_string();
+        self.max_steps_input = self.settings.max_steps.to_string();
+        self.confirmation_timeout_input = self.settings.confirmation_timeout_secs.to_string();
+        self.disabled_actions_input = self.settings.disabled_actions.join(", ");
+        self.calib_rounds_input = self.settings.calibration_rounds.to_string();
+    }
+
+    /// Steps and takeovers reported by the running agent, as they happen,
+    /// and keyboard shortcuts.
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            Subscription::run(agent_stream),
+            keyboard::on_key_press(shortcut),
+            iced::time::every(CONNECTION_CHECK_INTERVAL).map(|_| Message::CheckConnections),
+            iced::time::every(SETTINGS_WATCH_INTERVAL).map(|_| Message::CheckSettingsFile),
+        ];
+        // Keep the preview live while it is shown and nothing else drives the phone
+        let phone_free = self.state == AppState::Idle || self.takeover.is_some();
+        if self.view == View::Preview && phone_free {
+            subscriptions
+                .push(iced::time::every(PREVIEW_INTERVAL).map(|_| Message::RefreshPreview));
         }
+        // Expire toasts and count down the confirmation dialog
+        let counting_down = matches!(self.confirmation, Some((_, Some(_))));
+        if !self.toasts.is_empty() || counting_down {
+            subscriptions.push(iced::time::every(TICK_INTERVAL).map(|_| Message::Tick));
+        }
+        Subscription::batch(subscriptions)
     }
 
     /// Get the window title.
     pub fn title(&self) -> String {
-        "Phone Agent - AI 手机自动化".to_string()
+        self.messages().app_title.to_string()
     }
 
     /// Get the theme.
     pub fn theme(&self) -> Theme {
-        Theme::Dark
+        app_theme(self.theme, &self.settings.accent_color)
     }
 
     /// Update the application state based on messages.
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        let m = self.messages();
         match message {
             // Navigation
             Message::SwitchView(view) => {
                 self.view = view;
                 Task::none()
             }
+            Message::Shortcut(shortcut) => match shortcut {
+                Shortcut::Run if self.view == View::Main => Task::done(Message::RunTask),
+                Shortcut::Run => Task::none(),
+                // Esc rejects a pending confirmation or leaves a todo edit
+                // before it stops anything
+                Shortcut::Stop if self.confirmation.is_some() => {
+                    Task::done(Message::ConfirmAction(false))
+                }
+                Shortcut::Stop if self.todo_edit.is_some() => Task::done(Message::CancelTodoEdit),
+                Shortcut::Stop => Task::done(Message::StopTask),
+                Shortcut::SwitchView(view) => Task::done(Message::SwitchView(view)),
+                Shortcut::ClearLogs => Task::done(Message::ClearLogs),
+            },
 
             // Settings - Model
             Message::BaseUrlChanged(value) => {
@@ -285,14 +1018,69 @@ impl PhoneAgentApp {
                 self.settings.api_key = value;
                 Task::none()
             }
+            Message::PlaintextApiKeysToggled(enabled) => {
+                self.settings.plaintext_api_keys = enabled;
+                Task::none()
+            }
             Message::ModelNameChanged(value) => {
                 self.settings.model_name = value;
                 Task::none()
             }
+            Message::PriceChanged(value) => {
+                self.price_input = value.clone();
+                if let Ok(v) = value.parse() {
+                    self.settings.executor_price_per_million = v;
+                }
+                Task::none()
+            }
 
             // Settings - Device
-            Message::DeviceIdChanged(value) => {
-                self.settings.device_id = value;
+            Message::DeviceSelected(device) => {
+                self.settings.device_id = device.id;
+                Task::none()
+            }
+            Message::RefreshDevices => {
+                Task::perform(async { ADBConnection::new().list_devices() }, |result| {
+                    Message::DevicesLoaded(result.map_err(|e| e.to_string()))
+                })
+            }
+            Message::CheckConnections => {
+                let client = ModelClient::new(
+                    ModelConfig::default()
+                        .with_base_url(&self.settings.base_url)
+                        .with_api_key(&self.settings.api_key),
+                );
+                Task::batch([
+                    Task::perform(
+                        async move { client.health_check().await.map_err(|e| e.to_string()) },
+                        Message::ModelChecked,
+                    ),
+                    Task::perform(async { ADBConnection::new().list_devices() }, |result| {
+                        Message::DeviceChecked(result.map_err(|e| e.to_string()))
+                    }),
+                ])
+            }
+            Message::ModelChecked(result) => {
+                self.model_health = Some(result);
+                Task::none()
+            }
+            Message::DeviceChecked(result) => {
+                self.device_health = Some(result);
+                Task::none()
+            }
+            Message::DevicesLoaded(result) => {
+                match result {
+                    Ok(devices) => {
+                        self.logger
+                            .info(format!("{}: {}", m.devices_found, devices.len()));
+                        self.devices = devices;
+                    }
+                    Err(e) => {
+                        self.logger
+                            .error(format!("{}: {}", m.device_list_failed, e));
+                        self.devices.clear();
+                    }
+                }
                 Task::none()
             }
             Message::LanguageSelected(lang) => {
@@ -300,6 +1088,25 @@ impl PhoneAgentApp {
                 self.settings.lang = lang.as_code().to_string();
                 Task::none()
             }
+            Message::AppRegionSelected(region) => {
+                self.settings.app_region = region.as_str().to_string();
+                Task::none()
+            }
+
+            // Settings - Appearance
+            Message::PromptStyleSelected(style) => {
+                self.settings.prompt_style = style.as_str().to_string();
+                Task::none()
+            }
+            Message::ThemeSelected(theme) => {
+                self.theme = theme;
+                self.settings.theme = theme.as_str().to_string();
+                Task::none()
+            }
+            Message::AccentColorChanged(value) => {
+                self.settings.accent_color = value;
+                Task::none()
+            }
 
             // Settings - Coordinates
             Message::CoordSystemSelected(coord) => {
@@ -351,6 +1158,22 @@ impl PhoneAgentApp {
             }
 
             // Settings - Agent
+            Message::ConfirmationTimeoutChanged(value) => {
+                self.confirmation_timeout_input = value.clone();
+                if let Ok(v) = value.parse() {
+                    self.settings.confirmation_timeout_secs = v;
+                }
+                Task::none()
+            }
+            Message::PromptVariantSelected(variant) => {
+                self.settings.prompt_variant = variant;
+                Task::none()
+            }
+            Message::DisabledActionsChanged(value) => {
+                self.settings.set_disabled_actions(&value);
+                self.disabled_actions_input = value;
+                Task::none()
+            }
             Message::MaxStepsChanged(value) => {
                 self.max_steps_input = value.clone();
                 if let Ok(v) = value.parse() {
@@ -362,6 +1185,14 @@ impl PhoneAgentApp {
                 self.settings.enable_calibration = enabled;
                 Task::none()
             }
+            Message::CalibOnDeviceToggled(enabled) => {
+                self.settings.calibration_on_device = enabled;
+                Task::none()
+            }
+            Message::AutoRecalibrateToggled(enabled) => {
+                self.settings.auto_recalibrate = enabled;
+                Task::none()
+            }
             Message::CalibModeSelected(mode) => {
                 self.calib_mode = mode;
                 self.settings.calibration_mode = mode.as_str().to_string();
@@ -375,103 +1206,741 @@ impl PhoneAgentApp {
                 Task::none()
             }
 
-            // Settings actions
-            Message::SaveSettings => {
-                let settings = self.settings.clone();
-                Task::perform(async move { settings.save() }, Message::SettingsSaved)
+            // Settings - Profiles
+            Message::ProfileSelected(name) => {
+                match AppSettings::load_profile(&name) {
+                    Ok(settings) => {
+                        self.settings = settings;
+                        self.sync_inputs();
+                        self.field_errors.clear();
+                        self.profile_name_input = name.clone();
+                        // Switching also makes the profile the CLI's settings
+                        self.settings_watcher = SettingsWatcher::new(None);
+                        if let Err(e) = self.settings.save() {
+                            self.settings_error(m.settings_save_failed, &e);
+                        }
+                        self.logger
+                            .success(format!("{}: {}", m.profile_switched, name));
+                        self.status = format!("{}: {}", m.profile, name);
+                    }
+                    Err(e) => self.settings_error(m.profile_load_failed, &e),
+                }
+                Task::none()
             }
-            Message::ResetSettings => {
-                self.settings = AppSettings::default();
-                self.language = Language::Chinese;
-                self.coord_system = CoordSystemOption::Relative;
-                self.calib_mode = CalibModeOption::Simple;
-                self.scale_x_input = self.settings.scale_x.to_string();
-                self.scale_y_input = self.settings.scale_y.to_string();
-                self.max_retries_input = self.settings.max_retries.to_string();
-                self.retry_delay_input = self.settings.retry_delay.to_string();
-                self.max_steps_input = self.settings.max_steps.to_string();
-                self.calib_rounds_input = self.settings.calibration_rounds.to_string();
-                self.logger.info("设置已重置为默认值");
+            Message::ProfileNameChanged(value) => {
+                self.profile_name_input = value;
                 Task::none()
             }
-            Message::SettingsSaved(result) => {
-                match result {
+            Message::SaveProfile => {
+                let name = self.profile_name_input.trim().to_string();
+                match self.settings.save_profile(&name) {
                     Ok(()) => {
-                        self.logger.success("设置已保存");
-                        self.status = "设置已保存".to_string();
-                    }
-                    Err(e) => {
-                        self.logger.error(format!("保存设置失败: {}", e));
-                        self.status = format!("保存失败: {}", e);
+                        self.settings.active_profile = name.clone();
+                        self.profiles = AppSettings::list_profiles();
+                        self.logger
+                            .success(format!("{}: {}", m.profile_saved, name));
                     }
+                    Err(e) => self.settings_error(m.profile_save_failed, &e),
                 }
                 Task::none()
             }
-
-            // Task execution
-            Message::TaskInputChanged(value) => {
-                self.task_input = value;
-                Task::none()
-            }
-            Message::RunTask => {
-                if self.task_input.trim().is_empty() {
-                    self.logger.warning("请输入任务");
-                    return Task::none();
+            Message::DeleteProfile => {
+                let name = self.settings.active_profile.clone();
+                match AppSettings::delete_profile(&name) {
+                    Ok(()) => {
+                        self.settings.active_profile.clear();
+                        self.profiles = AppSettings::list_profiles();
+                        self.logger.info(format!("{}: {}", m.profile_deleted, name));
+                    }
+                    Err(e) => self
+                        .logger
+                        .error(format!("{}: {}", m.profile_delete_failed, e)),
                 }
-
-                self.state = AppState::Running;
-                self.logger
-                    .info(format!("开始执行任务: {}", self.task_input));
-                self.status = "正在执行...".to_string();
-
-                let settings = self.settings.clone();
-                let task = self.task_input.clone();
-
-                Task::perform(
-                    async move { run_agent_task(settings, task).await },
-                    Message::TaskCompleted,
-                )
+                Task::none()
             }
-            Message::StopTask => {
-                self.state = AppState::Idle;
-                self.logger.warning("任务已停止");
-                self.status = "已停止".to_string();
+            Message::SettingsFileChanged(value) => {
+                self.settings_file_input = value;
                 Task::none()
             }
-            Message::TaskStep(step) => {
-                let action_str = step
-                    .action
-                    .as_ref()
-                    .map(|a| a.to_string())
-                    .unwrap_or_else(|| "无".to_string());
-                self.logger.action(format!("动作: {}", action_str));
-                if !step.thinking.is_empty() {
-                    self.logger.thinking(step.thinking);
+            Message::ExportSettings => {
+                let path = match self.settings_file_input.trim() {
+                    "" => self.settings.exports_dir().map(|dir| {
+                        dir.join(format!(
+                            "settings-{}.toml",
+                            Local::now().format("%Y%m%d-%H%M%S")
+                        ))
+                    }),
+                    path => Some(PathBuf::from(path)),
+                };
+                let Some(path) = path else {
+                    self.logger
+                        .error(format!("{}: {}", m.export_failed, m.not_created));
+                    return Task::none();
+                };
+                match self.settings.export(&path) {
+                    Ok(()) => {
+                        let message = format!("{}: {}", m.settings_exported, path.display());
+                        self.logger.success(message.clone());
+                        self.toasts.push(message);
+                    }
+                    Err(e) => self.logger.error(format!("{}: {}", m.export_failed, e)),
                 }
                 Task::none()
             }
-            Message::TaskCompleted(result) => {
-                self.state = AppState::Idle;
-                match result {
-                    Ok(result) => {
-                        self.logger.success(format!("任务完成: {}", result));
-                        self.status = "任务完成".to_string();
-                    }
-                    Err(e) => {
-                        self.logger.error(format!("任务失败: {}", e));
-                        self.status = format!("失败: {}", e);
+            Message::ImportSettings => {
+                let path = self.settings_file_input.trim().to_string();
+                match self.settings.import(&path) {
+                    Ok(()) => {
+                        self.sync_inputs();
+                        self.field_errors.clear();
+                        if let Err(e) = self.settings.save() {
+                            self.settings_error(m.settings_save_failed, &e);
+                        }
+                        self.logger
+                            .success(format!("{}: {}", m.settings_imported, path));
                     }
+                    Err(e) => self.settings_error(m.settings_import_failed, &e),
                 }
                 Task::none()
             }
 
-            // Calibration
-            Message::RunCalibration => {
-                self.state = AppState::Calibrating;
-                self.logger.info("开始坐标校准...");
-                self.status = "正在校准...".to_string();
+            // App hints
+            Message::AppHintChanged(package, hint) => {
+                // Keep the row while its hint is cleared; saving drops it
+                self.app_hints.hints.insert(package, hint);
+                Task::none()
+            }
+            Message::RemoveAppHint(package) => {
+                self.app_hints.hints.remove(&package);
+                Task::none()
+            }
+            Message::NewHintPackageChanged(value) => {
+                self.new_hint_package = value;
+                Task::none()
+            }
+            Message::NewHintTextChanged(value) => {
+                self.new_hint_text = value;
+                Task::none()
+            }
+            Message::AddAppHint => {
+                self.app_hints
+                    .set(&self.new_hint_package, &self.new_hint_text);
+                self.new_hint_package.clear();
+                self.new_hint_text.clear();
+                Task::none()
+            }
+            Message::UseCurrentApp => {
+                let device_id = self.device_id();
+                Task::perform(
+                    async move { get_current_package(device_id.as_deref()) },
+                    Message::CurrentAppLoaded,
+                )
+            }
+            Message::CurrentAppLoaded(package) => {
+                match package {
+                    Some(package) => self.new_hint_package = package,
+                    None => self.logger.warning(m.no_device),
+                }
+                Task::none()
+            }
 
+            // Settings actions
+            Message::SaveSettings => {
                 let settings = self.settings.clone();
+                self.app_hints
+                    .hints
+                    .retain(|_, hint| !hint.trim().is_empty());
+                let app_hints = self.app_hints.clone();
+                Task::perform(
+                    async move {
+                        if let Some(path) = AppSettings::app_hints_path() {
+                            app_hints
+                                .save(&path)
+                                .map_err(|reason| SettingsError::Write { path, reason })?;
+                        }
+                        // Edits to the active profile are kept in its file too
+                        if !settings.active_profile.is_empty() {
+                            settings.save_profile(&settings.active_profile)?;
+                        }
+                        settings.save()
+                    },
+                    Message::SettingsSaved,
+                )
+            }
+            Message::ResetSettings => {
+                self.settings = AppSettings::default();
+                self.sync_inputs();
+                self.field_errors.clear();
+                self.logger.info(m.settings_reset);
+                Task::none()
+            }
+            Message::SettingsSaved(result) => {
+                match result {
+                    Ok(()) => {
+                        self.field_errors.clear();
+                        self.logger.success(m.settings_saved);
+                        self.status = m.settings_saved.to_string();
+                    }
+                    Err(e) => {
+                        self.settings_error(m.settings_save_failed, &e);
+                        self.status = match e {
+                            SettingsError::Invalid(_) => {
+                                format!("{}: {}", m.save_failed, m.invalid_settings)
+                            }
+                            e => format!("{}: {}", m.save_failed, e),
+                        };
+                    }
+                }
+                // The model or device may have changed
+                Task::done(Message::CheckConnections)
+            }
+            Message::CheckSettingsFile => {
+                let stored = match self.settings_watcher.poll() {
+                    Some(Ok(stored)) => stored,
+                    Some(Err(e)) => {
+                        self.settings_error(m.settings_load_failed, &e);
+                        return Task::none();
+                    }
+                    None => return Task::none(),
+                };
+                self.field_errors.clear();
+                // Other edits on the settings page stay until they are saved
+                self.settings.take_live_settings(&stored);
+                self.sync_inputs();
+                let live = self.config().live_settings();
+                let changes = live.changes_since(&self.live_settings);
+                self.live_settings = live;
+                if changes.is_empty() {
+                    return Task::none();
+                }
+                self.logger.info(m.settings_reloaded);
+                self.dual_loop_command(|handle| async move { handle.apply_settings(changes).await })
+            }
+
+            // Task execution
+            Message::TaskInputChanged(value) => {
+                self.task_input = value;
+                Task::none()
+            }
+            Message::RunTask => {
+                // Only a dual loop takes a second task while one is running
+                if self.state != AppState::Idle && self.dual_loop.is_none() {
+                    return Task::none();
+                }
+                if self.task_input.trim().is_empty() {
+                    self.logger.warning(m.enter_task);
+                    return Task::none();
+                }
+                if self.has_config_errors() {
+                    return Task::none();
+                }
+                if self.settings.dual_loop_mode {
+                    return self.run_dual_loop_task();
+                }
+
+                self.state = AppState::Running;
+                self.logger
+                    .info(format!("{}: {}", m.starting_task, self.task_input));
+                self.status = m.running.to_string();
+
+                self.timeline.clear();
+                self.usage.start_task(&self.settings.model_name);
+
+                let settings = self.effective_settings();
+                let task = self.task_input.clone();
+                self.running_task = Some((task.clone(), Local::now()));
+                let updates = self.agent_sender.clone();
+                let cancel = Arc::new(AtomicBool::new(false));
+                self.cancel = Some(cancel.clone());
+                let (resume, resumed) = std::sync::mpsc::channel();
+                self.resume_agent = Some(resume);
+                let (answer, answers) = std::sync::mpsc::channel();
+                self.answer_confirmation = Some(answer);
+
+                Task::perform(
+                    async move {
+                        run_agent_task(settings, task, updates, cancel, resumed, answers).await
+                    },
+                    Message::TaskCompleted,
+                )
+            }
+            Message::StopTask => {
+                // A task waiting on a takeover or a confirmation has to wake
+                // up to notice
+                if self.takeover.take().is_some() {
+                    if let Some(resume) = &self.resume_agent {
+                        let _ = resume.send(());
+                    }
+                }
+                if self.confirmation.take().is_some() {
+                    if let Some(answer) = &self.answer_confirmation {
+                        let _ = answer.send(false);
+                    }
+                }
+                if self.dual_loop.is_some() {
+                    self.logger.warning(m.stopping_task);
+                    self.status = m.stopping.to_string();
+                    return self.dual_loop_command(|handle| async move { handle.stop().await });
+                }
+                // The agent stops before its next action and reports back
+                // through TaskCompleted
+                if let Some(cancel) = &self.cancel {
+                    cancel.store(true, Ordering::SeqCst);
+                    self.logger.warning(m.stopping_task);
+                    self.status = m.stopping.to_string();
+                }
+                Task::none()
+            }
+            Message::AgentChannelReady(sender) => {
+                self.agent_sender = Some(sender);
+                Task::none()
+            }
+            Message::TaskStep(step) => {
+                self.logger
+                    .action(format!("{}: {}", m.action, step.action_text(m)));
+                if !step.thinking.is_empty() {
+                    self.logger.thinking(step.thinking.clone());
+                }
+                self.usage.record(&step.usage);
+                self.timeline.push(step);
+                Task::none()
+            }
+            Message::TaskCompleted(result) => {
+                self.state = AppState::Idle;
+                self.resume_agent = None;
+                self.takeover = None;
+                self.answer_confirmation = None;
+                self.confirmation = None;
+                let cancelled = self.cancel.take().is_some_and(|c| c.load(Ordering::SeqCst));
+                let (outcome, message) = match result {
+                    Ok(result) => {
+                        self.logger
+                            .success(format!("{}: {}", m.task_completed, result));
+                        self.status = m.task_completed.to_string();
+                        (TaskOutcome::Success, result)
+                    }
+                    Err(e) if cancelled => {
+                        self.logger.warning(m.task_cancelled);
+                        self.status = m.cancelled.to_string();
+                        (TaskOutcome::Cancelled, e)
+                    }
+                    Err(e) => {
+                        self.logger.error(format!("{}: {}", m.task_failed, e));
+                        self.status = format!("{}: {}", m.failed, e);
+                        (TaskOutcome::Failed, e)
+                    }
+                };
+                if let Some((task, started_at)) = self.running_task.take() {
+                    self.record_history(task, started_at, outcome, message);
+                }
+                Task::none()
+            }
+
+            // Dual loop
+            Message::DualLoopToggled(enabled) => {
+                self.settings.dual_loop_mode = enabled;
+                Task::none()
+            }
+            Message::PlannerBaseUrlChanged(value) => {
+                self.settings.planner_base_url = value;
+                Task::none()
+            }
+            Message::PlannerApiKeyChanged(value) => {
+                self.settings.planner_api_key = value;
+                Task::none()
+            }
+            Message::PlannerModelNameChanged(value) => {
+                self.settings.planner_model_name = value;
+                Task::none()
+            }
+            Message::PlannerPresetSelected(name) => {
+                self.settings.planner_preset = if name == m.none { String::new() } else { name };
+                Task::none()
+            }
+            Message::DualLoopStarted(Ok(handle)) => {
+                self.dual_loop = Some(handle);
+                self.logger.success(m.dual_loop_started);
+                Task::none()
+            }
+            Message::DualLoopStarted(Err(e)) => {
+                self.state = AppState::Idle;
+                self.resume_agent = None;
+                self.answer_confirmation = None;
+                self.logger.error(format!("{}: {}", m.dual_loop_failed, e));
+                self.status = format!("{}: {}", m.failed, e);
+                Task::none()
+            }
+            Message::DualLoop(event) => {
+                match event {
+                    DualLoopEvent::UserInput { input } => {
+                        self.push_chat(ChatRole::User, input);
+                    }
+                    DualLoopEvent::PlannerResponse { content } => {
+                        self.push_chat(ChatRole::Planner, content);
+                    }
+                    DualLoopEvent::SystemFeedback { content } => {
+                        self.push_chat(ChatRole::System, content);
+                    }
+                    DualLoopEvent::TodoChanged { items, .. } => {
+                        // Rows may have moved under the cursor
+                        self.todo_drag = None;
+                        self.todo_items = items;
+                    }
+                    DualLoopEvent::Report { message } => {
+                        self.logger.info(format!("📋 {}", message));
+                        self.push_chat(ChatRole::Planner, format!("📋 {}", message));
+                    }
+                    // Shown by TakeoverRequested, which the executor's
+                    // takeover callback sends along with a way to resume
+                    DualLoopEvent::TakeoverRequired { .. } => {}
+                    DualLoopEvent::SessionSummary { summary, .. } => {
+                        self.logger.success(summary);
+                    }
+                    DualLoopEvent::SettingsReloaded { changed } => {
+                        self.push_chat(
+                            ChatRole::System,
+                            format!("🔧 {}: {}", m.settings_reloaded, changed.join(", ")),
+                        );
+                    }
+                    DualLoopEvent::Stopped { .. } => {
+                        self.dual_loop = None;
+                        self.resume_agent = None;
+                        self.takeover = None;
+                        self.answer_confirmation = None;
+                        self.confirmation = None;
+                        self.todo_drag = None;
+                        self.todo_edit = None;
+                        self.state = AppState::Idle;
+                        self.logger.info(m.dual_loop_stopped);
+                        self.status = m.dual_loop_stopped.to_string();
+                    }
+                    _ => {}
+                }
+                Task::none()
+            }
+            Message::DualLoopCommandDone(result) => {
+                if let Err(e) = result {
+                    self.logger
+                        .error(format!("{}: {}", m.dual_loop_command_failed, e));
+                }
+                Task::none()
+            }
+            Message::PlannerInputChanged(value) => {
+                self.planner_input = value;
+                Task::none()
+            }
+            Message::SendToPlanner => {
+                let input = self.planner_input.trim().to_string();
+                if input.is_empty() || self.dual_loop.is_none() {
+                    return Task::none();
+                }
+                self.planner_input.clear();
+                self.logger
+                    .info(format!("{}: {}", m.sent_to_planner, input));
+                self.dual_loop_command(|handle| async move { handle.send_user_input(input).await })
+            }
+            Message::TodoDragStart(index) => {
+                if self.todo_edit.is_none() {
+                    self.todo_drag = Some((index, index));
+                }
+                Task::none()
+            }
+            Message::TodoDragOver(index) => {
+                if let Some((_, over)) = &mut self.todo_drag {
+                    *over = index;
+                }
+                Task::none()
+            }
+            Message::TodoDrop => {
+                let Some((from, to)) = self.todo_drag.take() else {
+                    return Task::none();
+                };
+                if from == to || to >= self.todo_items.len() {
+                    return Task::none();
+                }
+                // Show the new order right away; the planner confirms it with
+                // a todo change
+                let item = self.todo_items.remove(from);
+                self.todo_items.insert(to, item);
+                let ids: Vec<String> = self.todo_items.iter().map(|t| t.id.clone()).collect();
+                self.dual_loop_command(|handle| async move { handle.reorder_todos(ids).await })
+            }
+            Message::TodoDragCancel => {
+                self.todo_drag = None;
+                Task::none()
+            }
+            Message::EditTodo(index) => {
+                self.todo_edit = self
+                    .todo_items
+                    .get(index)
+                    .map(|item| (item.id.clone(), item.description.clone()));
+                Task::none()
+            }
+            Message::TodoEditChanged(value) => {
+                if let Some((_, description)) = &mut self.todo_edit {
+                    *description = value;
+                }
+                Task::none()
+            }
+            Message::SaveTodoEdit => {
+                let Some((id, description)) = self.todo_edit.take() else {
+                    return Task::none();
+                };
+                let description = description.trim().to_string();
+                if description.is_empty() {
+                    return Task::none();
+                }
+                if let Some(item) = self.todo_items.iter_mut().find(|item| item.id == id) {
+                    item.description = description.clone();
+                }
+                self.dual_loop_command(
+                    |handle| async move { handle.edit_todo(id, description).await },
+                )
+            }
+            Message::CancelTodoEdit => {
+                self.todo_edit = None;
+                Task::none()
+            }
+
+            // Sensitive operation confirmations and toasts
+            Message::ConfirmationRequested(message, timeout_secs) => {
+                self.logger
+                    .warning(format!("{}: {}", m.confirmation_required, message));
+                self.status = m.confirmation_required.to_string();
+                let deadline =
+                    confirmation_timeout(timeout_secs).map(|timeout| Instant::now() + timeout);
+                self.confirmation = Some((message, deadline));
+                Task::none()
+            }
+            Message::ConfirmAction(allowed) => {
+                if self.confirmation.take().is_some() {
+                    if let Some(answer) = &self.answer_confirmation {
+                        let _ = answer.send(allowed);
+                    }
+                    let result = if allowed {
+                        m.action_confirmed
+                    } else {
+                        m.action_rejected
+                    };
+                    self.logger.info(result);
+                    self.toasts.push(result);
+                    self.status = m.running.to_string();
+                }
+                Task::none()
+            }
+            Message::ConfirmationTimedOut => {
+                // The task already went on with a denial
+                if self.confirmation.take().is_some() {
+                    self.logger.warning(m.confirmation_timed_out);
+                    self.toasts.push(m.confirmation_timed_out);
+                    self.status = m.running.to_string();
+                }
+                Task::none()
+            }
+            Message::DismissToast(id) => {
+                self.toasts.dismiss(id);
+                Task::none()
+            }
+            Message::Tick => {
+                self.toasts.expire();
+                Task::none()
+            }
+
+            // Manual takeover
+            Message::TakeoverRequested(message) => {
+                self.logger
+                    .warning(format!("{}: {}", m.manual_operation_required, message));
+                self.toasts
+                    .push(format!("✋ {}: {}", m.manual_operation_required, message));
+                self.status = m.manual_operation_required.to_string();
+                self.takeover = Some(message);
+                self.view = View::Preview;
+                Task::done(Message::RefreshPreview)
+            }
+            Message::ResumeAgent => {
+                if self.takeover.take().is_some() {
+                    if let Some(resume) = &self.resume_agent {
+                        let _ = resume.send(());
+                    }
+                    self.logger.info(m.control_returned);
+                    self.status = m.running.to_string();
+                }
+                Task::none()
+            }
+            Message::ManualTextChanged(value) => {
+                self.manual_text = value;
+                Task::none()
+            }
+            Message::ManualType => {
+                if self.manual_text.is_empty() {
+                    return Task::none();
+                }
+                let text = std::mem::take(&mut self.manual_text);
+                self.perform_preview_action(do_action("Type", &[("text", json!(text))]))
+            }
+            Message::Recents => {
+                let device_id = self.device_id();
+                Task::perform(
+                    async move {
+                        recents(device_id.as_deref(), None);
+                        Ok(())
+                    },
+                    Message::PreviewActionDone,
+                )
+            }
+
+            // Task history
+            Message::RerunTask(index) => {
+                let Some(entry) = self.history.entries.get(index) else {
+                    return Task::none();
+                };
+                if self.state != AppState::Idle {
+                    self.logger.warning(m.wait_for_task);
+                    return Task::none();
+                }
+                self.task_input = entry.task.clone();
+                self.view = View::Main;
+                Task::done(Message::RunTask)
+            }
+            Message::ClearHistory => {
+                self.history.clear();
+                if let Err(e) = self.history.save() {
+                    self.logger
+                        .error(format!("{}: {}", m.history_save_failed, e));
+                }
+                Task::none()
+            }
+
+            // Preview and macros
+            Message::RefreshPreview => {
+                if self.preview_loading {
+                    return Task::none();
+                }
+                self.preview_loading = true;
+                let device_id = self.device_id();
+                Task::perform(
+                    async move { PreviewFrame::from_screenshot(&get_screenshot(device_id.as_deref())) },
+                    Message::PreviewCaptured,
+                )
+            }
+            Message::PreviewCaptured(frame) => {
+                self.preview_loading = false;
+                self.preview = frame;
+                Task::none()
+            }
+            Message::PreviewMoved(point) => {
+                self.preview_cursor = point;
+                Task::none()
+            }
+            Message::PreviewPressed => {
+                self.preview_press = Some((self.preview_cursor, Instant::now()));
+                Task::none()
+            }
+            Message::PreviewReleased => {
+                let (Some((start, pressed_at)), Some(frame)) =
+                    (self.preview_press.take(), &self.preview)
+                else {
+                    return Task::none();
+                };
+                let action = frame.gesture(start, self.preview_cursor, pressed_at.elapsed());
+                self.perform_preview_action(action)
+            }
+            Message::PreviewKey(key) => self.perform_preview_action(do_action(key, &[])),
+            Message::PreviewActionDone(result) => {
+                if let Err(e) = result {
+                    self.logger.error(format!("{}: {}", m.action_failed, e));
+                }
+                Task::done(Message::RefreshPreview)
+            }
+            Message::ToggleRecording => {
+                if let Some((recorded, _)) = self.recording.take() {
+                    let name = recorded.name.clone();
+                    let saved = AppSettings::macro_path(&name)
+                        .and_then(|path| recorded.save(&path).map_err(|e| e.to_string()));
+                    match saved {
+                        Ok(()) => {
+                            self.logger.success(format!(
+                                "{}: {} ({} {})",
+                                m.macro_saved,
+                                name,
+                                recorded.steps.len(),
+                                m.steps_unit
+                            ));
+                            self.macros = AppSettings::list_macros();
+                            self.selected_macro = Some(name);
+                        }
+                        Err(e) => self.logger.error(format!("{}: {}", m.macro_save_failed, e)),
+                    }
+                } else if let Some(frame) = &self.preview {
+                    if let Err(e) = AppSettings::macro_path(&self.macro_name_input) {
+                        self.logger
+                            .warning(format!("{}: {}", m.enter_macro_name, e));
+                        return Task::none();
+                    }
+                    self.recording = Some((
+                        Macro::new(
+                            self.macro_name_input.trim(),
+                            frame.screen_width,
+                            frame.screen_height,
+                        ),
+                        Instant::now(),
+                    ));
+                    self.logger.info(m.recording_started);
+                } else {
+                    self.logger.warning(m.refresh_preview_first);
+                }
+                Task::none()
+            }
+            Message::MacroNameChanged(value) => {
+                self.macro_name_input = value;
+                Task::none()
+            }
+            Message::MacroSelected(name) => {
+                self.selected_macro = Some(name);
+                Task::none()
+            }
+            Message::PlayMacro => {
+                let Some(name) = self.selected_macro.clone() else {
+                    return Task::none();
+                };
+                self.state = AppState::Replaying;
+                self.status = format!("{}: {}", m.replaying_macro, name);
+                self.logger.info(self.status.clone());
+                let device_id = self.device_id();
+                Task::perform(
+                    async move {
+                        let path = AppSettings::macro_path(&name)?;
+                        let recorded = Macro::load(&path).map_err(|e| e.to_string())?;
+                        let screen = get_screenshot(device_id.as_deref());
+                        recorded
+                            .play(device_id.as_deref(), screen.width, screen.height)
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::MacroPlayed,
+                )
+            }
+            Message::MacroPlayed(result) => {
+                self.state = AppState::Idle;
+                match result {
+                    Ok(steps) => {
+                        self.status = format!("{}: {} {}", m.macro_replayed, steps, m.steps_unit);
+                        self.logger.success(self.status.clone());
+                    }
+                    Err(e) => {
+                        self.status = format!("{}: {}", m.macro_replay_failed, e);
+                        self.logger.error(self.status.clone());
+                    }
+                }
+                Task::done(Message::RefreshPreview)
+            }
+
+            // Calibration
+            Message::RunCalibration => {
+                self.state = AppState::Calibrating;
+                self.logger.info(m.calibration_starting);
+                self.status = m.calibrating.to_string();
+
+                let settings = self.effective_settings();
 
                 Task::perform(
                     async move { run_calibration(settings).await },
@@ -481,18 +1950,27 @@ impl PhoneAgentApp {
             Message::CalibrationCompleted(result) => {
                 self.state = AppState::Idle;
                 match result {
-                    Ok((scale_x, scale_y)) => {
-                        self.settings.scale_x = scale_x;
-                        self.settings.scale_y = scale_y;
-                        self.scale_x_input = format!("{:.4}", scale_x);
-                        self.scale_y_input = format!("{:.4}", scale_y);
-                        self.logger
-                            .success(format!("校准完成: X={:.4}, Y={:.4}", scale_x, scale_y));
-                        self.status = "校准完成".to_string();
+                    Ok(result) => {
+                        // run_calibration already wrote these to the settings file
+                        self.settings.apply_calibration(&result);
+                        self.coord_system = CoordSystemOption::Absolute;
+                        self.calib_mode =
+                            CalibModeOption::from_str(&self.settings.calibration_mode);
+                        self.scale_x_input = format!("{:.4}", result.scale_x);
+                        self.scale_y_input = format!("{:.4}", result.scale_y);
+                        self.logger.success(format!(
+                            "{}: X={:.4}{:+.0}, Y={:.4}{:+.0}",
+                            m.calibration_completed,
+                            result.scale_x,
+                            result.offset_x,
+                            result.scale_y,
+                            result.offset_y
+                        ));
+                        self.status = m.calibration_completed.to_string();
                     }
                     Err(e) => {
-                        self.logger.error(format!("校准失败: {}", e));
-                        self.status = format!("校准失败: {}", e);
+                        self.status = format!("{}: {}", m.calibration_failed, e);
+                        self.logger.error(self.status.clone());
                     }
                 }
                 Task::none()
@@ -501,7 +1979,40 @@ impl PhoneAgentApp {
             // Logs
             Message::ClearLogs => {
                 self.logger.clear();
-                self.logger.info("日志已清空");
+                self.logger.info(m.logs_cleared);
+                Task::none()
+            }
+            Message::ExportDirChanged(value) => {
+                self.settings.export_dir = value;
+                Task::none()
+            }
+            Message::ExportReport => {
+                let Some(dir) = self.settings.exports_dir() else {
+                    self.logger
+                        .error(format!("{}: {}", m.export_failed, m.not_created));
+                    return Task::none();
+                };
+                // The running task, or else the last one
+                let task = self
+                    .running_task
+                    .as_ref()
+                    .map(|(task, _)| task.clone())
+                    .or_else(|| self.history.entries.first().map(|e| e.task.clone()));
+                let steps = self.timeline.clone();
+                let logs = self.logger.entries().to_vec();
+                Task::perform(
+                    async move { export_report(&dir, task.as_deref(), &steps, &logs, m) },
+                    Message::ReportExported,
+                )
+            }
+            Message::ReportExported(Ok(path)) => {
+                let message = format!("{}: {}", m.report_exported, path.display());
+                self.logger.success(message.clone());
+                self.toasts.push(message);
+                Task::none()
+            }
+            Message::ReportExported(Err(e)) => {
+                self.logger.error(format!("{}: {}", m.export_failed, e));
                 Task::none()
             }
         }
@@ -511,6 +2022,9 @@ impl PhoneAgentApp {
     pub fn view(&self) -> Element<'_, Message> {
         let content = match self.view {
             View::Main => self.view_main(),
+            View::Timeline => self.view_timeline(),
+            View::History => self.view_history(),
+            View::Preview => self.view_preview(),
             View::Settings => self.view_settings(),
             View::Logs => self.view_logs(),
         };
@@ -518,15 +2032,104 @@ impl PhoneAgentApp {
         let nav_bar = self.view_nav_bar();
         let status_bar = self.view_status_bar();
 
-        column![nav_bar, content, status_bar]
+        let page = column![nav_bar, content, status_bar]
             .spacing(10)
-            .padding(20)
+            .padding(20);
+
+        let mut layers = stack![page, self.view_toasts()];
+        if let Some((message, deadline)) = &self.confirmation {
+            layers = layers.push(self.view_confirmation(message, *deadline));
+        }
+        layers.into()
+    }
+
+    /// Toasts in the bottom right corner, newest at the bottom.
+    fn view_toasts(&self) -> Element<'_, Message> {
+        let toasts = column(self.toasts.iter().map(|toast| {
+            container(
+                row![
+                    text(&toast.text).size(14).width(Length::Fill),
+                    button(text("✕").size(12))
+                        .on_press(Message::DismissToast(toast.id))
+                        .style(button::text),
+                ]
+                .spacing(8),
+            )
+            .width(TOAST_WIDTH)
+            .padding(10)
+            .style(container::bordered_box)
+            .into()
+        }))
+        .spacing(8);
+
+        container(toasts)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_right(Length::Fill)
+            .align_bottom(Length::Fill)
+            .padding([70, 30])
             .into()
     }
 
+    /// Dialog asking whether the agent may perform a sensitive operation. It
+    /// covers the page, which can't be used until it is answered or its
+    /// deadline passes.
+    fn view_confirmation<'a>(
+        &self,
+        message: &'a str,
+        deadline: Option<Instant>,
+    ) -> Element<'a, Message> {
+        let m = self.messages();
+        let countdown = match deadline {
+            Some(deadline) => {
+                let left = seconds_left(deadline, Instant::now());
+                text(format!("⏱️ {} {}s", m.auto_deny_in, left)).size(12)
+            }
+            None => text(""),
+        };
+        let dialog = container(
+            column![
+                text(format!("⚠️ {}", m.confirmation_required)).size(20),
+                text(m.confirmation_hint).size(14),
+                text(message).size(16),
+                countdown,
+                row![
+                    horizontal_space(),
+                    button(text(m.reject))
+                        .on_press(Message::ConfirmAction(false))
+                        .style(button::danger)
+                        .padding([8, 16]),
+                    button(text(m.confirm))
+                        .on_press(Message::ConfirmAction(true))
+                        .style(button::success)
+                        .padding([8, 16]),
+                ]
+                .spacing(10),
+            ]
+            .spacing(12),
+        )
+        .width(420)
+        .padding(20)
+        .style(container::bordered_box);
+
+        opaque(center(opaque(dialog)).style(|_theme| {
+            container::Style {
+                background: Some(
+                    Color {
+                        a: 0.6,
+                        ..Color::BLACK
+                    }
+                    .into(),
+                ),
+                ..container::Style::default()
+            }
+        }))
+    }
+
     /// Navigation bar.
     fn view_nav_bar(&self) -> Element<'_, Message> {
-        let main_btn = button(text("🏠 主页"))
+        let m = self.messages();
+        let main_btn = button(text(m.tab_main))
             .on_press(Message::SwitchView(View::Main))
             .style(if self.view == View::Main {
                 button::primary
@@ -534,7 +2137,31 @@ impl PhoneAgentApp {
                 button::secondary
             });
 
-        let settings_btn = button(text("⚙️ 设置"))
+        let timeline_btn = button(text(m.tab_timeline))
+            .on_press(Message::SwitchView(View::Timeline))
+            .style(if self.view == View::Timeline {
+                button::primary
+            } else {
+                button::secondary
+            });
+
+        let history_btn = button(text(m.tab_history))
+            .on_press(Message::SwitchView(View::History))
+            .style(if self.view == View::History {
+                button::primary
+            } else {
+                button::secondary
+            });
+
+        let preview_btn = button(text(m.tab_preview))
+            .on_press(Message::SwitchView(View::Preview))
+            .style(if self.view == View::Preview {
+                button::primary
+            } else {
+                button::secondary
+            });
+
+        let settings_btn = button(text(m.tab_settings))
             .on_press(Message::SwitchView(View::Settings))
             .style(if self.view == View::Settings {
                 button::primary
@@ -542,7 +2169,7 @@ impl PhoneAgentApp {
                 button::secondary
             });
 
-        let logs_btn = button(text("📋 日志"))
+        let logs_btn = button(text(m.tab_logs))
             .on_press(Message::SwitchView(View::Logs))
             .style(if self.view == View::Logs {
                 button::primary
@@ -550,48 +2177,121 @@ impl PhoneAgentApp {
                 button::secondary
             });
 
-        row![main_btn, settings_btn, logs_btn].spacing(10).into()
+        row![
+            main_btn,
+            timeline_btn,
+            history_btn,
+            preview_btn,
+            settings_btn,
+            logs_btn
+        ]
+        .spacing(10)
+        .into()
     }
 
     /// Status bar.
     fn view_status_bar(&self) -> Element<'_, Message> {
+        let m = self.messages();
         let state_text = match self.state {
-            AppState::Idle => "🟢 就绪",
-            AppState::Running => "🔵 运行中",
-            AppState::Calibrating => "🟡 校准中",
+            AppState::Idle => m.state_idle,
+            AppState::Running => m.state_running,
+            AppState::Calibrating => m.state_calibrating,
+            AppState::Replaying => m.state_replaying,
         };
 
         row![
             text(state_text).size(14),
+            self.view_model_status(),
+            self.view_device_status(),
             horizontal_space(),
             text(&self.status).size(14),
         ]
+        .spacing(20)
         .padding(10)
         .into()
     }
 
+    /// Whether the model server answered the last check.
+    fn view_model_status(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let model = &self.settings.model_name;
+        let (icon, detail) = match &self.model_health {
+            None => ("⚪", m.checking.to_string()),
+            Some(Err(e)) => ("🔴", e.clone()),
+            // Servers that list models should offer the configured one
+            Some(Ok(models)) if !models.is_empty() && !models.contains(model) => {
+                ("🟡", format!("{}: {}", m.model_not_listed, model))
+            }
+            Some(Ok(_)) => ("🟢", format!("{}: {}", m.reachable, self.settings.base_url)),
+        };
+        status_indicator(icon, m.model, detail)
+    }
+
+    /// Whether the configured device (or any, when none is set) was online
+    /// in the last check.
+    fn view_device_status(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let (icon, detail) = match &self.device_health {
+            None => ("⚪", m.checking.to_string()),
+            Some(Err(e)) => ("🔴", e.clone()),
+            Some(Ok(devices)) => {
+                let device = match self.device_id() {
+                    Some(id) => devices.iter().find(|d| d.device_id == id),
+                    None => devices
+                        .iter()
+                        .find(|d| d.status == "device")
+                        .or(devices.first()),
+                };
+                match device {
+                    Some(d) if d.status == "device" => ("🟢", d.device_id.clone()),
+                    Some(d) => ("🟡", format!("{}: {}", d.device_id, d.status)),
+                    None => match self.device_id() {
+                        Some(id) => ("🔴", format!("{}: {}", id, m.not_connected)),
+                        None => ("🔴", m.no_device.to_string()),
+                    },
+                }
+            }
+        };
+        status_indicator(icon, m.device, detail)
+    }
+
     /// Main view with task input and execution.
     fn view_main(&self) -> Element<'_, Message> {
+        let m = self.messages();
         let title = text("📱 Phone Agent").size(28);
 
-        let task_input = text_input("输入任务，例如: 打开微信", &self.task_input)
+        let task_input = text_input(m.task_placeholder, &self.task_input)
             .on_input(Message::TaskInputChanged)
+            .on_submit(Message::RunTask)
             .padding(10)
             .size(16);
 
-        let run_btn = if self.state == AppState::Idle {
-            button(text("▶️ 运行").size(16))
+        let stop_btn = button(text(m.stop).size(16))
+            .on_press(Message::StopTask)
+            .style(button::danger)
+            .padding([10, 20]);
+        // A running dual loop takes further requests
+        let run_btn: Element<'_, Message> = if self.dual_loop.is_some() {
+            row![
+                button(text(m.send).size(16))
+                    .on_press(Message::RunTask)
+                    .style(button::success)
+                    .padding([10, 20]),
+                stop_btn,
+            ]
+            .spacing(10)
+            .into()
+        } else if self.state == AppState::Idle {
+            button(text(m.run).size(16))
                 .on_press(Message::RunTask)
                 .style(button::success)
                 .padding([10, 20])
+                .into()
         } else {
-            button(text("⏹️ 停止").size(16))
-                .on_press(Message::StopTask)
-                .style(button::danger)
-                .padding([10, 20])
+            stop_btn.into()
         };
 
-        let calibrate_btn = button(text("🎯 校准").size(16))
+        let calibrate_btn = button(text(m.calibrate).size(16))
             .on_press(Message::RunCalibration)
             .padding([10, 20]);
 
@@ -606,23 +2306,437 @@ impl PhoneAgentApp {
             .height(Length::Fill)
             .padding(10)
             .style(container::bordered_box);
+        let log_panel = column![text(m.execution_log).size(16), log_container]
+            .spacing(5)
+            .width(Length::Fill);
+
+        // In dual-loop mode the planner conversation sits next to the log
+        let bottom: Element<'_, Message> =
+            if self.dual_loop.is_some() || !self.planner_chat.is_empty() {
+                row![self.view_planner_chat(), log_panel]
+                    .spacing(10)
+                    .height(Length::Fill)
+                    .into()
+            } else {
+                log_panel.into()
+            };
 
         column![
             title,
             vertical_space().height(10),
             task_row,
+            self.view_takeover_banner(true),
+            self.view_todos(),
+            self.view_usage(),
+            bottom,
+        ]
+        .spacing(5)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// What the agent needs the user to do during a takeover, with a button to
+    /// hand control back (and one to open the control pad, if `link`).
+    /// Empty when there is no takeover.
+    fn view_takeover_banner(&self, link: bool) -> Element<'_, Message> {
+        let Some(message) = &self.takeover else {
+            return row![].into();
+        };
+        let m = self.messages();
+
+        let mut actions = row![].spacing(10);
+        if link {
+            actions = actions.push(
+                button(text(m.open_control_pad))
+                    .on_press(Message::SwitchView(View::Preview))
+                    .style(button::secondary),
+            );
+        }
+        actions = actions.push(
+            button(text(m.resume_agent))
+                .on_press(Message::ResumeAgent)
+                .style(button::success),
+        );
+
+        container(
+            column![
+                text(format!("✋ {}", m.manual_operation_required)).size(16),
+                text(message).size(14),
+                text(m.manual_operation_hint).size(12),
+                actions,
+            ]
+            .spacing(8),
+        )
+        .width(Length::Fill)
+        .padding(10)
+        .style(container::bordered_box)
+        .into()
+    }
+
+    /// The dual loop's todo list. Rows are dragged to reorder them and
+    /// pending ones can be edited. Empty outside dual-loop mode.
+    fn view_todos(&self) -> Element<'_, Message> {
+        if self.dual_loop.is_none() && self.todo_items.is_empty() {
+            return row![].into();
+        }
+        let m = self.messages();
+
+        let mut list = column![].spacing(4);
+        for (i, item) in self.todo_items.iter().enumerate() {
+            let editing = self.todo_edit.as_ref().filter(|(id, _)| *id == item.id);
+            let row_content: Element<'_, Message> = match editing {
+                Some((_, description)) => row![
+                    text(item.status.emoji()),
+                    text_input(m.task_placeholder, description)
+                        .on_input(Message::TodoEditChanged)
+                        .on_submit(Message::SaveTodoEdit)
+                        .width(Length::Fill),
+                    button(text(m.save))
+                        .on_press(Message::SaveTodoEdit)
+                        .style(button::success),
+                    button(text(m.cancel))
+                        .on_press(Message::CancelTodoEdit)
+                        .style(button::secondary),
+                ]
+                .spacing(8)
+                .into(),
+                None => {
+                    let mut content = row![
+                        text("⠿").size(14),
+                        text(item.status.emoji()),
+                        text(&item.description).size(14).width(Length::Fill),
+                    ]
+                    .spacing(8);
+                    if item.status == TodoStatus::Pending {
+                        content = content.push(
+                            button(text(m.edit).size(12))
+                                .on_press(Message::EditTodo(i))
+                                .style(button::secondary),
+                        );
+                    }
+                    mouse_area(content)
+                        .on_press(Message::TodoDragStart(i))
+                        .on_enter(Message::TodoDragOver(i))
+                        .interaction(mouse::Interaction::Grab)
+                        .into()
+                }
+            };
+
+            let style = match self.todo_drag {
+                Some((from, over)) if over == i && from != i => container::bordered_box,
+                Some((from, _)) if from == i => container::rounded_box,
+                _ => container::transparent,
+            };
+            list = list.push(
+                container(row_content)
+                    .width(Length::Fill)
+                    .padding([4, 8])
+                    .style(style),
+            );
+        }
+
+        // Releasing anywhere in the panel drops the dragged row on the one
+        // under the cursor; leaving the panel cancels the drag
+        mouse_area(
+            container(
+                column![
+                    text(format!("📝 {} ({})", m.todo_title, self.todo_items.len())).size(16),
+                    text(m.todo_hint).size(12),
+                    container(scrollable(list)).max_height(240),
+                ]
+                .spacing(5),
+            )
+            .width(Length::Fill)
+            .padding(10)
+            .style(container::rounded_box),
+        )
+        .on_release(Message::TodoDrop)
+        .on_exit(Message::TodoDragCancel)
+        .into()
+    }
+
+    /// The planner conversation of the dual loop: user requests, planner
+    /// replies and system feedback, with a box to message the planner.
+    fn view_planner_chat(&self) -> Element<'_, Message> {
+        let m = self.messages();
+
+        let mut lines = column![].spacing(8);
+        for (role, content) in &self.planner_chat {
+            let (label, style): (_, fn(&Theme) -> container::Style) = match role {
+                ChatRole::User => (format!("👤 {}", m.you), container::bordered_box),
+                ChatRole::Planner => (format!("🧠 {}", m.planner), container::rounded_box),
+                ChatRole::System => (format!("⚙️ {}", m.system), container::transparent),
+            };
+            lines = lines.push(
+                container(column![text(label).size(12), text(content).size(13)].spacing(2))
+                    .width(Length::Fill)
+                    .padding(8)
+                    .style(style),
+            );
+        }
+
+        let mut input = text_input(m.planner_chat_placeholder, &self.planner_input)
+            .padding(8)
+            .size(14);
+        let mut send = button(text(m.send)).style(button::success);
+        if self.dual_loop.is_some() {
+            input = input
+                .on_input(Message::PlannerInputChanged)
+                .on_submit(Message::SendToPlanner);
+            send = send.on_press(Message::SendToPlanner);
+        }
+
+        column![
+            text(format!("💬 {}", m.planner_chat_title)).size(16),
+            container(scrollable(lines).anchor_bottom().height(Length::Fill))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(10)
+                .style(container::bordered_box),
+            row![input, send].spacing(10),
+        ]
+        .spacing(5)
+        .width(Length::Fill)
+        .into()
+    }
+
+    /// Token usage of the current task and the session, with estimated cost.
+    fn view_usage(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let price = self.settings.executor_price_per_million;
+        let line = |label: &str, usage: &TokenUsage| {
+            text(format!(
+                "{}: {} tokens ({} {} / {} {}) · {} {:.4}",
+                label,
+                usage.total_tokens,
+                m.usage_input,
+                usage.prompt_tokens,
+                m.usage_output,
+                usage.completion_tokens,
+                m.estimated_cost,
+                estimate_cost(usage, price)
+            ))
+            .size(13)
+        };
+
+        let mut details = column![
+            text(m.usage_title).size(16),
+            line(m.usage_task, &self.usage.task),
+            line(m.usage_session, &self.usage.session),
+        ]
+        .spacing(5);
+        for model in &self.usage.models {
+            details = details.push(
+                text(format!(
+                    "🤖 {}: {} {} · {} tokens",
+                    model.model, model.calls, m.usage_calls, model.usage.total_tokens
+                ))
+                .size(12),
+            );
+        }
+
+        container(details)
+            .width(Length::Fill)
+            .padding(10)
+            .style(container::rounded_box)
+            .into()
+    }
+
+    /// Timeline view with one card per step.
+    fn view_timeline(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let title = text(format!(
+            "{} ({} {})",
+            m.tab_timeline,
+            self.timeline.len(),
+            m.steps_unit
+        ))
+        .size(28);
+        let export_btn = button(text(m.export_report))
+            .on_press(Message::ExportReport)
+            .style(button::primary);
+        let header = row![title, horizontal_space(), export_btn];
+
+        let steps: Element<'_, Message> = if self.timeline.is_empty() {
+            text(m.timeline_empty).size(14).into()
+        } else {
+            column(self.timeline.iter().map(|entry| timeline_card(entry, m)))
+                .spacing(10)
+                .into()
+        };
+
+        let timeline_container = container(scrollable(steps).height(Length::Fill))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(10)
+            .style(container::bordered_box);
+
+        column![header, vertical_space().height(10), timeline_container]
+            .spacing(10)
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// Finished tasks with a button to run each again.
+    fn view_history(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let title = text(format!(
+            "{} ({})",
+            m.history_title,
+            self.history.entries.len()
+        ))
+        .size(28);
+
+        let clear_btn = button(text(m.clear))
+            .on_press(Message::ClearHistory)
+            .style(button::danger);
+
+        let can_run = self.state == AppState::Idle;
+        let tasks: Element<'_, Message> = if self.history.entries.is_empty() {
+            text(m.history_empty).size(14).into()
+        } else {
+            column(
+                self.history
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| history_card(i, entry, can_run, m)),
+            )
+            .spacing(10)
+            .into()
+        };
+
+        let history_container = container(scrollable(tasks).height(Length::Fill))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(10)
+            .style(container::bordered_box);
+
+        column![
+            row![title, horizontal_space(), clear_btn].align_y(iced::Alignment::Center),
             vertical_space().height(10),
-            text("📜 执行日志").size(16),
-            log_container,
+            history_container
+        ]
+        .spacing(10)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Live device preview with macro recording and replay.
+    fn view_preview(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let title = text(m.preview_title).size(28);
+
+        let screen: Element<'_, Message> = match &self.preview {
+            Some(frame) => mouse_area(
+                image(frame.handle.clone())
+                    .width(PREVIEW_WIDTH)
+                    .height(frame.display_height()),
+            )
+            .on_move(Message::PreviewMoved)
+            .on_press(Message::PreviewPressed)
+            .on_release(Message::PreviewReleased)
+            .into(),
+            None => container(text(m.no_preview).size(14))
+                .width(PREVIEW_WIDTH)
+                .padding(10)
+                .into(),
+        };
+
+        let keys = row![
+            button(text(m.refresh))
+                .on_press(Message::RefreshPreview)
+                .style(button::secondary),
+            button(text(m.back))
+                .on_press(Message::PreviewKey("Back"))
+                .style(button::secondary),
+            button(text(m.home))
+                .on_press(Message::PreviewKey("Home"))
+                .style(button::secondary),
+            button(text(m.recents))
+                .on_press(Message::Recents)
+                .style(button::secondary),
+        ]
+        .spacing(10);
+
+        let typing = row![
+            text_input(m.type_placeholder, &self.manual_text)
+                .on_input(Message::ManualTextChanged)
+                .on_submit(Message::ManualType)
+                .width(250),
+            button(text(m.type_text))
+                .on_press(Message::ManualType)
+                .style(button::secondary),
+        ]
+        .spacing(10);
+
+        let record_btn = match &self.recording {
+            Some((recorded, _)) => button(text(format!(
+                "{} ({} {})",
+                m.stop_and_save,
+                recorded.steps.len(),
+                m.steps_unit
+            )))
+            .on_press(Message::ToggleRecording)
+            .style(button::danger),
+            None => button(text(m.record))
+                .on_press(Message::ToggleRecording)
+                .style(button::success),
+        };
+        let record = row![
+            text_input(m.macro_name, &self.macro_name_input)
+                .on_input(Message::MacroNameChanged)
+                .width(200),
+            record_btn,
+        ]
+        .spacing(10);
+
+        let mut play_btn = button(text(m.replay)).style(button::primary);
+        if self.state == AppState::Idle && self.recording.is_none() {
+            play_btn =
+                play_btn.on_press_maybe(self.selected_macro.as_ref().map(|_| Message::PlayMacro));
+        }
+        let replay = row![
+            pick_list(
+                self.macros.clone(),
+                self.selected_macro.clone(),
+                Message::MacroSelected,
+            )
+            .placeholder(m.select_macro)
+            .width(200),
+            play_btn,
+        ]
+        .spacing(10);
+
+        let controls = column![
+            text(m.control_pad).size(18),
+            keys,
+            typing,
+            text(m.gesture_hint).size(12),
+            horizontal_rule(1),
+            text(m.macros_title).size(18),
+            record,
+            replay,
+        ]
+        .spacing(10);
+
+        column![
+            title,
+            self.view_takeover_banner(false),
+            row![screen, controls].spacing(20),
         ]
-        .spacing(5)
+        .spacing(10)
         .height(Length::Fill)
         .into()
     }
 
     /// Settings view.
     fn view_settings(&self) -> Element<'_, Message> {
-        let title = text("⚙️ 设置").size(28);
+        let m = self.messages();
+        let title = text(m.tab_settings).size(28);
+
+        // Settings profiles section
+        let profile_section = self.view_profile_settings();
 
         // Model settings section
         let model_section = self.view_model_settings();
@@ -630,6 +2744,12 @@ impl PhoneAgentApp {
         // Device settings section
         let device_section = self.view_device_settings();
 
+        // Appearance settings section
+        let appearance_section = self.view_appearance_settings();
+
+        // Dual-loop settings section
+        let dual_loop_section = self.view_dual_loop_settings();
+
         // Coordinate settings section
         let coord_section = self.view_coord_settings();
 
@@ -639,13 +2759,16 @@ impl PhoneAgentApp {
         // Calibration settings section
         let calib_section = self.view_calib_settings();
 
+        // App hints section
+        let app_hints_section = self.view_app_hints_settings();
+
         // Action buttons
-        let save_btn = button(text("💾 保存设置"))
+        let save_btn = button(text(m.save_settings))
             .on_press(Message::SaveSettings)
             .style(button::success)
             .padding([10, 20]);
 
-        let reset_btn = button(text("🔄 重置默认"))
+        let reset_btn = button(text(m.reset_defaults))
             .on_press(Message::ResetSettings)
             .style(button::secondary)
             .padding([10, 20]);
@@ -655,15 +2778,23 @@ impl PhoneAgentApp {
         let content = column![
             title,
             vertical_space().height(10),
+            profile_section,
+            horizontal_rule(1),
             model_section,
             horizontal_rule(1),
             device_section,
             horizontal_rule(1),
+            appearance_section,
+            horizontal_rule(1),
+            dual_loop_section,
+            horizontal_rule(1),
             coord_section,
             horizontal_rule(1),
             retry_section,
             horizontal_rule(1),
             calib_section,
+            horizontal_rule(1),
+            app_hints_section,
             vertical_space().height(20),
             actions,
         ]
@@ -673,49 +2804,204 @@ impl PhoneAgentApp {
         scrollable(content).height(Length::Fill).into()
     }
 
+    fn view_profile_settings(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let section_title = text(m.profiles_title).size(18);
+
+        let selected = Some(self.settings.active_profile.clone()).filter(|p| !p.is_empty());
+        let mut delete_btn = button(text(m.delete)).style(button::danger);
+        if selected.is_some() {
+            delete_btn = delete_btn.on_press(Message::DeleteProfile);
+        }
+        let profile_picker = row![
+            text(m.current_profile).width(120),
+            pick_list(self.profiles.clone(), selected, Message::ProfileSelected)
+                .placeholder(m.no_profile)
+                .width(300),
+            delete_btn,
+        ]
+        .spacing(10);
+
+        let save_as = row![
+            text(m.save_as).width(120),
+            text_input(m.profile_placeholder, &self.profile_name_input)
+                .on_input(Message::ProfileNameChanged)
+                .on_submit(Message::SaveProfile)
+                .width(300),
+            button(text(m.save_profile))
+                .on_press(Message::SaveProfile)
+                .style(button::secondary),
+        ]
+        .spacing(10);
+
+        let mut import_btn = button(text(m.import_settings)).style(button::secondary);
+        if !self.settings_file_input.trim().is_empty() {
+            import_btn = import_btn.on_press(Message::ImportSettings);
+        }
+        let settings_file = row![
+            text(m.settings_file).width(120),
+            text_input(m.settings_file_placeholder, &self.settings_file_input)
+                .on_input(Message::SettingsFileChanged)
+                .width(300),
+            button(text(m.export_settings))
+                .on_press(Message::ExportSettings)
+                .style(button::secondary),
+            import_btn,
+        ]
+        .spacing(10);
+
+        let save_as = self.with_field_error("active_profile", save_as);
+
+        column![section_title, profile_picker, save_as, settings_file]
+            .spacing(10)
+            .into()
+    }
+
     fn view_model_settings(&self) -> Element<'_, Message> {
-        let section_title = text("🤖 模型配置").size(18);
+        let m = self.messages();
+        let section_title = text(m.model_section).size(18);
 
         let base_url = labeled_input(
-            "API 地址",
+            m.api_url,
             &self.settings.base_url,
             "http://localhost:8000/v1",
             Message::BaseUrlChanged,
         );
 
         let api_key = labeled_input(
-            "API 密钥",
+            m.api_key,
             &self.settings.api_key,
             "EMPTY",
             Message::ApiKeyChanged,
         );
 
+        let plaintext_toggle = row![
+            text(m.plaintext_api_keys).width(120),
+            toggler(self.settings.plaintext_api_keys).on_toggle(Message::PlaintextApiKeysToggled),
+        ]
+        .spacing(10);
+
         let model_name = labeled_input(
-            "模型名称",
+            m.model_name,
             &self.settings.model_name,
             "autoglm-phone-9b",
             Message::ModelNameChanged,
         );
 
-        column![section_title, base_url, api_key, model_name]
-            .spacing(10)
-            .into()
+        let price = labeled_input(
+            m.price_per_million,
+            &self.price_input,
+            "0",
+            Message::PriceChanged,
+        );
+
+        let base_url = self.with_field_error("base_url", base_url);
+        let api_key = self.with_field_error("api_key", api_key);
+
+        column![
+            section_title,
+            base_url,
+            api_key,
+            plaintext_toggle,
+            model_name,
+            price
+        ]
+        .spacing(10)
+        .into()
     }
 
-    fn view_device_settings(&self) -> Element<'_, Message> {
-        let section_title = text("📱 设备配置").size(18);
+    fn view_dual_loop_settings(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let section_title = text(m.dual_loop_section).size(18);
+
+        let enable_toggle = row![
+            text(m.dual_loop_mode).width(120),
+            toggler(self.settings.dual_loop_mode).on_toggle(Message::DualLoopToggled),
+        ]
+        .spacing(10);
+
+        let base_url = labeled_input(
+            m.api_url,
+            &self.settings.planner_base_url,
+            "https://api.openai.com/v1",
+            Message::PlannerBaseUrlChanged,
+        );
+
+        let api_key = labeled_input(
+            m.api_key,
+            &self.settings.planner_api_key,
+            "sk-...",
+            Message::PlannerApiKeyChanged,
+        );
 
-        let device_id = labeled_input(
-            "设备 ID (可选)",
-            &self.settings.device_id,
-            "留空自动检测",
-            Message::DeviceIdChanged,
+        let model_name = labeled_input(
+            m.model_name,
+            &self.settings.planner_model_name,
+            "gpt-4o",
+            Message::PlannerModelNameChanged,
         );
 
+        let base_url = self.with_field_error("planner_base_url", base_url);
+        let api_key = self.with_field_error("planner_api_key", api_key);
+
+        // Entry to pick no preset, then the available ones
+        let mut presets = vec![m.none.to_string()];
+        presets.extend(PlannerPreset::available(&self.settings.planner_presets));
+        let selected = match self.settings.planner_preset.as_str() {
+            "" => m.none.to_string(),
+            name => name.to_string(),
+        };
+        let description = PlannerPreset::find(
+            &self.settings.planner_preset,
+            &self.settings.planner_presets,
+        )
+        .map(|preset| preset.description)
+        .unwrap_or_default();
+        let preset_picker = column![
+            row![
+                text(m.planner_preset).width(120),
+                pick_list(presets, Some(selected), Message::PlannerPresetSelected).width(300),
+            ]
+            .spacing(10),
+            row![horizontal_space().width(130), text(description).size(12)],
+        ]
+        .spacing(4);
+        let preset_picker = self.with_field_error("planner_preset", preset_picker);
+
+        column![
+            section_title,
+            enable_toggle,
+            preset_picker,
+            base_url,
+            api_key,
+            model_name
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn view_device_settings(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let section_title = text(m.device_section).size(18);
+
+        let options = self.device_options();
+        let selected = options
+            .iter()
+            .find(|o| o.id == self.settings.device_id)
+            .cloned();
+        let device_picker = row![
+            text(m.device).width(120),
+            pick_list(options, selected, Message::DeviceSelected).width(300),
+            button(text(m.refresh))
+                .on_press(Message::RefreshDevices)
+                .style(button::secondary),
+        ]
+        .spacing(10);
+
         let lang_picker = row![
-            text("语言").width(120),
+            text(m.language).width(120),
             pick_list(
-                vec![Language::Chinese, Language::English],
+                Language::all(),
                 Some(self.language),
                 Message::LanguageSelected,
             )
@@ -723,20 +3009,118 @@ impl PhoneAgentApp {
         ]
         .spacing(10);
 
-        column![section_title, device_id, lang_picker]
+        let choice = |value: AppRegion| Choice {
+            value,
+            label: match value {
+                AppRegion::China => m.region_china,
+                AppRegion::International => m.region_international,
+            },
+        };
+        let region_picker = row![
+            text(m.app_region).width(120),
+            pick_list(
+                vec![choice(AppRegion::China), choice(AppRegion::International)],
+                Some(choice(
+                    AppRegion::from_name(&self.settings.app_region).unwrap_or_default()
+                )),
+                |c| Message::AppRegionSelected(c.value),
+            )
+            .width(200),
+        ]
+        .spacing(10);
+
+        let region_picker = self.with_field_error("app_region", region_picker);
+
+        column![section_title, device_picker, lang_picker, region_picker]
+            .spacing(10)
+            .into()
+    }
+
+    fn view_appearance_settings(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let section_title = text(m.appearance_section).size(18);
+
+        let choice = |value: ThemeOption| Choice {
+            value,
+            label: value.label(m),
+        };
+        let theme_picker = row![
+            text(m.theme).width(120),
+            pick_list(
+                vec![
+                    choice(ThemeOption::System),
+                    choice(ThemeOption::Light),
+                    choice(ThemeOption::Dark),
+                ],
+                Some(choice(self.theme)),
+                |c| Message::ThemeSelected(c.value),
+            )
+            .width(200),
+        ]
+        .spacing(10);
+
+        let presets = ACCENT_PRESETS.iter().filter_map(|&hex| {
+            let color = Color::parse(hex)?;
+            Some(
+                button(text("  "))
+                    .on_press(Message::AccentColorChanged(hex.to_string()))
+                    .style(move |theme, status| button::Style {
+                        background: Some(color.into()),
+                        ..button::primary(theme, status)
+                    })
+                    .into(),
+            )
+        });
+        let accent = row![
+            text(m.accent_color).width(120),
+            text_input(m.accent_placeholder, &self.settings.accent_color)
+                .on_input(Message::AccentColorChanged)
+                .width(200),
+            row(presets).spacing(5),
+        ]
+        .spacing(10);
+
+        column![section_title, theme_picker, accent]
             .spacing(10)
             .into()
     }
 
+    /// Picker entries: automatic detection, every listed device, and the
+    /// configured device if it isn't connected.
+    fn device_options(&self) -> Vec<DeviceOption> {
+        let mut options = vec![DeviceOption::auto(self.language)];
+        options.extend(self.devices.iter().map(|info| DeviceOption {
+            id: info.device_id.clone(),
+            info: Some(info.clone()),
+            language: self.language,
+        }));
+        if !options.iter().any(|o| o.id == self.settings.device_id) {
+            options.push(DeviceOption {
+                id: self.settings.device_id.clone(),
+                info: None,
+                language: self.language,
+            });
+        }
+        options
+    }
+
     fn view_coord_settings(&self) -> Element<'_, Message> {
-        let section_title = text("📐 坐标系统").size(18);
+        let m = self.messages();
+        let section_title = text(m.coordinate_section).size(18);
 
+        let choice = |value: CoordSystemOption| Choice {
+            value,
+            label: value.label(m),
+        };
         let coord_picker = row![
-            text("坐标系统").width(120),
+            text(m.coordinate_system).width(120),
             pick_list(
-                vec![CoordSystemOption::Absolute, CoordSystemOption::Relative],
-                Some(self.coord_system),
-                Message::CoordSystemSelected,
+                vec![
+                    choice(CoordSystemOption::Absolute),
+                    choice(CoordSystemOption::Relative)
+                ],
+                Some(choice(self.coord_system)),
+                |c| Message::CoordSystemSelected(c.value),
             )
             .width(200),
         ]
@@ -744,73 +3128,190 @@ impl PhoneAgentApp {
 
         let scale_inputs = if self.coord_system == CoordSystemOption::Absolute {
             let scale_x = labeled_input(
-                "缩放比例 X",
+                m.scale_x,
                 &self.scale_x_input,
                 "1.61",
                 Message::ScaleXChanged,
             );
 
             let scale_y = labeled_input(
-                "缩放比例 Y",
+                m.scale_y,
                 &self.scale_y_input,
                 "1.61",
                 Message::ScaleYChanged,
             );
 
-            column![scale_x, scale_y].spacing(10)
+            column![
+                self.with_field_error("scale_x", scale_x),
+                self.with_field_error("scale_y", scale_y)
+            ]
+            .spacing(10)
         } else {
-            column![text("相对坐标模式下不需要缩放设置").size(14)]
+            column![text(m.no_scale_needed).size(14)]
         };
 
+        let coord_picker = self.with_field_error("coordinate_system", coord_picker);
+
         column![section_title, coord_picker, scale_inputs]
             .spacing(10)
             .into()
     }
 
     fn view_retry_settings(&self) -> Element<'_, Message> {
-        let section_title = text("🔄 重试配置").size(18);
+        let m = self.messages();
+        let section_title = text(m.retry_section).size(18);
 
         let max_retries = labeled_input(
-            "最大重试次数",
+            m.max_retries,
             &self.max_retries_input,
             "3",
             Message::MaxRetriesChanged,
         );
 
         let retry_delay = labeled_input(
-            "重试延迟(秒)",
+            m.retry_delay,
             &self.retry_delay_input,
             "2",
             Message::RetryDelayChanged,
         );
 
         let max_steps = labeled_input(
-            "最大步骤数",
+            m.max_steps,
             &self.max_steps_input,
             "100",
             Message::MaxStepsChanged,
         );
 
-        column![section_title, max_retries, retry_delay, max_steps]
-            .spacing(10)
-            .into()
+        let choice = |value: PromptStyle| Choice {
+            value,
+            label: match value {
+                PromptStyle::Full => m.prompt_full,
+                PromptStyle::Concise => m.prompt_concise,
+            },
+        };
+        let prompt_style = row![
+            text(m.prompt_style).width(120),
+            pick_list(
+                vec![choice(PromptStyle::Full), choice(PromptStyle::Concise)],
+                Some(choice(
+                    PromptStyle::from_name(&self.settings.prompt_style).unwrap_or_default()
+                )),
+                |c| Message::PromptStyleSelected(c.value),
+            )
+            .width(200),
+        ]
+        .spacing(10);
+
+        let confirmation_timeout = labeled_input(
+            m.confirmation_timeout,
+            &self.confirmation_timeout_input,
+            "60",
+            Message::ConfirmationTimeoutChanged,
+        );
+
+        let prompt_variant = row![
+            text(m.prompt_variant).width(120),
+            pick_list(
+                prompt_variants(),
+                Some(self.settings.prompt_variant.clone()),
+                Message::PromptVariantSelected,
+            )
+            .width(200),
+        ]
+        .spacing(10);
+
+        let disabled_actions = labeled_input(
+            m.disabled_actions,
+            &self.disabled_actions_input,
+            "Type, Launch",
+            Message::DisabledActionsChanged,
+        );
+
+        column![
+            section_title,
+            max_retries,
+            retry_delay,
+            self.with_field_error("max_steps", max_steps),
+            self.with_field_error("prompt_style", prompt_style),
+            self.with_field_error("prompt_variant", prompt_variant),
+            disabled_actions,
+            confirmation_timeout
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    /// Hints per package, editable in place, and a row to add one.
+    fn view_app_hints_settings(&self) -> Element<'_, Message> {
+        let m = self.messages();
+        let section_title = text(m.app_hints_section).size(18);
+
+        let mut list = column![].spacing(8);
+        for (package, hint) in &self.app_hints.hints {
+            let key = package.clone();
+            list = list.push(
+                row![
+                    text(package).width(220),
+                    text_input(m.hint_placeholder, hint)
+                        .on_input(move |value| Message::AppHintChanged(key.clone(), value))
+                        .width(Length::Fill),
+                    button(text(m.delete))
+                        .on_press(Message::RemoveAppHint(package.clone()))
+                        .style(button::danger),
+                ]
+                .spacing(10),
+            );
+        }
+
+        let add_row = row![
+            text_input(m.package_name, &self.new_hint_package)
+                .on_input(Message::NewHintPackageChanged)
+                .width(220),
+            text_input(m.hint_placeholder, &self.new_hint_text)
+                .on_input(Message::NewHintTextChanged)
+                .on_submit(Message::AddAppHint)
+                .width(Length::Fill),
+            button(text(m.current_app))
+                .on_press(Message::UseCurrentApp)
+                .style(button::secondary),
+            button(text(m.add)).on_press(Message::AddAppHint),
+        ]
+        .spacing(10);
+
+        column![
+            section_title,
+            text(m.app_hints_hint).size(12),
+            list,
+            add_row
+        ]
+        .spacing(10)
+        .into()
     }
 
     fn view_calib_settings(&self) -> Element<'_, Message> {
-        let section_title = text("🎯 校准配置").size(18);
+        let m = self.messages();
+        let section_title = text(m.calibration_section).size(18);
 
         let enable_toggle = row![
-            text("启用自动校准").width(120),
+            text(m.auto_calibration).width(120),
             toggler(self.settings.enable_calibration).on_toggle(Message::EnableCalibrationToggled),
         ]
         .spacing(10);
 
+        let choice = |value: CalibModeOption| Choice {
+            value,
+            label: value.label(m),
+        };
         let mode_picker = row![
-            text("校准模式").width(120),
+            text(m.calibration_mode).width(120),
             pick_list(
-                vec![CalibModeOption::Simple, CalibModeOption::Complex],
-                Some(self.calib_mode),
-                Message::CalibModeSelected,
+                vec![
+                    choice(CalibModeOption::Simple),
+                    choice(CalibModeOption::Complex),
+                    choice(CalibModeOption::Grid),
+                ],
+                Some(choice(self.calib_mode)),
+                |c| Message::CalibModeSelected(c.value),
             )
             .width(200),
         ]
@@ -818,7 +3319,7 @@ impl PhoneAgentApp {
 
         let rounds = if self.calib_mode == CalibModeOption::Complex {
             labeled_input(
-                "复杂模式轮数",
+                m.complex_rounds,
                 &self.calib_rounds_input,
                 "5",
                 Message::CalibRoundsChanged,
@@ -827,20 +3328,55 @@ impl PhoneAgentApp {
             row![].into()
         };
 
-        column![section_title, enable_toggle, mode_picker, rounds]
-            .spacing(10)
-            .into()
+        let on_device_toggle = row![
+            text(m.show_markers).width(120),
+            toggler(self.settings.calibration_on_device).on_toggle(Message::CalibOnDeviceToggled),
+        ]
+        .spacing(10);
+
+        let auto_recalibrate_toggle = row![
+            text(m.recalibrate_on_drift).width(120),
+            toggler(self.settings.auto_recalibrate).on_toggle(Message::AutoRecalibrateToggled),
+        ]
+        .spacing(10);
+
+        column![
+            section_title,
+            enable_toggle,
+            self.with_field_error("calibration_mode", mode_picker),
+            rounds,
+            on_device_toggle,
+            auto_recalibrate_toggle
+        ]
+        .spacing(10)
+        .into()
     }
 
     /// Logs view.
     fn view_logs(&self) -> Element<'_, Message> {
-        let title = text("📋 日志").size(28);
+        let m = self.messages();
+        let title = text(m.tab_logs).size(28);
 
-        let clear_btn = button(text("🗑️ 清空日志"))
+        let clear_btn = button(text(m.clear_logs))
             .on_press(Message::ClearLogs)
             .style(button::secondary);
 
-        let header = row![title, horizontal_space(), clear_btn];
+        let export_btn = button(text(m.export_report))
+            .on_press(Message::ExportReport)
+            .style(button::primary);
+
+        let header = row![title, horizontal_space(), export_btn, clear_btn].spacing(10);
+
+        let default_dir = AppSettings::default_exports_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let export_dir = row![
+            text(m.export_dir).width(120),
+            text_input(&default_dir, &self.settings.export_dir)
+                .on_input(Message::ExportDirChanged)
+                .on_submit(Message::ExportReport),
+        ]
+        .spacing(10);
 
         let log_content = self.logger.format_all();
         let log_view = scrollable(text(log_content).size(13)).height(Length::Fill);
@@ -855,11 +3391,12 @@ impl PhoneAgentApp {
         let log_path = self
             .logger
             .log_file_path()
-            .map(|p| format!("日志文件: {}", p.display()))
-            .unwrap_or_else(|| "日志文件: 未创建".to_string());
+            .map(|p| format!("{}: {}", m.log_file, p.display()))
+            .unwrap_or_else(|| format!("{}: {}", m.log_file, m.not_created));
 
         column![
             header,
+            export_dir,
             vertical_space().height(10),
             log_container,
             text(log_path).size(12),
@@ -870,6 +3407,14 @@ impl PhoneAgentApp {
     }
 }
 
+/// Reasons of the values a settings error refused, by field.
+fn field_errors(e: &SettingsError) -> BTreeMap<String, String> {
+    e.field_errors()
+        .iter()
+        .map(|error| (error.field.clone(), error.reason.clone()))
+        .collect()
+}
+
 /// Helper function to create a labeled input row.
 fn labeled_input<'a>(
     label: &'a str,
@@ -887,49 +3432,345 @@ fn labeled_input<'a>(
     .into()
 }
 
-/// Run the agent task asynchronously.
-async fn run_agent_task(settings: AppSettings, task: String) -> Result<String, String> {
-    // Build model config
-    let model_config = ModelConfig::default()
-        .with_base_url(&settings.base_url)
-        .with_api_key(&settings.api_key)
-        .with_model_name(&settings.model_name)
-        .with_max_retries(settings.max_retries)
-        .with_retry_delay(settings.retry_delay);
+/// Theme for a theme option, with the accent color as primary color when it
+/// is a valid hex color.
+fn app_theme(option: ThemeOption, accent_color: &str) -> Theme {
+    let base = option.as_theme();
+    match Color::parse(accent_color.trim()) {
+        Some(primary) => Theme::custom(
+            format!("{} {}", base, accent_color.trim()),
+            Palette {
+                primary,
+                ..base.palette()
+            },
+        ),
+        None => base,
+    }
+}
 
-    // Build agent config
-    let coord_system =
-        CoordSystemOption::from_str(&settings.coordinate_system).as_coordinate_system();
-    let mut agent_config = AgentConfig::default()
-        .with_lang(&settings.lang)
-        .with_coordinate_system(coord_system)
-        .with_scale(settings.scale_x, settings.scale_y)
-        .with_max_steps(settings.max_steps);
+/// Shortcut for a key press no widget handled.
+fn shortcut(key: Key, modifiers: Modifiers) -> Option<Message> {
+    let shortcut = match key.as_ref() {
+        Key::Named(Named::Enter) if modifiers.is_empty() => Shortcut::Run,
+        Key::Named(Named::Escape) => Shortcut::Stop,
+        Key::Character("c") if modifiers.command() => Shortcut::Stop,
+        Key::Character("l") if modifiers.command() => Shortcut::ClearLogs,
+        Key::Character(digit) if modifiers.command() => {
+            let index = digit.parse::<usize>().ok()?.checked_sub(1)?;
+            Shortcut::SwitchView(*View::ALL.get(index)?)
+        }
+        _ => return None,
+    };
+    Some(Message::Shortcut(shortcut))
+}
 
-    if !settings.device_id.is_empty() {
-        agent_config = agent_config.with_device_id(&settings.device_id);
+/// A status bar indicator with its details in a tooltip.
+fn status_indicator<'a>(icon: &str, label: &str, detail: String) -> Element<'a, Message> {
+    tooltip(
+        text(format!("{} {}", icon, label)).size(14),
+        container(text(detail).size(12))
+            .padding(6)
+            .style(container::bordered_box),
+        tooltip::Position::Top,
+    )
+    .into()
+}
+
+/// How often the model server and the device are checked.
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often the settings file is checked for changes.
+const SETTINGS_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Width of toast notifications.
+const TOAST_WIDTH: f32 = 360.0;
+
+/// How often toasts are checked for expiry and countdowns redrawn.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the preview is refreshed while it is shown.
+const PREVIEW_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A finished task: outcome, text, timing, result and a rerun button.
+fn history_card<'a>(
+    index: usize,
+    entry: &'a HistoryEntry,
+    can_run: bool,
+    m: &'static Messages,
+) -> Element<'a, Message> {
+    let header = row![
+        text(format!("{} {}", entry.outcome.emoji(), entry.task)).size(16),
+        horizontal_space(),
+        button(text(m.run_again))
+            .on_press_maybe(can_run.then_some(Message::RerunTask(index)))
+            .style(button::primary),
+    ]
+    .align_y(iced::Alignment::Center);
+
+    let mut details = column![
+        header,
+        text(format!(
+            "{} · {} {:.1}s",
+            entry.started_at.format("%Y-%m-%d %H:%M:%S"),
+            m.duration,
+            entry.duration_secs
+        ))
+        .size(12),
+        text(&entry.result).size(13),
+    ]
+    .spacing(5);
+    if let Some(path) = &entry.report_path {
+        details = details.push(text(format!("📄 {}", path.display())).size(12));
     }
 
-    // Run calibration if enabled
-    let (scale_x, scale_y) = if settings.enable_calibration {
-        match run_calibration(settings.clone()).await {
-            Ok((x, y)) => (x, y),
-            Err(_) => (settings.scale_x, settings.scale_y),
-        }
+    container(details)
+        .width(Length::Fill)
+        .padding(10)
+        .style(container::rounded_box)
+        .into()
+}
+
+/// A timeline step: thumbnail on the left, status, action and thinking on the right.
+fn timeline_card<'a>(entry: &'a TimelineEntry, m: &'static Messages) -> Element<'a, Message> {
+    let status = if entry.finished {
+        "🏁"
+    } else if entry.success {
+        "✅"
     } else {
-        (settings.scale_x, settings.scale_y)
+        "❌"
+    };
+
+    let thumbnail: Element<'_, Message> = match &entry.thumbnail {
+        Some(handle) => image(handle.clone()).width(THUMBNAIL_WIDTH as f32).into(),
+        None => container(text(m.no_screenshot).size(12))
+            .width(THUMBNAIL_WIDTH as f32)
+            .into(),
     };
 
-    agent_config = agent_config.with_scale(scale_x, scale_y);
+    let mut details = column![
+        text(format!("{} {} {}", m.step, entry.step, status)).size(16),
+        text(format!("🎯 {}", entry.action_text(m))).size(13),
+    ]
+    .spacing(5);
+    if !entry.thinking.is_empty() {
+        details = details.push(text(format!("💭 {}", entry.thinking_excerpt())).size(12));
+    }
+    if let Some(message) = &entry.message {
+        details = details.push(text(format!("📝 {}", message)).size(12));
+    }
+
+    container(row![thumbnail, details].spacing(15))
+        .width(Length::Fill)
+        .padding(10)
+        .style(container::rounded_box)
+        .into()
+}
+
+/// Stream behind [`PhoneAgentApp::subscription`]: hands out the sending half
+/// of a channel once, then forwards every message sent into it.
+fn agent_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(100, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        if output
+            .send(Message::AgentChannelReady(sender))
+            .await
+            .is_err()
+        {
+            return;
+        }
+        while let Some(message) = receiver.next().await {
+            if output.send(message).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Run the agent task asynchronously, sending each finished step, takeover
+/// request and sensitive-operation confirmation to `updates`. A takeover
+/// blocks until `resumed` fires, a confirmation until `answers` has the
+/// user's answer.
+async fn run_agent_task(
+    settings: AppSettings,
+    task: String,
+    updates: Option<mpsc::UnboundedSender<Message>>,
+    cancel: Arc<AtomicBool>,
+    resumed: std::sync::mpsc::Receiver<()>,
+    answers: std::sync::mpsc::Receiver<bool>,
+) -> Result<String, String> {
+    let (model_config, agent_config) = agent_configs(&settings).await;
+
+    // Takeovers and confirmations are handed to the GUI instead of the console
+    let takeover = updates
+        .clone()
+        .map(|updates| gui_takeover(updates, resumed));
+    let timeout_secs = settings.confirmation_timeout_secs;
+    let confirmation = updates
+        .clone()
+        .map(|updates| gui_confirmation(updates, answers, timeout_secs));
 
     // Create and run agent
-    let mut agent = PhoneAgent::new(model_config, agent_config, None, None);
+    let mut agent = PhoneAgent::new(model_config, agent_config, confirmation, takeover)
+        .with_cancellation(cancel);
+    if let Some(updates) = updates {
+        agent = agent.with_step_callback(Box::new(move |event| {
+            let _ = updates.unbounded_send(Message::TaskStep(TimelineEntry::from_event(event)));
+        }));
+    }
 
     agent.run(&task).await.map_err(|e| e.to_string())
 }
 
-/// Run coordinate calibration.
-async fn run_calibration(settings: AppSettings) -> Result<(f64, f64), String> {
+/// Takeover callback that hands control to the GUI through `updates` and
+/// blocks until `resumed` fires.
+fn gui_takeover(
+    updates: mpsc::UnboundedSender<Message>,
+    resumed: std::sync::mpsc::Receiver<()>,
+) -> TakeoverCallback {
+    let resumed = Mutex::new(resumed);
+    Box::new(move |message: &str| {
+        await_takeover(&resumed.lock().unwrap(), || {
+            updates
+                .unbounded_send(Message::TakeoverRequested(message.to_string()))
+                .is_ok()
+        })
+    })
+}
+
+/// Confirmation callback that asks the GUI through `updates` and blocks until
+/// `answers` has the user's answer. One that isn't answered in time, or at
+/// all, is denied so the task can't hang on it.
+fn gui_confirmation(
+    updates: mpsc::UnboundedSender<Message>,
+    answers: std::sync::mpsc::Receiver<bool>,
+    timeout_secs: u64,
+) -> ConfirmationCallback {
+    let answers = Mutex::new(answers);
+    Box::new(move |message: &str| {
+        let request = Message::ConfirmationRequested(message.to_string(), timeout_secs);
+        let answer = await_confirmation(
+            &answers.lock().unwrap(),
+            confirmation_timeout(timeout_secs),
+            || updates.unbounded_send(request).is_ok(),
+        );
+        if answer == ConfirmationAnswer::TimedOut {
+            let _ = updates.unbounded_send(Message::ConfirmationTimedOut);
+        }
+        answer.allowed()
+    })
+}
+
+/// Start the dual loop with `task` as its first request, forwarding its
+/// events, takeovers and sensitive-operation confirmations to `updates`.
+/// A takeover blocks the executor until `resumed` fires, a confirmation
+/// until `answers` has the user's answer.
+async fn start_dual_loop(
+    settings: AppSettings,
+    task: String,
+    updates: Option<mpsc::UnboundedSender<Message>>,
+    resumed: std::sync::mpsc::Receiver<()>,
+    answers: std::sync::mpsc::Receiver<bool>,
+) -> Result<DualLoopHandle, String> {
+    let (model_config, agent_config) = agent_configs(&settings).await;
+
+    // Learned prompts are kept per device model, as in the CLI
+    let prompt_scope = match get_device_model(agent_config.device_id.as_deref()) {
+        Some(model) => PromptScope::new().with_device_model(&model),
+        None => PromptScope::new(),
+    };
+    let profile = settings.planner_profile(&settings.lang);
+    let planner_config = settings.planner_config(&settings.lang, profile.as_ref(), prompt_scope);
+    let mut planner = PlannerAgent::new(planner_config, model_config, agent_config);
+    if let Some(updates) = &updates {
+        planner = planner
+            .with_takeover_callback(gui_takeover(updates.clone(), resumed).into())
+            .with_confirmation_callback(
+                gui_confirmation(updates.clone(), answers, settings.confirmation_timeout_secs)
+                    .into(),
+            );
+    }
+
+    let runner = DualLoopRunner::new(planner, settings.dual_loop_config(profile.as_ref()));
+    let mut events = runner.subscribe();
+    let handle = runner.run().await;
+
+    if let Some(updates) = updates {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let stopped = matches!(event, DualLoopEvent::Stopped { .. });
+                        if updates.unbounded_send(Message::DualLoop(event)).is_err() || stopped {
+                            break;
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    handle
+        .send_user_input(task)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(handle)
+}
+
+/// Executor model and agent configuration from the settings, calibrating
+/// first if enabled.
+async fn agent_configs(settings: &AppSettings) -> (ModelConfig, AgentConfig) {
+    let config = Config::builder().with_settings(settings.clone()).build();
+    let model_config = config.model_config();
+    let mut agent_config = config.agent_config();
+    let coord_system = agent_config.coordinate_system;
+
+    // Run calibration if enabled, otherwise use this phone's stored calibration
+    let device_id = (!settings.device_id.is_empty()).then_some(settings.device_id.as_str());
+    let configured = (
+        agent_config.scale_x,
+        agent_config.scale_y,
+        agent_config.offset_x,
+        agent_config.offset_y,
+        None,
+    );
+    let calibrated = if settings.enable_calibration {
+        run_calibration(settings.clone()).await.ok()
+    } else {
+        None
+    };
+    // Calibrations of other orientations come from the stored profiles
+    let mut calibrations = AppSettings::calibration_profiles_path()
+        .filter(|_| coord_system == CoordinateSystem::Absolute)
+        .map(|path| load_device_calibrations(path, device_id))
+        .unwrap_or_default();
+    if let Some(result) = calibrated {
+        calibrations.insert(result);
+    }
+    let (scale_x, scale_y, offset_x, offset_y, mapping) = match calibrations.preferred() {
+        Some(result) => (
+            result.scale_x,
+            result.scale_y,
+            result.offset_x,
+            result.offset_y,
+            result.mapping.clone(),
+        ),
+        None => configured,
+    };
+
+    agent_config = agent_config
+        .with_scale(scale_x, scale_y)
+        .with_offset(offset_x, offset_y)
+        .with_coordinate_mapping(mapping)
+        .with_orientation_calibrations(calibrations);
+
+    (model_config, agent_config)
+}
+
+/// Run coordinate calibration in the configured orientations, storing each
+/// one's profile. Returns the portrait calibration when there is one.
+async fn run_calibration(settings: AppSettings) -> Result<CalibrationResult, String> {
     let model_config = ModelConfig::default()
         .with_base_url(&settings.base_url)
         .with_api_key(&settings.api_key)
@@ -942,7 +3783,13 @@ async fn run_calibration(settings: AppSettings) -> Result<(f64, f64), String> {
     let mut calibration_config = CalibrationConfig::default()
         .with_mode(calib_mode)
         .with_lang(&settings.lang)
-        .with_complex_rounds(settings.calibration_rounds);
+        .with_complex_rounds(settings.calibration_rounds)
+        .with_complex_scenes(
+            ComplexScene::parse_list(&settings.calibration_scenes).unwrap_or_default(),
+        )
+        .with_max_concurrency(settings.calibration_concurrency)
+        .with_max_residual(settings.calibration_max_residual)
+        .with_on_device(settings.calibration_on_device);
 
     if !settings.device_id.is_empty() {
         calibration_config = calibration_config.with_device_id(&settings.device_id);
@@ -951,11 +3798,165 @@ async fn run_calibration(settings: AppSettings) -> Result<(f64, f64), String> {
     let calibrator = CoordinateCalibrator::new(calibration_config);
     let model_client = ModelClient::new(model_config);
 
-    let result = calibrator.calibrate(&model_client).await;
+    let orientations =
+        Orientation::parse_selection(&settings.calibration_orientation).unwrap_or_default();
+    let results = calibrator
+        .calibrate_orientations(&model_client, &orientations)
+        .await;
+
+    let device_id = (!settings.device_id.is_empty()).then_some(settings.device_id.as_str());
+    if let Some(path) = AppSettings::calibration_profiles_path() {
+        for result in results.iter().filter(|r| r.success) {
+            if let Err(e) = save_device_profile(&path, device_id, result) {
+                tracing::warn!("Failed to save calibration profile: {}", e);
+            }
+        }
+    }
+
+    let mut calibrations = OrientedCalibrations::default();
+    for result in &results {
+        calibrations.insert(result.clone());
+    }
+    let result = match calibrations.preferred().or(results.first()) {
+        Some(result) => result.clone(),
+        None => return Err("No orientation was calibrated".to_string()),
+    };
 
     if result.success {
-        Ok((result.scale_x, result.scale_y))
+        if let Err(e) = AppSettings::save_calibration(&result) {
+            tracing::warn!("Failed to save calibration to settings: {}", e);
+        }
+        Ok(result)
     } else {
         Err(result.error.unwrap_or_else(|| "Unknown error".to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::SharedConfirmationCallback;
+
+    #[test]
+    fn test_shortcuts() {
+        let key = |c: &str| Key::Character(c.into());
+        let shortcut_of = |key, modifiers| match shortcut(key, modifiers) {
+            Some(Message::Shortcut(shortcut)) => Some(shortcut),
+            _ => None,
+        };
+
+        assert_eq!(
+            shortcut_of(Key::Named(Named::Enter), Modifiers::empty()),
+            Some(Shortcut::Run)
+        );
+        // Shift+Enter is left to the widgets
+        assert_eq!(
+            shortcut_of(Key::Named(Named::Enter), Modifiers::SHIFT),
+            None
+        );
+        assert_eq!(
+            shortcut_of(Key::Named(Named::Escape), Modifiers::empty()),
+            Some(Shortcut::Stop)
+        );
+        assert_eq!(
+            shortcut_of(key("c"), Modifiers::COMMAND),
+            Some(Shortcut::Stop)
+        );
+        assert_eq!(
+            shortcut_of(key("l"), Modifiers::COMMAND),
+            Some(Shortcut::ClearLogs)
+        );
+        assert_eq!(
+            shortcut_of(key("2"), Modifiers::COMMAND),
+            Some(Shortcut::SwitchView(View::Timeline))
+        );
+        assert_eq!(shortcut_of(key("0"), Modifiers::COMMAND), None);
+        assert_eq!(shortcut_of(key("9"), Modifiers::COMMAND), None);
+        // Typing isn't a shortcut
+        assert_eq!(shortcut_of(key("c"), Modifiers::empty()), None);
+    }
+
+    #[test]
+    fn test_app_theme() {
+        for option in [ThemeOption::System, ThemeOption::Light, ThemeOption::Dark] {
+            assert_eq!(ThemeOption::from_str(option.as_str()), option);
+        }
+        assert_eq!(ThemeOption::from_str("unknown"), ThemeOption::Dark);
+
+        assert_eq!(app_theme(ThemeOption::Light, ""), Theme::Light);
+        assert_eq!(app_theme(ThemeOption::Dark, "not a color"), Theme::Dark);
+
+        let themed = app_theme(ThemeOption::Light, " #D9485F ");
+        assert_eq!(themed.palette().primary, Color::parse("#D9485F").unwrap());
+        assert_eq!(
+            themed.palette().background,
+            Theme::Light.palette().background
+        );
+    }
+
+    #[tokio::test]
+    async fn test_agent_stream_forwards_messages() {
+        let mut stream = Box::pin(agent_stream());
+        let Some(Message::AgentChannelReady(sender)) = stream.next().await else {
+            panic!("expected the agent channel");
+        };
+
+        sender
+            .unbounded_send(Message::TakeoverRequested("log in".to_string()))
+            .unwrap();
+        sender
+            .unbounded_send(Message::ConfirmationTimedOut)
+            .unwrap();
+        assert!(matches!(
+            stream.next().await,
+            Some(Message::TakeoverRequested(message)) if message == "log in"
+        ));
+        assert!(matches!(
+            stream.next().await,
+            Some(Message::ConfirmationTimedOut)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dual_loop_confirmation_goes_through_gui() {
+        let mut app = PhoneAgentApp::default();
+        let m = app.messages();
+        let (updates, mut requests) = mpsc::unbounded();
+        let (answer, answers) = std::sync::mpsc::channel();
+        app.answer_confirmation = Some(answer);
+        let logged = |app: &PhoneAgentApp, text: &str| {
+            app.logger
+                .entries()
+                .iter()
+                .any(|entry| entry.message == text)
+        };
+
+        // The callback start_dual_loop hands the executor
+        let confirm: SharedConfirmationCallback = gui_confirmation(updates, answers, 1).into();
+
+        // Approved in the modal
+        let executor = confirm.clone();
+        let step = std::thread::spawn(move || executor("Pay 10 yuan"));
+        let _ = app.update(requests.next().await.unwrap());
+        assert!(matches!(
+            &app.confirmation,
+            Some((message, Some(_))) if message == "Pay 10 yuan"
+        ));
+        let _ = app.update(Message::ConfirmAction(true));
+        assert!(step.join().unwrap());
+        assert!(app.confirmation.is_none());
+        assert!(logged(&app, m.action_confirmed));
+
+        // Nobody answers: denied once the timeout passes
+        let executor = confirm.clone();
+        let step = std::thread::spawn(move || executor("Delete the album"));
+        let _ = app.update(requests.next().await.unwrap());
+        assert!(app.confirmation.is_some());
+        let timed_out = requests.next().await.unwrap();
+        assert!(matches!(timed_out, Message::ConfirmationTimedOut));
+        let _ = app.update(timed_out);
+        assert!(!step.join().unwrap());
+        assert!(app.confirmation.is_none());
+        assert!(logged(&app, m.confirmation_timed_out));
+    }
+}
@@ -0,0 +1,211 @@
+//! Report export for Phone Agent GUI.
+//!
+//! Writes the steps of the current run, screenshots included, and the session
+//! log into one self-contained HTML file that can be attached to a bug report.
+
+use chrono::{DateTime, Local};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::logger::LogEntry;
+use super::timeline::TimelineEntry;
+use crate::config::Messages;
+
+/// Page style of exported reports.
+const STYLE: &str = "body{font-family:sans-serif;max-width:960px;margin:2em auto;padding:0 1em}\
+.step{display:flex;gap:1em;border:1px solid #ccc;border-radius:8px;padding:1em;margin:1em 0}\
+.step img{width:240px;align-self:flex-start}\
+.step pre,.log{white-space:pre-wrap;word-break:break-word}\
+.log{background:#f4f4f4;padding:1em;border-radius:8px;font-size:13px}";
+
+/// Write a report of `steps` and `logs` to `dir`, in the language of
+/// `messages`. Screenshots are embedded, so the file stands on its own.
+///
+/// # Returns
+/// Path of the written report.
+pub fn export_report(
+    dir: &Path,
+    task: Option<&str>,
+    steps: &[TimelineEntry],
+    logs: &[LogEntry],
+    messages: &Messages,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let now = Local::now();
+    let html = render_html(task, steps, logs, messages, now);
+    let path = dir.join(format!("report-{}.html", now.format("%Y%m%d-%H%M%S")));
+    fs::write(&path, html).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// HTML page of a report exported at `now`.
+pub fn render_html(
+    task: Option<&str>,
+    steps: &[TimelineEntry],
+    logs: &[LogEntry],
+    messages: &Messages,
+    now: DateTime<Local>,
+) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(messages.report_title),
+        STYLE,
+        escape(messages.report_title)
+    );
+    let _ = writeln!(
+        html,
+        "<p>{}</p>",
+        escape(&now.format("%Y-%m-%d %H:%M:%S").to_string())
+    );
+    if let Some(task) = task {
+        let _ = writeln!(
+            html,
+            "<p><b>{}</b>: {}</p>",
+            escape(messages.task),
+            escape(task)
+        );
+    }
+
+    for step in steps {
+        let status = if step.finished {
+            "🏁"
+        } else if step.success {
+            "✅"
+        } else {
+            "❌"
+        };
+        html.push_str("<div class=\"step\">\n");
+        match &step.report_image {
+            Some(png) => {
+                let _ = writeln!(html, "<img src=\"data:image/png;base64,{}\">", png);
+            }
+            None => {
+                let _ = writeln!(html, "<p><i>{}</i></p>", escape(messages.no_screenshot));
+            }
+        }
+        html.push_str("<div>\n");
+        let _ = writeln!(
+            html,
+            "<h2>{} {} {}</h2>",
+            escape(messages.step),
+            step.step,
+            status
+        );
+        let _ = writeln!(
+            html,
+            "<p><b>{}</b>: <code>{}</code></p>",
+            escape(messages.action),
+            escape(&step.action_text(messages))
+        );
+        if !step.thinking.trim().is_empty() {
+            let _ = writeln!(html, "<pre>{}</pre>", escape(step.thinking.trim()));
+        }
+        if let Some(message) = &step.message {
+            let _ = writeln!(
+                html,
+                "<p><b>{}</b>: {}</p>",
+                escape(messages.message),
+                escape(message)
+            );
+        }
+        html.push_str("</div>\n</div>\n");
+    }
+
+    let log = logs
+        .iter()
+        .map(|entry| entry.format_file())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let _ = write!(
+        html,
+        "<h2>{}</h2>\n<div class=\"log\">{}</div>\n</body>\n</html>\n",
+        escape(messages.execution_log),
+        escape(&log)
+    );
+    html
+}
+
+/// Escape text for HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::get_messages;
+    use crate::gui::logger::LogLevel;
+    use crate::model::TokenUsage;
+    use serde_json::json;
+
+    fn step(step: u32, report_image: Option<&str>) -> TimelineEntry {
+        TimelineEntry {
+            step,
+            thumbnail: None,
+            report_image: report_image.map(str::to_string),
+            thinking: "Look for <Send>".to_string(),
+            action: Some(json!({"action": "Type", "text": "a & b"})),
+            success: true,
+            finished: false,
+            message: None,
+            usage: TokenUsage::default(),
+        }
+    }
+
+    #[test]
+    fn test_render_html() {
+        let messages = get_messages("en");
+        let mut last = step(2, None);
+        last.finished = true;
+        last.message = Some("Sent \"hi\"".to_string());
+        let logs = [LogEntry::new(
+            LogLevel::Warning,
+            "<script>alert(1)</script>",
+        )];
+
+        let html = render_html(
+            Some("Reply <b>hi</b>"),
+            &[step(1, Some("iVBORw0KGgo=")), last],
+            &logs,
+            messages,
+            Local::now(),
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        // Screenshots are embedded, a missing one is noted
+        assert!(html.contains("<img src=\"data:image/png;base64,iVBORw0KGgo=\">"));
+        assert!(html.contains(&format!("<i>{}</i>", escape(messages.no_screenshot))));
+        assert!(html.contains(&format!("{} 2 🏁", escape(messages.step))));
+        // Text from the task, the model and the log is escaped
+        assert!(html.contains("Reply &lt;b&gt;hi&lt;/b&gt;"));
+        assert!(html.contains("<pre>Look for &lt;Send&gt;</pre>"));
+        assert!(html.contains("&quot;a &amp; b&quot;"));
+        assert!(html.contains("Sent &quot;hi&quot;"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn test_export_report_writes_file() {
+        let dir = std::env::temp_dir().join(format!("phone-agent-export-{}", uuid::Uuid::new_v4()));
+        let path = export_report(&dir, None, &[], &[], get_messages("en")).unwrap();
+        assert_eq!(path.extension().unwrap(), "html");
+        assert!(fs::read_to_string(&path).unwrap().contains("<h1>"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
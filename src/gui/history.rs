@@ -0,0 +1,267 @@
+//! Task history for Phone Agent GUI.
+//!
+//! Every finished task is kept with its result, duration and a Markdown
+//! report of its steps, so past runs survive a restart and can be run again.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::settings::AppSettings;
+use super::timeline::TimelineEntry;
+use crate::config::Messages;
+
+/// Maximum number of tasks kept in the history.
+const MAX_HISTORY: usize = 200;
+
+/// How a task ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskOutcome {
+    Success,
+    Failed,
+    Cancelled,
+}
+
+impl TaskOutcome {
+    /// Icon shown in the history list.
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            TaskOutcome::Success => "✅",
+            TaskOutcome::Failed => "❌",
+            TaskOutcome::Cancelled => "⏹️",
+        }
+    }
+}
+
+/// A finished task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub task: String,
+    pub started_at: DateTime<Local>,
+    pub duration_secs: f64,
+    pub outcome: TaskOutcome,
+    /// Final message of the agent, or the error.
+    pub result: String,
+    /// Executor prompt variant the task ran with.
+    #[serde(default)]
+    pub prompt_variant: Option<String>,
+    /// Markdown report of the run's steps, if one could be written.
+    #[serde(default)]
+    pub report_path: Option<PathBuf>,
+}
+
+/// Finished tasks, newest first, persisted to the data directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskHistory {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl TaskHistory {
+    /// Load the history, empty if there is none yet.
+    pub fn load() -> Self {
+        AppSettings::task_history_path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    /// Load the history from `path`, empty if it can't be read.
+    pub fn load_from(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the history.
+    pub fn save(&self) -> Result<(), String> {
+        let path = AppSettings::task_history_path().ok_or("Cannot determine data directory")?;
+        self.save_to(&path)
+    }
+
+    /// Save the history to `path`.
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    /// Add a finished task in front, dropping the oldest beyond the limit.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        self.entries.insert(0, entry);
+        self.entries.truncate(MAX_HISTORY);
+    }
+
+    /// Remove all tasks.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Write a Markdown report of a run to `dir`, in the language of `messages`.
+///
+/// # Returns
+/// Path of the written report.
+pub fn write_report(
+    dir: &Path,
+    entry: &HistoryEntry,
+    steps: &[TimelineEntry],
+    messages: &Messages,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let report = render_report(entry, steps, messages);
+    let path = dir.join(format!(
+        "task-{}-{}.md",
+        entry.started_at.format("%Y%m%d-%H%M%S"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    ));
+    fs::write(&path, report).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Markdown report of a run, in the language of `messages`.
+pub fn render_report(entry: &HistoryEntry, steps: &[TimelineEntry], messages: &Messages) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "# {}\n", entry.task);
+    let _ = writeln!(
+        report,
+        "- {}: {}",
+        messages.started,
+        entry.started_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    let _ = writeln!(
+        report,
+        "- {}: {:.1}s",
+        messages.duration, entry.duration_secs
+    );
+    if let Some(variant) = &entry.prompt_variant {
+        let _ = writeln!(report, "- {}: {}", messages.prompt_variant, variant);
+    }
+    let _ = writeln!(
+        report,
+        "- {}: {} {}\n",
+        messages.result,
+        entry.outcome.emoji(),
+        entry.result
+    );
+
+    for step in steps {
+        let _ = writeln!(report, "## {} {}\n", messages.step, step.step);
+        if !step.thinking.trim().is_empty() {
+            let _ = writeln!(report, "{}\n", step.thinking.trim());
+        }
+        let _ = writeln!(
+            report,
+            "{}: `{}`\n",
+            messages.action,
+            step.action_text(messages)
+        );
+        if let Some(message) = &step.message {
+            let _ = writeln!(report, "{}: {}\n", messages.message, message);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::get_messages;
+    use crate::model::TokenUsage;
+    use serde_json::json;
+
+    fn entry(task: &str) -> HistoryEntry {
+        HistoryEntry {
+            task: task.to_string(),
+            started_at: Local::now(),
+            duration_secs: 12.5,
+            outcome: TaskOutcome::Success,
+            result: "Done".to_string(),
+            prompt_variant: Some("default".to_string()),
+            report_path: None,
+        }
+    }
+
+    #[test]
+    fn test_history_keeps_newest() {
+        let mut history = TaskHistory::default();
+        for i in 0..MAX_HISTORY + 5 {
+            history.push(entry(&format!("task {}", i)));
+        }
+        assert_eq!(history.entries.len(), MAX_HISTORY);
+        assert_eq!(history.entries[0].task, format!("task {}", MAX_HISTORY + 4));
+
+        history.clear();
+        assert!(history.entries.is_empty());
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("phone-agent-history-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("history.json");
+        assert!(TaskHistory::load_from(&path).entries.is_empty());
+
+        let mut history = TaskHistory::default();
+        let mut cancelled = entry("打开微信");
+        cancelled.outcome = TaskOutcome::Cancelled;
+        cancelled.report_path = Some(dir.join("task.md"));
+        history.push(cancelled);
+        history.save_to(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("\"outcome\": \"cancelled\""));
+        let loaded = TaskHistory::load_from(&path);
+        assert_eq!(loaded.entries.len(), 1);
+        assert_eq!(loaded.entries[0].task, "打开微信");
+        assert_eq!(loaded.entries[0].outcome, TaskOutcome::Cancelled);
+        assert_eq!(loaded.entries[0].report_path, Some(dir.join("task.md")));
+
+        // Entries written before the optional fields existed still load
+        fs::write(
+            &path,
+            json!({"entries": [{
+                "task": "old",
+                "started_at": "2026-01-01T08:00:00+08:00",
+                "duration_secs": 1.0,
+                "outcome": "failed",
+                "result": "error"
+            }]})
+            .to_string(),
+        )
+        .unwrap();
+        let loaded = TaskHistory::load_from(&path);
+        assert_eq!(loaded.entries[0].outcome, TaskOutcome::Failed);
+        assert_eq!(loaded.entries[0].prompt_variant, None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_render_report() {
+        let messages = get_messages("en");
+        let step = TimelineEntry {
+            step: 1,
+            thumbnail: None,
+            report_image: None,
+            thinking: "  Open the app drawer  ".to_string(),
+            action: Some(json!({"action": "Tap", "element": [500, 500]})),
+            success: true,
+            finished: false,
+            message: Some("tapped".to_string()),
+            usage: TokenUsage::default(),
+        };
+        let report = render_report(&entry("Open WeChat"), &[step], messages);
+
+        assert!(report.starts_with("# Open WeChat\n"));
+        assert!(report.contains(&format!("- {}: 12.5s", messages.duration)));
+        assert!(report.contains(&format!("- {}: default", messages.prompt_variant)));
+        assert!(report.contains(&format!("- {}: ✅ Done", messages.result)));
+        assert!(report.contains(&format!("## {} 1\n\nOpen the app drawer\n", messages.step)));
+        assert!(report.contains(r#"`{"action":"Tap","element":[500,500]}`"#));
+        assert!(report.contains(&format!("{}: tapped", messages.message)));
+    }
+}
@@ -3,9 +3,20 @@
 //! Provides a graphical user interface using Iced.
 
 pub mod app;
+pub mod export;
+pub mod history;
 pub mod logger;
+pub mod preview;
+pub mod requests;
 pub mod settings;
+pub mod timeline;
+pub mod toast;
+pub mod usage;
 
 pub use app::PhoneAgentApp;
+pub use history::{HistoryEntry, TaskHistory, TaskOutcome};
 pub use logger::{LogEntry, LogLevel, Logger};
 pub use settings::AppSettings;
+pub use timeline::TimelineEntry;
+pub use toast::{Toast, Toasts};
+pub use usage::{ModelUsage, UsageStats};
@@ -0,0 +1,100 @@
+//! Live device preview for Phone Agent GUI.
+//!
+//! Shows the device screen and turns clicks and drags on it into taps, long
+//! presses and swipes at the matching device coordinates.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use iced::widget::image::Handle;
+use iced::Point;
+use image::RgbaImage;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::actions::do_action;
+use crate::adb::Screenshot;
+
+/// Width the preview is shown at, in logical pixels.
+pub const PREVIEW_WIDTH: f32 = 320.0;
+
+/// Width screenshots are decoded to for the preview.
+const PREVIEW_DECODE_WIDTH: u32 = 540;
+
+/// Drags shorter than this many device pixels count as taps.
+const TAP_DISTANCE: f64 = 30.0;
+
+/// Presses held at least this long count as long presses.
+const LONG_PRESS: Duration = Duration::from_millis(600);
+
+/// The latest device screenshot shown in the preview.
+#[derive(Debug, Clone)]
+pub struct PreviewFrame {
+    pub handle: Handle,
+    pub screen_width: u32,
+    pub screen_height: u32,
+}
+
+impl PreviewFrame {
+    /// Decode a screenshot for the preview (`None` for sensitive screens).
+    pub fn from_screenshot(screenshot: &Screenshot) -> Option<Self> {
+        if screenshot.is_sensitive {
+            return None;
+        }
+        Some(Self {
+            handle: scaled_image(&screenshot.base64_data, PREVIEW_DECODE_WIDTH)?,
+            screen_width: screenshot.width,
+            screen_height: screenshot.height,
+        })
+    }
+
+    /// Height the preview is shown at for `PREVIEW_WIDTH`.
+    pub fn display_height(&self) -> f32 {
+        PREVIEW_WIDTH * self.screen_height as f32 / self.screen_width.max(1) as f32
+    }
+
+    /// Device pixel under a point of the preview.
+    pub fn to_device(&self, point: Point) -> (i64, i64) {
+        let scale = self.screen_width as f64 / PREVIEW_WIDTH as f64;
+        let clamp = |v: f64, max: u32| (v * scale).round().clamp(0.0, max as f64 - 1.0) as i64;
+        (
+            clamp(point.x as f64, self.screen_width),
+            clamp(point.y as f64, self.screen_height),
+        )
+    }
+
+    /// Action for a press at `start` released at `end` after `held`.
+    pub fn gesture(&self, start: Point, end: Point, held: Duration) -> Value {
+        let (sx, sy) = self.to_device(start);
+        let (ex, ey) = self.to_device(end);
+        let distance = (((ex - sx).pow(2) + (ey - sy).pow(2)) as f64).sqrt();
+
+        if distance >= TAP_DISTANCE {
+            do_action(
+                "Swipe",
+                &[("start", json!([sx, sy])), ("end", json!([ex, ey]))],
+            )
+        } else if held >= LONG_PRESS {
+            do_action("Long Press", &[("element", json!([sx, sy]))])
+        } else {
+            do_action("Tap", &[("element", json!([sx, sy]))])
+        }
+    }
+}
+
+/// Decode a base64 screenshot and shrink it to `width`, keeping its aspect ratio.
+pub fn scaled_image(base64_data: &str, width: u32) -> Option<Handle> {
+    scaled_rgba(base64_data, width).map(image_handle)
+}
+
+/// Decode a base64 screenshot into pixels shrunk to `width`, keeping its
+/// aspect ratio.
+pub fn scaled_rgba(base64_data: &str, width: u32) -> Option<RgbaImage> {
+    let bytes = STANDARD.decode(base64_data).ok()?;
+    let image = image::load_from_memory(&bytes).ok()?;
+    let height = (image.height() as u64 * width as u64 / image.width().max(1) as u64).max(1) as u32;
+    Some(image.thumbnail_exact(width, height).to_rgba8())
+}
+
+/// Image handle for decoded pixels.
+pub fn image_handle(rgba: RgbaImage) -> Handle {
+    Handle::from_rgba(rgba.width(), rgba.height(), rgba.into_raw())
+}
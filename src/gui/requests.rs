@@ -0,0 +1,147 @@
+//! Agent requests answered in Phone Agent GUI.
+//!
+//! Sensitive-operation confirmations and manual takeovers block the agent's
+//! thread until the user responds on the UI thread. A confirmation that isn't
+//! answered in time is denied, so an unattended task can't hang on it.
+
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// How a sensitive-operation confirmation ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationAnswer {
+    Allowed,
+    Denied,
+    /// Nobody answered before the timeout; the operation is denied.
+    TimedOut,
+}
+
+impl ConfirmationAnswer {
+    /// Whether the operation may go ahead.
+    pub fn allowed(self) -> bool {
+        self == ConfirmationAnswer::Allowed
+    }
+}
+
+/// Timeout of a confirmation for the `confirmation_timeout_secs` setting
+/// (`None` = wait forever).
+pub fn confirmation_timeout(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Whole seconds left until `deadline`, as shown on the dialog.
+pub fn seconds_left(deadline: Instant, now: Instant) -> u64 {
+    deadline.saturating_duration_since(now).as_secs()
+}
+
+/// Ask for a confirmation with `ask` and wait for the answer on `answers`.
+///
+/// Answers left over from an earlier confirmation that timed out are dropped
+/// first. The operation is denied if `ask` can't reach the GUI (returns
+/// `false`) or the GUI stops listening.
+pub fn await_confirmation(
+    answers: &Receiver<bool>,
+    timeout: Option<Duration>,
+    ask: impl FnOnce() -> bool,
+) -> ConfirmationAnswer {
+    while answers.try_recv().is_ok() {}
+    if !ask() {
+        return ConfirmationAnswer::Denied;
+    }
+    let answer = match timeout {
+        Some(timeout) => answers.recv_timeout(timeout),
+        None => answers.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    };
+    match answer {
+        Ok(true) => ConfirmationAnswer::Allowed,
+        Ok(false) | Err(RecvTimeoutError::Disconnected) => ConfirmationAnswer::Denied,
+        Err(RecvTimeoutError::Timeout) => ConfirmationAnswer::TimedOut,
+    }
+}
+
+/// Hand control to the user with `ask` and wait until they give it back on
+/// `resumed`. Returns at once if `ask` can't reach the GUI.
+pub fn await_takeover(resumed: &Receiver<()>, ask: impl FnOnce() -> bool) {
+    if ask() {
+        let _ = resumed.recv();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn test_confirmation_answers() {
+        let (answer, answers) = mpsc::channel();
+
+        // Answered in time
+        let ask = || answer.send(true).is_ok();
+        assert_eq!(
+            await_confirmation(&answers, Some(Duration::from_secs(5)), ask),
+            ConfirmationAnswer::Allowed
+        );
+        let ask = || answer.send(false).is_ok();
+        assert_eq!(
+            await_confirmation(&answers, None, ask),
+            ConfirmationAnswer::Denied
+        );
+
+        // The GUI is gone
+        assert_eq!(
+            await_confirmation(&answers, None, || false),
+            ConfirmationAnswer::Denied
+        );
+    }
+
+    #[test]
+    fn test_unanswered_confirmation_is_denied() {
+        let (answer, answers) = mpsc::channel();
+        let started = Instant::now();
+        let outcome = await_confirmation(&answers, Some(Duration::from_millis(50)), || true);
+        assert_eq!(outcome, ConfirmationAnswer::TimedOut);
+        assert!(!outcome.allowed());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // A late answer doesn't count for the next confirmation
+        answer.send(true).unwrap();
+        let outcome = await_confirmation(&answers, Some(Duration::from_millis(50)), || true);
+        assert_eq!(outcome, ConfirmationAnswer::TimedOut);
+
+        // Nobody left to answer
+        drop(answer);
+        assert_eq!(
+            await_confirmation(&answers, None, || true),
+            ConfirmationAnswer::Denied
+        );
+    }
+
+    #[test]
+    fn test_confirmation_deadline() {
+        assert_eq!(confirmation_timeout(0), None);
+        assert_eq!(confirmation_timeout(60), Some(Duration::from_secs(60)));
+
+        let now = Instant::now();
+        assert_eq!(seconds_left(now + Duration::from_millis(2500), now), 2);
+        assert_eq!(seconds_left(now, now + Duration::from_secs(1)), 0);
+    }
+
+    #[test]
+    fn test_takeover_waits_for_resume() {
+        let (resume, resumed) = mpsc::channel();
+        let user = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            resume.send(()).unwrap();
+        });
+        let started = Instant::now();
+        await_takeover(&resumed, || true);
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        user.join().unwrap();
+
+        // Without a GUI there is nobody to wait for
+        let (_resume, resumed) = mpsc::channel();
+        await_takeover(&resumed, || false);
+    }
+}
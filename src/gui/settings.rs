@@ -1,3 +1,3 @@
 //! GUI settings are now shared with the CLI via `crate::settings::AppSettings`.
 
-pub use crate::settings::AppSettings;
+pub use crate::settings::{AppSettings, SettingsError, SettingsWatcher};
@@ -0,0 +1,198 @@
+//! Step timeline for Phone Agent GUI.
+//!
+//! Keeps one entry per agent step with a screenshot thumbnail, so a run can be
+//! scrolled back to see where it went wrong. Thumbnails mark where the step
+//! tapped or swiped, so a wrong coordinate scale shows at a glance.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use iced::widget::image::Handle;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_hollow_circle_mut, draw_line_segment_mut};
+use serde_json::Value;
+use std::io::Cursor;
+
+use super::preview::{image_handle, scaled_rgba};
+use crate::config::Messages;
+use crate::model::TokenUsage;
+use crate::StepEvent;
+
+/// Width of screenshot thumbnails in pixels.
+pub const THUMBNAIL_WIDTH: u32 = 160;
+
+/// Width of screenshots in exported reports in pixels.
+const REPORT_IMAGE_WIDTH: u32 = 360;
+
+/// Characters of thinking shown per step.
+const THINKING_EXCERPT_CHARS: usize = 200;
+
+/// Radius of touch markers on thumbnails, in thumbnail pixels.
+const MARKER_RADIUS: i32 = 5;
+
+/// Colors of a touch (and a swipe's end) and of a swipe's start.
+const TOUCH_COLOR: Rgba<u8> = Rgba([255, 59, 48, 255]);
+const SWIPE_START_COLOR: Rgba<u8> = Rgba([52, 199, 89, 255]);
+const OUTLINE_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// A single step of a run.
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    /// Step number, starting at 1.
+    pub step: u32,
+    /// Downscaled screenshot (`None` for sensitive screens).
+    pub thumbnail: Option<Handle>,
+    /// Larger screenshot for exported reports, as base64 PNG.
+    pub report_image: Option<String>,
+    pub thinking: String,
+    pub action: Option<Value>,
+    pub success: bool,
+    pub finished: bool,
+    pub message: Option<String>,
+    /// Tokens used by the step's model call.
+    pub usage: TokenUsage,
+}
+
+impl TimelineEntry {
+    /// Build an entry from a step event. Decodes and downscales the
+    /// screenshot, so call this off the UI thread.
+    pub fn from_event(event: &StepEvent) -> Self {
+        Self {
+            step: event.step,
+            thumbnail: event.screenshot.as_ref().and_then(|s| {
+                let mut rgba = scaled_rgba(&s.base64_data, THUMBNAIL_WIDTH)?;
+                draw_touches(&mut rgba, &event.result.touches, s.width);
+                Some(image_handle(rgba))
+            }),
+            report_image: event.screenshot.as_ref().and_then(|s| {
+                let mut rgba = scaled_rgba(&s.base64_data, REPORT_IMAGE_WIDTH)?;
+                draw_touches(&mut rgba, &event.result.touches, s.width);
+                encode_png(rgba)
+            }),
+            thinking: event.result.thinking.clone(),
+            action: event.result.action.clone(),
+            success: event.result.success,
+            finished: event.result.finished,
+            message: event.result.message.clone(),
+            usage: event.result.usage,
+        }
+    }
+
+    /// The action as one line of text.
+    pub fn action_text(&self, messages: &Messages) -> String {
+        self.action
+            .as_ref()
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| messages.none.to_string())
+    }
+
+    /// Beginning of the thinking, cut at a character boundary.
+    pub fn thinking_excerpt(&self) -> String {
+        let thinking = self.thinking.trim();
+        match thinking.char_indices().nth(THINKING_EXCERPT_CHARS) {
+            Some((end, _)) => format!("{}…", &thinking[..end]),
+            None => thinking.to_string(),
+        }
+    }
+}
+
+/// Mark the touches of a step on its thumbnail: a dot per tap, and a line from
+/// a green start dot to a red end dot per swipe. `screen_width` is the width
+/// of the screen the touches' device pixels refer to.
+fn draw_touches(thumbnail: &mut RgbaImage, touches: &[(i32, i32)], screen_width: u32) {
+    let scale = thumbnail.width() as f32 / screen_width.max(1) as f32;
+    let points: Vec<(f32, f32)> = touches
+        .iter()
+        .map(|&(x, y)| (x as f32 * scale, y as f32 * scale))
+        .collect();
+
+    let marker = |image: &mut RgbaImage, (x, y): (f32, f32), color| {
+        let center = (x.round() as i32, y.round() as i32);
+        draw_filled_circle_mut(image, center, MARKER_RADIUS, color);
+        draw_hollow_circle_mut(image, center, MARKER_RADIUS, OUTLINE_COLOR);
+    };
+
+    match points.as_slice() {
+        [] => {}
+        [point] => marker(thumbnail, *point, TOUCH_COLOR),
+        [start, .., end] => {
+            // Two pixels wide so the path stays visible on busy screens
+            for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)] {
+                draw_line_segment_mut(
+                    thumbnail,
+                    (start.0 + dx, start.1 + dy),
+                    (end.0 + dx, end.1 + dy),
+                    TOUCH_COLOR,
+                );
+            }
+            marker(thumbnail, *start, SWIPE_START_COLOR);
+            marker(thumbnail, *end, TOUCH_COLOR);
+        }
+    }
+}
+
+/// Encode pixels as base64 PNG.
+fn encode_png(rgba: RgbaImage) -> Option<String> {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(rgba)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .ok()?;
+    Some(STANDARD.encode(png))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::get_messages;
+    use serde_json::json;
+
+    fn entry(thinking: &str, action: Option<Value>) -> TimelineEntry {
+        TimelineEntry {
+            step: 1,
+            thumbnail: None,
+            report_image: None,
+            thinking: thinking.to_string(),
+            action,
+            success: true,
+            finished: false,
+            message: None,
+            usage: TokenUsage::default(),
+        }
+    }
+
+    #[test]
+    fn test_entry_text() {
+        let messages = get_messages("en");
+        assert_eq!(entry("", None).action_text(messages), messages.none);
+        assert_eq!(
+            entry("", Some(json!({"action": "Back"}))).action_text(messages),
+            r#"{"action":"Back"}"#
+        );
+
+        assert_eq!(entry("  short  ", None).thinking_excerpt(), "short");
+        // Cut by characters, not bytes
+        let long = "点".repeat(THINKING_EXCERPT_CHARS + 10);
+        let excerpt = entry(&long, None).thinking_excerpt();
+        assert_eq!(excerpt.chars().count(), THINKING_EXCERPT_CHARS + 1);
+        assert!(excerpt.ends_with('…'));
+    }
+
+    #[test]
+    fn test_draw_touches() {
+        let blank = Rgba([0, 0, 0, 255]);
+        // A 1000px wide screen on a 100px thumbnail
+        let mut tap = RgbaImage::from_pixel(100, 200, blank);
+        draw_touches(&mut tap, &[(500, 1000)], 1000);
+        assert_eq!(*tap.get_pixel(50, 100), TOUCH_COLOR);
+        assert_eq!(*tap.get_pixel(10, 10), blank);
+
+        let mut swipe = RgbaImage::from_pixel(100, 200, blank);
+        draw_touches(&mut swipe, &[(500, 1500), (500, 500)], 1000);
+        assert_eq!(*swipe.get_pixel(50, 150), SWIPE_START_COLOR);
+        assert_eq!(*swipe.get_pixel(50, 50), TOUCH_COLOR);
+        // The path between them
+        assert_eq!(*swipe.get_pixel(50, 100), TOUCH_COLOR);
+
+        let mut untouched = RgbaImage::from_pixel(100, 200, blank);
+        draw_touches(&mut untouched, &[], 1000);
+        assert!(untouched.pixels().all(|pixel| *pixel == blank));
+    }
+}
@@ -0,0 +1,89 @@
+//! Toast notifications for Phone Agent GUI.
+//!
+//! Short messages shown over the current page, so requests from the agent are
+//! noticed on whatever tab the user is on. Each one disappears on its own.
+
+use std::time::{Duration, Instant};
+
+/// How long a toast stays up.
+pub const TOAST_DURATION: Duration = Duration::from_secs(8);
+
+/// Most toasts shown at once; older ones make room for new ones.
+const MAX_TOASTS: usize = 4;
+
+/// A notification on screen.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub id: u64,
+    pub text: String,
+    shown_at: Instant,
+}
+
+/// The toasts on screen, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct Toasts {
+    items: Vec<Toast>,
+    next_id: u64,
+}
+
+impl Toasts {
+    /// Show a new toast.
+    pub fn push(&mut self, text: impl Into<String>) {
+        self.items.push(Toast {
+            id: self.next_id,
+            text: text.into(),
+            shown_at: Instant::now(),
+        });
+        self.next_id += 1;
+        if self.items.len() > MAX_TOASTS {
+            self.items.remove(0);
+        }
+    }
+
+    /// Close a toast before it expires.
+    pub fn dismiss(&mut self, id: u64) {
+        self.items.retain(|toast| toast.id != id);
+    }
+
+    /// Remove toasts shown longer than [`TOAST_DURATION`].
+    pub fn expire(&mut self) {
+        self.items
+            .retain(|toast| toast.shown_at.elapsed() < TOAST_DURATION);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(toasts: &Toasts) -> Vec<&str> {
+        toasts.iter().map(|toast| toast.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_toasts() {
+        let mut toasts = Toasts::default();
+        assert!(toasts.is_empty());
+        for i in 0..MAX_TOASTS + 1 {
+            toasts.push(format!("toast {}", i));
+        }
+        // The oldest made room
+        assert_eq!(texts(&toasts), ["toast 1", "toast 2", "toast 3", "toast 4"]);
+
+        let id = toasts.iter().nth(1).unwrap().id;
+        toasts.dismiss(id);
+        assert_eq!(texts(&toasts), ["toast 1", "toast 3", "toast 4"]);
+
+        toasts.items[0].shown_at = Instant::now() - TOAST_DURATION;
+        toasts.expire();
+        assert_eq!(texts(&toasts), ["toast 3", "toast 4"]);
+    }
+}
@@ -0,0 +1,105 @@
+//! Token usage dashboard for Phone Agent GUI.
+//!
+//! Adds up the usage reported with every agent step for the running task, the
+//! whole session and each model, and estimates what it cost.
+
+use crate::model::TokenUsage;
+
+/// Calls and tokens of one model in this session.
+#[derive(Debug, Clone, Default)]
+pub struct ModelUsage {
+    pub model: String,
+    pub calls: u64,
+    pub usage: TokenUsage,
+}
+
+/// Usage of the current (or last) task and of the whole session.
+#[derive(Debug, Clone, Default)]
+pub struct UsageStats {
+    /// Usage of the current or last task.
+    pub task: TokenUsage,
+    /// Usage since the app started.
+    pub session: TokenUsage,
+    /// Per-model usage in order of first use.
+    pub models: Vec<ModelUsage>,
+    /// Model the current task runs on.
+    model: String,
+}
+
+impl UsageStats {
+    /// Start counting a new task running on `model`.
+    pub fn start_task(&mut self, model: &str) {
+        self.task = TokenUsage::default();
+        self.model = model.to_string();
+    }
+
+    /// Record one model call of the current task.
+    pub fn record(&mut self, usage: &TokenUsage) {
+        self.task.add(usage);
+        self.session.add(usage);
+
+        let entry = match self.models.iter().position(|m| m.model == self.model) {
+            Some(pos) => &mut self.models[pos],
+            None => {
+                self.models.push(ModelUsage {
+                    model: self.model.clone(),
+                    ..ModelUsage::default()
+                });
+                self.models.last_mut().unwrap()
+            }
+        };
+        entry.calls += 1;
+        entry.usage.add(usage);
+    }
+}
+
+/// Estimated cost of `usage` at a price per million tokens.
+pub fn estimate_cost(usage: &TokenUsage, price_per_million: f64) -> f64 {
+    usage.total_tokens as f64 * price_per_million / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt: u64, completion: u64) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+        }
+    }
+
+    #[test]
+    fn test_usage_stats() {
+        let mut stats = UsageStats::default();
+        stats.start_task("autoglm-phone");
+        stats.record(&usage(1000, 100));
+        stats.record(&usage(2000, 200));
+        assert_eq!(stats.task.total_tokens, 3300);
+
+        // A new task starts from zero; the session and models keep counting
+        stats.start_task("qwen-vl");
+        stats.record(&usage(500, 50));
+        stats.start_task("autoglm-phone");
+        stats.record(&usage(100, 10));
+        assert_eq!(stats.task.total_tokens, 110);
+        assert_eq!(stats.session.total_tokens, 3960);
+
+        let models: Vec<(&str, u64, u64)> = stats
+            .models
+            .iter()
+            .map(|m| (m.model.as_str(), m.calls, m.usage.total_tokens))
+            .collect();
+        assert_eq!(
+            models,
+            vec![("autoglm-phone", 3, 3410), ("qwen-vl", 1, 550)]
+        );
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        assert_eq!(estimate_cost(&usage(1_500_000, 500_000), 2.0), 4.0);
+        assert_eq!(estimate_cost(&usage(1000, 0), 0.0), 0.0);
+    }
+}
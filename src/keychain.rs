@@ -0,0 +1,58 @@
+//! API keys in the operating system's credential store.
+//!
+//! The settings file holds [`KEYCHAIN_MARKER`] in place of a key that lives
+//! in the macOS Keychain, the Windows Credential Manager or the Secret
+//! Service (GNOME Keyring, KWallet) on Linux.
+
+use keyring::Entry;
+
+/// Service name the keys are stored under.
+pub const KEYCHAIN_SERVICE: &str = "phone-agent";
+
+/// Value written to the settings file for a key kept in the keychain.
+pub const KEYCHAIN_MARKER: &str = "@keychain";
+
+/// Where secrets are kept, by account name (e.g. "api_key").
+pub trait SecretStore {
+    /// The secret of `account`, `None` if there is none.
+    fn get(&self, account: &str) -> Result<Option<String>, String>;
+
+    /// Store the secret of `account`, replacing the previous one.
+    fn set(&self, account: &str, secret: &str) -> Result<(), String>;
+
+    /// Remove the secret of `account`; removing a missing one is fine.
+    fn delete(&self, account: &str) -> Result<(), String>;
+}
+
+/// The operating system's credential store.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keychain;
+
+impl Keychain {
+    fn entry(account: &str) -> Result<Entry, String> {
+        Entry::new(KEYCHAIN_SERVICE, account).map_err(|e| e.to_string())
+    }
+}
+
+impl SecretStore for Keychain {
+    fn get(&self, account: &str) -> Result<Option<String>, String> {
+        match Self::entry(account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<(), String> {
+        Self::entry(account)?
+            .set_password(secret)
+            .map_err(|e| e.to_string())
+    }
+
+    fn delete(&self, account: &str) -> Result<(), String> {
+        match Self::entry(account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
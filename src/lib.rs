@@ -90,25 +90,34 @@ pub mod adb;
 pub mod agent;
 pub mod calibration;
 pub mod config;
+pub mod error;
 pub mod gui;
+pub mod keychain;
+pub mod mcp;
 pub mod model;
 pub mod settings;
 
 pub use actions::{CoordinateSystem, DEFAULT_COORDINATE_SCALE, RELATIVE_COORDINATE_MAX};
 
 // Single loop exports (original)
-pub use agent::{AgentConfig, AgentError, PhoneAgent, StepResult};
+pub use agent::{AgentConfig, AgentError, PhoneAgent, StepCallback, StepEvent, StepResult};
 
 // Dual loop exports (new)
 pub use agent::{
-    create_default_prompt_memory, DualLoopBuilder, DualLoopConfig, DualLoopError, DualLoopHandle,
-    DualLoopRunner, ExecutorCommand, ExecutorFeedback, ExecutorStatus, ExecutorWrapper,
-    PlannerAction, PlannerAgent, PlannerConfig, PromptEntry, PromptMemory, PromptMemoryError,
-    TodoItem, TodoList, TodoStats, TodoStatus,
+    create_default_prompt_memory, DualLoopBuilder, DualLoopConfig, DualLoopError, DualLoopEvent,
+    DualLoopHandle, DualLoopRunner, EventStream, ExecutorCommand, ExecutorFeedback,
+    ExecutorOverrides, ExecutorStatus, ExecutorWrapper, LiveSettings, MemorySync,
+    NotificationChannel, Notifier, PlannerAction, PlannerAgent, PlannerConfig, PlannerProfile,
+    PromptDiff, PromptEntry, PromptMemory, PromptMemoryError, PromptMemoryLimits, PromptReport,
+    PromptScope, PromptStore, PromptSummary, Recurrence, TaskTypeStats, TodoItem, TodoList,
+    TodoStats, TodoStatus, Trajectory,
 };
 
 pub use calibration::{
-    CalibrationConfig, CalibrationMode, CalibrationResult, CoordinateCalibrator,
+    CalibrationConfig, CalibrationError, CalibrationMode, CalibrationProfile, CalibrationProfiles,
+    CalibrationResult, CalibrationTheme, CalibrationVerification, CoordinateCalibrator,
+    CoordinateMapping, DriftMonitor, TapOutcome,
 };
-pub use model::{ModelClient, ModelConfig, ModelResponse};
-pub use settings::AppSettings;
+pub use error::ErrorCategory;
+pub use model::{ModelClient, ModelConfig, ModelError, ModelResponse};
+pub use settings::{AppSettings, EnvFormat, FieldError, SettingsError, SettingsWatcher};
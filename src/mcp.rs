@@ -0,0 +1,541 @@
+//! Model Context Protocol server.
+//!
+//! `phone-agent mcp` speaks MCP over stdin/stdout (JSON-RPC 2.0, one message
+//! per line), so MCP clients can drive the phone through this crate: take
+//! screenshots, tap, swipe, type, launch apps, or hand a whole task to the
+//! agent with `run_task`.
+//!
+//! Coordinates are relative, 0-999 on both axes of the screenshot, as in the
+//! AutoGLM prompts, so they don't depend on how the client scales the image.
+
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use crate::actions::{do_action, ActionHandler, ActionResult};
+use crate::adb::{get_current_app, get_screen_size, get_screenshot};
+use crate::agent::PhoneAgent;
+use crate::config::Config;
+
+/// Protocol version this server implements.
+pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Steps `run_task` allows when the call doesn't say.
+const DEFAULT_TASK_STEPS: u32 = 50;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A failed request: JSON-RPC error code and message.
+type RpcError = (i64, String);
+
+/// MCP server for one device, configured like the CLI.
+pub struct McpServer {
+    config: Config,
+    handler: ActionHandler,
+    /// Held while a tool uses the device, so a running task and direct
+    /// actions don't interleave.
+    device: tokio::sync::Mutex<()>,
+    /// Request id and cancellation flag of the running `run_task`.
+    running: Mutex<Option<(Value, Arc<AtomicBool>)>>,
+}
+
+impl McpServer {
+    /// Create a server using the device, app region and model of `config`.
+    pub fn new(config: Config) -> Self {
+        let agent_config = config.agent_config();
+        let mut handler = ActionHandler::with_relative_coordinates(
+            agent_config.device_id.clone(),
+            Some(Box::new(deny_confirmation)),
+            Some(Box::new(log_takeover)),
+        );
+        handler.set_app_region(agent_config.app_region);
+        handler.set_disabled_actions(agent_config.disabled_actions.clone());
+        handler.set_lang(&agent_config.lang);
+        Self {
+            config,
+            handler,
+            device: tokio::sync::Mutex::new(()),
+            running: Mutex::new(None),
+        }
+    }
+
+    /// Serve requests from stdin until it closes. Requests are handled
+    /// concurrently, so `ping` and cancellations are answered while a task
+    /// runs.
+    pub async fn serve_stdio(self) -> std::io::Result<()> {
+        let server = Arc::new(self);
+        let (responses, mut outgoing) = mpsc::unbounded_channel::<Value>();
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(response) = outgoing.recv().await {
+                let line = format!("{}\n", response);
+                if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let server = server.clone();
+            let responses = responses.clone();
+            tokio::spawn(async move {
+                if let Some(response) = server.handle_line(&line).await {
+                    let _ = responses.send(response);
+                }
+            });
+        }
+        drop(responses);
+        let _ = writer.await;
+        Ok(())
+    }
+
+    /// Answer one line of input; `None` for notifications.
+    pub async fn handle_line(&self, line: &str) -> Option<Value> {
+        match serde_json::from_str(line) {
+            Ok(message) => self.handle(message).await,
+            Err(e) => Some(error_response(
+                Value::Null,
+                (PARSE_ERROR, format!("Parse error: {}", e)),
+            )),
+        }
+    }
+
+    /// Answer one JSON-RPC message; `None` for notifications.
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            let id = message.get("id").cloned().unwrap_or(Value::Null);
+            return Some(error_response(
+                id,
+                (INVALID_REQUEST, "Invalid request: no method".to_string()),
+            ));
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let Some(id) = message.get("id").cloned() else {
+            self.notification(method, &params);
+            return None;
+        };
+
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call_tool(&id, &params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        json!({
+            "protocolVersion": MCP_PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": {
+                "name": "phone-agent",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "instructions": format!(
+                "Controls an Android phone over ADB{}. Take a screenshot first; tap and \
+                 swipe take coordinates from 0 to 999 relative to it (x from the left, \
+                 y from the top).",
+                params
+                    .pointer("/clientInfo/name")
+                    .and_then(Value::as_str)
+                    .map(|name| format!(" for {}", name))
+                    .unwrap_or_default()
+            ),
+        })
+    }
+
+    fn notification(&self, method: &str, params: &Value) {
+        if method != "notifications/cancelled" {
+            return;
+        }
+        let Some(request) = params.get("requestId") else {
+            return;
+        };
+        if let Some((id, cancel)) = &*self.running.lock().unwrap() {
+            if id == request {
+                tracing::info!("run_task {} cancelled by the client", id);
+                cancel.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    async fn call_tool(&self, id: &Value, params: &Value) -> Result<Value, RpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+        let args = params.get("arguments").cloned().unwrap_or(json!({}));
+        if !tools().iter().any(|tool| tool["name"] == name) {
+            return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)));
+        }
+
+        let Ok(_device) = self.device.try_lock() else {
+            return Ok(tool_error(
+                "The phone is busy with a running task; try again later",
+            ));
+        };
+        if name == "run_task" {
+            return self.run_task(id, &args).await;
+        }
+        if name == "screenshot" {
+            return Ok(self.screenshot());
+        }
+
+        let action = match name {
+            "tap" => do_action("Tap", &[("element", json!(point(&args, "x", "y")?))]),
+            "swipe" => do_action(
+                "Swipe",
+                &[
+                    ("start", json!(point(&args, "start_x", "start_y")?)),
+                    ("end", json!(point(&args, "end_x", "end_y")?)),
+                ],
+            ),
+            "type" => do_action("Type", &[("text", json!(string(&args, "text")?))]),
+            "launch" => do_action("Launch", &[("app", json!(string(&args, "app")?))]),
+            "back" => do_action("Back", &[]),
+            _ => do_action("Home", &[]),
+        };
+        let device_id = self.handler_device_id();
+        let Some((width, height)) = get_screen_size(device_id.as_deref()) else {
+            return Ok(tool_error("No device found; check `adb devices`"));
+        };
+        Ok(action_result(self.handler.execute(&action, width, height)))
+    }
+
+    fn screenshot(&self) -> Value {
+        let device_id = self.handler_device_id();
+        let screenshot = get_screenshot(device_id.as_deref());
+        let app = get_current_app(device_id.as_deref());
+        let summary = format!(
+            "Screen {}x{}, current app: {}. Coordinates for tap and swipe are 0-999 \
+             relative to this image.",
+            screenshot.width, screenshot.height, app
+        );
+        if screenshot.is_sensitive {
+            return json!({
+                "content": [{
+                    "type": "text",
+                    "text": format!("{} The screen is protected and can't be captured.", summary),
+                }],
+            });
+        }
+        json!({
+            "content": [
+                { "type": "image", "data": screenshot.base64_data, "mimeType": "image/png" },
+                { "type": "text", "text": summary },
+            ],
+        })
+    }
+
+    async fn run_task(&self, id: &Value, args: &Value) -> Result<Value, RpcError> {
+        let task = string(args, "task")?;
+        let max_steps = match args.get("max_steps") {
+            None | Some(Value::Null) => DEFAULT_TASK_STEPS,
+            Some(value) => value.as_u64().filter(|&n| n > 0).ok_or((
+                INVALID_PARAMS,
+                "max_steps must be a positive number".to_string(),
+            ))? as u32,
+        };
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        *self.running.lock().unwrap() = Some((id.clone(), cancel.clone()));
+        let mut agent = self.task_agent(max_steps).with_cancellation(cancel);
+        tracing::info!("run_task: {}", task);
+        let result = agent.run(&task).await;
+        *self.running.lock().unwrap() = None;
+
+        Ok(match result {
+            Ok(message) => json!({
+                "content": [{
+                    "type": "text",
+                    "text": format!("Finished after {} steps: {}", agent.step_count(), message),
+                }],
+            }),
+            Err(e) => tool_error(&format!(
+                "Stopped after {} steps: {}",
+                agent.step_count(),
+                e
+            )),
+        })
+    }
+
+    /// Agent for `run_task`. Nothing it does may write to stdout, which
+    /// carries the protocol: it is quiet and asks nobody on the console.
+    fn task_agent(&self, max_steps: u32) -> PhoneAgent {
+        let agent_config = self.config.agent_config().with_max_steps(max_steps).quiet();
+        PhoneAgent::new(
+            self.config.model_config(),
+            agent_config,
+            Some(Box::new(deny_confirmation)),
+            Some(Box::new(log_takeover)),
+        )
+    }
+
+    fn handler_device_id(&self) -> Option<String> {
+        self.config.agent_config().device_id
+    }
+}
+
+/// The tools this server offers, with their argument schemas.
+fn tools() -> Vec<Value> {
+    let coordinate = |description: &str| json!({ "type": "integer", "minimum": 0, "maximum": 999, "description": description });
+    let no_arguments = json!({ "type": "object", "properties": {} });
+    vec![
+        json!({
+            "name": "screenshot",
+            "description": "Take a screenshot of the phone and name the app in the foreground.",
+            "inputSchema": no_arguments,
+        }),
+        json!({
+            "name": "tap",
+            "description": "Tap a point of the screen.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "x": coordinate("0 (left) to 999 (right)"),
+                    "y": coordinate("0 (top) to 999 (bottom)"),
+                },
+                "required": ["x", "y"],
+            },
+        }),
+        json!({
+            "name": "swipe",
+            "description": "Swipe from one point of the screen to another, e.g. to scroll.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "start_x": coordinate("Start, 0 (left) to 999 (right)"),
+                    "start_y": coordinate("Start, 0 (top) to 999 (bottom)"),
+                    "end_x": coordinate("End, 0 (left) to 999 (right)"),
+                    "end_y": coordinate("End, 0 (top) to 999 (bottom)"),
+                },
+                "required": ["start_x", "start_y", "end_x", "end_y"],
+            },
+        }),
+        json!({
+            "name": "type",
+            "description": "Type text into the focused input field, replacing its content.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"],
+            },
+        }),
+        json!({
+            "name": "launch",
+            "description": "Open an app by its name (e.g. 微信, Settings) or package.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "app": { "type": "string" } },
+                "required": ["app"],
+            },
+        }),
+        json!({
+            "name": "back",
+            "description": "Press the Back button.",
+            "inputSchema": no_arguments,
+        }),
+        json!({
+            "name": "home",
+            "description": "Go to the home screen.",
+            "inputSchema": no_arguments,
+        }),
+        json!({
+            "name": "run_task",
+            "description": "Let the phone agent carry out a task on its own, e.g. \
+                            \"open WeChat and send hello to Alice\". Returns its final message.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "task": { "type": "string", "description": "What to do, in plain language" },
+                    "max_steps": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": format!("Step limit (default {})", DEFAULT_TASK_STEPS),
+                    },
+                },
+                "required": ["task"],
+            },
+        }),
+    ]
+}
+
+/// A string argument.
+fn string(args: &Value, key: &str) -> Result<String, RpcError> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or((INVALID_PARAMS, format!("Missing string argument: {}", key)))
+}
+
+/// A point given by two coordinate arguments.
+fn point(args: &Value, x: &str, y: &str) -> Result<[i64; 2], RpcError> {
+    let coordinate = |key: &str| {
+        args.get(key)
+            .and_then(Value::as_i64)
+            .ok_or((INVALID_PARAMS, format!("Missing integer argument: {}", key)))
+    };
+    Ok([coordinate(x)?, coordinate(y)?])
+}
+
+fn action_result(result: ActionResult) -> Value {
+    if !result.success {
+        let message = result
+            .message
+            .unwrap_or_else(|| "Action failed".to_string());
+        return tool_error(&message);
+    }
+    let text = result.message.unwrap_or_else(|| "Done".to_string());
+    json!({ "content": [{ "type": "text", "text": text }] })
+}
+
+/// Result of a tool call that failed; the client's model sees the message.
+fn tool_error(message: &str) -> Value {
+    json!({ "content": [{ "type": "text", "text": message }], "isError": true })
+}
+
+fn error_response(id: Value, (code, message): RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Sensitive actions need a person at the phone; nobody can confirm them here.
+fn deny_confirmation(message: &str) -> bool {
+    tracing::warn!("Sensitive action refused: {}", message);
+    false
+}
+
+/// Takeover requests can't wait for input on stdin, which carries the protocol.
+fn log_takeover(message: &str) {
+    tracing::warn!("Takeover requested: {}", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::AppSettings;
+
+    fn server() -> McpServer {
+        McpServer::new(
+            Config::builder()
+                .with_settings(AppSettings::default())
+                .build(),
+        )
+    }
+
+    #[test]
+    fn test_task_keeps_stdout_for_protocol() {
+        let settings = AppSettings {
+            auto_recalibrate: true,
+            ..AppSettings::default()
+        };
+        let server = McpServer::new(Config::builder().with_settings(settings).build());
+        let agent = server.task_agent(5);
+
+        // No step, result or drift reports, no calibration progress and no
+        // console prompts
+        assert!(!agent.config().verbose);
+        assert!(agent.config().auto_recalibrate);
+        assert!(agent.recalibration_config().progress_to_stderr);
+        assert!(!deny_confirmation("Pay 10 yuan"));
+    }
+
+    #[tokio::test]
+    async fn test_protocol() {
+        let server = server();
+
+        let response = server
+            .handle(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "protocolVersion": MCP_PROTOCOL_VERSION, "capabilities": {} },
+            }))
+            .await
+            .unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["serverInfo"]["name"], "phone-agent");
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server.handle(notification).await.is_none());
+
+        let response = server
+            .handle(json!({ "jsonrpc": "2.0", "id": "list", "method": "tools/list" }))
+            .await
+            .unwrap();
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|tool| tool["name"].as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "screenshot",
+                "tap",
+                "swipe",
+                "type",
+                "launch",
+                "back",
+                "home",
+                "run_task"
+            ]
+        );
+
+        let response = server
+            .handle(json!({ "jsonrpc": "2.0", "id": 2, "method": "resources/list" }))
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = server.handle_line("{not json").await.unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_tool_arguments() {
+        let server = server();
+        let call = |name: &str, arguments: Value| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+            })
+        };
+
+        let response = server.handle(call("fly", json!({}))).await.unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let response = server
+            .handle(call("tap", json!({ "x": 10 })))
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        assert!(response["error"]["message"].as_str().unwrap().contains("y"));
+
+        let response = server
+            .handle(call("run_task", json!({ "task": "x", "max_steps": 0 })))
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+}
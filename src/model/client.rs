@@ -1,18 +1,24 @@
 //! Model client for AI inference using OpenAI-compatible API.
 
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::time::Duration;
 use thiserror::Error;
 use tokio::time::sleep;
 
+use crate::config::PromptVars;
+
 /// Default number of retry attempts for failed requests.
 pub const DEFAULT_MAX_RETRIES: u32 = 3;
 
 /// Default delay between retry attempts in seconds.
 pub const DEFAULT_RETRY_DELAY_SECS: u64 = 2;
 
+/// How long [`ModelClient::health_check`] waits for the server.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Model client errors.
 #[derive(Error, Debug)]
 pub enum ModelError {
@@ -95,15 +101,51 @@ impl ModelConfig {
     }
 }
 
+/// Token usage reported by the API for one or more requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Parse the OpenAI-style `usage` object; missing fields count as zero.
+    pub fn from_json(usage: &Value) -> Self {
+        let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        let prompt_tokens = field("prompt_tokens");
+        let completion_tokens = field("completion_tokens");
+        let total_tokens = match field("total_tokens") {
+            0 => prompt_tokens + completion_tokens,
+            total => total,
+        };
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens,
+        }
+    }
+
+    /// Accumulate another usage record.
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
 /// Response from the AI model.
 #[derive(Debug, Clone)]
 pub struct ModelResponse {
     pub thinking: String,
     pub action: String,
     pub raw_content: String,
+    /// Token usage (zero when the API does not report it).
+    pub usage: TokenUsage,
 }
 
 /// Client for interacting with OpenAI-compatible vision-language models.
+#[derive(Clone)]
 pub struct ModelClient {
     config: ModelConfig,
     client: Client,
@@ -187,6 +229,45 @@ impl ModelClient {
         ))
     }
 
+    /// Check that the server is reachable and accepts the API key by listing
+    /// its models (`GET {base_url}/models`). Cheap, no tokens are used.
+    ///
+    /// # Returns
+    /// IDs of the models the server offers (empty if it doesn't list them).
+    pub async fn health_check(&self) -> Result<Vec<String>, ModelError> {
+        let url = format!("{}/models", self.config.base_url.trim_end_matches('/'));
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ModelError::ApiError(format!("HTTP {}", response.status())));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| ModelError::ParseError(e.to_string()))?;
+        Ok(Self::model_ids(&body))
+    }
+
+    /// Model IDs of a `/models` response.
+    fn model_ids(body: &Value) -> Vec<String> {
+        body.get("data")
+            .and_then(|data| data.as_array())
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m.get("id").and_then(|id| id.as_str()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Check if an error is retryable (network errors, timeouts, etc.)
     fn is_retryable_error(error: &ModelError) -> bool {
         match error {
@@ -255,11 +336,16 @@ impl ModelClient {
         })?;
 
         let (thinking, action) = Self::parse_response(&raw_content);
+        let usage = json_value
+            .get("usage")
+            .map(TokenUsage::from_json)
+            .unwrap_or_default();
 
         Ok(ModelResponse {
             thinking,
             action,
             raw_content: raw_content.clone(),
+            usage,
         })
     }
 
@@ -275,6 +361,7 @@ impl ModelClient {
     /// Parse streaming response (multiple JSON chunks concatenated)
     fn parse_streaming_response(&self, response_text: &str) -> Result<Value, ModelError> {
         let mut combined_content = String::new();
+        let mut usage = Value::Null;
 
         // Simple approach: split by }{ and reconstruct valid JSON objects
         let chunks: Vec<String> = if response_text.contains("}{") {
@@ -306,6 +393,10 @@ impl ModelClient {
 
             // Try to parse this chunk
             if let Ok(chunk_json) = serde_json::from_str::<Value>(json_str) {
+                // Usage is reported on the final chunk when requested
+                if let Some(u) = chunk_json.get("usage").filter(|u| u.is_object()) {
+                    usage = u.clone();
+                }
                 // Extract delta content from streaming chunk
                 if let Some(content) = chunk_json
                     .get("choices")
@@ -342,7 +433,8 @@ impl ModelClient {
                 "message": {
                     "content": combined_content
                 }
-            }]
+            }],
+            "usage": usage
         }))
     }
 
@@ -484,6 +576,12 @@ impl MessageBuilder {
         })
     }
 
+    /// Create a system message from a prompt template, filling in its
+    /// `{{name}}` placeholders.
+    pub fn create_system_message_from_template(template: &str, vars: &PromptVars) -> Value {
+        Self::create_system_message(&vars.render(template))
+    }
+
     /// Create a user message with optional image.
     ///
     /// # Arguments
@@ -496,10 +594,16 @@ impl MessageBuilder {
         let mut content = Vec::new();
 
         if let Some(img_data) = image_base64 {
+            // Screenshots may have been re-encoded as JPEG ("/9j/" = FF D8 FF)
+            let mime = if img_data.starts_with("/9j/") {
+                "image/jpeg"
+            } else {
+                "image/png"
+            };
             content.push(json!({
                 "type": "image_url",
                 "image_url": {
-                    "url": format!("data:image/png;base64,{}", img_data)
+                    "url": format!("data:{};base64,{}", mime, img_data)
                 }
             }));
         }
@@ -561,6 +665,31 @@ mod tests {
         assert_eq!(config.model_name, "autoglm-phone-9b");
     }
 
+    #[test]
+    fn test_model_ids() {
+        let body = json!({
+            "object": "list",
+            "data": [{"id": "autoglm-phone-9b", "object": "model"}, {"id": "glm-4v"}]
+        });
+        assert_eq!(
+            ModelClient::model_ids(&body),
+            vec!["autoglm-phone-9b", "glm-4v"]
+        );
+        assert!(ModelClient::model_ids(&json!({"status": "ok"})).is_empty());
+    }
+
+    #[test]
+    fn test_token_usage_from_json() {
+        let usage = TokenUsage::from_json(&json!({"prompt_tokens": 120, "completion_tokens": 30}));
+        assert_eq!(usage.total_tokens, 150);
+
+        let mut total = TokenUsage::default();
+        total.add(&usage);
+        total.add(&usage);
+        assert_eq!(total.prompt_tokens, 240);
+        assert_eq!(total.total_tokens, 300);
+    }
+
     #[test]
     fn test_parse_response() {
         let content = "<think>I need to tap the button</think><answer>do(action=\"Tap\", element=[100, 200])</answer>";
@@ -3,6 +3,6 @@
 mod client;
 
 pub use client::{
-    MessageBuilder, ModelClient, ModelConfig, ModelResponse, DEFAULT_MAX_RETRIES,
-    DEFAULT_RETRY_DELAY_SECS,
+    MessageBuilder, ModelClient, ModelConfig, ModelError, ModelResponse, TokenUsage,
+    DEFAULT_MAX_RETRIES, DEFAULT_RETRY_DELAY_SECS,
 };
@@ -1,14 +1,32 @@
 //! Shared settings for Phone Agent CLI and GUI.
-//! Persisted in the platform-specific config directory via `directories::ProjectDirs`.
+//! Persisted as `settings.toml` in the platform-specific config directory via
+//! `directories::ProjectDirs`.
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use thiserror::Error;
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::agent::{
+    DualLoopConfig, ExecutorOverrides, NotificationChannel, PlannerConfig, PlannerProfile,
+    PromptMemoryLimits, PromptScope, DEFAULT_MAX_CORRECTIONS_PER_TYPE, DEFAULT_MAX_TASK_TYPES,
+    DEFAULT_MAX_TRAJECTORIES_PER_TYPE, DEFAULT_SUCCESS_HALF_LIFE_DAYS,
+};
+use crate::calibration::CalibrationResult;
+use crate::config::{AppHints, PlannerPreset, ENV_VARS};
+use crate::keychain::{Keychain, SecretStore, KEYCHAIN_MARKER};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// Application settings that can be saved and loaded.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppSettings {
+    /// Layout version of the settings (see [`SETTINGS_SCHEMA_VERSION`])
+    pub schema_version: u32,
     /// Model API base URL
     pub base_url: String,
     /// Model API key
@@ -19,70 +37,347 @@ pub struct AppSettings {
     pub device_id: String,
     /// Language code ("cn" or "en")
     pub lang: String,
+    /// App catalog for `Launch` ("china" or "international")
+    pub app_region: String,
+    /// GUI theme ("dark", "light" or "system")
+    pub theme: String,
+    /// GUI accent color as `#RRGGBB` (empty = the theme's own)
+    pub accent_color: String,
     /// Coordinate system ("relative" or "absolute")
     pub coordinate_system: String,
     /// Coordinate scale X
     pub scale_x: f64,
     /// Coordinate scale Y
     pub scale_y: f64,
+    /// Coordinate offset X in pixels, added after scaling
+    pub offset_x: f64,
+    /// Coordinate offset Y in pixels, added after scaling
+    pub offset_y: f64,
     /// Maximum retries for model requests
     pub max_retries: u32,
     /// Retry delay in seconds
     pub retry_delay: u64,
     /// Maximum steps for agent
     pub max_steps: u32,
+    /// Executor system prompt ("full" or "concise" for small local models)
+    pub prompt_style: String,
+    /// Named version of the executor prompts ("default", "v2", ...)
+    pub prompt_variant: String,
+    /// Actions the model may not use, e.g. ["Type", "Launch"]
+    pub disabled_actions: Vec<String>,
+    /// Seconds the GUI waits for a sensitive-operation confirmation before
+    /// denying it (0 = wait forever)
+    pub confirmation_timeout_secs: u64,
     /// Enable calibration
     pub enable_calibration: bool,
     /// Calibration mode ("simple" or "complex")
     pub calibration_mode: String,
     /// Complex calibration rounds
     pub calibration_rounds: usize,
+    /// Comma-separated complex calibration scenes (empty = all)
+    pub calibration_scenes: String,
+    /// Calibration model requests in flight at the same time
+    pub calibration_concurrency: usize,
+    /// Largest verification residual in pixels before calibration fails (0 = skip)
+    pub calibration_max_residual: f64,
+    /// Show calibration markers on the device and screenshot them back
+    pub calibration_on_device: bool,
+    /// Orientations to calibrate ("portrait", "landscape", "both"; empty = current)
+    pub calibration_orientation: String,
+    /// Recalibrate mid-session when too many taps miss
+    pub auto_recalibrate: bool,
     /// Planner model API base URL
     pub planner_base_url: String,
     /// Planner model API key
     pub planner_api_key: String,
     /// Planner model name
     pub planner_model_name: String,
+    /// Selected planner profile ("cautious", "fast", "verbose" or a custom one; empty = none)
+    pub planner_profile: String,
+    /// User-defined planner profiles (override built-in ones with the same name)
+    pub planner_profiles: Vec<PlannerProfile>,
+    /// Selected planner preset ("deepseek-default", "gpt4o-careful" or a custom one; empty = none)
+    pub planner_preset: String,
+    /// User-defined planner presets (override built-in ones with the same name)
+    pub planner_presets: Vec<PlannerPreset>,
     /// Max executor feedback history for planner
     pub max_executor_feedback_history: usize,
     /// Stuck threshold for planner (consecutive unchanged screens)
     pub stuck_threshold: u32,
+    /// Screenshot similarity (0.0-1.0) at or above which the screen counts as unchanged
+    pub screen_similarity_threshold: f64,
+    /// Executor step timeout in seconds (0 disables the watchdog)
+    pub step_timeout_secs: u64,
+    /// Executor overrides by task type (max steps, coordinates, screenshot quality)
+    pub executor_overrides: HashMap<String, ExecutorOverrides>,
     /// Prompt memory file path
     pub prompt_memory_path: String,
+    /// Maximum task types kept in prompt memory (0 = unlimited)
+    pub prompt_memory_max_task_types: usize,
+    /// Maximum pending corrections kept per task type (0 = unlimited)
+    pub prompt_memory_max_corrections: usize,
+    /// Maximum successful trajectories kept per task type as examples (0 = unlimited)
+    pub prompt_memory_max_trajectories: usize,
+    /// Half-life of prompt success rates in days (0 = no decay)
+    pub prompt_success_half_life_days: u64,
+    /// Shared prompt memory endpoint for syncing across machines (empty = off)
+    pub memory_sync_url: String,
+    /// Episodic memory file path (summaries of past sessions)
+    pub episodic_memory_path: String,
     /// Planner loop interval in milliseconds
     pub planner_interval_ms: u64,
     /// Executor loop interval in milliseconds
     pub executor_interval_ms: u64,
     /// Enable dual-loop mode (planner + executor)
     pub dual_loop_mode: bool,
+    /// Require user approval before running payment/deletion/posting tasks
+    pub require_approval: bool,
+    /// Executor only proposes actions; each one must be approved before it runs
+    pub propose_only: bool,
+    /// Proposals are approved by the user instead of the planner
+    pub user_reviews_proposals: bool,
+    /// Write every dual-loop event to a per-session JSONL file in the data directory
+    pub event_log: bool,
+    /// Address the dual-loop event stream is served on over WebSocket: a port
+    /// on the loopback interface (8765) or host:port (empty = off)
+    pub event_stream_addr: String,
+    /// Webhook URL notified when a dual-loop session finishes or needs a human (empty = off)
+    pub notify_webhook_url: String,
+    /// Telegram bot token for notifications (empty = off)
+    pub notify_telegram_bot_token: String,
+    /// Telegram chat ID that receives notifications
+    pub notify_telegram_chat_id: String,
+    /// Bark push URL, e.g. https://api.day.app/<key> (empty = off)
+    pub notify_bark_url: String,
+    /// Pause dual-loop mode after this many tokens (0 = unlimited)
+    pub max_total_tokens: u64,
+    /// Pause dual-loop mode once the estimated cost reaches this amount (0 = unlimited)
+    pub max_cost: f64,
+    /// Planner price per million tokens (for cost estimation)
+    pub planner_price_per_million: f64,
+    /// Executor price per million tokens (for cost estimation)
+    pub executor_price_per_million: f64,
+    /// Named profile these settings were loaded from (empty = none)
+    pub active_profile: String,
+    /// Folder GUI reports are exported to (empty = `exports` in the data directory)
+    pub export_dir: String,
+    /// Keep API keys in this file instead of the system keychain
+    pub plaintext_api_keys: bool,
+    /// Entries of the file this version doesn't know, kept so that saving
+    /// doesn't drop them (e.g. settings of a newer version)
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, Value>,
+}
+
+/// A rejected settings value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Setting name, e.g. "max_steps".
+    pub field: String,
+    /// What is wrong with the value.
+    pub reason: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+/// Errors loading or saving settings.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SettingsError {
+    #[error("Cannot determine config directory")]
+    NoConfigDir,
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    Invalid(Vec<FieldError>),
+    #[error("Failed to read {}: {reason}", .path.display())]
+    Read { path: PathBuf, reason: String },
+    #[error("Failed to parse {}: {reason}", .path.display())]
+    Parse { path: PathBuf, reason: String },
+    #[error("Failed to write {}: {reason}", .path.display())]
+    Write { path: PathBuf, reason: String },
+    #[error("Failed to serialize settings: {0}")]
+    Serialize(String),
+}
+
+impl SettingsError {
+    /// Error for a single rejected value.
+    pub fn field(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::Invalid(vec![FieldError::new(field, reason)])
+    }
+
+    /// The rejected values, empty for errors not caused by one.
+    pub fn field_errors(&self) -> &[FieldError] {
+        match self {
+            Self::Invalid(errors) => errors,
+            _ => &[],
+        }
+    }
+}
+
+/// How [`AppSettings::to_env_file`] writes each variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvFormat {
+    /// `NAME=value`, for `.env` files, Docker's `--env-file` and systemd's
+    /// `EnvironmentFile=`.
+    #[default]
+    Dotenv,
+    /// `export NAME='value'`, to `source` from a shell.
+    Shell,
+}
+
+/// Layout version of the settings written by this version. Files without a
+/// `schema_version` are version 0.
+pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
+
+/// One step in upgrading stored settings from an older layout.
+struct Migration {
+    /// Version the settings have after this step.
+    version: u32,
+    /// Rewrites the settings document, e.g. renaming or splitting fields.
+    apply: fn(&mut Map<String, Value>),
+}
+
+/// Upgrades of the settings layout, oldest first. A change that renames,
+/// moves or splits a field adds a step here and bumps
+/// [`SETTINGS_SCHEMA_VERSION`], so older files keep their values.
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    apply: fill_legacy_blanks,
+}];
+
+/// Version 1: older versions wrote empty planner model fields, memory paths
+/// and loop intervals; they get their defaults.
+fn fill_legacy_blanks(settings: &mut Map<String, Value>) {
+    let Ok(Value::Object(defaults)) = serde_json::to_value(AppSettings::default()) else {
+        return;
+    };
+    for key in [
+        "planner_base_url",
+        "planner_api_key",
+        "planner_model_name",
+        "prompt_memory_path",
+        "episodic_memory_path",
+        "planner_interval_ms",
+        "executor_interval_ms",
+    ] {
+        let blank = match settings.get(key) {
+            Some(Value::String(value)) => value.is_empty(),
+            Some(value) => value.as_u64() == Some(0),
+            None => false,
+        };
+        if let Some(default) = defaults.get(key).filter(|_| blank) {
+            settings.insert(key.to_string(), default.clone());
+        }
+    }
+}
+
+/// Bring a settings document up to date with `migrations`. Returns the
+/// version it had.
+fn migrate(settings: &mut Map<String, Value>, migrations: &[Migration]) -> u32 {
+    let version = settings
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32;
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        (migration.apply)(settings);
+        settings.insert("schema_version".to_string(), migration.version.into());
+    }
+    version
 }
 
+/// Name of the settings file in the config directory.
+const SETTINGS_FILE: &str = "settings.toml";
+
+/// Name of the settings file before it moved to TOML.
+const LEGACY_SETTINGS_FILE: &str = "settings.json";
+
+/// Comment at the top of a new settings file.
+const SETTINGS_HEADER: &str = "# Phone Agent settings, shared by the CLI and the GUI.\n\
+# Comments added here are kept when the settings are saved.\n\n";
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            schema_version: SETTINGS_SCHEMA_VERSION,
             base_url: "http://localhost:8000/v1".to_string(),
             api_key: "EMPTY".to_string(),
             model_name: "autoglm-phone-9b".to_string(),
             device_id: String::new(),
+            app_region: "china".to_string(),
             lang: "cn".to_string(),
+            theme: "dark".to_string(),
+            accent_color: String::new(),
             coordinate_system: "relative".to_string(),
             scale_x: 1.0,
             scale_y: 1.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
             max_retries: 3,
             retry_delay: 2,
             max_steps: 100,
+            prompt_style: "full".to_string(),
+            prompt_variant: crate::config::DEFAULT_PROMPT_VARIANT.to_string(),
+            disabled_actions: Vec::new(),
+            confirmation_timeout_secs: 60,
             enable_calibration: false,
             calibration_mode: "simple".to_string(),
             calibration_rounds: 5,
+            calibration_scenes: String::new(),
+            calibration_concurrency: 4,
+            calibration_max_residual: 50.0,
+            calibration_on_device: false,
+            calibration_orientation: String::new(),
+            auto_recalibrate: false,
             planner_base_url: "https://api.deepseek.com/v1".to_string(),
             planner_api_key: "EMPTY".to_string(),
             planner_model_name: "deepseek-chat".to_string(),
+            planner_profile: String::new(),
+            planner_profiles: Vec::new(),
+            planner_preset: String::new(),
+            planner_presets: Vec::new(),
             max_executor_feedback_history: 2,
             stuck_threshold: 3,
+            screen_similarity_threshold: 0.95,
+            step_timeout_secs: 120,
+            executor_overrides: HashMap::new(),
             prompt_memory_path: "prompt_memory.json".to_string(),
+            prompt_memory_max_task_types: DEFAULT_MAX_TASK_TYPES,
+            prompt_memory_max_corrections: DEFAULT_MAX_CORRECTIONS_PER_TYPE,
+            prompt_memory_max_trajectories: DEFAULT_MAX_TRAJECTORIES_PER_TYPE,
+            prompt_success_half_life_days: DEFAULT_SUCCESS_HALF_LIFE_DAYS,
+            memory_sync_url: String::new(),
+            episodic_memory_path: "episodic_memory.json".to_string(),
             planner_interval_ms: 2000,
             executor_interval_ms: 500,
             dual_loop_mode: false,
+            require_approval: false,
+            propose_only: false,
+            user_reviews_proposals: false,
+            event_log: true,
+            event_stream_addr: String::new(),
+            notify_webhook_url: String::new(),
+            notify_telegram_bot_token: String::new(),
+            notify_telegram_chat_id: String::new(),
+            notify_bark_url: String::new(),
+            max_total_tokens: 0,
+            max_cost: 0.0,
+            planner_price_per_million: 0.0,
+            executor_price_per_million: 0.0,
+            active_profile: String::new(),
+            export_dir: String::new(),
+            plaintext_api_keys: false,
+            unknown: BTreeMap::new(),
         }
     }
 }
@@ -94,63 +389,1354 @@ impl AppSettings {
             .map(|dirs| dirs.config_dir().to_path_buf())
     }
 
+    /// Get the folder extra UI languages are loaded from (`ja.json`,
+    /// `zh-TW.ftl`, ...).
+    pub fn languages_dir() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("languages"))
+    }
+
+    /// Get the folder prompt overrides are read from when `PROMPTS_DIR`
+    /// isn't set (`system_prompt_zh.md`, `planner_system_prompt_en.md`, ...).
+    pub fn prompts_dir() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("prompts"))
+    }
+
+    /// Get the file per-app prompt hints are stored in.
+    pub fn app_hints_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("app_hints.json"))
+    }
+
+    /// Load the per-app prompt hints (none if the file is missing or broken).
+    pub fn load_app_hints() -> AppHints {
+        Self::app_hints_path()
+            .and_then(|path| AppHints::load(path).ok())
+            .unwrap_or_default()
+    }
+
     /// Get the settings file path.
     pub fn settings_path() -> Option<PathBuf> {
-        Self::config_dir().map(|dir| dir.join("settings.json"))
+        Self::config_dir().map(|dir| dir.join(SETTINGS_FILE))
+    }
+
+    /// Get the path of the JSON settings file used before `settings.toml`.
+    pub fn legacy_settings_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join(LEGACY_SETTINGS_FILE))
     }
 
     /// Load settings from the config file.
+    ///
+    /// Without a `settings.toml`, an existing `settings.json` is converted to
+    /// it once and renamed to `settings.json.bak`. API keys are read from the
+    /// system keychain; keys still written in the file move there the next
+    /// time the settings are saved. A file that can't be read gives the
+    /// defaults; see [`AppSettings::try_load`] for what is wrong with it.
     pub fn load() -> Self {
-        let defaults = Self::default();
+        Self::try_load().unwrap_or_else(|e| {
+            tracing::warn!("Using the default settings: {}", e);
+            Self::default()
+        })
+    }
 
-        let mut loaded: AppSettings = Self::settings_path()
-            .and_then(|path| fs::read_to_string(&path).ok())
+    /// Like [`AppSettings::load`], but failing on a settings file that can't
+    /// be read, with [`SettingsError::Invalid`] naming the fields whose
+    /// values don't fit.
+    pub fn try_load() -> Result<Self, SettingsError> {
+        let Some(dir) = Self::config_dir() else {
+            return Ok(Self::default());
+        };
+        let mut settings = Self::load_from(&dir)?;
+        if settings.has_plaintext_secrets() {
+            tracing::info!(
+                "API keys in {} move to the system keychain when the settings are next saved",
+                SETTINGS_FILE
+            );
+        }
+        settings.restore_secrets(&Keychain, "");
+        Ok(settings)
+    }
+
+    /// Load the settings file in `dir`, migrating a JSON one to TOML and an
+    /// older layout to the current one.
+    fn load_from(dir: &Path) -> Result<Self, SettingsError> {
+        let path = dir.join(SETTINGS_FILE);
+        if let Ok(content) = fs::read_to_string(&path) {
+            let document = toml_edit::de::from_str(&content).map_err(|e| SettingsError::Parse {
+                path: path.clone(),
+                reason: e.to_string(),
+            })?;
+            let (settings, upgraded) = Self::from_document(document, &path)?;
+            if upgraded {
+                if let Err(e) = settings.write_toml(dir) {
+                    tracing::warn!("Failed to upgrade {}: {}", path.display(), e);
+                }
+            }
+            return Ok(settings);
+        }
+
+        let legacy = dir.join(LEGACY_SETTINGS_FILE);
+        let Some((settings, _)) = fs::read_to_string(&legacy)
+            .ok()
             .and_then(|content| serde_json::from_str(&content).ok())
-            .unwrap_or_default();
+            .and_then(|document| Self::from_document(document, &legacy).ok())
+        else {
+            return Ok(Self::default());
+        };
+        match settings.write_toml(dir) {
+            Ok(()) => {
+                let backup = legacy.with_extension("json.bak");
+                if let Err(e) = fs::rename(&legacy, &backup) {
+                    tracing::warn!("Failed to rename {}: {}", legacy.display(), e);
+                }
+                tracing::info!("Migrated {} to {}", legacy.display(), path.display());
+            }
+            Err(e) => tracing::warn!("Failed to migrate settings to TOML: {}", e),
+        }
+        Ok(settings)
+    }
 
-        // Backfill new fields when loading older config files
-        if loaded.planner_base_url.is_empty() {
-            loaded.planner_base_url = defaults.planner_base_url;
+    /// Read a settings document of any layout version, upgrading it to the
+    /// current one. Returns the settings and whether they were upgraded.
+    /// `path` is the file the document came from, for error messages.
+    fn from_document(document: Value, path: &Path) -> Result<(Self, bool), SettingsError> {
+        let Value::Object(mut document) = document else {
+            return Err(SettingsError::Parse {
+                path: path.to_path_buf(),
+                reason: "settings must be a table".to_string(),
+            });
+        };
+        // Nulls (only JSON has them) mean "not set"
+        document.retain(|_, value| !value.is_null());
+        let version = migrate(&mut document, MIGRATIONS);
+        if version > SETTINGS_SCHEMA_VERSION {
+            tracing::warn!(
+                "Settings were written by a newer version (schema {}, this one knows {})",
+                version,
+                SETTINGS_SCHEMA_VERSION
+            );
         }
-        if loaded.planner_api_key.is_empty() {
-            loaded.planner_api_key = defaults.planner_api_key;
+        let settings: Self = match serde_json::from_value(Value::Object(document.clone())) {
+            Ok(settings) => settings,
+            Err(e) => {
+                let errors = Self::rejected_fields(&document);
+                return Err(if errors.is_empty() {
+                    SettingsError::Parse {
+                        path: path.to_path_buf(),
+                        reason: e.to_string(),
+                    }
+                } else {
+                    SettingsError::Invalid(errors)
+                });
+            }
+        };
+        if !settings.unknown.is_empty() {
+            tracing::warn!(
+                "Unknown settings kept as they are: {}",
+                settings
+                    .unknown
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
         }
-        if loaded.planner_model_name.is_empty() {
-            loaded.planner_model_name = defaults.planner_model_name;
+        Ok((settings, version < SETTINGS_SCHEMA_VERSION))
+    }
+
+    /// The entries of a document that don't fit their field, found by
+    /// reading each on its own on top of the defaults.
+    fn rejected_fields(document: &Map<String, Value>) -> Vec<FieldError> {
+        let Ok(Value::Object(defaults)) = serde_json::to_value(Self::default()) else {
+            return Vec::new();
+        };
+        document
+            .iter()
+            .filter_map(|(key, value)| {
+                let mut single = defaults.clone();
+                single.insert(key.clone(), value.clone());
+                serde_json::from_value::<Self>(Value::Object(single))
+                    .err()
+                    .map(|e| FieldError::new(key, e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Save settings to the config file. Comments and the order of the
+    /// entries already in the file are kept. Settings with values that are
+    /// wrong wherever they are used (see [`AppSettings::invalid_fields`])
+    /// are refused.
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let dir = Self::config_dir().ok_or(SettingsError::NoConfigDir)?;
+        self.check()?;
+        let mut settings = self.clone();
+        settings.stash_secrets(&Keychain, "");
+        settings.write_toml(&dir)
+    }
+
+    /// Fail with the [`AppSettings::invalid_fields`], if there are any.
+    fn check(&self) -> Result<(), SettingsError> {
+        let errors = self.invalid_fields();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SettingsError::Invalid(errors))
         }
-        if loaded.prompt_memory_path.is_empty() {
-            loaded.prompt_memory_path = defaults.prompt_memory_path;
+    }
+
+    /// Write these settings to a TOML file, e.g. to copy a working setup to
+    /// another machine or attach it to a bug report. API keys, the Telegram
+    /// bot token and the notification URLs are left blank.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<(), SettingsError> {
+        let mut settings = self.clone();
+        for value in settings.exported_secrets_mut() {
+            value.clear();
         }
-        if loaded.planner_interval_ms == 0 {
-            loaded.planner_interval_ms = defaults.planner_interval_ms;
+        settings.export_with_secrets(path)
+    }
+
+    /// Like [`AppSettings::export`], but keeping the secrets in the file.
+    pub fn export_with_secrets(&self, path: impl AsRef<Path>) -> Result<(), SettingsError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| SettingsError::Write {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            })?;
         }
-        if loaded.executor_interval_ms == 0 {
-            loaded.executor_interval_ms = defaults.executor_interval_ms;
+        let content = self.to_toml(None)?;
+        write_private(path, &content)
+    }
+
+    /// Take over the settings of an exported file (TOML, or JSON for a
+    /// `.json` file). Secrets the file leaves blank keep their current
+    /// values, as do the active profile and `plaintext_api_keys`, which
+    /// belong to this machine.
+    pub fn import(&mut self, path: impl AsRef<Path>) -> Result<(), SettingsError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| SettingsError::Read {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        let document = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            toml_edit::de::from_str(&content).map_err(|e| e.to_string())
+        };
+        let document = document.map_err(|reason| SettingsError::Parse {
+            path: path.to_path_buf(),
+            reason,
+        })?;
+        let (mut imported, _) = Self::from_document(document, path)?;
+        for (value, current) in imported
+            .exported_secrets_mut()
+            .into_iter()
+            .zip(self.exported_secrets_mut())
+        {
+            if value.trim().is_empty() || value.as_str() == KEYCHAIN_MARKER {
+                *value = std::mem::take(current);
+            }
         }
+        imported.active_profile = std::mem::take(&mut self.active_profile);
+        imported.plaintext_api_keys = self.plaintext_api_keys;
+        *self = imported;
+        Ok(())
+    }
 
-        loaded
+    /// These settings as the environment variables that set them (see
+    /// [`ENV_VARS`]), in that list's order. Lists are comma-separated and
+    /// tables are written as JSON.
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let Ok(Value::Object(settings)) = serde_json::to_value(self) else {
+            return Vec::new();
+        };
+        ENV_VARS
+            .iter()
+            .filter_map(|&(name, key)| {
+                let value = match settings.get(key)? {
+                    Value::String(value) => value.clone(),
+                    Value::Array(items) => items
+                        .iter()
+                        .map(|item| match item {
+                            Value::String(item) => item.clone(),
+                            item => item.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    value => value.to_string(),
+                };
+                Some((name, value))
+            })
+            .collect()
     }
 
-    /// Save settings to the config file.
-    pub fn save(&self) -> Result<(), String> {
-        let dir = Self::config_dir().ok_or("Cannot determine config directory")?;
+    /// These settings as environment variables, one per line, for
+    /// deployments without a settings file (Docker, systemd). The fields
+    /// [`AppSettings::export`] leaves out are commented out and blank unless
+    /// `with_secrets` is set.
+    pub fn to_env_file(&self, format: EnvFormat, with_secrets: bool) -> String {
+        let mut settings = self.clone();
+        for value in settings.exported_secrets_mut() {
+            if !with_secrets || value.as_str() == KEYCHAIN_MARKER {
+                value.clear();
+            }
+        }
+        let mut content = String::from("# Phone Agent settings as environment variables\n");
+        for ((name, value), (_, original)) in settings.env_vars().into_iter().zip(self.env_vars()) {
+            // A secret left out, to be filled in by hand
+            if value != original {
+                content.push_str("# ");
+            }
+            match format {
+                EnvFormat::Dotenv => content.push_str(&format!("{}={}\n", name, value)),
+                EnvFormat::Shell => content.push_str(&format!(
+                    "export {}='{}'\n",
+                    name,
+                    value.replace('\'', r"'\''")
+                )),
+            }
+        }
+        content
+    }
 
+    /// Fields [`AppSettings::export`] leaves out.
+    fn exported_secrets_mut(&mut self) -> [&mut String; 5] {
+        [
+            &mut self.api_key,
+            &mut self.planner_api_key,
+            &mut self.notify_telegram_bot_token,
+            &mut self.notify_webhook_url,
+            &mut self.notify_bark_url,
+        ]
+    }
+
+    /// The API key fields with their names.
+    fn secrets_mut(&mut self) -> [(&'static str, &mut String); 2] {
+        [
+            ("api_key", &mut self.api_key),
+            ("planner_api_key", &mut self.planner_api_key),
+        ]
+    }
+
+    /// Whether API keys would be written to the file although they belong
+    /// in the keychain.
+    fn has_plaintext_secrets(&self) -> bool {
+        !self.plaintext_api_keys
+            && [&self.api_key, &self.planner_api_key]
+                .iter()
+                .any(|key| is_secret(key) && key.as_str() != KEYCHAIN_MARKER)
+    }
+
+    /// Move the API keys into `store` under `prefix` + field name, leaving
+    /// [`KEYCHAIN_MARKER`] in their place. Entries of keys that are blank are
+    /// removed. Does nothing if `plaintext_api_keys` is set. Without a
+    /// usable keychain (e.g. on a headless host) a key stays in the file,
+    /// with a warning.
+    fn stash_secrets(&mut self, store: &dyn SecretStore, prefix: &str) {
+        if self.plaintext_api_keys {
+            return;
+        }
+        for (field, value) in self.secrets_mut() {
+            let account = format!("{}{}", prefix, field);
+            if value.as_str() == KEYCHAIN_MARKER {
+                continue;
+            }
+            if is_secret(value) {
+                match store.set(&account, value) {
+                    Ok(()) => *value = KEYCHAIN_MARKER.to_string(),
+                    Err(e) => tracing::warn!(
+                        "Keeping {} in plain text in the settings file, the system keychain \
+                         is unavailable: {} (set plaintext_api_keys = true to silence this)",
+                        field,
+                        e
+                    ),
+                }
+            } else if let Err(e) = store.delete(&account) {
+                tracing::debug!("Failed to remove {} from the keychain: {}", account, e);
+            }
+        }
+    }
+
+    /// Replace every [`KEYCHAIN_MARKER`] with the key stored in `store`. A
+    /// key that can't be read keeps the marker, which
+    /// [`Config::validate`](crate::config::Config::validate) reports.
+    fn restore_secrets(&mut self, store: &dyn SecretStore, prefix: &str) {
+        for (field, value) in self.secrets_mut() {
+            if value.as_str() != KEYCHAIN_MARKER {
+                continue;
+            }
+            let account = format!("{}{}", prefix, field);
+            match store.get(&account) {
+                Ok(Some(secret)) => *value = secret,
+                Ok(None) => tracing::warn!("{} is not in the system keychain", account),
+                Err(e) => tracing::warn!("Failed to read {} from the keychain: {}", account, e),
+            }
+        }
+    }
+
+    /// Write these settings as `settings.toml` into `dir`, updating the file
+    /// that is already there.
+    fn write_toml(&self, dir: &Path) -> Result<(), SettingsError> {
+        let path = dir.join(SETTINGS_FILE);
+        fs::create_dir_all(dir).map_err(|e| SettingsError::Write {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+
+        let existing = fs::read_to_string(&path).ok();
+        let content = self.to_toml(existing.as_deref())?;
+
+        write_private(&path, &content)
+    }
+
+    /// These settings as a TOML document. Given the current file, its
+    /// comments and layout are kept and only the values are updated.
+    fn to_toml(&self, existing: Option<&str>) -> Result<String, SettingsError> {
+        let updated: DocumentMut = toml_edit::ser::to_string_pretty(self)
+            .map_err(|e| SettingsError::Serialize(e.to_string()))?
+            .parse()
+            .map_err(|e: toml_edit::TomlError| SettingsError::Serialize(e.to_string()))?;
+        let document = match existing.and_then(|text| text.parse::<DocumentMut>().ok()) {
+            Some(mut document) => {
+                merge_toml(document.as_table_mut(), updated.as_table());
+                document
+            }
+            None => {
+                let mut document = updated;
+                document.decor_mut().set_prefix(SETTINGS_HEADER);
+                document
+            }
+        };
+        Ok(document.to_string())
+    }
+
+    /// Write these settings as `file_name` into `dir`, creating it if needed.
+    fn write_to(&self, dir: &Path, file_name: &str) -> Result<(), SettingsError> {
+        let path = dir.join(file_name);
         // Create config directory if it doesn't exist
-        fs::create_dir_all(&dir)
-            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        fs::create_dir_all(dir).map_err(|e| SettingsError::Write {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
 
-        let path = dir.join("settings.json");
         let content = serde_json::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+            .map_err(|e| SettingsError::Serialize(e.to_string()))?;
 
-        fs::write(&path, content).map_err(|e| format!("Failed to write settings file: {}", e))?;
+        write_private(&path, &content)
+    }
+
+    /// Get the directory named settings profiles are stored in, one file each.
+    pub fn profiles_dir() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("profiles"))
+    }
+
+    /// Names of all stored settings profiles, sorted.
+    pub fn list_profiles() -> Vec<String> {
+        list_json_files(Self::profiles_dir())
+    }
 
+    /// Load a named settings profile.
+    pub fn load_profile(name: &str) -> Result<Self, SettingsError> {
+        let path = Self::profile_path(name)?;
+        let content = fs::read_to_string(&path).map_err(|e| SettingsError::Read {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+        let document = serde_json::from_str(&content).map_err(|e| SettingsError::Parse {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+        let (mut loaded, upgraded) = Self::from_document(document, &path)?;
+        if upgraded {
+            if let Err(e) = loaded.save_profile(name) {
+                tracing::warn!("Failed to upgrade profile {}: {}", name, e);
+            }
+        }
+        loaded.restore_secrets(&Keychain, &profile_account(name));
+        loaded.active_profile = name.trim().to_string();
+        Ok(loaded)
+    }
+
+    /// Store these settings as a named profile, replacing one with the same name.
+    pub fn save_profile(&self, name: &str) -> Result<(), SettingsError> {
+        let file_name = profile_file_name(name)
+            .map_err(|reason| SettingsError::field("active_profile", reason))?;
+        let dir = Self::profiles_dir().ok_or(SettingsError::NoConfigDir)?;
+        self.check()?;
+        let mut profile = self.clone();
+        profile.active_profile = name.trim().to_string();
+        profile.stash_secrets(&Keychain, &profile_account(name));
+        profile.write_to(&dir, &file_name)
+    }
+
+    /// Delete a named settings profile.
+    pub fn delete_profile(name: &str) -> Result<(), SettingsError> {
+        let path = Self::profile_path(name)?;
+        fs::remove_file(&path).map_err(|e| SettingsError::Write {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+        // Without keys, stashing removes the profile's keychain entries
+        Self::default().stash_secrets(&Keychain, &profile_account(name));
         Ok(())
     }
 
+    /// File of a named settings profile.
+    fn profile_path(name: &str) -> Result<PathBuf, SettingsError> {
+        let file_name = profile_file_name(name)
+            .map_err(|reason| SettingsError::field("active_profile", reason))?;
+        Ok(Self::profiles_dir()
+            .ok_or(SettingsError::NoConfigDir)?
+            .join(file_name))
+    }
+
+    /// Take over a successful calibration: its scale factors, offsets and mode.
+    /// Switches to the absolute coordinate system, the only one they apply to.
+    pub fn apply_calibration(&mut self, result: &CalibrationResult) {
+        self.coordinate_system = "absolute".to_string();
+        self.scale_x = result.scale_x;
+        self.scale_y = result.scale_y;
+        self.offset_x = result.offset_x;
+        self.offset_y = result.offset_y;
+        self.calibration_mode = result.mode.as_str().to_string();
+    }
+
+    /// Write a successful calibration into the settings file, leaving every
+    /// other stored value (and any environment overrides) untouched.
+    pub fn save_calibration(result: &CalibrationResult) -> Result<(), SettingsError> {
+        let mut stored = Self::load();
+        stored.apply_calibration(result);
+        stored.save()
+    }
+
+    /// Get the default dual-loop session file path.
+    pub fn session_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("session.json"))
+    }
+
+    /// Get the file the dual-loop todo list is persisted to.
+    pub fn todo_list_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("todo_list.json"))
+    }
+
+    /// Get the file per-device calibration profiles are stored in.
+    pub fn calibration_profiles_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("calibration_profiles.json"))
+    }
+
+    /// Get the file the apps found on each device are cached in.
+    pub fn installed_apps_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("installed_apps.json"))
+    }
+
+    /// Get the directory for per-session dual-loop event logs.
+    pub fn event_logs_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("events"))
+    }
+
+    /// Get the directory screenshots attached to user corrections are saved to.
+    pub fn correction_screenshots_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("correction_screenshots"))
+    }
+
+    /// Notification channels configured in these settings.
+    pub fn notification_channels(&self) -> Vec<NotificationChannel> {
+        let mut channels = Vec::new();
+        if !self.notify_webhook_url.is_empty() {
+            channels.push(NotificationChannel::Webhook {
+                url: self.notify_webhook_url.clone(),
+            });
+        }
+        if !self.notify_telegram_bot_token.is_empty() && !self.notify_telegram_chat_id.is_empty() {
+            channels.push(NotificationChannel::Telegram {
+                bot_token: self.notify_telegram_bot_token.clone(),
+                chat_id: self.notify_telegram_chat_id.clone(),
+            });
+        }
+        if !self.notify_bark_url.is_empty() {
+            channels.push(NotificationChannel::Bark {
+                url: self.notify_bark_url.clone(),
+            });
+        }
+        channels
+    }
+
+    /// Prompt memory size caps configured in these settings.
+    pub fn prompt_memory_limits(&self) -> PromptMemoryLimits {
+        PromptMemoryLimits {
+            max_task_types: Some(self.prompt_memory_max_task_types).filter(|&n| n > 0),
+            max_corrections_per_type: Some(self.prompt_memory_max_corrections).filter(|&n| n > 0),
+            max_trajectories_per_type: Some(self.prompt_memory_max_trajectories).filter(|&n| n > 0),
+        }
+    }
+
+    /// The selected planner profile, `None` if none is set or it is unknown.
+    pub fn planner_profile(&self, lang: &str) -> Option<PlannerProfile> {
+        if self.planner_profile.is_empty() {
+            return None;
+        }
+        PlannerProfile::find(&self.planner_profile, &self.planner_profiles, lang)
+    }
+
+    /// Planner configuration built from these settings, with learned prompts
+    /// kept under `prompt_scope`.
+    pub fn planner_config(
+        &self,
+        lang: &str,
+        profile: Option<&PlannerProfile>,
+        prompt_scope: PromptScope,
+    ) -> PlannerConfig {
+        let model_config = crate::model::ModelConfig::default()
+            .with_base_url(&self.planner_base_url)
+            .with_api_key(&self.planner_api_key)
+            .with_model_name(&self.planner_model_name);
+
+        let config = PlannerConfig::default()
+            .with_model_config(model_config)
+            .with_max_feedback_history(self.max_executor_feedback_history)
+            .with_stuck_threshold(self.stuck_threshold)
+            .with_screen_similarity_threshold(self.screen_similarity_threshold)
+            .with_step_timeout_secs(self.step_timeout_secs)
+            .with_prompt_memory_path(&self.prompt_memory_path)
+            .with_prompt_memory_limits(self.prompt_memory_limits())
+            .with_prompt_success_half_life_days(self.prompt_success_half_life_days)
+            .with_prompt_scope(prompt_scope)
+            .with_episodic_memory_path(&self.episodic_memory_path)
+            .with_propose_only(self.propose_only, self.user_reviews_proposals)
+            .with_lang(lang);
+        let config =
+            self.executor_overrides
+                .iter()
+                .fold(config, |config, (task_type, overrides)| {
+                    config.with_executor_overrides(task_type, overrides.clone())
+                });
+        let config = match profile {
+            Some(profile) => config.with_profile(profile),
+            None => config,
+        };
+        let config = if self.memory_sync_url.is_empty() {
+            config
+        } else {
+            config.with_memory_sync_url(&self.memory_sync_url)
+        };
+        let config = match Self::correction_screenshots_dir() {
+            Some(dir) => config.with_correction_screenshot_dir(dir.display().to_string()),
+            None => config,
+        };
+        match Self::todo_list_path() {
+            Some(path) => config.with_todo_list_path(path.display().to_string()),
+            None => config,
+        }
+    }
+
+    /// Planner interval, overridden by the profile if it sets one.
+    pub fn planner_interval_ms(&self, profile: Option<&PlannerProfile>) -> u64 {
+        profile
+            .and_then(|p| p.planner_interval_ms)
+            .unwrap_or(self.planner_interval_ms)
+    }
+
+    /// Dual-loop configuration built from these settings.
+    pub fn dual_loop_config(&self, profile: Option<&PlannerProfile>) -> DualLoopConfig {
+        let mut config = DualLoopConfig::default()
+            .with_planner_interval(self.planner_interval_ms(profile))
+            .with_executor_interval(self.executor_interval_ms)
+            .with_require_approval(self.require_approval)
+            .with_token_prices(
+                self.planner_price_per_million,
+                self.executor_price_per_million,
+            );
+        if self.max_total_tokens > 0 {
+            config = config.with_max_tokens(self.max_total_tokens);
+        }
+        if self.max_cost > 0.0 {
+            config = config.with_max_cost(self.max_cost);
+        }
+        if let Some(path) = Self::session_path() {
+            config = config.with_session_path(path.display().to_string());
+        }
+        if self.event_log {
+            if let Some(dir) = Self::event_logs_dir() {
+                config = config.with_event_log_dir(dir.display().to_string());
+            }
+        }
+        if !self.event_stream_addr.trim().is_empty() {
+            config = config.with_event_stream_addr(self.event_stream_addr.trim());
+        }
+        config
+    }
+
+    /// Get the directory recorded macros are stored in.
+    pub fn macros_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("macros"))
+    }
+
+    /// Names of all recorded macros, sorted.
+    pub fn list_macros() -> Vec<String> {
+        list_json_files(Self::macros_dir())
+    }
+
+    /// Get the file of a named macro.
+    pub fn macro_path(name: &str) -> Result<PathBuf, String> {
+        let file_name = macro_file_name(name)?;
+        Ok(Self::macros_dir()
+            .ok_or("Cannot determine data directory")?
+            .join(file_name))
+    }
+
+    /// Get the file finished GUI tasks are stored in.
+    pub fn task_history_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("task_history.json"))
+    }
+
+    /// Get the directory Markdown reports of GUI tasks are written to.
+    pub fn reports_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("reports"))
+    }
+
+    /// Get the directory GUI reports are exported to when no folder is chosen.
+    pub fn default_exports_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "moderras", "phone-agent")
+            .map(|dirs| dirs.data_dir().join("exports"))
+    }
+
+    /// Folder GUI reports are exported to.
+    pub fn exports_dir(&self) -> Option<PathBuf> {
+        match self.export_dir.trim() {
+            "" => Self::default_exports_dir(),
+            dir => Some(PathBuf::from(dir)),
+        }
+    }
+
+    /// Take over the settings a running session can change without a
+    /// restart (see [`LiveSettings`](crate::agent::LiveSettings)) from `stored`.
+    pub fn take_live_settings(&mut self, stored: &AppSettings) {
+        self.scale_x = stored.scale_x;
+        self.scale_y = stored.scale_y;
+        self.offset_x = stored.offset_x;
+        self.offset_y = stored.offset_y;
+        self.planner_interval_ms = stored.planner_interval_ms;
+        self.executor_interval_ms = stored.executor_interval_ms;
+        self.stuck_threshold = stored.stuck_threshold;
+        self.screen_similarity_threshold = stored.screen_similarity_threshold;
+        self.lang = stored.lang.clone();
+    }
+
+    /// Set the disabled actions from a comma-separated list, e.g. "Type, Launch".
+    pub fn set_disabled_actions(&mut self, list: &str) {
+        self.disabled_actions = split_list(list);
+    }
+
+    /// Set a setting from text, as given in an environment variable or on the
+    /// command line. `key` is a field name such as "max_steps"; "scale" sets
+    /// both scale factors. The text is read according to the field's type:
+    /// numbers, booleans ("1"/"true"/"yes"/"on" or "0"/"false"/"no"/"off"),
+    /// comma-separated lists, and JSON for anything else.
+    pub fn set_value(&mut self, key: &str, text: &str) -> Result<(), String> {
+        if key == "scale" {
+            self.set_value("scale_x", text)?;
+            return self.set_value("scale_y", text);
+        }
+        let mut settings = serde_json::to_value(&*self).map_err(|e| e.to_string())?;
+        let field = settings
+            .get_mut(key)
+            .ok_or_else(|| format!("Unknown setting: {}", key))?;
+        let trimmed = text.trim();
+        *field = match field {
+            serde_json::Value::String(_) => serde_json::Value::String(text.to_string()),
+            serde_json::Value::Bool(_) => serde_json::Value::Bool(
+                parse_bool(trimmed)
+                    .ok_or_else(|| format!("expected true or false, got \"{}\"", text))?,
+            ),
+            serde_json::Value::Number(_) => serde_json::from_str(trimmed)
+                .map_err(|_| format!("expected a number, got \"{}\"", text))?,
+            serde_json::Value::Array(_) if !trimmed.starts_with('[') => {
+                serde_json::Value::from(split_list(text))
+            }
+            _ => serde_json::from_str(trimmed).map_err(|e| e.to_string())?,
+        };
+        *self = serde_json::from_value(settings).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Whether `key` names a setting accepted by [`AppSettings::set_value`].
+    pub fn has_key(key: &str) -> bool {
+        key == "scale"
+            || serde_json::to_value(Self::default())
+                .ok()
+                .is_some_and(|settings| settings.get(key).is_some())
+    }
+
     /// Get logs directory path.
     pub fn logs_dir() -> Option<PathBuf> {
         directories::ProjectDirs::from("com", "moderras", "phone-agent")
             .map(|dirs| dirs.data_dir().join("logs"))
     }
 }
+
+/// Notices when the settings file changes, so running sessions can take over
+/// the new values (see [`Config::live_settings`](crate::config::Config::live_settings)).
+#[derive(Debug, Clone)]
+pub struct SettingsWatcher {
+    /// Profile whose file is watched (empty = `settings.toml`).
+    profile: String,
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+}
+
+impl SettingsWatcher {
+    /// Watch the file of the named settings profile, or `settings.toml`
+    /// without one.
+    pub fn new(profile: Option<&str>) -> Self {
+        let profile = profile.unwrap_or_default().trim().to_string();
+        let path = if profile.is_empty() {
+            AppSettings::settings_path()
+        } else {
+            profile_file_name(&profile)
+                .ok()
+                .and_then(|file_name| Some(AppSettings::profiles_dir()?.join(file_name)))
+        };
+        Self::with_path(profile, path)
+    }
+
+    fn with_path(profile: String, path: Option<PathBuf>) -> Self {
+        let modified = path.as_deref().and_then(modified_time);
+        Self {
+            profile,
+            path,
+            modified,
+        }
+    }
+
+    /// The stored settings if the file changed since the last call, or why
+    /// the changed file can't be read.
+    pub fn poll(&mut self) -> Option<Result<AppSettings, SettingsError>> {
+        if !self.changed() {
+            return None;
+        }
+        let settings = if self.profile.is_empty() {
+            AppSettings::try_load()
+        } else {
+            AppSettings::load_profile(&self.profile)
+        };
+        // Loading may rewrite the file (upgrades, keys moved to the keychain)
+        self.changed();
+        Some(settings)
+    }
+
+    /// Whether the file's modification time changed since the last check.
+    fn changed(&mut self) -> bool {
+        let modified = self.path.as_deref().and_then(modified_time);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        // A deleted file isn't a change to apply
+        modified.is_some()
+    }
+}
+
+/// Modification time of a file, `None` if it doesn't exist.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Whether an API key is a real one, not blank or the "EMPTY" placeholder.
+fn is_secret(key: &str) -> bool {
+    let key = key.trim();
+    !key.is_empty() && !key.eq_ignore_ascii_case("EMPTY")
+}
+
+/// Prefix of the keychain entries of a settings profile.
+fn profile_account(name: &str) -> String {
+    format!("profile:{}:", name.trim())
+}
+
+/// Write a file only the current user can read, as it may hold API keys.
+fn write_private(path: &Path, content: &str) -> Result<(), SettingsError> {
+    let failed = |e: std::io::Error| SettingsError::Write {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    };
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(failed)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // `mode` only applies to new files
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(failed)?;
+    }
+    std::io::Write::write_all(&mut file, content.as_bytes()).map_err(failed)
+}
+
+/// Update `table` to the values of `updated`, keeping the comments and
+/// formatting around the entries both have.
+fn merge_toml(table: &mut Table, updated: &Table) {
+    let removed: Vec<String> = table
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !updated.contains_key(key))
+        .collect();
+    for key in removed {
+        table.remove(&key);
+    }
+    for (key, item) in updated.iter() {
+        match (table.get_mut(key), item) {
+            (Some(Item::Table(current)), Item::Table(item)) => merge_toml(current, item),
+            (Some(Item::Value(current)), Item::Value(item)) => {
+                let decor = current.decor().clone();
+                *current = item.clone();
+                *current.decor_mut() = decor;
+            }
+            (Some(current), item) => *current = item.clone(),
+            (None, item) => {
+                table.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+/// Items of a comma-separated list, trimmed, without empty ones.
+fn split_list(list: &str) -> Vec<String> {
+    list.split([',', '，'])
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read a yes/no setting.
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Names (file stems) of the JSON files in a directory, sorted.
+fn list_json_files(dir: Option<PathBuf>) -> Vec<String> {
+    let Some(entries) = dir.and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// File name of a settings profile. Names that could leave the profiles
+/// directory are rejected.
+fn profile_file_name(name: &str) -> Result<String, String> {
+    json_file_name(name, "Profile")
+}
+
+/// File name of a recorded macro, checked like profile names.
+fn macro_file_name(name: &str) -> Result<String, String> {
+    json_file_name(name, "Macro")
+}
+
+/// `<name>.json`, unless the name is empty or could leave its directory.
+fn json_file_name(name: &str, kind: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("{} name is empty", kind));
+    }
+    if name.starts_with('.') || name.contains(['/', '\\', ':']) {
+        return Err(format!("Invalid {} name: {}", kind.to_lowercase(), name));
+    }
+    Ok(format!("{}.json", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_profile_file_name() {
+        assert_eq!(profile_file_name(" home vLLM ").unwrap(), "home vLLM.json");
+        assert_eq!(profile_file_name("工作手机").unwrap(), "工作手机.json");
+        assert!(profile_file_name("  ").is_err());
+        assert!(profile_file_name("../settings").is_err());
+        assert!(profile_file_name("a/b").is_err());
+        assert!(profile_file_name("a\\b").is_err());
+    }
+
+    #[test]
+    fn test_macro_file_name() {
+        assert_eq!(macro_file_name("登录").unwrap(), "登录.json");
+        assert_eq!(
+            macro_file_name("../login").unwrap_err(),
+            "Invalid macro name: ../login"
+        );
+    }
+
+    #[test]
+    fn test_toml_keeps_comments() {
+        let mut settings = AppSettings::default();
+        settings.executor_overrides.insert(
+            "地图导航".to_string(),
+            ExecutorOverrides {
+                max_steps: Some(200),
+                ..ExecutorOverrides::default()
+            },
+        );
+        let text = settings.to_toml(None).unwrap();
+        assert!(text.starts_with("# Phone Agent settings"));
+        let parsed: AppSettings = toml_edit::de::from_str(&text).unwrap();
+        assert_eq!(parsed.executor_overrides["地图导航"].max_steps, Some(200));
+
+        let text = text.replace(
+            "max_steps = 100",
+            "# More for long tasks\nmax_steps = 100 # was 50",
+        );
+        settings.max_steps = 150;
+        let text = settings.to_toml(Some(&text)).unwrap();
+        assert!(text.contains("# More for long tasks\nmax_steps = 150 # was 50"));
+    }
+
+    #[test]
+    fn test_json_migration() {
+        let dir = std::env::temp_dir().join(format!("settings-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(LEGACY_SETTINGS_FILE),
+            r#"{"model_name": "glm-4v", "max_steps": 42}"#,
+        )
+        .unwrap();
+
+        let settings = AppSettings::load_from(&dir).unwrap();
+        assert_eq!(
+            (settings.model_name.as_str(), settings.max_steps),
+            ("glm-4v", 42)
+        );
+        assert!(dir.join(SETTINGS_FILE).exists());
+        assert!(!dir.join(LEGACY_SETTINGS_FILE).exists());
+        assert!(dir.join("settings.json.bak").exists());
+        // Loaded from TOML from now on
+        assert_eq!(AppSettings::load_from(&dir).unwrap().max_steps, 42);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrations() {
+        let document = json!({
+            "planner_base_url": "",
+            "executor_interval_ms": 0,
+            "max_steps": 42,
+            "from_the_future": { "enabled": true },
+        });
+        let (settings, upgraded) =
+            AppSettings::from_document(document, Path::new("settings.toml")).unwrap();
+        assert!(upgraded);
+        assert_eq!(settings.schema_version, SETTINGS_SCHEMA_VERSION);
+        assert_eq!(settings.planner_base_url, "https://api.deepseek.com/v1");
+        assert_eq!(settings.executor_interval_ms, 500);
+        assert_eq!(settings.max_steps, 42);
+        // Unknown entries survive a save
+        let text = settings.to_toml(None).unwrap();
+        assert!(text.contains("[from_the_future]\nenabled = true"));
+
+        // Current files are left alone; newer ones keep their version
+        let current = json!({ "schema_version": SETTINGS_SCHEMA_VERSION, "planner_base_url": "" });
+        let (settings, upgraded) =
+            AppSettings::from_document(current, Path::new("settings.toml")).unwrap();
+        assert!(!upgraded);
+        assert_eq!(settings.planner_base_url, "");
+        let newer = json!({ "schema_version": 99 });
+        assert_eq!(
+            AppSettings::from_document(newer, Path::new("settings.toml"))
+                .unwrap()
+                .0
+                .schema_version,
+            99
+        );
+
+        // A later step renaming a field
+        fn rename_steps(settings: &mut Map<String, Value>) {
+            if let Some(steps) = settings.remove("steps") {
+                settings.insert("max_steps".to_string(), steps);
+            }
+        }
+        let migrations = [
+            Migration {
+                version: 1,
+                apply: fill_legacy_blanks,
+            },
+            Migration {
+                version: 2,
+                apply: rename_steps,
+            },
+        ];
+        let mut document = json!({ "schema_version": 1, "steps": 7 });
+        let document = document.as_object_mut().unwrap();
+        assert_eq!(migrate(document, &migrations), 1);
+        assert_eq!(document["schema_version"], 2);
+        assert_eq!(document["max_steps"], 7);
+        assert!(!document.contains_key("steps"));
+    }
+
+    #[test]
+    fn test_set_value() {
+        let mut settings = AppSettings::default();
+        settings.set_value("max_steps", " 42 ").unwrap();
+        settings.set_value("event_log", "yes").unwrap();
+        settings.set_value("model_name", "glm-4v").unwrap();
+        settings.set_value("scale", "1.5").unwrap();
+        settings
+            .set_value("disabled_actions", "Type, Launch")
+            .unwrap();
+        assert_eq!(settings.max_steps, 42);
+        assert!(settings.event_log);
+        assert_eq!(settings.model_name, "glm-4v");
+        assert_eq!((settings.scale_x, settings.scale_y), (1.5, 1.5));
+        assert_eq!(settings.disabled_actions, ["Type", "Launch"]);
+
+        assert!(settings.set_value("max_steps", "many").is_err());
+        assert!(settings.set_value("no_such_setting", "1").is_err());
+        assert_eq!(settings.max_steps, 42);
+        assert!(AppSettings::has_key("scale"));
+        assert!(!AppSettings::has_key("no_such_setting"));
+    }
+
+    #[test]
+    fn test_env_file() {
+        let settings = AppSettings {
+            api_key: "sk-secret".to_string(),
+            coordinate_system: "absolute".to_string(),
+            scale_x: 1.61,
+            offset_y: -12.5,
+            calibration_mode: "grid".to_string(),
+            disabled_actions: vec!["Type".to_string(), "Launch".to_string()],
+            model_name: "it's".to_string(),
+            ..AppSettings::default()
+        };
+
+        let content = settings.to_env_file(EnvFormat::Dotenv, false);
+        assert!(content.contains("\n# MODEL_API_KEY=\n"));
+        assert!(content.contains("\nCOORDINATE_SCALE_X=1.61\n"));
+        assert!(content.contains("\nDISABLED_ACTIONS=Type,Launch\n"));
+        assert!(!content.contains("COORDINATE_SCALE="));
+
+        // The variables give back the same settings
+        let vars = content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.to_string()));
+        let loaded = crate::config::Config::builder()
+            .with_env_vars(vars)
+            .build()
+            .into_settings();
+        let expected = AppSettings {
+            api_key: AppSettings::default().api_key,
+            ..settings.clone()
+        };
+        assert_eq!(loaded.env_vars(), expected.env_vars());
+
+        let content = settings.to_env_file(EnvFormat::Shell, true);
+        assert!(content.contains("\nexport MODEL_API_KEY='sk-secret'\n"));
+        assert!(content.contains("\nexport MODEL_NAME='it'\\''s'\n"));
+    }
+
+    #[test]
+    fn test_field_errors() {
+        let document = json!({ "model_name": "glm-4v", "max_steps": "many" });
+        let error = AppSettings::from_document(document, Path::new("settings.toml")).unwrap_err();
+        let fields: Vec<&str> = error
+            .field_errors()
+            .iter()
+            .map(|e| e.field.as_str())
+            .collect();
+        assert_eq!(fields, ["max_steps"]);
+
+        let error =
+            AppSettings::from_document(json!([1, 2]), Path::new("settings.toml")).unwrap_err();
+        assert!(matches!(error, SettingsError::Parse { .. }));
+        assert!(error.field_errors().is_empty());
+
+        let settings = AppSettings {
+            base_url: "localhost:8000".to_string(),
+            max_steps: 0,
+            ..AppSettings::default()
+        };
+        let error = settings.check().unwrap_err();
+        let fields: Vec<&str> = error
+            .field_errors()
+            .iter()
+            .map(|e| e.field.as_str())
+            .collect();
+        assert_eq!(fields, ["base_url", "max_steps"]);
+        assert!(error.to_string().starts_with("base_url: "));
+        assert!(AppSettings::default().check().is_ok());
+    }
+
+    #[test]
+    fn test_watcher_notices_changes() {
+        let dir = std::env::temp_dir().join(format!("settings-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SETTINGS_FILE);
+        let mut watcher = SettingsWatcher::with_path(String::new(), Some(path.clone()));
+        assert!(!watcher.changed());
+
+        fs::write(&path, "scale_x = 1.5\n").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        // Some filesystems only keep whole seconds
+        let later = SystemTime::now() + std::time::Duration::from_secs(2);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(later))
+            .unwrap();
+        assert!(watcher.changed());
+
+        fs::remove_file(&path).unwrap();
+        assert!(!watcher.changed());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_import() {
+        let dir = std::env::temp_dir().join(format!("settings-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("exported.toml");
+        let exported = AppSettings {
+            api_key: "sk-executor".to_string(),
+            notify_bark_url: "https://api.day.app/secret".to_string(),
+            model_name: "glm-4v".to_string(),
+            max_steps: 42,
+            ..AppSettings::default()
+        };
+        exported.export(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("sk-executor"));
+        assert!(!text.contains("secret"));
+
+        let mut settings = AppSettings {
+            api_key: "sk-mine".to_string(),
+            active_profile: "home".to_string(),
+            ..AppSettings::default()
+        };
+        settings.import(&path).unwrap();
+        assert_eq!(settings.model_name, "glm-4v");
+        assert_eq!(settings.max_steps, 42);
+        assert_eq!(settings.api_key, "sk-mine");
+        assert_eq!(settings.notify_bark_url, "");
+        assert_eq!(settings.active_profile, "home");
+
+        exported.export_with_secrets(&path).unwrap();
+        settings.import(&path).unwrap();
+        assert_eq!(settings.api_key, "sk-executor");
+        assert!(settings.import(dir.join("missing.toml")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Secrets kept in memory.
+    #[derive(Default)]
+    struct MemoryStore(std::cell::RefCell<HashMap<String, String>>);
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, account: &str) -> Result<Option<String>, String> {
+            Ok(self.0.borrow().get(account).cloned())
+        }
+
+        fn set(&self, account: &str, secret: &str) -> Result<(), String> {
+            self.0
+                .borrow_mut()
+                .insert(account.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, account: &str) -> Result<(), String> {
+            self.0.borrow_mut().remove(account);
+            Ok(())
+        }
+    }
+
+    /// A host without a keychain service.
+    struct NoKeychain;
+
+    impl SecretStore for NoKeychain {
+        fn get(&self, _: &str) -> Result<Option<String>, String> {
+            Err("no keychain".to_string())
+        }
+
+        fn set(&self, _: &str, _: &str) -> Result<(), String> {
+            Err("no keychain".to_string())
+        }
+
+        fn delete(&self, _: &str) -> Result<(), String> {
+            Err("no keychain".to_string())
+        }
+    }
+
+    #[test]
+    fn test_keychain_secrets() {
+        let store = MemoryStore::default();
+        store.set("profile:work:planner_api_key", "sk-old").unwrap();
+        let mut settings = AppSettings {
+            api_key: "sk-executor".to_string(),
+            ..AppSettings::default()
+        };
+        assert!(settings.has_plaintext_secrets());
+
+        settings.stash_secrets(&store, "profile:work:");
+        assert_eq!(settings.api_key, KEYCHAIN_MARKER);
+        // "EMPTY" is no key; its old entry is removed
+        assert_eq!(settings.planner_api_key, "EMPTY");
+        assert_eq!(
+            store.get("profile:work:api_key").unwrap().as_deref(),
+            Some("sk-executor")
+        );
+        assert_eq!(store.get("profile:work:planner_api_key").unwrap(), None);
+        assert!(!settings.has_plaintext_secrets());
+        assert!(!settings.to_toml(None).unwrap().contains("sk-executor"));
+
+        settings.restore_secrets(&store, "profile:work:");
+        assert_eq!(settings.api_key, "sk-executor");
+        // A key missing from the store keeps the marker
+        settings.api_key = KEYCHAIN_MARKER.to_string();
+        settings.restore_secrets(&store, "");
+        assert_eq!(settings.api_key, KEYCHAIN_MARKER);
+
+        let mut plaintext = AppSettings {
+            api_key: "sk-executor".to_string(),
+            plaintext_api_keys: true,
+            ..AppSettings::default()
+        };
+        assert!(!plaintext.has_plaintext_secrets());
+        plaintext.stash_secrets(&store, "");
+        assert_eq!(plaintext.api_key, "sk-executor");
+        assert_eq!(store.get("api_key").unwrap(), None);
+
+        // Without a keychain the key stays in the file
+        let mut headless = AppSettings {
+            api_key: "sk-executor".to_string(),
+            ..AppSettings::default()
+        };
+        headless.stash_secrets(&NoKeychain, "");
+        assert_eq!(headless.api_key, "sk-executor");
+        assert!(headless.to_toml(None).unwrap().contains("sk-executor"));
+    }
+}